@@ -0,0 +1,44 @@
+-- Create scheduled_executions table for the scheduler daemon
+-- Each row is one window of a schedule trigger; a trigger has at most one pending window
+
+CREATE TABLE scheduled_executions (
+    -- Execution ID (derived from trigger ID and scheduled time)
+    id TEXT PRIMARY KEY,
+
+    -- Reference to the schedule trigger
+    trigger_id TEXT NOT NULL REFERENCES triggers(id) ON DELETE CASCADE,
+
+    -- Reference to the workflow to run
+    workflow_id TEXT NOT NULL REFERENCES workflows(id) ON DELETE CASCADE,
+
+    -- When this execution is scheduled to fire
+    scheduled_for TIMESTAMPTZ NOT NULL,
+
+    -- Current status
+    -- 'pending': Waiting for its window
+    -- 'running': Run is being launched
+    -- 'completed': Run was launched
+    -- 'failed': Run could not be launched
+    -- 'skipped': Window was missed and skipped by policy
+    status TEXT NOT NULL DEFAULT 'pending',
+
+    -- When this record was created
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    -- When the run launch started
+    started_at TIMESTAMPTZ,
+
+    -- When the execution reached a final status
+    completed_at TIMESTAMPTZ
+);
+
+-- At most one pending execution per trigger
+CREATE UNIQUE INDEX scheduled_executions_pending_unique ON scheduled_executions (trigger_id)
+    WHERE status = 'pending';
+
+-- Index for finding due executions
+CREATE INDEX scheduled_executions_due_idx ON scheduled_executions (scheduled_for)
+    WHERE status = 'pending';
+
+-- Next scheduled fire time for schedule triggers (maintained by the scheduler)
+ALTER TABLE triggers ADD COLUMN next_run TIMESTAMPTZ;
@@ -0,0 +1,11 @@
+-- Add item index to node_executions for fan-out iterations
+-- A node inside a fan-out scope has one execution record per item
+
+-- Index of the item within the fan-out (NULL for the node as a whole)
+ALTER TABLE node_executions ADD COLUMN item_index INTEGER;
+
+-- A node may now have several records per run, one per item
+ALTER TABLE node_executions DROP CONSTRAINT node_executions_run_node_unique;
+
+CREATE UNIQUE INDEX node_executions_run_node_item_unique
+    ON node_executions (run_id, node_id, COALESCE(item_index, -1));
@@ -0,0 +1,29 @@
+-- Create workflow_versions table for immutable workflow graphs
+-- Every saved graph is a new numbered version; runs reference the version
+-- they execute, so editing a workflow never changes a run in progress
+
+CREATE TABLE workflow_versions (
+    -- Reference to the workflow
+    workflow_id TEXT NOT NULL REFERENCES workflows(id) ON DELETE CASCADE,
+
+    -- Version number, starting at 1 and increasing with each save
+    version INTEGER NOT NULL,
+
+    -- The workflow graph as saved (nodes and edges as JSONB)
+    graph_data JSONB NOT NULL,
+
+    -- When this version was saved
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (workflow_id, version)
+);
+
+-- The version the workflow's graph_data currently holds
+ALTER TABLE workflows ADD COLUMN current_version INTEGER NOT NULL DEFAULT 1;
+
+-- Existing graphs become version 1
+INSERT INTO workflow_versions (workflow_id, version, graph_data, created_at)
+SELECT id, 1, graph_data, updated_at FROM workflows;
+
+-- The version a run executes (NULL for runs from before versioning)
+ALTER TABLE workflow_runs ADD COLUMN workflow_version INTEGER;
@@ -0,0 +1,12 @@
+-- Link runs started by a sub-workflow node to the run that started them
+-- A child run is cancelled along with its parent, and the chain of parents
+-- guards against workflows that invoke themselves
+
+-- The run whose sub-workflow node started this run (NULL for top-level runs)
+ALTER TABLE workflow_runs ADD COLUMN parent_run_id TEXT REFERENCES workflow_runs(id) ON DELETE SET NULL;
+
+-- The sub-workflow node that started this run
+ALTER TABLE workflow_runs ADD COLUMN parent_node_id TEXT;
+
+CREATE INDEX workflow_runs_parent_run_id ON workflow_runs (parent_run_id)
+    WHERE parent_run_id IS NOT NULL;
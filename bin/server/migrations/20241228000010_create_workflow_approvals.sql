@@ -0,0 +1,50 @@
+-- Create workflow_approvals table for approval nodes
+-- Each row mirrors one approval requested in the workflow engine, so users
+-- can review the payload and decide; the engine's event log stays the source
+-- of truth for the run
+
+CREATE TABLE workflow_approvals (
+    -- Approval ID (derived from run ID, node ID, and item index)
+    id TEXT PRIMARY KEY,
+
+    -- The run that waits for the decision
+    run_id TEXT NOT NULL REFERENCES workflow_runs(id) ON DELETE CASCADE,
+
+    -- The workflow of the run
+    workflow_id TEXT NOT NULL REFERENCES workflows(id) ON DELETE CASCADE,
+
+    -- The approval node within the workflow
+    node_id TEXT NOT NULL,
+
+    -- Item index for approval nodes executed per item of a fan-out
+    item_index INTEGER,
+
+    -- The node's input, which the approver decides on
+    payload JSONB NOT NULL,
+
+    -- Current status
+    -- 'pending': Waiting for a decision
+    -- 'approved': Approved by a user (or on expiry)
+    -- 'rejected': Rejected by a user (or on expiry)
+    status TEXT NOT NULL DEFAULT 'pending',
+
+    -- When the approval was requested
+    requested_at TIMESTAMPTZ NOT NULL,
+
+    -- When the approval expires (NULL to wait indefinitely)
+    expires_at TIMESTAMPTZ,
+
+    -- When the decision was made
+    decided_at TIMESTAMPTZ,
+
+    -- The user who decided (NULL if the approval expired)
+    decided_by TEXT
+);
+
+-- Index for listing a workflow's pending approvals
+CREATE INDEX workflow_approvals_pending_idx ON workflow_approvals (workflow_id)
+    WHERE status = 'pending';
+
+-- Index for finding expired approvals
+CREATE INDEX workflow_approvals_expiry_idx ON workflow_approvals (expires_at)
+    WHERE status = 'pending' AND expires_at IS NOT NULL;
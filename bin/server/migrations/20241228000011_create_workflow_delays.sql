@@ -0,0 +1,37 @@
+-- Create workflow_delays table for delay nodes
+-- Each row mirrors one delay started in the workflow engine, so the server
+-- can end it once its time is reached, including after a restart; the
+-- engine's event log stays the source of truth for the run
+
+CREATE TABLE workflow_delays (
+    -- Delay ID (derived from run ID, node ID, and item index)
+    id TEXT PRIMARY KEY,
+
+    -- The run whose path waits
+    run_id TEXT NOT NULL REFERENCES workflow_runs(id) ON DELETE CASCADE,
+
+    -- The workflow of the run
+    workflow_id TEXT NOT NULL REFERENCES workflows(id) ON DELETE CASCADE,
+
+    -- The delay node within the workflow
+    node_id TEXT NOT NULL,
+
+    -- Item index for delay nodes executed per item of a fan-out
+    item_index INTEGER,
+
+    -- When the delay started
+    started_at TIMESTAMPTZ NOT NULL,
+
+    -- When the delay ends
+    ends_at TIMESTAMPTZ NOT NULL,
+
+    -- When the engine ended the delay (NULL while waiting)
+    ended_at TIMESTAMPTZ
+);
+
+-- Index for listing a run's delays
+CREATE INDEX workflow_delays_run_idx ON workflow_delays (run_id);
+
+-- Index for finding delays that are due
+CREATE INDEX workflow_delays_due_idx ON workflow_delays (ends_at)
+    WHERE ended_at IS NULL;
@@ -0,0 +1,12 @@
+-- Link runs that retry a failed run from one of its nodes to the failed run
+-- The retry reuses the failed run's outputs for nodes upstream of the retried
+-- node, so the link shows where those outputs came from
+
+-- The failed run this run retries (NULL for runs that are not retries)
+ALTER TABLE workflow_runs ADD COLUMN retry_of_run_id TEXT REFERENCES workflow_runs(id) ON DELETE SET NULL;
+
+-- The node the retry re-ran from
+ALTER TABLE workflow_runs ADD COLUMN retry_from_node_id TEXT;
+
+CREATE INDEX workflow_runs_retry_of_run_id ON workflow_runs (retry_of_run_id)
+    WHERE retry_of_run_id IS NOT NULL;
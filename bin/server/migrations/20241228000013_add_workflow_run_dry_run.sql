@@ -0,0 +1,6 @@
+-- Flag dry runs, which substitute integration writes (and optionally LLM
+-- calls) with fixture responses instead of executing them, so the run
+-- history can tell them apart from real runs
+
+-- Whether the run is a dry run
+ALTER TABLE workflow_runs ADD COLUMN dry_run BOOLEAN NOT NULL DEFAULT FALSE;
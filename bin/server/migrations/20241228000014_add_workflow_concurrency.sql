@@ -0,0 +1,16 @@
+-- Per-workflow concurrency limits, so runs of a workflow that take longer
+-- than its schedule interval do not pile up and process the same data twice
+
+-- Maximum number of runs in progress at once (NULL for no limit)
+ALTER TABLE workflows ADD COLUMN max_concurrent_runs INTEGER;
+
+-- What happens to a new run at the limit: 'queue', 'skip', or 'cancel_older'
+ALTER TABLE workflows ADD COLUMN overlap_policy TEXT NOT NULL DEFAULT 'queue';
+
+-- Why a run was skipped instead of executed (NULL for runs that were not
+-- skipped); skipped runs have the state 'skipped'
+ALTER TABLE workflow_runs ADD COLUMN skip_reason TEXT;
+
+-- Index for finding the queued runs that wait for a free slot
+CREATE INDEX workflow_runs_queued_idx ON workflow_runs (workflow_id, queued_at)
+    WHERE state = 'queued';
@@ -0,0 +1,17 @@
+-- Retention of node outputs, so the object store does not keep the outputs
+-- of every run forever
+
+-- Days after a run finishes until its outputs expire (NULL to use the
+-- global policy)
+ALTER TABLE workflows ADD COLUMN retention_max_age_days INTEGER;
+
+-- Number of most recent finished runs whose outputs are kept (NULL to use
+-- the global policy)
+ALTER TABLE workflows ADD COLUMN retention_max_runs INTEGER;
+
+-- When the run's outputs and events were deleted (NULL while they are kept)
+ALTER TABLE workflow_runs ADD COLUMN outputs_expired_at TIMESTAMPTZ;
+
+-- Index for finding the finished runs whose outputs are still kept
+CREATE INDEX workflow_runs_outputs_kept_idx ON workflow_runs (workflow_id, finished_at)
+    WHERE outputs_expired_at IS NULL AND finished_at IS NOT NULL;
@@ -0,0 +1,5 @@
+-- Per-workflow deadlines, so a run whose results never arrive does not stay
+-- in progress forever
+
+-- Milliseconds a run may take before it is failed (NULL for no deadline)
+ALTER TABLE workflows ADD COLUMN max_duration_ms BIGINT;
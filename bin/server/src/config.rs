@@ -6,8 +6,9 @@
 //! See [`OidcConfig`](silver_telegram_platform_access::OidcConfig) for
 //! OIDC authentication configuration.
 
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer, de};
 use silver_telegram_platform_access::OidcConfig;
+use silver_telegram_workflow::{Capability, RetentionPolicy, WorkerRuntimeConfig};
 
 /// Server configuration composed from library configs.
 #[derive(Debug, Deserialize)]
@@ -29,6 +30,191 @@ pub struct ServerConfig {
     /// Google OAuth configuration for Gmail integration.
     #[serde(default)]
     pub google: GoogleOAuthConfig,
+
+    /// NATS configuration for the workflow engine.
+    #[serde(default)]
+    pub nats: NatsConfig,
+
+    /// Scheduler configuration.
+    #[serde(default)]
+    pub scheduler: SchedulerConfig,
+
+    /// Retention of node outputs.
+    #[serde(default)]
+    pub retention: RetentionConfig,
+
+    /// Worker configuration.
+    #[serde(default)]
+    pub worker: WorkerConfig,
+}
+
+/// NATS configuration for the workflow engine.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct NatsConfig {
+    /// NATS server URL (e.g., "nats://localhost:4222").
+    /// When unset, runs are recorded as queued but not executed.
+    #[serde(default)]
+    pub url: Option<String>,
+}
+
+/// Scheduler configuration.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SchedulerConfig {
+    /// Whether this server runs the scheduler daemon.
+    #[serde(default = "default_scheduler_enabled")]
+    pub enabled: bool,
+
+    /// Maximum time between scheduler passes, in seconds.
+    /// The scheduler also wakes up when the next execution is due.
+    #[serde(default = "default_scheduler_poll_interval_seconds")]
+    pub poll_interval_seconds: u64,
+
+    /// How late a pending execution may start before it counts as missed,
+    /// in seconds.
+    #[serde(default = "default_missed_execution_grace_seconds")]
+    pub missed_execution_grace_seconds: i64,
+}
+
+fn default_scheduler_enabled() -> bool {
+    true
+}
+
+fn default_scheduler_poll_interval_seconds() -> u64 {
+    60
+}
+
+fn default_missed_execution_grace_seconds() -> i64 {
+    300
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_scheduler_enabled(),
+            poll_interval_seconds: default_scheduler_poll_interval_seconds(),
+            missed_execution_grace_seconds: default_missed_execution_grace_seconds(),
+        }
+    }
+}
+
+/// Worker configuration.
+#[derive(Debug, Clone, Deserialize)]
+pub struct WorkerConfig {
+    /// Whether this server runs a worker executing work items.
+    /// Requires the workflow engine.
+    #[serde(default = "default_worker_enabled")]
+    pub enabled: bool,
+
+    /// Maximum number of work items the worker processes at once.
+    #[serde(default = "default_worker_max_concurrency")]
+    pub max_concurrency: usize,
+
+    /// Capabilities the worker advertises, comma-separated
+    /// (e.g. "ollama,transform"). Defaults to those of all node categories
+    /// workers execute; work items requiring other capabilities are left
+    /// to other workers.
+    #[serde(
+        default = "default_worker_capabilities",
+        deserialize_with = "deserialize_capabilities"
+    )]
+    pub capabilities: Vec<Capability>,
+}
+
+fn default_worker_enabled() -> bool {
+    true
+}
+
+fn default_worker_max_concurrency() -> usize {
+    4
+}
+
+fn default_worker_capabilities() -> Vec<Capability> {
+    Capability::categories()
+}
+
+fn deserialize_capabilities<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<Capability>, D::Error> {
+    let names = String::deserialize(deserializer)?;
+    let capabilities = names
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(|name| Capability::new(name).map_err(de::Error::custom))
+        .collect::<Result<Vec<_>, _>>()?;
+    if capabilities.is_empty() {
+        return Err(de::Error::custom(
+            "a worker must advertise at least one capability",
+        ));
+    }
+    Ok(capabilities)
+}
+
+impl Default for WorkerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_worker_enabled(),
+            max_concurrency: default_worker_max_concurrency(),
+            capabilities: default_worker_capabilities(),
+        }
+    }
+}
+
+impl WorkerConfig {
+    /// Returns the configuration of the worker's runtime.
+    #[must_use]
+    pub fn runtime_config(&self) -> WorkerRuntimeConfig {
+        WorkerRuntimeConfig {
+            max_concurrency: self.max_concurrency,
+            capabilities: self.capabilities.clone(),
+            ..WorkerRuntimeConfig::default()
+        }
+    }
+}
+
+/// Retention of node outputs.
+///
+/// The limits apply to every workflow that does not set its own.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RetentionConfig {
+    /// Days after a run finishes until its outputs expire.
+    /// When unset, outputs do not expire by age.
+    #[serde(default)]
+    pub max_age_days: Option<u32>,
+
+    /// Number of most recent finished runs per workflow whose outputs are
+    /// kept. When unset, outputs do not expire by count.
+    #[serde(default)]
+    pub max_runs: Option<u32>,
+
+    /// Interval between passes deleting expired outputs, in seconds.
+    #[serde(default = "default_retention_interval_seconds")]
+    pub interval_seconds: u64,
+}
+
+fn default_retention_interval_seconds() -> u64 {
+    3600
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            max_age_days: None,
+            max_runs: None,
+            interval_seconds: default_retention_interval_seconds(),
+        }
+    }
+}
+
+impl RetentionConfig {
+    /// Returns the global retention policy.
+    #[must_use]
+    pub fn policy(&self) -> RetentionPolicy {
+        RetentionPolicy {
+            max_age_days: self.max_age_days,
+            max_runs: self.max_runs,
+        }
+    }
 }
 
 /// Google OAuth configuration for Gmail integration.
@@ -131,17 +317,50 @@ impl ServerConfig {
     ///
     /// Returns an error if required configuration is missing or invalid.
     pub fn from_env() -> Result<Self, config::ConfigError> {
-        config::Config::builder()
-            .add_source(
-                config::Environment::default()
-                    .separator("__")
-                    .try_parsing(true),
-            )
-            .build()?
-            .try_deserialize()
+        from_environment(config::Environment::default())
+    }
+}
+
+/// Configuration of a process that only runs a worker
+/// (`silver-telegram-server worker`).
+///
+/// Read from the same environment variables as [`ServerConfig`], so a
+/// worker deployment needs neither OIDC nor SpiceDB settings.
+#[derive(Debug, Deserialize)]
+pub struct WorkerProcessConfig {
+    /// PostgreSQL database connection URL.
+    pub database_url: String,
+
+    /// NATS configuration for the workflow engine.
+    #[serde(default)]
+    pub nats: NatsConfig,
+
+    /// Worker configuration.
+    #[serde(default)]
+    pub worker: WorkerConfig,
+}
+
+impl WorkerProcessConfig {
+    /// Loads configuration from environment variables.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if required configuration is missing or invalid.
+    pub fn from_env() -> Result<Self, config::ConfigError> {
+        from_environment(config::Environment::default())
     }
 }
 
+/// Deserializes a configuration from `__`-separated environment variables.
+fn from_environment<T: for<'de> Deserialize<'de>>(
+    environment: config::Environment,
+) -> Result<T, config::ConfigError> {
+    config::Config::builder()
+        .add_source(environment.separator("__").try_parsing(true))
+        .build()?
+        .try_deserialize()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -152,4 +371,71 @@ mod tests {
         assert_eq!(config.duration_minutes, 5);
         assert_eq!(config.cleanup_interval_seconds, 300);
     }
+
+    #[test]
+    fn scheduler_config_has_correct_defaults() {
+        let config = SchedulerConfig::default();
+        assert!(config.enabled);
+        assert_eq!(config.poll_interval_seconds, 60);
+        assert_eq!(config.missed_execution_grace_seconds, 300);
+    }
+
+    #[test]
+    fn worker_config_has_correct_defaults() {
+        let config = WorkerConfig::default();
+        assert!(config.enabled);
+        assert_eq!(config.runtime_config().max_concurrency, 4);
+        assert_eq!(
+            config.runtime_config().capabilities,
+            Capability::categories()
+        );
+    }
+
+    fn worker_process_config(
+        variables: &[(&str, &str)],
+    ) -> Result<WorkerProcessConfig, config::ConfigError> {
+        let variables = variables
+            .iter()
+            .map(|(key, value)| ((*key).to_string(), (*value).to_string()))
+            .collect();
+        from_environment(config::Environment::default().source(Some(variables)))
+    }
+
+    #[test]
+    fn worker_capabilities_are_read_from_the_environment() {
+        let config = worker_process_config(&[
+            ("DATABASE_URL", "postgres://localhost/test"),
+            ("WORKER__CAPABILITIES", "ollama, transform"),
+        ])
+        .unwrap();
+        assert_eq!(
+            config.worker.runtime_config().capabilities,
+            vec![
+                Capability::new("ollama").unwrap(),
+                Capability::new("transform").unwrap()
+            ]
+        );
+
+        assert!(
+            worker_process_config(&[
+                ("DATABASE_URL", "postgres://localhost/test"),
+                ("WORKER__CAPABILITIES", "ai.layer"),
+            ])
+            .is_err()
+        );
+        assert!(
+            worker_process_config(&[
+                ("DATABASE_URL", "postgres://localhost/test"),
+                ("WORKER__CAPABILITIES", ","),
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn retention_config_keeps_outputs_by_default() {
+        let config = RetentionConfig::default();
+        assert!(config.policy().is_unlimited());
+        assert_eq!(config.interval_seconds, 3600);
+    }
 }
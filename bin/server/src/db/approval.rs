@@ -0,0 +1,284 @@
+//! Database repository for approvals requested by approval nodes.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use silver_telegram_core::{WorkflowId, WorkflowRunId};
+use sqlx::{FromRow, PgPool};
+use std::str::FromStr;
+
+/// Status of an approval.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalStatus {
+    /// Waiting for a decision.
+    Pending,
+    /// Approved by a user or on expiry.
+    Approved,
+    /// Rejected by a user or on expiry.
+    Rejected,
+}
+
+impl ApprovalStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Approved => "approved",
+            Self::Rejected => "rejected",
+        }
+    }
+
+    fn from_str_value(s: &str) -> Self {
+        match s {
+            "approved" => Self::Approved,
+            "rejected" => Self::Rejected,
+            _ => Self::Pending,
+        }
+    }
+}
+
+/// An approval record.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApprovalRecord {
+    /// Approval ID.
+    pub id: String,
+    /// Run waiting for the decision.
+    pub run_id: WorkflowRunId,
+    /// Workflow of the run.
+    pub workflow_id: WorkflowId,
+    /// Approval node within the workflow.
+    pub node_id: String,
+    /// Item index for approval nodes executed per item of a fan-out.
+    pub item_index: Option<i32>,
+    /// The node's input, which the approver decides on.
+    pub payload: serde_json::Value,
+    /// Current status.
+    pub status: ApprovalStatus,
+    /// When requested.
+    pub requested_at: DateTime<Utc>,
+    /// When the approval expires.
+    pub expires_at: Option<DateTime<Utc>>,
+    /// When decided.
+    pub decided_at: Option<DateTime<Utc>>,
+    /// User who decided (None if the approval expired).
+    pub decided_by: Option<String>,
+}
+
+impl ApprovalRecord {
+    /// Creates a pending approval.
+    ///
+    /// The ID is derived from the run, node, and item, so recording the same
+    /// request twice is idempotent.
+    #[must_use]
+    pub fn new(
+        run_id: WorkflowRunId,
+        workflow_id: WorkflowId,
+        node_id: String,
+        item_index: Option<i32>,
+        payload: serde_json::Value,
+    ) -> Self {
+        Self {
+            id: Self::id_for(run_id, &node_id, item_index),
+            run_id,
+            workflow_id,
+            node_id,
+            item_index,
+            payload,
+            status: ApprovalStatus::Pending,
+            requested_at: Utc::now(),
+            expires_at: None,
+            decided_at: None,
+            decided_by: None,
+        }
+    }
+
+    /// Returns the ID of the approval for a node (or item) of a run.
+    #[must_use]
+    pub fn id_for(run_id: WorkflowRunId, node_id: &str, item_index: Option<i32>) -> String {
+        match item_index {
+            Some(index) => format!("{run_id}.{node_id}.{index}"),
+            None => format!("{run_id}.{node_id}"),
+        }
+    }
+}
+
+/// Row type for approval queries.
+#[derive(FromRow)]
+struct ApprovalRow {
+    id: String,
+    run_id: String,
+    workflow_id: String,
+    node_id: String,
+    item_index: Option<i32>,
+    payload: serde_json::Value,
+    status: String,
+    requested_at: DateTime<Utc>,
+    expires_at: Option<DateTime<Utc>>,
+    decided_at: Option<DateTime<Utc>>,
+    decided_by: Option<String>,
+}
+
+impl ApprovalRow {
+    fn try_into_record(self) -> Result<ApprovalRecord, sqlx::Error> {
+        let run_id = WorkflowRunId::from_str(&self.run_id).map_err(|e| {
+            sqlx::Error::Decode(Box::new(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("invalid run id '{}': {}", self.run_id, e),
+            )))
+        })?;
+        let workflow_id = WorkflowId::from_str(&self.workflow_id).map_err(|e| {
+            sqlx::Error::Decode(Box::new(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("invalid workflow id '{}': {}", self.workflow_id, e),
+            )))
+        })?;
+
+        Ok(ApprovalRecord {
+            id: self.id,
+            run_id,
+            workflow_id,
+            node_id: self.node_id,
+            item_index: self.item_index,
+            payload: self.payload,
+            status: ApprovalStatus::from_str_value(&self.status),
+            requested_at: self.requested_at,
+            expires_at: self.expires_at,
+            decided_at: self.decided_at,
+            decided_by: self.decided_by,
+        })
+    }
+}
+
+/// Repository for approval operations.
+pub struct ApprovalRepository {
+    pool: PgPool,
+}
+
+impl ApprovalRepository {
+    /// Creates a new repository.
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Finds an approval by ID.
+    pub async fn find_by_id(&self, id: &str) -> Result<Option<ApprovalRecord>, sqlx::Error> {
+        let row: Option<ApprovalRow> = sqlx::query_as(
+            r#"
+            SELECT id, run_id, workflow_id, node_id, item_index, payload, status,
+                   requested_at, expires_at, decided_at, decided_by
+            FROM workflow_approvals
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|r| r.try_into_record()).transpose()
+    }
+
+    /// Lists the pending approvals of a workflow, oldest first.
+    pub async fn list_pending_for_workflow(
+        &self,
+        workflow_id: WorkflowId,
+    ) -> Result<Vec<ApprovalRecord>, sqlx::Error> {
+        let rows: Vec<ApprovalRow> = sqlx::query_as(
+            r#"
+            SELECT id, run_id, workflow_id, node_id, item_index, payload, status,
+                   requested_at, expires_at, decided_at, decided_by
+            FROM workflow_approvals
+            WHERE workflow_id = $1 AND status = 'pending'
+            ORDER BY requested_at ASC
+            "#,
+        )
+        .bind(workflow_id.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(|r| r.try_into_record()).collect()
+    }
+
+    /// Lists the runs with a pending approval that expired by `now`.
+    pub async fn list_runs_with_expired(
+        &self,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<WorkflowRunId>, sqlx::Error> {
+        let rows: Vec<(String,)> = sqlx::query_as(
+            r#"
+            SELECT DISTINCT run_id
+            FROM workflow_approvals
+            WHERE status = 'pending' AND expires_at <= $1
+            "#,
+        )
+        .bind(now)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|(id,)| {
+                WorkflowRunId::from_str(&id).map_err(|e| {
+                    sqlx::Error::Decode(Box::new(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!("invalid run id '{}': {}", id, e),
+                    )))
+                })
+            })
+            .collect()
+    }
+
+    /// Creates an approval unless one with the same ID already exists.
+    ///
+    /// Returns true if the approval was created.
+    pub async fn create_if_absent(&self, approval: &ApprovalRecord) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO workflow_approvals
+                (id, run_id, workflow_id, node_id, item_index, payload, status,
+                 requested_at, expires_at, decided_at, decided_by)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            ON CONFLICT (id) DO NOTHING
+            "#,
+        )
+        .bind(&approval.id)
+        .bind(approval.run_id.to_string())
+        .bind(approval.workflow_id.to_string())
+        .bind(&approval.node_id)
+        .bind(approval.item_index)
+        .bind(&approval.payload)
+        .bind(approval.status.as_str())
+        .bind(approval.requested_at)
+        .bind(approval.expires_at)
+        .bind(approval.decided_at)
+        .bind(&approval.decided_by)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Records the decision on a pending approval.
+    ///
+    /// Returns false if the approval does not exist or was already decided.
+    pub async fn decide(
+        &self,
+        id: &str,
+        status: ApprovalStatus,
+        decided_by: Option<&str>,
+        decided_at: DateTime<Utc>,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            UPDATE workflow_approvals
+            SET status = $2, decided_by = $3, decided_at = $4
+            WHERE id = $1 AND status = 'pending'
+            "#,
+        )
+        .bind(id)
+        .bind(status.as_str())
+        .bind(decided_by)
+        .bind(decided_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,210 @@
+//! Database repository for delays started by delay nodes.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use silver_telegram_core::{WorkflowId, WorkflowRunId};
+use sqlx::{FromRow, PgPool};
+use std::str::FromStr;
+
+/// A delay record.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DelayRecord {
+    /// Delay ID.
+    pub id: String,
+    /// Run whose path waits.
+    pub run_id: WorkflowRunId,
+    /// Workflow of the run.
+    pub workflow_id: WorkflowId,
+    /// Delay node within the workflow.
+    pub node_id: String,
+    /// Item index for delay nodes executed per item of a fan-out.
+    pub item_index: Option<i32>,
+    /// When the delay started.
+    pub started_at: DateTime<Utc>,
+    /// When the delay ends.
+    pub ends_at: DateTime<Utc>,
+    /// When the engine ended the delay (None while waiting).
+    pub ended_at: Option<DateTime<Utc>>,
+}
+
+impl DelayRecord {
+    /// Creates a delay that is waiting.
+    ///
+    /// The ID is derived from the run, node, and item, so recording the same
+    /// delay twice is idempotent.
+    #[must_use]
+    pub fn new(
+        run_id: WorkflowRunId,
+        workflow_id: WorkflowId,
+        node_id: String,
+        item_index: Option<i32>,
+        started_at: DateTime<Utc>,
+        ends_at: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            id: Self::id_for(run_id, &node_id, item_index),
+            run_id,
+            workflow_id,
+            node_id,
+            item_index,
+            started_at,
+            ends_at,
+            ended_at: None,
+        }
+    }
+
+    /// Returns the ID of the delay for a node (or item) of a run.
+    #[must_use]
+    pub fn id_for(run_id: WorkflowRunId, node_id: &str, item_index: Option<i32>) -> String {
+        match item_index {
+            Some(index) => format!("{run_id}.{node_id}.{index}"),
+            None => format!("{run_id}.{node_id}"),
+        }
+    }
+}
+
+/// Row type for delay queries.
+#[derive(FromRow)]
+struct DelayRow {
+    id: String,
+    run_id: String,
+    workflow_id: String,
+    node_id: String,
+    item_index: Option<i32>,
+    started_at: DateTime<Utc>,
+    ends_at: DateTime<Utc>,
+    ended_at: Option<DateTime<Utc>>,
+}
+
+impl DelayRow {
+    fn try_into_record(self) -> Result<DelayRecord, sqlx::Error> {
+        let run_id = WorkflowRunId::from_str(&self.run_id).map_err(|e| {
+            sqlx::Error::Decode(Box::new(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("invalid run id '{}': {}", self.run_id, e),
+            )))
+        })?;
+        let workflow_id = WorkflowId::from_str(&self.workflow_id).map_err(|e| {
+            sqlx::Error::Decode(Box::new(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("invalid workflow id '{}': {}", self.workflow_id, e),
+            )))
+        })?;
+
+        Ok(DelayRecord {
+            id: self.id,
+            run_id,
+            workflow_id,
+            node_id: self.node_id,
+            item_index: self.item_index,
+            started_at: self.started_at,
+            ends_at: self.ends_at,
+            ended_at: self.ended_at,
+        })
+    }
+}
+
+/// Repository for delay operations.
+pub struct DelayRepository {
+    pool: PgPool,
+}
+
+impl DelayRepository {
+    /// Creates a new repository.
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Lists the delays of a run, in the order they started.
+    pub async fn list_by_run(
+        &self,
+        run_id: WorkflowRunId,
+    ) -> Result<Vec<DelayRecord>, sqlx::Error> {
+        let rows: Vec<DelayRow> = sqlx::query_as(
+            r#"
+            SELECT id, run_id, workflow_id, node_id, item_index, started_at, ends_at, ended_at
+            FROM workflow_delays
+            WHERE run_id = $1
+            ORDER BY started_at ASC, item_index ASC NULLS FIRST
+            "#,
+        )
+        .bind(run_id.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(|r| r.try_into_record()).collect()
+    }
+
+    /// Lists the runs with a waiting delay that ends by `now`.
+    pub async fn list_runs_due(
+        &self,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<WorkflowRunId>, sqlx::Error> {
+        let rows: Vec<(String,)> = sqlx::query_as(
+            r#"
+            SELECT DISTINCT run_id
+            FROM workflow_delays
+            WHERE ended_at IS NULL AND ends_at <= $1
+            "#,
+        )
+        .bind(now)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|(id,)| {
+                WorkflowRunId::from_str(&id).map_err(|e| {
+                    sqlx::Error::Decode(Box::new(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!("invalid run id '{}': {}", id, e),
+                    )))
+                })
+            })
+            .collect()
+    }
+
+    /// Creates a delay unless one with the same ID already exists.
+    ///
+    /// Returns true if the delay was created.
+    pub async fn create_if_absent(&self, delay: &DelayRecord) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO workflow_delays
+                (id, run_id, workflow_id, node_id, item_index, started_at, ends_at, ended_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT (id) DO NOTHING
+            "#,
+        )
+        .bind(&delay.id)
+        .bind(delay.run_id.to_string())
+        .bind(delay.workflow_id.to_string())
+        .bind(&delay.node_id)
+        .bind(delay.item_index)
+        .bind(delay.started_at)
+        .bind(delay.ends_at)
+        .bind(delay.ended_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Records that a waiting delay ended.
+    ///
+    /// Returns false if the delay does not exist or already ended.
+    pub async fn mark_ended(&self, id: &str, ended_at: DateTime<Utc>) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            UPDATE workflow_delays
+            SET ended_at = $2
+            WHERE id = $1 AND ended_at IS NULL
+            "#,
+        )
+        .bind(id)
+        .bind(ended_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -4,16 +4,26 @@
 //! - Integration accounts and credentials
 //! - Workflows and their components
 //! - Workflow runs and execution history
+//! - Scheduled executions for schedule triggers
+//! - Approvals requested by approval nodes
+//! - Delays started by delay nodes
 
+pub mod approval;
+pub mod delay;
 pub mod integration;
+pub mod schedule;
 pub mod workflow;
 pub mod workflow_run;
 
+pub use approval::{ApprovalRecord, ApprovalRepository, ApprovalStatus};
+pub use delay::{DelayRecord, DelayRepository};
 pub use integration::{
     IntegrationAccount, IntegrationAccountRepository, IntegrationConfigRepository,
 };
+pub use schedule::ScheduledExecutionRepository;
 pub use workflow::{
     TriggerRecord, TriggerRepository, WorkflowMemoryRepository, WorkflowRecord, WorkflowRepository,
+    WorkflowVersionRecord,
 };
 pub use workflow_run::{
     DecisionTraceRecord, DecisionTraceRepository, NodeExecutionRecord, NodeExecutionRepository,
@@ -0,0 +1,189 @@
+//! Database repository for scheduled executions.
+
+use chrono::{DateTime, Utc};
+use silver_telegram_core::{TriggerId, WorkflowId};
+use silver_telegram_scheduler::ScheduledExecution;
+use silver_telegram_scheduler::schedule::{ExecutionStatus, ScheduledExecutionId};
+use sqlx::{FromRow, PgPool};
+use std::str::FromStr;
+
+fn status_as_str(status: ExecutionStatus) -> &'static str {
+    match status {
+        ExecutionStatus::Pending => "pending",
+        ExecutionStatus::Ready => "ready",
+        ExecutionStatus::Running => "running",
+        ExecutionStatus::Completed => "completed",
+        ExecutionStatus::Failed => "failed",
+        ExecutionStatus::Skipped => "skipped",
+    }
+}
+
+fn status_from_str(s: &str) -> ExecutionStatus {
+    match s {
+        "ready" => ExecutionStatus::Ready,
+        "running" => ExecutionStatus::Running,
+        "completed" => ExecutionStatus::Completed,
+        "failed" => ExecutionStatus::Failed,
+        "skipped" => ExecutionStatus::Skipped,
+        _ => ExecutionStatus::Pending,
+    }
+}
+
+/// Row type for scheduled execution queries.
+#[derive(FromRow)]
+struct ScheduledExecutionRow {
+    id: String,
+    trigger_id: String,
+    workflow_id: String,
+    scheduled_for: DateTime<Utc>,
+    status: String,
+    created_at: DateTime<Utc>,
+    started_at: Option<DateTime<Utc>>,
+    completed_at: Option<DateTime<Utc>>,
+}
+
+impl ScheduledExecutionRow {
+    fn try_into_record(self) -> Result<ScheduledExecution, sqlx::Error> {
+        let id = ScheduledExecutionId::from_str(&self.id).map_err(|e| {
+            sqlx::Error::Decode(Box::new(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("invalid scheduled execution id '{}': {}", self.id, e),
+            )))
+        })?;
+        let trigger_id = TriggerId::from_str(&self.trigger_id).map_err(|e| {
+            sqlx::Error::Decode(Box::new(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("invalid trigger id '{}': {}", self.trigger_id, e),
+            )))
+        })?;
+        let workflow_id = WorkflowId::from_str(&self.workflow_id).map_err(|e| {
+            sqlx::Error::Decode(Box::new(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("invalid workflow id '{}': {}", self.workflow_id, e),
+            )))
+        })?;
+
+        Ok(ScheduledExecution {
+            id,
+            trigger_id,
+            workflow_id,
+            scheduled_for: self.scheduled_for,
+            status: status_from_str(&self.status),
+            created_at: self.created_at,
+            started_at: self.started_at,
+            completed_at: self.completed_at,
+        })
+    }
+}
+
+/// Repository for scheduled execution operations.
+pub struct ScheduledExecutionRepository {
+    pool: PgPool,
+}
+
+impl ScheduledExecutionRepository {
+    /// Creates a new repository.
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Finds the pending execution for a trigger, if any.
+    pub async fn find_pending(
+        &self,
+        trigger_id: TriggerId,
+    ) -> Result<Option<ScheduledExecution>, sqlx::Error> {
+        let row: Option<ScheduledExecutionRow> = sqlx::query_as(
+            r#"
+            SELECT id, trigger_id, workflow_id, scheduled_for, status,
+                   created_at, started_at, completed_at
+            FROM scheduled_executions
+            WHERE trigger_id = $1 AND status = 'pending'
+            "#,
+        )
+        .bind(trigger_id.to_string())
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|r| r.try_into_record()).transpose()
+    }
+
+    /// Lists pending executions that are due, for active triggers only.
+    pub async fn list_due(
+        &self,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<ScheduledExecution>, sqlx::Error> {
+        let rows: Vec<ScheduledExecutionRow> = sqlx::query_as(
+            r#"
+            SELECT e.id, e.trigger_id, e.workflow_id, e.scheduled_for, e.status,
+                   e.created_at, e.started_at, e.completed_at
+            FROM scheduled_executions e
+            JOIN triggers t ON t.id = e.trigger_id
+            WHERE e.status = 'pending' AND e.scheduled_for <= $1 AND t.active = true
+            ORDER BY e.scheduled_for ASC
+            "#,
+        )
+        .bind(now)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(|r| r.try_into_record()).collect()
+    }
+
+    /// Creates an execution.
+    ///
+    /// Returns false if it already exists or the trigger already has a
+    /// pending execution.
+    pub async fn create(&self, execution: &ScheduledExecution) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO scheduled_executions
+                (id, trigger_id, workflow_id, scheduled_for, status,
+                 created_at, started_at, completed_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT DO NOTHING
+            "#,
+        )
+        .bind(execution.id.to_string())
+        .bind(execution.trigger_id.to_string())
+        .bind(execution.workflow_id.to_string())
+        .bind(execution.scheduled_for)
+        .bind(status_as_str(execution.status))
+        .bind(execution.created_at)
+        .bind(execution.started_at)
+        .bind(execution.completed_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Updates an execution's schedule and status.
+    pub async fn update(&self, execution: &ScheduledExecution) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE scheduled_executions
+            SET scheduled_for = $2, status = $3, started_at = $4, completed_at = $5
+            WHERE id = $1
+            "#,
+        )
+        .bind(execution.id.to_string())
+        .bind(execution.scheduled_for)
+        .bind(status_as_str(execution.status))
+        .bind(execution.started_at)
+        .bind(execution.completed_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Deletes an execution.
+    pub async fn delete(&self, id: ScheduledExecutionId) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM scheduled_executions WHERE id = $1")
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+}
@@ -1,8 +1,10 @@
 //! Database repositories for workflows, triggers, and memory.
 
+use super::WorkflowRunRecord;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use silver_telegram_core::{TriggerId, WorkflowId};
+use silver_telegram_workflow::{ConcurrencyPolicy, OverlapPolicy, RetentionPolicy};
 use sqlx::{FromRow, PgPool};
 use std::str::FromStr;
 
@@ -24,6 +26,16 @@ pub struct WorkflowRecord {
     pub tags: Vec<String>,
     /// The workflow graph (nodes and edges).
     pub graph_data: serde_json::Value,
+    /// The saved version that `graph_data` holds.
+    pub version: i32,
+    /// How many runs may be in progress at once.
+    pub concurrency: ConcurrencyPolicy,
+    /// How long the outputs of the workflow's runs are kept, on top of the
+    /// global retention policy.
+    pub retention: RetentionPolicy,
+    /// How long a run may take before it is failed, in milliseconds (None
+    /// for no deadline).
+    pub max_duration_ms: Option<u64>,
     /// When created.
     pub created_at: DateTime<Utc>,
     /// When last updated.
@@ -45,17 +57,34 @@ impl WorkflowRecord {
             enabled: true,
             tags: Vec::new(),
             graph_data: serde_json::json!({"nodes": [], "edges": []}),
+            version: 1,
+            concurrency: ConcurrencyPolicy::default(),
+            retention: RetentionPolicy::default(),
+            max_duration_ms: None,
             created_at: now,
             updated_at: now,
         }
     }
 
     /// Updates the graph data.
+    ///
+    /// The graph is only stored, as a new version, by
+    /// [`WorkflowRepository::save_graph`].
     pub fn set_graph(&mut self, graph: serde_json::Value) {
         self.graph_data = graph;
         self.updated_at = Utc::now();
     }
 
+    /// Returns this workflow with the graph of one of its saved versions.
+    #[must_use]
+    pub fn at_version(&self, version: &WorkflowVersionRecord) -> Self {
+        Self {
+            graph_data: version.graph_data.clone(),
+            version: version.version,
+            ..self.clone()
+        }
+    }
+
     /// Enables the workflow.
     pub fn enable(&mut self) {
         self.enabled = true;
@@ -78,6 +107,12 @@ struct WorkflowRow {
     enabled: bool,
     tags: serde_json::Value,
     graph_data: serde_json::Value,
+    current_version: i32,
+    max_concurrent_runs: Option<i32>,
+    overlap_policy: String,
+    retention_max_age_days: Option<i32>,
+    retention_max_runs: Option<i32>,
+    max_duration_ms: Option<i64>,
     created_at: DateTime<Utc>,
     updated_at: DateTime<Utc>,
 }
@@ -100,12 +135,98 @@ impl WorkflowRow {
             enabled: self.enabled,
             tags,
             graph_data: self.graph_data,
+            version: self.current_version,
+            concurrency: ConcurrencyPolicy {
+                max_concurrent_runs: self
+                    .max_concurrent_runs
+                    .and_then(|max| u32::try_from(max).ok()),
+                overlap: overlap_policy_from_str(&self.overlap_policy),
+            },
+            retention: RetentionPolicy {
+                max_age_days: self
+                    .retention_max_age_days
+                    .and_then(|days| u32::try_from(days).ok()),
+                max_runs: self
+                    .retention_max_runs
+                    .and_then(|runs| u32::try_from(runs).ok()),
+            },
+            max_duration_ms: self.max_duration_ms.and_then(|ms| u64::try_from(ms).ok()),
             created_at: self.created_at,
             updated_at: self.updated_at,
         })
     }
 }
 
+fn overlap_policy_as_str(policy: OverlapPolicy) -> &'static str {
+    match policy {
+        OverlapPolicy::Queue => "queue",
+        OverlapPolicy::Skip => "skip",
+        OverlapPolicy::CancelOlder => "cancel_older",
+    }
+}
+
+fn max_concurrent_runs(concurrency: &ConcurrencyPolicy) -> Option<i32> {
+    concurrency
+        .max_concurrent_runs
+        .map(|max| i32::try_from(max).unwrap_or(i32::MAX))
+}
+
+fn retention_limit(limit: Option<u32>) -> Option<i32> {
+    limit.map(|limit| i32::try_from(limit).unwrap_or(i32::MAX))
+}
+
+fn max_duration_ms(max_duration_ms: Option<u64>) -> Option<i64> {
+    max_duration_ms.map(|ms| i64::try_from(ms).unwrap_or(i64::MAX))
+}
+
+fn overlap_policy_from_str(s: &str) -> OverlapPolicy {
+    match s {
+        "skip" => OverlapPolicy::Skip,
+        "cancel_older" => OverlapPolicy::CancelOlder,
+        _ => OverlapPolicy::Queue,
+    }
+}
+
+/// An immutable saved version of a workflow's graph.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkflowVersionRecord {
+    /// Workflow ID.
+    pub workflow_id: WorkflowId,
+    /// Version number, starting at 1.
+    pub version: i32,
+    /// The graph as saved.
+    pub graph_data: serde_json::Value,
+    /// When saved.
+    pub created_at: DateTime<Utc>,
+}
+
+/// Row type for workflow version queries.
+#[derive(FromRow)]
+struct WorkflowVersionRow {
+    workflow_id: String,
+    version: i32,
+    graph_data: serde_json::Value,
+    created_at: DateTime<Utc>,
+}
+
+impl WorkflowVersionRow {
+    fn try_into_record(self) -> Result<WorkflowVersionRecord, sqlx::Error> {
+        let workflow_id = WorkflowId::from_str(&self.workflow_id).map_err(|e| {
+            sqlx::Error::Decode(Box::new(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("invalid workflow id '{}': {}", self.workflow_id, e),
+            )))
+        })?;
+
+        Ok(WorkflowVersionRecord {
+            workflow_id,
+            version: self.version,
+            graph_data: self.graph_data,
+            created_at: self.created_at,
+        })
+    }
+}
+
 /// Summary information for workflow listings.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorkflowSummaryRow {
@@ -248,8 +369,9 @@ impl WorkflowRepository {
     pub async fn find_by_id(&self, id: WorkflowId) -> Result<Option<WorkflowRecord>, sqlx::Error> {
         let row: Option<WorkflowRow> = sqlx::query_as(
             r#"
-            SELECT id, name, description, enabled, tags, graph_data,
-                   created_at, updated_at
+            SELECT id, name, description, enabled, tags, graph_data, current_version,
+                   max_concurrent_runs, overlap_policy, retention_max_age_days,
+                   retention_max_runs, max_duration_ms, created_at, updated_at
             FROM workflows
             WHERE id = $1
             "#,
@@ -266,16 +388,21 @@ impl WorkflowRepository {
 
     /// Creates a new workflow.
     ///
+    /// The workflow's graph is stored as its first version.
+    ///
     /// Note: After creating the workflow, you must also create an ownership
     /// relationship in SpiceDB using AuthzClient::write_relationship.
     pub async fn create(&self, workflow: &WorkflowRecord) -> Result<(), sqlx::Error> {
         let tags_json = serde_json::to_value(&workflow.tags).unwrap_or_default();
+        let mut tx = self.pool.begin().await?;
 
         sqlx::query(
             r#"
             INSERT INTO workflows
-                (id, name, description, enabled, tags, graph_data, created_at, updated_at)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                (id, name, description, enabled, tags, graph_data, current_version,
+                 max_concurrent_runs, overlap_policy, retention_max_age_days,
+                 retention_max_runs, max_duration_ms, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
             "#,
         )
         .bind(workflow.id.to_string())
@@ -284,23 +411,46 @@ impl WorkflowRepository {
         .bind(workflow.enabled)
         .bind(&tags_json)
         .bind(&workflow.graph_data)
+        .bind(workflow.version)
+        .bind(max_concurrent_runs(&workflow.concurrency))
+        .bind(overlap_policy_as_str(workflow.concurrency.overlap))
+        .bind(retention_limit(workflow.retention.max_age_days))
+        .bind(retention_limit(workflow.retention.max_runs))
+        .bind(max_duration_ms(workflow.max_duration_ms))
         .bind(workflow.created_at)
         .bind(workflow.updated_at)
-        .execute(&self.pool)
+        .execute(&mut *tx)
         .await?;
 
-        Ok(())
+        sqlx::query(
+            r#"
+            INSERT INTO workflow_versions (workflow_id, version, graph_data, created_at)
+            VALUES ($1, $2, $3, $4)
+            "#,
+        )
+        .bind(workflow.id.to_string())
+        .bind(workflow.version)
+        .bind(&workflow.graph_data)
+        .bind(workflow.created_at)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await
     }
 
-    /// Updates an existing workflow.
+    /// Updates an existing workflow's details.
+    ///
+    /// The graph is not updated; saving a graph creates a new version
+    /// (see [`Self::save_graph`]).
     pub async fn update(&self, workflow: &WorkflowRecord) -> Result<(), sqlx::Error> {
         let tags_json = serde_json::to_value(&workflow.tags).unwrap_or_default();
 
         sqlx::query(
             r#"
             UPDATE workflows
-            SET name = $2, description = $3, enabled = $4, tags = $5, graph_data = $6,
-                updated_at = $7
+            SET name = $2, description = $3, enabled = $4, tags = $5, updated_at = $6,
+                max_concurrent_runs = $7, overlap_policy = $8, retention_max_age_days = $9,
+                retention_max_runs = $10, max_duration_ms = $11
             WHERE id = $1
             "#,
         )
@@ -309,14 +459,121 @@ impl WorkflowRepository {
         .bind(&workflow.description)
         .bind(workflow.enabled)
         .bind(&tags_json)
-        .bind(&workflow.graph_data)
         .bind(workflow.updated_at)
+        .bind(max_concurrent_runs(&workflow.concurrency))
+        .bind(overlap_policy_as_str(workflow.concurrency.overlap))
+        .bind(retention_limit(workflow.retention.max_age_days))
+        .bind(retention_limit(workflow.retention.max_runs))
+        .bind(max_duration_ms(workflow.max_duration_ms))
         .execute(&self.pool)
         .await?;
 
         Ok(())
     }
 
+    /// Saves the workflow's graph as a new version.
+    ///
+    /// Versions are never changed once saved, so runs keep executing the
+    /// version they started on. Sets the record's version to the new one.
+    pub async fn save_graph(&self, workflow: &mut WorkflowRecord) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        let (version,): (i32,) = sqlx::query_as(
+            r#"
+            INSERT INTO workflow_versions (workflow_id, version, graph_data, created_at)
+            SELECT $1, COALESCE(MAX(version), 0) + 1, $2, $3
+            FROM workflow_versions
+            WHERE workflow_id = $1
+            RETURNING version
+            "#,
+        )
+        .bind(workflow.id.to_string())
+        .bind(&workflow.graph_data)
+        .bind(workflow.updated_at)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"
+            UPDATE workflows
+            SET graph_data = $2, current_version = $3, updated_at = $4
+            WHERE id = $1
+            "#,
+        )
+        .bind(workflow.id.to_string())
+        .bind(&workflow.graph_data)
+        .bind(version)
+        .bind(workflow.updated_at)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        workflow.version = version;
+        Ok(())
+    }
+
+    /// Finds the workflow a run executes, with the graph of the version the
+    /// run is pinned to.
+    pub async fn find_for_run(
+        &self,
+        run: &WorkflowRunRecord,
+    ) -> Result<Option<WorkflowRecord>, sqlx::Error> {
+        let Some(workflow) = self.find_by_id(run.workflow_id).await? else {
+            return Ok(None);
+        };
+        match run.workflow_version {
+            Some(version) if version != workflow.version => Ok(self
+                .find_version(workflow.id, version)
+                .await?
+                .map(|version| workflow.at_version(&version))),
+            _ => Ok(Some(workflow)),
+        }
+    }
+
+    /// Lists the saved versions of a workflow, newest first.
+    pub async fn list_versions(
+        &self,
+        workflow_id: WorkflowId,
+    ) -> Result<Vec<WorkflowVersionRecord>, sqlx::Error> {
+        let rows: Vec<WorkflowVersionRow> = sqlx::query_as(
+            r#"
+            SELECT workflow_id, version, graph_data, created_at
+            FROM workflow_versions
+            WHERE workflow_id = $1
+            ORDER BY version DESC
+            "#,
+        )
+        .bind(workflow_id.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(|r| r.try_into_record()).collect()
+    }
+
+    /// Finds a saved version of a workflow.
+    pub async fn find_version(
+        &self,
+        workflow_id: WorkflowId,
+        version: i32,
+    ) -> Result<Option<WorkflowVersionRecord>, sqlx::Error> {
+        let row: Option<WorkflowVersionRow> = sqlx::query_as(
+            r#"
+            SELECT workflow_id, version, graph_data, created_at
+            FROM workflow_versions
+            WHERE workflow_id = $1 AND version = $2
+            "#,
+        )
+        .bind(workflow_id.to_string())
+        .bind(version)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        match row {
+            Some(r) => Ok(Some(r.try_into_record()?)),
+            None => Ok(None),
+        }
+    }
+
     /// Deletes a workflow.
     pub async fn delete(&self, id: WorkflowId) -> Result<(), sqlx::Error> {
         sqlx::query(
@@ -365,6 +622,9 @@ pub struct TriggerRecord {
     pub config_data: serde_json::Value,
     /// Whether the trigger is active.
     pub active: bool,
+    /// Next scheduled fire time (schedule triggers only, maintained by the
+    /// scheduler).
+    pub next_run: Option<DateTime<Utc>>,
     /// When created.
     pub created_at: DateTime<Utc>,
     /// When last updated.
@@ -380,6 +640,7 @@ struct TriggerRow {
     trigger_type: String,
     config_data: serde_json::Value,
     active: bool,
+    next_run: Option<DateTime<Utc>>,
     created_at: DateTime<Utc>,
     updated_at: DateTime<Utc>,
 }
@@ -406,6 +667,7 @@ impl TriggerRow {
             trigger_type: self.trigger_type,
             config_data: self.config_data,
             active: self.active,
+            next_run: self.next_run,
             created_at: self.created_at,
             updated_at: self.updated_at,
         })
@@ -427,7 +689,7 @@ impl TriggerRepository {
     pub async fn list_active_schedules(&self) -> Result<Vec<TriggerRecord>, sqlx::Error> {
         let rows: Vec<TriggerRow> = sqlx::query_as(
             r#"
-            SELECT id, workflow_id, node_id, trigger_type, config_data, active, created_at, updated_at
+            SELECT id, workflow_id, node_id, trigger_type, config_data, active, next_run, created_at, updated_at
             FROM triggers
             WHERE trigger_type = 'schedule' AND active = true
             "#,
@@ -438,6 +700,22 @@ impl TriggerRepository {
         rows.into_iter().map(|r| r.try_into_record()).collect()
     }
 
+    /// Finds a trigger by ID.
+    pub async fn find_by_id(&self, id: TriggerId) -> Result<Option<TriggerRecord>, sqlx::Error> {
+        let row: Option<TriggerRow> = sqlx::query_as(
+            r#"
+            SELECT id, workflow_id, node_id, trigger_type, config_data, active, next_run, created_at, updated_at
+            FROM triggers
+            WHERE id = $1
+            "#,
+        )
+        .bind(id.to_string())
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|r| r.try_into_record()).transpose()
+    }
+
     /// Lists triggers for a workflow.
     pub async fn list_by_workflow(
         &self,
@@ -445,7 +723,7 @@ impl TriggerRepository {
     ) -> Result<Vec<TriggerRecord>, sqlx::Error> {
         let rows: Vec<TriggerRow> = sqlx::query_as(
             r#"
-            SELECT id, workflow_id, node_id, trigger_type, config_data, active, created_at, updated_at
+            SELECT id, workflow_id, node_id, trigger_type, config_data, active, next_run, created_at, updated_at
             FROM triggers
             WHERE workflow_id = $1
             "#,
@@ -513,6 +791,27 @@ impl TriggerRepository {
         Ok(())
     }
 
+    /// Records the next scheduled fire time for a trigger.
+    pub async fn set_next_run(
+        &self,
+        id: TriggerId,
+        next_run: Option<DateTime<Utc>>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE triggers
+            SET next_run = $2
+            WHERE id = $1
+            "#,
+        )
+        .bind(id.to_string())
+        .bind(next_run)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
     /// Updates the active state for all triggers of a workflow.
     pub async fn set_active(
         &self,
@@ -3,7 +3,8 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use silver_telegram_core::{NodeExecutionId, TriggerId, WorkflowId, WorkflowRunId};
-use sqlx::{FromRow, PgPool};
+use silver_telegram_workflow::{Admission, ConcurrencyPolicy, RetentionPolicy};
+use sqlx::{FromRow, PgPool, Postgres, Transaction};
 use std::str::FromStr;
 
 /// Execution state for a workflow run.
@@ -20,6 +21,8 @@ pub enum RunState {
     Failed,
     /// Cancelled by user or system.
     Cancelled,
+    /// Not executed because the workflow was at its concurrency limit.
+    Skipped,
 }
 
 impl RunState {
@@ -30,6 +33,7 @@ impl RunState {
             Self::Completed => "completed",
             Self::Failed => "failed",
             Self::Cancelled => "cancelled",
+            Self::Skipped => "skipped",
         }
     }
 
@@ -40,6 +44,7 @@ impl RunState {
             "completed" => Self::Completed,
             "failed" => Self::Failed,
             "cancelled" => Self::Cancelled,
+            "skipped" => Self::Skipped,
             _ => Self::Queued,
         }
     }
@@ -47,7 +52,10 @@ impl RunState {
     /// Returns true if this is a terminal state.
     #[must_use]
     pub fn is_terminal(&self) -> bool {
-        matches!(self, Self::Completed | Self::Failed | Self::Cancelled)
+        matches!(
+            self,
+            Self::Completed | Self::Failed | Self::Cancelled | Self::Skipped
+        )
     }
 }
 
@@ -58,6 +66,9 @@ pub struct WorkflowRunRecord {
     pub id: WorkflowRunId,
     /// Workflow being executed.
     pub workflow_id: WorkflowId,
+    /// Workflow version being executed (None for runs from before
+    /// versioning).
+    pub workflow_version: Option<i32>,
     /// Trigger that initiated the run.
     pub trigger_id: Option<TriggerId>,
     /// Current state.
@@ -76,6 +87,22 @@ pub struct WorkflowRunRecord {
     pub error_message: Option<String>,
     /// Duration in milliseconds.
     pub duration_ms: Option<i64>,
+    /// Run whose sub-workflow node started this run.
+    pub parent_run_id: Option<WorkflowRunId>,
+    /// Sub-workflow node that started this run.
+    pub parent_node_id: Option<String>,
+    /// Failed run this run retries.
+    pub retry_of_run_id: Option<WorkflowRunId>,
+    /// Node the retry re-ran from.
+    pub retry_from_node_id: Option<String>,
+    /// Whether the run is a dry run, which substitutes integration writes
+    /// with fixture responses.
+    pub dry_run: bool,
+    /// Why the run was skipped instead of executed.
+    pub skip_reason: Option<String>,
+    /// When the run's outputs expired under the retention policy (None
+    /// while they are kept).
+    pub outputs_expired_at: Option<DateTime<Utc>>,
 }
 
 impl WorkflowRunRecord {
@@ -89,6 +116,7 @@ impl WorkflowRunRecord {
         Self {
             id: WorkflowRunId::new(),
             workflow_id,
+            workflow_version: None,
             trigger_id,
             state: RunState::Queued,
             queued_at: Utc::now(),
@@ -98,9 +126,56 @@ impl WorkflowRunRecord {
             output_data: None,
             error_message: None,
             duration_ms: None,
+            parent_run_id: None,
+            parent_node_id: None,
+            retry_of_run_id: None,
+            retry_from_node_id: None,
+            dry_run: false,
+            skip_reason: None,
+            outputs_expired_at: None,
         }
     }
 
+    /// Sets a specific run ID.
+    ///
+    /// Used when the ID is derived from its source (e.g., a scheduled
+    /// execution) so that creating the run is idempotent.
+    #[must_use]
+    pub fn with_id(mut self, id: WorkflowRunId) -> Self {
+        self.id = id;
+        self
+    }
+
+    /// Pins the run to the workflow version it executes.
+    #[must_use]
+    pub fn with_workflow_version(mut self, version: i32) -> Self {
+        self.workflow_version = Some(version);
+        self
+    }
+
+    /// Records the run and sub-workflow node that started this run.
+    #[must_use]
+    pub fn with_parent(mut self, run_id: WorkflowRunId, node_id: impl Into<String>) -> Self {
+        self.parent_run_id = Some(run_id);
+        self.parent_node_id = Some(node_id.into());
+        self
+    }
+
+    /// Records the failed run this run retries and the node it re-runs from.
+    #[must_use]
+    pub fn with_retry_of(mut self, run_id: WorkflowRunId, node_id: impl Into<String>) -> Self {
+        self.retry_of_run_id = Some(run_id);
+        self.retry_from_node_id = Some(node_id.into());
+        self
+    }
+
+    /// Marks the run as a dry run.
+    #[must_use]
+    pub fn as_dry_run(mut self) -> Self {
+        self.dry_run = true;
+        self
+    }
+
     /// Starts the run.
     pub fn start(&mut self) {
         self.state = RunState::Running;
@@ -135,6 +210,13 @@ impl WorkflowRunRecord {
             self.duration_ms = Some((Utc::now() - start).num_milliseconds());
         }
     }
+
+    /// Skips the run without executing it.
+    pub fn skip(&mut self, reason: String) {
+        self.state = RunState::Skipped;
+        self.finished_at = Some(Utc::now());
+        self.skip_reason = Some(reason);
+    }
 }
 
 /// Row type for run queries.
@@ -142,6 +224,7 @@ impl WorkflowRunRecord {
 struct WorkflowRunRow {
     id: String,
     workflow_id: String,
+    workflow_version: Option<i32>,
     trigger_id: Option<String>,
     state: String,
     queued_at: DateTime<Utc>,
@@ -151,6 +234,13 @@ struct WorkflowRunRow {
     output_data: Option<serde_json::Value>,
     error_message: Option<String>,
     duration_ms: Option<i64>,
+    parent_run_id: Option<String>,
+    parent_node_id: Option<String>,
+    retry_of_run_id: Option<String>,
+    retry_from_node_id: Option<String>,
+    dry_run: bool,
+    skip_reason: Option<String>,
+    outputs_expired_at: Option<DateTime<Utc>>,
 }
 
 impl WorkflowRunRow {
@@ -178,10 +268,33 @@ impl WorkflowRunRow {
                 })
             })
             .transpose()?;
+        let parent_run_id = self
+            .parent_run_id
+            .map(|pid| {
+                WorkflowRunId::from_str(&pid).map_err(|e| {
+                    sqlx::Error::Decode(Box::new(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!("invalid parent run id '{}': {}", pid, e),
+                    )))
+                })
+            })
+            .transpose()?;
+        let retry_of_run_id = self
+            .retry_of_run_id
+            .map(|rid| {
+                WorkflowRunId::from_str(&rid).map_err(|e| {
+                    sqlx::Error::Decode(Box::new(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!("invalid retried run id '{}': {}", rid, e),
+                    )))
+                })
+            })
+            .transpose()?;
 
         Ok(WorkflowRunRecord {
             id,
             workflow_id,
+            workflow_version: self.workflow_version,
             trigger_id,
             state: RunState::from_str_value(&self.state),
             queued_at: self.queued_at,
@@ -191,6 +304,13 @@ impl WorkflowRunRow {
             output_data: self.output_data,
             error_message: self.error_message,
             duration_ms: self.duration_ms,
+            parent_run_id,
+            parent_node_id: self.parent_node_id,
+            retry_of_run_id,
+            retry_from_node_id: self.retry_from_node_id,
+            dry_run: self.dry_run,
+            skip_reason: self.skip_reason,
+            outputs_expired_at: self.outputs_expired_at,
         })
     }
 }
@@ -214,8 +334,10 @@ impl WorkflowRunRepository {
     ) -> Result<Vec<WorkflowRunRecord>, sqlx::Error> {
         let rows: Vec<WorkflowRunRow> = sqlx::query_as(
             r#"
-            SELECT id, workflow_id, trigger_id, state, queued_at, started_at, finished_at,
-                   input_data, output_data, error_message, duration_ms
+            SELECT id, workflow_id, workflow_version, trigger_id, state, queued_at, started_at,
+                   finished_at, input_data, output_data, error_message, duration_ms,
+                   parent_run_id, parent_node_id, retry_of_run_id, retry_from_node_id, dry_run,
+                   skip_reason, outputs_expired_at
             FROM workflow_runs
             WHERE workflow_id = $1
             ORDER BY queued_at DESC
@@ -237,8 +359,10 @@ impl WorkflowRunRepository {
     ) -> Result<Option<WorkflowRunRecord>, sqlx::Error> {
         let row: Option<WorkflowRunRow> = sqlx::query_as(
             r#"
-            SELECT id, workflow_id, trigger_id, state, queued_at, started_at, finished_at,
-                   input_data, output_data, error_message, duration_ms
+            SELECT id, workflow_id, workflow_version, trigger_id, state, queued_at, started_at,
+                   finished_at, input_data, output_data, error_message, duration_ms,
+                   parent_run_id, parent_node_id, retry_of_run_id, retry_from_node_id, dry_run,
+                   skip_reason, outputs_expired_at
             FROM workflow_runs
             WHERE id = $1
             "#,
@@ -259,8 +383,11 @@ impl WorkflowRunRepository {
             r#"
             INSERT INTO workflow_runs
                 (id, workflow_id, trigger_id, state, queued_at, started_at, finished_at,
-                 input_data, output_data, error_message, duration_ms)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+                 input_data, output_data, error_message, duration_ms, workflow_version,
+                 parent_run_id, parent_node_id, retry_of_run_id, retry_from_node_id, dry_run,
+                 skip_reason)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16,
+                    $17, $18)
             "#,
         )
         .bind(run.id.to_string())
@@ -274,12 +401,222 @@ impl WorkflowRunRepository {
         .bind(&run.output_data)
         .bind(&run.error_message)
         .bind(run.duration_ms)
+        .bind(run.workflow_version)
+        .bind(run.parent_run_id.map(|id| id.to_string()))
+        .bind(&run.parent_node_id)
+        .bind(run.retry_of_run_id.map(|id| id.to_string()))
+        .bind(&run.retry_from_node_id)
+        .bind(run.dry_run)
+        .bind(&run.skip_reason)
         .execute(&self.pool)
         .await?;
 
         Ok(())
     }
 
+    /// Creates a run unless one with the same ID already exists.
+    ///
+    /// Returns true if the run was created.
+    pub async fn create_if_absent(&self, run: &WorkflowRunRecord) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO workflow_runs
+                (id, workflow_id, trigger_id, state, queued_at, started_at, finished_at,
+                 input_data, output_data, error_message, duration_ms, workflow_version,
+                 parent_run_id, parent_node_id, retry_of_run_id, retry_from_node_id, dry_run,
+                 skip_reason)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16,
+                    $17, $18)
+            ON CONFLICT (id) DO NOTHING
+            "#,
+        )
+        .bind(run.id.to_string())
+        .bind(run.workflow_id.to_string())
+        .bind(run.trigger_id.map(|t| t.to_string()))
+        .bind(run.state.as_str())
+        .bind(run.queued_at)
+        .bind(run.started_at)
+        .bind(run.finished_at)
+        .bind(&run.input_data)
+        .bind(&run.output_data)
+        .bind(&run.error_message)
+        .bind(run.duration_ms)
+        .bind(run.workflow_version)
+        .bind(run.parent_run_id.map(|id| id.to_string()))
+        .bind(&run.parent_node_id)
+        .bind(run.retry_of_run_id.map(|id| id.to_string()))
+        .bind(&run.retry_from_node_id)
+        .bind(run.dry_run)
+        .bind(&run.skip_reason)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Creates a run under its workflow's concurrency policy.
+    ///
+    /// Runs of the same workflow are admitted one at a time, so concurrent
+    /// triggers see each other's runs. Dry runs do not count as runs in
+    /// progress. The run is recorded as started if it is admitted, as
+    /// queued if it waits for a slot, or as skipped; for
+    /// [`Admission::CancelOlder`], the older runs are recorded as cancelled
+    /// and the caller cancels them in the engine. Returns None, without
+    /// changing anything, if a run with the same ID already exists.
+    pub async fn admit(
+        &self,
+        run: &mut WorkflowRunRecord,
+        policy: &ConcurrencyPolicy,
+    ) -> Result<Option<Admission>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        lock_workflow_runs(&mut tx, run.workflow_id).await?;
+
+        let in_progress = in_progress_runs(&mut tx, run.workflow_id).await?;
+        let (queued,): (i64,) = sqlx::query_as(
+            r#"
+            SELECT COUNT(*)
+            FROM workflow_runs
+            WHERE workflow_id = $1 AND state = 'queued' AND NOT dry_run
+            "#,
+        )
+        .bind(run.workflow_id.to_string())
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let admission = policy.admit(&in_progress, usize::try_from(queued).unwrap_or(0));
+        match &admission {
+            Admission::Start | Admission::CancelOlder { .. } => run.start(),
+            Admission::Queue => {}
+            Admission::Skip { reason } => run.skip(reason.clone()),
+        }
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO workflow_runs
+                (id, workflow_id, trigger_id, state, queued_at, started_at, finished_at,
+                 input_data, output_data, error_message, duration_ms, workflow_version,
+                 parent_run_id, parent_node_id, retry_of_run_id, retry_from_node_id, dry_run,
+                 skip_reason)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16,
+                    $17, $18)
+            ON CONFLICT (id) DO NOTHING
+            "#,
+        )
+        .bind(run.id.to_string())
+        .bind(run.workflow_id.to_string())
+        .bind(run.trigger_id.map(|t| t.to_string()))
+        .bind(run.state.as_str())
+        .bind(run.queued_at)
+        .bind(run.started_at)
+        .bind(run.finished_at)
+        .bind(&run.input_data)
+        .bind(&run.output_data)
+        .bind(&run.error_message)
+        .bind(run.duration_ms)
+        .bind(run.workflow_version)
+        .bind(run.parent_run_id.map(|id| id.to_string()))
+        .bind(&run.parent_node_id)
+        .bind(run.retry_of_run_id.map(|id| id.to_string()))
+        .bind(&run.retry_from_node_id)
+        .bind(run.dry_run)
+        .bind(&run.skip_reason)
+        .execute(&mut *tx)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        if let Admission::CancelOlder { runs } = &admission {
+            let ids: Vec<String> = runs.iter().map(ToString::to_string).collect();
+            sqlx::query(
+                r#"
+                UPDATE workflow_runs
+                SET state = 'cancelled', finished_at = NOW()
+                WHERE id = ANY($1) AND state = 'running'
+                "#,
+            )
+            .bind(&ids)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(Some(admission))
+    }
+
+    /// Lists the workflows with queued runs that wait for a slot.
+    pub async fn list_workflows_with_queued(&self) -> Result<Vec<WorkflowId>, sqlx::Error> {
+        let rows: Vec<(String,)> = sqlx::query_as(
+            r#"
+            SELECT DISTINCT workflow_id
+            FROM workflow_runs
+            WHERE state = 'queued' AND NOT dry_run
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|(id,)| {
+                WorkflowId::from_str(&id).map_err(|e| {
+                    sqlx::Error::Decode(Box::new(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!("invalid workflow id '{}': {}", id, e),
+                    )))
+                })
+            })
+            .collect()
+    }
+
+    /// Claims the queued runs of a workflow that fit in its free slots,
+    /// oldest first, and records them as started.
+    ///
+    /// The caller starts the claimed runs in the engine.
+    pub async fn claim_queued(
+        &self,
+        workflow_id: WorkflowId,
+        policy: &ConcurrencyPolicy,
+    ) -> Result<Vec<WorkflowRunRecord>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        lock_workflow_runs(&mut tx, workflow_id).await?;
+
+        let in_progress = in_progress_runs(&mut tx, workflow_id).await?;
+        let free_slots = policy.free_slots(in_progress.len());
+        if free_slots == 0 {
+            return Ok(Vec::new());
+        }
+
+        let rows: Vec<WorkflowRunRow> = sqlx::query_as(
+            r#"
+            UPDATE workflow_runs
+            SET state = 'running', started_at = NOW()
+            WHERE id IN (
+                SELECT id
+                FROM workflow_runs
+                WHERE workflow_id = $1 AND state = 'queued' AND NOT dry_run
+                ORDER BY queued_at ASC
+                LIMIT $2
+            )
+            RETURNING id, workflow_id, workflow_version, trigger_id, state, queued_at,
+                      started_at, finished_at, input_data, output_data, error_message,
+                      duration_ms, parent_run_id, parent_node_id, retry_of_run_id,
+                      retry_from_node_id, dry_run, skip_reason, outputs_expired_at
+            "#,
+        )
+        .bind(workflow_id.to_string())
+        .bind(i64::try_from(free_slots).unwrap_or(i64::MAX))
+        .fetch_all(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        let mut runs = rows
+            .into_iter()
+            .map(|r| r.try_into_record())
+            .collect::<Result<Vec<_>, _>>()?;
+        runs.sort_by_key(|run| run.queued_at);
+        Ok(runs)
+    }
+
     /// Updates a run.
     pub async fn update(&self, run: &WorkflowRunRecord) -> Result<(), sqlx::Error> {
         sqlx::query(
@@ -307,8 +644,10 @@ impl WorkflowRunRepository {
     pub async fn list_active(&self) -> Result<Vec<WorkflowRunRecord>, sqlx::Error> {
         let rows: Vec<WorkflowRunRow> = sqlx::query_as(
             r#"
-            SELECT id, workflow_id, trigger_id, state, queued_at, started_at, finished_at,
-                   input_data, output_data, error_message, duration_ms
+            SELECT id, workflow_id, workflow_version, trigger_id, state, queued_at, started_at,
+                   finished_at, input_data, output_data, error_message, duration_ms,
+                   parent_run_id, parent_node_id, retry_of_run_id, retry_from_node_id, dry_run,
+                   skip_reason, outputs_expired_at
             FROM workflow_runs
             WHERE state IN ('queued', 'running')
             ORDER BY queued_at ASC
@@ -320,6 +659,217 @@ impl WorkflowRunRepository {
         rows.into_iter().map(|r| r.try_into_record()).collect()
     }
 
+    /// Lists queued or running runs of a workflow.
+    pub async fn list_active_for_workflow(
+        &self,
+        workflow_id: WorkflowId,
+    ) -> Result<Vec<WorkflowRunRecord>, sqlx::Error> {
+        let rows: Vec<WorkflowRunRow> = sqlx::query_as(
+            r#"
+            SELECT id, workflow_id, workflow_version, trigger_id, state, queued_at, started_at,
+                   finished_at, input_data, output_data, error_message, duration_ms,
+                   parent_run_id, parent_node_id, retry_of_run_id, retry_from_node_id, dry_run,
+                   skip_reason, outputs_expired_at
+            FROM workflow_runs
+            WHERE workflow_id = $1 AND state IN ('queued', 'running')
+            ORDER BY queued_at ASC
+            "#,
+        )
+        .bind(workflow_id.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(|r| r.try_into_record()).collect()
+    }
+
+    /// Lists running runs that have been running longer than their
+    /// workflow's maximum duration at `now`, oldest first.
+    pub async fn list_past_deadline(
+        &self,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<WorkflowRunRecord>, sqlx::Error> {
+        let rows: Vec<WorkflowRunRow> = sqlx::query_as(
+            r#"
+            SELECT id, workflow_id, workflow_version, trigger_id, state, queued_at, started_at,
+                   finished_at, input_data, output_data, error_message, duration_ms,
+                   parent_run_id, parent_node_id, retry_of_run_id, retry_from_node_id, dry_run,
+                   skip_reason, outputs_expired_at
+            FROM workflow_runs
+            WHERE state = 'running'
+              AND started_at + (
+                  SELECT max_duration_ms FROM workflows WHERE workflows.id = workflow_id
+              ) * INTERVAL '1 millisecond' <= $1
+            ORDER BY started_at ASC
+            "#,
+        )
+        .bind(now)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(|r| r.try_into_record()).collect()
+    }
+
+    /// Lists queued or running runs started by a run's sub-workflow nodes.
+    pub async fn list_active_children(
+        &self,
+        parent_run_id: WorkflowRunId,
+    ) -> Result<Vec<WorkflowRunRecord>, sqlx::Error> {
+        let rows: Vec<WorkflowRunRow> = sqlx::query_as(
+            r#"
+            SELECT id, workflow_id, workflow_version, trigger_id, state, queued_at, started_at,
+                   finished_at, input_data, output_data, error_message, duration_ms,
+                   parent_run_id, parent_node_id, retry_of_run_id, retry_from_node_id, dry_run,
+                   skip_reason, outputs_expired_at
+            FROM workflow_runs
+            WHERE parent_run_id = $1 AND state IN ('queued', 'running')
+            ORDER BY queued_at ASC
+            "#,
+        )
+        .bind(parent_run_id.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(|r| r.try_into_record()).collect()
+    }
+
+    /// Lists the runs that retry a failed run, oldest first.
+    pub async fn list_retries(
+        &self,
+        run_id: WorkflowRunId,
+    ) -> Result<Vec<WorkflowRunRecord>, sqlx::Error> {
+        let rows: Vec<WorkflowRunRow> = sqlx::query_as(
+            r#"
+            SELECT id, workflow_id, workflow_version, trigger_id, state, queued_at, started_at,
+                   finished_at, input_data, output_data, error_message, duration_ms,
+                   parent_run_id, parent_node_id, retry_of_run_id, retry_from_node_id, dry_run,
+                   skip_reason, outputs_expired_at
+            FROM workflow_runs
+            WHERE retry_of_run_id = $1
+            ORDER BY queued_at ASC
+            "#,
+        )
+        .bind(run_id.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(|r| r.try_into_record()).collect()
+    }
+
+    /// Returns the workflows of a run and its ancestors, outermost first.
+    pub async fn workflow_chain(
+        &self,
+        run_id: WorkflowRunId,
+    ) -> Result<Vec<WorkflowId>, sqlx::Error> {
+        let rows: Vec<(String,)> = sqlx::query_as(
+            r#"
+            WITH RECURSIVE chain AS (
+                SELECT id, workflow_id, parent_run_id, 0 AS depth
+                FROM workflow_runs
+                WHERE id = $1
+                UNION ALL
+                SELECT r.id, r.workflow_id, r.parent_run_id, c.depth + 1
+                FROM workflow_runs r
+                JOIN chain c ON r.id = c.parent_run_id
+            )
+            SELECT workflow_id FROM chain ORDER BY depth DESC
+            "#,
+        )
+        .bind(run_id.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|(id,)| {
+                WorkflowId::from_str(&id).map_err(|e| {
+                    sqlx::Error::Decode(Box::new(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!("invalid workflow id '{}': {}", id, e),
+                    )))
+                })
+            })
+            .collect()
+    }
+
+    /// Lists the workflows with finished runs whose outputs are kept.
+    pub async fn list_workflows_with_kept_outputs(&self) -> Result<Vec<WorkflowId>, sqlx::Error> {
+        let rows: Vec<(String,)> = sqlx::query_as(
+            r#"
+            SELECT DISTINCT workflow_id
+            FROM workflow_runs
+            WHERE state IN ('completed', 'failed', 'cancelled') AND outputs_expired_at IS NULL
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|(id,)| {
+                WorkflowId::from_str(&id).map_err(|e| {
+                    sqlx::Error::Decode(Box::new(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!("invalid workflow id '{}': {}", id, e),
+                    )))
+                })
+            })
+            .collect()
+    }
+
+    /// Lists the finished runs of a workflow whose outputs expired under
+    /// `policy` but are still kept, oldest first.
+    ///
+    /// Runs whose outputs a newer retry took over are kept until the retry
+    /// expires, since the retry reads them.
+    pub async fn list_expired_outputs(
+        &self,
+        workflow_id: WorkflowId,
+        policy: &RetentionPolicy,
+    ) -> Result<Vec<WorkflowRunRecord>, sqlx::Error> {
+        let rows: Vec<WorkflowRunRow> = sqlx::query_as(
+            r#"
+            SELECT id, workflow_id, workflow_version, trigger_id, state, queued_at, started_at,
+                   finished_at, input_data, output_data, error_message, duration_ms,
+                   parent_run_id, parent_node_id, retry_of_run_id, retry_from_node_id, dry_run,
+                   skip_reason, outputs_expired_at
+            FROM (
+                SELECT *, ROW_NUMBER() OVER (ORDER BY finished_at DESC) AS recency
+                FROM workflow_runs
+                WHERE workflow_id = $1 AND state IN ('completed', 'failed', 'cancelled')
+            ) finished
+            WHERE outputs_expired_at IS NULL
+              AND (finished_at < $2 OR recency > $3)
+              AND NOT EXISTS (
+                  SELECT 1
+                  FROM workflow_runs retry
+                  WHERE retry.retry_of_run_id = finished.id AND retry.outputs_expired_at IS NULL
+              )
+            ORDER BY finished_at ASC
+            "#,
+        )
+        .bind(workflow_id.to_string())
+        .bind(policy.expired_before(Utc::now()))
+        .bind(policy.max_runs.map(i64::from))
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(|r| r.try_into_record()).collect()
+    }
+
+    /// Records that a run's outputs expired.
+    pub async fn mark_outputs_expired(&self, run_id: WorkflowRunId) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE workflow_runs
+            SET outputs_expired_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(run_id.to_string())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
     /// Cancels all running runs for a workflow.
     pub async fn cancel_for_workflow(&self, workflow_id: WorkflowId) -> Result<u64, sqlx::Error> {
         let result = sqlx::query(
@@ -337,6 +887,49 @@ impl WorkflowRunRepository {
     }
 }
 
+/// Serializes the admission of a workflow's runs until the transaction
+/// ends.
+async fn lock_workflow_runs(
+    tx: &mut Transaction<'_, Postgres>,
+    workflow_id: WorkflowId,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1))")
+        .bind(workflow_id.to_string())
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+/// Lists a workflow's runs in progress that count against its concurrency
+/// limit, oldest first.
+async fn in_progress_runs(
+    tx: &mut Transaction<'_, Postgres>,
+    workflow_id: WorkflowId,
+) -> Result<Vec<WorkflowRunId>, sqlx::Error> {
+    let rows: Vec<(String,)> = sqlx::query_as(
+        r#"
+        SELECT id
+        FROM workflow_runs
+        WHERE workflow_id = $1 AND state = 'running' AND NOT dry_run
+        ORDER BY started_at ASC, queued_at ASC
+        "#,
+    )
+    .bind(workflow_id.to_string())
+    .fetch_all(&mut **tx)
+    .await?;
+
+    rows.into_iter()
+        .map(|(id,)| {
+            WorkflowRunId::from_str(&id).map_err(|e| {
+                sqlx::Error::Decode(Box::new(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("invalid run id '{}': {}", id, e),
+                )))
+            })
+        })
+        .collect()
+}
+
 /// Execution state for a node.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -389,6 +982,8 @@ pub struct NodeExecutionRecord {
     pub run_id: WorkflowRunId,
     /// Node ID within the workflow.
     pub node_id: String,
+    /// Item index for nodes executed per item of a fan-out.
+    pub item_index: Option<i32>,
     /// Current state.
     pub state: NodeState,
     /// When started.
@@ -413,6 +1008,7 @@ impl NodeExecutionRecord {
             id: NodeExecutionId::new(),
             run_id,
             node_id,
+            item_index: None,
             state: NodeState::Pending,
             started_at: None,
             finished_at: None,
@@ -430,6 +1026,7 @@ struct NodeExecutionRow {
     id: String,
     run_id: String,
     node_id: String,
+    item_index: Option<i32>,
     state: String,
     started_at: Option<DateTime<Utc>>,
     finished_at: Option<DateTime<Utc>>,
@@ -458,6 +1055,7 @@ impl NodeExecutionRow {
             id,
             run_id,
             node_id: self.node_id,
+            item_index: self.item_index,
             state: NodeState::from_str_value(&self.state),
             started_at: self.started_at,
             finished_at: self.finished_at,
@@ -487,11 +1085,11 @@ impl NodeExecutionRepository {
     ) -> Result<Vec<NodeExecutionRecord>, sqlx::Error> {
         let rows: Vec<NodeExecutionRow> = sqlx::query_as(
             r#"
-            SELECT id, run_id, node_id, state, started_at, finished_at,
+            SELECT id, run_id, node_id, item_index, state, started_at, finished_at,
                    input_data, output_key, error_message, duration_ms
             FROM node_executions
             WHERE run_id = $1
-            ORDER BY started_at ASC NULLS FIRST
+            ORDER BY started_at ASC NULLS FIRST, item_index ASC NULLS FIRST
             "#,
         )
         .bind(run_id.to_string())
@@ -506,14 +1104,15 @@ impl NodeExecutionRepository {
         sqlx::query(
             r#"
             INSERT INTO node_executions
-                (id, run_id, node_id, state, started_at, finished_at,
+                (id, run_id, node_id, item_index, state, started_at, finished_at,
                  input_data, output_key, error_message, duration_ms)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
             "#,
         )
         .bind(execution.id.to_string())
         .bind(execution.run_id.to_string())
         .bind(&execution.node_id)
+        .bind(execution.item_index)
         .bind(execution.state.as_str())
         .bind(execution.started_at)
         .bind(execution.finished_at)
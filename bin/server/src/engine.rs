@@ -0,0 +1,2099 @@
+//! Workflow engine wiring for the server.
+//!
+//! Connects the server to the NATS-backed event store (ADR-006). The engine
+//! is optional: without a NATS URL, runs are only recorded as queued.
+//!
+//! Runs created by the server (e.g., by the scheduler) are admitted under
+//! their workflow's concurrency policy and started in an orchestrator, or
+//! queued until a slot frees up. The results workers publish are applied to
+//! their runs, and run results are mirrored into the run history. Each
+//! change to a run holds the run's lock, so changes to a run never
+//! interleave. Sub-workflow nodes start child runs through
+//! [`EngineSubWorkflows`] and wait until the child run's end, as mirrored
+//! into the run history, resumes them; a child run is cancelled when its
+//! parent ends without completing. A failed run can be retried from one of its failed
+//! nodes. Dry runs substitute integration writes with fixture responses,
+//! which can be recorded from the outputs of an earlier run.
+//!
+//! Runs left in progress by a previous server process are recovered on
+//! startup, before anything else starts runs.
+//!
+//! Approvals requested by approval nodes are mirrored into the approvals
+//! table, where users decide on them, and delays started by delay nodes
+//! into the delays table. Expired approvals are resolved, delays that are
+//! over ended, and runs past their deadline failed, periodically.
+//!
+//! The outputs and events of finished runs are deleted periodically once
+//! they expire under the retention policy.
+
+use crate::config::RetentionConfig;
+use crate::db::workflow_run::RunState;
+use crate::db::{
+    ApprovalRecord, ApprovalRepository, ApprovalStatus, DelayRecord, DelayRepository,
+    WorkflowRecord, WorkflowRepository, WorkflowRunRecord, WorkflowRunRepository,
+};
+use crate::error::EngineError;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde_json::Value as JsonValue;
+use silver_telegram_core::WorkflowRunId;
+use silver_telegram_workflow::execution::ExecutionEvent;
+use silver_telegram_workflow::{
+    Admission, ApprovalDecision, ConcurrencyPolicy, DryRunConfig, EventStore, ExecutionState,
+    NatsEventStore, NatsObjectStore, NodeExecutionError, NodeExecutionState, NodeId, ObjectStore,
+    ObjectStoreError, Orchestrator, OrchestratorError, RetentionPolicy, RunLocks, RunStateError,
+    StateOverlay, SubWorkflowRequest, SubWorkflowRunner, WorkItemResult, Workflow, WorkflowGraph,
+    check_call_chain, create_nats_stores, run_outputs,
+};
+use sqlx::PgPool;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Durable consumer through which run results reach the run history.
+const RUN_HISTORY_CONSUMER: &str = "run-history";
+
+/// Durable consumer through which work item results reach the orchestrator.
+const WORK_RESULTS_CONSUMER: &str = "orchestrator";
+
+/// How often expired approvals are resolved.
+const APPROVAL_EXPIRY_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// How often delays that are over are ended.
+const DELAY_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// How often runs are checked against their deadlines.
+const DEADLINE_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// How often queued runs are started as their workflows' slots free up.
+const QUEUED_RUN_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// An orchestrator driving a run in the engine.
+type EngineOrchestrator<E, O> = Orchestrator<Arc<E>, Arc<O>>;
+
+/// A node that failed in a run, which the run can be retried from.
+#[derive(Debug, Clone)]
+pub struct FailedNode {
+    /// The node that failed.
+    pub node_id: NodeId,
+    /// The node's name in the workflow.
+    pub name: String,
+    /// Why the node failed.
+    pub error: Option<String>,
+}
+
+/// A node that a dry run substituted instead of executing it.
+#[derive(Debug, Clone)]
+pub struct Substitution {
+    /// The substituted node.
+    pub node_id: NodeId,
+    /// The item of the fan-out the node ran for, if any.
+    pub item_index: Option<usize>,
+    /// What the node would have sent.
+    pub request: JsonValue,
+    /// The fixture response the node completed with.
+    pub response: JsonValue,
+}
+
+/// Handle to the workflow engine.
+///
+/// Every change to a run goes through the run's lock in `run_locks`, so
+/// the orchestrators replaying a run change it one at a time.
+pub struct WorkflowEngine<E = NatsEventStore, O = NatsObjectStore> {
+    event_store: Arc<E>,
+    object_store: Arc<O>,
+    run_locks: RunLocks,
+}
+
+impl<E, O> Clone for WorkflowEngine<E, O> {
+    fn clone(&self) -> Self {
+        Self {
+            event_store: self.event_store.clone(),
+            object_store: self.object_store.clone(),
+            run_locks: self.run_locks.clone(),
+        }
+    }
+}
+
+impl<E: EventStore, O: ObjectStore> WorkflowEngine<E, O> {
+    /// Creates an engine on the given stores.
+    #[must_use]
+    pub fn new(event_store: E, object_store: O) -> Self {
+        Self {
+            event_store: Arc::new(event_store),
+            object_store: Arc::new(object_store),
+            run_locks: RunLocks::new(),
+        }
+    }
+
+    /// Returns the event store.
+    #[must_use]
+    pub fn event_store(&self) -> Arc<E> {
+        self.event_store.clone()
+    }
+
+    /// Returns the object store.
+    #[must_use]
+    pub fn object_store(&self) -> Arc<O> {
+        self.object_store.clone()
+    }
+
+    /// Queues and starts an orchestrator for a run record.
+    ///
+    /// The run's events use the record's ID, so the run history and the
+    /// event stream refer to the same run. `workflow` must hold the graph
+    /// of the version the run is pinned to.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the graph cannot be executed or the orchestrator
+    /// fails to publish its events.
+    pub async fn start_run(
+        &self,
+        workflow: &WorkflowRecord,
+        run: &WorkflowRunRecord,
+    ) -> Result<(), EngineError> {
+        self.start(workflow, run, None).await
+    }
+
+    /// Queues and starts an orchestrator for the record of a dry run.
+    ///
+    /// Like [`Self::start_run`], except that the orchestrator substitutes
+    /// the run's integration writes, and LLM calls if the configuration
+    /// stubs them, with fixture responses.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the graph cannot be executed or the orchestrator
+    /// fails to publish its events.
+    pub async fn start_dry_run(
+        &self,
+        workflow: &WorkflowRecord,
+        run: &WorkflowRunRecord,
+        dry_run: DryRunConfig,
+    ) -> Result<(), EngineError> {
+        self.start(workflow, run, Some(dry_run)).await
+    }
+
+    /// Queues and starts an orchestrator for a run record, as a dry run if
+    /// a configuration is given.
+    async fn start(
+        &self,
+        workflow: &WorkflowRecord,
+        run: &WorkflowRunRecord,
+        dry_run: Option<DryRunConfig>,
+    ) -> Result<(), EngineError> {
+        let _run_lock = self.run_locks.lock(run.id).await;
+        let workflow = executable_workflow(workflow)?;
+        let mut orchestrator = Orchestrator::new(
+            workflow,
+            self.event_store.clone(),
+            self.object_store.clone(),
+        );
+
+        let queued = match dry_run {
+            Some(dry_run) => {
+                orchestrator
+                    .queue_dry_run(run.id, run.trigger_id, run.input_data.clone(), dry_run)
+                    .await
+            }
+            None => {
+                orchestrator
+                    .queue_run(run.id, run.trigger_id, run.input_data.clone())
+                    .await
+            }
+        };
+        queued.map_err(|e| EngineError::OrchestratorFailed {
+            details: e.to_string(),
+        })?;
+        orchestrator
+            .start()
+            .await
+            .map_err(|e| EngineError::OrchestratorFailed {
+                details: e.to_string(),
+            })?;
+
+        Ok(())
+    }
+
+    /// Cancels a run in the engine.
+    ///
+    /// Replays the run's events and publishes `RunCancelled`, which also
+    /// tells workers to abort the run's executions. Runs that never reached
+    /// the engine or already ended there are left alone.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the graph cannot be executed or the orchestrator
+    /// fails to load or publish events.
+    pub async fn cancel_run(
+        &self,
+        workflow: &WorkflowRecord,
+        run_id: WorkflowRunId,
+        reason: &str,
+    ) -> Result<(), EngineError> {
+        let _run_lock = self.run_locks.lock(run_id).await;
+        let workflow = executable_workflow(workflow)?;
+        let mut orchestrator = Orchestrator::new(
+            workflow,
+            self.event_store.clone(),
+            self.object_store.clone(),
+        );
+
+        match orchestrator.initialize(Some(run_id)).await {
+            Ok(()) => {}
+            Err(
+                OrchestratorError::RunAlreadyTerminal { .. }
+                | OrchestratorError::RunState(RunStateError::NoEvents),
+            ) => return Ok(()),
+            Err(e) => {
+                return Err(EngineError::OrchestratorFailed {
+                    details: e.to_string(),
+                });
+            }
+        }
+        orchestrator
+            .cancel(reason)
+            .await
+            .map_err(|e| EngineError::OrchestratorFailed {
+                details: e.to_string(),
+            })
+    }
+
+    /// Records a user's decision on a pending approval in the engine.
+    ///
+    /// Replays the run's events and resumes it down the decision's port.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`EngineError::ApprovalNotPending`] if the run ended or the
+    /// approval was already decided, or another error if the graph cannot be
+    /// executed or the orchestrator fails to load or publish events.
+    pub async fn resolve_approval(
+        &self,
+        workflow: &WorkflowRecord,
+        run_id: WorkflowRunId,
+        node_id: NodeId,
+        item_index: Option<usize>,
+        decision: ApprovalDecision,
+        decided_by: String,
+    ) -> Result<(), EngineError> {
+        let _run_lock = self.run_locks.lock(run_id).await;
+        let workflow = executable_workflow(workflow)?;
+        let mut orchestrator = Orchestrator::new(
+            workflow,
+            self.event_store.clone(),
+            self.object_store.clone(),
+        );
+
+        let not_resolved = |e: OrchestratorError| match e {
+            OrchestratorError::RunAlreadyTerminal { .. }
+            | OrchestratorError::ApprovalNotPending { .. } => EngineError::ApprovalNotPending {
+                details: e.to_string(),
+            },
+            e => EngineError::OrchestratorFailed {
+                details: e.to_string(),
+            },
+        };
+        orchestrator
+            .initialize(Some(run_id))
+            .await
+            .map_err(not_resolved)?;
+        orchestrator
+            .resolve_approval(node_id, item_index, decision, Some(decided_by))
+            .await
+            .map_err(not_resolved)
+    }
+
+    /// Lists the nodes that failed in a failed run, in graph order.
+    ///
+    /// Returns an empty list if the run is unknown to the engine or did not
+    /// fail.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the graph cannot be executed or the orchestrator
+    /// fails to load the run's events.
+    pub async fn failed_nodes(
+        &self,
+        workflow: &WorkflowRecord,
+        run_id: WorkflowRunId,
+    ) -> Result<Vec<FailedNode>, EngineError> {
+        let workflow = executable_workflow(workflow)?;
+        let orchestrator = Orchestrator::new(
+            workflow.clone(),
+            self.event_store.clone(),
+            self.object_store.clone(),
+        );
+
+        let state = match orchestrator.load_run_state(run_id).await {
+            Ok(state) => state,
+            Err(OrchestratorError::RunNotFound { .. }) => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(EngineError::OrchestratorFailed {
+                    details: e.to_string(),
+                });
+            }
+        };
+        if state.execution_state != ExecutionState::Failed {
+            return Ok(Vec::new());
+        }
+        Ok(workflow
+            .graph
+            .nodes()
+            .filter_map(|node| {
+                let exec = state.node_states.get(&node.id)?;
+                (exec.state == NodeExecutionState::Failed).then(|| FailedNode {
+                    node_id: node.id,
+                    name: node.name.clone(),
+                    error: exec.error.clone(),
+                })
+            })
+            .collect())
+    }
+
+    /// Returns the execution state of each node of a run, to render over
+    /// the run's graph.
+    ///
+    /// Returns an empty overlay if the run is unknown to the engine.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the graph cannot be executed or the orchestrator
+    /// fails to load the run's events.
+    pub async fn state_overlay(
+        &self,
+        workflow: &WorkflowRecord,
+        run_id: WorkflowRunId,
+    ) -> Result<StateOverlay, EngineError> {
+        let orchestrator = Orchestrator::new(
+            executable_workflow(workflow)?,
+            self.event_store.clone(),
+            self.object_store.clone(),
+        );
+
+        match orchestrator.load_run_state(run_id).await {
+            Ok(state) => Ok(state
+                .node_states
+                .iter()
+                .map(|(node_id, exec)| (*node_id, exec.state))
+                .collect()),
+            Err(OrchestratorError::RunNotFound { .. }) => Ok(StateOverlay::new()),
+            Err(e) => Err(EngineError::OrchestratorFailed {
+                details: e.to_string(),
+            }),
+        }
+    }
+
+    /// Queues and starts a run that retries a failed run from one of its
+    /// failed nodes.
+    ///
+    /// The new run reuses the outputs of the nodes that completed upstream
+    /// of the node, so those outputs must still be in the object store.
+    /// `workflow` must hold the graph of the version the failed run is
+    /// pinned to, and `run` is the new run's record.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`EngineError::RetryNotAllowed`] if the run is unknown to the
+    /// engine, did not fail, or the node did not fail in it, or another
+    /// error if the graph cannot be executed or the orchestrator fails to
+    /// load or publish events.
+    pub async fn retry_run(
+        &self,
+        workflow: &WorkflowRecord,
+        failed_run_id: WorkflowRunId,
+        run: &WorkflowRunRecord,
+        node_id: NodeId,
+    ) -> Result<(), EngineError> {
+        let _run_lock = self.run_locks.lock(run.id).await;
+        let workflow = executable_workflow(workflow)?;
+        let mut orchestrator = Orchestrator::new(
+            workflow,
+            self.event_store.clone(),
+            self.object_store.clone(),
+        );
+
+        let not_retried = |e: OrchestratorError| match e {
+            OrchestratorError::RunNotFound { .. }
+            | OrchestratorError::RunNotFailed { .. }
+            | OrchestratorError::NodeNotFailed { .. } => EngineError::RetryNotAllowed {
+                details: e.to_string(),
+            },
+            e => EngineError::OrchestratorFailed {
+                details: e.to_string(),
+            },
+        };
+        let failed = orchestrator
+            .load_run_state(failed_run_id)
+            .await
+            .map_err(not_retried)?;
+        orchestrator
+            .queue_retry(run.id, &failed, node_id)
+            .await
+            .map_err(not_retried)?;
+        orchestrator.start().await.map_err(not_retried)
+    }
+
+    /// Creates a run under its workflow's concurrency policy and starts it
+    /// if it is admitted.
+    ///
+    /// `workflow` must hold the graph of the version the run is pinned to,
+    /// and its concurrency policy decides on the run; dry runs are not
+    /// limited. A run that waits for a slot stays queued until
+    /// [`Self::start_queued_runs`] starts it. The runs cancelled to make
+    /// room for the run are cancelled in the engine, and a run that fails
+    /// to start is recorded as failed. A run that retries a failed run is
+    /// started as a retry. Returns None if a run with the same ID already
+    /// exists.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the run history cannot be updated or the run
+    /// fails to start.
+    pub async fn launch_run(
+        &self,
+        pool: PgPool,
+        workflow: &WorkflowRecord,
+        run: &mut WorkflowRunRecord,
+    ) -> Result<Option<Admission>, EngineError> {
+        let history_failed = |e: sqlx::Error| EngineError::RunHistoryFailed {
+            details: e.to_string(),
+        };
+        let run_repo = WorkflowRunRepository::new(pool.clone());
+        let policy = if run.dry_run {
+            ConcurrencyPolicy::unlimited()
+        } else {
+            workflow.concurrency
+        };
+
+        let Some(admission) = run_repo.admit(run, &policy).await.map_err(history_failed)? else {
+            return Ok(None);
+        };
+
+        match &admission {
+            Admission::Queue | Admission::Skip { .. } => return Ok(Some(admission)),
+            Admission::CancelOlder { runs } => {
+                let workflow_repo = WorkflowRepository::new(pool);
+                for &older in runs {
+                    if let Err(e) = self
+                        .cancel_older_run(&run_repo, &workflow_repo, older)
+                        .await
+                    {
+                        tracing::warn!(run_id = %older, error = %e, "Failed to cancel older run");
+                    }
+                }
+            }
+            Admission::Start => {}
+        }
+
+        if let Err(e) = self.start_admitted(workflow, run).await {
+            run.fail(e.to_string());
+            run_repo.update(run).await.map_err(history_failed)?;
+            return Err(e);
+        }
+        Ok(Some(admission))
+    }
+
+    /// Cancels a run in the engine to make room for a newer run.
+    async fn cancel_older_run(
+        &self,
+        run_repo: &WorkflowRunRepository,
+        workflow_repo: &WorkflowRepository,
+        run_id: WorkflowRunId,
+    ) -> Result<(), EngineError> {
+        let history_failed = |e: sqlx::Error| EngineError::RunHistoryFailed {
+            details: e.to_string(),
+        };
+        let Some(run) = run_repo.find_by_id(run_id).await.map_err(history_failed)? else {
+            return Ok(());
+        };
+        let Some(workflow) = workflow_repo
+            .find_for_run(&run)
+            .await
+            .map_err(history_failed)?
+        else {
+            return Ok(());
+        };
+        self.cancel_run(&workflow, run_id, "cancelled by a newer run")
+            .await
+    }
+
+    /// Starts an admitted run in the engine, as a retry if it retries a
+    /// failed run.
+    async fn start_admitted(
+        &self,
+        workflow: &WorkflowRecord,
+        run: &WorkflowRunRecord,
+    ) -> Result<(), EngineError> {
+        match (run.retry_of_run_id, &run.retry_from_node_id) {
+            (Some(failed_run_id), Some(node_id)) => {
+                let node_id =
+                    NodeId::from_str(node_id).map_err(|e| EngineError::RetryNotAllowed {
+                        details: format!("invalid node id '{node_id}': {e}"),
+                    })?;
+                self.retry_run(workflow, failed_run_id, run, node_id).await
+            }
+            _ => self.start_run(workflow, run).await,
+        }
+    }
+
+    /// Records the outputs of a run's substitutable nodes as fixtures for
+    /// dry runs.
+    ///
+    /// Every top-level integration node with a write operation that
+    /// completed in the run, and every AI layer node if `stub_llm` is set,
+    /// gets its output as fixture. Returns an empty configuration if the
+    /// run is unknown to the engine.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the graph cannot be executed, the orchestrator
+    /// fails to load the run's events, or an output cannot be read.
+    pub async fn recorded_fixtures(
+        &self,
+        workflow: &WorkflowRecord,
+        run_id: WorkflowRunId,
+        stub_llm: bool,
+    ) -> Result<DryRunConfig, EngineError> {
+        let workflow = executable_workflow(workflow)?;
+        let orchestrator = Orchestrator::new(
+            workflow.clone(),
+            self.event_store.clone(),
+            self.object_store.clone(),
+        );
+
+        let mut config = DryRunConfig::new();
+        if stub_llm {
+            config = config.with_stub_llm();
+        }
+        let state = match orchestrator.load_run_state(run_id).await {
+            Ok(state) => state,
+            Err(OrchestratorError::RunNotFound { .. }) => return Ok(config),
+            Err(e) => {
+                return Err(EngineError::OrchestratorFailed {
+                    details: e.to_string(),
+                });
+            }
+        };
+
+        let read_failed = |e: String| EngineError::OrchestratorFailed {
+            details: format!("failed to read the output of run {run_id}: {e}"),
+        };
+        for node in workflow.graph.nodes() {
+            if !config.substitutes(node) {
+                continue;
+            }
+            let Some(key) = state
+                .node_states
+                .get(&node.id)
+                .filter(|exec| exec.state == NodeExecutionState::Completed)
+                .and_then(|exec| exec.output_key.as_deref())
+            else {
+                continue;
+            };
+            let bytes = self
+                .object_store
+                .get(key)
+                .await
+                .map_err(|e| read_failed(e.to_string()))?;
+            let output = serde_json::from_slice(&bytes).map_err(|e| read_failed(e.to_string()))?;
+            config = config.with_fixture(node.id, output);
+        }
+        Ok(config)
+    }
+
+    /// Lists the nodes a dry run substituted, with what each would have
+    /// sent and the response it got instead, in the order substituted.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the run's events cannot be loaded.
+    pub async fn substitutions(
+        &self,
+        run_id: WorkflowRunId,
+    ) -> Result<Vec<Substitution>, EngineError> {
+        let events = self.event_store.load_events(run_id).await.map_err(|e| {
+            EngineError::OrchestratorFailed {
+                details: e.to_string(),
+            }
+        })?;
+        Ok(events
+            .into_iter()
+            .filter_map(|event| match event {
+                ExecutionEvent::NodeSubstituted {
+                    node_id,
+                    item_index,
+                    request,
+                    response,
+                    ..
+                } => Some(Substitution {
+                    node_id,
+                    item_index,
+                    request,
+                    response,
+                }),
+                _ => None,
+            })
+            .collect())
+    }
+
+    /// Resolves the approvals that expired, with their nodes' expiry
+    /// decisions.
+    ///
+    /// Each run with an expired pending approval in the approvals table is
+    /// replayed, and its orchestrator resolves every approval that expired.
+    /// A run that fails to resolve is logged and skipped.
+    ///
+    /// Returns the number of approvals resolved.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the approvals or run history cannot be read.
+    pub async fn expire_approvals(&self, pool: PgPool) -> Result<usize, EngineError> {
+        let history_failed = |e: sqlx::Error| EngineError::RunHistoryFailed {
+            details: e.to_string(),
+        };
+        let run_repo = WorkflowRunRepository::new(pool.clone());
+        let workflow_repo = WorkflowRepository::new(pool.clone());
+        let now = Utc::now();
+        let run_ids = ApprovalRepository::new(pool)
+            .list_runs_with_expired(now)
+            .await
+            .map_err(history_failed)?;
+
+        let mut resolved = 0;
+        for run_id in run_ids {
+            let Some(run) = run_repo.find_by_id(run_id).await.map_err(history_failed)? else {
+                continue;
+            };
+            let Some(workflow) = workflow_repo
+                .find_for_run(&run)
+                .await
+                .map_err(history_failed)?
+            else {
+                continue;
+            };
+
+            match self.expire_run_approvals(&workflow, run_id, now).await {
+                Ok(count) => resolved += count,
+                Err(e) => {
+                    tracing::warn!(%run_id, error = %e, "Failed to expire approvals of run");
+                }
+            }
+        }
+
+        Ok(resolved)
+    }
+
+    /// Resolves expired approvals every [`APPROVAL_EXPIRY_INTERVAL`].
+    ///
+    /// Failures are logged and retried on the next pass. Never returns.
+    pub async fn expire_approvals_periodically(self, pool: PgPool) {
+        let mut interval = tokio::time::interval(APPROVAL_EXPIRY_INTERVAL);
+        loop {
+            interval.tick().await;
+            match self.expire_approvals(pool.clone()).await {
+                Ok(count) if count > 0 => {
+                    tracing::info!(expired_approvals = count, "Resolved expired approvals");
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    tracing::warn!(error = %e, "Failed to resolve expired approvals");
+                }
+            }
+        }
+    }
+
+    /// Replays a run and resolves its approvals that expired by `now`.
+    async fn expire_run_approvals(
+        &self,
+        workflow: &WorkflowRecord,
+        run_id: WorkflowRunId,
+        now: DateTime<Utc>,
+    ) -> Result<usize, EngineError> {
+        let _run_lock = self.run_locks.lock(run_id).await;
+        let Some(mut orchestrator) = self.replay_active_run(workflow, run_id).await? else {
+            return Ok(0);
+        };
+        orchestrator
+            .expire_approvals(now)
+            .await
+            .map_err(|e| EngineError::OrchestratorFailed {
+                details: e.to_string(),
+            })
+    }
+
+    /// Ends the delays that are over, passing each delay node's input on.
+    ///
+    /// Each run with a delay in the delays table that ended is replayed, and
+    /// its orchestrator ends every delay that is over. The table outlives
+    /// the server process, so delays that ended while the server was down
+    /// end on the first pass after it starts. A run that fails to resume is
+    /// logged and skipped.
+    ///
+    /// Returns the number of delays ended.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the delays or run history cannot be read.
+    pub async fn end_delays(&self, pool: PgPool) -> Result<usize, EngineError> {
+        let history_failed = |e: sqlx::Error| EngineError::RunHistoryFailed {
+            details: e.to_string(),
+        };
+        let run_repo = WorkflowRunRepository::new(pool.clone());
+        let workflow_repo = WorkflowRepository::new(pool.clone());
+        let now = Utc::now();
+        let run_ids = DelayRepository::new(pool)
+            .list_runs_due(now)
+            .await
+            .map_err(history_failed)?;
+
+        let mut ended = 0;
+        for run_id in run_ids {
+            let Some(run) = run_repo.find_by_id(run_id).await.map_err(history_failed)? else {
+                continue;
+            };
+            let Some(workflow) = workflow_repo
+                .find_for_run(&run)
+                .await
+                .map_err(history_failed)?
+            else {
+                continue;
+            };
+
+            match self.end_run_delays(&workflow, run_id, now).await {
+                Ok(count) => ended += count,
+                Err(e) => {
+                    tracing::warn!(%run_id, error = %e, "Failed to end delays of run");
+                }
+            }
+        }
+
+        Ok(ended)
+    }
+
+    /// Ends the delays that are over every [`DELAY_POLL_INTERVAL`].
+    ///
+    /// Failures are logged and retried on the next pass. Never returns.
+    pub async fn end_delays_periodically(self, pool: PgPool) {
+        let mut interval = tokio::time::interval(DELAY_POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+            match self.end_delays(pool.clone()).await {
+                Ok(count) if count > 0 => {
+                    tracing::info!(ended_delays = count, "Ended delays");
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    tracing::warn!(error = %e, "Failed to end delays");
+                }
+            }
+        }
+    }
+
+    /// Replays a run and ends its delays that are over by `now`.
+    async fn end_run_delays(
+        &self,
+        workflow: &WorkflowRecord,
+        run_id: WorkflowRunId,
+        now: DateTime<Utc>,
+    ) -> Result<usize, EngineError> {
+        let _run_lock = self.run_locks.lock(run_id).await;
+        let Some(mut orchestrator) = self.replay_active_run(workflow, run_id).await? else {
+            return Ok(0);
+        };
+        orchestrator
+            .end_delays(now)
+            .await
+            .map_err(|e| EngineError::OrchestratorFailed {
+                details: e.to_string(),
+            })
+    }
+
+    /// Fails the runs that are still going past their deadline.
+    ///
+    /// The orchestrator only checks a run's deadline as results arrive, so
+    /// a run whose results never arrive would stay in progress forever.
+    /// Each running run that has been running longer than its workflow's
+    /// maximum duration is replayed, and its orchestrator fails it along
+    /// with its running nodes if its recorded deadline passed. Workers abort
+    /// the run's executions as it fails, and its child runs are cancelled. A
+    /// run that fails to resume is logged and skipped.
+    ///
+    /// Returns the number of runs failed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the run history cannot be read.
+    pub async fn enforce_deadlines(&self, pool: PgPool) -> Result<usize, EngineError> {
+        let history_failed = |e: sqlx::Error| EngineError::RunHistoryFailed {
+            details: e.to_string(),
+        };
+        let run_repo = WorkflowRunRepository::new(pool.clone());
+        let workflow_repo = WorkflowRepository::new(pool);
+        let runs = run_repo
+            .list_past_deadline(Utc::now())
+            .await
+            .map_err(history_failed)?;
+
+        let mut failed = 0;
+        for run in runs {
+            let Some(workflow) = workflow_repo
+                .find_for_run(&run)
+                .await
+                .map_err(history_failed)?
+            else {
+                continue;
+            };
+
+            match self.enforce_run_deadline(&workflow, run.id).await {
+                Ok(true) => failed += 1,
+                Ok(false) => {}
+                Err(e) => {
+                    tracing::warn!(run_id = %run.id, error = %e, "Failed to enforce deadline of run");
+                }
+            }
+        }
+
+        Ok(failed)
+    }
+
+    /// Fails the runs past their deadline every [`DEADLINE_POLL_INTERVAL`].
+    ///
+    /// Failures are logged and retried on the next pass. Never returns.
+    pub async fn enforce_deadlines_periodically(self, pool: PgPool) {
+        let mut interval = tokio::time::interval(DEADLINE_POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+            match self.enforce_deadlines(pool.clone()).await {
+                Ok(count) if count > 0 => {
+                    tracing::info!(failed_runs = count, "Failed runs past their deadline");
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    tracing::warn!(error = %e, "Failed to enforce run deadlines");
+                }
+            }
+        }
+    }
+
+    /// Replays a run and fails it if it is past its deadline.
+    ///
+    /// Returns true if the run was failed.
+    async fn enforce_run_deadline(
+        &self,
+        workflow: &WorkflowRecord,
+        run_id: WorkflowRunId,
+    ) -> Result<bool, EngineError> {
+        let _run_lock = self.run_locks.lock(run_id).await;
+        let Some(mut orchestrator) = self.replay_active_run(workflow, run_id).await? else {
+            return Ok(false);
+        };
+        orchestrator
+            .enforce_deadline()
+            .await
+            .map_err(|e| EngineError::OrchestratorFailed {
+                details: e.to_string(),
+            })
+    }
+
+    /// Starts the queued runs that fit in their workflows' concurrency
+    /// limits, oldest first.
+    ///
+    /// Returns the number of runs started.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the run history cannot be read.
+    pub async fn start_queued_runs(&self, pool: PgPool) -> Result<usize, EngineError> {
+        let history_failed = |e: sqlx::Error| EngineError::RunHistoryFailed {
+            details: e.to_string(),
+        };
+        let run_repo = WorkflowRunRepository::new(pool.clone());
+        let workflow_repo = WorkflowRepository::new(pool);
+        let workflow_ids = run_repo
+            .list_workflows_with_queued()
+            .await
+            .map_err(history_failed)?;
+
+        let mut started = 0;
+        for workflow_id in workflow_ids {
+            let Some(current) = workflow_repo
+                .find_by_id(workflow_id)
+                .await
+                .map_err(history_failed)?
+            else {
+                continue;
+            };
+            let runs = run_repo
+                .claim_queued(workflow_id, &current.concurrency)
+                .await
+                .map_err(history_failed)?;
+
+            for mut run in runs {
+                let workflow = match workflow_repo.find_for_run(&run).await {
+                    Ok(Some(workflow)) => workflow,
+                    Ok(None) => {
+                        run.fail("workflow version no longer exists".to_string());
+                        run_repo.update(&run).await.map_err(history_failed)?;
+                        continue;
+                    }
+                    Err(e) => {
+                        tracing::warn!(run_id = %run.id, error = %e, "Failed to load workflow of run");
+                        run.fail(e.to_string());
+                        run_repo.update(&run).await.map_err(history_failed)?;
+                        continue;
+                    }
+                };
+                match self.start_admitted(&workflow, &run).await {
+                    Ok(()) => started += 1,
+                    Err(e) => {
+                        tracing::warn!(run_id = %run.id, error = %e, "Failed to start queued run");
+                        run.fail(e.to_string());
+                        run_repo.update(&run).await.map_err(history_failed)?;
+                    }
+                }
+            }
+        }
+
+        Ok(started)
+    }
+
+    /// Starts the queued runs that fit in their workflows' concurrency
+    /// limits every [`QUEUED_RUN_POLL_INTERVAL`].
+    ///
+    /// Failures are logged and retried on the next pass. Never returns.
+    pub async fn start_queued_runs_periodically(self, pool: PgPool) {
+        let mut interval = tokio::time::interval(QUEUED_RUN_POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+            match self.start_queued_runs(pool.clone()).await {
+                Ok(count) if count > 0 => {
+                    tracing::info!(started_runs = count, "Started queued runs");
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    tracing::warn!(error = %e, "Failed to start queued runs");
+                }
+            }
+        }
+    }
+
+    /// Replays a run into a new orchestrator. The caller holds the run's
+    /// lock while it uses the orchestrator.
+    ///
+    /// Returns None if the run is unknown to the engine or already ended.
+    async fn replay_active_run(
+        &self,
+        workflow: &WorkflowRecord,
+        run_id: WorkflowRunId,
+    ) -> Result<Option<EngineOrchestrator<E, O>>, EngineError> {
+        let workflow = executable_workflow(workflow)?;
+        let mut orchestrator = Orchestrator::new(
+            workflow,
+            self.event_store.clone(),
+            self.object_store.clone(),
+        );
+
+        match orchestrator.initialize(Some(run_id)).await {
+            Ok(()) => Ok(Some(orchestrator)),
+            Err(
+                OrchestratorError::RunAlreadyTerminal { .. }
+                | OrchestratorError::RunState(RunStateError::NoEvents),
+            ) => Ok(None),
+            Err(e) => Err(EngineError::OrchestratorFailed {
+                details: e.to_string(),
+            }),
+        }
+    }
+
+    /// Recovers the runs that were in progress when the server stopped.
+    ///
+    /// Must run before anything else starts runs in the engine: a run
+    /// recorded as started without events is taken to be interrupted, which
+    /// only holds while no start is in progress.
+    ///
+    /// Each queued or running run in the run history is replayed from its
+    /// events and picked up by a new orchestrator, which dispatches the
+    /// work that never finished. A run that was recorded as started but
+    /// never reached the engine is started, unless it is a dry run, which is
+    /// recorded as failed; queued runs that never reached it wait for a
+    /// slot, and runs that already ended there are left alone. A run that
+    /// fails to recover is logged and skipped, and a run whose interrupted
+    /// start fails is recorded as failed.
+    ///
+    /// Returns the number of runs recovered.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the run history cannot be read.
+    pub async fn recover_runs(&self, pool: PgPool) -> Result<usize, EngineError> {
+        let run_repo = WorkflowRunRepository::new(pool.clone());
+        let workflow_repo = WorkflowRepository::new(pool);
+        let runs = run_repo
+            .list_active()
+            .await
+            .map_err(|e| EngineError::RunHistoryFailed {
+                details: e.to_string(),
+            })?;
+
+        let mut recovered = 0;
+        for mut run in runs {
+            let workflow = match workflow_repo.find_for_run(&run).await {
+                Ok(Some(workflow)) => workflow,
+                Ok(None) => continue,
+                Err(e) => {
+                    tracing::warn!(run_id = %run.id, error = %e, "Failed to load workflow of run");
+                    continue;
+                }
+            };
+
+            match self.recover_run(&run_repo, &workflow, &mut run).await {
+                Ok(true) => recovered += 1,
+                Ok(false) => continue,
+                Err(e) => {
+                    tracing::warn!(run_id = %run.id, error = %e, "Failed to recover run");
+                    continue;
+                }
+            }
+
+            // A recovered run is running in the engine
+            if run.state == RunState::Queued {
+                run.start();
+                if let Err(e) = run_repo.update(&run).await {
+                    tracing::warn!(run_id = %run.id, error = %e, "Failed to mark run started");
+                }
+            }
+        }
+
+        Ok(recovered)
+    }
+
+    /// Replays a run and lets a new orchestrator pick it up, or starts it
+    /// if its start was interrupted before it reached the engine.
+    ///
+    /// A run whose interrupted start fails, and a dry run whose start was
+    /// interrupted, are recorded as failed. Returns false if the run is not
+    /// picked up (see [`recovery_for`]).
+    async fn recover_run(
+        &self,
+        run_repo: &WorkflowRunRepository,
+        workflow: &WorkflowRecord,
+        run: &mut WorkflowRunRecord,
+    ) -> Result<bool, EngineError> {
+        let run_lock = self.run_locks.lock(run.id).await;
+        let mut orchestrator = Orchestrator::new(
+            executable_workflow(workflow)?,
+            self.event_store.clone(),
+            self.object_store.clone(),
+        );
+
+        let has_events = match orchestrator.initialize(Some(run.id)).await {
+            Ok(()) => true,
+            Err(OrchestratorError::RunAlreadyTerminal { .. }) => return Ok(false),
+            Err(
+                OrchestratorError::RunNotFound { .. }
+                | OrchestratorError::RunState(RunStateError::NoEvents),
+            ) => false,
+            Err(e) => {
+                return Err(EngineError::OrchestratorFailed {
+                    details: e.to_string(),
+                });
+            }
+        };
+
+        match recovery_for(run, has_events) {
+            Recovery::Resume => {
+                orchestrator
+                    .recover()
+                    .await
+                    .map_err(|e| EngineError::OrchestratorFailed {
+                        details: e.to_string(),
+                    })?;
+                Ok(true)
+            }
+            Recovery::Start => {
+                // Starting the run takes its lock again
+                drop(run_lock);
+                tracing::info!(run_id = %run.id, "Starting run whose start was interrupted");
+                if let Err(e) = self.start_admitted(workflow, run).await {
+                    run.fail(e.to_string());
+                    run_repo
+                        .update(run)
+                        .await
+                        .map_err(|e| EngineError::RunHistoryFailed {
+                            details: e.to_string(),
+                        })?;
+                    return Err(e);
+                }
+                Ok(true)
+            }
+            Recovery::Fail => {
+                tracing::warn!(run_id = %run.id, "Failing dry run whose start was interrupted");
+                run.fail("dry run was interrupted before it started".to_string());
+                run_repo
+                    .update(run)
+                    .await
+                    .map_err(|e| EngineError::RunHistoryFailed {
+                        details: e.to_string(),
+                    })?;
+                Ok(false)
+            }
+            Recovery::Wait => Ok(false),
+        }
+    }
+
+    /// Replays a result's run and hands the result to its orchestrator.
+    async fn apply_work_result(
+        &self,
+        run_repo: &WorkflowRunRepository,
+        workflow_repo: &WorkflowRepository,
+        result: WorkItemResult,
+    ) -> Result<(), EngineError> {
+        let history_failed = |e: sqlx::Error| EngineError::RunHistoryFailed {
+            details: e.to_string(),
+        };
+        let Some(run) = run_repo
+            .find_by_id(result.run_id())
+            .await
+            .map_err(history_failed)?
+        else {
+            return Ok(());
+        };
+        let Some(workflow) = workflow_repo
+            .find_for_run(&run)
+            .await
+            .map_err(history_failed)?
+        else {
+            return Ok(());
+        };
+        let _run_lock = self.run_locks.lock(run.id).await;
+        let Some(mut orchestrator) = self.replay_active_run(&workflow, run.id).await? else {
+            return Ok(());
+        };
+        orchestrator
+            .handle_result(result)
+            .await
+            .map_err(|e| EngineError::OrchestratorFailed {
+                details: e.to_string(),
+            })
+    }
+
+    /// Cancels the unfinished child runs of a run, and theirs in turn.
+    ///
+    /// Each child is cancelled in the engine and in the run history, so
+    /// children that never reached the engine end as well.
+    async fn cancel_children(
+        &self,
+        run_repo: &WorkflowRunRepository,
+        workflow_repo: &WorkflowRepository,
+        run_id: WorkflowRunId,
+    ) -> Result<(), EngineError> {
+        let history_failed = |e: sqlx::Error| EngineError::RunHistoryFailed {
+            details: e.to_string(),
+        };
+
+        let mut parents = vec![run_id];
+        while let Some(parent) = parents.pop() {
+            for mut child in run_repo
+                .list_active_children(parent)
+                .await
+                .map_err(history_failed)?
+            {
+                if let Some(workflow) = workflow_repo
+                    .find_for_run(&child)
+                    .await
+                    .map_err(history_failed)?
+                {
+                    self.cancel_run(&workflow, child.id, "parent run ended")
+                        .await?;
+                }
+                child.cancel();
+                run_repo.update(&child).await.map_err(history_failed)?;
+                parents.push(child.id);
+            }
+        }
+        Ok(())
+    }
+
+    /// Resumes the sub-workflow node that started a child run, now that the
+    /// child run ended with `outcome`.
+    ///
+    /// Runs without a parent, and parents that ended, are left alone.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the run history cannot be read, or the parent's
+    /// orchestrator fails to load or publish events.
+    pub async fn end_child_run(
+        &self,
+        pool: PgPool,
+        child: &WorkflowRunRecord,
+        outcome: Result<JsonValue, NodeExecutionError>,
+    ) -> Result<(), EngineError> {
+        let run_repo = WorkflowRunRepository::new(pool.clone());
+        let workflow_repo = WorkflowRepository::new(pool);
+        self.resume_parent(&run_repo, &workflow_repo, child, outcome)
+            .await
+    }
+
+    /// Replays a child run's parent and hands it the child run's outcome.
+    async fn resume_parent(
+        &self,
+        run_repo: &WorkflowRunRepository,
+        workflow_repo: &WorkflowRepository,
+        child: &WorkflowRunRecord,
+        outcome: Result<JsonValue, NodeExecutionError>,
+    ) -> Result<(), EngineError> {
+        let history_failed = |e: sqlx::Error| EngineError::RunHistoryFailed {
+            details: e.to_string(),
+        };
+        let (Some(parent_run_id), Some(parent_node_id)) =
+            (child.parent_run_id, &child.parent_node_id)
+        else {
+            return Ok(());
+        };
+        let node_id =
+            NodeId::from_str(parent_node_id).map_err(|e| EngineError::OrchestratorFailed {
+                details: format!("invalid parent node id '{parent_node_id}': {e}"),
+            })?;
+        let Some(parent) = run_repo
+            .find_by_id(parent_run_id)
+            .await
+            .map_err(history_failed)?
+        else {
+            return Ok(());
+        };
+        let Some(workflow) = workflow_repo
+            .find_for_run(&parent)
+            .await
+            .map_err(history_failed)?
+        else {
+            return Ok(());
+        };
+        let _run_lock = self.run_locks.lock(parent.id).await;
+        let Some(mut orchestrator) = self.replay_active_run(&workflow, parent.id).await? else {
+            return Ok(());
+        };
+        orchestrator
+            .end_sub_workflow(node_id, child.id, outcome)
+            .await
+            .map(|_| ())
+            .map_err(|e| EngineError::OrchestratorFailed {
+                details: e.to_string(),
+            })
+    }
+}
+
+impl WorkflowEngine {
+    /// Connects to NATS and ensures the engine streams and bucket exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the connection or stream setup fails.
+    pub async fn connect(url: &str) -> Result<Self, EngineError> {
+        let config = silver_telegram_workflow::NatsConfig::new(url);
+        let (event_store, object_store) =
+            create_nats_stores(&config)
+                .await
+                .map_err(|e| EngineError::ConnectionFailed {
+                    details: e.to_string(),
+                })?;
+
+        Ok(Self::new(event_store, object_store))
+    }
+
+    /// Deletes the outputs of finished runs that expired under their
+    /// workflow's retention policy, with the limits the workflow leaves
+    /// unset taken from `global`.
+    ///
+    /// A run's outputs are deleted from the object store and its events are
+    /// purged, then the run history records that its outputs expired.
+    /// Returns the number of runs whose outputs expired.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the run history cannot be read or updated.
+    pub async fn expire_outputs(
+        &self,
+        pool: PgPool,
+        global: RetentionPolicy,
+    ) -> Result<usize, EngineError> {
+        let history_failed = |e: sqlx::Error| EngineError::RunHistoryFailed {
+            details: e.to_string(),
+        };
+        let run_repo = WorkflowRunRepository::new(pool.clone());
+        let workflow_repo = WorkflowRepository::new(pool);
+        let workflow_ids = run_repo
+            .list_workflows_with_kept_outputs()
+            .await
+            .map_err(history_failed)?;
+
+        let mut expired = 0;
+        for workflow_id in workflow_ids {
+            let policy = workflow_repo
+                .find_by_id(workflow_id)
+                .await
+                .map_err(history_failed)?
+                .map_or(global, |workflow| workflow.retention.or(global));
+            if policy.is_unlimited() {
+                continue;
+            }
+            let runs = run_repo
+                .list_expired_outputs(workflow_id, &policy)
+                .await
+                .map_err(history_failed)?;
+
+            for run in runs {
+                if let Err(e) = self.delete_run_outputs(run.id).await {
+                    tracing::warn!(run_id = %run.id, error = %e, "Failed to delete outputs of run");
+                    continue;
+                }
+                run_repo
+                    .mark_outputs_expired(run.id)
+                    .await
+                    .map_err(history_failed)?;
+                expired += 1;
+            }
+        }
+
+        Ok(expired)
+    }
+
+    /// Deletes expired outputs every `config.interval_seconds`.
+    ///
+    /// Failures are logged and retried on the next pass. Never returns.
+    pub async fn expire_outputs_periodically(self, pool: PgPool, config: RetentionConfig) {
+        let global = config.policy();
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(
+            config.interval_seconds.max(1),
+        ));
+        loop {
+            interval.tick().await;
+            match self.expire_outputs(pool.clone(), global).await {
+                Ok(count) if count > 0 => {
+                    tracing::info!(expired_runs = count, "Deleted expired run outputs");
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    tracing::warn!(error = %e, "Failed to delete expired run outputs");
+                }
+            }
+        }
+    }
+
+    /// Deletes the outputs a finished run produced and purges its events.
+    ///
+    /// Outputs that are already gone are skipped, so a pass that failed
+    /// halfway can be repeated.
+    async fn delete_run_outputs(&self, run_id: WorkflowRunId) -> Result<(), EngineError> {
+        let store_failed = |details: String| EngineError::OrchestratorFailed { details };
+        let events = self
+            .event_store
+            .load_events(run_id)
+            .await
+            .map_err(|e| store_failed(e.to_string()))?;
+        for key in run_outputs(&events) {
+            match self.object_store.delete(&key).await {
+                Ok(()) | Err(ObjectStoreError::NotFound { .. }) => {}
+                Err(e) => return Err(store_failed(e.to_string())),
+            }
+        }
+        self.event_store
+            .purge_run(run_id)
+            .await
+            .map_err(|e| store_failed(e.to_string()))
+    }
+
+    /// Mirrors run results from the engine into the run history.
+    ///
+    /// Records each run's output, error, or cancellation on its
+    /// `workflow_runs` row as the run ends, each approval requested or
+    /// resolved in the approvals table, and each delay started or ended in
+    /// the delays table. A child run's end resumes the sub-workflow node
+    /// that started it. Events of runs without a row are ignored. Returns
+    /// when the event subscription ends.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if following the run events fails.
+    pub async fn sync_run_history(self, pool: PgPool) -> Result<(), EngineError> {
+        let repo = WorkflowRunRepository::new(pool.clone());
+        let workflow_repo = WorkflowRepository::new(pool.clone());
+        let approval_repo = ApprovalRepository::new(pool.clone());
+        let delay_repo = DelayRepository::new(pool);
+        self.event_store
+            .follow_run_events(RUN_HISTORY_CONSUMER, |event| async {
+                let unfinished = ended_without_completing(&event);
+                let ended = run_outcome(&event);
+                record_approval(&approval_repo, &repo, &event)
+                    .await
+                    .map_err(|e| EngineError::RunHistoryFailed {
+                        details: e.to_string(),
+                    })
+                    .inspect_err(|e| {
+                        tracing::warn!(error = %e, "Failed to record approval");
+                    })?;
+                record_delay(&delay_repo, &repo, &event)
+                    .await
+                    .map_err(|e| EngineError::RunHistoryFailed {
+                        details: e.to_string(),
+                    })
+                    .inspect_err(|e| {
+                        tracing::warn!(error = %e, "Failed to record delay");
+                    })?;
+                record_run_result(&repo, event)
+                    .await
+                    .map_err(|e| EngineError::RunHistoryFailed {
+                        details: e.to_string(),
+                    })
+                    .inspect_err(|e| {
+                        tracing::warn!(error = %e, "Failed to record run result");
+                    })?;
+                if let Some(run_id) = unfinished {
+                    self.cancel_children(&repo, &workflow_repo, run_id)
+                        .await
+                        .inspect_err(|e| {
+                            tracing::warn!(%run_id, error = %e, "Failed to cancel child runs");
+                        })?;
+                }
+                let Some((run_id, outcome)) = ended else {
+                    return Ok(());
+                };
+                let Some(run) =
+                    repo.find_by_id(run_id)
+                        .await
+                        .map_err(|e| EngineError::RunHistoryFailed {
+                            details: e.to_string(),
+                        })?
+                else {
+                    return Ok(());
+                };
+                self.resume_parent(&repo, &workflow_repo, &run, outcome)
+                    .await
+                    .inspect_err(|e| {
+                        tracing::warn!(%run_id, error = %e, "Failed to resume parent run");
+                    })
+            })
+            .await
+            .map_err(|e| EngineError::ConnectionFailed {
+                details: e.to_string(),
+            })
+    }
+
+    /// Applies the results workers publish to their runs.
+    ///
+    /// Each result is handed to an orchestrator that replays the result's
+    /// run, which records the node's outcome and dispatches the nodes that
+    /// became ready. Results of runs without a row, or that ended, are
+    /// ignored. Returns when the subscription ends.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if following the results fails.
+    pub async fn apply_work_results(self, pool: PgPool) -> Result<(), EngineError> {
+        let run_repo = WorkflowRunRepository::new(pool.clone());
+        let workflow_repo = WorkflowRepository::new(pool);
+        self.event_store
+            .follow_work_results(WORK_RESULTS_CONSUMER, |result| async {
+                let run_id = result.run_id();
+                self.apply_work_result(&run_repo, &workflow_repo, result)
+                    .await
+                    .inspect_err(|e| {
+                        tracing::warn!(%run_id, error = %e, "Failed to apply work item result");
+                    })
+            })
+            .await
+            .map_err(|e| EngineError::ConnectionFailed {
+                details: e.to_string(),
+            })
+    }
+
+    /// Returns a runner that starts sub-workflow runs in this engine.
+    #[must_use]
+    pub fn sub_workflows(&self, pool: PgPool) -> EngineSubWorkflows {
+        EngineSubWorkflows {
+            engine: self.clone(),
+            pool,
+        }
+    }
+}
+
+/// Starts the workflows invoked by sub-workflow nodes as child runs.
+///
+/// A child run is recorded in the run history under the ID derived from
+/// the node's attempt, with a link to the run and node that started it,
+/// pinned to the current version of its workflow, and started in the engine
+/// once its workflow's concurrency policy admits it; a skipped child run
+/// fails the node. A child run that already exists is not started again.
+/// The node then waits until [`WorkflowEngine::sync_run_history`] sees the
+/// child run end and resumes it.
+#[derive(Clone)]
+pub struct EngineSubWorkflows {
+    engine: WorkflowEngine,
+    pool: PgPool,
+}
+
+#[async_trait]
+impl SubWorkflowRunner for EngineSubWorkflows {
+    async fn start(
+        &self,
+        request: SubWorkflowRequest,
+    ) -> Result<WorkflowRunId, NodeExecutionError> {
+        let history_failed = |e: sqlx::Error| NodeExecutionError::ExternalServiceError {
+            service: "run history".to_string(),
+            message: e.to_string(),
+        };
+        let run_repo = WorkflowRunRepository::new(self.pool.clone());
+
+        let call_chain = run_repo
+            .workflow_chain(request.parent_run_id)
+            .await
+            .map_err(history_failed)?;
+        if call_chain.is_empty() {
+            return Err(NodeExecutionError::ExecutionFailed {
+                message: format!(
+                    "parent run {} is not in the run history",
+                    request.parent_run_id
+                ),
+            });
+        }
+        check_call_chain(&call_chain, request.workflow_id)?;
+
+        // A redelivered work item finds the child run it started
+        let child_run_id = request.child_run_id();
+        if run_repo
+            .find_by_id(child_run_id)
+            .await
+            .map_err(history_failed)?
+            .is_some()
+        {
+            return Ok(child_run_id);
+        }
+
+        let workflow = WorkflowRepository::new(self.pool.clone())
+            .find_by_id(request.workflow_id)
+            .await
+            .map_err(history_failed)?
+            .ok_or_else(|| NodeExecutionError::InvalidInput {
+                message: format!("workflow {} does not exist", request.workflow_id),
+            })?;
+
+        // The run is recorded as started before the engine sees it, so a
+        // result mirrored from the engine is never overwritten
+        let mut run = WorkflowRunRecord::new(workflow.id, None, Some(request.input))
+            .with_id(child_run_id)
+            .with_workflow_version(workflow.version)
+            .with_parent(request.parent_run_id, request.parent_node_id.to_string());
+        if request.dry_run.is_some() {
+            run = run.as_dry_run();
+        }
+        let started = match request.dry_run {
+            Some(dry_run) => {
+                run.start();
+                run_repo.create(&run).await.map_err(history_failed)?;
+                let started = self.engine.start_dry_run(&workflow, &run, dry_run).await;
+                if let Err(e) = &started {
+                    run.fail(e.to_string());
+                    run_repo.update(&run).await.map_err(history_failed)?;
+                }
+                started
+            }
+            // The child run waits for a slot like any other run of its
+            // workflow
+            None => match self
+                .engine
+                .launch_run(self.pool.clone(), &workflow, &mut run)
+                .await
+            {
+                Ok(Some(Admission::Skip { reason })) => {
+                    return Err(NodeExecutionError::ExecutionFailed {
+                        message: format!("sub-workflow run {} was skipped: {reason}", run.id),
+                    });
+                }
+                started => started.map(|_| ()),
+            },
+        };
+        if let Err(e) = started {
+            return Err(NodeExecutionError::ExecutionFailed {
+                message: format!("failed to start sub-workflow run {}: {e}", run.id),
+            });
+        }
+        Ok(child_run_id)
+    }
+}
+
+/// Returns the run an event ends and the outcome it hands to the run's
+/// parent, if the run is a child run.
+fn run_outcome(
+    event: &ExecutionEvent,
+) -> Option<(WorkflowRunId, Result<JsonValue, NodeExecutionError>)> {
+    match event {
+        ExecutionEvent::RunCompleted { run_id, output, .. } => {
+            Some((*run_id, Ok(output.clone().unwrap_or(JsonValue::Null))))
+        }
+        ExecutionEvent::RunFailed { run_id, error, .. } => Some((
+            *run_id,
+            Err(NodeExecutionError::ExecutionFailed {
+                message: format!("sub-workflow run {run_id} failed: {error}"),
+            }),
+        )),
+        ExecutionEvent::RunCancelled { run_id, .. } => {
+            Some((*run_id, Err(NodeExecutionError::Cancelled)))
+        }
+        _ => None,
+    }
+}
+
+/// Returns the run an event ends without completing it, if any.
+fn ended_without_completing(event: &ExecutionEvent) -> Option<WorkflowRunId> {
+    match event {
+        ExecutionEvent::RunFailed { run_id, .. } | ExecutionEvent::RunCancelled { run_id, .. } => {
+            Some(*run_id)
+        }
+        _ => None,
+    }
+}
+
+/// Records the outcome of a run event on the run's record.
+///
+/// Runs that already ended in the run history (e.g., cancelled by an admin)
+/// keep their outcome.
+async fn record_run_result(
+    repo: &WorkflowRunRepository,
+    event: ExecutionEvent,
+) -> Result<(), sqlx::Error> {
+    let (run_id, timestamp) = match &event {
+        ExecutionEvent::RunCompleted {
+            run_id, timestamp, ..
+        }
+        | ExecutionEvent::RunFailed {
+            run_id, timestamp, ..
+        }
+        | ExecutionEvent::RunCancelled {
+            run_id, timestamp, ..
+        } => (*run_id, *timestamp),
+        _ => return Ok(()),
+    };
+    let Some(mut run) = repo.find_by_id(run_id).await? else {
+        return Ok(());
+    };
+    if run.state.is_terminal() {
+        return Ok(());
+    }
+
+    match event {
+        ExecutionEvent::RunCompleted { output, .. } => run.complete(output),
+        ExecutionEvent::RunFailed { error, .. } => run.fail(error),
+        _ => run.cancel(),
+    }
+    finish_at(&mut run, timestamp);
+    repo.update(&run).await
+}
+
+/// Records an approval requested or resolved by the engine.
+///
+/// Requests of runs without a row are ignored; a request seen again is
+/// recorded once.
+async fn record_approval(
+    approval_repo: &ApprovalRepository,
+    run_repo: &WorkflowRunRepository,
+    event: &ExecutionEvent,
+) -> Result<(), sqlx::Error> {
+    match event {
+        ExecutionEvent::ApprovalRequested {
+            run_id,
+            node_id,
+            item_index,
+            payload,
+            expires_at,
+            timestamp,
+        } => {
+            let Some(run) = run_repo.find_by_id(*run_id).await? else {
+                return Ok(());
+            };
+            let mut approval = ApprovalRecord::new(
+                *run_id,
+                run.workflow_id,
+                node_id.to_string(),
+                item_index.and_then(|index| i32::try_from(index).ok()),
+                payload.clone(),
+            );
+            approval.requested_at = *timestamp;
+            approval.expires_at = *expires_at;
+            approval_repo.create_if_absent(&approval).await?;
+        }
+        ExecutionEvent::ApprovalResolved {
+            run_id,
+            node_id,
+            item_index,
+            decision,
+            decided_by,
+            timestamp,
+        } => {
+            let id = ApprovalRecord::id_for(
+                *run_id,
+                &node_id.to_string(),
+                item_index.and_then(|index| i32::try_from(index).ok()),
+            );
+            let status = match decision {
+                ApprovalDecision::Approved => ApprovalStatus::Approved,
+                ApprovalDecision::Rejected => ApprovalStatus::Rejected,
+            };
+            approval_repo
+                .decide(&id, status, decided_by.as_deref(), *timestamp)
+                .await?;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Records a delay started or ended by the engine.
+///
+/// Delays of runs without a row are ignored; a delay seen again is recorded
+/// once.
+async fn record_delay(
+    delay_repo: &DelayRepository,
+    run_repo: &WorkflowRunRepository,
+    event: &ExecutionEvent,
+) -> Result<(), sqlx::Error> {
+    match event {
+        ExecutionEvent::DelayStarted {
+            run_id,
+            node_id,
+            item_index,
+            until,
+            timestamp,
+        } => {
+            let Some(run) = run_repo.find_by_id(*run_id).await? else {
+                return Ok(());
+            };
+            let delay = DelayRecord::new(
+                *run_id,
+                run.workflow_id,
+                node_id.to_string(),
+                item_index.and_then(|index| i32::try_from(index).ok()),
+                *timestamp,
+                *until,
+            );
+            delay_repo.create_if_absent(&delay).await?;
+        }
+        ExecutionEvent::DelayEnded {
+            run_id,
+            node_id,
+            item_index,
+            timestamp,
+        } => {
+            let id = DelayRecord::id_for(
+                *run_id,
+                &node_id.to_string(),
+                item_index.and_then(|index| i32::try_from(index).ok()),
+            );
+            delay_repo.mark_ended(&id, *timestamp).await?;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Sets when a run ended to when the engine recorded it.
+fn finish_at(run: &mut WorkflowRunRecord, timestamp: DateTime<Utc>) {
+    run.finished_at = Some(timestamp);
+    run.duration_ms = run
+        .started_at
+        .map(|start| (timestamp - start).num_milliseconds());
+}
+
+/// What recovery does with a run the run history records as in progress.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Recovery {
+    /// Replay the run's events and continue it.
+    Resume,
+    /// Start the run: it was recorded as started, but the server stopped
+    /// before its start reached the engine.
+    Start,
+    /// Fail the run: it is a dry run whose start was interrupted, and its
+    /// fixtures only ever reached the engine with its start.
+    Fail,
+    /// Leave the run alone: it is queued and waits for a free slot.
+    Wait,
+}
+
+/// Decides what recovery does with an in-progress run, from its record in
+/// the run history and whether the engine has events for it.
+///
+/// An interrupted dry run is never started again, since it would start
+/// without its fixtures and perform real integration writes.
+fn recovery_for(run: &WorkflowRunRecord, has_events: bool) -> Recovery {
+    match (run.state, has_events) {
+        (_, true) => Recovery::Resume,
+        (RunState::Running, false) if run.dry_run => Recovery::Fail,
+        (RunState::Running, false) => Recovery::Start,
+        _ => Recovery::Wait,
+    }
+}
+
+/// Builds an executable workflow from a stored workflow record.
+///
+/// # Errors
+///
+/// Returns an error if the stored graph is not in the engine's graph format
+/// or fails validation.
+pub fn executable_workflow(record: &WorkflowRecord) -> Result<Workflow, EngineError> {
+    let mut graph: WorkflowGraph =
+        serde_json::from_value(record.graph_data.clone()).map_err(|e| {
+            EngineError::GraphNotExecutable {
+                details: e.to_string(),
+            }
+        })?;
+    graph.rebuild_index_map();
+
+    let mut workflow = Workflow::with_id(record.id, record.name.clone());
+    workflow.metadata.description = record.description.clone();
+    workflow.metadata.enabled = record.enabled;
+    workflow.graph = graph;
+    workflow.version = u32::try_from(record.version).ok();
+    workflow.concurrency = record.concurrency;
+    workflow.retention = record.retention;
+    workflow.max_duration_ms = record.max_duration_ms;
+    workflow
+        .validate()
+        .map_err(|e| EngineError::GraphNotExecutable {
+            details: e.to_string(),
+        })?;
+
+    Ok(workflow)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use silver_telegram_core::WorkflowId;
+    use silver_telegram_workflow::node::TriggerNodeConfig;
+    use silver_telegram_workflow::{
+        Envelope, EventStoreError, Node, NodeConfig, ObjectStoreError, WorkItem,
+    };
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    /// In-memory event store for testing.
+    #[derive(Default)]
+    struct InMemoryEventStore {
+        events: Mutex<Vec<ExecutionEvent>>,
+        work_items: Mutex<Vec<WorkItem>>,
+    }
+
+    #[async_trait]
+    impl EventStore for InMemoryEventStore {
+        async fn publish(&self, event: Envelope<ExecutionEvent>) -> Result<(), EventStoreError> {
+            self.events.lock().unwrap().push(event.payload);
+            Ok(())
+        }
+
+        async fn load_events(
+            &self,
+            run_id: WorkflowRunId,
+        ) -> Result<Vec<ExecutionEvent>, EventStoreError> {
+            Ok(self
+                .events
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|event| event.run_id() == run_id)
+                .cloned()
+                .collect())
+        }
+
+        async fn publish_work_item(&self, item: Envelope<WorkItem>) -> Result<(), EventStoreError> {
+            self.work_items.lock().unwrap().push(item.payload);
+            Ok(())
+        }
+    }
+
+    /// In-memory object store for testing.
+    #[derive(Default)]
+    struct InMemoryObjectStore {
+        data: Mutex<HashMap<String, Vec<u8>>>,
+    }
+
+    #[async_trait]
+    impl ObjectStore for InMemoryObjectStore {
+        async fn put(&self, data: &[u8]) -> Result<String, ObjectStoreError> {
+            let mut stored = self.data.lock().unwrap();
+            let key = format!("obj_{}", stored.len() + 1);
+            stored.insert(key.clone(), data.to_vec());
+            Ok(key)
+        }
+
+        async fn get(&self, key: &str) -> Result<Vec<u8>, ObjectStoreError> {
+            self.data
+                .lock()
+                .unwrap()
+                .get(key)
+                .cloned()
+                .ok_or_else(|| ObjectStoreError::NotFound {
+                    key: key.to_string(),
+                })
+        }
+
+        async fn delete(&self, key: &str) -> Result<(), ObjectStoreError> {
+            self.data.lock().unwrap().remove(key);
+            Ok(())
+        }
+    }
+
+    type TestEngine = WorkflowEngine<InMemoryEventStore, InMemoryObjectStore>;
+
+    fn test_engine() -> TestEngine {
+        WorkflowEngine::new(
+            InMemoryEventStore::default(),
+            InMemoryObjectStore::default(),
+        )
+    }
+
+    /// A workflow of a single manual trigger, which never reports back
+    /// since no worker runs in the tests.
+    fn trigger_only_workflow(max_duration_ms: Option<u64>) -> (WorkflowRecord, NodeId) {
+        let mut graph = WorkflowGraph::new();
+        let trigger_id = graph.add_node(Node::new(
+            "Trigger",
+            NodeConfig::Trigger(TriggerNodeConfig::Manual),
+        ));
+        let mut workflow = WorkflowRecord::new("Test Workflow".to_string());
+        workflow.graph_data = serde_json::to_value(&graph).unwrap();
+        workflow.max_duration_ms = max_duration_ms;
+        (workflow, trigger_id)
+    }
+
+    async fn start_test_run(engine: &TestEngine, workflow: &WorkflowRecord) -> WorkflowRunId {
+        let mut run = WorkflowRunRecord::new(workflow.id, None, None);
+        run.start();
+        engine.start_run(workflow, &run).await.unwrap();
+        run.id
+    }
+
+    fn run_in(state: RunState) -> WorkflowRunRecord {
+        let mut run = WorkflowRunRecord::new(WorkflowId::new(), None, None);
+        if state == RunState::Running {
+            run.start();
+        }
+        run
+    }
+
+    #[tokio::test]
+    async fn run_without_results_is_failed_by_the_deadline_timer() {
+        let engine = test_engine();
+        let (workflow, trigger_id) = trigger_only_workflow(Some(0));
+        let run_id = start_test_run(&engine, &workflow).await;
+
+        assert!(
+            engine
+                .enforce_run_deadline(&workflow, run_id)
+                .await
+                .unwrap()
+        );
+
+        let events = engine.event_store.load_events(run_id).await.unwrap();
+        assert!(events.iter().any(|event| matches!(
+            event,
+            ExecutionEvent::NodeFailed { node_id, .. } if *node_id == trigger_id
+        )));
+        assert!(matches!(
+            events.last(),
+            Some(ExecutionEvent::RunFailed { .. })
+        ));
+        // The run already ended, so the next pass leaves it alone
+        assert!(
+            !engine
+                .enforce_run_deadline(&workflow, run_id)
+                .await
+                .unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn deadline_timer_leaves_runs_within_their_deadline_alone() {
+        let engine = test_engine();
+        let (workflow, _) = trigger_only_workflow(Some(60_000));
+        let run_id = start_test_run(&engine, &workflow).await;
+
+        assert!(
+            !engine
+                .enforce_run_deadline(&workflow, run_id)
+                .await
+                .unwrap()
+        );
+        let events = engine.event_store.load_events(run_id).await.unwrap();
+        assert!(!events.iter().any(|event| matches!(
+            event,
+            ExecutionEvent::NodeFailed { .. } | ExecutionEvent::RunFailed { .. }
+        )));
+    }
+
+    #[test]
+    fn run_ends_are_handed_to_the_parent() {
+        let run_id = WorkflowRunId::new();
+        let timestamp = Utc::now();
+
+        let completed = ExecutionEvent::RunCompleted {
+            run_id,
+            output: Some(serde_json::json!({"summary": "done"})),
+            timestamp,
+        };
+        assert_eq!(
+            run_outcome(&completed),
+            Some((run_id, Ok(serde_json::json!({"summary": "done"}))))
+        );
+
+        let failed = ExecutionEvent::RunFailed {
+            run_id,
+            error: "node failed".to_string(),
+            timestamp,
+        };
+        assert_eq!(
+            run_outcome(&failed),
+            Some((
+                run_id,
+                Err(NodeExecutionError::ExecutionFailed {
+                    message: format!("sub-workflow run {run_id} failed: node failed"),
+                })
+            ))
+        );
+
+        let cancelled = ExecutionEvent::RunCancelled {
+            run_id,
+            reason: "parent run ended".to_string(),
+            timestamp,
+        };
+        assert_eq!(
+            run_outcome(&cancelled),
+            Some((run_id, Err(NodeExecutionError::Cancelled)))
+        );
+    }
+
+    #[test]
+    fn run_started_without_events_is_started_on_recovery() {
+        // The server stopped between recording the started run and
+        // publishing its first event
+        let run = run_in(RunState::Running);
+        assert_eq!(recovery_for(&run, false), Recovery::Start);
+        assert_eq!(recovery_for(&run, true), Recovery::Resume);
+    }
+
+    #[test]
+    fn queued_run_without_events_waits_for_a_slot() {
+        let run = run_in(RunState::Queued);
+        assert_eq!(recovery_for(&run, false), Recovery::Wait);
+        assert_eq!(recovery_for(&run, true), Recovery::Resume);
+    }
+
+    #[test]
+    fn dry_run_without_events_fails_instead_of_running_for_real() {
+        // The fixtures were never stored, so starting the run again would
+        // perform real integration writes
+        let run = run_in(RunState::Running).as_dry_run();
+        assert_eq!(recovery_for(&run, false), Recovery::Fail);
+        assert_eq!(recovery_for(&run, true), Recovery::Resume);
+    }
+}
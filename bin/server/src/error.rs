@@ -73,6 +73,10 @@ pub enum WorkflowError {
     AuthorizationError { details: String },
     /// Failed to parse workflow graph.
     InvalidGraph { details: String },
+    /// Failed to export or import a workflow document.
+    InvalidDocument { details: String },
+    /// Invalid workflow settings.
+    InvalidSettings { details: String },
 }
 
 impl fmt::Display for WorkflowError {
@@ -105,6 +109,12 @@ impl fmt::Display for WorkflowError {
             Self::InvalidGraph { details } => {
                 write!(f, "invalid workflow graph: {}", details)
             }
+            Self::InvalidDocument { details } => {
+                write!(f, "invalid workflow document: {}", details)
+            }
+            Self::InvalidSettings { details } => {
+                write!(f, "invalid workflow settings: {}", details)
+            }
         }
     }
 }
@@ -122,6 +132,12 @@ impl WorkflowError {
             WorkflowError::DatabaseError { .. } => ServerFnError::new("Database error"),
             WorkflowError::AuthorizationError { .. } => ServerFnError::new("Authorization error"),
             WorkflowError::InvalidGraph { .. } => ServerFnError::new("Invalid workflow graph"),
+            WorkflowError::InvalidDocument { details } => {
+                ServerFnError::new(format!("Invalid workflow document: {}", details))
+            }
+            WorkflowError::InvalidSettings { details } => {
+                ServerFnError::new(format!("Invalid workflow settings: {}", details))
+            }
         }
     }
 }
@@ -218,6 +234,40 @@ impl WorkflowRunError {
     }
 }
 
+/// Approval-related errors.
+#[derive(Debug)]
+pub enum ApprovalError {
+    /// Approval was not found.
+    NotFound { id: String },
+    /// Approval was already decided.
+    NotPending { id: String },
+    /// Database error while accessing approval.
+    DatabaseError { details: String },
+}
+
+impl fmt::Display for ApprovalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound { id } => write!(f, "approval '{}' not found", id),
+            Self::NotPending { id } => write!(f, "approval '{}' is not pending", id),
+            Self::DatabaseError { details } => {
+                write!(f, "approval database error: {}", details)
+            }
+        }
+    }
+}
+
+impl ApprovalError {
+    /// Convert to a user-safe ServerFnError.
+    pub fn into_server_error(self) -> ServerFnError {
+        match &self {
+            ApprovalError::NotFound { .. } => ServerFnError::new("Approval not found"),
+            ApprovalError::NotPending { .. } => ServerFnError::new("Approval is no longer pending"),
+            ApprovalError::DatabaseError { .. } => ServerFnError::new("Database error"),
+        }
+    }
+}
+
 /// User-related errors.
 #[derive(Debug)]
 pub enum UserError {
@@ -321,3 +371,85 @@ impl ModelDiscoveryError {
         }
     }
 }
+
+/// Workflow engine errors.
+#[derive(Debug)]
+pub enum EngineError {
+    /// Failed to connect to NATS.
+    ConnectionFailed { details: String },
+    /// The stored graph cannot be executed by the engine.
+    GraphNotExecutable { details: String },
+    /// The orchestrator failed to queue or start the run.
+    OrchestratorFailed { details: String },
+    /// The run history could not be read.
+    RunHistoryFailed { details: String },
+    /// The approval is no longer waiting for a decision.
+    ApprovalNotPending { details: String },
+    /// The run cannot be retried from the node.
+    RetryNotAllowed { details: String },
+    /// The fixtures for a dry run are malformed.
+    InvalidFixtures { details: String },
+    /// The run's outputs expired under the retention policy.
+    OutputsExpired { details: String },
+}
+
+impl fmt::Display for EngineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ConnectionFailed { details } => {
+                write!(f, "workflow engine connection failed: {}", details)
+            }
+            Self::GraphNotExecutable { details } => {
+                write!(f, "workflow graph is not executable: {}", details)
+            }
+            Self::OrchestratorFailed { details } => {
+                write!(f, "orchestrator failed: {}", details)
+            }
+            Self::RunHistoryFailed { details } => {
+                write!(f, "failed to read run history: {}", details)
+            }
+            Self::ApprovalNotPending { details } => {
+                write!(f, "approval is not pending: {}", details)
+            }
+            Self::RetryNotAllowed { details } => {
+                write!(f, "run cannot be retried: {}", details)
+            }
+            Self::InvalidFixtures { details } => {
+                write!(f, "invalid dry run fixtures: {}", details)
+            }
+            Self::OutputsExpired { details } => {
+                write!(f, "run outputs expired: {}", details)
+            }
+        }
+    }
+}
+
+impl EngineError {
+    /// Convert to a user-safe ServerFnError.
+    pub fn into_server_error(self) -> ServerFnError {
+        match &self {
+            EngineError::ConnectionFailed { .. } => {
+                ServerFnError::new("Workflow engine unavailable")
+            }
+            EngineError::GraphNotExecutable { .. } => {
+                ServerFnError::new("Workflow graph is not executable")
+            }
+            EngineError::OrchestratorFailed { .. } => {
+                ServerFnError::new("Failed to start workflow run")
+            }
+            EngineError::RunHistoryFailed { .. } => {
+                ServerFnError::new("Failed to read run history")
+            }
+            EngineError::ApprovalNotPending { .. } => {
+                ServerFnError::new("Approval is no longer pending")
+            }
+            EngineError::RetryNotAllowed { .. } => {
+                ServerFnError::new("Run cannot be retried from this node")
+            }
+            EngineError::InvalidFixtures { .. } => ServerFnError::new("Invalid dry run fixtures"),
+            EngineError::OutputsExpired { .. } => {
+                ServerFnError::new("The run's outputs have expired")
+            }
+        }
+    }
+}
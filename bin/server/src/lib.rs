@@ -19,12 +19,21 @@ pub mod config;
 #[cfg(feature = "ssr")]
 pub mod db;
 
+#[cfg(feature = "ssr")]
+pub mod engine;
+
 #[cfg(feature = "ssr")]
 pub mod error;
 
+#[cfg(feature = "ssr")]
+pub mod scheduler;
+
 #[cfg(feature = "ssr")]
 pub mod server_helpers;
 
+#[cfg(feature = "ssr")]
+pub mod worker;
+
 #[cfg(feature = "hydrate")]
 #[wasm_bindgen::prelude::wasm_bindgen]
 pub fn hydrate() {
@@ -12,6 +12,8 @@ async fn main() {
             gmail_callback, gmail_start,
         },
         config::ServerConfig,
+        engine::WorkflowEngine,
+        scheduler, worker,
     };
     use sqlx::postgres::PgPoolOptions;
     use std::sync::Arc;
@@ -26,6 +28,12 @@ async fn main() {
         .with(tracing_subscriber::fmt::layer())
         .init();
 
+    // `silver-telegram-server worker` only executes work items
+    if std::env::args().nth(1).as_deref() == Some("worker") {
+        run_worker_process().await;
+        return;
+    }
+
     // Load configuration from environment
     let config = ServerConfig::from_env().expect("failed to load configuration");
     tracing::info!("Loaded configuration");
@@ -80,6 +88,103 @@ async fn main() {
         }
     });
 
+    // Connect to the workflow engine if configured
+    let workflow_engine = match &config.nats.url {
+        Some(url) => {
+            tracing::info!("Connecting to workflow engine...");
+            match WorkflowEngine::connect(url).await {
+                Ok(engine) => Some(engine),
+                Err(e) => {
+                    tracing::warn!("Failed to connect to workflow engine: {}", e);
+                    None
+                }
+            }
+        }
+        None => {
+            tracing::info!("Workflow engine not configured (set NATS__URL)");
+            None
+        }
+    };
+
+    // Pick up runs left in progress by a previous server process, before
+    // anything else starts runs
+    if let Some(engine) = &workflow_engine {
+        match engine.recover_runs(db_pool.clone()).await {
+            Ok(count) if count > 0 => {
+                tracing::info!(recovered_runs = count, "Recovered in-progress runs");
+            }
+            Ok(_) => {}
+            Err(e) => {
+                tracing::warn!(error = %e, "Failed to recover in-progress runs");
+            }
+        }
+    }
+
+    // Mirror run results from the engine into the run history
+    if let Some(engine) = workflow_engine.clone() {
+        let history_pool = db_pool.clone();
+        tokio::spawn(async move {
+            if let Err(e) = engine.sync_run_history(history_pool).await {
+                tracing::warn!("Run history sync stopped: {}", e);
+            }
+        });
+    }
+
+    // Apply the results workers publish to their runs
+    if let Some(engine) = workflow_engine.clone() {
+        let results_pool = db_pool.clone();
+        tokio::spawn(async move {
+            if let Err(e) = engine.apply_work_results(results_pool).await {
+                tracing::warn!("Work item results stopped: {}", e);
+            }
+        });
+    }
+
+    // Resolve approvals that expired without a decision
+    if let Some(engine) = workflow_engine.clone() {
+        tokio::spawn(engine.expire_approvals_periodically(db_pool.clone()));
+    }
+
+    // End delays once their time is reached
+    if let Some(engine) = workflow_engine.clone() {
+        tokio::spawn(engine.end_delays_periodically(db_pool.clone()));
+    }
+
+    // Fail runs whose results never arrived by their deadline
+    if let Some(engine) = workflow_engine.clone() {
+        tokio::spawn(engine.enforce_deadlines_periodically(db_pool.clone()));
+    }
+
+    // Start queued runs as their workflows' concurrency limits allow
+    if let Some(engine) = workflow_engine.clone() {
+        tokio::spawn(engine.start_queued_runs_periodically(db_pool.clone()));
+    }
+
+    // Delete run outputs that expired under the retention policy
+    if let Some(engine) = workflow_engine.clone() {
+        tokio::spawn(engine.expire_outputs_periodically(db_pool.clone(), config.retention.clone()));
+    }
+
+    // Execute work items in this process
+    if config.worker.enabled
+        && let Some(engine) = workflow_engine.clone()
+    {
+        tokio::spawn(worker::run_worker(
+            engine,
+            db_pool.clone(),
+            config.worker.clone(),
+        ));
+    }
+
+    // Spawn the scheduler daemon
+    if config.scheduler.enabled {
+        tokio::spawn(scheduler::run_scheduler(
+            db_pool.clone(),
+            workflow_engine.clone(),
+            config.scheduler.clone(),
+        ));
+    }
+
     // Initialize OIDC client
     tracing::info!("Discovering OIDC provider...");
     let oidc_client = OidcClient::discover(config.oidc)
@@ -179,10 +284,12 @@ async fn main() {
     // Add layers and static file serving
     let app = app
         .nest_service("/pkg", ServeDir::new("target/site/pkg"))
-        // Provide database pool, OIDC config, and authz client as request extensions
+        // Provide database pool, OIDC config, authz client, and workflow engine
+        // as request extensions
         .layer(axum::Extension(db_pool_for_context))
         .layer(axum::Extension(oidc_config_for_context))
-        .layer(axum::Extension(authz_client_for_context));
+        .layer(axum::Extension(authz_client_for_context))
+        .layer(axum::Extension(workflow_engine));
 
     let listener = tokio::net::TcpListener::bind(&addr)
         .await
@@ -195,6 +302,36 @@ async fn main() {
         .expect("server error");
 }
 
+/// Runs a worker without the web server, e.g. on a machine next to a local
+/// model server that advertises the capabilities only it can serve.
+#[cfg(feature = "ssr")]
+async fn run_worker_process() {
+    use silver_telegram_server::{config::WorkerProcessConfig, engine::WorkflowEngine, worker};
+    use sqlx::postgres::PgPoolOptions;
+
+    let config = WorkerProcessConfig::from_env().expect("failed to load configuration");
+    tracing::info!(
+        capabilities = ?config.worker.capabilities,
+        "Loaded worker configuration"
+    );
+
+    let db_pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&config.database_url)
+        .await
+        .expect("failed to connect to database");
+
+    let url = config
+        .nats
+        .url
+        .expect("the worker needs the workflow engine (set NATS__URL)");
+    let engine = WorkflowEngine::connect(&url)
+        .await
+        .expect("failed to connect to workflow engine");
+
+    worker::run_worker(engine, db_pool, config.worker).await;
+}
+
 /// Combined state for the application.
 #[cfg(feature = "ssr")]
 #[derive(Clone)]
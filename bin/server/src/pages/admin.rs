@@ -57,8 +57,10 @@ pub async fn list_all_workflows() -> Result<Vec<WorkflowSummary>, ServerFnError>
 #[server]
 pub async fn trigger_workflow(workflow_id: String) -> Result<(), ServerFnError> {
     use crate::db::{WorkflowRepository, WorkflowRunRecord, WorkflowRunRepository};
+    use crate::engine::WorkflowEngine;
     use crate::error::{WorkflowError, WorkflowRunError};
     use crate::server_helpers::get_admin_session;
+    use axum::Extension;
     use silver_telegram_core::WorkflowId;
     use std::str::FromStr;
 
@@ -118,27 +120,48 @@ pub async fn trigger_workflow(workflow_id: String) -> Result<(), ServerFnError>
         .into_server_error());
     }
 
-    // Create a new run in queued state (orchestrator will pick it up)
-    let run = WorkflowRunRecord::new(
+    // The run is admitted under the workflow's concurrency policy; without
+    // an engine it stays queued
+    let mut run = WorkflowRunRecord::new(
         wf_id,
         None,
         Some(serde_json::json!({"triggered_by": "admin"})),
-    );
-    let run_repo = WorkflowRunRepository::new(db_pool);
-    run_repo.create(&run).await.map_err(|e| {
-        tracing::error!(
-            workflow_id = %workflow_id,
-            error = %e,
-            "Failed to create workflow run"
-        );
-        WorkflowRunError::DatabaseError {
-            details: e.to_string(),
+    )
+    .with_workflow_version(workflow.version);
+    let Extension(engine): Extension<Option<WorkflowEngine>> = leptos_axum::extract().await?;
+    match engine {
+        Some(engine) => {
+            engine
+                .launch_run(db_pool, &workflow, &mut run)
+                .await
+                .map_err(|e| {
+                    tracing::error!(
+                        workflow_id = %workflow_id,
+                        error = %e,
+                        "Failed to launch workflow run"
+                    );
+                    e.into_server_error()
+                })?;
         }
-        .into_server_error()
-    })?;
+        None => {
+            let run_repo = WorkflowRunRepository::new(db_pool);
+            run_repo.create(&run).await.map_err(|e| {
+                tracing::error!(
+                    workflow_id = %workflow_id,
+                    error = %e,
+                    "Failed to create workflow run"
+                );
+                WorkflowRunError::DatabaseError {
+                    details: e.to_string(),
+                }
+                .into_server_error()
+            })?;
+        }
+    }
 
     tracing::info!(
         workflow_id = %workflow_id,
+        state = ?run.state,
         "Admin triggered workflow successfully"
     );
 
@@ -149,8 +172,10 @@ pub async fn trigger_workflow(workflow_id: String) -> Result<(), ServerFnError>
 #[server]
 pub async fn cancel_workflow(workflow_id: String) -> Result<(), ServerFnError> {
     use crate::db::WorkflowRunRepository;
+    use crate::engine::WorkflowEngine;
     use crate::error::{WorkflowError, WorkflowRunError};
     use crate::server_helpers::get_admin_session;
+    use axum::Extension;
     use silver_telegram_core::WorkflowId;
     use std::str::FromStr;
 
@@ -172,9 +197,16 @@ pub async fn cancel_workflow(workflow_id: String) -> Result<(), ServerFnError> {
         .into_server_error()
     })?;
 
-    // Cancel all active runs for this workflow
     let db_pool: sqlx::PgPool = leptos::prelude::expect_context();
-    let run_repo = WorkflowRunRepository::new(db_pool);
+    let run_repo = WorkflowRunRepository::new(db_pool.clone());
+
+    // Stop the active runs in the engine, which also aborts their workers
+    let Extension(engine): Extension<Option<WorkflowEngine>> = leptos_axum::extract().await?;
+    if let Some(engine) = engine {
+        cancel_engine_runs(&engine, &db_pool, &run_repo, wf_id).await;
+    }
+
+    // Cancel all active runs for this workflow
     run_repo.cancel_for_workflow(wf_id).await.map_err(|e| {
         tracing::error!(
             workflow_id = %workflow_id,
@@ -195,6 +227,78 @@ pub async fn cancel_workflow(workflow_id: String) -> Result<(), ServerFnError> {
     Ok(())
 }
 
+/// Cancels a workflow's active runs in the workflow engine.
+///
+/// Failures are logged rather than returned, so the run records are
+/// cancelled regardless.
+#[cfg(feature = "ssr")]
+async fn cancel_engine_runs(
+    engine: &crate::engine::WorkflowEngine,
+    db_pool: &sqlx::PgPool,
+    run_repo: &crate::db::WorkflowRunRepository,
+    workflow_id: silver_telegram_core::WorkflowId,
+) {
+    use crate::db::WorkflowRepository;
+
+    let runs = match run_repo.list_active_for_workflow(workflow_id).await {
+        Ok(runs) => runs,
+        Err(e) => {
+            tracing::error!(
+                workflow_id = %workflow_id,
+                error = %e,
+                "Failed to list active workflow runs"
+            );
+            return;
+        }
+    };
+
+    let workflow_repo = WorkflowRepository::new(db_pool.clone());
+    for run in runs {
+        // Each run is replayed against the version it executes
+        let workflow = match workflow_repo.find_for_run(&run).await {
+            Ok(Some(workflow)) => workflow,
+            Ok(None) => continue,
+            Err(e) => {
+                tracing::error!(
+                    run_id = %run.id,
+                    workflow_id = %workflow_id,
+                    error = %e,
+                    "Failed to load workflow for cancellation"
+                );
+                continue;
+            }
+        };
+        if let Err(e) = engine
+            .cancel_run(&workflow, run.id, "cancelled by an administrator")
+            .await
+        {
+            tracing::warn!(
+                run_id = %run.id,
+                workflow_id = %workflow_id,
+                error = %e,
+                "Failed to cancel run in the workflow engine"
+            );
+        }
+        // A queued child run may never have reached the engine, so its
+        // parent learns about the cancellation here
+        if let Err(e) = engine
+            .end_child_run(
+                db_pool.clone(),
+                &run,
+                Err(silver_telegram_workflow::NodeExecutionError::Cancelled),
+            )
+            .await
+        {
+            tracing::warn!(
+                run_id = %run.id,
+                workflow_id = %workflow_id,
+                error = %e,
+                "Failed to resume the parent of a cancelled run"
+            );
+        }
+    }
+}
+
 /// Admin page (requires admin access).
 #[component]
 pub fn AdminPage() -> impl IntoView {
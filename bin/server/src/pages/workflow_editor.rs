@@ -1,24 +1,40 @@
 //! Workflow editor page module.
 //!
 //! Provides the visual workflow editor with tabs for graph editing,
-//! settings, memory, and execution history.
+//! settings, memory, execution history, pending approvals, and versions.
 
+mod approvals;
 mod editor;
+mod export;
 mod graph;
 mod history;
+mod lint;
+mod versions;
 
+pub use approvals::{ApprovalSummary, decide_approval, list_pending_approvals};
+pub use export::{WorkflowExport, export_workflow, export_workflow_diagram};
 pub use graph::{WorkflowEdge, WorkflowGraph, WorkflowNode, update_workflow_graph};
 pub use history::{
-    DecisionTraceSummary, NodeExecutionSummary, RunDetailView, WorkflowRunSummary, get_run_detail,
-    list_workflow_runs,
+    DecisionTraceSummary, DelaySummary, FailedNodeSummary, NodeExecutionSummary, RunDetailView,
+    SubstitutionSummary, WorkflowRunSummary, get_run_detail, list_workflow_runs,
+    retry_run_from_node, start_dry_run,
+};
+pub use lint::{GraphDiagnostic, lint_workflow_graph};
+pub use versions::{
+    GraphDiff, WorkflowVersionSummary, diff_graphs, diff_workflow_versions, list_workflow_versions,
+    rollback_workflow_version,
 };
 
 use crate::pages::integrations::list_integrations;
+use approvals::ApprovalsTab;
 use editor::EditorTabContent;
+use export::{DiagramPanel, ExportPanel};
 use history::HistoryTab;
 use leptos::prelude::*;
 use leptos::task::spawn_local;
 use leptos_router::{hooks::use_params, params::Params};
+use lint::lint_key;
+use versions::VersionsTab;
 
 /// URL params for workflow editor.
 #[derive(Params, PartialEq, Clone, Debug)]
@@ -35,6 +51,16 @@ pub struct WorkflowDetail {
     pub enabled: bool,
     pub graph_data: String,
     pub memory_content: Option<String>,
+    /// Maximum number of runs in progress at once (None for no limit).
+    pub max_concurrent_runs: Option<u32>,
+    /// What happens to a run at the limit: "queue", "skip", or
+    /// "cancel_older".
+    pub overlap_policy: String,
+    /// Days run outputs are kept (None for the global policy).
+    pub retention_max_age_days: Option<u32>,
+    /// Number of recent runs whose outputs are kept (None for the global
+    /// policy).
+    pub retention_max_runs: Option<u32>,
 }
 
 /// Server function to get workflow details for editing.
@@ -131,21 +157,34 @@ pub async fn get_workflow_detail(workflow_id: String) -> Result<WorkflowDetail,
         enabled: workflow.enabled,
         graph_data: workflow.graph_data.to_string(),
         memory_content,
+        max_concurrent_runs: workflow.concurrency.max_concurrent_runs,
+        overlap_policy: overlap_policy_name(workflow.concurrency.overlap).to_string(),
+        retention_max_age_days: workflow.retention.max_age_days,
+        retention_max_runs: workflow.retention.max_runs,
     })
 }
 
 /// Server function to update workflow details.
+///
+/// `max_concurrent_runs` and `overlap_policy` set the workflow's
+/// concurrency policy, and `retention_max_age_days` and
+/// `retention_max_runs` its retention policy (see [`WorkflowDetail`]).
 #[server]
 pub async fn update_workflow_detail(
     workflow_id: String,
     name: String,
     description: Option<String>,
+    max_concurrent_runs: Option<u32>,
+    overlap_policy: String,
+    retention_max_age_days: Option<u32>,
+    retention_max_runs: Option<u32>,
 ) -> Result<(), ServerFnError> {
     use crate::db::WorkflowRepository;
     use crate::error::WorkflowError;
     use crate::server_helpers::{get_authenticated_session, get_authz_client, get_db_pool};
     use silver_telegram_authz::{Permission, Resource, Subject};
     use silver_telegram_core::WorkflowId;
+    use silver_telegram_workflow::{ConcurrencyPolicy, OverlapPolicy, RetentionPolicy};
     use std::str::FromStr;
 
     let auth = get_authenticated_session().await.map_err(|e| {
@@ -210,8 +249,40 @@ pub async fn update_workflow_detail(
             .into_server_error()
         })?;
 
+    let overlap = match overlap_policy.as_str() {
+        "queue" => OverlapPolicy::Queue,
+        "skip" => OverlapPolicy::Skip,
+        "cancel_older" => OverlapPolicy::CancelOlder,
+        other => {
+            return Err(WorkflowError::InvalidSettings {
+                details: format!("unknown overlap policy '{other}'"),
+            }
+            .into_server_error());
+        }
+    };
+    if max_concurrent_runs == Some(0) {
+        return Err(WorkflowError::InvalidSettings {
+            details: "the maximum number of concurrent runs must be at least 1".to_string(),
+        }
+        .into_server_error());
+    }
+    if retention_max_age_days == Some(0) || retention_max_runs == Some(0) {
+        return Err(WorkflowError::InvalidSettings {
+            details: "outputs must be kept for at least 1 day and 1 run".to_string(),
+        }
+        .into_server_error());
+    }
+
     workflow.name = name.clone();
     workflow.description = description.clone();
+    workflow.concurrency = ConcurrencyPolicy {
+        max_concurrent_runs,
+        overlap,
+    };
+    workflow.retention = RetentionPolicy {
+        max_age_days: retention_max_age_days,
+        max_runs: retention_max_runs,
+    };
     workflow.updated_at = chrono::Utc::now();
 
     workflow_repo.update(&workflow).await.map_err(|e| {
@@ -235,6 +306,18 @@ pub async fn update_workflow_detail(
     Ok(())
 }
 
+/// Returns the name of an overlap policy in settings.
+#[cfg(feature = "ssr")]
+fn overlap_policy_name(policy: silver_telegram_workflow::OverlapPolicy) -> &'static str {
+    use silver_telegram_workflow::OverlapPolicy;
+
+    match policy {
+        OverlapPolicy::Queue => "queue",
+        OverlapPolicy::Skip => "skip",
+        OverlapPolicy::CancelOlder => "cancel_older",
+    }
+}
+
 /// Server function to update workflow memory.
 #[server]
 pub async fn update_workflow_memory(
@@ -332,6 +415,10 @@ pub fn WorkflowEditorPage() -> impl IntoView {
     // State for editing
     let (edit_name, set_edit_name) = signal(String::new());
     let (edit_desc, set_edit_desc) = signal(String::new());
+    let (edit_max_runs, set_edit_max_runs) = signal(String::new());
+    let (edit_overlap, set_edit_overlap) = signal("queue".to_string());
+    let (edit_keep_days, set_edit_keep_days) = signal(String::new());
+    let (edit_keep_runs, set_edit_keep_runs) = signal(String::new());
     let (graph, set_graph) = signal(WorkflowGraph::default());
     let (memory_content, set_memory_content) = signal(String::new());
     let (saving, set_saving) = signal(false);
@@ -346,11 +433,46 @@ pub fn WorkflowEditorPage() -> impl IntoView {
         |_| async move { list_integrations().await.ok().unwrap_or_default() },
     );
 
+    // Lint the graph whenever its structure changes
+    let diagnostics = Resource::new(
+        move || (workflow_id.get(), lint_key(&graph.get())),
+        move |(id, _)| async move {
+            match id {
+                Some(id) => {
+                    let graph_json =
+                        serde_json::to_string(&graph.get_untracked()).unwrap_or_default();
+                    lint_workflow_graph(id, graph_json)
+                        .await
+                        .ok()
+                        .unwrap_or_default()
+                }
+                None => vec![],
+            }
+        },
+    );
+    let diagnostics = Signal::derive(move || diagnostics.get().unwrap_or_default());
+
     // Initialize form when workflow loads
     Effect::new(move || {
         if let Some(Some(wf)) = workflow.get() {
             set_edit_name.set(wf.name.clone());
             set_edit_desc.set(wf.description.clone().unwrap_or_default());
+            set_edit_max_runs.set(
+                wf.max_concurrent_runs
+                    .map(|max| max.to_string())
+                    .unwrap_or_default(),
+            );
+            set_edit_overlap.set(wf.overlap_policy.clone());
+            set_edit_keep_days.set(
+                wf.retention_max_age_days
+                    .map(|days| days.to_string())
+                    .unwrap_or_default(),
+            );
+            set_edit_keep_runs.set(
+                wf.retention_max_runs
+                    .map(|runs| runs.to_string())
+                    .unwrap_or_default(),
+            );
             set_memory_content.set(wf.memory_content.clone().unwrap_or_default());
 
             // Parse graph
@@ -371,6 +493,12 @@ pub fn WorkflowEditorPage() -> impl IntoView {
         } else {
             Some(edit_desc.get())
         };
+        // An empty limit means no limit
+        let max_runs = edit_max_runs.get().trim().parse::<u32>().ok();
+        let overlap = edit_overlap.get();
+        // An empty retention limit falls back to the global policy
+        let keep_days = edit_keep_days.get().trim().parse::<u32>().ok();
+        let keep_runs = edit_keep_runs.get().trim().parse::<u32>().ok();
         let g = graph.get();
         let graph_json = serde_json::to_string(&g).unwrap_or_default();
         let mem = memory_content.get();
@@ -378,7 +506,16 @@ pub fn WorkflowEditorPage() -> impl IntoView {
         set_saving.set(true);
         spawn_local(async move {
             // Save details
-            let _ = update_workflow_detail(wf_id.clone(), name, desc).await;
+            let _ = update_workflow_detail(
+                wf_id.clone(),
+                name,
+                desc,
+                max_runs,
+                overlap,
+                keep_days,
+                keep_runs,
+            )
+            .await;
 
             // Save graph
             let _ = update_workflow_graph(wf_id.clone(), graph_json).await;
@@ -418,16 +555,26 @@ pub fn WorkflowEditorPage() -> impl IntoView {
                                             selected_node_id=selected_node_id
                                             set_selected_node_id=set_selected_node_id
                                             available_integrations=available_integrations
+                                            diagnostics=diagnostics
                                         />
                                     })}
 
                                     // Settings Tab
                                     {move || (active_tab.get() == "settings").then(|| view! {
                                         <SettingsTabContent
+                                            workflow_id=workflow_id
                                             edit_name=edit_name
                                             set_edit_name=set_edit_name
                                             edit_desc=edit_desc
                                             set_edit_desc=set_edit_desc
+                                            edit_max_runs=edit_max_runs
+                                            set_edit_max_runs=set_edit_max_runs
+                                            edit_overlap=edit_overlap
+                                            set_edit_overlap=set_edit_overlap
+                                            edit_keep_days=edit_keep_days
+                                            set_edit_keep_days=set_edit_keep_days
+                                            edit_keep_runs=edit_keep_runs
+                                            set_edit_keep_runs=set_edit_keep_runs
                                         />
                                     })}
 
@@ -443,6 +590,19 @@ pub fn WorkflowEditorPage() -> impl IntoView {
                                     {move || (active_tab.get() == "history").then(|| view! {
                                         <HistoryTab workflow_id=workflow_id />
                                     })}
+
+                                    // Approvals Tab
+                                    {move || (active_tab.get() == "approvals").then(|| view! {
+                                        <ApprovalsTab workflow_id=workflow_id />
+                                    })}
+
+                                    // Versions Tab
+                                    {move || (active_tab.get() == "versions").then(|| view! {
+                                        <VersionsTab
+                                            workflow_id=workflow_id
+                                            on_rollback=Callback::new(move |()| workflow.refetch())
+                                        />
+                                    })}
                                 </div>
                             }.into_any()
                         },
@@ -507,6 +667,14 @@ fn EditorTabs(
                 class=move || if active_tab.get() == "history" { "tab active" } else { "tab" }
                 on:click=move |_| set_active_tab.set("history".to_string())
             >"History"</button>
+            <button
+                class=move || if active_tab.get() == "approvals" { "tab active" } else { "tab" }
+                on:click=move |_| set_active_tab.set("approvals".to_string())
+            >"Approvals"</button>
+            <button
+                class=move || if active_tab.get() == "versions" { "tab active" } else { "tab" }
+                on:click=move |_| set_active_tab.set("versions".to_string())
+            >"Versions"</button>
         </div>
     }
 }
@@ -514,10 +682,19 @@ fn EditorTabs(
 /// Settings tab content.
 #[component]
 fn SettingsTabContent(
+    workflow_id: Signal<Option<String>>,
     edit_name: ReadSignal<String>,
     set_edit_name: WriteSignal<String>,
     edit_desc: ReadSignal<String>,
     set_edit_desc: WriteSignal<String>,
+    edit_max_runs: ReadSignal<String>,
+    set_edit_max_runs: WriteSignal<String>,
+    edit_overlap: ReadSignal<String>,
+    set_edit_overlap: WriteSignal<String>,
+    edit_keep_days: ReadSignal<String>,
+    set_edit_keep_days: WriteSignal<String>,
+    edit_keep_runs: ReadSignal<String>,
+    set_edit_keep_runs: WriteSignal<String>,
 ) -> impl IntoView {
     view! {
         <div class="settings-content">
@@ -537,6 +714,49 @@ fn SettingsTabContent(
                     on:input=move |ev| set_edit_desc.set(event_target_value(&ev))
                 ></textarea>
             </div>
+            <div class="form-group">
+                <label>"Max Concurrent Runs"</label>
+                <input
+                    type="number"
+                    min="1"
+                    placeholder="No limit"
+                    prop:value=move || edit_max_runs.get()
+                    on:input=move |ev| set_edit_max_runs.set(event_target_value(&ev))
+                />
+            </div>
+            <div class="form-group">
+                <label>"When the Limit Is Reached"</label>
+                <select
+                    prop:value=move || edit_overlap.get()
+                    on:change=move |ev| set_edit_overlap.set(event_target_value(&ev))
+                >
+                    <option value="queue">"Queue the new run"</option>
+                    <option value="skip">"Skip the new run"</option>
+                    <option value="cancel_older">"Cancel the oldest run"</option>
+                </select>
+            </div>
+            <div class="form-group">
+                <label>"Keep Run Outputs for Days"</label>
+                <input
+                    type="number"
+                    min="1"
+                    placeholder="Global policy"
+                    prop:value=move || edit_keep_days.get()
+                    on:input=move |ev| set_edit_keep_days.set(event_target_value(&ev))
+                />
+            </div>
+            <div class="form-group">
+                <label>"Keep Outputs of the Last Runs"</label>
+                <input
+                    type="number"
+                    min="1"
+                    placeholder="Global policy"
+                    prop:value=move || edit_keep_runs.get()
+                    on:input=move |ev| set_edit_keep_runs.set(event_target_value(&ev))
+                />
+            </div>
+            <ExportPanel workflow_id=workflow_id />
+            <DiagramPanel workflow_id=workflow_id />
         </div>
     }
 }
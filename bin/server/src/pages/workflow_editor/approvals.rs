@@ -0,0 +1,370 @@
+//! Workflow approval types, server functions, and UI components.
+//!
+//! Approval nodes pause a run until a user approves or rejects the node's
+//! input. The approvals tab lists the workflow's pending approvals with
+//! their payload and records the user's decision, which resumes the run.
+
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+
+/// Pending approval summary for the approvals list.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct ApprovalSummary {
+    pub id: String,
+    pub run_id: String,
+    pub node_id: String,
+    pub item_index: Option<i32>,
+    pub payload: serde_json::Value,
+    pub requested_at: String,
+    pub expires_at: Option<String>,
+}
+
+/// Server function to list a workflow's pending approvals.
+#[server]
+pub async fn list_pending_approvals(
+    workflow_id: String,
+) -> Result<Vec<ApprovalSummary>, ServerFnError> {
+    use crate::db::ApprovalRepository;
+    use crate::error::{ApprovalError, WorkflowError};
+    use crate::server_helpers::{get_authenticated_session, get_authz_client, get_db_pool};
+    use silver_telegram_authz::{Permission, Resource, Subject};
+    use silver_telegram_core::WorkflowId;
+    use std::str::FromStr;
+
+    let auth = get_authenticated_session().await.map_err(|e| {
+        tracing::debug!(error = %e, "Authentication failed for list_pending_approvals");
+        e.into_server_error()
+    })?;
+
+    let wf_id = WorkflowId::from_str(&workflow_id).map_err(|e| {
+        tracing::debug!(
+            workflow_id = %workflow_id,
+            error = %e,
+            "Invalid workflow ID format"
+        );
+        WorkflowError::InvalidId {
+            id: workflow_id.clone(),
+            reason: e.to_string(),
+        }
+        .into_server_error()
+    })?;
+
+    // Check view permission via SpiceDB
+    let authz_client = get_authz_client();
+    let resource = Resource::workflow(wf_id);
+    let subject = Subject::user(auth.user_id);
+    authz_client
+        .require_permission(&resource, Permission::View, &subject)
+        .await
+        .map_err(|e| {
+            tracing::warn!(
+                workflow_id = %wf_id,
+                user_id = %auth.user_id,
+                error = %e,
+                "Access denied to workflow"
+            );
+            WorkflowError::AccessDenied {
+                id: wf_id.to_string(),
+            }
+            .into_server_error()
+        })?;
+
+    let approvals = ApprovalRepository::new(get_db_pool())
+        .list_pending_for_workflow(wf_id)
+        .await
+        .map_err(|e| {
+            tracing::error!(
+                error = %e,
+                workflow_id = %wf_id,
+                "Database error loading approvals"
+            );
+            ApprovalError::DatabaseError {
+                details: e.to_string(),
+            }
+            .into_server_error()
+        })?;
+
+    Ok(approvals
+        .into_iter()
+        .map(|a| ApprovalSummary {
+            id: a.id,
+            run_id: a.run_id.to_string(),
+            node_id: a.node_id,
+            item_index: a.item_index,
+            payload: a.payload,
+            requested_at: a.requested_at.to_rfc3339(),
+            expires_at: a.expires_at.map(|dt| dt.to_rfc3339()),
+        })
+        .collect())
+}
+
+/// Server function to approve or reject a pending approval.
+///
+/// The decision is recorded in the workflow engine, which resumes the run
+/// down the approved or rejected port.
+#[server]
+pub async fn decide_approval(
+    workflow_id: String,
+    approval_id: String,
+    approve: bool,
+) -> Result<(), ServerFnError> {
+    use crate::db::{
+        ApprovalRepository, ApprovalStatus, WorkflowRepository, WorkflowRunRepository,
+    };
+    use crate::engine::WorkflowEngine;
+    use crate::error::{ApprovalError, EngineError, WorkflowError, WorkflowRunError};
+    use crate::server_helpers::{get_authenticated_session, get_authz_client, get_db_pool};
+    use axum::Extension;
+    use silver_telegram_authz::{Permission, Resource, Subject};
+    use silver_telegram_core::WorkflowId;
+    use silver_telegram_workflow::{ApprovalDecision, NodeId};
+    use std::str::FromStr;
+
+    let auth = get_authenticated_session().await.map_err(|e| {
+        tracing::debug!(error = %e, "Authentication failed for decide_approval");
+        e.into_server_error()
+    })?;
+
+    let wf_id = WorkflowId::from_str(&workflow_id).map_err(|e| {
+        tracing::debug!(
+            workflow_id = %workflow_id,
+            error = %e,
+            "Invalid workflow ID format"
+        );
+        WorkflowError::InvalidId {
+            id: workflow_id.clone(),
+            reason: e.to_string(),
+        }
+        .into_server_error()
+    })?;
+
+    // Deciding resumes the run, so it needs execute permission
+    let authz_client = get_authz_client();
+    let resource = Resource::workflow(wf_id);
+    let subject = Subject::user(auth.user_id);
+    authz_client
+        .require_permission(&resource, Permission::Execute, &subject)
+        .await
+        .map_err(|e| {
+            tracing::warn!(
+                workflow_id = %wf_id,
+                user_id = %auth.user_id,
+                error = %e,
+                "Access denied to execute workflow"
+            );
+            WorkflowError::AccessDenied {
+                id: wf_id.to_string(),
+            }
+            .into_server_error()
+        })?;
+
+    let db_pool = get_db_pool();
+    let approval_repo = ApprovalRepository::new(db_pool.clone());
+    let database_error = |e: sqlx::Error| {
+        tracing::error!(
+            error = %e,
+            approval_id = %approval_id,
+            "Database error loading approval"
+        );
+        ApprovalError::DatabaseError {
+            details: e.to_string(),
+        }
+        .into_server_error()
+    };
+    let approval = approval_repo
+        .find_by_id(&approval_id)
+        .await
+        .map_err(database_error)?
+        .filter(|a| a.workflow_id == wf_id)
+        .ok_or_else(|| {
+            tracing::debug!(approval_id = %approval_id, "Approval not found");
+            ApprovalError::NotFound {
+                id: approval_id.clone(),
+            }
+            .into_server_error()
+        })?;
+    if approval.status != ApprovalStatus::Pending {
+        return Err(ApprovalError::NotPending { id: approval.id }.into_server_error());
+    }
+
+    // The run is replayed against the version it executes
+    let run = WorkflowRunRepository::new(db_pool.clone())
+        .find_by_id(approval.run_id)
+        .await
+        .map_err(database_error)?
+        .ok_or_else(|| {
+            WorkflowRunError::NotFound {
+                id: approval.run_id.to_string(),
+            }
+            .into_server_error()
+        })?;
+    let workflow = WorkflowRepository::new(db_pool)
+        .find_for_run(&run)
+        .await
+        .map_err(database_error)?
+        .ok_or_else(|| {
+            WorkflowError::NotFound {
+                id: wf_id.to_string(),
+            }
+            .into_server_error()
+        })?;
+
+    let node_id = NodeId::from_str(&approval.node_id).map_err(|e| {
+        tracing::error!(
+            approval_id = %approval.id,
+            node_id = %approval.node_id,
+            error = %e,
+            "Invalid node ID on approval"
+        );
+        ApprovalError::NotFound {
+            id: approval.id.clone(),
+        }
+        .into_server_error()
+    })?;
+    let item_index = approval
+        .item_index
+        .and_then(|index| usize::try_from(index).ok());
+    let (decision, status) = if approve {
+        (ApprovalDecision::Approved, ApprovalStatus::Approved)
+    } else {
+        (ApprovalDecision::Rejected, ApprovalStatus::Rejected)
+    };
+
+    let Extension(engine): Extension<Option<WorkflowEngine>> = leptos_axum::extract().await?;
+    let engine = engine.ok_or_else(|| {
+        EngineError::ConnectionFailed {
+            details: "workflow engine not configured".to_string(),
+        }
+        .into_server_error()
+    })?;
+    engine
+        .resolve_approval(
+            &workflow,
+            run.id,
+            node_id,
+            item_index,
+            decision,
+            auth.user_id.to_string(),
+        )
+        .await
+        .map_err(|e| {
+            tracing::warn!(
+                approval_id = %approval.id,
+                run_id = %run.id,
+                error = %e,
+                "Failed to resolve approval in the workflow engine"
+            );
+            e.into_server_error()
+        })?;
+
+    // The run history sync records the decision as well; recording it here
+    // shows it right away
+    approval_repo
+        .decide(
+            &approval.id,
+            status,
+            Some(&auth.user_id.to_string()),
+            chrono::Utc::now(),
+        )
+        .await
+        .map_err(database_error)?;
+
+    tracing::info!(
+        approval_id = %approval.id,
+        run_id = %run.id,
+        user_id = %auth.user_id,
+        approved = approve,
+        "Approval decided"
+    );
+
+    Ok(())
+}
+
+/// Approvals tab component listing pending approvals with their payload.
+#[component]
+pub fn ApprovalsTab(workflow_id: Signal<Option<String>>) -> impl IntoView {
+    let (refresh, set_refresh) = signal(0u32);
+    let (decide_error, set_decide_error) = signal(Option::<String>::None);
+
+    let approvals = Resource::new(
+        move || (workflow_id.get(), refresh.get()),
+        |(id, _)| async move {
+            match id {
+                Some(id) => list_pending_approvals(id).await.ok().unwrap_or_default(),
+                None => vec![],
+            }
+        },
+    );
+
+    let decide = move |approval_id: String, approve: bool| {
+        let Some(wf_id) = workflow_id.get() else {
+            return;
+        };
+        spawn_local(async move {
+            match decide_approval(wf_id, approval_id, approve).await {
+                Ok(()) => set_decide_error.set(None),
+                Err(e) => set_decide_error.set(Some(e.to_string())),
+            }
+            set_refresh.update(|n| *n += 1);
+        });
+    };
+
+    view! {
+        <div class="approvals-content">
+            <h3>"Pending Approvals"</h3>
+            <p>"Runs wait at approval nodes until the input is approved or rejected."</p>
+            {move || decide_error.get().map(|e| view! { <p class="error">{e}</p> })}
+            <Suspense fallback=move || view! { <p>"Loading approvals..."</p> }>
+                {move || {
+                    let approvals_list = approvals.get().unwrap_or_default();
+                    if approvals_list.is_empty() {
+                        view! {
+                            <p class="empty-state">"No approvals are waiting for a decision."</p>
+                        }.into_any()
+                    } else {
+                        view! {
+                            <div class="approvals-list">
+                                {approvals_list.into_iter().map(|approval| {
+                                    let approve_id = approval.id.clone();
+                                    let reject_id = approval.id.clone();
+                                    let payload = serde_json::to_string_pretty(&approval.payload)
+                                        .unwrap_or_default();
+                                    view! {
+                                        <div class="approval">
+                                            <div class="approval-header">
+                                                <span class="node-id">{approval.node_id}</span>
+                                                {approval.item_index.map(|i| view! {
+                                                    <span class="node-item">"item "{i}</span>
+                                                })}
+                                                <span class="approval-run">"run "<code>{approval.run_id}</code></span>
+                                            </div>
+                                            <p>"Requested: "{approval.requested_at}</p>
+                                            {approval.expires_at.map(|at| view! {
+                                                <p>"Expires: "{at}</p>
+                                            })}
+                                            <pre class="approval-payload">{payload}</pre>
+                                            <div class="approval-actions">
+                                                <button
+                                                    class="approve-btn primary-btn"
+                                                    on:click=move |_| decide(approve_id.clone(), true)
+                                                >
+                                                    "Approve"
+                                                </button>
+                                                <button
+                                                    class="reject-btn"
+                                                    on:click=move |_| decide(reject_id.clone(), false)
+                                                >
+                                                    "Reject"
+                                                </button>
+                                            </div>
+                                        </div>
+                                    }
+                                }).collect_view()}
+                            </div>
+                        }.into_any()
+                    }
+                }}
+            </Suspense>
+        </div>
+    }
+}
@@ -1,6 +1,7 @@
 //! Editor tab content with visual node canvas and configuration panel.
 
 use super::graph::{WorkflowEdge, WorkflowGraph, WorkflowNode};
+use super::lint::{DiagnosticsPanel, GraphDiagnostic};
 use crate::pages::integrations::{IntegrationInfo, ModelInfo, discover_models};
 use leptos::prelude::*;
 
@@ -16,6 +17,7 @@ pub fn EditorTabContent(
     selected_node_id: ReadSignal<Option<String>>,
     set_selected_node_id: WriteSignal<Option<String>>,
     available_integrations: Resource<Vec<IntegrationInfo>>,
+    diagnostics: Signal<Vec<GraphDiagnostic>>,
 ) -> impl IntoView {
     // Track which node is being dragged
     let (dragging_node, set_dragging_node) = signal(Option::<String>::None);
@@ -67,6 +69,11 @@ pub fn EditorTabContent(
                 })}
             </div>
 
+            <DiagnosticsPanel
+                diagnostics=diagnostics
+                set_selected_node_id=set_selected_node_id
+            />
+
             <div class="editor-layout">
                 <NodeCanvas
                     graph=graph
@@ -77,6 +84,7 @@ pub fn EditorTabContent(
                     set_dragging_node=set_dragging_node
                     connecting_from=connecting_from
                     set_connecting_from=set_connecting_from
+                    diagnostics=diagnostics
                 />
 
                 <NodeConfigPanel
@@ -102,6 +110,7 @@ fn NodeCanvas(
     set_dragging_node: WriteSignal<Option<String>>,
     connecting_from: ReadSignal<Option<String>>,
     set_connecting_from: WriteSignal<Option<String>>,
+    diagnostics: Signal<Vec<GraphDiagnostic>>,
 ) -> impl IntoView {
     // Track last mouse position for drag delta calculation
     let (last_mouse_pos, set_last_mouse_pos) = signal((0.0f64, 0.0f64));
@@ -252,6 +261,7 @@ fn NodeCanvas(
                     let g = graph.get();
                     let sel_id = selected_node_id.get();
                     let conn_from = connecting_from.get();
+                    let findings = diagnostics.get();
                     g.nodes.iter().map(|node| {
                         let node_id = node.id.clone();
                         let node_id_select = node.id.clone();
@@ -266,10 +276,16 @@ fn NodeCanvas(
                         let is_selected = sel_id.as_ref() == Some(&node_id);
                         let is_connecting = conn_from.is_some();
                         let type_class = format!("node-type-{}", node_type);
+                        let node_findings = findings.iter().filter(|d| d.node_id.as_ref() == Some(&node_id));
+                        let lint_class = match node_findings.map(|d| d.is_error()).max() {
+                            Some(true) => "has-error",
+                            Some(false) => "has-warning",
+                            None => "",
+                        };
 
                         view! {
                             <g
-                                class=format!("workflow-node {} {}", type_class, if is_selected { "selected" } else { "" })
+                                class=format!("workflow-node {} {} {}", type_class, if is_selected { "selected" } else { "" }, lint_class)
                                 transform=format!("translate({}, {})", x, y)
                                 on:mousedown=move |ev: leptos::ev::MouseEvent| {
                                     ev.prevent_default();
@@ -0,0 +1,508 @@
+//! Workflow export types, server functions, and UI components.
+//!
+//! Exports a workflow as a portable document, in YAML or JSON, that can be
+//! imported on the workflows page of this or another instance, or its graph
+//! as a Graphviz DOT or Mermaid diagram, optionally colored by the node
+//! states of a run. The settings tab offers both as downloads.
+
+use super::history::list_workflow_runs;
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+
+/// An exported file, ready for download.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct WorkflowExport {
+    pub file_name: String,
+    pub content: String,
+    /// The content as a `data:` URL, for download links.
+    pub data_url: String,
+}
+
+/// Server function to export a workflow as a portable document.
+///
+/// `format` is `yaml` or `json`. The workflow's memory contents are only
+/// included if requested, as they may hold personal data.
+#[server]
+pub async fn export_workflow(
+    workflow_id: String,
+    format: String,
+    include_memory: bool,
+) -> Result<WorkflowExport, ServerFnError> {
+    use crate::db::{WorkflowMemoryRepository, WorkflowRepository};
+    use crate::engine::executable_workflow;
+    use crate::error::WorkflowError;
+    use crate::server_helpers::{get_authenticated_session, get_authz_client, get_db_pool};
+    use silver_telegram_authz::{Permission, Resource, Subject};
+    use silver_telegram_core::WorkflowId;
+    use silver_telegram_workflow::{DocumentFormat, WorkflowDocument};
+    use std::str::FromStr;
+
+    let auth = get_authenticated_session().await.map_err(|e| {
+        tracing::debug!(error = %e, "Authentication failed for export_workflow");
+        e.into_server_error()
+    })?;
+
+    let wf_id = WorkflowId::from_str(&workflow_id).map_err(|e| {
+        tracing::debug!(
+            workflow_id = %workflow_id,
+            error = %e,
+            "Invalid workflow ID format"
+        );
+        WorkflowError::InvalidId {
+            id: workflow_id.clone(),
+            reason: e.to_string(),
+        }
+        .into_server_error()
+    })?;
+
+    let format = match format.as_str() {
+        "yaml" => DocumentFormat::Yaml,
+        "json" => DocumentFormat::Json,
+        other => {
+            return Err(WorkflowError::InvalidDocument {
+                details: format!("unknown format '{}'", other),
+            }
+            .into_server_error());
+        }
+    };
+
+    // Check view permission via SpiceDB
+    let authz_client = get_authz_client();
+    let resource = Resource::workflow(wf_id);
+    let subject = Subject::user(auth.user_id);
+    authz_client
+        .require_permission(&resource, Permission::View, &subject)
+        .await
+        .map_err(|e| {
+            tracing::warn!(
+                workflow_id = %wf_id,
+                user_id = %auth.user_id,
+                error = %e,
+                "Access denied to workflow"
+            );
+            WorkflowError::AccessDenied {
+                id: wf_id.to_string(),
+            }
+            .into_server_error()
+        })?;
+
+    let db_pool = get_db_pool();
+    let database_error = |e: sqlx::Error| {
+        tracing::error!(
+            workflow_id = %wf_id,
+            error = %e,
+            "Database error exporting workflow"
+        );
+        WorkflowError::DatabaseError {
+            details: e.to_string(),
+        }
+        .into_server_error()
+    };
+    let record = WorkflowRepository::new(db_pool.clone())
+        .find_by_id(wf_id)
+        .await
+        .map_err(database_error)?
+        .ok_or_else(|| {
+            WorkflowError::NotFound {
+                id: wf_id.to_string(),
+            }
+            .into_server_error()
+        })?;
+
+    // Only graphs the engine can execute have a portable form
+    let mut workflow = executable_workflow(&record).map_err(|e| {
+        tracing::debug!(workflow_id = %wf_id, error = %e, "Workflow cannot be exported");
+        WorkflowError::InvalidGraph {
+            details: e.to_string(),
+        }
+        .into_server_error()
+    })?;
+    workflow.metadata.tags = record.tags.clone();
+
+    let mut document = WorkflowDocument::from_workflow(&workflow);
+    if include_memory {
+        let memory = WorkflowMemoryRepository::new(db_pool)
+            .find_by_workflow(wf_id)
+            .await
+            .map_err(database_error)?;
+        if let Some(contents) = memory.and_then(|m| String::from_utf8(m.content).ok()) {
+            document = document.with_memory_contents(contents);
+        }
+    }
+
+    let content = document.encode(format).map_err(|e| {
+        tracing::error!(workflow_id = %wf_id, error = %e, "Failed to encode workflow document");
+        WorkflowError::InvalidDocument {
+            details: e.to_string(),
+        }
+        .into_server_error()
+    })?;
+
+    tracing::info!(
+        workflow_id = %wf_id,
+        user_id = %auth.user_id,
+        include_memory = include_memory,
+        "Exported workflow"
+    );
+
+    Ok(WorkflowExport {
+        file_name: format!("{}.{}", file_stem(&record.name), format.extension()),
+        data_url: data_url(format.media_type(), &content),
+        content,
+    })
+}
+
+/// Server function to render a workflow's graph as a diagram.
+///
+/// `format` is `dot` or `mermaid`. Given a run, the graph of the version
+/// the run executed is rendered, with its nodes colored by their state in
+/// the run.
+#[server]
+pub async fn export_workflow_diagram(
+    workflow_id: String,
+    format: String,
+    run_id: Option<String>,
+) -> Result<WorkflowExport, ServerFnError> {
+    use crate::db::{WorkflowRepository, WorkflowRunRepository};
+    use crate::engine::{WorkflowEngine, executable_workflow};
+    use crate::error::{EngineError, WorkflowError, WorkflowRunError};
+    use crate::server_helpers::{get_authenticated_session, get_authz_client, get_db_pool};
+    use axum::Extension;
+    use silver_telegram_authz::{Permission, Resource, Subject};
+    use silver_telegram_core::{WorkflowId, WorkflowRunId};
+    use silver_telegram_workflow::{RenderFormat, render};
+    use std::str::FromStr;
+
+    let auth = get_authenticated_session().await.map_err(|e| {
+        tracing::debug!(error = %e, "Authentication failed for export_workflow_diagram");
+        e.into_server_error()
+    })?;
+
+    let wf_id = WorkflowId::from_str(&workflow_id).map_err(|e| {
+        tracing::debug!(
+            workflow_id = %workflow_id,
+            error = %e,
+            "Invalid workflow ID format"
+        );
+        WorkflowError::InvalidId {
+            id: workflow_id.clone(),
+            reason: e.to_string(),
+        }
+        .into_server_error()
+    })?;
+    let r_id = run_id
+        .map(|run_id| {
+            WorkflowRunId::from_str(&run_id).map_err(|e| {
+                tracing::debug!(run_id = %run_id, error = %e, "Invalid run ID format");
+                WorkflowRunError::InvalidId {
+                    id: run_id.clone(),
+                    reason: e.to_string(),
+                }
+                .into_server_error()
+            })
+        })
+        .transpose()?;
+
+    let format = match format.as_str() {
+        "dot" => RenderFormat::Dot,
+        "mermaid" => RenderFormat::Mermaid,
+        other => {
+            return Err(WorkflowError::InvalidDocument {
+                details: format!("unknown format '{}'", other),
+            }
+            .into_server_error());
+        }
+    };
+
+    // Check view permission via SpiceDB
+    let authz_client = get_authz_client();
+    let resource = Resource::workflow(wf_id);
+    let subject = Subject::user(auth.user_id);
+    authz_client
+        .require_permission(&resource, Permission::View, &subject)
+        .await
+        .map_err(|e| {
+            tracing::warn!(
+                workflow_id = %wf_id,
+                user_id = %auth.user_id,
+                error = %e,
+                "Access denied to workflow"
+            );
+            WorkflowError::AccessDenied {
+                id: wf_id.to_string(),
+            }
+            .into_server_error()
+        })?;
+
+    let db_pool = get_db_pool();
+    let database_error = |e: sqlx::Error| {
+        tracing::error!(
+            workflow_id = %wf_id,
+            error = %e,
+            "Database error rendering workflow diagram"
+        );
+        WorkflowError::DatabaseError {
+            details: e.to_string(),
+        }
+        .into_server_error()
+    };
+    let workflow_repo = WorkflowRepository::new(db_pool.clone());
+    let run = match r_id {
+        Some(r_id) => Some(
+            WorkflowRunRepository::new(db_pool)
+                .find_by_id(r_id)
+                .await
+                .map_err(database_error)?
+                .filter(|run| run.workflow_id == wf_id)
+                .ok_or_else(|| {
+                    WorkflowRunError::NotFound {
+                        id: r_id.to_string(),
+                    }
+                    .into_server_error()
+                })?,
+        ),
+        None => None,
+    };
+    let record = match &run {
+        Some(run) => workflow_repo.find_for_run(run).await,
+        None => workflow_repo.find_by_id(wf_id).await,
+    }
+    .map_err(database_error)?
+    .ok_or_else(|| {
+        WorkflowError::NotFound {
+            id: wf_id.to_string(),
+        }
+        .into_server_error()
+    })?;
+
+    let workflow = executable_workflow(&record).map_err(|e| {
+        tracing::debug!(workflow_id = %wf_id, error = %e, "Workflow cannot be rendered");
+        WorkflowError::InvalidGraph {
+            details: e.to_string(),
+        }
+        .into_server_error()
+    })?;
+
+    let overlay = match &run {
+        Some(run) => {
+            let Extension(engine): Extension<Option<WorkflowEngine>> =
+                leptos_axum::extract().await?;
+            let engine = engine.ok_or_else(|| {
+                EngineError::ConnectionFailed {
+                    details: "workflow engine not configured".to_string(),
+                }
+                .into_server_error()
+            })?;
+            Some(engine.state_overlay(&record, run.id).await.map_err(|e| {
+                tracing::warn!(
+                    run_id = %run.id,
+                    error = %e,
+                    "Failed to load node states from the workflow engine"
+                );
+                e.into_server_error()
+            })?)
+        }
+        None => None,
+    };
+
+    let content = render(&workflow.graph, format, overlay.as_ref());
+    let file_stem = match &run {
+        Some(run) => format!("{}-{}", file_stem(&record.name), run.id),
+        None => file_stem(&record.name),
+    };
+
+    Ok(WorkflowExport {
+        file_name: format!("{}.{}", file_stem, format.extension()),
+        data_url: data_url(format.media_type(), &content),
+        content,
+    })
+}
+
+/// Returns a file name for a workflow's name, keeping only characters that
+/// are safe in file names.
+#[cfg(feature = "ssr")]
+fn file_stem(name: &str) -> String {
+    let stem: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    let stem = stem.trim_matches('-');
+    if stem.is_empty() {
+        "workflow".to_string()
+    } else {
+        stem.to_string()
+    }
+}
+
+/// Returns a `data:` URL holding text content.
+#[cfg(feature = "ssr")]
+pub(crate) fn data_url(media_type: &str, content: &str) -> String {
+    use base64::Engine;
+    format!(
+        "data:{};base64,{}",
+        media_type,
+        base64::engine::general_purpose::STANDARD.encode(content)
+    )
+}
+
+/// Export panel with a format choice, a download link, and a preview of
+/// the exported document.
+#[component]
+pub fn ExportPanel(workflow_id: Signal<Option<String>>) -> impl IntoView {
+    let (format, set_format) = signal("yaml".to_string());
+    let (include_memory, set_include_memory) = signal(false);
+    let (exporting, set_exporting) = signal(false);
+    let (export, set_export) = signal(Option::<WorkflowExport>::None);
+    let (export_error, set_export_error) = signal(Option::<String>::None);
+
+    let on_export = move |_| {
+        let Some(wf_id) = workflow_id.get() else {
+            return;
+        };
+        set_exporting.set(true);
+        spawn_local(async move {
+            match export_workflow(
+                wf_id,
+                format.get_untracked(),
+                include_memory.get_untracked(),
+            )
+            .await
+            {
+                Ok(exported) => {
+                    set_export.set(Some(exported));
+                    set_export_error.set(None);
+                }
+                Err(e) => {
+                    set_export.set(None);
+                    set_export_error.set(Some(e.to_string()));
+                }
+            }
+            set_exporting.set(false);
+        });
+    };
+
+    view! {
+        <div class="export-panel">
+            <h3>"Export"</h3>
+            <p>"Export the saved workflow as a document that can be imported on the workflows page."</p>
+            <div class="export-form">
+                <select
+                    prop:value=move || format.get()
+                    on:change=move |ev| set_format.set(event_target_value(&ev))
+                >
+                    <option value="yaml">"YAML"</option>
+                    <option value="json">"JSON"</option>
+                </select>
+                <label>
+                    <input
+                        type="checkbox"
+                        prop:checked=move || include_memory.get()
+                        on:change=move |ev| set_include_memory.set(event_target_checked(&ev))
+                    />
+                    " Include memory contents"
+                </label>
+                <button on:click=on_export disabled=move || exporting.get()>
+                    {move || if exporting.get() { "Exporting..." } else { "Export" }}
+                </button>
+            </div>
+            {move || export_error.get().map(|e| view! { <p class="error">{e}</p> })}
+            {move || export.get().map(|exported| view! { <ExportDownload exported=exported /> })}
+        </div>
+    }
+    .into_any()
+}
+
+/// Diagram panel with a format choice, an optional run to color the nodes
+/// by, a download link, and a preview of the diagram.
+#[component]
+pub fn DiagramPanel(workflow_id: Signal<Option<String>>) -> impl IntoView {
+    let (format, set_format) = signal("mermaid".to_string());
+    let (run_id, set_run_id) = signal(String::new());
+    let (rendering, set_rendering) = signal(false);
+    let (diagram, set_diagram) = signal(Option::<WorkflowExport>::None);
+    let (diagram_error, set_diagram_error) = signal(Option::<String>::None);
+
+    let runs = Resource::new(
+        move || workflow_id.get(),
+        |id| async move {
+            match id {
+                Some(id) => list_workflow_runs(id).await.ok().unwrap_or_default(),
+                None => vec![],
+            }
+        },
+    );
+
+    let on_render = move |_| {
+        let Some(wf_id) = workflow_id.get() else {
+            return;
+        };
+        let run_id = Some(run_id.get_untracked()).filter(|id| !id.is_empty());
+        set_rendering.set(true);
+        spawn_local(async move {
+            match export_workflow_diagram(wf_id, format.get_untracked(), run_id).await {
+                Ok(rendered) => {
+                    set_diagram.set(Some(rendered));
+                    set_diagram_error.set(None);
+                }
+                Err(e) => {
+                    set_diagram.set(None);
+                    set_diagram_error.set(Some(e.to_string()));
+                }
+            }
+            set_rendering.set(false);
+        });
+    };
+
+    view! {
+        <div class="export-panel">
+            <h3>"Diagram"</h3>
+            <p>"Render the saved graph for review, optionally colored by the node states of a run."</p>
+            <div class="export-form">
+                <select
+                    prop:value=move || format.get()
+                    on:change=move |ev| set_format.set(event_target_value(&ev))
+                >
+                    <option value="mermaid">"Mermaid"</option>
+                    <option value="dot">"Graphviz DOT"</option>
+                </select>
+                <select
+                    prop:value=move || run_id.get()
+                    on:change=move |ev| set_run_id.set(event_target_value(&ev))
+                >
+                    <option value="">"No run overlay"</option>
+                    <Suspense fallback=|| ()>
+                        {move || runs.get().unwrap_or_default().into_iter().map(|run| view! {
+                            <option value=run.id.clone()>
+                                {format!("{} ({})", run.queued_at, run.state)}
+                            </option>
+                        }).collect_view()}
+                    </Suspense>
+                </select>
+                <button on:click=on_render disabled=move || rendering.get()>
+                    {move || if rendering.get() { "Rendering..." } else { "Render" }}
+                </button>
+            </div>
+            {move || diagram_error.get().map(|e| view! { <p class="error">{e}</p> })}
+            {move || diagram.get().map(|rendered| view! { <ExportDownload exported=rendered /> })}
+        </div>
+    }
+    .into_any()
+}
+
+/// Download link and preview of an exported file.
+#[component]
+fn ExportDownload(exported: WorkflowExport) -> impl IntoView {
+    let label = format!("Download {}", exported.file_name);
+    view! {
+        <a class="download-link" href=exported.data_url download=exported.file_name>
+            {label}
+        </a>
+        <pre class="export-preview">{exported.content}</pre>
+    }
+}
@@ -95,6 +95,25 @@ pub async fn update_workflow_graph(
         .into_server_error()
     })?;
 
+    // Graphs in the engine's format are validated on save, so invalid
+    // expressions are reported here rather than when a run starts.
+    if let Ok(mut engine_graph) =
+        serde_json::from_value::<silver_telegram_workflow::WorkflowGraph>(graph.clone())
+    {
+        engine_graph.rebuild_index_map();
+        engine_graph.validate().map_err(|e| {
+            tracing::debug!(
+                workflow_id = %wf_id,
+                error = %e,
+                "Graph failed validation"
+            );
+            WorkflowError::InvalidGraph {
+                details: e.to_string(),
+            }
+            .into_server_error()
+        })?;
+    }
+
     let db_pool = get_db_pool();
     let workflow_repo = WorkflowRepository::new(db_pool.clone());
     let mut workflow = workflow_repo
@@ -119,19 +138,22 @@ pub async fn update_workflow_graph(
             .into_server_error()
         })?;
 
-    workflow.set_graph(graph.clone());
-
-    workflow_repo.update(&workflow).await.map_err(|e| {
-        tracing::error!(
-            error = %e,
-            workflow_id = %wf_id,
-            "Failed to update workflow"
-        );
-        WorkflowError::DatabaseError {
-            details: e.to_string(),
-        }
-        .into_server_error()
-    })?;
+    // Each save of a changed graph is a new version; runs keep the version
+    // they started on
+    if workflow.graph_data != graph {
+        workflow.set_graph(graph.clone());
+        workflow_repo.save_graph(&mut workflow).await.map_err(|e| {
+            tracing::error!(
+                error = %e,
+                workflow_id = %wf_id,
+                "Failed to save workflow version"
+            );
+            WorkflowError::DatabaseError {
+                details: e.to_string(),
+            }
+            .into_server_error()
+        })?;
+    }
 
     // Update triggers from graph nodes
     let trigger_repo = TriggerRepository::new(db_pool);
@@ -154,6 +176,7 @@ pub async fn update_workflow_graph(
                     trigger_type: "schedule".to_string(),
                     config_data: config,
                     active: workflow.enabled,
+                    next_run: None,
                     created_at: now,
                     updated_at: now,
                 };
@@ -192,6 +215,7 @@ pub async fn update_workflow_graph(
     tracing::info!(
         workflow_id = %wf_id,
         user_id = %auth.user_id,
+        version = workflow.version,
         trigger_count = trigger_node_ids.len(),
         "Workflow graph updated"
     );
@@ -1,19 +1,30 @@
 //! Workflow run history types, server functions, and UI components.
 //!
-//! Contains everything related to viewing workflow execution history.
+//! Contains everything related to viewing workflow execution history,
+//! including retrying a failed run from one of its failed nodes and
+//! starting dry runs, which substitute integration writes with fixture
+//! responses. Runs whose outputs expired under the retention policy can no
+//! longer be retried or record fixtures.
 
 use leptos::prelude::*;
+use leptos::task::spawn_local;
 
 /// Workflow run summary for history list.
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub struct WorkflowRunSummary {
     pub id: String,
     pub state: String,
+    pub workflow_version: Option<i32>,
     pub queued_at: String,
     pub started_at: Option<String>,
     pub finished_at: Option<String>,
     pub duration_ms: Option<i64>,
     pub error_message: Option<String>,
+    pub dry_run: bool,
+    /// Why the run was skipped instead of executed.
+    pub skip_reason: Option<String>,
+    /// Whether the run's outputs expired under the retention policy.
+    pub outputs_expired: bool,
 }
 
 /// Node execution summary for run details.
@@ -21,6 +32,7 @@ pub struct WorkflowRunSummary {
 pub struct NodeExecutionSummary {
     pub id: String,
     pub node_id: String,
+    pub item_index: Option<i32>,
     pub state: String,
     pub started_at: Option<String>,
     pub finished_at: Option<String>,
@@ -30,6 +42,35 @@ pub struct NodeExecutionSummary {
     pub output_key: Option<String>,
 }
 
+/// Delay summary for run details.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct DelaySummary {
+    pub node_id: String,
+    pub item_index: Option<i32>,
+    pub started_at: String,
+    pub ends_at: String,
+    pub ended_at: Option<String>,
+}
+
+/// Failed node summary for retrying a failed run.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct FailedNodeSummary {
+    pub node_id: String,
+    pub name: String,
+    pub error: Option<String>,
+}
+
+/// Summary of a node a dry run substituted instead of executing it.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct SubstitutionSummary {
+    pub node_id: String,
+    pub item_index: Option<usize>,
+    /// What the node would have sent.
+    pub request: serde_json::Value,
+    /// The fixture response the node completed with.
+    pub response: serde_json::Value,
+}
+
 /// Decision trace summary for AI node debugging.
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub struct DecisionTraceSummary {
@@ -43,6 +84,21 @@ pub struct DecisionTraceSummary {
 pub struct RunDetailView {
     pub id: String,
     pub state: String,
+    pub workflow_version: Option<i32>,
+    /// Run whose sub-workflow node started this run.
+    pub parent_run_id: Option<String>,
+    /// Failed run this run retries.
+    pub retry_of_run_id: Option<String>,
+    /// Node the retry re-ran from.
+    pub retry_from_node_id: Option<String>,
+    /// Runs that retried this run.
+    pub retried_by: Vec<String>,
+    /// Whether the run is a dry run.
+    pub dry_run: bool,
+    /// Why the run was skipped instead of executed.
+    pub skip_reason: Option<String>,
+    /// When the run's outputs expired under the retention policy.
+    pub outputs_expired_at: Option<String>,
     pub queued_at: String,
     pub started_at: Option<String>,
     pub finished_at: Option<String>,
@@ -51,6 +107,12 @@ pub struct RunDetailView {
     pub input_data: Option<serde_json::Value>,
     pub output_data: Option<serde_json::Value>,
     pub node_executions: Vec<NodeExecutionSummary>,
+    /// Delays started by the run's delay nodes.
+    pub delays: Vec<DelaySummary>,
+    /// Nodes that failed, which the run can be retried from.
+    pub failed_nodes: Vec<FailedNodeSummary>,
+    /// Nodes the dry run substituted.
+    pub substitutions: Vec<SubstitutionSummary>,
 }
 
 /// Server function to list workflow runs.
@@ -122,11 +184,15 @@ pub async fn list_workflow_runs(
         .map(|r| WorkflowRunSummary {
             id: r.id.to_string(),
             state: format!("{:?}", r.state).to_lowercase(),
+            workflow_version: r.workflow_version,
             queued_at: r.queued_at.to_rfc3339(),
             started_at: r.started_at.map(|dt| dt.to_rfc3339()),
             finished_at: r.finished_at.map(|dt| dt.to_rfc3339()),
             duration_ms: r.duration_ms,
             error_message: r.error_message,
+            dry_run: r.dry_run,
+            skip_reason: r.skip_reason,
+            outputs_expired: r.outputs_expired_at.is_some(),
         })
         .collect())
 }
@@ -137,9 +203,14 @@ pub async fn get_run_detail(
     workflow_id: String,
     run_id: String,
 ) -> Result<RunDetailView, ServerFnError> {
-    use crate::db::{NodeExecutionRepository, WorkflowRunRepository};
+    use crate::db::workflow_run::RunState;
+    use crate::db::{
+        DelayRepository, NodeExecutionRepository, WorkflowRepository, WorkflowRunRepository,
+    };
+    use crate::engine::WorkflowEngine;
     use crate::error::{WorkflowError, WorkflowRunError};
     use crate::server_helpers::{get_authenticated_session, get_authz_client, get_db_pool};
+    use axum::Extension;
     use silver_telegram_authz::{Permission, Resource, Subject};
     use silver_telegram_core::{WorkflowId, WorkflowRunId};
     use std::str::FromStr;
@@ -221,7 +292,7 @@ pub async fn get_run_detail(
         })?;
 
     // Get node executions
-    let exec_repo = NodeExecutionRepository::new(db_pool);
+    let exec_repo = NodeExecutionRepository::new(db_pool.clone());
     let executions = exec_repo.list_by_run(r_id).await.map_err(|e| {
         tracing::error!(
             error = %e,
@@ -239,6 +310,7 @@ pub async fn get_run_detail(
         .map(|e| NodeExecutionSummary {
             id: e.id.to_string(),
             node_id: e.node_id,
+            item_index: e.item_index,
             state: format!("{:?}", e.state).to_lowercase(),
             started_at: e.started_at.map(|dt| dt.to_rfc3339()),
             finished_at: e.finished_at.map(|dt| dt.to_rfc3339()),
@@ -249,9 +321,127 @@ pub async fn get_run_detail(
         })
         .collect();
 
+    // Get delays
+    let delays = DelayRepository::new(db_pool.clone())
+        .list_by_run(r_id)
+        .await
+        .map_err(|e| {
+            tracing::error!(
+                error = %e,
+                run_id = %r_id,
+                "Database error loading delays"
+            );
+            WorkflowRunError::DatabaseError {
+                details: e.to_string(),
+            }
+            .into_server_error()
+        })?
+        .into_iter()
+        .map(|d| DelaySummary {
+            node_id: d.node_id,
+            item_index: d.item_index,
+            started_at: d.started_at.to_rfc3339(),
+            ends_at: d.ends_at.to_rfc3339(),
+            ended_at: d.ended_at.map(|dt| dt.to_rfc3339()),
+        })
+        .collect();
+
+    // Get the runs that retried this run
+    let retried_by = run_repo
+        .list_retries(r_id)
+        .await
+        .map_err(|e| {
+            tracing::error!(
+                error = %e,
+                run_id = %r_id,
+                "Database error loading run retries"
+            );
+            WorkflowRunError::DatabaseError {
+                details: e.to_string(),
+            }
+            .into_server_error()
+        })?
+        .into_iter()
+        .map(|r| r.id.to_string())
+        .collect();
+
+    // The engine knows which nodes failed; without it, or once the run's
+    // outputs expired, the run cannot be retried, so no failed nodes are
+    // listed
+    let mut failed_nodes = Vec::new();
+    let mut substitutions = Vec::new();
+    let Extension(engine): Extension<Option<WorkflowEngine>> = leptos_axum::extract().await?;
+    if let (Some(engine), true) = (&engine, run.dry_run) {
+        match engine.substitutions(r_id).await {
+            Ok(substituted) => {
+                substitutions = substituted
+                    .into_iter()
+                    .map(|s| SubstitutionSummary {
+                        node_id: s.node_id.to_string(),
+                        item_index: s.item_index,
+                        request: s.request,
+                        response: s.response,
+                    })
+                    .collect();
+            }
+            Err(e) => {
+                tracing::warn!(
+                    run_id = %r_id,
+                    error = %e,
+                    "Failed to load substitutions from the workflow engine"
+                );
+            }
+        }
+    }
+    if let (Some(engine), RunState::Failed, None) = (engine, run.state, run.outputs_expired_at) {
+        let workflow = WorkflowRepository::new(db_pool)
+            .find_for_run(&run)
+            .await
+            .map_err(|e| {
+                tracing::error!(
+                    error = %e,
+                    run_id = %r_id,
+                    "Database error loading workflow for run"
+                );
+                WorkflowRunError::DatabaseError {
+                    details: e.to_string(),
+                }
+                .into_server_error()
+            })?;
+        if let Some(workflow) = workflow {
+            match engine.failed_nodes(&workflow, r_id).await {
+                Ok(nodes) => {
+                    failed_nodes = nodes
+                        .into_iter()
+                        .map(|n| FailedNodeSummary {
+                            node_id: n.node_id.to_string(),
+                            name: n.name,
+                            error: n.error,
+                        })
+                        .collect();
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        run_id = %r_id,
+                        error = %e,
+                        "Failed to load failed nodes from the workflow engine"
+                    );
+                }
+            }
+        }
+    }
+
     Ok(RunDetailView {
         id: run.id.to_string(),
         state: format!("{:?}", run.state).to_lowercase(),
+        workflow_version: run.workflow_version,
+        parent_run_id: run.parent_run_id.map(|id| id.to_string()),
+        retry_of_run_id: run.retry_of_run_id.map(|id| id.to_string()),
+        retry_from_node_id: run.retry_from_node_id,
+        retried_by,
+        dry_run: run.dry_run,
+        skip_reason: run.skip_reason,
+        outputs_expired_at: run.outputs_expired_at.map(|dt| dt.to_rfc3339()),
         queued_at: run.queued_at.to_rfc3339(),
         started_at: run.started_at.map(|dt| dt.to_rfc3339()),
         finished_at: run.finished_at.map(|dt| dt.to_rfc3339()),
@@ -260,18 +450,418 @@ pub async fn get_run_detail(
         input_data: run.input_data,
         output_data: run.output_data,
         node_executions,
+        delays,
+        failed_nodes,
+        substitutions,
     })
 }
 
+/// Server function to retry a failed run from one of its failed nodes.
+///
+/// Starts a new run of the same workflow version and input, which reuses the
+/// outputs of the nodes upstream of the node. Returns the new run's ID.
+#[server]
+pub async fn retry_run_from_node(
+    workflow_id: String,
+    run_id: String,
+    node_id: String,
+) -> Result<String, ServerFnError> {
+    use crate::db::workflow_run::RunState;
+    use crate::db::{WorkflowRepository, WorkflowRunRecord, WorkflowRunRepository};
+    use crate::engine::WorkflowEngine;
+    use crate::error::{EngineError, WorkflowError, WorkflowRunError};
+    use crate::server_helpers::{get_authenticated_session, get_authz_client, get_db_pool};
+    use axum::Extension;
+    use silver_telegram_authz::{Permission, Resource, Subject};
+    use silver_telegram_core::{WorkflowId, WorkflowRunId};
+    use silver_telegram_workflow::NodeId;
+    use std::str::FromStr;
+
+    let auth = get_authenticated_session().await.map_err(|e| {
+        tracing::debug!(error = %e, "Authentication failed for retry_run_from_node");
+        e.into_server_error()
+    })?;
+
+    let wf_id = WorkflowId::from_str(&workflow_id).map_err(|e| {
+        tracing::debug!(
+            workflow_id = %workflow_id,
+            error = %e,
+            "Invalid workflow ID format"
+        );
+        WorkflowError::InvalidId {
+            id: workflow_id.clone(),
+            reason: e.to_string(),
+        }
+        .into_server_error()
+    })?;
+
+    let r_id = WorkflowRunId::from_str(&run_id).map_err(|e| {
+        tracing::debug!(
+            run_id = %run_id,
+            error = %e,
+            "Invalid run ID format"
+        );
+        WorkflowRunError::InvalidId {
+            id: run_id.clone(),
+            reason: e.to_string(),
+        }
+        .into_server_error()
+    })?;
+
+    let n_id = NodeId::from_str(&node_id).map_err(|e| {
+        tracing::debug!(
+            node_id = %node_id,
+            error = %e,
+            "Invalid node ID format"
+        );
+        EngineError::RetryNotAllowed {
+            details: e.to_string(),
+        }
+        .into_server_error()
+    })?;
+
+    // Retrying starts a run, so it needs execute permission
+    let authz_client = get_authz_client();
+    let resource = Resource::workflow(wf_id);
+    let subject = Subject::user(auth.user_id);
+    authz_client
+        .require_permission(&resource, Permission::Execute, &subject)
+        .await
+        .map_err(|e| {
+            tracing::warn!(
+                workflow_id = %wf_id,
+                user_id = %auth.user_id,
+                error = %e,
+                "Access denied to execute workflow"
+            );
+            WorkflowError::AccessDenied {
+                id: wf_id.to_string(),
+            }
+            .into_server_error()
+        })?;
+
+    let db_pool = get_db_pool();
+    let run_repo = WorkflowRunRepository::new(db_pool.clone());
+    let database_error = |e: sqlx::Error| {
+        tracing::error!(
+            error = %e,
+            run_id = %r_id,
+            "Database error retrying workflow run"
+        );
+        WorkflowRunError::DatabaseError {
+            details: e.to_string(),
+        }
+        .into_server_error()
+    };
+    let failed_run = run_repo
+        .find_by_id(r_id)
+        .await
+        .map_err(database_error)?
+        .filter(|r| r.workflow_id == wf_id)
+        .ok_or_else(|| {
+            tracing::debug!(run_id = %r_id, "Workflow run not found");
+            WorkflowRunError::NotFound {
+                id: r_id.to_string(),
+            }
+            .into_server_error()
+        })?;
+    if failed_run.state != RunState::Failed {
+        return Err(EngineError::RetryNotAllowed {
+            details: format!("run {r_id} did not fail"),
+        }
+        .into_server_error());
+    }
+    if failed_run.outputs_expired_at.is_some() {
+        return Err(EngineError::OutputsExpired {
+            details: format!("run {r_id} has no outputs left to reuse"),
+        }
+        .into_server_error());
+    }
+
+    // The retry runs the version the failed run executed, whose outputs it
+    // reuses
+    let workflow = WorkflowRepository::new(db_pool.clone())
+        .find_for_run(&failed_run)
+        .await
+        .map_err(database_error)?
+        .ok_or_else(|| {
+            WorkflowError::NotFound {
+                id: wf_id.to_string(),
+            }
+            .into_server_error()
+        })?;
+
+    let Extension(engine): Extension<Option<WorkflowEngine>> = leptos_axum::extract().await?;
+    let engine = engine.ok_or_else(|| {
+        EngineError::ConnectionFailed {
+            details: "workflow engine not configured".to_string(),
+        }
+        .into_server_error()
+    })?;
+
+    let mut run =
+        WorkflowRunRecord::new(wf_id, failed_run.trigger_id, failed_run.input_data.clone())
+            .with_retry_of(r_id, n_id.to_string());
+    if let Some(version) = failed_run.workflow_version {
+        run = run.with_workflow_version(version);
+    }
+    // The engine retries a dry run as a dry run
+    if failed_run.dry_run {
+        run = run.as_dry_run();
+    }
+    // The retry is a run of the workflow like any other, so it may wait for
+    // a slot or be skipped
+    if let Err(e) = engine.launch_run(db_pool, &workflow, &mut run).await {
+        tracing::warn!(
+            run_id = %r_id,
+            retry_run_id = %run.id,
+            node_id = %n_id,
+            error = %e,
+            "Failed to retry run in the workflow engine"
+        );
+        return Err(e.into_server_error());
+    }
+
+    tracing::info!(
+        run_id = %r_id,
+        retry_run_id = %run.id,
+        node_id = %n_id,
+        user_id = %auth.user_id,
+        state = ?run.state,
+        "Workflow run retried"
+    );
+
+    Ok(run.id.to_string())
+}
+
+/// Server function to start a dry run of a workflow.
+///
+/// The run executes the current version of the workflow, except that
+/// integration writes, and LLM calls if `stub_llm` is set, are substituted
+/// with fixture responses. Fixtures are recorded from the outputs of
+/// `record_from_run`, if given, and overridden by `fixtures`, a JSON object
+/// keyed by node ID. `input` is the run's input as JSON. Returns the run's
+/// ID.
+#[server]
+pub async fn start_dry_run(
+    workflow_id: String,
+    input: Option<String>,
+    fixtures: Option<String>,
+    stub_llm: bool,
+    record_from_run: Option<String>,
+) -> Result<String, ServerFnError> {
+    use crate::db::{WorkflowRepository, WorkflowRunRecord, WorkflowRunRepository};
+    use crate::engine::WorkflowEngine;
+    use crate::error::{EngineError, WorkflowError, WorkflowRunError};
+    use crate::server_helpers::{get_authenticated_session, get_authz_client, get_db_pool};
+    use axum::Extension;
+    use silver_telegram_authz::{Permission, Resource, Subject};
+    use silver_telegram_core::{WorkflowId, WorkflowRunId};
+    use silver_telegram_workflow::{DryRunConfig, NodeId};
+    use std::collections::HashMap;
+    use std::str::FromStr;
+
+    let auth = get_authenticated_session().await.map_err(|e| {
+        tracing::debug!(error = %e, "Authentication failed for start_dry_run");
+        e.into_server_error()
+    })?;
+
+    let wf_id = WorkflowId::from_str(&workflow_id).map_err(|e| {
+        tracing::debug!(
+            workflow_id = %workflow_id,
+            error = %e,
+            "Invalid workflow ID format"
+        );
+        WorkflowError::InvalidId {
+            id: workflow_id.clone(),
+            reason: e.to_string(),
+        }
+        .into_server_error()
+    })?;
+
+    let source_run_id = record_from_run
+        .filter(|id| !id.trim().is_empty())
+        .map(|id| {
+            WorkflowRunId::from_str(id.trim()).map_err(|e| {
+                tracing::debug!(
+                    run_id = %id,
+                    error = %e,
+                    "Invalid run ID format"
+                );
+                WorkflowRunError::InvalidId {
+                    id: id.clone(),
+                    reason: e.to_string(),
+                }
+                .into_server_error()
+            })
+        })
+        .transpose()?;
+
+    let invalid_fixtures = |details: String| {
+        tracing::debug!(details = %details, "Invalid dry run fixtures");
+        EngineError::InvalidFixtures { details }.into_server_error()
+    };
+    let input = input
+        .filter(|input| !input.trim().is_empty())
+        .map(|input| serde_json::from_str::<serde_json::Value>(&input))
+        .transpose()
+        .map_err(|e| invalid_fixtures(format!("input is not valid JSON: {e}")))?;
+    let supplied: HashMap<String, serde_json::Value> = match fixtures {
+        Some(fixtures) if !fixtures.trim().is_empty() => serde_json::from_str(&fixtures)
+            .map_err(|e| invalid_fixtures(format!("fixtures are not a JSON object: {e}")))?,
+        _ => HashMap::new(),
+    };
+    let supplied = supplied
+        .into_iter()
+        .map(|(node_id, response)| {
+            NodeId::from_str(&node_id)
+                .map(|node_id| (node_id, response))
+                .map_err(|e| invalid_fixtures(format!("invalid node ID '{node_id}': {e}")))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // A dry run starts a run, so it needs execute permission
+    let authz_client = get_authz_client();
+    let resource = Resource::workflow(wf_id);
+    let subject = Subject::user(auth.user_id);
+    authz_client
+        .require_permission(&resource, Permission::Execute, &subject)
+        .await
+        .map_err(|e| {
+            tracing::warn!(
+                workflow_id = %wf_id,
+                user_id = %auth.user_id,
+                error = %e,
+                "Access denied to execute workflow"
+            );
+            WorkflowError::AccessDenied {
+                id: wf_id.to_string(),
+            }
+            .into_server_error()
+        })?;
+
+    let db_pool = get_db_pool();
+    let run_repo = WorkflowRunRepository::new(db_pool.clone());
+    let workflow_repo = WorkflowRepository::new(db_pool);
+    let database_error = |e: sqlx::Error| {
+        tracing::error!(
+            error = %e,
+            workflow_id = %wf_id,
+            "Database error starting dry run"
+        );
+        WorkflowRunError::DatabaseError {
+            details: e.to_string(),
+        }
+        .into_server_error()
+    };
+    let workflow = workflow_repo
+        .find_by_id(wf_id)
+        .await
+        .map_err(database_error)?
+        .ok_or_else(|| {
+            tracing::debug!(workflow_id = %wf_id, "Workflow not found");
+            WorkflowError::NotFound {
+                id: wf_id.to_string(),
+            }
+            .into_server_error()
+        })?;
+
+    let Extension(engine): Extension<Option<WorkflowEngine>> = leptos_axum::extract().await?;
+    let engine = engine.ok_or_else(|| {
+        EngineError::ConnectionFailed {
+            details: "workflow engine not configured".to_string(),
+        }
+        .into_server_error()
+    })?;
+
+    // Recorded fixtures come from the outputs of the version the source run
+    // executed
+    let mut dry_run = DryRunConfig::new();
+    if stub_llm {
+        dry_run = dry_run.with_stub_llm();
+    }
+    if let Some(source_run_id) = source_run_id {
+        let source_run = run_repo
+            .find_by_id(source_run_id)
+            .await
+            .map_err(database_error)?
+            .filter(|r| r.workflow_id == wf_id)
+            .ok_or_else(|| {
+                tracing::debug!(run_id = %source_run_id, "Workflow run not found");
+                WorkflowRunError::NotFound {
+                    id: source_run_id.to_string(),
+                }
+                .into_server_error()
+            })?;
+        if source_run.outputs_expired_at.is_some() {
+            return Err(EngineError::OutputsExpired {
+                details: format!("run {source_run_id} has no outputs to record fixtures from"),
+            }
+            .into_server_error());
+        }
+        let source_workflow = workflow_repo
+            .find_for_run(&source_run)
+            .await
+            .map_err(database_error)?
+            .ok_or_else(|| {
+                WorkflowError::NotFound {
+                    id: wf_id.to_string(),
+                }
+                .into_server_error()
+            })?;
+        dry_run = engine
+            .recorded_fixtures(&source_workflow, source_run_id, stub_llm)
+            .await
+            .map_err(|e| {
+                tracing::warn!(
+                    run_id = %source_run_id,
+                    error = %e,
+                    "Failed to record dry run fixtures"
+                );
+                e.into_server_error()
+            })?;
+    }
+    for (node_id, response) in supplied {
+        dry_run = dry_run.with_fixture(node_id, response);
+    }
+
+    let mut run = WorkflowRunRecord::new(wf_id, None, input)
+        .with_workflow_version(workflow.version)
+        .as_dry_run();
+    run.start();
+    run_repo.create(&run).await.map_err(database_error)?;
+
+    if let Err(e) = engine.start_dry_run(&workflow, &run, dry_run).await {
+        tracing::warn!(
+            run_id = %run.id,
+            error = %e,
+            "Failed to start dry run in the workflow engine"
+        );
+        run.fail(e.to_string());
+        run_repo.update(&run).await.map_err(database_error)?;
+        return Err(e.into_server_error());
+    }
+
+    tracing::info!(
+        workflow_id = %wf_id,
+        run_id = %run.id,
+        user_id = %auth.user_id,
+        "Dry run started"
+    );
+
+    Ok(run.id.to_string())
+}
+
 /// History tab component displaying workflow runs and run details.
 #[component]
 pub fn HistoryTab(workflow_id: Signal<Option<String>>) -> impl IntoView {
     let (selected_run_id, set_selected_run_id) = signal(Option::<String>::None);
+    let (refresh, set_refresh) = signal(0u32);
 
     // Runs resource
     let runs = Resource::new(
-        move || workflow_id.get(),
-        |id| async move {
+        move || (workflow_id.get(), refresh.get()),
+        |(id, _)| async move {
             match id {
                 Some(id) => list_workflow_runs(id).await.ok().unwrap_or_default(),
                 None => vec![],
@@ -290,11 +880,21 @@ pub fn HistoryTab(workflow_id: Signal<Option<String>>) -> impl IntoView {
         },
     );
 
+    // Erasing the view type keeps the editor page's view within the
+    // compiler's query depth limit
     view! {
         <div class="history-content">
             <div class="history-layout">
                 <div class="runs-list">
                     <h3>"Execution History"</h3>
+                    <DryRunForm
+                        workflow_id=workflow_id
+                        selected_run_id=selected_run_id
+                        on_started=move |run_id: String| {
+                            set_refresh.update(|n| *n += 1);
+                            set_selected_run_id.set(Some(run_id));
+                        }
+                    />
                     <Suspense fallback=move || view! { <p>"Loading runs..."</p> }>
                         {move || {
                             let runs_list = runs.get().unwrap_or_default();
@@ -327,11 +927,20 @@ pub fn HistoryTab(workflow_id: Signal<Option<String>>) -> impl IntoView {
                                                             set_selected_run_id.set(Some(run_id_for_click.clone()));
                                                         }
                                                     >
-                                                        <td class=status_class>{run.state}</td>
+                                                        <td class=status_class>
+                                                            {run.state}
+                                                            {run.dry_run.then(|| view! {
+                                                                <span class="dry-run-badge">"dry run"</span>
+                                                            })}
+                                                            {run.outputs_expired.then(|| view! {
+                                                                <span class="outputs-expired-badge">"outputs expired"</span>
+                                                            })}
+                                                        </td>
                                                         <td>{started}</td>
                                                         <td>{duration}</td>
                                                         <td class="error-cell">
                                                             {run.error_message.map(|e| view! { <span class="error">{e}</span> })}
+                                                            {run.skip_reason.map(|r| view! { <span class="skip-reason">{r}</span> })}
                                                         </td>
                                                     </tr>
                                                 }
@@ -360,7 +969,17 @@ pub fn HistoryTab(workflow_id: Signal<Option<String>>) -> impl IntoView {
                             {move || run_detail.get().map(|detail_opt| {
                                 match detail_opt {
                                     Some(detail) => {
-                                        view! { <RunDetailPanel detail=detail /> }.into_any()
+                                        let on_retried = move |run_id: String| {
+                                            set_refresh.update(|n| *n += 1);
+                                            set_selected_run_id.set(Some(run_id));
+                                        };
+                                        view! {
+                                            <RunDetailPanel
+                                                detail=detail
+                                                workflow_id=workflow_id
+                                                on_retried=on_retried
+                                            />
+                                        }.into_any()
                                     },
                                     None => view! {
                                         <p class="error">"Failed to load run details."</p>
@@ -373,20 +992,125 @@ pub fn HistoryTab(workflow_id: Signal<Option<String>>) -> impl IntoView {
             </div>
         </div>
     }
+    .into_any()
+}
+
+/// Form for starting a dry run of the workflow.
+///
+/// Fixtures can be recorded from the selected run. `on_started` is called
+/// with the ID of the dry run.
+#[component]
+fn DryRunForm(
+    workflow_id: Signal<Option<String>>,
+    selected_run_id: ReadSignal<Option<String>>,
+    on_started: impl Fn(String) + Copy + Send + Sync + 'static,
+) -> impl IntoView {
+    let (input, set_input) = signal(String::new());
+    let (fixtures, set_fixtures) = signal(String::new());
+    let (stub_llm, set_stub_llm) = signal(false);
+    let (record, set_record) = signal(false);
+    let (error, set_error) = signal(Option::<String>::None);
+
+    let start = move |_| {
+        let Some(wf_id) = workflow_id.get() else {
+            return;
+        };
+        let record_from_run = if record.get() {
+            selected_run_id.get()
+        } else {
+            None
+        };
+        let input = Some(input.get());
+        let fixtures = Some(fixtures.get());
+        let stub_llm = stub_llm.get();
+        spawn_local(async move {
+            match start_dry_run(wf_id, input, fixtures, stub_llm, record_from_run).await {
+                Ok(run_id) => {
+                    set_error.set(None);
+                    on_started(run_id);
+                }
+                Err(e) => set_error.set(Some(e.to_string())),
+            }
+        });
+    };
+
+    view! {
+        <details class="dry-run-form">
+            <summary>"Dry Run"</summary>
+            <p>"Runs the workflow without sending anything: integration writes respond with fixtures instead."</p>
+            <label>
+                "Input (JSON)"
+                <textarea
+                    prop:value=input
+                    on:input=move |ev| set_input.set(event_target_value(&ev))
+                />
+            </label>
+            <label>
+                "Fixtures (JSON object keyed by node ID)"
+                <textarea
+                    prop:value=fixtures
+                    on:input=move |ev| set_fixtures.set(event_target_value(&ev))
+                />
+            </label>
+            <label>
+                <input
+                    type="checkbox"
+                    prop:checked=stub_llm
+                    on:change=move |ev| set_stub_llm.set(event_target_checked(&ev))
+                />
+                " Stub LLM calls"
+            </label>
+            <label>
+                <input
+                    type="checkbox"
+                    prop:checked=record
+                    disabled=move || selected_run_id.get().is_none()
+                    on:change=move |ev| set_record.set(event_target_checked(&ev))
+                />
+                " Record fixtures from the selected run"
+            </label>
+            {move || error.get().map(|e| view! { <p class="error">{e}</p> })}
+            <button class="primary-btn" on:click=start>"Start Dry Run"</button>
+        </details>
+    }
 }
 
 /// Run detail panel component showing execution information.
+///
+/// `on_retried` is called with the ID of the run that retries this run.
 #[component]
-fn RunDetailPanel(detail: RunDetailView) -> impl IntoView {
+fn RunDetailPanel(
+    detail: RunDetailView,
+    workflow_id: Signal<Option<String>>,
+    on_retried: impl Fn(String) + Copy + Send + Sync + 'static,
+) -> impl IntoView {
+    let (retry_error, set_retry_error) = signal(Option::<String>::None);
     let run_state = detail.state.clone();
     let duration = detail
         .duration_ms
         .map(|ms| format!("{}ms", ms))
         .unwrap_or_else(|| "-".to_string());
     let status_class = format!("status-{}", detail.state);
+    let version = detail
+        .workflow_version
+        .map(|v| format!("v{}", v))
+        .unwrap_or_else(|| "-".to_string());
+    let parent_run = detail.parent_run_id.clone();
+    let retry_of = detail.retry_of_run_id.clone().map(|id| {
+        let from = detail.retry_from_node_id.clone().unwrap_or_default();
+        (id, from)
+    });
+    let retried_by = detail.retried_by;
+    let dry_run = detail.dry_run;
+    let skip_reason = detail.skip_reason;
+    let outputs_expired_at = detail.outputs_expired_at;
+    let substitutions = detail.substitutions;
+    let failed_nodes = detail.failed_nodes;
+    let run_id = detail.id.clone();
     let run_error = detail.error_message.clone();
     let node_execs = detail.node_executions;
     let has_nodes = !node_execs.is_empty();
+    let delays = detail.delays;
     let run_input = detail
         .input_data
         .map(|d| serde_json::to_string_pretty(&d).unwrap_or_default());
@@ -397,13 +1121,124 @@ fn RunDetailPanel(detail: RunDetailView) -> impl IntoView {
     view! {
         <div class="run-detail-content">
             <div class="run-summary">
-                <p><strong>"Status:"</strong>" "<span class=status_class>{run_state}</span></p>
+                <p>
+                    <strong>"Status:"</strong>" "<span class=status_class>{run_state}</span>
+                    {dry_run.then(|| view! { <span class="dry-run-badge">"dry run"</span> })}
+                </p>
+                <p><strong>"Version:"</strong>" "{version}</p>
+                {skip_reason.map(|reason| view! {
+                    <p><strong>"Skipped:"</strong>" "<span class="skip-reason">{reason}</span></p>
+                })}
+                {outputs_expired_at.map(|at| view! {
+                    <p>
+                        <strong>"Outputs:"</strong>" "
+                        <span class="outputs-expired-badge">"outputs expired"</span>" "{at}
+                    </p>
+                })}
+                {parent_run.map(|id| view! {
+                    <p><strong>"Started by run:"</strong>" "<code>{id}</code></p>
+                })}
+                {retry_of.map(|(id, from)| view! {
+                    <p>
+                        <strong>"Retry of run:"</strong>" "<code>{id}</code>
+                        " from node "<code>{from}</code>
+                    </p>
+                })}
+                {(!retried_by.is_empty()).then(|| view! {
+                    <p>
+                        <strong>"Retried by:"</strong>" "
+                        {retried_by.into_iter().map(|id| view! {
+                            <button class="link-btn" on:click=move |_| on_retried(id.clone())>
+                                <code>{id.clone()}</code>
+                            </button>
+                        }).collect_view()}
+                    </p>
+                })}
                 <p><strong>"Duration:"</strong>" "{duration}</p>
                 {run_error.map(|e| view! {
                     <p class="run-error"><strong>"Error:"</strong>" "{e}</p>
                 })}
             </div>
 
+            {(!failed_nodes.is_empty()).then(|| view! {
+                <h4>"Failed Nodes"</h4>
+                <p>"Retrying from a node reuses the outputs of the nodes before it."</p>
+                {move || retry_error.get().map(|e| view! { <p class="error">{e}</p> })}
+                <div class="failed-nodes">
+                    {failed_nodes.into_iter().map(|node| {
+                        let run_id = run_id.clone();
+                        let node_id = node.node_id.clone();
+                        let retry = move |_| {
+                            let Some(wf_id) = workflow_id.get() else {
+                                return;
+                            };
+                            let run_id = run_id.clone();
+                            let node_id = node_id.clone();
+                            spawn_local(async move {
+                                match retry_run_from_node(wf_id, run_id, node_id).await {
+                                    Ok(retry_run_id) => {
+                                        set_retry_error.set(None);
+                                        on_retried(retry_run_id);
+                                    }
+                                    Err(e) => set_retry_error.set(Some(e.to_string())),
+                                }
+                            });
+                        };
+                        view! {
+                            <div class="failed-node">
+                                <span class="node-name">{node.name}</span>
+                                <span class="node-id">{node.node_id}</span>
+                                {node.error.map(|e| view! {
+                                    <div class="node-error">
+                                        <strong>"Error:"</strong>" "{e}
+                                    </div>
+                                })}
+                                <button class="retry-btn" on:click=retry>
+                                    "Retry from here"
+                                </button>
+                            </div>
+                        }
+                    }).collect_view()}
+                </div>
+            })}
+
+            {dry_run.then(|| view! {
+                <h4>"Substituted Nodes"</h4>
+                <p>"This dry run did not execute these nodes. It shows what each would have sent and the response it used instead."</p>
+                {if substitutions.is_empty() {
+                    view! {
+                        <p class="empty-state">"No nodes were substituted."</p>
+                    }.into_any()
+                } else {
+                    view! {
+                        <div class="substitutions">
+                            {substitutions.into_iter().map(|sub| {
+                                let request = serde_json::to_string_pretty(&sub.request)
+                                    .unwrap_or_default();
+                                let response = serde_json::to_string_pretty(&sub.response)
+                                    .unwrap_or_default();
+                                view! {
+                                    <div class="substitution">
+                                        <span class="node-id">{sub.node_id}</span>
+                                        {sub.item_index.map(|i| view! {
+                                            <span class="node-item">"item "{i}</span>
+                                        })}
+                                        <details class="node-data">
+                                            <summary>"Would have sent"</summary>
+                                            <pre>{request}</pre>
+                                        </details>
+                                        <details class="node-data">
+                                            <summary>"Response"</summary>
+                                            <pre>{response}</pre>
+                                        </details>
+                                    </div>
+                                }
+                            }).collect_view()}
+                        </div>
+                    }.into_any()
+                }}
+            })}
+
             <h4>"Node Executions"</h4>
             {if !has_nodes {
                 view! {
@@ -419,6 +1254,27 @@ fn RunDetailPanel(detail: RunDetailView) -> impl IntoView {
                 }.into_any()
             }}
 
+            {(!delays.is_empty()).then(|| view! {
+                <h4>"Delays"</h4>
+                <div class="delays">
+                    {delays.into_iter().map(|delay| {
+                        let status = if delay.ended_at.is_some() { "ended" } else { "waiting" };
+                        view! {
+                            <div class="delay">
+                                <span class="node-id">{delay.node_id}</span>
+                                {delay.item_index.map(|i| view! {
+                                    <span class="node-item">"item "{i}</span>
+                                })}
+                                <span class=format!("status-{status}")>{status}</span>
+                                <p>"Started: "{delay.started_at}</p>
+                                <p>"Ends: "{delay.ends_at}</p>
+                                {delay.ended_at.map(|at| view! { <p>"Ended: "{at}</p> })}
+                            </div>
+                        }
+                    }).collect_view()}
+                </div>
+            })}
+
             // Input/Output data for the run
             {run_input.map(|data| view! {
                 <details class="run-data">
@@ -440,6 +1296,7 @@ fn RunDetailPanel(detail: RunDetailView) -> impl IntoView {
 #[component]
 fn NodeExecutionItem(exec: NodeExecutionSummary) -> impl IntoView {
     let node_id = exec.node_id;
+    let item_index = exec.item_index;
     let node_state = exec.state.clone();
     let node_status_class = format!("status-{}", exec.state);
     let node_duration = exec
@@ -456,6 +1313,9 @@ fn NodeExecutionItem(exec: NodeExecutionSummary) -> impl IntoView {
         <div class="node-execution">
             <div class="node-exec-header">
                 <span class="node-id">{node_id}</span>
+                {item_index.map(|i| view! {
+                    <span class="node-item">"item "{i}</span>
+                })}
                 <span class=node_status_class>{node_state}</span>
                 <span class="node-duration">{node_duration}</span>
             </div>
@@ -0,0 +1,502 @@
+//! Workflow lint types, server functions, and UI components.
+//!
+//! The editor lints the graph as it changes and shows every finding next to
+//! the canvas, so problems are visible before the workflow is saved or
+//! enabled. Graphs in the engine's format go through the workflow crate's
+//! linter; graphs drawn in the editor get the checks that apply to them.
+
+use super::graph::WorkflowGraph;
+use leptos::prelude::*;
+
+/// A lint finding for display in the editor.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct GraphDiagnostic {
+    /// "error" or "warning".
+    pub severity: String,
+    /// The check that produced the finding (e.g. "missing_model").
+    pub rule: String,
+    pub node_id: Option<String>,
+    pub message: String,
+}
+
+impl GraphDiagnostic {
+    /// Returns true if the finding is an error.
+    #[must_use]
+    pub fn is_error(&self) -> bool {
+        self.severity == "error"
+    }
+}
+
+/// Returns the parts of a graph the linter looks at.
+///
+/// Moving nodes around does not change the key, so dragging a node does
+/// not lint the graph again.
+#[must_use]
+pub fn lint_key(graph: &WorkflowGraph) -> String {
+    let mut graph = graph.clone();
+    for node in &mut graph.nodes {
+        node.x = 0.0;
+        node.y = 0.0;
+    }
+    serde_json::to_string(&graph).unwrap_or_default()
+}
+
+/// Server function to lint a workflow graph.
+#[server]
+pub async fn lint_workflow_graph(
+    workflow_id: String,
+    graph_json: String,
+) -> Result<Vec<GraphDiagnostic>, ServerFnError> {
+    use crate::db::IntegrationAccountRepository;
+    use crate::error::WorkflowError;
+    use crate::server_helpers::{get_authenticated_session, get_authz_client, get_db_pool};
+    use silver_telegram_authz::{Permission, Resource, ResourceType, Subject};
+    use silver_telegram_core::{IntegrationAccountId, WorkflowId};
+    use std::str::FromStr;
+
+    let auth = get_authenticated_session().await.map_err(|e| {
+        tracing::debug!(error = %e, "Authentication failed for lint_workflow_graph");
+        e.into_server_error()
+    })?;
+
+    let wf_id = WorkflowId::from_str(&workflow_id).map_err(|e| {
+        tracing::debug!(
+            workflow_id = %workflow_id,
+            error = %e,
+            "Invalid workflow ID format"
+        );
+        WorkflowError::InvalidId {
+            id: workflow_id.clone(),
+            reason: e.to_string(),
+        }
+        .into_server_error()
+    })?;
+
+    // Check view permission via SpiceDB
+    let authz_client = get_authz_client();
+    let resource = Resource::workflow(wf_id);
+    let subject = Subject::user(auth.user_id);
+    authz_client
+        .require_permission(&resource, Permission::View, &subject)
+        .await
+        .map_err(|e| {
+            tracing::warn!(
+                workflow_id = %wf_id,
+                user_id = %auth.user_id,
+                error = %e,
+                "Access denied to workflow"
+            );
+            WorkflowError::AccessDenied {
+                id: wf_id.to_string(),
+            }
+            .into_server_error()
+        })?;
+
+    let graph: serde_json::Value = serde_json::from_str(&graph_json).map_err(|e| {
+        WorkflowError::InvalidGraph {
+            details: e.to_string(),
+        }
+        .into_server_error()
+    })?;
+
+    // The integration accounts the user can use; references to any other
+    // account are reported
+    let integration_ids = authz_client
+        .lookup_resources(ResourceType::Integration, Permission::View, &subject)
+        .await
+        .map_err(|e| {
+            tracing::error!(
+                error = %e,
+                user_id = %auth.user_id,
+                "Failed to lookup accessible integrations from SpiceDB"
+            );
+            WorkflowError::DatabaseError {
+                details: e.to_string(),
+            }
+            .into_server_error()
+        })?;
+    let integration_ids: Vec<IntegrationAccountId> = integration_ids
+        .iter()
+        .filter_map(|id| IntegrationAccountId::from_str(id).ok())
+        .collect();
+    let integrations = IntegrationAccountRepository::new(get_db_pool())
+        .list_by_ids(&integration_ids)
+        .await
+        .map_err(|e| {
+            tracing::error!(
+                error = %e,
+                user_id = %auth.user_id,
+                "Failed to load integrations for linting"
+            );
+            WorkflowError::DatabaseError {
+                details: e.to_string(),
+            }
+            .into_server_error()
+        })?;
+    let context = ServerLintContext {
+        integrations: integrations.iter().map(|i| i.id.to_string()).collect(),
+    };
+
+    if let Ok(mut engine_graph) =
+        serde_json::from_value::<silver_telegram_workflow::WorkflowGraph>(graph.clone())
+    {
+        engine_graph.rebuild_index_map();
+        return Ok(silver_telegram_workflow::lint(&engine_graph, &context)
+            .into_iter()
+            .map(|d| {
+                diagnostic(
+                    d.severity,
+                    d.rule,
+                    d.node_id.map(|id| id.to_string()),
+                    d.message,
+                )
+            })
+            .collect());
+    }
+
+    let editor_graph: WorkflowGraph = serde_json::from_value(graph).map_err(|e| {
+        WorkflowError::InvalidGraph {
+            details: e.to_string(),
+        }
+        .into_server_error()
+    })?;
+    Ok(lint_editor_graph(&editor_graph, &context))
+}
+
+/// What the server knows about the world outside the graph.
+#[cfg(feature = "ssr")]
+struct ServerLintContext {
+    integrations: std::collections::HashSet<String>,
+}
+
+#[cfg(feature = "ssr")]
+impl silver_telegram_workflow::LintContext for ServerLintContext {
+    fn integration_exists(&self, integration_id: &str) -> bool {
+        self.integrations.contains(integration_id)
+    }
+
+    fn check_schedule(&self, cron: &str, timezone: Option<&str>) -> Result<(), String> {
+        let mut schedule = silver_telegram_scheduler::CronSchedule::new(cron);
+        if let Some(timezone) = timezone {
+            schedule = schedule.with_timezone(timezone);
+        }
+        schedule.validate().map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(feature = "ssr")]
+fn diagnostic(
+    severity: silver_telegram_workflow::Severity,
+    rule: silver_telegram_workflow::LintRule,
+    node_id: Option<String>,
+    message: String,
+) -> GraphDiagnostic {
+    GraphDiagnostic {
+        severity: severity.to_string(),
+        rule: serde_json::to_value(rule)
+            .ok()
+            .and_then(|rule| rule.as_str().map(str::to_string))
+            .unwrap_or_default(),
+        node_id,
+        message,
+    }
+}
+
+/// Lints a graph drawn in the editor.
+///
+/// Editor graphs have triggers, models, AI nodes, tools, and data sources.
+/// Models, tools, and data sources feed AI nodes, so they need no trigger
+/// but do need an outgoing edge.
+#[cfg(feature = "ssr")]
+fn lint_editor_graph(
+    graph: &WorkflowGraph,
+    context: &impl silver_telegram_workflow::LintContext,
+) -> Vec<GraphDiagnostic> {
+    use silver_telegram_workflow::{LintRule, Severity};
+    use std::collections::{HashSet, VecDeque};
+
+    let mut diagnostics = Vec::new();
+    let config = |config: &str, key: &str| {
+        serde_json::from_str::<serde_json::Value>(config)
+            .ok()
+            .and_then(|c| c.get(key).and_then(|v| v.as_str()).map(str::to_string))
+            .unwrap_or_default()
+    };
+    let node_type = |id: &str| {
+        graph
+            .nodes
+            .iter()
+            .find(|n| n.id == id)
+            .map(|n| n.node_type.as_str())
+    };
+
+    let has_trigger = graph.nodes.iter().any(|n| n.node_type == "trigger");
+    if !has_trigger {
+        diagnostics.push(diagnostic(
+            Severity::Error,
+            LintRule::NoTrigger,
+            None,
+            "the workflow has no trigger, so it never runs; add a trigger node".to_string(),
+        ));
+    }
+
+    let mut reached: HashSet<&str> = HashSet::new();
+    let mut queue: VecDeque<&str> = graph
+        .nodes
+        .iter()
+        .filter(|n| n.node_type == "trigger")
+        .map(|n| n.id.as_str())
+        .collect();
+    while let Some(id) = queue.pop_front() {
+        if reached.insert(id) {
+            queue.extend(
+                graph
+                    .edges
+                    .iter()
+                    .filter(|e| e.source == id)
+                    .map(|e| e.target.as_str()),
+            );
+        }
+    }
+
+    for node in &graph.nodes {
+        let id = Some(node.id.clone());
+        let label = &node.label;
+        match node.node_type.as_str() {
+            "trigger" => {
+                let cron = config(&node.config, "cron");
+                let timezone = config(&node.config, "timezone");
+                let timezone = (!timezone.is_empty()).then_some(timezone.as_str());
+                let problem = if cron.trim().is_empty() {
+                    Some("no schedule is set".to_string())
+                } else {
+                    context.check_schedule(cron.trim(), timezone).err()
+                };
+                if let Some(problem) = problem {
+                    diagnostics.push(diagnostic(
+                        Severity::Error,
+                        LintRule::InvalidSchedule,
+                        id,
+                        format!("'{label}' has an invalid schedule: {problem}"),
+                    ));
+                }
+            }
+            "ai" => {
+                let has_model = graph
+                    .edges
+                    .iter()
+                    .any(|e| e.target == node.id && node_type(&e.source) == Some("model"));
+                if !has_model {
+                    diagnostics.push(diagnostic(
+                        Severity::Error,
+                        LintRule::MissingModel,
+                        id.clone(),
+                        format!("'{label}' has no model connected; connect a model node to it"),
+                    ));
+                }
+                if has_trigger && !reached.contains(node.id.as_str()) {
+                    diagnostics.push(diagnostic(
+                        Severity::Warning,
+                        LintRule::UnreachableNode,
+                        id,
+                        format!("'{label}' is not connected to a trigger, so it never runs"),
+                    ));
+                }
+            }
+            "model" | "tool" | "data" => {
+                let key = match node.node_type.as_str() {
+                    "data" => "source",
+                    _ => "integration_id",
+                };
+                let integration_id = config(&node.config, key);
+                if !integration_id.is_empty()
+                    && integration_id != "__workflow_memory__"
+                    && !context.integration_exists(&integration_id)
+                {
+                    diagnostics.push(diagnostic(
+                        Severity::Error,
+                        LintRule::UnknownIntegration,
+                        id.clone(),
+                        format!(
+                            "'{label}' uses an integration account that no longer exists; \
+                             select another one"
+                        ),
+                    ));
+                }
+                if !graph.edges.iter().any(|e| e.source == node.id) {
+                    diagnostics.push(diagnostic(
+                        Severity::Warning,
+                        LintRule::UnreachableNode,
+                        id,
+                        format!("'{label}' is not connected to any node, so it has no effect"),
+                    ));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Stable, so nodes keep their order within a severity
+    diagnostics.sort_by_key(|d| !d.is_error());
+    diagnostics
+}
+
+/// Panel listing lint findings; clicking one selects its node.
+#[component]
+pub fn DiagnosticsPanel(
+    diagnostics: Signal<Vec<GraphDiagnostic>>,
+    set_selected_node_id: WriteSignal<Option<String>>,
+) -> impl IntoView {
+    view! {
+        {move || {
+            let items = diagnostics.get();
+            (!items.is_empty()).then(|| {
+                let errors = items.iter().filter(|d| d.is_error()).count();
+                let warnings = items.len() - errors;
+                view! {
+                    <div class="diagnostics-panel">
+                        <p class="diagnostics-summary">
+                            {format!("{errors} error(s), {warnings} warning(s)")}
+                        </p>
+                        <ul>
+                            {items.into_iter().map(|d| {
+                                let node_id = d.node_id.clone();
+                                let class = format!("diagnostic diagnostic-{}", d.severity);
+                                view! {
+                                    <li
+                                        class=class
+                                        on:click=move |_| {
+                                            if let Some(id) = node_id.clone() {
+                                                set_selected_node_id.set(Some(id));
+                                            }
+                                        }
+                                    >
+                                        <strong>{d.severity}</strong>" "{d.message}
+                                    </li>
+                                }
+                            }).collect_view()}
+                        </ul>
+                    </div>
+                }
+            })
+        }}
+    }
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+    use super::*;
+    use crate::pages::workflow_editor::{WorkflowEdge, WorkflowNode};
+
+    struct TestContext;
+
+    impl silver_telegram_workflow::LintContext for TestContext {
+        fn integration_exists(&self, integration_id: &str) -> bool {
+            integration_id == "known"
+        }
+
+        fn check_schedule(&self, cron: &str, _timezone: Option<&str>) -> Result<(), String> {
+            (cron == "0 9 * * *")
+                .then_some(())
+                .ok_or_else(|| "bad cron".to_string())
+        }
+    }
+
+    fn node(id: &str, node_type: &str, config: serde_json::Value) -> WorkflowNode {
+        WorkflowNode {
+            id: id.to_string(),
+            node_type: node_type.to_string(),
+            label: id.to_string(),
+            config: config.to_string(),
+            x: 0.0,
+            y: 0.0,
+        }
+    }
+
+    fn edge(source: &str, target: &str) -> WorkflowEdge {
+        WorkflowEdge {
+            id: format!("{source}-{target}"),
+            source: source.to_string(),
+            target: target.to_string(),
+            source_port: "output".to_string(),
+            target_port: "input".to_string(),
+        }
+    }
+
+    fn found(diagnostics: &[GraphDiagnostic]) -> Vec<(&str, &str, Option<&str>)> {
+        diagnostics
+            .iter()
+            .map(|d| (d.severity.as_str(), d.rule.as_str(), d.node_id.as_deref()))
+            .collect()
+    }
+
+    #[test]
+    fn connected_editor_graph_is_clean() {
+        let graph = WorkflowGraph {
+            nodes: vec![
+                node(
+                    "trigger",
+                    "trigger",
+                    serde_json::json!({"cron": "0 9 * * *"}),
+                ),
+                node(
+                    "model",
+                    "model",
+                    serde_json::json!({"integration_id": "known"}),
+                ),
+                node("ai", "ai", serde_json::json!({"prompt": "Summarize"})),
+                node(
+                    "memory",
+                    "tool",
+                    serde_json::json!({"integration_id": "__workflow_memory__"}),
+                ),
+            ],
+            edges: vec![
+                edge("trigger", "ai"),
+                edge("model", "ai"),
+                edge("memory", "ai"),
+            ],
+        };
+
+        assert_eq!(lint_editor_graph(&graph, &TestContext), vec![]);
+    }
+
+    #[test]
+    fn editor_graph_findings_name_their_nodes() {
+        let graph = WorkflowGraph {
+            nodes: vec![
+                node("trigger", "trigger", serde_json::json!({"cron": "daily"})),
+                node(
+                    "model",
+                    "model",
+                    serde_json::json!({"integration_id": "deleted"}),
+                ),
+                node("ai", "ai", serde_json::json!({})),
+                node("data", "data", serde_json::json!({"source": "known"})),
+            ],
+            edges: vec![edge("model", "ai")],
+        };
+
+        assert_eq!(
+            found(&lint_editor_graph(&graph, &TestContext)),
+            vec![
+                ("error", "invalid_schedule", Some("trigger")),
+                ("error", "unknown_integration", Some("model")),
+                ("warning", "unreachable_node", Some("ai")),
+                ("warning", "unreachable_node", Some("data")),
+            ]
+        );
+
+        let no_trigger = WorkflowGraph {
+            nodes: vec![node("ai", "ai", serde_json::json!({}))],
+            edges: vec![],
+        };
+        assert_eq!(
+            found(&lint_editor_graph(&no_trigger, &TestContext)),
+            vec![
+                ("error", "no_trigger", None),
+                ("error", "missing_model", Some("ai")),
+            ]
+        );
+    }
+}
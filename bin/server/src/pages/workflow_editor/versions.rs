@@ -0,0 +1,592 @@
+//! Workflow version types, server functions, and UI components.
+//!
+//! Every saved graph is an immutable numbered version. The versions tab
+//! lists them, diffs two of them, and rolls back by saving an earlier
+//! version's graph as a new version.
+
+use super::graph::WorkflowGraph;
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use std::collections::HashMap;
+
+/// Workflow version summary for the versions list.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct WorkflowVersionSummary {
+    pub version: i32,
+    pub created_at: String,
+    pub node_count: usize,
+    pub edge_count: usize,
+    pub current: bool,
+}
+
+/// Differences between two versions of a workflow graph.
+///
+/// Nodes are listed by label. Moving a node on the canvas is not a change.
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct GraphDiff {
+    pub added_nodes: Vec<String>,
+    pub removed_nodes: Vec<String>,
+    pub changed_nodes: Vec<String>,
+    pub added_edges: Vec<String>,
+    pub removed_edges: Vec<String>,
+}
+
+impl GraphDiff {
+    /// Returns true if the graphs are the same.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.added_nodes.is_empty()
+            && self.removed_nodes.is_empty()
+            && self.changed_nodes.is_empty()
+            && self.added_edges.is_empty()
+            && self.removed_edges.is_empty()
+    }
+}
+
+/// Computes the differences from one graph to another.
+#[must_use]
+pub fn diff_graphs(from: &WorkflowGraph, to: &WorkflowGraph) -> GraphDiff {
+    let from_nodes: HashMap<&str, _> = from.nodes.iter().map(|n| (n.id.as_str(), n)).collect();
+    let to_nodes: HashMap<&str, _> = to.nodes.iter().map(|n| (n.id.as_str(), n)).collect();
+
+    let mut diff = GraphDiff::default();
+    for node in &to.nodes {
+        match from_nodes.get(node.id.as_str()) {
+            None => diff.added_nodes.push(node.label.clone()),
+            Some(old) => {
+                if old.node_type != node.node_type
+                    || old.label != node.label
+                    || old.config != node.config
+                {
+                    diff.changed_nodes.push(node.label.clone());
+                }
+            }
+        }
+    }
+    for node in &from.nodes {
+        if !to_nodes.contains_key(node.id.as_str()) {
+            diff.removed_nodes.push(node.label.clone());
+        }
+    }
+
+    // Edges are identified by what they connect, so re-drawing an edge
+    // between the same ports is not a change
+    let describe = |graph: &WorkflowGraph| -> Vec<String> {
+        let labels: HashMap<&str, &str> = graph
+            .nodes
+            .iter()
+            .map(|n| (n.id.as_str(), n.label.as_str()))
+            .collect();
+        let label = |id: &str| labels.get(id).copied().unwrap_or(id).to_string();
+        graph
+            .edges
+            .iter()
+            .map(|e| {
+                format!(
+                    "{}.{} → {}.{}",
+                    label(&e.source),
+                    e.source_port,
+                    label(&e.target),
+                    e.target_port
+                )
+            })
+            .collect()
+    };
+    let from_edges = describe(from);
+    let to_edges = describe(to);
+    diff.added_edges = to_edges
+        .iter()
+        .filter(|e| !from_edges.contains(e))
+        .cloned()
+        .collect();
+    diff.removed_edges = from_edges
+        .iter()
+        .filter(|e| !to_edges.contains(e))
+        .cloned()
+        .collect();
+
+    diff
+}
+
+/// Server function to list the saved versions of a workflow.
+#[server]
+pub async fn list_workflow_versions(
+    workflow_id: String,
+) -> Result<Vec<WorkflowVersionSummary>, ServerFnError> {
+    use crate::db::WorkflowRepository;
+    use crate::error::WorkflowError;
+    use crate::server_helpers::{get_authenticated_session, get_authz_client, get_db_pool};
+    use silver_telegram_authz::{Permission, Resource, Subject};
+    use silver_telegram_core::WorkflowId;
+    use std::str::FromStr;
+
+    let auth = get_authenticated_session().await.map_err(|e| {
+        tracing::debug!(error = %e, "Authentication failed for list_workflow_versions");
+        e.into_server_error()
+    })?;
+
+    let wf_id = WorkflowId::from_str(&workflow_id).map_err(|e| {
+        tracing::debug!(
+            workflow_id = %workflow_id,
+            error = %e,
+            "Invalid workflow ID format"
+        );
+        WorkflowError::InvalidId {
+            id: workflow_id.clone(),
+            reason: e.to_string(),
+        }
+        .into_server_error()
+    })?;
+
+    // Check view permission via SpiceDB
+    let authz_client = get_authz_client();
+    let resource = Resource::workflow(wf_id);
+    let subject = Subject::user(auth.user_id);
+    authz_client
+        .require_permission(&resource, Permission::View, &subject)
+        .await
+        .map_err(|e| {
+            tracing::warn!(
+                workflow_id = %wf_id,
+                user_id = %auth.user_id,
+                error = %e,
+                "Access denied to workflow"
+            );
+            WorkflowError::AccessDenied {
+                id: wf_id.to_string(),
+            }
+            .into_server_error()
+        })?;
+
+    let workflow_repo = WorkflowRepository::new(get_db_pool());
+    let database_error = |e: sqlx::Error| {
+        tracing::error!(
+            error = %e,
+            workflow_id = %wf_id,
+            "Database error loading workflow versions"
+        );
+        WorkflowError::DatabaseError {
+            details: e.to_string(),
+        }
+        .into_server_error()
+    };
+    let current = workflow_repo
+        .find_by_id(wf_id)
+        .await
+        .map_err(database_error)?
+        .map(|w| w.version);
+    let versions = workflow_repo
+        .list_versions(wf_id)
+        .await
+        .map_err(database_error)?;
+
+    Ok(versions
+        .into_iter()
+        .map(|v| {
+            let graph: Option<WorkflowGraph> = serde_json::from_value(v.graph_data).ok();
+            WorkflowVersionSummary {
+                version: v.version,
+                created_at: v.created_at.to_rfc3339(),
+                node_count: graph.as_ref().map_or(0, |g| g.nodes.len()),
+                edge_count: graph.as_ref().map_or(0, |g| g.edges.len()),
+                current: current == Some(v.version),
+            }
+        })
+        .collect())
+}
+
+/// Server function to diff two versions of a workflow.
+#[server]
+pub async fn diff_workflow_versions(
+    workflow_id: String,
+    from_version: i32,
+    to_version: i32,
+) -> Result<GraphDiff, ServerFnError> {
+    use crate::db::WorkflowRepository;
+    use crate::error::WorkflowError;
+    use crate::server_helpers::{get_authenticated_session, get_authz_client, get_db_pool};
+    use silver_telegram_authz::{Permission, Resource, Subject};
+    use silver_telegram_core::WorkflowId;
+    use std::str::FromStr;
+
+    let auth = get_authenticated_session().await.map_err(|e| {
+        tracing::debug!(error = %e, "Authentication failed for diff_workflow_versions");
+        e.into_server_error()
+    })?;
+
+    let wf_id = WorkflowId::from_str(&workflow_id).map_err(|e| {
+        tracing::debug!(
+            workflow_id = %workflow_id,
+            error = %e,
+            "Invalid workflow ID format"
+        );
+        WorkflowError::InvalidId {
+            id: workflow_id.clone(),
+            reason: e.to_string(),
+        }
+        .into_server_error()
+    })?;
+
+    // Check view permission via SpiceDB
+    let authz_client = get_authz_client();
+    let resource = Resource::workflow(wf_id);
+    let subject = Subject::user(auth.user_id);
+    authz_client
+        .require_permission(&resource, Permission::View, &subject)
+        .await
+        .map_err(|e| {
+            tracing::warn!(
+                workflow_id = %wf_id,
+                user_id = %auth.user_id,
+                error = %e,
+                "Access denied to workflow"
+            );
+            WorkflowError::AccessDenied {
+                id: wf_id.to_string(),
+            }
+            .into_server_error()
+        })?;
+
+    let workflow_repo = WorkflowRepository::new(get_db_pool());
+    let mut graphs = Vec::with_capacity(2);
+    for version in [from_version, to_version] {
+        let record = workflow_repo
+            .find_version(wf_id, version)
+            .await
+            .map_err(|e| {
+                tracing::error!(
+                    error = %e,
+                    workflow_id = %wf_id,
+                    version = version,
+                    "Database error loading workflow version"
+                );
+                WorkflowError::DatabaseError {
+                    details: e.to_string(),
+                }
+                .into_server_error()
+            })?
+            .ok_or_else(|| {
+                WorkflowError::NotFound {
+                    id: format!("{wf_id} version {version}"),
+                }
+                .into_server_error()
+            })?;
+        let graph: WorkflowGraph = serde_json::from_value(record.graph_data).map_err(|e| {
+            WorkflowError::InvalidGraph {
+                details: format!("version {version}: {e}"),
+            }
+            .into_server_error()
+        })?;
+        graphs.push(graph);
+    }
+
+    Ok(diff_graphs(&graphs[0], &graphs[1]))
+}
+
+/// Server function to roll a workflow back to an earlier version.
+///
+/// The earlier version's graph is saved as a new version, so the history
+/// stays intact and runs of later versions keep their graph.
+#[server]
+pub async fn rollback_workflow_version(
+    workflow_id: String,
+    version: i32,
+) -> Result<(), ServerFnError> {
+    use super::graph::update_workflow_graph;
+    use crate::db::WorkflowRepository;
+    use crate::error::WorkflowError;
+    use crate::server_helpers::{get_authenticated_session, get_authz_client, get_db_pool};
+    use silver_telegram_authz::{Permission, Resource, Subject};
+    use silver_telegram_core::WorkflowId;
+    use std::str::FromStr;
+
+    let auth = get_authenticated_session().await.map_err(|e| {
+        tracing::debug!(error = %e, "Authentication failed for rollback_workflow_version");
+        e.into_server_error()
+    })?;
+
+    let wf_id = WorkflowId::from_str(&workflow_id).map_err(|e| {
+        tracing::debug!(
+            workflow_id = %workflow_id,
+            error = %e,
+            "Invalid workflow ID format"
+        );
+        WorkflowError::InvalidId {
+            id: workflow_id.clone(),
+            reason: e.to_string(),
+        }
+        .into_server_error()
+    })?;
+
+    // Check edit permission via SpiceDB
+    let authz_client = get_authz_client();
+    let resource = Resource::workflow(wf_id);
+    let subject = Subject::user(auth.user_id);
+    authz_client
+        .require_permission(&resource, Permission::Edit, &subject)
+        .await
+        .map_err(|e| {
+            tracing::warn!(
+                workflow_id = %wf_id,
+                user_id = %auth.user_id,
+                error = %e,
+                "Access denied to edit workflow"
+            );
+            WorkflowError::AccessDenied {
+                id: wf_id.to_string(),
+            }
+            .into_server_error()
+        })?;
+
+    let record = WorkflowRepository::new(get_db_pool())
+        .find_version(wf_id, version)
+        .await
+        .map_err(|e| {
+            tracing::error!(
+                error = %e,
+                workflow_id = %wf_id,
+                version = version,
+                "Database error loading workflow version"
+            );
+            WorkflowError::DatabaseError {
+                details: e.to_string(),
+            }
+            .into_server_error()
+        })?
+        .ok_or_else(|| {
+            WorkflowError::NotFound {
+                id: format!("{wf_id} version {version}"),
+            }
+            .into_server_error()
+        })?;
+
+    // Saving goes through the regular path, so the graph is validated and
+    // the triggers follow the restored version
+    update_workflow_graph(workflow_id, record.graph_data.to_string()).await?;
+
+    tracing::info!(
+        workflow_id = %wf_id,
+        user_id = %auth.user_id,
+        version = version,
+        "Workflow rolled back"
+    );
+
+    Ok(())
+}
+
+/// Versions tab component listing versions with diff and rollback.
+#[component]
+pub fn VersionsTab(
+    workflow_id: Signal<Option<String>>,
+    on_rollback: Callback<()>,
+) -> impl IntoView {
+    let (refresh, set_refresh) = signal(0u32);
+    let (diff_from, set_diff_from) = signal(Option::<i32>::None);
+    let (diff_to, set_diff_to) = signal(Option::<i32>::None);
+    let (rollback_error, set_rollback_error) = signal(Option::<String>::None);
+
+    let versions = Resource::new(
+        move || (workflow_id.get(), refresh.get()),
+        |(id, _)| async move {
+            match id {
+                Some(id) => list_workflow_versions(id).await.ok().unwrap_or_default(),
+                None => vec![],
+            }
+        },
+    );
+
+    let diff = Resource::new(
+        move || (workflow_id.get(), diff_from.get(), diff_to.get()),
+        |(wf_id, from, to)| async move {
+            match (wf_id, from, to) {
+                (Some(wf_id), Some(from), Some(to)) if from != to => {
+                    Some(diff_workflow_versions(wf_id, from, to).await)
+                }
+                _ => None,
+            }
+        },
+    );
+
+    let rollback = move |version: i32| {
+        let Some(wf_id) = workflow_id.get() else {
+            return;
+        };
+        spawn_local(async move {
+            match rollback_workflow_version(wf_id, version).await {
+                Ok(()) => {
+                    set_rollback_error.set(None);
+                    set_refresh.update(|n| *n += 1);
+                    on_rollback.run(());
+                }
+                Err(e) => set_rollback_error.set(Some(e.to_string())),
+            }
+        });
+    };
+
+    view! {
+        <div class="versions-content">
+            <h3>"Versions"</h3>
+            <p>"Every save creates a new version. Runs keep the version they started on."</p>
+            {move || rollback_error.get().map(|e| view! { <p class="error">{e}</p> })}
+            <Suspense fallback=move || view! { <p>"Loading versions..."</p> }>
+                {move || {
+                    let versions_list = versions.get().unwrap_or_default();
+                    view! {
+                        <table class="versions-table">
+                            <thead>
+                                <tr>
+                                    <th>"Version"</th>
+                                    <th>"Saved"</th>
+                                    <th>"Nodes"</th>
+                                    <th>"Edges"</th>
+                                    <th>"Diff from"</th>
+                                    <th>"Diff to"</th>
+                                    <th></th>
+                                </tr>
+                            </thead>
+                            <tbody>
+                                {versions_list.into_iter().map(|v| {
+                                    let version = v.version;
+                                    view! {
+                                        <tr class:current=v.current>
+                                            <td>"v"{version}{v.current.then_some(" (current)")}</td>
+                                            <td>{v.created_at}</td>
+                                            <td>{v.node_count}</td>
+                                            <td>{v.edge_count}</td>
+                                            <td>
+                                                <input
+                                                    type="radio"
+                                                    name="diff-from"
+                                                    prop:checked=move || diff_from.get() == Some(version)
+                                                    on:change=move |_| set_diff_from.set(Some(version))
+                                                />
+                                            </td>
+                                            <td>
+                                                <input
+                                                    type="radio"
+                                                    name="diff-to"
+                                                    prop:checked=move || diff_to.get() == Some(version)
+                                                    on:change=move |_| set_diff_to.set(Some(version))
+                                                />
+                                            </td>
+                                            <td>
+                                                {(!v.current).then(|| view! {
+                                                    <button
+                                                        class="rollback-btn"
+                                                        on:click=move |_| rollback(version)
+                                                    >
+                                                        "Roll back"
+                                                    </button>
+                                                })}
+                                            </td>
+                                        </tr>
+                                    }
+                                }).collect_view()}
+                            </tbody>
+                        </table>
+                    }
+                }}
+            </Suspense>
+
+            <Suspense fallback=move || view! { <p>"Loading diff..."</p> }>
+                {move || diff.get().flatten().map(|result| match result {
+                    Ok(diff) => view! { <GraphDiffPanel diff=diff /> }.into_any(),
+                    Err(e) => view! { <p class="error">{e.to_string()}</p> }.into_any(),
+                })}
+            </Suspense>
+        </div>
+    }
+}
+
+/// Panel listing the differences between two versions.
+#[component]
+fn GraphDiffPanel(diff: GraphDiff) -> impl IntoView {
+    if diff.is_empty() {
+        return view! { <p class="empty-state">"The versions have the same graph."</p> }.into_any();
+    }
+
+    let section = |title: &'static str, class: &'static str, items: Vec<String>| {
+        (!items.is_empty()).then(|| {
+            view! {
+                <div class=format!("diff-section {class}")>
+                    <h4>{title}</h4>
+                    <ul>
+                        {items.into_iter().map(|item| view! { <li>{item}</li> }).collect_view()}
+                    </ul>
+                </div>
+            }
+        })
+    };
+
+    view! {
+        <div class="graph-diff">
+            {section("Added nodes", "diff-added", diff.added_nodes)}
+            {section("Removed nodes", "diff-removed", diff.removed_nodes)}
+            {section("Changed nodes", "diff-changed", diff.changed_nodes)}
+            {section("Added edges", "diff-added", diff.added_edges)}
+            {section("Removed edges", "diff-removed", diff.removed_edges)}
+        </div>
+    }
+    .into_any()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pages::workflow_editor::{WorkflowEdge, WorkflowNode};
+
+    fn node(id: &str, label: &str, config: &str) -> WorkflowNode {
+        WorkflowNode {
+            id: id.to_string(),
+            node_type: "transform".to_string(),
+            label: label.to_string(),
+            config: config.to_string(),
+            x: 0.0,
+            y: 0.0,
+        }
+    }
+
+    fn edge(source: &str, target: &str) -> WorkflowEdge {
+        WorkflowEdge {
+            id: format!("{source}-{target}"),
+            source: source.to_string(),
+            target: target.to_string(),
+            source_port: "output".to_string(),
+            target_port: "input".to_string(),
+        }
+    }
+
+    #[test]
+    fn diff_reports_node_and_edge_changes() {
+        let from = WorkflowGraph {
+            nodes: vec![
+                node("a", "A", "{}"),
+                node("b", "B", "{}"),
+                node("c", "C", "{}"),
+            ],
+            edges: vec![edge("a", "b"), edge("b", "c")],
+        };
+        let mut moved = node("a", "A", "{}");
+        moved.x = 100.0;
+        let to = WorkflowGraph {
+            nodes: vec![moved, node("b", "B", r#"{"x":1}"#), node("d", "D", "{}")],
+            edges: vec![edge("a", "b"), edge("b", "d")],
+        };
+
+        let diff = diff_graphs(&from, &to);
+        assert_eq!(diff.added_nodes, vec!["D"]);
+        assert_eq!(diff.removed_nodes, vec!["C"]);
+        assert_eq!(diff.changed_nodes, vec!["B"]);
+        assert_eq!(diff.added_edges, vec!["B.output → D.input"]);
+        assert_eq!(diff.removed_edges, vec!["B.output → C.input"]);
+    }
+
+    #[test]
+    fn identical_graphs_have_an_empty_diff() {
+        let graph = WorkflowGraph {
+            nodes: vec![node("a", "A", "{}"), node("b", "B", "{}")],
+            edges: vec![edge("a", "b")],
+        };
+        assert!(diff_graphs(&graph, &graph.clone()).is_empty());
+    }
+}
@@ -1,8 +1,10 @@
 //! Workflows page component and server functions.
 
+use crate::pages::integrations::{IntegrationInfo, list_integrations};
 use crate::user::get_current_user;
 use leptos::prelude::*;
 use leptos::task::spawn_local;
+use std::collections::HashMap;
 
 /// User workflow info for display.
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
@@ -155,6 +157,215 @@ pub async fn create_workflow(
     Ok(workflow.id.to_string())
 }
 
+/// Summary of a workflow document before it is imported.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct ImportPreview {
+    pub name: String,
+    pub description: Option<String>,
+    pub node_count: usize,
+    pub has_memory_contents: bool,
+    pub references: Vec<ImportReference>,
+}
+
+/// A node of an imported document that refers to an integration account of
+/// the exporting instance.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct ImportReference {
+    pub node_id: String,
+    pub node_name: String,
+    /// The exporting instance's integration account ID.
+    pub integration_id: String,
+    /// The selected model, for model configuration nodes.
+    pub model_id: Option<String>,
+}
+
+/// Server function to read a workflow document and list what must be
+/// mapped before it can be imported.
+#[server]
+pub async fn inspect_workflow_import(document: String) -> Result<ImportPreview, ServerFnError> {
+    use crate::error::WorkflowError;
+    use crate::server_helpers::get_authenticated_session;
+    use silver_telegram_workflow::{ExternalReference, WorkflowDocument};
+
+    let _auth = get_authenticated_session().await.map_err(|e| {
+        tracing::debug!(error = %e, "Authentication failed for inspect_workflow_import");
+        e.into_server_error()
+    })?;
+
+    let document = WorkflowDocument::decode(&document).map_err(|e| {
+        tracing::debug!(error = %e, "Invalid workflow document");
+        WorkflowError::InvalidDocument {
+            details: e.to_string(),
+        }
+        .into_server_error()
+    })?;
+
+    let references = document
+        .references()
+        .into_iter()
+        .map(|reference| match reference {
+            ExternalReference::IntegrationAccount {
+                node_id,
+                node_name,
+                integration_id,
+            } => ImportReference {
+                node_id: node_id.to_string(),
+                node_name,
+                integration_id,
+                model_id: None,
+            },
+            ExternalReference::ModelConfig {
+                node_id,
+                node_name,
+                integration_id,
+                model_id,
+            } => ImportReference {
+                node_id: node_id.to_string(),
+                node_name,
+                integration_id,
+                model_id: Some(model_id),
+            },
+        })
+        .collect();
+
+    Ok(ImportPreview {
+        name: document.name,
+        description: document.description,
+        node_count: document.graph.node_count(),
+        has_memory_contents: document.memory_contents.is_some(),
+        references,
+    })
+}
+
+/// Server function to import a workflow document as a new workflow.
+///
+/// `mapping` is a JSON import mapping: the user's integration account for
+/// each exported one, and the model for each model configuration node. The
+/// workflow is created disabled, with new node IDs, and owned by the user.
+#[server]
+pub async fn import_workflow(document: String, mapping: String) -> Result<String, ServerFnError> {
+    use crate::db::{WorkflowMemoryRepository, WorkflowRecord, WorkflowRepository};
+    use crate::error::WorkflowError;
+    use crate::server_helpers::{get_authenticated_session, get_authz_client, get_db_pool};
+    use silver_telegram_authz::{Permission, Relationship, ResourceType, Subject};
+    use silver_telegram_workflow::{ImportMapping, WorkflowDocument};
+    use std::collections::HashSet;
+
+    let auth = get_authenticated_session().await.map_err(|e| {
+        tracing::debug!(error = %e, "Authentication failed for import_workflow");
+        e.into_server_error()
+    })?;
+
+    let invalid_document = |details: String| {
+        tracing::debug!(error = %details, "Invalid workflow import");
+        WorkflowError::InvalidDocument { details }.into_server_error()
+    };
+    let document =
+        WorkflowDocument::decode(&document).map_err(|e| invalid_document(e.to_string()))?;
+    let mapping: ImportMapping = serde_json::from_str(&mapping)
+        .map_err(|e| invalid_document(format!("invalid mapping: {}", e)))?;
+
+    // References may only be mapped onto integrations the user can see
+    let authz_client = get_authz_client();
+    let subject = Subject::user(auth.user_id);
+    let accessible: HashSet<String> = authz_client
+        .lookup_resources(ResourceType::Integration, Permission::View, &subject)
+        .await
+        .map_err(|e| {
+            tracing::error!(
+                error = %e,
+                user_id = %auth.user_id,
+                "Failed to lookup accessible integrations from SpiceDB"
+            );
+            WorkflowError::AuthorizationError {
+                details: e.to_string(),
+            }
+            .into_server_error()
+        })?
+        .into_iter()
+        .collect();
+    let mapped = mapping
+        .integrations
+        .values()
+        .chain(mapping.models.values().map(|model| &model.integration_id));
+    for integration_id in mapped {
+        if !accessible.contains(integration_id) {
+            return Err(invalid_document(format!(
+                "integration '{}' is not available",
+                integration_id
+            )));
+        }
+    }
+
+    let workflow = document
+        .import(&mapping)
+        .map_err(|e| invalid_document(e.to_string()))?;
+    let graph_data =
+        serde_json::to_value(&workflow.graph).map_err(|e| invalid_document(e.to_string()))?;
+
+    let mut record = WorkflowRecord::new(workflow.metadata.name.clone());
+    record.id = workflow.id;
+    record.description = workflow.metadata.description.clone();
+    record.tags = workflow.metadata.tags.clone();
+    record.concurrency = workflow.concurrency;
+    record.retention = workflow.retention;
+    record.max_duration_ms = workflow.max_duration_ms;
+    record.enabled = workflow.metadata.enabled;
+    record.graph_data = graph_data;
+
+    let db_pool = get_db_pool();
+    let database_error = |e: sqlx::Error| {
+        tracing::error!(
+            error = %e,
+            user_id = %auth.user_id,
+            workflow_id = %record.id,
+            "Failed to store imported workflow"
+        );
+        WorkflowError::DatabaseError {
+            details: e.to_string(),
+        }
+        .into_server_error()
+    };
+    WorkflowRepository::new(db_pool.clone())
+        .create(&record)
+        .await
+        .map_err(database_error)?;
+
+    // Create ownership relationship in SpiceDB
+    let relationship = Relationship::workflow_owner(record.id, auth.user_id);
+    authz_client
+        .write_relationship(&relationship)
+        .await
+        .map_err(|e| {
+            tracing::error!(
+                error = %e,
+                user_id = %auth.user_id,
+                workflow_id = %record.id,
+                "Failed to set workflow ownership in SpiceDB"
+            );
+            WorkflowError::AuthorizationError {
+                details: e.to_string(),
+            }
+            .into_server_error()
+        })?;
+
+    if let Some(contents) = document.memory_contents {
+        WorkflowMemoryRepository::new(db_pool)
+            .upsert(record.id, contents.into_bytes(), None)
+            .await
+            .map_err(database_error)?;
+    }
+
+    tracing::info!(
+        user_id = %auth.user_id,
+        workflow_id = %record.id,
+        workflow_name = %record.name,
+        "Imported workflow"
+    );
+
+    Ok(record.id.to_string())
+}
+
 /// Server function to toggle workflow enabled state.
 #[server]
 pub async fn toggle_workflow_enabled(workflow_id: String) -> Result<bool, ServerFnError> {
@@ -415,6 +626,8 @@ pub fn WorkflowsPage() -> impl IntoView {
                                         </div>
                                     </section>
 
+                                    <ImportWorkflowSection on_imported=Callback::new(move |()| workflows.refetch()) />
+
                                     // Delete Confirmation Modal
                                     {move || delete_id.get().map(|_| view! {
                                         <div class="modal-overlay">
@@ -545,3 +758,236 @@ pub fn WorkflowsPage() -> impl IntoView {
         </div>
     }
 }
+
+/// Section for importing a workflow document.
+///
+/// Inspecting the document lists the integration accounts and models it
+/// refers to; each must be mapped onto one of the user's integrations
+/// before the workflow can be imported.
+#[component]
+fn ImportWorkflowSection(on_imported: Callback<()>) -> impl IntoView {
+    let integrations = Resource::new(
+        || (),
+        |_| async move { list_integrations().await.ok().unwrap_or_default() },
+    );
+
+    let (document, set_document) = signal(String::new());
+    let (preview, set_preview) = signal(Option::<ImportPreview>::None);
+    let (import_error, set_import_error) = signal(Option::<String>::None);
+    let (importing, set_importing) = signal(false);
+    // Exported integration ID -> the user's integration ID
+    let integration_choices = RwSignal::new(HashMap::<String, String>::new());
+    // Model configuration node ID -> (integration ID, model ID)
+    let model_choices = RwSignal::new(HashMap::<String, (String, String)>::new());
+
+    let on_inspect = move |_| {
+        let text = document.get();
+        spawn_local(async move {
+            match inspect_workflow_import(text).await {
+                Ok(inspected) => {
+                    integration_choices.set(HashMap::new());
+                    model_choices.set(
+                        inspected
+                            .references
+                            .iter()
+                            .filter_map(|r| {
+                                r.model_id
+                                    .clone()
+                                    .map(|model_id| (r.node_id.clone(), (String::new(), model_id)))
+                            })
+                            .collect(),
+                    );
+                    set_preview.set(Some(inspected));
+                    set_import_error.set(None);
+                }
+                Err(e) => {
+                    set_preview.set(None);
+                    set_import_error.set(Some(e.to_string()));
+                }
+            }
+        });
+    };
+
+    let on_import = move |_| {
+        let models: serde_json::Map<String, serde_json::Value> = model_choices
+            .get()
+            .into_iter()
+            .map(|(node_id, (integration_id, model_id))| {
+                (
+                    node_id,
+                    serde_json::json!({ "integration_id": integration_id, "model_id": model_id }),
+                )
+            })
+            .collect();
+        let mapping = serde_json::json!({
+            "integrations": integration_choices.get(),
+            "models": models,
+        })
+        .to_string();
+        let text = document.get();
+        set_importing.set(true);
+        spawn_local(async move {
+            match import_workflow(text, mapping).await {
+                Ok(_) => {
+                    set_document.set(String::new());
+                    set_preview.set(None);
+                    set_import_error.set(None);
+                    on_imported.run(());
+                }
+                Err(e) => set_import_error.set(Some(e.to_string())),
+            }
+            set_importing.set(false);
+        });
+    };
+
+    // Every reference needs an integration before importing
+    let mapping_complete = move || {
+        preview.get().is_some_and(|p| {
+            p.references.iter().all(|r| match r.model_id {
+                Some(_) => model_choices.with(|choices| {
+                    choices
+                        .get(&r.node_id)
+                        .is_some_and(|(integration_id, model_id)| {
+                            !integration_id.is_empty() && !model_id.is_empty()
+                        })
+                }),
+                None => integration_choices.with(|choices| {
+                    choices
+                        .get(&r.integration_id)
+                        .is_some_and(|id| !id.is_empty())
+                }),
+            })
+        })
+    };
+
+    let integration_options = move |selected: String| {
+        let available: Vec<IntegrationInfo> = integrations.get().unwrap_or_default();
+        view! {
+            <option value="" selected=selected.is_empty()>"Select an integration"</option>
+            {available.into_iter().map(|i| {
+                let is_selected = i.id == selected;
+                view! {
+                    <option value=i.id.clone() selected=is_selected>
+                        {format!("{} ({})", i.name, i.integration_type)}
+                    </option>
+                }
+            }).collect_view()}
+        }
+    };
+
+    view! {
+        <section class="import-workflow">
+            <h2>"Import Workflow"</h2>
+            <p>"Paste an exported workflow document (YAML or JSON)."</p>
+            <textarea
+                rows="8"
+                prop:value=move || document.get()
+                on:input=move |ev| {
+                    set_document.set(event_target_value(&ev));
+                    set_preview.set(None);
+                }
+            ></textarea>
+            <button on:click=on_inspect disabled=move || document.get().trim().is_empty()>
+                "Inspect"
+            </button>
+            {move || import_error.get().map(|e| view! { <p class="error">{e}</p> })}
+            {move || preview.get().map(|p| {
+                // One choice per exported integration account, however many
+                // nodes use it
+                let mut integration_ids: Vec<String> = p
+                    .references
+                    .iter()
+                    .filter(|r| r.model_id.is_none())
+                    .map(|r| r.integration_id.clone())
+                    .collect();
+                integration_ids.dedup();
+                let model_refs: Vec<ImportReference> = p
+                    .references
+                    .iter()
+                    .filter(|r| r.model_id.is_some())
+                    .cloned()
+                    .collect();
+                view! {
+                    <div class="import-preview">
+                        <p>
+                            <strong>{p.name.clone()}</strong>
+                            {format!(" — {} nodes", p.node_count)}
+                            {p.has_memory_contents.then_some(", with memory contents")}
+                        </p>
+                        {p.description.clone().map(|d| view! { <p><small>{d}</small></p> })}
+                        {(!p.references.is_empty()).then(|| view! {
+                            <p>"Choose which of your integrations the workflow uses:"</p>
+                        })}
+                        {integration_ids.into_iter().map(|exported_id| {
+                            let nodes = p
+                                .references
+                                .iter()
+                                .filter(|r| r.model_id.is_none() && r.integration_id == exported_id)
+                                .map(|r| r.node_name.clone())
+                                .collect::<Vec<_>>()
+                                .join(", ");
+                            let key = exported_id.clone();
+                            let selected = integration_choices
+                                .with_untracked(|c| c.get(&exported_id).cloned().unwrap_or_default());
+                            view! {
+                                <div class="form-group import-mapping">
+                                    <label>{format!("{} (used by {})", exported_id, nodes)}</label>
+                                    <select on:change=move |ev| {
+                                        let value = event_target_value(&ev);
+                                        integration_choices.update(|c| {
+                                            c.insert(key.clone(), value);
+                                        });
+                                    }>
+                                        {integration_options(selected)}
+                                    </select>
+                                </div>
+                            }
+                        }).collect_view()}
+                        {model_refs.into_iter().map(|r| {
+                            let integration_key = r.node_id.clone();
+                            let model_key = r.node_id.clone();
+                            let node_id = r.node_id.clone();
+                            let selected = model_choices
+                                .with_untracked(|c| c.get(&r.node_id).map(|(i, _)| i.clone()))
+                                .unwrap_or_default();
+                            view! {
+                                <div class="form-group import-mapping">
+                                    <label>{format!("Model for {}", r.node_name)}</label>
+                                    <select on:change=move |ev| {
+                                        let value = event_target_value(&ev);
+                                        model_choices.update(|c| {
+                                            c.entry(integration_key.clone()).or_default().0 = value;
+                                        });
+                                    }>
+                                        {integration_options(selected)}
+                                    </select>
+                                    <input
+                                        type="text"
+                                        placeholder="Model ID"
+                                        prop:value=move || model_choices
+                                            .with(|c| c.get(&node_id).map(|(_, m)| m.clone()))
+                                            .unwrap_or_default()
+                                        on:input=move |ev| {
+                                            let value = event_target_value(&ev);
+                                            model_choices.update(|c| {
+                                                c.entry(model_key.clone()).or_default().1 = value;
+                                            });
+                                        }
+                                    />
+                                </div>
+                            }
+                        }).collect_view()}
+                        <p class="muted">"Imported workflows start disabled."</p>
+                        <button
+                            on:click=on_import
+                            disabled=move || importing.get() || !mapping_complete()
+                        >
+                            {move || if importing.get() { "Importing..." } else { "Import Workflow" }}
+                        </button>
+                    </div>
+                }
+            })}
+        </section>
+    }
+    .into_any()
+}
@@ -0,0 +1,381 @@
+//! Scheduler daemon wiring for the server.
+//!
+//! Backs the scheduler crate's [`SchedulerDaemon`] with Postgres: schedule
+//! triggers come from the `triggers` table, pending windows are stored in
+//! `scheduled_executions`, and due windows become `workflow_runs` that are
+//! handed to the workflow engine.
+
+use crate::config::SchedulerConfig;
+use crate::db::{
+    ScheduledExecutionRepository, TriggerRecord, TriggerRepository, WorkflowRepository,
+    WorkflowRunRecord, WorkflowRunRepository,
+};
+use crate::engine::WorkflowEngine;
+use crate::error::EngineError;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use silver_telegram_core::{TriggerId, WorkflowId, WorkflowRunId};
+use silver_telegram_scheduler::{
+    CronSchedule, RunLauncher, ScheduleError, ScheduleEvaluator, ScheduledExecution,
+    ScheduledTrigger, SchedulerDaemon, SchedulerError,
+};
+use silver_telegram_workflow::trigger::MissedExecutionBehavior;
+use sqlx::PgPool;
+
+/// Parses a schedule trigger's stored configuration.
+///
+/// The editor stores node config as a JSON string, so both a string and an
+/// object are accepted. Returns `None` if there is no cron expression.
+fn schedule_from_config(
+    config: &serde_json::Value,
+) -> Option<(CronSchedule, MissedExecutionBehavior)> {
+    let parsed;
+    let config = match config {
+        serde_json::Value::String(s) => {
+            parsed = serde_json::from_str::<serde_json::Value>(s).ok()?;
+            &parsed
+        }
+        other => other,
+    };
+
+    let cron = config.get("cron")?.as_str()?.trim();
+    if cron.is_empty() {
+        return None;
+    }
+    let mut schedule = CronSchedule::new(cron);
+    if let Some(timezone) = config.get("timezone").and_then(|t| t.as_str()) {
+        schedule = schedule.with_timezone(timezone);
+    }
+    let missed_execution = config
+        .get("missed_execution")
+        .and_then(|m| serde_json::from_value(m.clone()).ok())
+        .unwrap_or_default();
+
+    Some((schedule, missed_execution))
+}
+
+fn scheduled_trigger(trigger: &TriggerRecord) -> Option<ScheduledTrigger> {
+    let (schedule, missed_execution) = schedule_from_config(&trigger.config_data)?;
+    Some(ScheduledTrigger {
+        trigger_id: trigger.id,
+        workflow_id: trigger.workflow_id,
+        schedule,
+        missed_execution,
+    })
+}
+
+fn storage_error(e: sqlx::Error) -> ScheduleError {
+    ScheduleError::EvaluationFailed {
+        reason: e.to_string(),
+    }
+}
+
+/// Postgres-backed schedule evaluator.
+pub struct PgScheduleEvaluator {
+    pool: PgPool,
+    missed_grace: Duration,
+}
+
+impl PgScheduleEvaluator {
+    /// Creates a new evaluator.
+    pub fn new(pool: PgPool, missed_grace: Duration) -> Self {
+        Self { pool, missed_grace }
+    }
+}
+
+#[async_trait]
+impl ScheduleEvaluator for PgScheduleEvaluator {
+    async fn list_schedules(&self) -> Result<Vec<ScheduledTrigger>, ScheduleError> {
+        let triggers = TriggerRepository::new(self.pool.clone())
+            .list_active_schedules()
+            .await
+            .map_err(storage_error)?;
+
+        Ok(triggers
+            .iter()
+            .filter_map(|trigger| {
+                let scheduled = scheduled_trigger(trigger);
+                if scheduled.is_none() {
+                    tracing::debug!(
+                        trigger_id = %trigger.id,
+                        "Schedule trigger has no cron expression"
+                    );
+                }
+                scheduled
+            })
+            .collect())
+    }
+
+    async fn get_ready_executions(&self) -> Result<Vec<ScheduledExecution>, ScheduleError> {
+        ScheduledExecutionRepository::new(self.pool.clone())
+            .list_due(Utc::now())
+            .await
+            .map_err(storage_error)
+    }
+
+    async fn schedule_next(
+        &self,
+        trigger_id: TriggerId,
+        workflow_id: WorkflowId,
+        schedule: &CronSchedule,
+    ) -> Result<ScheduledExecution, ScheduleError> {
+        let repo = ScheduledExecutionRepository::new(self.pool.clone());
+        let now = Utc::now();
+        let next = schedule.next_after(now);
+
+        let pending = repo.find_pending(trigger_id).await.map_err(storage_error)?;
+        let execution = match (pending, next) {
+            // A due execution is left for the next pass to launch.
+            (Some(pending), _) if pending.scheduled_for <= now => pending,
+            // Keep the pending window unless the schedule was edited.
+            (Some(pending), Some(next)) if pending.scheduled_for == next => pending,
+            (pending, next) => {
+                if let Some(stale) = pending {
+                    repo.delete(stale.id).await.map_err(storage_error)?;
+                }
+                schedule.validate()?;
+                let next = next.ok_or_else(|| ScheduleError::EvaluationFailed {
+                    reason: format!("schedule '{}' never fires again", schedule.expression),
+                })?;
+                let execution = ScheduledExecution::new(trigger_id, workflow_id, next);
+                if !repo.create(&execution).await.map_err(storage_error)? {
+                    // Another scheduler created the pending window first.
+                    return repo
+                        .find_pending(trigger_id)
+                        .await
+                        .map_err(storage_error)?
+                        .ok_or_else(|| ScheduleError::EvaluationFailed {
+                            reason: "pending execution disappeared".to_string(),
+                        });
+                }
+                execution
+            }
+        };
+
+        TriggerRepository::new(self.pool.clone())
+            .set_next_run(trigger_id, Some(execution.scheduled_for))
+            .await
+            .map_err(storage_error)?;
+
+        Ok(execution)
+    }
+
+    async fn handle_missed_executions(
+        &self,
+        trigger_id: TriggerId,
+        behavior: MissedExecutionBehavior,
+    ) -> Result<Vec<ScheduledExecution>, ScheduleError> {
+        let repo = ScheduledExecutionRepository::new(self.pool.clone());
+        let Some(mut execution) = repo.find_pending(trigger_id).await.map_err(storage_error)?
+        else {
+            return Ok(Vec::new());
+        };
+        if !execution.is_missed(self.missed_grace) {
+            return Ok(Vec::new());
+        }
+
+        let trigger = TriggerRepository::new(self.pool.clone())
+            .find_by_id(trigger_id)
+            .await
+            .map_err(storage_error)?;
+        let Some(scheduled) = trigger.as_ref().and_then(scheduled_trigger) else {
+            return Ok(Vec::new());
+        };
+
+        tracing::info!(
+            trigger_id = %trigger_id,
+            scheduled_for = %execution.scheduled_for,
+            behavior = ?behavior,
+            "Handling missed scheduled execution"
+        );
+        execution.apply_missed_behavior(behavior, &scheduled.schedule, Utc::now());
+        repo.update(&execution).await.map_err(storage_error)?;
+
+        Ok(vec![execution])
+    }
+
+    async fn update_execution(&self, execution: ScheduledExecution) -> Result<(), ScheduleError> {
+        ScheduledExecutionRepository::new(self.pool.clone())
+            .update(&execution)
+            .await
+            .map_err(storage_error)
+    }
+}
+
+/// Launches scheduled runs into the run table and the workflow engine.
+pub struct EngineRunLauncher {
+    pool: PgPool,
+    engine: Option<WorkflowEngine>,
+}
+
+impl EngineRunLauncher {
+    /// Creates a new launcher. Without an engine, runs stay queued.
+    pub fn new(pool: PgPool, engine: Option<WorkflowEngine>) -> Self {
+        Self { pool, engine }
+    }
+}
+
+#[async_trait]
+impl RunLauncher for EngineRunLauncher {
+    async fn launch(
+        &self,
+        execution: &ScheduledExecution,
+    ) -> Result<WorkflowRunId, SchedulerError> {
+        let launch_failed = |e: sqlx::Error| SchedulerError::LaunchFailed {
+            trigger_id: execution.trigger_id,
+            reason: e.to_string(),
+        };
+
+        let workflow = WorkflowRepository::new(self.pool.clone())
+            .find_by_id(execution.workflow_id)
+            .await
+            .map_err(launch_failed)?;
+
+        let mut run = WorkflowRunRecord::new(
+            execution.workflow_id,
+            Some(execution.trigger_id),
+            Some(serde_json::json!({
+                "triggered_by": "schedule",
+                "scheduled_for": execution.scheduled_for,
+            })),
+        )
+        .with_id(execution.run_id());
+        if let Some(workflow) = &workflow {
+            run = run.with_workflow_version(workflow.version);
+        }
+
+        let (Some(engine), Some(workflow)) = (&self.engine, &workflow) else {
+            let run_repo = WorkflowRunRepository::new(self.pool.clone());
+            if run_repo
+                .create_if_absent(&run)
+                .await
+                .map_err(launch_failed)?
+            {
+                tracing::info!(
+                    run_id = %run.id,
+                    workflow_id = %run.workflow_id,
+                    "Scheduled run queued (workflow engine not configured)"
+                );
+            }
+            return Ok(run.id);
+        };
+
+        match engine
+            .launch_run(self.pool.clone(), workflow, &mut run)
+            .await
+        {
+            Ok(Some(_)) => {}
+            Ok(None) => {
+                tracing::info!(
+                    run_id = %run.id,
+                    trigger_id = %execution.trigger_id,
+                    "Scheduled run already exists, not launching again"
+                );
+                return Ok(run.id);
+            }
+            Err(EngineError::RunHistoryFailed { details }) => {
+                return Err(SchedulerError::LaunchFailed {
+                    trigger_id: execution.trigger_id,
+                    reason: details,
+                });
+            }
+            // The run was recorded as failed, so launching it again would
+            // not start it
+            Err(e) => {
+                tracing::warn!(
+                    run_id = %run.id,
+                    workflow_id = %run.workflow_id,
+                    error = %e,
+                    "Failed to start scheduled run"
+                );
+                return Err(SchedulerError::StartFailed {
+                    trigger_id: execution.trigger_id,
+                    run_id: run.id,
+                    reason: e.to_string(),
+                });
+            }
+        }
+
+        tracing::info!(
+            run_id = %run.id,
+            workflow_id = %run.workflow_id,
+            scheduled_for = %execution.scheduled_for,
+            state = ?run.state,
+            "Launched scheduled run"
+        );
+        Ok(run.id)
+    }
+}
+
+/// Runs the scheduler daemon until the process exits.
+///
+/// Sleeps until the next execution is due, but never longer than the
+/// configured poll interval so that newly saved schedules are picked up.
+pub async fn run_scheduler(pool: PgPool, engine: Option<WorkflowEngine>, config: SchedulerConfig) {
+    let daemon = SchedulerDaemon::new(
+        PgScheduleEvaluator::new(
+            pool.clone(),
+            Duration::seconds(config.missed_execution_grace_seconds),
+        ),
+        EngineRunLauncher::new(pool, engine),
+    );
+    let poll_interval = std::time::Duration::from_secs(config.poll_interval_seconds.max(1));
+
+    loop {
+        let next_due = match daemon.tick().await {
+            Ok(report) => {
+                for error in &report.errors {
+                    tracing::warn!(error = %error, "Scheduler error");
+                }
+                if !report.launched.is_empty() {
+                    tracing::debug!(launched = report.launched.len(), "Scheduler pass");
+                }
+                report.next_due
+            }
+            Err(e) => {
+                tracing::warn!(error = %e, "Scheduler pass failed");
+                None
+            }
+        };
+
+        tokio::time::sleep(sleep_duration(next_due, Utc::now(), poll_interval)).await;
+    }
+}
+
+fn sleep_duration(
+    next_due: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+    poll_interval: std::time::Duration,
+) -> std::time::Duration {
+    next_due
+        .and_then(|due| (due - now).to_std().ok())
+        .map_or(poll_interval, |until_due| until_due.min(poll_interval))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schedule_config_accepts_string_and_object() {
+        let from_string =
+            schedule_from_config(&serde_json::json!("{\"cron\":\"0 9 * * *\"}")).unwrap();
+        assert_eq!(from_string.0, CronSchedule::new("0 9 * * *"));
+        assert_eq!(from_string.1, MissedExecutionBehavior::Skip);
+
+        let from_object = schedule_from_config(&serde_json::json!({
+            "cron": "*/5 * * * *",
+            "timezone": "Europe/Berlin",
+            "missed_execution": "run_immediately",
+        }))
+        .unwrap();
+        assert_eq!(
+            from_object.0,
+            CronSchedule::new("*/5 * * * *").with_timezone("Europe/Berlin")
+        );
+        assert_eq!(from_object.1, MissedExecutionBehavior::RunImmediately);
+
+        assert!(schedule_from_config(&serde_json::json!({"cron": ""})).is_none());
+        assert!(schedule_from_config(&serde_json::json!("")).is_none());
+    }
+}
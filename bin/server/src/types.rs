@@ -110,6 +110,7 @@ pub struct WorkflowRunSummary {
 pub struct NodeExecutionSummary {
     pub id: String,
     pub node_id: String,
+    pub item_index: Option<i32>,
     pub state: String,
     pub started_at: Option<String>,
     pub finished_at: Option<String>,
@@ -0,0 +1,148 @@
+//! Worker wiring for the server.
+//!
+//! Runs a [`WorkerRuntime`] against the engine's work stream, either inside
+//! the server or as its own process (`silver-telegram-server worker`)
+//! advertising the capabilities it is configured with. The node a
+//! work item executes is looked up in the workflow version its run is
+//! pinned to, so a run keeps executing the graph it started on after the
+//! workflow is saved again. Transform nodes are executed in the worker, and
+//! sub-workflow nodes start their child runs in the engine; node kinds
+//! without an executor fail as unsupported.
+
+use crate::config::WorkerConfig;
+use crate::db::{WorkflowRecord, WorkflowRepository, WorkflowRunRepository};
+use crate::engine::{WorkflowEngine, executable_workflow};
+use async_trait::async_trait;
+use silver_telegram_workflow::{
+    Node, NodeResolver, ResolveError, TransformExecutor, WorkItem, Worker, WorkerRuntime,
+};
+use sqlx::PgPool;
+use std::sync::Arc;
+
+/// Looks up work items' nodes in the workflow versions their runs are
+/// pinned to.
+pub struct WorkflowNodeResolver {
+    runs: WorkflowRunRepository,
+    workflows: WorkflowRepository,
+}
+
+impl WorkflowNodeResolver {
+    /// Creates a resolver reading runs and workflows from `pool`.
+    #[must_use]
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            runs: WorkflowRunRepository::new(pool.clone()),
+            workflows: WorkflowRepository::new(pool),
+        }
+    }
+}
+
+#[async_trait]
+impl NodeResolver for WorkflowNodeResolver {
+    async fn resolve(&self, work_item: &WorkItem) -> Result<Node, ResolveError> {
+        let unavailable = |e: sqlx::Error| ResolveError::Unavailable {
+            message: e.to_string(),
+        };
+        let run = self
+            .runs
+            .find_by_id(work_item.run_id)
+            .await
+            .map_err(unavailable)?
+            .ok_or_else(|| ResolveError::NotFound {
+                message: format!("run {} does not exist", work_item.run_id),
+            })?;
+        let workflow = self
+            .workflows
+            .find_for_run(&run)
+            .await
+            .map_err(unavailable)?
+            .ok_or_else(|| ResolveError::NotFound {
+                message: format!("the workflow version of run {} does not exist", run.id),
+            })?;
+        node_in(&workflow, work_item)
+    }
+}
+
+/// Finds a work item's node in the graph of a workflow version.
+fn node_in(workflow: &WorkflowRecord, work_item: &WorkItem) -> Result<Node, ResolveError> {
+    let executable = executable_workflow(workflow).map_err(|e| ResolveError::NotFound {
+        message: e.to_string(),
+    })?;
+    executable
+        .graph
+        .get_node(work_item.node_id)
+        .cloned()
+        .ok_or_else(|| ResolveError::NotFound {
+            message: format!(
+                "node {} is not in version {} of workflow {}",
+                work_item.node_id, workflow.version, workflow.id
+            ),
+        })
+}
+
+/// Runs a worker on the engine's work stream until its connection ends.
+pub async fn run_worker(engine: WorkflowEngine, pool: PgPool, config: WorkerConfig) {
+    let worker = Worker::new(engine.object_store(), TransformExecutor)
+        .with_sub_workflows(Arc::new(engine.sub_workflows(pool.clone())));
+    let runtime = WorkerRuntime::new(
+        worker,
+        WorkflowNodeResolver::new(pool),
+        engine.event_store(),
+        config.runtime_config(),
+    );
+
+    if let Err(e) = runtime.run().await {
+        tracing::warn!(error = %e, "Worker stopped");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use silver_telegram_core::WorkflowRunId;
+    use silver_telegram_workflow::node::{TransformNodeConfig, TriggerNodeConfig};
+    use silver_telegram_workflow::{Edge, NodeConfig, NodeId, WorkflowGraph};
+    use std::collections::HashMap;
+
+    fn work_item(node_id: NodeId) -> WorkItem {
+        WorkItem {
+            run_id: WorkflowRunId::new(),
+            node_id,
+            capability: None,
+            item_index: None,
+            inputs: HashMap::new(),
+            attempt: 1,
+            not_before: None,
+            dry_run: None,
+        }
+    }
+
+    #[test]
+    fn nodes_are_found_in_the_workflow_version() {
+        let mut graph = WorkflowGraph::new();
+        let trigger_id = graph.add_node(Node::new(
+            "Trigger",
+            NodeConfig::Trigger(TriggerNodeConfig::Manual),
+        ));
+        let node_id = graph.add_node(Node::new(
+            "Transform",
+            NodeConfig::Transform(TransformNodeConfig {
+                expression: "input".to_string(),
+            }),
+        ));
+        graph
+            .add_edge(trigger_id, node_id, Edge::new("output", "input"))
+            .unwrap();
+        let mut workflow = WorkflowRecord::new("Test Workflow".to_string());
+        workflow.graph_data = serde_json::to_value(&graph).unwrap();
+
+        let node = node_in(&workflow, &work_item(node_id)).unwrap();
+        assert_eq!(node.id, node_id);
+        assert_eq!(node.name, "Transform");
+
+        assert!(matches!(
+            node_in(&workflow, &work_item(NodeId::new())),
+            Err(ResolveError::NotFound { .. })
+        ));
+    }
+}
@@ -0,0 +1,611 @@
+//! Cron expression parsing and evaluation.
+//!
+//! Supports the classic 5-field format (`minute hour day-of-month month
+//! day-of-week`) and a 6-field format with a leading seconds field. Each
+//! field accepts `*`, single values, ranges (`1-5`), steps (`*/15`, `0-30/5`,
+//! `10/5`), and comma-separated lists of those. Months and weekdays accept
+//! three-letter names (`JAN`, `MON`), and day-of-week accepts both `0` and `7`
+//! for Sunday. The macros `@yearly`, `@annually`, `@monthly`, `@weekly`,
+//! `@daily`, `@midnight`, and `@hourly` are also recognized.
+//!
+//! When both day-of-month and day-of-week are restricted, a day matches if
+//! either field matches (traditional cron semantics).
+//!
+//! # Daylight saving time
+//!
+//! Expressions are matched against wall-clock time in the evaluation
+//! timezone. Wall-clock times that are skipped or repeated by a DST
+//! transition are handled as follows:
+//!
+//! - **Fixed-time schedules** (the hour field does not match every hour):
+//!   a time that falls in a DST gap fires at the first instant after the gap,
+//!   and a time that occurs twice in a DST overlap fires once, at its first
+//!   occurrence.
+//! - **Interval schedules** (the hour field matches every hour): times in a
+//!   DST gap do not exist and are skipped, and times in a DST overlap fire on
+//!   both passes, so the interval keeps running in real time.
+
+use crate::error::ScheduleError;
+use chrono::{
+    DateTime, Datelike, Duration, LocalResult, NaiveDate, NaiveDateTime, Offset, TimeZone,
+    Timelike, Utc,
+};
+
+/// How many years ahead to search before concluding an expression never
+/// fires (e.g., `0 0 30 2 *`). Covers the longest gap between leap days.
+const SEARCH_YEARS: i32 = 9;
+
+const MONTH_NAMES: &[&str] = &[
+    "JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC",
+];
+
+const WEEKDAY_NAMES: &[&str] = &["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"];
+
+/// A cron field, with its valid range and accepted names.
+#[derive(Debug, Clone, Copy)]
+enum Field {
+    Second,
+    Minute,
+    Hour,
+    DayOfMonth,
+    Month,
+    DayOfWeek,
+}
+
+impl Field {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Second => "second",
+            Self::Minute => "minute",
+            Self::Hour => "hour",
+            Self::DayOfMonth => "day-of-month",
+            Self::Month => "month",
+            Self::DayOfWeek => "day-of-week",
+        }
+    }
+
+    fn min(self) -> u32 {
+        match self {
+            Self::DayOfMonth | Self::Month => 1,
+            _ => 0,
+        }
+    }
+
+    fn max(self) -> u32 {
+        match self {
+            Self::Second | Self::Minute => 59,
+            Self::Hour => 23,
+            Self::DayOfMonth => 31,
+            Self::Month => 12,
+            // 7 is accepted as an alias for Sunday and folded into 0.
+            Self::DayOfWeek => 7,
+        }
+    }
+
+    fn parse_value(self, text: &str) -> Result<u32, String> {
+        let names = match self {
+            Self::Month => Some((MONTH_NAMES, 1)),
+            Self::DayOfWeek => Some((WEEKDAY_NAMES, 0)),
+            _ => None,
+        };
+        if let Some((names, offset)) = names
+            && let Some(index) = names
+                .iter()
+                .position(|name| name.eq_ignore_ascii_case(text))
+        {
+            return Ok(index as u32 + offset);
+        }
+
+        let value: u32 = text
+            .parse()
+            .map_err(|_| format!("invalid value '{text}'"))?;
+        if value < self.min() || value > self.max() {
+            return Err(format!(
+                "value {value} out of range {}-{}",
+                self.min(),
+                self.max()
+            ));
+        }
+        Ok(value)
+    }
+}
+
+/// The set of values a single field matches, as a bitmask.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FieldSet(u64);
+
+impl FieldSet {
+    fn contains(self, value: u32) -> bool {
+        self.0 & (1 << value) != 0
+    }
+
+    fn parse(field: Field, text: &str) -> Result<Self, FieldError> {
+        Self::parse_inner(field, text).map_err(|reason| FieldError { field, reason })
+    }
+
+    fn parse_inner(field: Field, text: &str) -> Result<Self, String> {
+        if text.is_empty() {
+            return Err("empty field".to_string());
+        }
+
+        let mut bits = 0u64;
+        for item in text.split(',') {
+            if item.is_empty() {
+                return Err("empty list item".to_string());
+            }
+
+            let (range, step) = match item.split_once('/') {
+                Some((range, step)) => {
+                    let step: u32 = step.parse().map_err(|_| format!("invalid step '{step}'"))?;
+                    if step == 0 {
+                        return Err("step must be greater than 0".to_string());
+                    }
+                    (range, Some(step))
+                }
+                None => (item, None),
+            };
+
+            let (start, end) = if range == "*" || range == "?" {
+                if range == "?" && !matches!(field, Field::DayOfMonth | Field::DayOfWeek) {
+                    return Err("'?' is only allowed in day fields".to_string());
+                }
+                (field.min(), field.max())
+            } else if let Some((start, end)) = range.split_once('-') {
+                let start = field.parse_value(start)?;
+                let end = field.parse_value(end)?;
+                if start > end {
+                    return Err(format!("range start {start} is greater than end {end}"));
+                }
+                (start, end)
+            } else {
+                let start = field.parse_value(range)?;
+                // `10/5` means "from 10 to the end of the range, every 5".
+                let end = if step.is_some() { field.max() } else { start };
+                (start, end)
+            };
+
+            let step = step.unwrap_or(1) as usize;
+            for value in (start..=end).step_by(step) {
+                bits |= 1 << value;
+            }
+        }
+
+        if matches!(field, Field::DayOfWeek) && bits & (1 << 7) != 0 {
+            bits = (bits & !(1 << 7)) | 1;
+        }
+
+        Ok(Self(bits))
+    }
+}
+
+/// A field-level parse failure.
+struct FieldError {
+    field: Field,
+    reason: String,
+}
+
+/// A parsed cron expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronExpression {
+    seconds: FieldSet,
+    minutes: FieldSet,
+    hours: FieldSet,
+    days_of_month: FieldSet,
+    months: FieldSet,
+    days_of_week: FieldSet,
+    /// Whether day-of-month was restricted (did not start with `*` or `?`).
+    day_of_month_restricted: bool,
+    /// Whether day-of-week was restricted (did not start with `*` or `?`).
+    day_of_week_restricted: bool,
+}
+
+impl CronExpression {
+    /// Parses a cron expression.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScheduleError::InvalidCronExpression` naming the offending
+    /// field if the expression is malformed.
+    pub fn parse(expression: &str) -> Result<Self, ScheduleError> {
+        let invalid = |reason: String| ScheduleError::InvalidCronExpression {
+            expression: expression.to_string(),
+            reason,
+        };
+
+        let trimmed = expression.trim();
+        let expanded = if let Some(name) = trimmed.strip_prefix('@') {
+            match name.to_ascii_lowercase().as_str() {
+                "yearly" | "annually" => "0 0 1 1 *",
+                "monthly" => "0 0 1 * *",
+                "weekly" => "0 0 * * 0",
+                "daily" | "midnight" => "0 0 * * *",
+                "hourly" => "0 * * * *",
+                _ => return Err(invalid(format!("unknown macro '@{name}'"))),
+            }
+        } else {
+            trimmed
+        };
+
+        let parts: Vec<&str> = expanded.split_whitespace().collect();
+        let (second, rest) = match parts.len() {
+            5 => ("0", &parts[..]),
+            6 => (parts[0], &parts[1..]),
+            n => return Err(invalid(format!("expected 5 or 6 fields, got {n}"))),
+        };
+
+        let parse = |field: Field, text: &str| {
+            FieldSet::parse(field, text)
+                .map_err(|e| invalid(format!("{} field: {}", e.field.name(), e.reason)))
+        };
+        let is_restricted = |text: &str| !(text.starts_with('*') || text.starts_with('?'));
+
+        Ok(Self {
+            seconds: parse(Field::Second, second)?,
+            minutes: parse(Field::Minute, rest[0])?,
+            hours: parse(Field::Hour, rest[1])?,
+            days_of_month: parse(Field::DayOfMonth, rest[2])?,
+            months: parse(Field::Month, rest[3])?,
+            days_of_week: parse(Field::DayOfWeek, rest[4])?,
+            day_of_month_restricted: is_restricted(rest[2]),
+            day_of_week_restricted: is_restricted(rest[4]),
+        })
+    }
+
+    /// Returns the first time strictly after `after` that matches this
+    /// expression when evaluated as wall-clock time in `tz`.
+    ///
+    /// Returns `None` if the expression never matches (e.g., February 30th).
+    /// See the module documentation for how DST transitions are handled.
+    #[must_use]
+    pub fn next_after<Z: TimeZone>(&self, after: DateTime<Utc>, tz: &Z) -> Option<DateTime<Utc>> {
+        let limit_year = after.with_timezone(tz).year() + SEARCH_YEARS;
+        let mut cursor =
+            truncate_to_second(after.with_timezone(tz).naive_local()) + Duration::seconds(1);
+
+        loop {
+            let naive = self.next_naive_match(cursor, limit_year)?;
+            let resolved = match tz.from_local_datetime(&naive) {
+                LocalResult::Single(dt) => Some(dt.with_timezone(&Utc)),
+                LocalResult::Ambiguous(earliest, latest) => {
+                    let earliest = earliest.with_timezone(&Utc);
+                    let latest = latest.with_timezone(&Utc);
+                    if earliest > after {
+                        Some(earliest)
+                    } else if self.is_interval() && latest > after {
+                        Some(latest)
+                    } else {
+                        None
+                    }
+                }
+                LocalResult::None if self.is_interval() => None,
+                LocalResult::None => first_instant_after_gap(tz, naive),
+            };
+
+            if let Some(candidate) = resolved.filter(|candidate| *candidate > after) {
+                if self.is_interval() {
+                    return Some(self.earlier_in_overlap(after, candidate, tz, limit_year));
+                }
+                return Some(candidate);
+            }
+            cursor = naive + Duration::seconds(1);
+        }
+    }
+
+    /// Whether this schedule fires every hour, which makes it an interval
+    /// schedule for DST purposes.
+    fn is_interval(&self) -> bool {
+        (0..24).all(|hour| self.hours.contains(hour))
+    }
+
+    fn day_matches(&self, date: NaiveDate) -> bool {
+        let dom = self.days_of_month.contains(date.day());
+        let dow = self
+            .days_of_week
+            .contains(date.weekday().num_days_from_sunday());
+        if self.day_of_month_restricted && self.day_of_week_restricted {
+            dom || dow
+        } else {
+            dom && dow
+        }
+    }
+
+    /// Finds the first wall-clock time at or after `start` matching every
+    /// field, ignoring timezones entirely.
+    fn next_naive_match(&self, start: NaiveDateTime, limit_year: i32) -> Option<NaiveDateTime> {
+        let mut t = start;
+        loop {
+            if t.year() > limit_year {
+                return None;
+            }
+            if !self.months.contains(t.month()) {
+                let (year, month) = if t.month() == 12 {
+                    (t.year() + 1, 1)
+                } else {
+                    (t.year(), t.month() + 1)
+                };
+                t = NaiveDate::from_ymd_opt(year, month, 1)?.and_hms_opt(0, 0, 0)?;
+                continue;
+            }
+            if !self.day_matches(t.date()) {
+                t = t.date().succ_opt()?.and_hms_opt(0, 0, 0)?;
+                continue;
+            }
+            if !self.hours.contains(t.hour()) {
+                t = t.date().and_hms_opt(t.hour(), 0, 0)? + Duration::hours(1);
+                continue;
+            }
+            if !self.minutes.contains(t.minute()) {
+                t = t.date().and_hms_opt(t.hour(), t.minute(), 0)? + Duration::minutes(1);
+                continue;
+            }
+            if !self.seconds.contains(t.second()) {
+                t += Duration::seconds(1);
+                continue;
+            }
+            return Some(t);
+        }
+    }
+
+    /// For interval schedules, checks whether a DST overlap between `after`
+    /// and `candidate` repeats wall-clock times that match before
+    /// `candidate`, and returns the earliest such time.
+    fn earlier_in_overlap<Z: TimeZone>(
+        &self,
+        after: DateTime<Utc>,
+        candidate: DateTime<Utc>,
+        tz: &Z,
+        limit_year: i32,
+    ) -> DateTime<Utc> {
+        if utc_offset(tz, candidate) >= utc_offset(tz, after) {
+            return candidate;
+        }
+        let Some(transition) = first_offset_change(tz, after, candidate) else {
+            return candidate;
+        };
+        let start = truncate_to_second(transition.with_timezone(tz).naive_local());
+        self.next_naive_match(start, limit_year)
+            .and_then(|naive| tz.from_local_datetime(&naive).latest())
+            .map(|dt| dt.with_timezone(&Utc))
+            .filter(|repeat| *repeat > after && *repeat < candidate)
+            .unwrap_or(candidate)
+    }
+}
+
+fn truncate_to_second(t: NaiveDateTime) -> NaiveDateTime {
+    t.with_nanosecond(0).unwrap_or(t)
+}
+
+fn utc_offset<Z: TimeZone>(tz: &Z, at: DateTime<Utc>) -> i32 {
+    at.with_timezone(tz).offset().fix().local_minus_utc()
+}
+
+/// Returns the first valid instant after a wall-clock time that falls in a
+/// DST gap.
+fn first_instant_after_gap<Z: TimeZone>(tz: &Z, naive: NaiveDateTime) -> Option<DateTime<Utc>> {
+    let mut probe = naive.with_second(0)?.with_nanosecond(0)?;
+    for _ in 0..(24 * 60) {
+        probe += Duration::minutes(1);
+        if let Some(dt) = tz.from_local_datetime(&probe).earliest() {
+            return Some(dt.with_timezone(&Utc));
+        }
+    }
+    None
+}
+
+/// Finds the first instant in `(from, until]` whose UTC offset differs from
+/// the offset at `from`.
+fn first_offset_change<Z: TimeZone>(
+    tz: &Z,
+    from: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    let base = utc_offset(tz, from);
+    let mut lo = from;
+    loop {
+        let mut hi = (lo + Duration::days(1)).min(until);
+        if utc_offset(tz, hi) != base {
+            while hi - lo > Duration::seconds(1) {
+                let mid = lo + (hi - lo) / 2;
+                if utc_offset(tz, mid) == base {
+                    lo = mid;
+                } else {
+                    hi = mid;
+                }
+            }
+            return Some(hi);
+        }
+        if hi >= until {
+            return None;
+        }
+        lo = hi;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono_tz::America::New_York;
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    fn next(expression: &str, after: &str) -> Option<DateTime<Utc>> {
+        CronExpression::parse(expression)
+            .unwrap()
+            .next_after(utc(after), &Utc)
+    }
+
+    fn reason(expression: &str) -> String {
+        match CronExpression::parse(expression) {
+            Err(ScheduleError::InvalidCronExpression { reason, .. }) => reason,
+            other => panic!("expected InvalidCronExpression, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn parses_ranges_steps_lists_and_names() {
+        let expr = CronExpression::parse("0,30 9-17/2 */10 JAN-mar mon-FRI").unwrap();
+        assert!(expr.minutes.contains(0) && expr.minutes.contains(30));
+        assert!(!expr.minutes.contains(15));
+        assert!(expr.hours.contains(9) && expr.hours.contains(11) && !expr.hours.contains(10));
+        assert!(expr.days_of_month.contains(1) && expr.days_of_month.contains(11));
+        assert!(expr.months.contains(3) && !expr.months.contains(4));
+        assert!(expr.days_of_week.contains(1) && !expr.days_of_week.contains(0));
+    }
+
+    #[test]
+    fn sunday_accepts_seven() {
+        let expr = CronExpression::parse("0 0 * * 7").unwrap();
+        assert!(expr.days_of_week.contains(0));
+    }
+
+    #[test]
+    fn field_errors_name_the_field() {
+        assert!(reason("60 * * * *").contains("minute field"));
+        assert!(reason("* 24 * * *").contains("hour field"));
+        assert!(reason("* * 0 * *").contains("day-of-month field"));
+        assert!(reason("* * * FOO *").contains("month field"));
+        assert!(reason("* * * * 8").contains("day-of-week field"));
+        assert!(reason("*/0 * * * *").contains("step must be greater than 0"));
+        assert!(reason("5-1 * * * *").contains("greater than end"));
+        assert!(reason("? * * * *").contains("only allowed in day fields"));
+        assert!(reason("1,,2 * * * *").contains("empty list item"));
+        assert!(reason("* * * *").contains("expected 5 or 6 fields"));
+        assert!(reason("@reboot").contains("unknown macro"));
+    }
+
+    #[test]
+    fn five_field_expression_fires_on_the_minute() {
+        assert_eq!(
+            next("*/15 * * * *", "2025-01-01T10:07:30Z"),
+            Some(utc("2025-01-01T10:15:00Z"))
+        );
+        assert_eq!(
+            next("0 7 * * *", "2025-01-01T07:00:00Z"),
+            Some(utc("2025-01-02T07:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn six_field_expression_has_seconds() {
+        assert_eq!(
+            next("*/20 * * * * *", "2025-01-01T10:00:05Z"),
+            Some(utc("2025-01-01T10:00:20Z"))
+        );
+    }
+
+    #[test]
+    fn macros_expand() {
+        assert_eq!(
+            next("@daily", "2025-01-01T10:00:00Z"),
+            Some(utc("2025-01-02T00:00:00Z"))
+        );
+        assert_eq!(
+            next("@hourly", "2025-01-01T10:00:00Z"),
+            Some(utc("2025-01-01T11:00:00Z"))
+        );
+        assert_eq!(
+            next("@weekly", "2025-01-01T10:00:00Z"),
+            Some(utc("2025-01-05T00:00:00Z"))
+        );
+        assert_eq!(
+            next("@yearly", "2025-01-01T10:00:00Z"),
+            Some(utc("2026-01-01T00:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn restricted_day_fields_match_either() {
+        // The 15th, or any Monday. 2025-01-06 is a Monday.
+        assert_eq!(
+            next("0 0 15 * MON", "2025-01-01T00:00:00Z"),
+            Some(utc("2025-01-06T00:00:00Z"))
+        );
+        // Unrestricted day-of-month defers to day-of-week.
+        assert_eq!(
+            next("0 0 * * MON", "2025-01-07T00:00:00Z"),
+            Some(utc("2025-01-13T00:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn leap_day_and_impossible_dates() {
+        assert_eq!(
+            next("0 0 29 2 *", "2025-01-01T00:00:00Z"),
+            Some(utc("2028-02-29T00:00:00Z"))
+        );
+        assert_eq!(next("0 0 30 2 *", "2025-01-01T00:00:00Z"), None);
+    }
+
+    #[test]
+    fn evaluates_in_timezone() {
+        let expr = CronExpression::parse("0 7 * * *").unwrap();
+        // EST (UTC-5) in winter, EDT (UTC-4) in summer.
+        assert_eq!(
+            expr.next_after(utc("2025-01-15T00:00:00Z"), &New_York),
+            Some(utc("2025-01-15T12:00:00Z"))
+        );
+        assert_eq!(
+            expr.next_after(utc("2025-07-15T00:00:00Z"), &New_York),
+            Some(utc("2025-07-15T11:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn fixed_time_in_dst_gap_fires_after_gap() {
+        // 2025-03-09 02:00 EST jumps to 03:00 EDT; 02:30 does not exist.
+        let expr = CronExpression::parse("30 2 * * *").unwrap();
+        assert_eq!(
+            expr.next_after(utc("2025-03-09T05:00:00Z"), &New_York),
+            Some(utc("2025-03-09T07:00:00Z"))
+        );
+        assert_eq!(
+            expr.next_after(utc("2025-03-09T07:00:00Z"), &New_York),
+            Some(utc("2025-03-10T06:30:00Z"))
+        );
+    }
+
+    #[test]
+    fn interval_schedule_skips_dst_gap() {
+        let expr = CronExpression::parse("30 * * * *").unwrap();
+        // 01:30 EST, then 02:30 is skipped, then 03:30 EDT.
+        assert_eq!(
+            expr.next_after(utc("2025-03-09T06:30:00Z"), &New_York),
+            Some(utc("2025-03-09T07:30:00Z"))
+        );
+    }
+
+    #[test]
+    fn fixed_time_in_dst_overlap_fires_once() {
+        // 2025-11-02 02:00 EDT falls back to 01:00 EST; 01:30 occurs twice.
+        let expr = CronExpression::parse("30 1 * * *").unwrap();
+        let first = expr.next_after(utc("2025-11-02T04:00:00Z"), &New_York);
+        assert_eq!(first, Some(utc("2025-11-02T05:30:00Z")));
+        assert_eq!(
+            expr.next_after(first.unwrap(), &New_York),
+            Some(utc("2025-11-03T06:30:00Z"))
+        );
+    }
+
+    #[test]
+    fn interval_schedule_fires_on_both_overlap_passes() {
+        let expr = CronExpression::parse("*/30 * * * *").unwrap();
+        let mut fired = Vec::new();
+        let mut cursor = utc("2025-11-02T04:45:00Z");
+        for _ in 0..5 {
+            cursor = expr.next_after(cursor, &New_York).unwrap();
+            fired.push(cursor);
+        }
+        assert_eq!(
+            fired,
+            vec![
+                utc("2025-11-02T05:00:00Z"),
+                utc("2025-11-02T05:30:00Z"),
+                utc("2025-11-02T06:00:00Z"),
+                utc("2025-11-02T06:30:00Z"),
+                utc("2025-11-02T07:00:00Z"),
+            ]
+        );
+    }
+}
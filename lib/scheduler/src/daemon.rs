@@ -0,0 +1,454 @@
+//! Scheduler daemon that turns due schedule triggers into workflow runs.
+//!
+//! Each tick:
+//! 1. Applies each trigger's missed-execution policy to pending executions
+//!    whose window passed while the scheduler was not running
+//! 2. Launches a workflow run for every due execution
+//! 3. Ensures every trigger has a pending execution for its next window
+//!
+//! Runs are launched with an ID derived from the scheduled execution (see
+//! [`ScheduledExecution::run_id`]), and an execution is only marked complete
+//! after its launch succeeds. A restart between the two launches the same run
+//! ID again, which [`RunLauncher`] implementations treat as a no-op, so a
+//! window never fires twice. For the same reason, an execution whose run was
+//! created but failed to start is marked failed instead of retried.
+//!
+//! The daemon does not sleep or spawn tasks itself; the host calls
+//! [`SchedulerDaemon::tick`] in a loop and uses [`TickReport::next_due`] to
+//! decide how long to wait.
+
+use crate::error::{ScheduleError, SchedulerError};
+use crate::schedule::{ScheduleEvaluator, ScheduledExecution};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use silver_telegram_core::WorkflowRunId;
+
+/// Launches workflow runs for due scheduled executions.
+#[async_trait]
+pub trait RunLauncher: Send + Sync {
+    /// Creates and starts the run for `execution`.
+    ///
+    /// Must be idempotent for `execution.run_id()`: if that run already
+    /// exists, return its ID without starting it again. Returns
+    /// [`SchedulerError::StartFailed`] if the run was created but failed to
+    /// start.
+    async fn launch(&self, execution: &ScheduledExecution)
+    -> Result<WorkflowRunId, SchedulerError>;
+}
+
+/// Outcome of a single scheduler tick.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TickReport {
+    /// Runs launched during this tick.
+    pub launched: Vec<WorkflowRunId>,
+    /// Per-trigger failures. Failed launches stay pending and are retried
+    /// on the next tick, unless their run failed to start.
+    pub errors: Vec<SchedulerError>,
+    /// The earliest pending execution across all triggers, if any.
+    pub next_due: Option<DateTime<Utc>>,
+}
+
+/// Long-running scheduler for schedule triggers.
+pub struct SchedulerDaemon<E, L> {
+    evaluator: E,
+    launcher: L,
+}
+
+impl<E: ScheduleEvaluator, L: RunLauncher> SchedulerDaemon<E, L> {
+    /// Creates a new scheduler daemon.
+    pub fn new(evaluator: E, launcher: L) -> Self {
+        Self {
+            evaluator,
+            launcher,
+        }
+    }
+
+    /// Runs one scheduling pass.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the schedules or ready executions cannot be
+    /// loaded. Failures for individual triggers are collected in the report
+    /// instead.
+    pub async fn tick(&self) -> Result<TickReport, ScheduleError> {
+        let schedules = self.evaluator.list_schedules().await?;
+        let mut report = TickReport::default();
+
+        for trigger in &schedules {
+            if let Err(e) = self
+                .evaluator
+                .handle_missed_executions(trigger.trigger_id, trigger.missed_execution)
+                .await
+            {
+                report.errors.push(SchedulerError::ScheduleFailed {
+                    trigger_id: trigger.trigger_id,
+                    reason: e.to_string(),
+                });
+            }
+        }
+
+        for mut execution in self.evaluator.get_ready_executions().await? {
+            let run_id = match self.launcher.launch(&execution).await {
+                Ok(run_id) => run_id,
+                Err(e @ SchedulerError::StartFailed { .. }) => {
+                    execution.start();
+                    execution.fail();
+                    let trigger_id = execution.trigger_id;
+                    report.errors.push(e);
+                    if let Err(e) = self.evaluator.update_execution(execution).await {
+                        report.errors.push(SchedulerError::ScheduleFailed {
+                            trigger_id,
+                            reason: e.to_string(),
+                        });
+                    }
+                    continue;
+                }
+                Err(e) => {
+                    report.errors.push(e);
+                    continue;
+                }
+            };
+
+            // The scheduled execution is done once its run exists; the run's
+            // own progress is tracked on the run record.
+            execution.start();
+            execution.complete();
+            let trigger_id = execution.trigger_id;
+            if let Err(e) = self.evaluator.update_execution(execution).await {
+                report.errors.push(SchedulerError::ScheduleFailed {
+                    trigger_id,
+                    reason: e.to_string(),
+                });
+            }
+            report.launched.push(run_id);
+        }
+
+        for trigger in &schedules {
+            match self
+                .evaluator
+                .schedule_next(trigger.trigger_id, trigger.workflow_id, &trigger.schedule)
+                .await
+            {
+                Ok(next) => {
+                    report.next_due = Some(match report.next_due {
+                        Some(due) => due.min(next.scheduled_for),
+                        None => next.scheduled_for,
+                    });
+                }
+                Err(e) => report.errors.push(SchedulerError::ScheduleFailed {
+                    trigger_id: trigger.trigger_id,
+                    reason: e.to_string(),
+                }),
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schedule::{CronSchedule, ExecutionStatus, ScheduledTrigger};
+    use chrono::Duration;
+    use silver_telegram_core::{TriggerId, WorkflowId};
+    use silver_telegram_workflow::trigger::MissedExecutionBehavior;
+    use std::collections::HashSet;
+    use std::sync::Mutex;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    /// In-memory evaluator for testing.
+    struct InMemoryEvaluator {
+        triggers: Vec<ScheduledTrigger>,
+        executions: Mutex<Vec<ScheduledExecution>>,
+    }
+
+    impl InMemoryEvaluator {
+        fn new(trigger: ScheduledTrigger) -> Self {
+            Self {
+                triggers: vec![trigger],
+                executions: Mutex::new(Vec::new()),
+            }
+        }
+
+        fn seed(&self, execution: ScheduledExecution) {
+            self.executions.lock().unwrap().push(execution);
+        }
+
+        fn executions(&self) -> Vec<ScheduledExecution> {
+            self.executions.lock().unwrap().clone()
+        }
+    }
+
+    #[async_trait]
+    impl ScheduleEvaluator for InMemoryEvaluator {
+        async fn list_schedules(&self) -> Result<Vec<ScheduledTrigger>, ScheduleError> {
+            Ok(self.triggers.clone())
+        }
+
+        async fn get_ready_executions(&self) -> Result<Vec<ScheduledExecution>, ScheduleError> {
+            Ok(self
+                .executions()
+                .into_iter()
+                .filter(ScheduledExecution::is_ready)
+                .collect())
+        }
+
+        async fn schedule_next(
+            &self,
+            trigger_id: TriggerId,
+            workflow_id: WorkflowId,
+            schedule: &CronSchedule,
+        ) -> Result<ScheduledExecution, ScheduleError> {
+            let mut executions = self.executions.lock().unwrap();
+            if let Some(pending) = executions
+                .iter()
+                .find(|e| e.trigger_id == trigger_id && e.status == ExecutionStatus::Pending)
+            {
+                return Ok(pending.clone());
+            }
+            let next = schedule
+                .next_after(Utc::now())
+                .ok_or(ScheduleError::EvaluationFailed {
+                    reason: "schedule never fires".to_string(),
+                })?;
+            let execution = ScheduledExecution::new(trigger_id, workflow_id, next);
+            executions.push(execution.clone());
+            Ok(execution)
+        }
+
+        async fn handle_missed_executions(
+            &self,
+            trigger_id: TriggerId,
+            behavior: MissedExecutionBehavior,
+        ) -> Result<Vec<ScheduledExecution>, ScheduleError> {
+            let trigger = self
+                .triggers
+                .iter()
+                .find(|t| t.trigger_id == trigger_id)
+                .expect("known trigger");
+            let mut handled = Vec::new();
+            for execution in self.executions.lock().unwrap().iter_mut() {
+                if execution.trigger_id == trigger_id && execution.is_missed(Duration::minutes(5)) {
+                    execution.apply_missed_behavior(behavior, &trigger.schedule, Utc::now());
+                    handled.push(execution.clone());
+                }
+            }
+            Ok(handled)
+        }
+
+        async fn update_execution(
+            &self,
+            execution: ScheduledExecution,
+        ) -> Result<(), ScheduleError> {
+            let mut executions = self.executions.lock().unwrap();
+            if let Some(existing) = executions.iter_mut().find(|e| e.id == execution.id) {
+                *existing = execution;
+            }
+            Ok(())
+        }
+    }
+
+    /// Launcher that records created runs, like a run table keyed by run ID.
+    #[derive(Default)]
+    struct RecordingLauncher {
+        runs: Mutex<HashSet<WorkflowRunId>>,
+        fail_next: AtomicBool,
+        fail_next_start: AtomicBool,
+    }
+
+    impl RecordingLauncher {
+        fn run_count(&self) -> usize {
+            self.runs.lock().unwrap().len()
+        }
+    }
+
+    #[async_trait]
+    impl RunLauncher for RecordingLauncher {
+        async fn launch(
+            &self,
+            execution: &ScheduledExecution,
+        ) -> Result<WorkflowRunId, SchedulerError> {
+            if self.fail_next.swap(false, Ordering::SeqCst) {
+                return Err(SchedulerError::LaunchFailed {
+                    trigger_id: execution.trigger_id,
+                    reason: "unavailable".to_string(),
+                });
+            }
+            let created = self.runs.lock().unwrap().insert(execution.run_id());
+            if created && self.fail_next_start.swap(false, Ordering::SeqCst) {
+                return Err(SchedulerError::StartFailed {
+                    trigger_id: execution.trigger_id,
+                    run_id: execution.run_id(),
+                    reason: "graph is not executable".to_string(),
+                });
+            }
+            Ok(execution.run_id())
+        }
+    }
+
+    fn trigger(behavior: MissedExecutionBehavior) -> ScheduledTrigger {
+        ScheduledTrigger {
+            trigger_id: TriggerId::new(),
+            workflow_id: WorkflowId::new(),
+            schedule: CronSchedule::new("0 7 * * *"),
+            missed_execution: behavior,
+        }
+    }
+
+    fn due_execution(trigger: &ScheduledTrigger, ago: Duration) -> ScheduledExecution {
+        ScheduledExecution::new(trigger.trigger_id, trigger.workflow_id, Utc::now() - ago)
+    }
+
+    #[tokio::test]
+    async fn first_tick_schedules_next_window() {
+        let trigger = trigger(MissedExecutionBehavior::Skip);
+        let daemon = SchedulerDaemon::new(
+            InMemoryEvaluator::new(trigger.clone()),
+            RecordingLauncher::default(),
+        );
+
+        let report = daemon.tick().await.unwrap();
+
+        assert!(report.launched.is_empty());
+        let pending = daemon.evaluator.executions();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(report.next_due, Some(pending[0].scheduled_for));
+        assert!(pending[0].scheduled_for > Utc::now());
+    }
+
+    #[tokio::test]
+    async fn launches_due_execution_once() {
+        let trigger = trigger(MissedExecutionBehavior::Skip);
+        let evaluator = InMemoryEvaluator::new(trigger.clone());
+        let due = due_execution(&trigger, Duration::minutes(1));
+        evaluator.seed(due.clone());
+        let daemon = SchedulerDaemon::new(evaluator, RecordingLauncher::default());
+
+        let report = daemon.tick().await.unwrap();
+        assert_eq!(report.launched, vec![due.run_id()]);
+        assert!(report.errors.is_empty());
+
+        let executions = daemon.evaluator.executions();
+        assert_eq!(executions[0].status, ExecutionStatus::Completed);
+        assert_eq!(executions[1].status, ExecutionStatus::Pending);
+        assert!(report.next_due.unwrap() > Utc::now());
+
+        let report = daemon.tick().await.unwrap();
+        assert!(report.launched.is_empty());
+        assert_eq!(daemon.launcher.run_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn failed_launch_stays_pending_and_retries() {
+        let trigger = trigger(MissedExecutionBehavior::Skip);
+        let evaluator = InMemoryEvaluator::new(trigger.clone());
+        evaluator.seed(due_execution(&trigger, Duration::minutes(1)));
+        let launcher = RecordingLauncher::default();
+        launcher.fail_next.store(true, Ordering::SeqCst);
+        let daemon = SchedulerDaemon::new(evaluator, launcher);
+
+        let report = daemon.tick().await.unwrap();
+        assert!(report.launched.is_empty());
+        assert!(matches!(
+            report.errors.as_slice(),
+            [SchedulerError::LaunchFailed { .. }]
+        ));
+        assert_eq!(
+            daemon.evaluator.executions()[0].status,
+            ExecutionStatus::Pending
+        );
+
+        let report = daemon.tick().await.unwrap();
+        assert_eq!(report.launched.len(), 1);
+        assert_eq!(daemon.launcher.run_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn run_that_failed_to_start_fails_the_execution() {
+        let trigger = trigger(MissedExecutionBehavior::Skip);
+        let evaluator = InMemoryEvaluator::new(trigger.clone());
+        evaluator.seed(due_execution(&trigger, Duration::minutes(1)));
+        let launcher = RecordingLauncher::default();
+        launcher.fail_next_start.store(true, Ordering::SeqCst);
+        let daemon = SchedulerDaemon::new(evaluator, launcher);
+
+        let report = daemon.tick().await.unwrap();
+        assert!(report.launched.is_empty());
+        assert!(matches!(
+            report.errors.as_slice(),
+            [SchedulerError::StartFailed { .. }]
+        ));
+        let executions = daemon.evaluator.executions();
+        assert_eq!(executions[0].status, ExecutionStatus::Failed);
+        assert_eq!(executions[1].status, ExecutionStatus::Pending);
+
+        // The failed run is not launched again
+        let report = daemon.tick().await.unwrap();
+        assert!(report.launched.is_empty());
+        assert_eq!(daemon.launcher.run_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn restart_after_launch_does_not_double_fire() {
+        let trigger = trigger(MissedExecutionBehavior::Skip);
+        let evaluator = InMemoryEvaluator::new(trigger.clone());
+        let due = due_execution(&trigger, Duration::minutes(1));
+        evaluator.seed(due.clone());
+
+        // Simulate a crash after the run was created but before the
+        // execution was marked complete.
+        let launcher = RecordingLauncher::default();
+        launcher.launch(&due).await.unwrap();
+        let daemon = SchedulerDaemon::new(evaluator, launcher);
+
+        let report = daemon.tick().await.unwrap();
+        assert_eq!(report.launched, vec![due.run_id()]);
+        assert_eq!(daemon.launcher.run_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn missed_execution_with_skip_policy_does_not_run() {
+        let trigger = trigger(MissedExecutionBehavior::Skip);
+        let evaluator = InMemoryEvaluator::new(trigger.clone());
+        evaluator.seed(due_execution(&trigger, Duration::hours(2)));
+        let daemon = SchedulerDaemon::new(evaluator, RecordingLauncher::default());
+
+        let report = daemon.tick().await.unwrap();
+
+        assert!(report.launched.is_empty());
+        let executions = daemon.evaluator.executions();
+        assert_eq!(executions[0].status, ExecutionStatus::Skipped);
+        assert_eq!(executions[1].status, ExecutionStatus::Pending);
+    }
+
+    #[tokio::test]
+    async fn missed_execution_with_run_immediately_policy_runs() {
+        let trigger = trigger(MissedExecutionBehavior::RunImmediately);
+        let evaluator = InMemoryEvaluator::new(trigger.clone());
+        evaluator.seed(due_execution(&trigger, Duration::hours(2)));
+        let daemon = SchedulerDaemon::new(evaluator, RecordingLauncher::default());
+
+        let report = daemon.tick().await.unwrap();
+
+        assert_eq!(report.launched.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn missed_execution_with_next_window_policy_waits() {
+        let trigger = trigger(MissedExecutionBehavior::RunAtNextWindow);
+        let evaluator = InMemoryEvaluator::new(trigger.clone());
+        let missed = due_execution(&trigger, Duration::hours(2));
+        evaluator.seed(missed.clone());
+        let daemon = SchedulerDaemon::new(evaluator, RecordingLauncher::default());
+
+        let report = daemon.tick().await.unwrap();
+
+        assert!(report.launched.is_empty());
+        let executions = daemon.evaluator.executions();
+        assert_eq!(executions.len(), 1);
+        assert_eq!(executions[0].id, missed.id);
+        assert!(executions[0].scheduled_for > Utc::now());
+        assert_eq!(report.next_due, Some(executions[0].scheduled_for));
+    }
+}
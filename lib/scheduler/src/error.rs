@@ -5,7 +5,7 @@
 //! - `ScheduleError`: Errors from schedule operations
 //! - `SchedulerError`: High-level wrapper for context
 
-use silver_telegram_core::TriggerId;
+use silver_telegram_core::{TriggerId, WorkflowRunId};
 use std::fmt;
 
 /// Errors from trigger operations.
@@ -76,6 +76,23 @@ pub enum SchedulerError {
     TriggerOperation { trigger_id: TriggerId },
     /// Registration failed.
     RegistrationFailed { reason: String },
+    /// Evaluating a trigger's schedule failed.
+    ScheduleFailed {
+        trigger_id: TriggerId,
+        reason: String,
+    },
+    /// Launching a workflow run for a due execution failed.
+    LaunchFailed {
+        trigger_id: TriggerId,
+        reason: String,
+    },
+    /// The run for a due execution was created but failed to start, so
+    /// launching it again would not start it.
+    StartFailed {
+        trigger_id: TriggerId,
+        run_id: WorkflowRunId,
+        reason: String,
+    },
 }
 
 impl fmt::Display for SchedulerError {
@@ -87,6 +104,19 @@ impl fmt::Display for SchedulerError {
             Self::RegistrationFailed { reason } => {
                 write!(f, "trigger registration failed: {reason}")
             }
+            Self::ScheduleFailed { trigger_id, reason } => {
+                write!(f, "schedule evaluation failed for {trigger_id}: {reason}")
+            }
+            Self::LaunchFailed { trigger_id, reason } => {
+                write!(f, "failed to launch run for {trigger_id}: {reason}")
+            }
+            Self::StartFailed {
+                trigger_id,
+                run_id,
+                reason,
+            } => {
+                write!(f, "run {run_id} for {trigger_id} failed to start: {reason}")
+            }
         }
     }
 }
@@ -119,5 +149,12 @@ mod tests {
         let id = TriggerId::new();
         let err = SchedulerError::TriggerOperation { trigger_id: id };
         assert!(err.to_string().contains("trigger operation failed"));
+
+        let err = SchedulerError::LaunchFailed {
+            trigger_id: id,
+            reason: "database unavailable".to_string(),
+        };
+        assert!(err.to_string().contains("failed to launch run"));
+        assert!(err.to_string().contains("database unavailable"));
     }
 }
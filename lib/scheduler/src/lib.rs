@@ -4,12 +4,17 @@
 //!
 //! - **Trigger Manager**: Registration and lookup of triggers
 //! - **Scheduler**: Cron-based scheduling with missed execution handling
+//! - **Daemon**: Turns due schedule triggers into workflow runs
 //! - **Event Router**: Routing integration events to workflows
 
+pub mod cron;
+pub mod daemon;
 pub mod error;
 pub mod manager;
 pub mod schedule;
 
+pub use cron::CronExpression;
+pub use daemon::{RunLauncher, SchedulerDaemon, TickReport};
 pub use error::{ScheduleError, SchedulerError, TriggerError};
 pub use manager::{TriggerManager, TriggerRecord};
-pub use schedule::{CronSchedule, ScheduleEvaluator, ScheduledExecution};
+pub use schedule::{CronSchedule, ScheduleEvaluator, ScheduledExecution, ScheduledTrigger};
@@ -1,15 +1,17 @@
 //! Cron-based scheduling with missed execution handling.
 
+use crate::cron::CronExpression;
 use crate::error::ScheduleError;
 use async_trait::async_trait;
 use chrono::{DateTime, Duration, Utc};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
-use silver_telegram_core::{TriggerId, WorkflowId};
+use silver_telegram_core::{TriggerId, WorkflowId, WorkflowRunId};
 use silver_telegram_workflow::trigger::MissedExecutionBehavior;
 use ulid::Ulid;
 
 /// A parsed cron schedule.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CronSchedule {
     /// The cron expression.
     pub expression: String,
@@ -34,32 +36,49 @@ impl CronSchedule {
         self
     }
 
-    /// Validates the cron expression.
+    /// Validates the cron expression and timezone.
     ///
     /// # Errors
     ///
-    /// Returns an error if the expression is invalid.
+    /// Returns an error if the expression or timezone is invalid.
     pub fn validate(&self) -> Result<(), ScheduleError> {
-        // Basic validation - in production, use a proper cron parser
-        let parts: Vec<&str> = self.expression.split_whitespace().collect();
-        if parts.len() != 5 {
-            return Err(ScheduleError::InvalidCronExpression {
-                expression: self.expression.clone(),
-                reason: format!("expected 5 parts, got {}", parts.len()),
-            });
-        }
+        self.parse()?;
+        self.tz()?;
         Ok(())
     }
 
+    /// Parses the cron expression.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScheduleError::InvalidCronExpression` if the expression is
+    /// invalid.
+    pub fn parse(&self) -> Result<CronExpression, ScheduleError> {
+        CronExpression::parse(&self.expression)
+    }
+
+    /// Resolves the IANA timezone, defaulting to UTC when none is set.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScheduleError::InvalidTimezone` if the timezone is unknown.
+    pub fn tz(&self) -> Result<Tz, ScheduleError> {
+        match &self.timezone {
+            None => Ok(Tz::UTC),
+            Some(name) => name.parse().map_err(|_| ScheduleError::InvalidTimezone {
+                timezone: name.clone(),
+            }),
+        }
+    }
+
     /// Calculates the next execution time after the given time.
     ///
-    /// Note: This is a placeholder. A production implementation would use
-    /// a proper cron parsing library.
+    /// Returns `None` if the schedule is invalid or never fires again.
     #[must_use]
     pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
-        // Placeholder: return 1 hour after the given time
-        // In production, use a cron library like `cron` or `croner`
-        Some(after + Duration::hours(1))
+        let expression = self.parse().ok()?;
+        let tz = self.tz().ok()?;
+        expression.next_after(after, &tz)
     }
 }
 
@@ -95,6 +114,29 @@ impl ScheduledExecutionId {
     pub fn new() -> Self {
         Self(Ulid::new())
     }
+
+    /// Creates the deterministic ID for a trigger's execution at a given time.
+    ///
+    /// The same trigger and scheduled time always produce the same ID, so
+    /// anything keyed on it (such as the workflow run it launches) can be
+    /// created idempotently.
+    #[must_use]
+    pub fn for_trigger(trigger_id: TriggerId, scheduled_for: DateTime<Utc>) -> Self {
+        let millis = u64::try_from(scheduled_for.timestamp_millis()).unwrap_or_default();
+        Self(Ulid::from_parts(millis, trigger_id.as_ulid().random()))
+    }
+
+    /// Creates an ID from an existing ULID.
+    #[must_use]
+    pub const fn from_ulid(ulid: Ulid) -> Self {
+        Self(ulid)
+    }
+
+    /// Returns the underlying ULID.
+    #[must_use]
+    pub const fn as_ulid(&self) -> Ulid {
+        self.0
+    }
 }
 
 impl Default for ScheduledExecutionId {
@@ -109,6 +151,15 @@ impl std::fmt::Display for ScheduledExecutionId {
     }
 }
 
+impl std::str::FromStr for ScheduledExecutionId {
+    type Err = ulid::DecodeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let ulid_str = s.strip_prefix("sched_").unwrap_or(s);
+        Ulid::from_string(ulid_str).map(Self)
+    }
+}
+
 /// Status of a scheduled execution.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -136,7 +187,7 @@ impl ScheduledExecution {
         scheduled_for: DateTime<Utc>,
     ) -> Self {
         Self {
-            id: ScheduledExecutionId::new(),
+            id: ScheduledExecutionId::for_trigger(trigger_id, scheduled_for),
             trigger_id,
             workflow_id,
             scheduled_for,
@@ -159,6 +210,41 @@ impl ScheduledExecution {
         self.status == ExecutionStatus::Pending && Utc::now() > self.scheduled_for + threshold
     }
 
+    /// Returns the ID of the workflow run this execution launches.
+    ///
+    /// Derived from the execution ID, so launching the same execution twice
+    /// (e.g., after a restart mid-tick) targets the same run.
+    #[must_use]
+    pub fn run_id(&self) -> WorkflowRunId {
+        WorkflowRunId::from_ulid(self.id.as_ulid())
+    }
+
+    /// Applies a missed-execution policy to a pending execution whose
+    /// window has passed.
+    ///
+    /// - `Skip` marks the execution skipped; the next window is scheduled
+    ///   as usual.
+    /// - `RunImmediately` leaves the execution pending so it runs on the
+    ///   next tick. Only one execution is pending per trigger, so several
+    ///   missed windows still produce a single run.
+    /// - `RunAtNextWindow` moves the execution to the schedule's next
+    ///   window after `now`, or skips it if the schedule never fires again.
+    pub fn apply_missed_behavior(
+        &mut self,
+        behavior: MissedExecutionBehavior,
+        schedule: &CronSchedule,
+        now: DateTime<Utc>,
+    ) {
+        match behavior {
+            MissedExecutionBehavior::Skip => self.skip(),
+            MissedExecutionBehavior::RunImmediately => {}
+            MissedExecutionBehavior::RunAtNextWindow => match schedule.next_after(now) {
+                Some(next) => self.scheduled_for = next,
+                None => self.skip(),
+            },
+        }
+    }
+
     /// Marks the execution as started.
     pub fn start(&mut self) {
         self.status = ExecutionStatus::Running;
@@ -184,13 +270,34 @@ impl ScheduledExecution {
     }
 }
 
+/// An active schedule trigger, as seen by the scheduler.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScheduledTrigger {
+    /// The trigger ID.
+    pub trigger_id: TriggerId,
+    /// The workflow the trigger starts.
+    pub workflow_id: WorkflowId,
+    /// The trigger's schedule.
+    pub schedule: CronSchedule,
+    /// What to do when executions are missed (e.g., during downtime).
+    pub missed_execution: MissedExecutionBehavior,
+}
+
 /// Evaluates schedules and handles missed executions.
+///
+/// Implementations persist scheduled executions so that pending windows
+/// survive restarts. Each trigger has at most one pending execution.
 #[async_trait]
 pub trait ScheduleEvaluator: Send + Sync {
+    /// Lists the active schedule triggers.
+    async fn list_schedules(&self) -> Result<Vec<ScheduledTrigger>, ScheduleError>;
+
     /// Gets executions that are ready to run.
     async fn get_ready_executions(&self) -> Result<Vec<ScheduledExecution>, ScheduleError>;
 
     /// Creates the next scheduled execution for a trigger.
+    ///
+    /// If the trigger already has a pending execution, returns it instead.
     async fn schedule_next(
         &self,
         trigger_id: TriggerId,
@@ -228,6 +335,28 @@ mod tests {
 
         let invalid = CronSchedule::new("invalid");
         assert!(invalid.validate().is_err());
+
+        let bad_timezone = CronSchedule::new("0 7 * * *").with_timezone("Mars/Olympus_Mons");
+        assert!(matches!(
+            bad_timezone.validate(),
+            Err(ScheduleError::InvalidTimezone { .. })
+        ));
+    }
+
+    #[test]
+    fn cron_schedule_next_after_uses_timezone() {
+        let schedule = CronSchedule::new("0 7 * * *").with_timezone("America/New_York");
+        let after: DateTime<Utc> = "2025-01-15T00:00:00Z".parse().unwrap();
+        assert_eq!(
+            schedule.next_after(after),
+            Some("2025-01-15T12:00:00Z".parse().unwrap())
+        );
+
+        let utc_schedule = CronSchedule::new("0 7 * * *");
+        assert_eq!(
+            utc_schedule.next_after(after),
+            Some("2025-01-15T07:00:00Z".parse().unwrap())
+        );
     }
 
     #[test]
@@ -261,6 +390,51 @@ mod tests {
         assert!(!execution.is_missed(Duration::hours(3)));
     }
 
+    #[test]
+    fn execution_id_is_deterministic_per_trigger_and_time() {
+        let trigger_id = TriggerId::new();
+        let at: DateTime<Utc> = "2025-01-15T12:00:00Z".parse().unwrap();
+
+        let first = ScheduledExecution::new(trigger_id, WorkflowId::new(), at);
+        let second = ScheduledExecution::new(trigger_id, WorkflowId::new(), at);
+        assert_eq!(first.id, second.id);
+        assert_eq!(first.run_id(), second.run_id());
+
+        let later = ScheduledExecution::new(trigger_id, WorkflowId::new(), at + Duration::hours(1));
+        assert_ne!(first.id, later.id);
+
+        let other = ScheduledExecution::new(TriggerId::new(), WorkflowId::new(), at);
+        assert_ne!(first.id, other.id);
+
+        let parsed: ScheduledExecutionId = first.id.to_string().parse().unwrap();
+        assert_eq!(parsed, first.id);
+    }
+
+    #[test]
+    fn missed_execution_behaviors() {
+        let schedule = CronSchedule::new("0 7 * * *");
+        let missed_at: DateTime<Utc> = "2025-01-15T07:00:00Z".parse().unwrap();
+        let now: DateTime<Utc> = "2025-01-15T09:30:00Z".parse().unwrap();
+        let missed = ScheduledExecution::new(TriggerId::new(), WorkflowId::new(), missed_at);
+
+        let mut skipped = missed.clone();
+        skipped.apply_missed_behavior(MissedExecutionBehavior::Skip, &schedule, now);
+        assert_eq!(skipped.status, ExecutionStatus::Skipped);
+
+        let mut immediate = missed.clone();
+        immediate.apply_missed_behavior(MissedExecutionBehavior::RunImmediately, &schedule, now);
+        assert_eq!(immediate.status, ExecutionStatus::Pending);
+        assert_eq!(immediate.scheduled_for, missed_at);
+
+        let mut deferred = missed;
+        deferred.apply_missed_behavior(MissedExecutionBehavior::RunAtNextWindow, &schedule, now);
+        assert_eq!(deferred.status, ExecutionStatus::Pending);
+        assert_eq!(
+            deferred.scheduled_for,
+            "2025-01-16T07:00:00Z".parse::<DateTime<Utc>>().unwrap()
+        );
+    }
+
     #[test]
     fn execution_id_display() {
         let id = ScheduledExecutionId::new();
@@ -0,0 +1,173 @@
+//! Capabilities that route work items to workers.
+//!
+//! Every worker node requires one capability, and a work item only reaches
+//! workers that serve it. A node requires the capability named after its
+//! category (e.g. `ai_layer` or `integration`) unless it declares its own,
+//! e.g. `ollama` for LLM calls that must run next to a local model server.
+//!
+//! Workers advertise the capabilities they serve, so the orchestrator can
+//! tell when nobody would ever pick up a work item.
+
+use crate::node::NodeCategory;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// A capability a node requires and a worker serves.
+///
+/// Names consist of lowercase ASCII letters, digits, `_`, and `-`, since
+/// they become part of NATS subjects and keys.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Capability(String);
+
+impl Capability {
+    /// Creates a capability with the given name.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the name is empty or contains other characters
+    /// than lowercase ASCII letters, digits, `_`, and `-`.
+    pub fn new(name: impl Into<String>) -> Result<Self, InvalidCapability> {
+        let name = name.into();
+        let valid = !name.is_empty()
+            && name
+                .bytes()
+                .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'_' || b == b'-');
+        if valid {
+            Ok(Self(name))
+        } else {
+            Err(InvalidCapability { name })
+        }
+    }
+
+    /// Returns the capability that nodes of a category require by default.
+    #[must_use]
+    pub fn for_category(category: &NodeCategory) -> Self {
+        let name = match category {
+            NodeCategory::Trigger => "trigger",
+            NodeCategory::AiLayer => "ai_layer",
+            NodeCategory::Integration => "integration",
+            NodeCategory::Transform => "transform",
+            NodeCategory::ControlFlow => "control_flow",
+            NodeCategory::Memory => "memory",
+            NodeCategory::Output => "output",
+            NodeCategory::Configuration => "configuration",
+            NodeCategory::SubWorkflow => "sub_workflow",
+        };
+        Self(name.to_string())
+    }
+
+    /// Returns the capabilities of the node categories that workers execute.
+    ///
+    /// Control flow nodes are evaluated by the orchestrator, so no worker
+    /// needs to serve them.
+    #[must_use]
+    pub fn categories() -> Vec<Self> {
+        [
+            NodeCategory::Trigger,
+            NodeCategory::AiLayer,
+            NodeCategory::Integration,
+            NodeCategory::Transform,
+            NodeCategory::Memory,
+            NodeCategory::Output,
+            NodeCategory::Configuration,
+            NodeCategory::SubWorkflow,
+        ]
+        .iter()
+        .map(Self::for_category)
+        .collect()
+    }
+
+    /// Returns the name of the capability.
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromStr for Capability {
+    type Err = InvalidCapability;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::new(s)
+    }
+}
+
+impl TryFrom<String> for Capability {
+    type Error = InvalidCapability;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        Self::new(name)
+    }
+}
+
+impl From<Capability> for String {
+    fn from(capability: Capability) -> Self {
+        capability.0
+    }
+}
+
+/// A capability name that cannot be used for routing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidCapability {
+    /// The rejected name.
+    pub name: String,
+}
+
+impl fmt::Display for InvalidCapability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid capability '{}': use lowercase letters, digits, '_', and '-'",
+            self.name
+        )
+    }
+}
+
+impl std::error::Error for InvalidCapability {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_must_be_subject_tokens() {
+        assert_eq!(Capability::new("ollama").unwrap().as_str(), "ollama");
+        assert!(Capability::new("gpu-24gb_1").is_ok());
+        for name in ["", "GPU", "ai.layer", "ai layer", "ai*", "ai>"] {
+            assert_eq!(
+                Capability::new(name),
+                Err(InvalidCapability {
+                    name: name.to_string()
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn deserializing_validates_the_name() {
+        let capability: Capability = serde_json::from_str("\"ollama\"").unwrap();
+        assert_eq!(capability.to_string(), "ollama");
+        assert!(serde_json::from_str::<Capability>("\"workflow.work\"").is_err());
+    }
+
+    #[test]
+    fn categories_match_their_serialized_names() {
+        for capability in Capability::categories() {
+            let category: NodeCategory =
+                serde_json::from_value(serde_json::json!(capability.as_str())).unwrap();
+            assert_eq!(Capability::for_category(&category), capability);
+        }
+        assert!(
+            !Capability::categories()
+                .contains(&Capability::for_category(&NodeCategory::ControlFlow))
+        );
+    }
+}
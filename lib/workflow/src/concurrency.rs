@@ -0,0 +1,186 @@
+//! Per-workflow concurrency limits.
+//!
+//! A workflow can limit how many of its runs are in progress at once. When
+//! a new run would exceed the limit, the workflow's overlap policy decides
+//! what happens: the run waits in a queue until a run ends, it is skipped,
+//! or the oldest runs in progress are cancelled to make room for it.
+//!
+//! The policy is applied where runs are created, whatever triggered them,
+//! so [`ConcurrencyPolicy::admit`] only decides; the caller records the
+//! decision and starts, queues, skips, or cancels runs accordingly.
+
+use serde::{Deserialize, Serialize};
+use silver_telegram_core::WorkflowRunId;
+
+/// What happens to a new run when the workflow is at its limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverlapPolicy {
+    /// Wait until a run in progress ends.
+    #[default]
+    Queue,
+    /// Skip the new run.
+    Skip,
+    /// Cancel the oldest runs in progress to make room.
+    CancelOlder,
+}
+
+/// How many runs of a workflow may be in progress at once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct ConcurrencyPolicy {
+    /// Maximum number of runs in progress (None for no limit).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_concurrent_runs: Option<u32>,
+    /// What happens to a new run when the limit is reached.
+    #[serde(default)]
+    pub overlap: OverlapPolicy,
+}
+
+/// The decision on a new run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Admission {
+    /// Start the run.
+    Start,
+    /// Keep the run queued until a run in progress ends.
+    Queue,
+    /// Do not execute the run.
+    Skip {
+        /// Why the run was skipped.
+        reason: String,
+    },
+    /// Cancel these runs, oldest first, then start the run.
+    CancelOlder {
+        /// The runs to cancel.
+        runs: Vec<WorkflowRunId>,
+    },
+}
+
+impl ConcurrencyPolicy {
+    /// Creates a policy without a limit.
+    #[must_use]
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+
+    /// Creates a policy with a limit.
+    #[must_use]
+    pub fn limited(max_concurrent_runs: u32, overlap: OverlapPolicy) -> Self {
+        Self {
+            max_concurrent_runs: Some(max_concurrent_runs),
+            overlap,
+        }
+    }
+
+    /// Returns true if the policy does not limit concurrent runs.
+    #[must_use]
+    pub fn is_unlimited(&self) -> bool {
+        self.max_concurrent_runs.is_none()
+    }
+
+    /// Decides on a new run.
+    ///
+    /// `in_progress` are the workflow's runs in progress, oldest first, and
+    /// `queued` is the number of runs already waiting for a slot, which a
+    /// queued run waits behind.
+    #[must_use]
+    pub fn admit(&self, in_progress: &[WorkflowRunId], queued: usize) -> Admission {
+        let Some(max) = self.max_concurrent_runs else {
+            return Admission::Start;
+        };
+        // A limit of zero would never run anything, so it allows one run
+        let max = usize::try_from(max.max(1)).unwrap_or(usize::MAX);
+        let at_limit = in_progress.len() >= max;
+
+        match self.overlap {
+            OverlapPolicy::Queue if at_limit || queued > 0 => Admission::Queue,
+            OverlapPolicy::Skip if at_limit => Admission::Skip {
+                reason: format!(
+                    "{} run(s) already in progress, the workflow allows {}",
+                    in_progress.len(),
+                    max
+                ),
+            },
+            OverlapPolicy::CancelOlder if at_limit => Admission::CancelOlder {
+                runs: in_progress[..=in_progress.len() - max].to_vec(),
+            },
+            _ => Admission::Start,
+        }
+    }
+
+    /// Returns how many queued runs can start, given the number of runs in
+    /// progress.
+    #[must_use]
+    pub fn free_slots(&self, in_progress: usize) -> usize {
+        match self.max_concurrent_runs {
+            Some(max) => usize::try_from(max.max(1))
+                .unwrap_or(usize::MAX)
+                .saturating_sub(in_progress),
+            None => usize::MAX,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unlimited_policy_always_starts() {
+        let running = vec![WorkflowRunId::new(); 5];
+        assert_eq!(
+            ConcurrencyPolicy::unlimited().admit(&running, 3),
+            Admission::Start
+        );
+        assert_eq!(ConcurrencyPolicy::unlimited().free_slots(5), usize::MAX);
+    }
+
+    #[test]
+    fn queue_waits_behind_queued_runs() {
+        let policy = ConcurrencyPolicy::limited(2, OverlapPolicy::Queue);
+        let one = vec![WorkflowRunId::new()];
+        let two = vec![WorkflowRunId::new(), WorkflowRunId::new()];
+
+        assert_eq!(policy.admit(&one, 0), Admission::Start);
+        assert_eq!(policy.admit(&two, 0), Admission::Queue);
+        // A free slot goes to the runs already waiting
+        assert_eq!(policy.admit(&one, 1), Admission::Queue);
+        assert_eq!(policy.free_slots(1), 1);
+        assert_eq!(policy.free_slots(3), 0);
+    }
+
+    #[test]
+    fn skip_records_a_reason() {
+        let policy = ConcurrencyPolicy::limited(1, OverlapPolicy::Skip);
+        let running = vec![WorkflowRunId::new()];
+
+        assert_eq!(policy.admit(&[], 0), Admission::Start);
+        let Admission::Skip { reason } = policy.admit(&running, 0) else {
+            panic!("expected the run to be skipped");
+        };
+        assert!(reason.contains("allows 1"));
+    }
+
+    #[test]
+    fn cancel_older_makes_room_for_the_new_run() {
+        let policy = ConcurrencyPolicy::limited(2, OverlapPolicy::CancelOlder);
+        let running = vec![
+            WorkflowRunId::new(),
+            WorkflowRunId::new(),
+            WorkflowRunId::new(),
+        ];
+
+        assert_eq!(
+            policy.admit(&running[..2], 0),
+            Admission::CancelOlder {
+                runs: vec![running[0]]
+            }
+        );
+        // Runs beyond the limit (e.g. after it was lowered) are cancelled too
+        assert_eq!(
+            policy.admit(&running, 0),
+            Admission::CancelOlder {
+                runs: running[..2].to_vec()
+            }
+        );
+    }
+}
@@ -0,0 +1,169 @@
+//! Branch condition evaluation.
+//!
+//! Branch nodes route their input to the output ports whose condition
+//! evaluates to true. Conditions are [expressions](crate::expression) over
+//! the branch node's input value, such as `confidence > 0.8`,
+//! `email.from == 'a@example.com'`, or `any(labels, l => l == 'urgent')`.
+//!
+//! The result is interpreted by truthiness: a value is true unless it is
+//! `null`, `false`, `0`, or empty.
+
+use crate::expression::{Expression, ExpressionError, Type};
+use serde_json::Value as JsonValue;
+
+/// Errors from parsing or evaluating a branch condition.
+pub type ConditionError = ExpressionError;
+
+/// A parsed branch condition.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Condition {
+    expression: Expression,
+}
+
+impl Condition {
+    /// Parses a condition expression.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the expression is not a valid condition.
+    pub fn parse(source: &str) -> Result<Self, ConditionError> {
+        Ok(Self {
+            expression: Expression::parse(source)?,
+        })
+    }
+
+    /// Evaluates the condition against an input value.
+    ///
+    /// A condition that fails to evaluate is false.
+    #[must_use]
+    pub fn evaluate(&self, input: &JsonValue) -> bool {
+        self.try_evaluate(input).unwrap_or(false)
+    }
+
+    /// Evaluates the condition against an input value.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if evaluation fails (e.g., arithmetic on a string).
+    pub fn try_evaluate(&self, input: &JsonValue) -> Result<bool, ConditionError> {
+        self.expression.evaluate_truthy(input)
+    }
+
+    /// Type checks the condition for an input type.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the condition misuses a value whose type is
+    /// known from the input type.
+    pub fn check(&self, input: &Type) -> Result<(), ConditionError> {
+        self.expression.check(input).map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn eval(condition: &str, input: &JsonValue) -> bool {
+        Condition::parse(condition).unwrap().evaluate(input)
+    }
+
+    #[test]
+    fn numeric_comparisons() {
+        let input = json!({"confidence": 0.9});
+        assert!(eval("confidence > 0.8", &input));
+        assert!(!eval("confidence <= 0.8", &input));
+        assert!(eval("confidence >= 0.9", &input));
+        assert!(eval("confidence == 0.9", &input));
+        assert!(eval("confidence != 1", &input));
+    }
+
+    #[test]
+    fn string_equality_and_paths() {
+        let input = json!({
+            "category": "spam",
+            "email": {"from": "a@example.com", "labels": ["inbox", "work"]}
+        });
+        assert!(eval("category == 'spam'", &input));
+        assert!(eval("email.from == \"a@example.com\"", &input));
+        assert!(eval("email.labels[1] == 'work'", &input));
+        assert!(eval("email['from'] != 'b@example.com'", &input));
+        assert!(eval("input.category == 'spam'", &input));
+    }
+
+    #[test]
+    fn input_refers_to_whole_value() {
+        assert!(eval("input == 'urgent'", &json!("urgent")));
+        assert!(eval("input > 3", &json!(5)));
+        // A field named `input` takes precedence.
+        assert!(eval("input == 1", &json!({"input": 1})));
+    }
+
+    #[test]
+    fn boolean_operators_and_precedence() {
+        let input = json!({"a": true, "b": false, "n": 3});
+        assert!(eval("a && !b", &input));
+        assert!(eval("b || a", &input));
+        assert!(eval("a and not b", &input));
+        assert!(eval("b or n > 2 and a", &input));
+        assert!(!eval("(b or n > 2) and b", &input));
+    }
+
+    #[test]
+    fn missing_fields_and_mismatched_types() {
+        let input = json!({"count": 0, "name": "x"});
+        assert!(!eval("missing", &input));
+        assert!(eval("missing == null", &input));
+        assert!(!eval("missing > 1", &input));
+        assert!(!eval("name > 1", &input));
+        assert!(!eval("count", &input));
+        assert!(eval("name", &input));
+        assert!(eval("true", &input));
+    }
+
+    #[test]
+    fn functions_and_evaluation_errors() {
+        let input = json!({"labels": ["inbox", "Urgent"], "subject": "x"});
+        assert!(eval("any(labels, l => lower(l) == 'urgent')", &input));
+        assert!(eval(
+            "len(labels) >= 2 && !contains(labels, 'spam')",
+            &input
+        ));
+
+        let condition = Condition::parse("subject * 2 > 1").unwrap();
+        assert!(!condition.evaluate(&input));
+        assert!(matches!(
+            condition.try_evaluate(&input),
+            Err(ConditionError::EvaluationFailed { .. })
+        ));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(Condition::parse(""), Err(ConditionError::UnexpectedEnd));
+        assert_eq!(
+            Condition::parse("a > > 1"),
+            Err(ConditionError::UnexpectedToken {
+                token: ">".to_string(),
+                position: 4,
+            })
+        );
+        assert!(matches!(
+            Condition::parse("a == 'open"),
+            Err(ConditionError::UnterminatedString { position: 5 })
+        ));
+        assert!(matches!(
+            Condition::parse("a # b"),
+            Err(ConditionError::InvalidCharacter { character: '#', .. })
+        ));
+        assert!(matches!(
+            Condition::parse("(a"),
+            Err(ConditionError::UnexpectedEnd)
+        ));
+        assert!(matches!(
+            Condition::parse("a b"),
+            Err(ConditionError::UnexpectedToken { .. })
+        ));
+    }
+}
@@ -4,8 +4,11 @@
 //! - Metadata (name, description, timestamps)
 //! - A directed graph of nodes
 //! - Memory configuration (optional)
+//! - Concurrency policy (optional)
 
+use crate::concurrency::ConcurrencyPolicy;
 use crate::graph::WorkflowGraph;
+use crate::retention::RetentionPolicy;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use silver_telegram_core::WorkflowId;
@@ -89,6 +92,22 @@ pub struct Workflow {
     pub graph: WorkflowGraph,
     /// Memory configuration.
     pub memory: WorkflowMemoryConfig,
+    /// Maximum duration of a run, in milliseconds. Runs still going when
+    /// it passes are failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_duration_ms: Option<u64>,
+    /// How many runs may be in progress at once.
+    #[serde(default, skip_serializing_if = "ConcurrencyPolicy::is_unlimited")]
+    pub concurrency: ConcurrencyPolicy,
+    /// How long the outputs of the workflow's runs are kept, on top of the
+    /// global retention policy.
+    #[serde(default, skip_serializing_if = "RetentionPolicy::is_unlimited")]
+    pub retention: RetentionPolicy,
+    /// The saved version this definition was loaded from, if the workflow
+    /// is versioned. Runs record it so they can be traced to the exact
+    /// graph they executed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<u32>,
 }
 
 impl Workflow {
@@ -100,6 +119,10 @@ impl Workflow {
             metadata: WorkflowMetadata::new(name),
             graph: WorkflowGraph::new(),
             memory: WorkflowMemoryConfig::default(),
+            max_duration_ms: None,
+            concurrency: ConcurrencyPolicy::default(),
+            retention: RetentionPolicy::default(),
+            version: None,
         }
     }
 
@@ -111,9 +134,41 @@ impl Workflow {
             metadata: WorkflowMetadata::new(name),
             graph: WorkflowGraph::new(),
             memory: WorkflowMemoryConfig::default(),
+            max_duration_ms: None,
+            concurrency: ConcurrencyPolicy::default(),
+            retention: RetentionPolicy::default(),
+            version: None,
         }
     }
 
+    /// Sets the maximum duration of a run, in milliseconds.
+    #[must_use]
+    pub fn with_max_duration_ms(mut self, max_duration_ms: u64) -> Self {
+        self.max_duration_ms = Some(max_duration_ms);
+        self
+    }
+
+    /// Sets how many runs may be in progress at once.
+    #[must_use]
+    pub fn with_concurrency(mut self, concurrency: ConcurrencyPolicy) -> Self {
+        self.concurrency = concurrency;
+        self
+    }
+
+    /// Sets how long the outputs of the workflow's runs are kept.
+    #[must_use]
+    pub fn with_retention(mut self, retention: RetentionPolicy) -> Self {
+        self.retention = retention;
+        self
+    }
+
+    /// Sets the saved version this definition was loaded from.
+    #[must_use]
+    pub fn with_version(mut self, version: u32) -> Self {
+        self.version = Some(version);
+        self
+    }
+
     /// Returns the workflow name.
     #[must_use]
     pub fn name(&self) -> &str {
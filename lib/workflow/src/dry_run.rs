@@ -0,0 +1,228 @@
+//! Dry runs, which exercise a workflow without side effects.
+//!
+//! A run queued with a [`DryRunConfig`] executes like any other run, except
+//! for the nodes that would reach outside the platform: integration nodes
+//! with write operations and, if LLM calls are stubbed, AI layer nodes. The
+//! orchestrator substitutes these nodes itself, so they never reach a worker
+//! and their connector is never called. It records what the node would have
+//! sent in a `NodeSubstituted` event and completes the node with a fixture
+//! response: the one supplied for the node, or a placeholder that matches
+//! the node's output port.
+//!
+//! Workers refuse to execute substituted nodes of a dry run, in case one is
+//! dispatched anyway, and start the child runs of sub-workflow nodes as dry
+//! runs too.
+
+use crate::node::{Node, NodeConfig, NodeId};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+
+/// Integration operations that only read data.
+///
+/// An operation is a read if it is one of these verbs or starts with one
+/// followed by `_` (e.g. `list_events`). Every other operation counts as a
+/// write, so that dry runs err on the side of not calling the integration.
+const READ_OPERATIONS: &[&str] = &["fetch", "get", "list", "read", "search", "query", "check"];
+
+/// Configuration of a dry run.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DryRunConfig {
+    /// Responses for substituted nodes, keyed by node. Nodes without one
+    /// respond with a placeholder.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub fixtures: HashMap<NodeId, JsonValue>,
+    /// Whether AI layer nodes are substituted instead of calling an LLM.
+    #[serde(default)]
+    pub stub_llm: bool,
+}
+
+impl DryRunConfig {
+    /// Creates a dry run configuration without fixtures.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the response of a substituted node.
+    #[must_use]
+    pub fn with_fixture(mut self, node_id: NodeId, response: JsonValue) -> Self {
+        self.fixtures.insert(node_id, response);
+        self
+    }
+
+    /// Substitutes AI layer nodes as well.
+    #[must_use]
+    pub fn with_stub_llm(mut self) -> Self {
+        self.stub_llm = true;
+        self
+    }
+
+    /// Returns the configuration without its fixtures, which only apply to
+    /// the nodes of this run (e.g. for the child runs of sub-workflow nodes).
+    #[must_use]
+    pub fn without_fixtures(&self) -> Self {
+        Self {
+            fixtures: HashMap::new(),
+            stub_llm: self.stub_llm,
+        }
+    }
+
+    /// Returns true if the node is substituted in the dry run.
+    #[must_use]
+    pub fn substitutes(&self, node: &Node) -> bool {
+        match &node.config {
+            NodeConfig::Integration(config) => is_write_operation(&config.operation),
+            NodeConfig::AiLayer(_) => self.stub_llm,
+            _ => false,
+        }
+    }
+
+    /// Returns the response of a substituted node: its fixture, or a
+    /// placeholder that matches its first output port.
+    #[must_use]
+    pub fn response(&self, node: &Node) -> JsonValue {
+        if let Some(fixture) = self.fixtures.get(&node.id) {
+            return fixture.clone();
+        }
+        node.outputs
+            .first()
+            .map_or(JsonValue::Null, |port| placeholder(&port.schema.schema))
+    }
+}
+
+/// Returns true if an integration operation may change data.
+#[must_use]
+pub fn is_write_operation(operation: &str) -> bool {
+    !READ_OPERATIONS.iter().any(|verb| {
+        operation == *verb
+            || operation
+                .strip_prefix(verb)
+                .is_some_and(|rest| rest.starts_with('_'))
+    })
+}
+
+/// Returns what a substituted node would have sent: its configuration and
+/// the values on its input ports.
+#[must_use]
+pub fn substituted_request(node: &Node, inputs: &HashMap<String, JsonValue>) -> JsonValue {
+    serde_json::json!({
+        "config": node.config,
+        "inputs": inputs,
+    })
+}
+
+/// Builds a value that matches a JSON Schema, as far as
+/// [`PortSchema::validate`](crate::port::PortSchema::validate) checks it.
+fn placeholder(schema: &JsonValue) -> JsonValue {
+    if let Some(value) = schema.get("const") {
+        return value.clone();
+    }
+    if let Some(value) = schema
+        .get("enum")
+        .and_then(JsonValue::as_array)
+        .and_then(|values| values.first())
+    {
+        return value.clone();
+    }
+
+    let type_name = match schema.get("type") {
+        Some(JsonValue::String(name)) => name.as_str(),
+        Some(JsonValue::Array(names)) => names.first().and_then(JsonValue::as_str).unwrap_or(""),
+        _ => "",
+    };
+    match type_name {
+        "string" => JsonValue::String("dry run".to_string()),
+        "number" | "integer" => JsonValue::from(0),
+        "boolean" => JsonValue::Bool(false),
+        "array" => JsonValue::Array(Vec::new()),
+        "null" => JsonValue::Null,
+        _ => {
+            let properties = schema.get("properties");
+            let required = schema
+                .get("required")
+                .and_then(JsonValue::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(JsonValue::as_str);
+            let object: serde_json::Map<String, JsonValue> = required
+                .map(|name| {
+                    let property = properties
+                        .and_then(|properties| properties.get(name))
+                        .map_or(JsonValue::Null, placeholder);
+                    (name.to_string(), property)
+                })
+                .collect();
+            if object.is_empty() && type_name != "object" {
+                serde_json::json!({ "dry_run": true })
+            } else {
+                JsonValue::Object(object)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node::{AiLayerNodeConfig, IntegrationNodeConfig, TransformNodeConfig};
+
+    fn integration_node(operation: &str) -> Node {
+        Node::new(
+            "Email",
+            NodeConfig::Integration(IntegrationNodeConfig {
+                integration_type: "email".to_string(),
+                operation: operation.to_string(),
+                parameters: serde_json::json!({}),
+            }),
+        )
+    }
+
+    #[test]
+    fn only_write_operations_are_substituted() {
+        let config = DryRunConfig::new();
+
+        assert!(config.substitutes(&integration_node("send")));
+        assert!(config.substitutes(&integration_node("create_event")));
+        assert!(config.substitutes(&integration_node("listen")));
+        assert!(!config.substitutes(&integration_node("fetch")));
+        assert!(!config.substitutes(&integration_node("list_events")));
+        assert!(!config.substitutes(&Node::new(
+            "Transform",
+            NodeConfig::Transform(TransformNodeConfig {
+                expression: "input".to_string(),
+            }),
+        )));
+    }
+
+    #[test]
+    fn ai_nodes_are_substituted_when_llm_calls_are_stubbed() {
+        let node = Node::new(
+            "Classify",
+            NodeConfig::AiLayer(AiLayerNodeConfig::Classify {
+                categories: vec!["urgent".to_string(), "later".to_string()],
+            }),
+        );
+
+        assert!(!DryRunConfig::new().substitutes(&node));
+        let config = DryRunConfig::new().with_stub_llm();
+        assert!(config.substitutes(&node));
+
+        // The placeholder passes the output port's validation
+        let response = config.response(&node);
+        assert!(node.outputs[0].schema.validate(&response).is_ok());
+    }
+
+    #[test]
+    fn fixtures_are_used_as_responses() {
+        let node = integration_node("send");
+        let config = DryRunConfig::new().with_fixture(node.id, serde_json::json!({"id": "m1"}));
+
+        assert_eq!(config.response(&node), serde_json::json!({"id": "m1"}));
+        assert_eq!(
+            DryRunConfig::new().response(&node),
+            serde_json::json!({"dry_run": true})
+        );
+        assert!(config.without_fixtures().fixtures.is_empty());
+    }
+}
@@ -4,12 +4,201 @@
 //! - Schema evolution
 //! - In-place upgrades
 //! - Rolling deployments
+//!
+//! Payloads written by an older version are upcast when they are read: the
+//! [`UpcasterRegistry`] holds, per payload type, a function that transforms
+//! a payload from version N to N+1, and these are applied in turn until the
+//! payload reaches [`CURRENT_VERSION`]. Changing the serialized form of a
+//! [`Versioned`] type therefore means bumping [`CURRENT_VERSION`] and
+//! registering an upcaster for every type in [`UpcasterRegistry::standard`].
+//! The golden files under `testdata/envelopes` pin the serialized forms of
+//! each version, so a change without an upcaster fails the tests.
 
+use crate::execution::ExecutionEvent;
+use crate::orchestrator::{WorkItem, WorkItemResult};
+use crate::portable::WorkflowDocument;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+use std::sync::LazyLock;
 
 /// The current envelope version.
 pub const CURRENT_VERSION: u32 = 1;
 
+/// A payload type that is stored in envelopes.
+pub trait Versioned: DeserializeOwned {
+    /// Name of the payload type, used to look up its upcasters.
+    const PAYLOAD_TYPE: &'static str;
+}
+
+impl Versioned for ExecutionEvent {
+    const PAYLOAD_TYPE: &'static str = "execution_event";
+}
+
+impl Versioned for WorkItem {
+    const PAYLOAD_TYPE: &'static str = "work_item";
+}
+
+impl Versioned for WorkItemResult {
+    const PAYLOAD_TYPE: &'static str = "work_item_result";
+}
+
+impl Versioned for WorkflowDocument {
+    const PAYLOAD_TYPE: &'static str = "workflow_document";
+}
+
+/// Transforms a payload from one version to the next.
+pub type Upcaster = fn(JsonValue) -> Result<JsonValue, String>;
+
+/// Errors from reading envelopes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EnvelopeError {
+    /// The bytes are not an envelope, or the payload does not match its type.
+    Malformed { message: String },
+    /// The envelope was written by a newer version than this one.
+    UnsupportedVersion { payload_type: String, version: u32 },
+    /// No upcaster is registered for the payload type and version.
+    MissingUpcaster {
+        payload_type: String,
+        from_version: u32,
+    },
+    /// An upcaster rejected the payload.
+    UpcastFailed {
+        payload_type: String,
+        from_version: u32,
+        reason: String,
+    },
+}
+
+impl std::fmt::Display for EnvelopeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Malformed { message } => write!(f, "malformed envelope: {message}"),
+            Self::UnsupportedVersion {
+                payload_type,
+                version,
+            } => {
+                write!(
+                    f,
+                    "{payload_type} version {version} is newer than the supported version {CURRENT_VERSION}"
+                )
+            }
+            Self::MissingUpcaster {
+                payload_type,
+                from_version,
+            } => {
+                write!(
+                    f,
+                    "no upcaster for {payload_type} from version {from_version}"
+                )
+            }
+            Self::UpcastFailed {
+                payload_type,
+                from_version,
+                reason,
+            } => {
+                write!(
+                    f,
+                    "failed to upcast {payload_type} from version {from_version}: {reason}"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for EnvelopeError {}
+
+impl From<serde_json::Error> for EnvelopeError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Malformed {
+            message: e.to_string(),
+        }
+    }
+}
+
+/// Upcasters for each payload type, keyed by the version they upcast from.
+#[derive(Debug, Clone, Default)]
+pub struct UpcasterRegistry {
+    upcasters: HashMap<(&'static str, u32), Upcaster>,
+}
+
+static STANDARD_UPCASTERS: LazyLock<UpcasterRegistry> = LazyLock::new(|| {
+    // Register upcasters here when bumping CURRENT_VERSION, e.g.
+    // `.with_upcaster::<ExecutionEvent>(1, execution_event_v1_to_v2)`
+    UpcasterRegistry::new()
+});
+
+impl UpcasterRegistry {
+    /// Creates an empty registry.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the registry with the upcasters for the workflow engine's
+    /// payload types.
+    #[must_use]
+    pub fn standard() -> &'static Self {
+        &STANDARD_UPCASTERS
+    }
+
+    /// Registers the upcaster that transforms `T` payloads from
+    /// `from_version` to `from_version + 1`.
+    #[must_use]
+    pub fn with_upcaster<T: Versioned>(mut self, from_version: u32, upcaster: Upcaster) -> Self {
+        self.upcasters
+            .insert((T::PAYLOAD_TYPE, from_version), upcaster);
+        self
+    }
+
+    /// Upcasts an envelope's payload to the current version and
+    /// deserializes it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the envelope is newer than the current version,
+    /// an upcaster is missing or fails, or the upcast payload does not
+    /// deserialize.
+    pub fn decode<T: Versioned>(&self, raw: RawEnvelope) -> Result<Envelope<T>, EnvelopeError> {
+        let raw = self.upcast::<T>(raw, CURRENT_VERSION)?;
+        Ok(Envelope {
+            version: raw.version,
+            payload: serde_json::from_value(raw.payload)?,
+        })
+    }
+
+    /// Upcasts an envelope's payload to the target version.
+    fn upcast<T: Versioned>(
+        &self,
+        mut raw: RawEnvelope,
+        target_version: u32,
+    ) -> Result<RawEnvelope, EnvelopeError> {
+        if raw.version > target_version {
+            return Err(EnvelopeError::UnsupportedVersion {
+                payload_type: T::PAYLOAD_TYPE.to_string(),
+                version: raw.version,
+            });
+        }
+        while raw.version < target_version {
+            let upcaster = self
+                .upcasters
+                .get(&(T::PAYLOAD_TYPE, raw.version))
+                .ok_or_else(|| EnvelopeError::MissingUpcaster {
+                    payload_type: T::PAYLOAD_TYPE.to_string(),
+                    from_version: raw.version,
+                })?;
+            raw.payload = upcaster(raw.payload).map_err(|reason| EnvelopeError::UpcastFailed {
+                payload_type: T::PAYLOAD_TYPE.to_string(),
+                from_version: raw.version,
+                reason,
+            })?;
+            raw.version += 1;
+        }
+        Ok(raw)
+    }
+}
+
 /// A versioned envelope that wraps serialized data.
 ///
 /// All data persisted to NATS (events, outputs) or stored in the database
@@ -65,6 +254,9 @@ impl<T: Serialize> Envelope<T> {
 impl<T: for<'de> Deserialize<'de>> Envelope<T> {
     /// Deserializes an envelope from JSON bytes.
     ///
+    /// The payload must already be in the current version; use
+    /// [`Envelope::decode`] for data written by older versions.
+    ///
     /// # Errors
     ///
     /// Returns an error if deserialization fails.
@@ -73,6 +265,19 @@ impl<T: for<'de> Deserialize<'de>> Envelope<T> {
     }
 }
 
+impl<T: Versioned> Envelope<T> {
+    /// Deserializes an envelope from JSON bytes, upcasting the payload to
+    /// the current version with the standard upcasters.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bytes are not an envelope or the payload
+    /// cannot be upcast.
+    pub fn decode(bytes: &[u8]) -> Result<Self, EnvelopeError> {
+        RawEnvelope::from_json_bytes(bytes)?.deserialize_payload()
+    }
+}
+
 /// A versioned envelope that supports lazy deserialization of the payload.
 ///
 /// This is useful when you need to check the version before deserializing
@@ -86,19 +291,15 @@ pub struct RawEnvelope {
 }
 
 impl RawEnvelope {
-    /// Attempts to deserialize the payload into the given type.
+    /// Upcasts the payload to the current version with the standard
+    /// upcasters and deserializes it into the given type.
     ///
     /// # Errors
     ///
-    /// Returns an error if the payload cannot be deserialized into `T`.
-    pub fn deserialize_payload<T: for<'de> Deserialize<'de>>(
-        self,
-    ) -> Result<Envelope<T>, serde_json::Error> {
-        let payload: T = serde_json::from_value(self.payload)?;
-        Ok(Envelope {
-            version: self.version,
-            payload,
-        })
+    /// Returns an error if the payload cannot be upcast or deserialized
+    /// into `T`.
+    pub fn deserialize_payload<T: Versioned>(self) -> Result<Envelope<T>, EnvelopeError> {
+        UpcasterRegistry::standard().decode(self)
     }
 
     /// Returns the version of this envelope.
@@ -127,6 +328,10 @@ mod tests {
         count: u32,
     }
 
+    impl Versioned for TestPayload {
+        const PAYLOAD_TYPE: &'static str = "test_payload";
+    }
+
     #[test]
     fn envelope_creation() {
         let payload = TestPayload {
@@ -186,4 +391,185 @@ mod tests {
         assert!(json.get("payload").is_some());
         assert_eq!(json["version"], CURRENT_VERSION);
     }
+
+    /// Decodes every envelope in a golden file and checks that the decoded
+    /// payload serializes back to exactly what the file holds.
+    fn assert_golden_roundtrip<T: Versioned + Serialize>(golden: &str) -> Vec<T> {
+        let envelopes: Vec<RawEnvelope> = serde_json::from_str(golden).expect("parse golden file");
+        envelopes
+            .into_iter()
+            .map(|raw| {
+                let expected = raw.payload.clone();
+                let decoded: Envelope<T> =
+                    raw.deserialize_payload().expect("decode golden envelope");
+                assert_eq!(decoded.version, CURRENT_VERSION);
+                let reserialized = serde_json::to_value(&decoded.payload).expect("to_value");
+                assert_eq!(reserialized, expected);
+                decoded.payload
+            })
+            .collect()
+    }
+
+    #[test]
+    fn golden_v1_execution_events() {
+        let events: Vec<ExecutionEvent> = assert_golden_roundtrip(include_str!(
+            "../testdata/envelopes/v1/execution_events.json"
+        ));
+        assert_eq!(events.len(), 13);
+    }
+
+    #[test]
+    fn golden_v1_execution_events_without_optional_fields() {
+        let golden = include_str!("../testdata/envelopes/v1/execution_events_minimal.json");
+        let envelopes: Vec<RawEnvelope> = serde_json::from_str(golden).expect("parse golden file");
+        let events: Vec<ExecutionEvent> = envelopes
+            .into_iter()
+            .map(|raw| raw.deserialize_payload().expect("decode").payload)
+            .collect();
+
+        assert!(matches!(
+            events[0],
+            ExecutionEvent::RunQueued {
+                workflow_version: None,
+                trigger_id: None,
+                ..
+            }
+        ));
+        assert!(matches!(
+            events[2],
+            ExecutionEvent::NodeCompleted {
+                attempt: 1,
+                item_index: None,
+                ..
+            }
+        ));
+        assert!(matches!(
+            events[3],
+            ExecutionEvent::NodeFailed { attempt: 1, .. }
+        ));
+    }
+
+    #[test]
+    fn golden_v1_work_items() {
+        let items: Vec<WorkItem> =
+            assert_golden_roundtrip(include_str!("../testdata/envelopes/v1/work_items.json"));
+        assert_eq!(items.len(), 3);
+    }
+
+    #[test]
+    fn golden_v1_work_item_results() {
+        let results: Vec<WorkItemResult> = assert_golden_roundtrip(include_str!(
+            "../testdata/envelopes/v1/work_item_results.json"
+        ));
+        assert_eq!(results.len(), 2);
+    }
+
+    #[test]
+    fn golden_v1_workflow_documents() {
+        let documents: Vec<WorkflowDocument> = assert_golden_roundtrip(include_str!(
+            "../testdata/envelopes/v1/workflow_documents.json"
+        ));
+        assert_eq!(documents.len(), 2);
+    }
+
+    fn rename_text_to_message(mut payload: JsonValue) -> Result<JsonValue, String> {
+        let object = payload.as_object_mut().ok_or("payload is not an object")?;
+        let text = object.remove("text").ok_or("missing 'text'")?;
+        object.insert("message".to_string(), text);
+        Ok(payload)
+    }
+
+    fn add_default_count(mut payload: JsonValue) -> Result<JsonValue, String> {
+        let object = payload.as_object_mut().ok_or("payload is not an object")?;
+        object.entry("count").or_insert(JsonValue::from(0));
+        Ok(payload)
+    }
+
+    fn registry() -> UpcasterRegistry {
+        UpcasterRegistry::new()
+            .with_upcaster::<TestPayload>(1, rename_text_to_message)
+            .with_upcaster::<TestPayload>(2, add_default_count)
+    }
+
+    #[test]
+    fn upcasters_are_chained_to_the_target_version() {
+        let raw = RawEnvelope {
+            version: 1,
+            payload: serde_json::json!({ "text": "old" }),
+        };
+
+        let upcast = registry().upcast::<TestPayload>(raw, 3).expect("upcast");
+
+        assert_eq!(upcast.version, 3);
+        let payload: TestPayload = serde_json::from_value(upcast.payload).expect("payload");
+        assert_eq!(
+            payload,
+            TestPayload {
+                message: "old".to_string(),
+                count: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn upcasting_starts_from_the_stored_version() {
+        let raw = RawEnvelope {
+            version: 2,
+            payload: serde_json::json!({ "message": "newer" }),
+        };
+
+        let upcast = registry().upcast::<TestPayload>(raw, 3).expect("upcast");
+
+        assert_eq!(upcast.payload["message"], "newer");
+        assert_eq!(upcast.payload["count"], 0);
+    }
+
+    #[test]
+    fn upcasting_without_a_registered_step_fails() {
+        let raw = RawEnvelope {
+            version: 1,
+            payload: serde_json::json!({ "text": "old" }),
+        };
+
+        let err = registry().upcast::<TestPayload>(raw, 4).unwrap_err();
+
+        assert_eq!(
+            err,
+            EnvelopeError::MissingUpcaster {
+                payload_type: "test_payload".to_string(),
+                from_version: 3,
+            }
+        );
+    }
+
+    #[test]
+    fn envelopes_newer_than_the_target_are_rejected() {
+        let raw = RawEnvelope {
+            version: CURRENT_VERSION + 1,
+            payload: serde_json::json!({ "message": "future", "count": 1 }),
+        };
+
+        let err = raw.deserialize_payload::<TestPayload>().unwrap_err();
+
+        assert!(matches!(err, EnvelopeError::UnsupportedVersion { .. }));
+    }
+
+    #[test]
+    fn upcaster_errors_are_reported_with_the_failing_step() {
+        let raw = RawEnvelope {
+            version: 1,
+            payload: serde_json::json!({ "message": "already renamed" }),
+        };
+
+        let err = registry().upcast::<TestPayload>(raw, 3).unwrap_err();
+
+        assert_eq!(
+            err,
+            EnvelopeError::UpcastFailed {
+                payload_type: "test_payload".to_string(),
+                from_version: 1,
+                reason: "missing 'text'".to_string(),
+            }
+        );
+    }
 }
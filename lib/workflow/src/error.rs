@@ -28,11 +28,18 @@ pub enum GraphError {
         source_port: String,
         target_node: NodeId,
         target_port: String,
+        reason: String,
     },
     /// A required input port has no incoming edge.
     RequiredInputMissing { node_id: NodeId, port_name: String },
     /// Graph contains cycles.
     CycleDetected,
+    /// A fan-out or fan-in node is wired so that items cannot be executed
+    /// and collected.
+    InvalidFanOut { node_id: NodeId, reason: String },
+    /// A transform expression or branch condition does not parse or type
+    /// check.
+    InvalidExpression { node_id: NodeId, reason: String },
 }
 
 impl fmt::Display for GraphError {
@@ -52,10 +59,11 @@ impl fmt::Display for GraphError {
                 source_port,
                 target_node,
                 target_port,
+                reason,
             } => {
                 write!(
                     f,
-                    "incompatible schemas: {source_node}:{source_port} -> {target_node}:{target_port}"
+                    "incompatible schemas: {source_node}:{source_port} -> {target_node}:{target_port}: {reason}"
                 )
             }
             Self::RequiredInputMissing { node_id, port_name } => {
@@ -65,6 +73,12 @@ impl fmt::Display for GraphError {
                 )
             }
             Self::CycleDetected => write!(f, "graph contains cycles"),
+            Self::InvalidFanOut { node_id, reason } => {
+                write!(f, "invalid fan-out at node {node_id}: {reason}")
+            }
+            Self::InvalidExpression { node_id, reason } => {
+                write!(f, "invalid expression on node {node_id}: {reason}")
+            }
         }
     }
 }
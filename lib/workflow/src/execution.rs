@@ -6,7 +6,8 @@
 //! - Per-node execution state
 //! - Remaining work graph
 
-use crate::node::NodeId;
+use crate::dry_run::DryRunConfig;
+use crate::node::{ApprovalDecision, NodeId};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
@@ -20,6 +21,9 @@ pub enum ExecutionState {
     Queued,
     /// Run is actively executing.
     Running,
+    /// Run is executing, but paused at an approval node until a decision
+    /// is made, or at a delay node until its delay ends.
+    Waiting,
     /// Run completed successfully (all nodes completed or skipped).
     Completed,
     /// Run failed (at least one node failed, blocking downstream).
@@ -34,6 +38,12 @@ impl ExecutionState {
     pub fn is_terminal(&self) -> bool {
         matches!(self, Self::Completed | Self::Failed | Self::Cancelled)
     }
+
+    /// Returns true if the run has started and not yet ended.
+    #[must_use]
+    pub fn is_active(&self) -> bool {
+        matches!(self, Self::Running | Self::Waiting)
+    }
 }
 
 /// The execution state of a single node within a run.
@@ -46,6 +56,9 @@ pub enum NodeExecutionState {
     Ready,
     /// Node is currently executing.
     Running,
+    /// Approval node is waiting for a decision, or delay node for its
+    /// delay to end.
+    Waiting,
     /// Node completed successfully.
     Completed,
     /// Node failed.
@@ -75,6 +88,9 @@ pub struct WorkflowRun {
     pub id: WorkflowRunId,
     /// The workflow being executed.
     pub workflow_id: WorkflowId,
+    /// The workflow version being executed, if the workflow is versioned.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workflow_version: Option<u32>,
     /// The trigger that initiated this run, if any.
     pub trigger_id: Option<TriggerId>,
     /// Current execution state.
@@ -104,6 +120,7 @@ impl WorkflowRun {
         Self {
             id: WorkflowRunId::new(),
             workflow_id,
+            workflow_version: None,
             trigger_id,
             state: ExecutionState::Queued,
             queued_at: Utc::now(),
@@ -115,6 +132,13 @@ impl WorkflowRun {
         }
     }
 
+    /// Pins the run to a workflow version.
+    #[must_use]
+    pub fn with_workflow_version(mut self, version: u32) -> Self {
+        self.workflow_version = Some(version);
+        self
+    }
+
     /// Starts the run.
     pub fn start(&mut self) {
         self.state = ExecutionState::Running;
@@ -171,6 +195,30 @@ pub struct NodeExecution {
     pub output_key: Option<String>,
     /// Error message if failed.
     pub error: Option<String>,
+    /// Output ports taken by a branch or approval node (None for other
+    /// nodes).
+    #[serde(default)]
+    pub taken_ports: Option<Vec<String>>,
+    /// Index of the item this execution handles, for nodes inside a
+    /// fan-out scope (None for the node as a whole).
+    #[serde(default)]
+    pub item_index: Option<usize>,
+    /// The current attempt, starting at 1 and increased by each retry.
+    #[serde(default = "first_attempt")]
+    pub attempt: u32,
+    /// When a waiting node stops waiting: its pending approval expires or
+    /// its delay ends.
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// When the current attempt may start, if it is a retry waiting out its
+    /// backoff.
+    #[serde(default)]
+    pub retry_at: Option<DateTime<Utc>>,
+}
+
+/// The number of a node's first attempt; serde default for attempt fields.
+pub(crate) fn first_attempt() -> u32 {
+    1
 }
 
 impl NodeExecution {
@@ -187,6 +235,20 @@ impl NodeExecution {
             input: None,
             output_key: None,
             error: None,
+            taken_ports: None,
+            item_index: None,
+            attempt: first_attempt(),
+            expires_at: None,
+            retry_at: None,
+        }
+    }
+
+    /// Creates a new pending execution of one fan-out item.
+    #[must_use]
+    pub fn for_item(run_id: WorkflowRunId, node_id: NodeId, item_index: usize) -> Self {
+        Self {
+            item_index: Some(item_index),
+            ..Self::new(run_id, node_id)
         }
     }
 
@@ -202,11 +264,35 @@ impl NodeExecution {
         self.input = input;
     }
 
+    /// Pauses an approval or delay node, until `expires_at` if set.
+    pub fn wait(&mut self, expires_at: Option<DateTime<Utc>>) {
+        self.state = NodeExecutionState::Waiting;
+        self.expires_at = expires_at;
+    }
+
+    /// Resumes a waiting node once a decision was made or its delay ended.
+    pub fn resume(&mut self) {
+        self.state = NodeExecutionState::Running;
+        self.expires_at = None;
+    }
+
     /// Marks the node as completed.
     pub fn complete(&mut self, output_key: String) {
         self.state = NodeExecutionState::Completed;
         self.finished_at = Some(Utc::now());
         self.output_key = Some(output_key);
+        self.error = None;
+    }
+
+    /// Records a failed attempt that will be retried as `attempt`, no
+    /// earlier than `retry_at`.
+    ///
+    /// The node stays running; `error` holds the last attempt's error until
+    /// the node completes.
+    pub fn retry(&mut self, attempt: u32, error: String, retry_at: DateTime<Utc>) {
+        self.attempt = attempt;
+        self.error = Some(error);
+        self.retry_at = Some(retry_at);
     }
 
     /// Marks the node as failed.
@@ -221,6 +307,36 @@ impl NodeExecution {
         self.state = NodeExecutionState::Skipped;
         self.finished_at = Some(Utc::now());
     }
+
+    /// Records the output ports taken by a branch node.
+    pub fn take_branch(&mut self, ports: Vec<String>) {
+        self.taken_ports = Some(ports);
+    }
+
+    /// Returns true if data flows out of the given output port.
+    ///
+    /// Only branch and approval nodes restrict their active ports; every
+    /// port of any other node is active.
+    #[must_use]
+    pub fn is_port_active(&self, port: &str) -> bool {
+        self.taken_ports
+            .as_ref()
+            .is_none_or(|ports| ports.iter().any(|p| p == port))
+    }
+}
+
+/// A completed node a run takes over from the failed run it retries.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SeededNode {
+    /// The node.
+    pub node_id: NodeId,
+    /// Object store key of the node's output (None for nodes inside a
+    /// fan-out, whose outputs are per item).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_key: Option<String>,
+    /// Output ports taken by a branch or approval node.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub taken_ports: Option<Vec<String>>,
 }
 
 /// Events for workflow execution (for event sourcing).
@@ -231,26 +347,164 @@ pub enum ExecutionEvent {
     RunQueued {
         run_id: WorkflowRunId,
         workflow_id: WorkflowId,
+        /// The workflow version the run executes, if the workflow is
+        /// versioned.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        workflow_version: Option<u32>,
         trigger_id: Option<TriggerId>,
         input: Option<JsonValue>,
+        /// How integration writes and LLM calls are substituted, if this is
+        /// a dry run.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        dry_run: Option<DryRunConfig>,
         timestamp: DateTime<Utc>,
     },
     /// Run started executing.
     RunStarted {
         run_id: WorkflowRunId,
+        /// When the run fails if it has not finished, from the workflow's
+        /// maximum run duration.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        deadline: Option<DateTime<Utc>>,
+        timestamp: DateTime<Utc>,
+    },
+    /// Run takes over the completed nodes of a failed run it retries, so
+    /// only the other nodes execute.
+    ///
+    /// Follows `RunQueued`.
+    RunSeeded {
+        run_id: WorkflowRunId,
+        /// The failed run being retried.
+        source_run_id: WorkflowRunId,
+        /// The node the retry starts from.
+        from_node: NodeId,
+        /// The nodes taken over from the failed run.
+        nodes: Vec<SeededNode>,
         timestamp: DateTime<Utc>,
     },
     /// Node started executing.
     NodeStarted {
         run_id: WorkflowRunId,
         node_id: NodeId,
+        /// Item index, for nodes executed per item of a fan-out.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        item_index: Option<usize>,
         input: Option<JsonValue>,
         timestamp: DateTime<Utc>,
     },
+    /// Branch node evaluated its conditions.
+    BranchTaken {
+        run_id: WorkflowRunId,
+        node_id: NodeId,
+        /// Item index, for nodes executed per item of a fan-out.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        item_index: Option<usize>,
+        /// Output ports whose condition matched.
+        ports: Vec<String>,
+        timestamp: DateTime<Utc>,
+    },
+    /// Approval node paused the run until a decision is made.
+    ApprovalRequested {
+        run_id: WorkflowRunId,
+        node_id: NodeId,
+        /// Item index, for nodes executed per item of a fan-out.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        item_index: Option<usize>,
+        /// The input to approve or reject.
+        payload: JsonValue,
+        /// When the approval expires, if ever.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        expires_at: Option<DateTime<Utc>>,
+        timestamp: DateTime<Utc>,
+    },
+    /// A decision was made on an approval node.
+    ///
+    /// Followed by `BranchTaken` for the decision's port and
+    /// `NodeCompleted`.
+    ApprovalResolved {
+        run_id: WorkflowRunId,
+        node_id: NodeId,
+        /// Item index, for nodes executed per item of a fan-out.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        item_index: Option<usize>,
+        decision: ApprovalDecision,
+        /// The user who decided; None if the approval expired.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        decided_by: Option<String>,
+        timestamp: DateTime<Utc>,
+    },
+    /// Delay node paused its path until a time.
+    DelayStarted {
+        run_id: WorkflowRunId,
+        node_id: NodeId,
+        /// Item index, for nodes executed per item of a fan-out.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        item_index: Option<usize>,
+        /// When the delay ends.
+        until: DateTime<Utc>,
+        timestamp: DateTime<Utc>,
+    },
+    /// A delay node's delay ended.
+    ///
+    /// Followed by `NodeCompleted`.
+    DelayEnded {
+        run_id: WorkflowRunId,
+        node_id: NodeId,
+        /// Item index, for nodes executed per item of a fan-out.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        item_index: Option<usize>,
+        timestamp: DateTime<Utc>,
+    },
+    /// Sub-workflow node started a child run and waits for it to end.
+    SubWorkflowStarted {
+        run_id: WorkflowRunId,
+        node_id: NodeId,
+        /// Item index, for nodes executed per item of a fan-out.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        item_index: Option<usize>,
+        /// The attempt that started the child run.
+        attempt: u32,
+        child_run_id: WorkflowRunId,
+        timestamp: DateTime<Utc>,
+    },
+    /// A sub-workflow node's child run ended.
+    ///
+    /// Followed by `NodeCompleted` with the child run's output, or by the
+    /// node's failure or retry.
+    SubWorkflowEnded {
+        run_id: WorkflowRunId,
+        node_id: NodeId,
+        /// Item index, for nodes executed per item of a fan-out.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        item_index: Option<usize>,
+        child_run_id: WorkflowRunId,
+        timestamp: DateTime<Utc>,
+    },
+    /// A dry run substituted a node instead of executing it.
+    ///
+    /// Followed by `NodeCompleted` with the response.
+    NodeSubstituted {
+        run_id: WorkflowRunId,
+        node_id: NodeId,
+        /// Item index, for nodes executed per item of a fan-out.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        item_index: Option<usize>,
+        /// What the node would have sent.
+        request: JsonValue,
+        /// The fixture response the node completed with.
+        response: JsonValue,
+        timestamp: DateTime<Utc>,
+    },
     /// Node completed successfully.
     NodeCompleted {
         run_id: WorkflowRunId,
         node_id: NodeId,
+        /// Item index, for nodes executed per item of a fan-out.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        item_index: Option<usize>,
+        /// The attempt that produced the output, starting at 1.
+        #[serde(default = "first_attempt")]
+        attempt: u32,
         output_key: String,
         timestamp: DateTime<Utc>,
     },
@@ -258,16 +512,58 @@ pub enum ExecutionEvent {
     NodeFailed {
         run_id: WorkflowRunId,
         node_id: NodeId,
+        /// Item index, for nodes executed per item of a fan-out.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        item_index: Option<usize>,
+        /// The attempt that failed, starting at 1.
+        #[serde(default = "first_attempt")]
+        attempt: u32,
         error: String,
         timestamp: DateTime<Utc>,
     },
+    /// Node failed and will be retried.
+    ///
+    /// The node stays running until the retry completes or fails.
+    NodeRetryScheduled {
+        run_id: WorkflowRunId,
+        node_id: NodeId,
+        /// Item index, for nodes executed per item of a fan-out.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        item_index: Option<usize>,
+        /// The attempt that will run next.
+        attempt: u32,
+        /// Error of the failed attempt.
+        error: String,
+        /// When the next attempt may start.
+        retry_at: DateTime<Utc>,
+        timestamp: DateTime<Utc>,
+    },
     /// Node was skipped.
     NodeSkipped {
         run_id: WorkflowRunId,
         node_id: NodeId,
+        /// Item index, for nodes executed per item of a fan-out.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        item_index: Option<usize>,
         reason: String,
         timestamp: DateTime<Utc>,
     },
+    /// FanOut node split its input into items.
+    ///
+    /// The nodes in the fan-out's scope now execute once per item.
+    FanOutExpanded {
+        run_id: WorkflowRunId,
+        node_id: NodeId,
+        /// Object store keys of the items, in order.
+        item_keys: Vec<String>,
+        timestamp: DateTime<Utc>,
+    },
+    /// Every item of a fan-out has completed, failed, or been blocked.
+    FanOutFinished {
+        run_id: WorkflowRunId,
+        node_id: NodeId,
+        timestamp: DateTime<Utc>,
+    },
     /// Run completed successfully.
     RunCompleted {
         run_id: WorkflowRunId,
@@ -286,6 +582,15 @@ pub enum ExecutionEvent {
         reason: String,
         timestamp: DateTime<Utc>,
     },
+    /// A failed run was retried by a new run. Recorded on the failed run.
+    RunRetried {
+        run_id: WorkflowRunId,
+        /// The run retrying this one.
+        retry_run_id: WorkflowRunId,
+        /// The node the retry starts from.
+        from_node: NodeId,
+        timestamp: DateTime<Utc>,
+    },
 }
 
 impl ExecutionEvent {
@@ -295,13 +600,26 @@ impl ExecutionEvent {
         match self {
             Self::RunQueued { run_id, .. }
             | Self::RunStarted { run_id, .. }
+            | Self::RunSeeded { run_id, .. }
             | Self::NodeStarted { run_id, .. }
+            | Self::BranchTaken { run_id, .. }
+            | Self::ApprovalRequested { run_id, .. }
+            | Self::ApprovalResolved { run_id, .. }
+            | Self::DelayStarted { run_id, .. }
+            | Self::DelayEnded { run_id, .. }
+            | Self::SubWorkflowStarted { run_id, .. }
+            | Self::SubWorkflowEnded { run_id, .. }
+            | Self::NodeSubstituted { run_id, .. }
             | Self::NodeCompleted { run_id, .. }
             | Self::NodeFailed { run_id, .. }
+            | Self::NodeRetryScheduled { run_id, .. }
             | Self::NodeSkipped { run_id, .. }
+            | Self::FanOutExpanded { run_id, .. }
+            | Self::FanOutFinished { run_id, .. }
             | Self::RunCompleted { run_id, .. }
             | Self::RunFailed { run_id, .. }
-            | Self::RunCancelled { run_id, .. } => *run_id,
+            | Self::RunCancelled { run_id, .. }
+            | Self::RunRetried { run_id, .. } => *run_id,
         }
     }
 
@@ -311,13 +629,26 @@ impl ExecutionEvent {
         match self {
             Self::RunQueued { timestamp, .. }
             | Self::RunStarted { timestamp, .. }
+            | Self::RunSeeded { timestamp, .. }
             | Self::NodeStarted { timestamp, .. }
+            | Self::BranchTaken { timestamp, .. }
+            | Self::ApprovalRequested { timestamp, .. }
+            | Self::ApprovalResolved { timestamp, .. }
+            | Self::DelayStarted { timestamp, .. }
+            | Self::DelayEnded { timestamp, .. }
+            | Self::SubWorkflowStarted { timestamp, .. }
+            | Self::SubWorkflowEnded { timestamp, .. }
+            | Self::NodeSubstituted { timestamp, .. }
             | Self::NodeCompleted { timestamp, .. }
             | Self::NodeFailed { timestamp, .. }
+            | Self::NodeRetryScheduled { timestamp, .. }
             | Self::NodeSkipped { timestamp, .. }
+            | Self::FanOutExpanded { timestamp, .. }
+            | Self::FanOutFinished { timestamp, .. }
             | Self::RunCompleted { timestamp, .. }
             | Self::RunFailed { timestamp, .. }
-            | Self::RunCancelled { timestamp, .. } => *timestamp,
+            | Self::RunCancelled { timestamp, .. }
+            | Self::RunRetried { timestamp, .. } => *timestamp,
         }
     }
 }
@@ -379,11 +710,40 @@ mod tests {
         assert_eq!(exec.output_key, Some("output_123".to_string()));
     }
 
+    #[test]
+    fn retry_keeps_node_running() {
+        let mut exec = NodeExecution::new(WorkflowRunId::new(), NodeId::new());
+        assert_eq!(exec.attempt, 1);
+        exec.start(None);
+
+        let retry_at = Utc::now();
+        exec.retry(2, "timed out".to_string(), retry_at);
+        assert_eq!(exec.state, NodeExecutionState::Running);
+        assert_eq!(exec.attempt, 2);
+        assert_eq!(exec.retry_at, Some(retry_at));
+        assert_eq!(exec.error, Some("timed out".to_string()));
+
+        exec.complete("output_123".to_string());
+        assert_eq!(exec.error, None);
+    }
+
+    #[test]
+    fn branch_node_restricts_active_ports() {
+        let mut exec = NodeExecution::new(WorkflowRunId::new(), NodeId::new());
+        assert!(exec.is_port_active("high"));
+
+        exec.take_branch(vec!["high".to_string()]);
+        assert!(exec.is_port_active("high"));
+        assert!(!exec.is_port_active("low"));
+    }
+
     #[test]
     fn execution_event_serde_roundtrip() {
         let event = ExecutionEvent::NodeCompleted {
             run_id: WorkflowRunId::new(),
             node_id: NodeId::new(),
+            item_index: None,
+            attempt: 1,
             output_key: "key_123".to_string(),
             timestamp: Utc::now(),
         };
@@ -393,4 +753,34 @@ mod tests {
 
         assert_eq!(event.run_id(), parsed.run_id());
     }
+
+    #[test]
+    fn item_index_is_omitted_outside_fan_out() {
+        let run_id = WorkflowRunId::new();
+        let node_id = NodeId::new();
+        let event = ExecutionEvent::NodeSkipped {
+            run_id,
+            node_id,
+            item_index: None,
+            reason: "test".to_string(),
+            timestamp: Utc::now(),
+        };
+        let json = serde_json::to_value(&event).expect("serialize");
+        assert!(json.get("item_index").is_none());
+
+        let item_event = ExecutionEvent::NodeSkipped {
+            run_id,
+            node_id,
+            item_index: Some(2),
+            reason: "test".to_string(),
+            timestamp: Utc::now(),
+        };
+        let json = serde_json::to_string(&item_event).expect("serialize");
+        let parsed: ExecutionEvent = serde_json::from_str(&json).expect("deserialize");
+        assert_eq!(parsed, item_event);
+
+        let exec = NodeExecution::for_item(run_id, node_id, 2);
+        assert_eq!(exec.item_index, Some(2));
+        assert_eq!(exec.state, NodeExecutionState::Pending);
+    }
 }
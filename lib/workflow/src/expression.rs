@@ -0,0 +1,363 @@
+//! Expression language for Transform nodes and branch conditions.
+//!
+//! Expressions are small, sandboxed programs over a node's input value.
+//! They cannot perform I/O, read the clock, or loop unboundedly, so the
+//! same expression always produces the same result for the same input.
+//!
+//! # Syntax
+//!
+//! - Literals: numbers, `'single'` or `"double"` quoted strings, `true`,
+//!   `false`, `null`, arrays `[1, 2]`, and objects `{name: 'x', "a b": 1}`
+//! - Field paths: `confidence`, `email.from`, `labels[0]`, `labels[-1]`,
+//!   `email['from']`, `input.category`
+//! - Arithmetic: `+`, `-`, `*`, `/`, `%` on numbers; `+` also concatenates
+//!   strings and arrays
+//! - Comparisons: `==`, `!=`, `<`, `<=`, `>`, `>=`
+//! - Boolean operators: `&&` / `and`, `||` / `or`, `!` / `not`
+//! - Conditionals: `score > 0.5 ? 'keep' : 'drop'`
+//! - Function calls: `lower(subject)`, `filter(emails, e => e.unread)`
+//!
+//! Field paths resolve against the input value; the identifier `input`
+//! refers to the whole input unless the input has a field of that name.
+//! Inside a lambda (`item => ...`), the parameter name refers to the
+//! current element. Missing fields evaluate to `null`. Ordering comparisons
+//! between values that are not both numbers or both strings are false. A
+//! value used as a condition is true unless it is `null`, `false`, `0`, or
+//! empty.
+//!
+//! # Functions
+//!
+//! - Collections: `len`, `filter`, `map`, `any`, `all`, `find`, `first`,
+//!   `last`, `sum`, `min`, `max`, `sort`, `reverse`, `flatten`, `contains`,
+//!   `keys`, `values`, `join`
+//! - Strings: `lower`, `upper`, `trim`, `split`, `replace`, `starts_with`,
+//!   `ends_with`, `substring`
+//! - Numbers and conversion: `round`, `floor`, `ceil`, `abs`, `number`,
+//!   `string`, `default`
+//! - Dates (RFC 3339 strings in UTC): `date`, `format_date`, `add_days`,
+//!   `add_seconds`, `seconds_between`, `year`, `month`, `day`, `weekday`
+//!
+//! # Type checking
+//!
+//! [`Expression::check`] infers an expression's result [`Type`] from the
+//! type of its input, which is usually derived from a port's JSON Schema.
+//! It rejects expressions that misuse a value of a known type, such as
+//! `lower(5)`, `'a' * 2`, or reading a field of a number.
+
+mod eval;
+mod functions;
+mod lexer;
+mod parser;
+mod types;
+
+pub(crate) use functions::parse_date;
+pub use types::Type;
+
+use parser::Expr;
+use serde_json::Value as JsonValue;
+use std::fmt;
+
+/// A parsed expression.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expression {
+    expr: Expr,
+}
+
+impl Expression {
+    /// Parses an expression.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the expression is not valid syntax, calls an
+    /// unknown function, or passes the wrong number of arguments.
+    pub fn parse(source: &str) -> Result<Self, ExpressionError> {
+        let tokens = lexer::tokenize(source)?;
+        let expr = parser::parse(&tokens)?;
+        Ok(Self { expr })
+    }
+
+    /// Evaluates the expression against an input value.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an operation is applied to values it does not
+    /// support (e.g., dividing a string).
+    pub fn evaluate(&self, input: &JsonValue) -> Result<JsonValue, ExpressionError> {
+        eval::evaluate(&self.expr, input)
+    }
+
+    /// Evaluates the expression as a condition.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if evaluation fails.
+    pub fn evaluate_truthy(&self, input: &JsonValue) -> Result<bool, ExpressionError> {
+        self.evaluate(input).map(|value| eval::truthy(&value))
+    }
+
+    /// Infers the type of the expression's result for an input type.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the expression misuses a value whose type is
+    /// known from the input type.
+    pub fn check(&self, input: &Type) -> Result<Type, ExpressionError> {
+        types::check(&self.expr, input)
+    }
+}
+
+/// Errors from parsing, checking, or evaluating an expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExpressionError {
+    /// The expression is empty or ends unexpectedly.
+    UnexpectedEnd,
+    /// A token appeared where it is not allowed.
+    UnexpectedToken { token: String, position: usize },
+    /// A character that cannot start a token.
+    InvalidCharacter { character: char, position: usize },
+    /// A string literal is missing its closing quote.
+    UnterminatedString { position: usize },
+    /// A call to a function that does not exist.
+    UnknownFunction { name: String, position: usize },
+    /// A function was called with the wrong arguments.
+    InvalidArguments {
+        function: String,
+        reason: String,
+        position: usize,
+    },
+    /// The expression nests deeper than the parser allows.
+    NestingTooDeep { position: usize },
+    /// The expression cannot succeed for inputs of the checked type.
+    TypeMismatch { message: String, position: usize },
+    /// Evaluation failed.
+    EvaluationFailed { message: String },
+}
+
+impl fmt::Display for ExpressionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedEnd => write!(f, "unexpected end of expression"),
+            Self::UnexpectedToken { token, position } => {
+                write!(f, "unexpected '{token}' at position {position}")
+            }
+            Self::InvalidCharacter {
+                character,
+                position,
+            } => write!(f, "invalid character '{character}' at position {position}"),
+            Self::UnterminatedString { position } => {
+                write!(f, "unterminated string starting at position {position}")
+            }
+            Self::UnknownFunction { name, position } => {
+                write!(f, "unknown function '{name}' at position {position}")
+            }
+            Self::InvalidArguments {
+                function,
+                reason,
+                position,
+            } => write!(f, "{function}() at position {position}: {reason}"),
+            Self::NestingTooDeep { position } => {
+                write!(f, "expression nests too deeply at position {position}")
+            }
+            Self::TypeMismatch { message, position } => {
+                write!(f, "type error at position {position}: {message}")
+            }
+            Self::EvaluationFailed { message } => write!(f, "evaluation failed: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for ExpressionError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn eval(source: &str, input: &JsonValue) -> JsonValue {
+        Expression::parse(source)
+            .unwrap()
+            .evaluate(input)
+            .unwrap_or_else(|e| panic!("{source}: {e}"))
+    }
+
+    fn check(source: &str, input: &Type) -> Result<Type, ExpressionError> {
+        Expression::parse(source).unwrap().check(input)
+    }
+
+    #[test]
+    fn arithmetic_and_precedence() {
+        let input = json!({"a": 6, "b": 4});
+        assert_eq!(eval("a + b * 2", &input), json!(14));
+        assert_eq!(eval("(a + b) / 4", &input), json!(2.5));
+        assert_eq!(eval("a % b - -1", &input), json!(3));
+        assert_eq!(eval("'re: ' + 'hi'", &input), json!("re: hi"));
+        assert_eq!(eval("[1] + [2, 3]", &input), json!([1, 2, 3]));
+        assert_eq!(eval("a > 5 ? 'big' : 'small'", &input), json!("big"));
+    }
+
+    #[test]
+    fn builds_arrays_and_objects() {
+        let input = json!({"email": {"from": "a@example.com", "subject": " Hi "}});
+        assert_eq!(
+            eval(
+                "{sender: email.from, 'subject': trim(email.subject), tags: ['x']}",
+                &input
+            ),
+            json!({"sender": "a@example.com", "subject": "Hi", "tags": ["x"]})
+        );
+    }
+
+    #[test]
+    fn filters_and_maps_with_lambdas() {
+        let input = json!({"emails": [
+            {"subject": "A", "unread": true, "score": 3},
+            {"subject": "B", "unread": false, "score": 1},
+            {"subject": "C", "unread": true, "score": 2}
+        ]});
+        assert_eq!(
+            eval("map(filter(emails, e => e.unread), e => e.subject)", &input),
+            json!(["A", "C"])
+        );
+        assert_eq!(eval("sum(map(emails, e => e.score))", &input), json!(6));
+        assert_eq!(eval("any(emails, e => e.score > 2)", &input), json!(true));
+        assert_eq!(eval("all(emails, e => e.unread)", &input), json!(false));
+        assert_eq!(
+            eval("find(emails, e => !e.unread).subject", &input),
+            json!("B")
+        );
+        assert_eq!(eval("len(emails)", &input), json!(3));
+        assert_eq!(eval("emails[-1].subject", &input), json!("C"));
+        // The lambda parameter shadows input fields of the same name.
+        assert_eq!(
+            eval("map([1, 2], emails => emails * 10)", &input),
+            json!([10, 20])
+        );
+    }
+
+    #[test]
+    fn string_functions() {
+        let input = json!({"s": "Hello, World"});
+        assert_eq!(eval("lower(s)", &input), json!("hello, world"));
+        assert_eq!(eval("upper(substring(s, 0, 5))", &input), json!("HELLO"));
+        assert_eq!(eval("split(s, ', ')", &input), json!(["Hello", "World"]));
+        assert_eq!(
+            eval("join(split(s, ', '), '-')", &input),
+            json!("Hello-World")
+        );
+        assert_eq!(
+            eval("replace(s, 'World', 'there')", &input),
+            json!("Hello, there")
+        );
+        assert_eq!(eval("starts_with(s, 'Hell')", &input), json!(true));
+        assert_eq!(eval("contains(s, 'lo, W')", &input), json!(true));
+        assert_eq!(eval("string(1.5) + '!'", &input), json!("1.5!"));
+        assert_eq!(eval("number('42') + 1", &input), json!(43));
+        assert_eq!(eval("default(missing, 'n/a')", &input), json!("n/a"));
+    }
+
+    #[test]
+    fn date_functions() {
+        let input = json!({"sent": "2025-03-01T10:30:00+01:00"});
+        assert_eq!(eval("date(sent)", &input), json!("2025-03-01T09:30:00Z"));
+        assert_eq!(
+            eval("add_days(sent, 30)", &input),
+            json!("2025-03-31T09:30:00Z")
+        );
+        assert_eq!(
+            eval("format_date(sent, '%Y/%m/%d')", &input),
+            json!("2025/03/01")
+        );
+        assert_eq!(
+            eval("seconds_between('2025-03-01', sent)", &input),
+            json!(34200)
+        );
+        assert_eq!(eval("weekday(sent)", &input), json!(6));
+        assert_eq!(
+            eval("year(sent) * 100 + month(sent)", &input),
+            json!(202503)
+        );
+    }
+
+    #[test]
+    fn evaluation_errors() {
+        let input = json!({"s": "x", "n": 0});
+        let error = |source: &str| Expression::parse(source).unwrap().evaluate(&input);
+        assert!(matches!(
+            error("s * 2"),
+            Err(ExpressionError::EvaluationFailed { .. })
+        ));
+        assert!(matches!(
+            error("1 / n"),
+            Err(ExpressionError::EvaluationFailed { .. })
+        ));
+        assert!(matches!(
+            error("date('yesterday')"),
+            Err(ExpressionError::EvaluationFailed { .. })
+        ));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(matches!(
+            Expression::parse("shout(x)"),
+            Err(ExpressionError::UnknownFunction { position: 0, .. })
+        ));
+        assert!(matches!(
+            Expression::parse("lower(a, b)"),
+            Err(ExpressionError::InvalidArguments { .. })
+        ));
+        assert!(matches!(
+            Expression::parse("filter(items, 1)"),
+            Err(ExpressionError::InvalidArguments { .. })
+        ));
+        assert!(matches!(
+            Expression::parse("x => x"),
+            Err(ExpressionError::UnexpectedToken { .. })
+        ));
+        assert!(matches!(
+            Expression::parse(&"(".repeat(200)),
+            Err(ExpressionError::NestingTooDeep { .. })
+        ));
+    }
+
+    #[test]
+    fn type_checking() {
+        let input = Type::from_schema(&json!({
+            "type": "object",
+            "properties": {
+                "count": {"type": "integer"},
+                "subject": {"type": "string"},
+                "labels": {"type": "array", "items": {"type": "string"}}
+            }
+        }));
+
+        assert_eq!(check("count * 2", &input), Ok(Type::Number));
+        assert_eq!(
+            check("map(labels, l => upper(l))", &input),
+            Ok(Type::Array(Box::new(Type::String)))
+        );
+        assert_eq!(check("count > 1 && subject", &input), Ok(Type::Bool));
+        assert_eq!(check("unknown_field", &input), Ok(Type::Any));
+
+        assert!(matches!(
+            check("lower(count)", &input),
+            Err(ExpressionError::TypeMismatch { position: 0, .. })
+        ));
+        assert!(matches!(
+            check("subject * 2", &input),
+            Err(ExpressionError::TypeMismatch { .. })
+        ));
+        assert!(matches!(
+            check("count.value", &input),
+            Err(ExpressionError::TypeMismatch { .. })
+        ));
+        assert!(matches!(
+            check("filter(labels, l => l * 2)", &input),
+            Err(ExpressionError::TypeMismatch { .. })
+        ));
+
+        // Anything goes for untyped input, as long as it can succeed
+        assert_eq!(check("a.b[0] + 1", &Type::Any), Ok(Type::Number));
+        assert!(check("'a' - 1", &Type::Any).is_err());
+    }
+}
@@ -0,0 +1,279 @@
+//! Expression evaluation.
+
+use super::ExpressionError;
+use super::functions::Function;
+use super::lexer::CompareOp;
+use super::parser::{ArithmeticOp, Expr};
+use super::types::Type;
+use serde_json::Value as JsonValue;
+use std::cmp::Ordering;
+
+/// Largest magnitude at which every integer is exactly representable as an
+/// `f64`; integral results below it are emitted as JSON integers.
+const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_991.0;
+
+/// Evaluates an expression against an input value.
+pub(super) fn evaluate(expr: &Expr, input: &JsonValue) -> Result<JsonValue, ExpressionError> {
+    let mut scope = Scope {
+        input,
+        bindings: Vec::new(),
+    };
+    scope
+        .evaluate(expr)
+        .map_err(|message| ExpressionError::EvaluationFailed { message })
+}
+
+/// Lambda parameters in scope, innermost last.
+struct Scope<'a> {
+    input: &'a JsonValue,
+    bindings: Vec<(&'a str, JsonValue)>,
+}
+
+impl<'a> Scope<'a> {
+    fn evaluate(&mut self, expr: &'a Expr) -> Result<JsonValue, String> {
+        match expr {
+            Expr::Literal(value) => Ok(value.clone()),
+            Expr::Variable { name, .. } => Ok(self.variable(name)),
+            Expr::Field { target, name, .. } => Ok(self
+                .evaluate(target)?
+                .get(name)
+                .cloned()
+                .unwrap_or_default()),
+            Expr::Index { target, index, .. } => {
+                let target = self.evaluate(target)?;
+                let index = self.evaluate(index)?;
+                Ok(lookup(&target, &index))
+            }
+            Expr::Array(items) => items
+                .iter()
+                .map(|item| self.evaluate(item))
+                .collect::<Result<_, _>>()
+                .map(JsonValue::Array),
+            Expr::Object(entries) => {
+                let mut object = serde_json::Map::new();
+                for (key, value) in entries {
+                    object.insert(key.clone(), self.evaluate(value)?);
+                }
+                Ok(JsonValue::Object(object))
+            }
+            Expr::Negate { operand, .. } => match self.evaluate(operand)? {
+                JsonValue::Number(n) => number(-n.as_f64().unwrap_or_default()),
+                other => Err(format!("cannot negate {}", Type::of(&other).name())),
+            },
+            Expr::Not(operand) => Ok(JsonValue::Bool(!truthy(&self.evaluate(operand)?))),
+            Expr::Arithmetic {
+                op, left, right, ..
+            } => {
+                let left = self.evaluate(left)?;
+                let right = self.evaluate(right)?;
+                arithmetic(*op, left, right)
+            }
+            Expr::Compare {
+                op, left, right, ..
+            } => {
+                let left = self.evaluate(left)?;
+                let right = self.evaluate(right)?;
+                Ok(JsonValue::Bool(compare(&left, *op, &right)))
+            }
+            Expr::And(left, right) => Ok(JsonValue::Bool(
+                truthy(&self.evaluate(left)?) && truthy(&self.evaluate(right)?),
+            )),
+            Expr::Or(left, right) => Ok(JsonValue::Bool(
+                truthy(&self.evaluate(left)?) || truthy(&self.evaluate(right)?),
+            )),
+            Expr::Conditional {
+                condition,
+                then,
+                otherwise,
+            } => {
+                if truthy(&self.evaluate(condition)?) {
+                    self.evaluate(then)
+                } else {
+                    self.evaluate(otherwise)
+                }
+            }
+            Expr::Call { function, args, .. } => self.call(*function, args),
+            Expr::Lambda { .. } => Err("a lambda can only be passed to a function".to_string()),
+        }
+    }
+
+    fn variable(&self, name: &str) -> JsonValue {
+        if let Some((_, value)) = self.bindings.iter().rev().find(|(n, _)| *n == name) {
+            return value.clone();
+        }
+        // `input` names the whole value unless the input has such a field.
+        if name == "input" && self.input.get("input").is_none() {
+            return self.input.clone();
+        }
+        self.input.get(name).cloned().unwrap_or_default()
+    }
+
+    fn call(&mut self, function: Function, args: &'a [Expr]) -> Result<JsonValue, String> {
+        let Some(Expr::Lambda { param, body }) = args.get(1).filter(|_| function.takes_lambda())
+        else {
+            let values = args
+                .iter()
+                .map(|arg| self.evaluate(arg))
+                .collect::<Result<Vec<_>, _>>()?;
+            return function.call(&values);
+        };
+
+        let items = self.evaluate(&args[0])?;
+        let items = function.array(&items)?;
+        let mut results = Vec::with_capacity(items.len());
+        for item in items {
+            self.bindings.push((param, item.clone()));
+            let result = self.evaluate(body);
+            self.bindings.pop();
+            results.push(result?);
+        }
+
+        let mut matching = items
+            .iter()
+            .zip(&results)
+            .filter(|(_, result)| truthy(result))
+            .map(|(item, _)| item.clone());
+        Ok(match function {
+            Function::Filter => JsonValue::Array(matching.collect()),
+            Function::Find => matching.next().unwrap_or_default(),
+            Function::Any => JsonValue::Bool(results.iter().any(truthy)),
+            Function::All => JsonValue::Bool(results.iter().all(truthy)),
+            Function::Sort => {
+                let mut keyed: Vec<_> = items.iter().zip(&results).collect();
+                keyed.sort_by(|(_, a), (_, b)| order(a, b));
+                JsonValue::Array(keyed.into_iter().map(|(item, _)| item.clone()).collect())
+            }
+            _ => JsonValue::Array(results),
+        })
+    }
+}
+
+fn lookup(target: &JsonValue, index: &JsonValue) -> JsonValue {
+    let found = match (target, index) {
+        (JsonValue::Array(items), JsonValue::Number(n)) => n.as_f64().and_then(|n| {
+            if n.fract() != 0.0 {
+                return None;
+            }
+            // Negative indexes count from the end.
+            let n = if n < 0.0 { n + items.len() as f64 } else { n };
+            if n < 0.0 { None } else { items.get(n as usize) }
+        }),
+        (JsonValue::Object(_), JsonValue::String(key)) => target.get(key),
+        _ => None,
+    };
+    found.cloned().unwrap_or_default()
+}
+
+fn arithmetic(op: ArithmeticOp, left: JsonValue, right: JsonValue) -> Result<JsonValue, String> {
+    match (op, left, right) {
+        (_, JsonValue::Number(l), JsonValue::Number(r)) => {
+            let l = l.as_f64().unwrap_or_default();
+            let r = r.as_f64().unwrap_or_default();
+            match op {
+                ArithmeticOp::Add => number(l + r),
+                ArithmeticOp::Subtract => number(l - r),
+                ArithmeticOp::Multiply => number(l * r),
+                ArithmeticOp::Divide | ArithmeticOp::Remainder if r == 0.0 => {
+                    Err("division by zero".to_string())
+                }
+                ArithmeticOp::Divide => number(l / r),
+                ArithmeticOp::Remainder => number(l % r),
+            }
+        }
+        (ArithmeticOp::Add, JsonValue::String(l), JsonValue::String(r)) => {
+            Ok(JsonValue::String(l + &r))
+        }
+        (ArithmeticOp::Add, JsonValue::Array(mut l), JsonValue::Array(r)) => {
+            l.extend(r);
+            Ok(JsonValue::Array(l))
+        }
+        (op, left, right) => Err(format!(
+            "cannot {} {} and {}",
+            op.verb(),
+            Type::of(&left).name(),
+            Type::of(&right).name()
+        )),
+    }
+}
+
+/// Converts a number to JSON, using an integer when it is integral.
+pub(super) fn number(n: f64) -> Result<JsonValue, String> {
+    if n.fract() == 0.0 && n.abs() <= MAX_SAFE_INTEGER {
+        return Ok(JsonValue::from(n as i64));
+    }
+    serde_json::Number::from_f64(n)
+        .map(JsonValue::Number)
+        .ok_or_else(|| "result is not a finite number".to_string())
+}
+
+/// Compares two values. Numbers and strings are ordered; any other pair is
+/// only equal or not.
+fn compare(left: &JsonValue, op: CompareOp, right: &JsonValue) -> bool {
+    let ordering = match (left, right) {
+        (JsonValue::Number(l), JsonValue::Number(r)) => match (l.as_f64(), r.as_f64()) {
+            (Some(l), Some(r)) => l.partial_cmp(&r),
+            _ => None,
+        },
+        (JsonValue::String(l), JsonValue::String(r)) => Some(l.cmp(r)),
+        _ => None,
+    };
+
+    match op {
+        CompareOp::Eq => ordering.map_or_else(|| left == right, |o| o == Ordering::Equal),
+        CompareOp::Ne => ordering.map_or_else(|| left != right, |o| o != Ordering::Equal),
+        CompareOp::Lt => ordering == Some(Ordering::Less),
+        CompareOp::Le => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+        CompareOp::Gt => ordering == Some(Ordering::Greater),
+        CompareOp::Ge => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+    }
+}
+
+/// Whether two values are equal, treating `1` and `1.0` as the same.
+pub(super) fn equal(left: &JsonValue, right: &JsonValue) -> bool {
+    compare(left, CompareOp::Eq, right)
+}
+
+/// A total order for sorting: null, booleans, numbers, strings, arrays,
+/// then objects. Values of the same kind that cannot be ordered are equal.
+pub(super) fn order(left: &JsonValue, right: &JsonValue) -> Ordering {
+    fn rank(value: &JsonValue) -> u8 {
+        match value {
+            JsonValue::Null => 0,
+            JsonValue::Bool(_) => 1,
+            JsonValue::Number(_) => 2,
+            JsonValue::String(_) => 3,
+            JsonValue::Array(_) => 4,
+            JsonValue::Object(_) => 5,
+        }
+    }
+
+    match (left, right) {
+        (JsonValue::Bool(l), JsonValue::Bool(r)) => l.cmp(r),
+        (JsonValue::Number(l), JsonValue::Number(r)) => l
+            .as_f64()
+            .partial_cmp(&r.as_f64())
+            .unwrap_or(Ordering::Equal),
+        (JsonValue::String(l), JsonValue::String(r)) => l.cmp(r),
+        _ => rank(left).cmp(&rank(right)),
+    }
+}
+
+/// Renders a value as text: strings as-is, everything else as JSON.
+pub(super) fn stringify(value: &JsonValue) -> String {
+    match value {
+        JsonValue::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Whether a value counts as true in a condition.
+pub(super) fn truthy(value: &JsonValue) -> bool {
+    match value {
+        JsonValue::Null => false,
+        JsonValue::Bool(b) => *b,
+        JsonValue::Number(n) => n.as_f64().is_some_and(|n| n != 0.0),
+        JsonValue::String(s) => !s.is_empty(),
+        JsonValue::Array(a) => !a.is_empty(),
+        JsonValue::Object(o) => !o.is_empty(),
+    }
+}
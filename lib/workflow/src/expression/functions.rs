@@ -0,0 +1,483 @@
+//! Built-in functions.
+//!
+//! Functions that take a lambda (`filter`, `map`, ...) are evaluated and
+//! type checked by the evaluator and checker, which own lambda scopes.
+//! Everything else is implemented here on plain argument values.
+
+use super::eval::{equal, number, order, stringify};
+use super::types::Type;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, SecondsFormat, Utc};
+use serde_json::Value as JsonValue;
+use std::fmt::Write;
+
+macro_rules! functions {
+    ($($variant:ident => $name:literal, $min:literal..=$max:literal;)*) => {
+        /// A built-in function.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub(super) enum Function {
+            $($variant,)*
+        }
+
+        impl Function {
+            /// Looks up a function by name.
+            pub(super) fn from_name(name: &str) -> Option<Self> {
+                match name {
+                    $($name => Some(Self::$variant),)*
+                    _ => None,
+                }
+            }
+
+            /// The function's name.
+            pub(super) fn name(self) -> &'static str {
+                match self {
+                    $(Self::$variant => $name,)*
+                }
+            }
+
+            /// The minimum and maximum number of arguments.
+            pub(super) fn arity(self) -> (usize, usize) {
+                match self {
+                    $(Self::$variant => ($min, $max),)*
+                }
+            }
+        }
+    };
+}
+
+functions! {
+    Len => "len", 1..=1;
+    Filter => "filter", 2..=2;
+    Map => "map", 2..=2;
+    Any => "any", 2..=2;
+    All => "all", 2..=2;
+    Find => "find", 2..=2;
+    First => "first", 1..=1;
+    Last => "last", 1..=1;
+    Sum => "sum", 1..=1;
+    Min => "min", 1..=1;
+    Max => "max", 1..=1;
+    Sort => "sort", 1..=2;
+    Reverse => "reverse", 1..=1;
+    Flatten => "flatten", 1..=1;
+    Contains => "contains", 2..=2;
+    Keys => "keys", 1..=1;
+    Values => "values", 1..=1;
+    Join => "join", 2..=2;
+    Lower => "lower", 1..=1;
+    Upper => "upper", 1..=1;
+    Trim => "trim", 1..=1;
+    Split => "split", 2..=2;
+    Replace => "replace", 3..=3;
+    StartsWith => "starts_with", 2..=2;
+    EndsWith => "ends_with", 2..=2;
+    Substring => "substring", 2..=3;
+    Round => "round", 1..=2;
+    Floor => "floor", 1..=1;
+    Ceil => "ceil", 1..=1;
+    Abs => "abs", 1..=1;
+    Number => "number", 1..=1;
+    String => "string", 1..=1;
+    Default => "default", 2..=2;
+    Date => "date", 1..=1;
+    FormatDate => "format_date", 2..=2;
+    AddDays => "add_days", 2..=2;
+    AddSeconds => "add_seconds", 2..=2;
+    SecondsBetween => "seconds_between", 2..=2;
+    Year => "year", 1..=1;
+    Month => "month", 1..=1;
+    Day => "day", 1..=1;
+    Weekday => "weekday", 1..=1;
+}
+
+impl Function {
+    /// Whether the function takes a lambda as its second argument.
+    pub(super) fn takes_lambda(self) -> bool {
+        matches!(
+            self,
+            Self::Filter | Self::Map | Self::Any | Self::All | Self::Find | Self::Sort
+        )
+    }
+
+    /// Calls a function that does not take a lambda.
+    pub(super) fn call(self, args: &[JsonValue]) -> Result<JsonValue, String> {
+        let arg = |i: usize| args.get(i).unwrap_or(&JsonValue::Null);
+
+        match self {
+            Self::Len => match arg(0) {
+                JsonValue::Null => Ok(JsonValue::from(0)),
+                JsonValue::String(s) => Ok(JsonValue::from(s.chars().count())),
+                JsonValue::Array(a) => Ok(JsonValue::from(a.len())),
+                JsonValue::Object(o) => Ok(JsonValue::from(o.len())),
+                other => Err(self.wrong_type(other)),
+            },
+            Self::First => Ok(self.array(arg(0))?.first().cloned().unwrap_or_default()),
+            Self::Last => Ok(self.array(arg(0))?.last().cloned().unwrap_or_default()),
+            Self::Sum => {
+                let mut total = 0.0;
+                for item in self.array(arg(0))? {
+                    total += self.number(item)?;
+                }
+                number(total)
+            }
+            Self::Min | Self::Max => {
+                let items = self.array(arg(0))?;
+                if let Some(item) = items
+                    .iter()
+                    .find(|item| !matches!(item, JsonValue::Number(_) | JsonValue::String(_)))
+                {
+                    return Err(self.wrong_type(item));
+                }
+                let found = if self == Self::Min {
+                    items.iter().min_by(|a, b| order(a, b))
+                } else {
+                    items.iter().max_by(|a, b| order(a, b))
+                };
+                Ok(found.cloned().unwrap_or_default())
+            }
+            Self::Sort => {
+                let mut items = self.array(arg(0))?.to_vec();
+                items.sort_by(order);
+                Ok(JsonValue::Array(items))
+            }
+            Self::Reverse => match arg(0) {
+                JsonValue::String(s) => Ok(JsonValue::String(s.chars().rev().collect())),
+                other => Ok(JsonValue::Array(
+                    self.array(other)?.iter().rev().cloned().collect(),
+                )),
+            },
+            Self::Flatten => {
+                let mut flat = Vec::new();
+                for item in self.array(arg(0))? {
+                    match item {
+                        JsonValue::Array(inner) => flat.extend(inner.iter().cloned()),
+                        other => flat.push(other.clone()),
+                    }
+                }
+                Ok(JsonValue::Array(flat))
+            }
+            Self::Contains => match (arg(0), arg(1)) {
+                (JsonValue::String(s), JsonValue::String(part)) => {
+                    Ok(JsonValue::Bool(s.contains(part.as_str())))
+                }
+                (JsonValue::Array(items), value) => {
+                    Ok(JsonValue::Bool(items.iter().any(|item| equal(item, value))))
+                }
+                (JsonValue::Object(o), JsonValue::String(key)) => {
+                    Ok(JsonValue::Bool(o.contains_key(key)))
+                }
+                (JsonValue::Null, _) => Ok(JsonValue::Bool(false)),
+                (other, _) => Err(self.wrong_type(other)),
+            },
+            Self::Keys => Ok(JsonValue::Array(
+                self.object(arg(0))?
+                    .keys()
+                    .map(|k| JsonValue::String(k.clone()))
+                    .collect(),
+            )),
+            Self::Values => Ok(JsonValue::Array(
+                self.object(arg(0))?.values().cloned().collect(),
+            )),
+            Self::Join => {
+                let separator = self.string(arg(1))?;
+                let parts: Vec<_> = self.array(arg(0))?.iter().map(stringify).collect();
+                Ok(JsonValue::String(parts.join(separator)))
+            }
+            Self::Lower => Ok(JsonValue::String(self.string(arg(0))?.to_lowercase())),
+            Self::Upper => Ok(JsonValue::String(self.string(arg(0))?.to_uppercase())),
+            Self::Trim => Ok(JsonValue::String(self.string(arg(0))?.trim().to_string())),
+            Self::Split => {
+                let s = self.string(arg(0))?;
+                let separator = self.string(arg(1))?;
+                let parts = if separator.is_empty() {
+                    s.chars()
+                        .map(|c| JsonValue::String(c.to_string()))
+                        .collect()
+                } else {
+                    s.split(separator)
+                        .map(|part| JsonValue::String(part.to_string()))
+                        .collect()
+                };
+                Ok(JsonValue::Array(parts))
+            }
+            Self::Replace => Ok(JsonValue::String(
+                self.string(arg(0))?
+                    .replace(self.string(arg(1))?, self.string(arg(2))?),
+            )),
+            Self::StartsWith => Ok(JsonValue::Bool(
+                self.string(arg(0))?.starts_with(self.string(arg(1))?),
+            )),
+            Self::EndsWith => Ok(JsonValue::Bool(
+                self.string(arg(0))?.ends_with(self.string(arg(1))?),
+            )),
+            Self::Substring => {
+                let chars: Vec<char> = self.string(arg(0))?.chars().collect();
+                let clamp = |n: f64| n.max(0.0).min(chars.len() as f64) as usize;
+                let start = clamp(self.number(arg(1))?);
+                let end = match arg(2) {
+                    JsonValue::Null => chars.len(),
+                    end => clamp(self.number(end)?),
+                };
+                Ok(JsonValue::String(
+                    chars[start..end.max(start)].iter().collect(),
+                ))
+            }
+            Self::Round => {
+                let n = self.number(arg(0))?;
+                let digits = match arg(1) {
+                    JsonValue::Null => 0.0,
+                    digits => self.number(digits)?.clamp(0.0, 15.0).trunc(),
+                };
+                let scale = 10f64.powf(digits);
+                number((n * scale).round() / scale)
+            }
+            Self::Floor => number(self.number(arg(0))?.floor()),
+            Self::Ceil => number(self.number(arg(0))?.ceil()),
+            Self::Abs => number(self.number(arg(0))?.abs()),
+            Self::Number => match arg(0) {
+                JsonValue::Number(n) => Ok(JsonValue::Number(n.clone())),
+                JsonValue::Bool(b) => Ok(JsonValue::from(u8::from(*b))),
+                JsonValue::String(s) => match s.trim().parse::<f64>() {
+                    Ok(n) => number(n),
+                    Err(_) => Err(format!("number(): '{s}' is not a number")),
+                },
+                other => Err(self.wrong_type(other)),
+            },
+            Self::String => Ok(JsonValue::String(stringify(arg(0)))),
+            Self::Default => match arg(0) {
+                JsonValue::Null => Ok(arg(1).clone()),
+                value => Ok(value.clone()),
+            },
+            Self::Date => Ok(date_value(self.date(arg(0))?)),
+            Self::FormatDate => {
+                let date = self.date(arg(0))?;
+                let format = self.string(arg(1))?;
+                let mut formatted = String::new();
+                write!(formatted, "{}", date.format(format))
+                    .map_err(|_| format!("format_date(): invalid format '{format}'"))?;
+                Ok(JsonValue::String(formatted))
+            }
+            Self::AddDays | Self::AddSeconds => {
+                let date = self.date(arg(0))?;
+                let amount = self.number(arg(1))?;
+                let seconds = if self == Self::AddDays {
+                    amount * 86_400.0
+                } else {
+                    amount
+                };
+                Duration::try_milliseconds((seconds * 1000.0).round() as i64)
+                    .and_then(|offset| date.checked_add_signed(offset))
+                    .map(date_value)
+                    .ok_or_else(|| format!("{}(): date out of range", self.name()))
+            }
+            Self::SecondsBetween => {
+                let from = self.date(arg(0))?;
+                let to = self.date(arg(1))?;
+                number((to - from).num_milliseconds() as f64 / 1000.0)
+            }
+            Self::Year => Ok(JsonValue::from(self.date(arg(0))?.year())),
+            Self::Month => Ok(JsonValue::from(self.date(arg(0))?.month())),
+            Self::Day => Ok(JsonValue::from(self.date(arg(0))?.day())),
+            Self::Weekday => Ok(JsonValue::from(
+                self.date(arg(0))?.weekday().number_from_monday(),
+            )),
+            Self::Filter | Self::Map | Self::Any | Self::All | Self::Find => {
+                Err(format!("{}() requires a lambda", self.name()))
+            }
+        }
+    }
+
+    /// Infers the result type of a function that does not take a lambda.
+    pub(super) fn return_type(self, args: &[Type]) -> Result<Type, String> {
+        let arg = |i: usize| args.get(i).cloned().unwrap_or(Type::Null);
+        let string = Type::String;
+        let num = Type::Number;
+
+        match self {
+            Self::Len => {
+                self.expect(&arg(0), &[Type::String, Type::array(), Type::object()])?;
+                Ok(num)
+            }
+            Self::First | Self::Last | Self::Min | Self::Max => {
+                self.expect(&arg(0), &[Type::array()])?;
+                Ok(arg(0).element())
+            }
+            Self::Sum => {
+                self.expect(&arg(0), &[Type::array()])?;
+                self.expect(&arg(0).element(), &[Type::Number])?;
+                Ok(num)
+            }
+            Self::Sort => {
+                self.expect(&arg(0), &[Type::array()])?;
+                Ok(arg(0))
+            }
+            Self::Reverse => {
+                self.expect(&arg(0), &[Type::String, Type::array()])?;
+                Ok(arg(0))
+            }
+            Self::Flatten => {
+                self.expect(&arg(0), &[Type::array()])?;
+                Ok(match arg(0).element() {
+                    Type::Array(inner) => Type::Array(inner),
+                    _ => Type::array(),
+                })
+            }
+            Self::Contains => {
+                self.expect(&arg(0), &[Type::String, Type::array(), Type::object()])?;
+                Ok(Type::Bool)
+            }
+            Self::Keys => {
+                self.expect(&arg(0), &[Type::object()])?;
+                Ok(Type::Array(Box::new(string)))
+            }
+            Self::Values => {
+                self.expect(&arg(0), &[Type::object()])?;
+                Ok(Type::array())
+            }
+            Self::Join => {
+                self.expect(&arg(0), &[Type::array()])?;
+                self.expect(&arg(1), &[Type::String])?;
+                Ok(string)
+            }
+            Self::Lower | Self::Upper | Self::Trim => {
+                self.expect(&arg(0), &[Type::String])?;
+                Ok(string)
+            }
+            Self::Split => {
+                self.expect(&arg(0), &[Type::String])?;
+                self.expect(&arg(1), &[Type::String])?;
+                Ok(Type::Array(Box::new(string)))
+            }
+            Self::Replace => {
+                for i in 0..3 {
+                    self.expect(&arg(i), &[Type::String])?;
+                }
+                Ok(string)
+            }
+            Self::StartsWith | Self::EndsWith => {
+                self.expect(&arg(0), &[Type::String])?;
+                self.expect(&arg(1), &[Type::String])?;
+                Ok(Type::Bool)
+            }
+            Self::Substring => {
+                self.expect(&arg(0), &[Type::String])?;
+                for i in 1..args.len() {
+                    self.expect(&arg(i), &[Type::Number])?;
+                }
+                Ok(string)
+            }
+            Self::Round | Self::Floor | Self::Ceil | Self::Abs => {
+                for i in 0..args.len() {
+                    self.expect(&arg(i), &[Type::Number])?;
+                }
+                Ok(num)
+            }
+            Self::Number => {
+                self.expect(&arg(0), &[Type::Number, Type::String, Type::Bool])?;
+                Ok(num)
+            }
+            Self::String => Ok(string),
+            Self::Default => Ok(match arg(0) {
+                Type::Null => arg(1),
+                first => first.union(&arg(1)),
+            }),
+            Self::Date | Self::AddDays | Self::AddSeconds | Self::FormatDate => {
+                self.expect(&arg(0), &[Type::String, Type::Number])?;
+                if args.len() > 1 {
+                    let second = if self == Self::FormatDate {
+                        Type::String
+                    } else {
+                        Type::Number
+                    };
+                    self.expect(&arg(1), &[second])?;
+                }
+                Ok(string)
+            }
+            Self::SecondsBetween => {
+                self.expect(&arg(0), &[Type::String, Type::Number])?;
+                self.expect(&arg(1), &[Type::String, Type::Number])?;
+                Ok(num)
+            }
+            Self::Year | Self::Month | Self::Day | Self::Weekday => {
+                self.expect(&arg(0), &[Type::String, Type::Number])?;
+                Ok(num)
+            }
+            Self::Filter | Self::Map | Self::Any | Self::All | Self::Find => {
+                Err(format!("{}() requires a lambda", self.name()))
+            }
+        }
+    }
+
+    fn expect(self, actual: &Type, allowed: &[Type]) -> Result<(), String> {
+        if allowed.iter().any(|allowed| actual.may_be(allowed)) {
+            Ok(())
+        } else {
+            let allowed: Vec<_> = allowed.iter().map(Type::name).collect();
+            Err(format!(
+                "{}() expects {}, found {}",
+                self.name(),
+                allowed.join(" or "),
+                actual.name()
+            ))
+        }
+    }
+
+    fn wrong_type(self, value: &JsonValue) -> String {
+        format!(
+            "{}() does not accept {}",
+            self.name(),
+            Type::of(value).name()
+        )
+    }
+
+    pub(super) fn array(self, value: &JsonValue) -> Result<&[JsonValue], String> {
+        match value {
+            JsonValue::Array(items) => Ok(items),
+            JsonValue::Null => Ok(&[]),
+            other => Err(self.wrong_type(other)),
+        }
+    }
+
+    fn object(self, value: &JsonValue) -> Result<&serde_json::Map<String, JsonValue>, String> {
+        value.as_object().ok_or_else(|| self.wrong_type(value))
+    }
+
+    fn string(self, value: &JsonValue) -> Result<&str, String> {
+        value.as_str().ok_or_else(|| self.wrong_type(value))
+    }
+
+    fn number(self, value: &JsonValue) -> Result<f64, String> {
+        value.as_f64().ok_or_else(|| self.wrong_type(value))
+    }
+
+    fn date(self, value: &JsonValue) -> Result<DateTime<Utc>, String> {
+        parse_date(value).ok_or_else(|| match value {
+            JsonValue::String(s) => format!("{}(): '{s}' is not a date", self.name()),
+            other => self.wrong_type(other),
+        })
+    }
+}
+
+/// Parses an RFC 3339 timestamp, a `YYYY-MM-DD` date (midnight UTC), or a
+/// Unix timestamp in seconds.
+pub(crate) fn parse_date(value: &JsonValue) -> Option<DateTime<Utc>> {
+    match value {
+        JsonValue::String(s) => DateTime::parse_from_rfc3339(s)
+            .map(|date| date.with_timezone(&Utc))
+            .ok()
+            .or_else(|| {
+                NaiveDate::parse_from_str(s, "%Y-%m-%d")
+                    .ok()
+                    .and_then(|date| date.and_hms_opt(0, 0, 0))
+                    .map(|date| date.and_utc())
+            }),
+        JsonValue::Number(n) => {
+            let seconds = n.as_f64()?;
+            DateTime::from_timestamp_millis((seconds * 1000.0).round() as i64)
+        }
+        _ => None,
+    }
+}
+
+fn date_value(date: DateTime<Utc>) -> JsonValue {
+    JsonValue::String(date.to_rfc3339_opts(SecondsFormat::AutoSi, true))
+}
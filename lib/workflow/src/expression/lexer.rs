@@ -0,0 +1,217 @@
+//! Tokenizer for the expression language.
+
+use super::ExpressionError;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Token {
+    Number(f64),
+    Str(String),
+    Ident(String),
+    Dot,
+    Comma,
+    Colon,
+    Question,
+    Arrow,
+    LBracket,
+    RBracket,
+    LParen,
+    RParen,
+    LBrace,
+    RBrace,
+    Op(CompareOp),
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Percent,
+    And,
+    Or,
+    Not,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Number(n) => write!(f, "{n}"),
+            Self::Str(s) => write!(f, "\"{s}\""),
+            Self::Ident(s) => write!(f, "{s}"),
+            Self::Dot => write!(f, "."),
+            Self::Comma => write!(f, ","),
+            Self::Colon => write!(f, ":"),
+            Self::Question => write!(f, "?"),
+            Self::Arrow => write!(f, "=>"),
+            Self::LBracket => write!(f, "["),
+            Self::RBracket => write!(f, "]"),
+            Self::LParen => write!(f, "("),
+            Self::RParen => write!(f, ")"),
+            Self::LBrace => write!(f, "{{"),
+            Self::RBrace => write!(f, "}}"),
+            Self::Op(op) => write!(f, "{op}"),
+            Self::Plus => write!(f, "+"),
+            Self::Minus => write!(f, "-"),
+            Self::Star => write!(f, "*"),
+            Self::Slash => write!(f, "/"),
+            Self::Percent => write!(f, "%"),
+            Self::And => write!(f, "&&"),
+            Self::Or => write!(f, "||"),
+            Self::Not => write!(f, "!"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl fmt::Display for CompareOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::Eq => "==",
+            Self::Ne => "!=",
+            Self::Lt => "<",
+            Self::Le => "<=",
+            Self::Gt => ">",
+            Self::Ge => ">=",
+        };
+        write!(f, "{s}")
+    }
+}
+
+/// Splits an expression into tokens, each with its character position.
+pub(super) fn tokenize(source: &str) -> Result<Vec<(usize, Token)>, ExpressionError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        let next = chars.get(i + 1).copied();
+
+        let token = match c {
+            c if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            '.' => Token::Dot,
+            ',' => Token::Comma,
+            ':' => Token::Colon,
+            '?' => Token::Question,
+            '[' => Token::LBracket,
+            ']' => Token::RBracket,
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            '{' => Token::LBrace,
+            '}' => Token::RBrace,
+            '+' => Token::Plus,
+            '-' => Token::Minus,
+            '*' => Token::Star,
+            '/' => Token::Slash,
+            '%' => Token::Percent,
+            '=' if next == Some('=') => {
+                i += 1;
+                Token::Op(CompareOp::Eq)
+            }
+            '=' if next == Some('>') => {
+                i += 1;
+                Token::Arrow
+            }
+            '!' if next == Some('=') => {
+                i += 1;
+                Token::Op(CompareOp::Ne)
+            }
+            '!' => Token::Not,
+            '<' if next == Some('=') => {
+                i += 1;
+                Token::Op(CompareOp::Le)
+            }
+            '<' => Token::Op(CompareOp::Lt),
+            '>' if next == Some('=') => {
+                i += 1;
+                Token::Op(CompareOp::Ge)
+            }
+            '>' => Token::Op(CompareOp::Gt),
+            '&' if next == Some('&') => {
+                i += 1;
+                Token::And
+            }
+            '|' if next == Some('|') => {
+                i += 1;
+                Token::Or
+            }
+            '\'' | '"' => {
+                let quote = c;
+                let mut value = String::new();
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        None => {
+                            return Err(ExpressionError::UnterminatedString { position: start });
+                        }
+                        Some('\\') if chars.get(i + 1).is_some() => {
+                            value.push(chars[i + 1]);
+                            i += 2;
+                        }
+                        Some(&ch) if ch == quote => break,
+                        Some(&ch) => {
+                            value.push(ch);
+                            i += 1;
+                        }
+                    }
+                }
+                Token::Str(value)
+            }
+            c if c.is_ascii_digit() => {
+                let mut end = i + 1;
+                while end < chars.len()
+                    && (chars[end].is_ascii_digit()
+                        || (chars[end] == '.'
+                            && chars.get(end + 1).is_some_and(char::is_ascii_digit)))
+                {
+                    end += 1;
+                }
+                let text: String = chars[i..end].iter().collect();
+                let number = text
+                    .parse::<f64>()
+                    .map_err(|_| ExpressionError::UnexpectedToken {
+                        token: text.clone(),
+                        position: start,
+                    })?;
+                i = end - 1;
+                Token::Number(number)
+            }
+            c if c.is_alphabetic() || c == '_' || c == '$' => {
+                let mut end = i + 1;
+                while end < chars.len() && (chars[end].is_alphanumeric() || chars[end] == '_') {
+                    end += 1;
+                }
+                let word: String = chars[i..end].iter().collect();
+                i = end - 1;
+                match word.as_str() {
+                    "and" => Token::And,
+                    "or" => Token::Or,
+                    "not" => Token::Not,
+                    _ => Token::Ident(word),
+                }
+            }
+            other => {
+                return Err(ExpressionError::InvalidCharacter {
+                    character: other,
+                    position: start,
+                });
+            }
+        };
+
+        tokens.push((start, token));
+        i += 1;
+    }
+
+    Ok(tokens)
+}
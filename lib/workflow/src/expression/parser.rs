@@ -0,0 +1,481 @@
+//! Recursive-descent parser for the expression language.
+//!
+//! Precedence, from loosest to tightest: `?:`, `||`, `&&`, `!`,
+//! comparisons, `+ -`, `* / %`, unary `-`, then field access, indexing,
+//! and calls.
+
+use super::ExpressionError;
+use super::eval::number;
+use super::functions::Function;
+use super::lexer::{CompareOp, Token};
+use serde_json::Value as JsonValue;
+
+/// Maximum nesting of sub-expressions, counting each operator in a chain
+/// such as `a + b + c` as one level. Keeps evaluation and checking stack
+/// usage bounded.
+const MAX_DEPTH: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum ArithmeticOp {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+}
+
+impl ArithmeticOp {
+    pub(super) fn verb(self) -> &'static str {
+        match self {
+            Self::Add => "add",
+            Self::Subtract => "subtract",
+            Self::Multiply => "multiply",
+            Self::Divide => "divide",
+            Self::Remainder => "take the remainder of",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Expr {
+    Literal(JsonValue),
+    /// An identifier: a lambda parameter or a field of the input.
+    Variable {
+        name: String,
+        position: usize,
+    },
+    Field {
+        target: Box<Expr>,
+        name: String,
+        position: usize,
+    },
+    Index {
+        target: Box<Expr>,
+        index: Box<Expr>,
+        position: usize,
+    },
+    Array(Vec<Expr>),
+    Object(Vec<(String, Expr)>),
+    Negate {
+        operand: Box<Expr>,
+        position: usize,
+    },
+    Not(Box<Expr>),
+    Arithmetic {
+        op: ArithmeticOp,
+        left: Box<Expr>,
+        right: Box<Expr>,
+        position: usize,
+    },
+    Compare {
+        op: CompareOp,
+        left: Box<Expr>,
+        right: Box<Expr>,
+        position: usize,
+    },
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Conditional {
+        condition: Box<Expr>,
+        then: Box<Expr>,
+        otherwise: Box<Expr>,
+    },
+    Call {
+        function: Function,
+        args: Vec<Expr>,
+        position: usize,
+    },
+    /// Only valid as the lambda argument of a call.
+    Lambda {
+        param: String,
+        body: Box<Expr>,
+    },
+}
+
+/// Parses a token stream into an expression.
+pub(super) fn parse(tokens: &[(usize, Token)]) -> Result<Expr, ExpressionError> {
+    let mut parser = Parser {
+        tokens,
+        pos: 0,
+        depth: 0,
+    };
+    let expr = parser.parse_conditional()?;
+    if let Some((position, token)) = parser.peek() {
+        return Err(ExpressionError::UnexpectedToken {
+            token: token.to_string(),
+            position,
+        });
+    }
+    Ok(expr)
+}
+
+struct Parser<'a> {
+    tokens: &'a [(usize, Token)],
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<(usize, &Token)> {
+        self.tokens.get(self.pos).map(|(p, t)| (*p, t))
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<&Token> {
+        self.tokens.get(self.pos + offset).map(|(_, t)| t)
+    }
+
+    fn next(&mut self) -> Result<(usize, Token), ExpressionError> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or(ExpressionError::UnexpectedEnd)?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn eat(&mut self, expected: &Token) -> bool {
+        if self.peek().is_some_and(|(_, t)| t == expected) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, expected: &Token) -> Result<(), ExpressionError> {
+        if self.eat(expected) {
+            Ok(())
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    /// Parses a nested sub-expression, enforcing the depth limit.
+    fn nested<T>(
+        &mut self,
+        position: usize,
+        parse: impl FnOnce(&mut Self) -> Result<T, ExpressionError>,
+    ) -> Result<T, ExpressionError> {
+        let depth = self.depth;
+        self.deepen(position)?;
+        let result = parse(self);
+        self.depth = depth;
+        result
+    }
+
+    /// Adds a level of nesting, failing if the limit is reached.
+    fn deepen(&mut self, position: usize) -> Result<(), ExpressionError> {
+        if self.depth >= MAX_DEPTH {
+            return Err(ExpressionError::NestingTooDeep { position });
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn parse_conditional(&mut self) -> Result<Expr, ExpressionError> {
+        let condition = self.parse_or()?;
+        if let Some((position, Token::Question)) = self.peek() {
+            self.pos += 1;
+            return self.nested(position, |parser| {
+                let then = parser.parse_conditional()?;
+                parser.expect(&Token::Colon)?;
+                let otherwise = parser.parse_conditional()?;
+                Ok(Expr::Conditional {
+                    condition: Box::new(condition),
+                    then: Box::new(then),
+                    otherwise: Box::new(otherwise),
+                })
+            });
+        }
+        Ok(condition)
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, ExpressionError> {
+        let depth = self.depth;
+        let mut left = self.parse_and()?;
+        while let Some((position, Token::Or)) = self.peek() {
+            self.pos += 1;
+            self.deepen(position)?;
+            left = Expr::Or(Box::new(left), Box::new(self.parse_and()?));
+        }
+        self.depth = depth;
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, ExpressionError> {
+        let depth = self.depth;
+        let mut left = self.parse_not()?;
+        while let Some((position, Token::And)) = self.peek() {
+            self.pos += 1;
+            self.deepen(position)?;
+            left = Expr::And(Box::new(left), Box::new(self.parse_not()?));
+        }
+        self.depth = depth;
+        Ok(left)
+    }
+
+    fn parse_not(&mut self) -> Result<Expr, ExpressionError> {
+        if let Some((position, Token::Not)) = self.peek() {
+            self.pos += 1;
+            return self.nested(position, |parser| {
+                Ok(Expr::Not(Box::new(parser.parse_not()?)))
+            });
+        }
+        self.parse_compare()
+    }
+
+    fn parse_compare(&mut self) -> Result<Expr, ExpressionError> {
+        let left = self.parse_additive()?;
+        if let Some((position, Token::Op(op))) = self.peek() {
+            let op = *op;
+            self.pos += 1;
+            let right = self.parse_additive()?;
+            return Ok(Expr::Compare {
+                op,
+                left: Box::new(left),
+                right: Box::new(right),
+                position,
+            });
+        }
+        Ok(left)
+    }
+
+    fn parse_additive(&mut self) -> Result<Expr, ExpressionError> {
+        let depth = self.depth;
+        let mut left = self.parse_multiplicative()?;
+        while let Some((position, token)) = self.peek() {
+            let op = match token {
+                Token::Plus => ArithmeticOp::Add,
+                Token::Minus => ArithmeticOp::Subtract,
+                _ => break,
+            };
+            self.pos += 1;
+            self.deepen(position)?;
+            let right = self.parse_multiplicative()?;
+            left = Expr::Arithmetic {
+                op,
+                left: Box::new(left),
+                right: Box::new(right),
+                position,
+            };
+        }
+        self.depth = depth;
+        Ok(left)
+    }
+
+    fn parse_multiplicative(&mut self) -> Result<Expr, ExpressionError> {
+        let depth = self.depth;
+        let mut left = self.parse_unary()?;
+        while let Some((position, token)) = self.peek() {
+            let op = match token {
+                Token::Star => ArithmeticOp::Multiply,
+                Token::Slash => ArithmeticOp::Divide,
+                Token::Percent => ArithmeticOp::Remainder,
+                _ => break,
+            };
+            self.pos += 1;
+            self.deepen(position)?;
+            let right = self.parse_unary()?;
+            left = Expr::Arithmetic {
+                op,
+                left: Box::new(left),
+                right: Box::new(right),
+                position,
+            };
+        }
+        self.depth = depth;
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, ExpressionError> {
+        if let Some((position, Token::Minus)) = self.peek() {
+            self.pos += 1;
+            return self.nested(position, |parser| {
+                Ok(Expr::Negate {
+                    operand: Box::new(parser.parse_unary()?),
+                    position,
+                })
+            });
+        }
+        self.parse_postfix()
+    }
+
+    fn parse_postfix(&mut self) -> Result<Expr, ExpressionError> {
+        let depth = self.depth;
+        let mut expr = self.parse_primary()?;
+        loop {
+            if let Some((position, Token::Dot)) = self.peek() {
+                self.pos += 1;
+                self.deepen(position)?;
+                match self.next()? {
+                    (_, Token::Ident(name)) => {
+                        expr = Expr::Field {
+                            target: Box::new(expr),
+                            name,
+                            position,
+                        };
+                    }
+                    (position, other) => {
+                        return Err(ExpressionError::UnexpectedToken {
+                            token: other.to_string(),
+                            position,
+                        });
+                    }
+                }
+            } else if let Some((position, Token::LBracket)) = self.peek() {
+                self.pos += 1;
+                self.deepen(position)?;
+                let index = self.parse_conditional()?;
+                self.expect(&Token::RBracket)?;
+                expr = Expr::Index {
+                    target: Box::new(expr),
+                    index: Box::new(index),
+                    position,
+                };
+            } else {
+                self.depth = depth;
+                return Ok(expr);
+            }
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, ExpressionError> {
+        let (position, token) = self.next()?;
+        match token {
+            Token::Number(n) => Ok(Expr::Literal(number(n).unwrap_or_default())),
+            Token::Str(s) => Ok(Expr::Literal(JsonValue::String(s))),
+            Token::Ident(word) => match word.as_str() {
+                "true" => Ok(Expr::Literal(JsonValue::Bool(true))),
+                "false" => Ok(Expr::Literal(JsonValue::Bool(false))),
+                "null" => Ok(Expr::Literal(JsonValue::Null)),
+                _ if self.peek_at(0) == Some(&Token::LParen) => self.parse_call(word, position),
+                _ => Ok(Expr::Variable {
+                    name: word,
+                    position,
+                }),
+            },
+            Token::LParen => self.nested(position, |parser| {
+                let inner = parser.parse_conditional()?;
+                parser.expect(&Token::RParen)?;
+                Ok(inner)
+            }),
+            Token::LBracket => self.nested(position, |parser| {
+                let items = parser.parse_list(&Token::RBracket, Self::parse_conditional)?;
+                Ok(Expr::Array(items))
+            }),
+            Token::LBrace => self.nested(position, |parser| {
+                let entries = parser.parse_list(&Token::RBrace, Self::parse_entry)?;
+                Ok(Expr::Object(entries))
+            }),
+            other => Err(ExpressionError::UnexpectedToken {
+                token: other.to_string(),
+                position,
+            }),
+        }
+    }
+
+    /// Parses comma-separated items up to and including the closing token.
+    fn parse_list<T>(
+        &mut self,
+        close: &Token,
+        mut item: impl FnMut(&mut Self) -> Result<T, ExpressionError>,
+    ) -> Result<Vec<T>, ExpressionError> {
+        let mut items = Vec::new();
+        if self.eat(close) {
+            return Ok(items);
+        }
+        loop {
+            items.push(item(self)?);
+            if self.eat(close) {
+                return Ok(items);
+            }
+            self.expect(&Token::Comma)?;
+        }
+    }
+
+    fn parse_entry(&mut self) -> Result<(String, Expr), ExpressionError> {
+        let key = match self.next()? {
+            (_, Token::Ident(key) | Token::Str(key)) => key,
+            (position, other) => {
+                return Err(ExpressionError::UnexpectedToken {
+                    token: other.to_string(),
+                    position,
+                });
+            }
+        };
+        self.expect(&Token::Colon)?;
+        Ok((key, self.parse_conditional()?))
+    }
+
+    fn parse_call(&mut self, name: String, position: usize) -> Result<Expr, ExpressionError> {
+        let function = Function::from_name(&name)
+            .ok_or(ExpressionError::UnknownFunction { name, position })?;
+        self.expect(&Token::LParen)?;
+
+        let args = self.nested(position, |parser| {
+            parser.parse_list(&Token::RParen, Self::parse_argument)
+        })?;
+
+        let invalid = |reason: String| ExpressionError::InvalidArguments {
+            function: function.name().to_string(),
+            reason,
+            position,
+        };
+        let (min, max) = function.arity();
+        if args.len() < min || args.len() > max {
+            let expected = if min == max {
+                min.to_string()
+            } else {
+                format!("{min} to {max}")
+            };
+            return Err(invalid(format!(
+                "expected {expected} arguments, found {}",
+                args.len()
+            )));
+        }
+        for (i, arg) in args.iter().enumerate() {
+            let is_lambda = matches!(arg, Expr::Lambda { .. });
+            let wants_lambda = function.takes_lambda() && i == 1;
+            if is_lambda && !wants_lambda {
+                return Err(invalid(format!("argument {} cannot be a lambda", i + 1)));
+            }
+            if wants_lambda && !is_lambda {
+                return Err(invalid(format!(
+                    "argument {} must be a lambda such as `item => item.field`",
+                    i + 1
+                )));
+            }
+        }
+
+        Ok(Expr::Call {
+            function,
+            args,
+            position,
+        })
+    }
+
+    fn parse_argument(&mut self) -> Result<Expr, ExpressionError> {
+        if let (Some(Token::Ident(param)), Some(Token::Arrow)) = (self.peek_at(0), self.peek_at(1))
+        {
+            let param = param.clone();
+            self.pos += 2;
+            let body = self.parse_conditional()?;
+            return Ok(Expr::Lambda {
+                param,
+                body: Box::new(body),
+            });
+        }
+        self.parse_conditional()
+    }
+
+    fn unexpected(&self) -> ExpressionError {
+        match self.peek() {
+            Some((position, token)) => ExpressionError::UnexpectedToken {
+                token: token.to_string(),
+                position,
+            },
+            None => ExpressionError::UnexpectedEnd,
+        }
+    }
+}
@@ -0,0 +1,388 @@
+//! Static type checking.
+
+use super::ExpressionError;
+use super::functions::Function;
+use super::lexer::CompareOp;
+use super::parser::{ArithmeticOp, Expr};
+use serde_json::Value as JsonValue;
+use std::collections::BTreeMap;
+
+/// The type of a value, as far as it is known before evaluation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Type {
+    /// Any value; nothing is known about it.
+    Any,
+    /// `null`.
+    Null,
+    /// A boolean.
+    Bool,
+    /// A number.
+    Number,
+    /// A string.
+    String,
+    /// An array with elements of the given type.
+    Array(Box<Type>),
+    /// An object with the given known fields. Other fields may be present
+    /// and have type [`Type::Any`].
+    Object(BTreeMap<String, Type>),
+}
+
+impl Type {
+    /// Derives a type from a JSON Schema.
+    ///
+    /// Only `type`, `properties`, and `items` are considered. Schemas that
+    /// allow several types (other than a type or `null`) become
+    /// [`Type::Any`].
+    #[must_use]
+    pub fn from_schema(schema: &JsonValue) -> Self {
+        let type_name = match schema.get("type") {
+            Some(JsonValue::String(name)) => name.as_str(),
+            Some(JsonValue::Array(names)) => {
+                let mut non_null = names.iter().filter(|name| name.as_str() != Some("null"));
+                match (non_null.next().and_then(JsonValue::as_str), non_null.next()) {
+                    (Some(name), None) => name,
+                    _ => return Self::Any,
+                }
+            }
+            _ => return Self::Any,
+        };
+
+        match type_name {
+            "null" => Self::Null,
+            "boolean" => Self::Bool,
+            "number" | "integer" => Self::Number,
+            "string" => Self::String,
+            "array" => Self::Array(Box::new(
+                schema.get("items").map_or(Self::Any, Self::from_schema),
+            )),
+            "object" => Self::Object(
+                schema
+                    .get("properties")
+                    .and_then(JsonValue::as_object)
+                    .map(|properties| {
+                        properties
+                            .iter()
+                            .map(|(name, schema)| (name.clone(), Self::from_schema(schema)))
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+            ),
+            _ => Self::Any,
+        }
+    }
+
+    /// The type of a value.
+    #[must_use]
+    pub fn of(value: &JsonValue) -> Self {
+        match value {
+            JsonValue::Null => Self::Null,
+            JsonValue::Bool(_) => Self::Bool,
+            JsonValue::Number(_) => Self::Number,
+            JsonValue::String(_) => Self::String,
+            JsonValue::Array(items) => Self::Array(Box::new(
+                items
+                    .iter()
+                    .map(Self::of)
+                    .reduce(|a, b| a.union(&b))
+                    .unwrap_or(Self::Any),
+            )),
+            JsonValue::Object(fields) => Self::Object(
+                fields
+                    .iter()
+                    .map(|(name, value)| (name.clone(), Self::of(value)))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// A human-readable name, used in error messages.
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Any => "any value",
+            Self::Null => "null",
+            Self::Bool => "a boolean",
+            Self::Number => "a number",
+            Self::String => "a string",
+            Self::Array(_) => "an array",
+            Self::Object(_) => "an object",
+        }
+    }
+
+    pub(super) fn array() -> Self {
+        Self::Array(Box::new(Self::Any))
+    }
+
+    pub(super) fn object() -> Self {
+        Self::Object(BTreeMap::new())
+    }
+
+    /// The element type of an array type.
+    pub(super) fn element(&self) -> Self {
+        match self {
+            Self::Array(element) => (**element).clone(),
+            _ => Self::Any,
+        }
+    }
+
+    /// Whether a value of this type may also be of the other type's kind.
+    pub(super) fn may_be(&self, other: &Self) -> bool {
+        matches!(
+            (self, other),
+            (Self::Any, _)
+                | (_, Self::Any)
+                | (Self::Null, Self::Null)
+                | (Self::Bool, Self::Bool)
+                | (Self::Number, Self::Number)
+                | (Self::String, Self::String)
+                | (Self::Array(_), Self::Array(_))
+                | (Self::Object(_), Self::Object(_))
+        )
+    }
+
+    /// The narrowest type that covers both types.
+    pub(super) fn union(&self, other: &Self) -> Self {
+        match (self, other) {
+            (a, b) if a == b => a.clone(),
+            (Self::Array(a), Self::Array(b)) => Self::Array(Box::new(a.union(b))),
+            (Self::Object(_), Self::Object(_)) => Self::object(),
+            _ => Self::Any,
+        }
+    }
+
+    fn field(&self, name: &str) -> Result<Self, String> {
+        match self {
+            Self::Any => Ok(Self::Any),
+            Self::Null => Ok(Self::Null),
+            Self::Object(fields) => Ok(fields.get(name).cloned().unwrap_or(Self::Any)),
+            other => Err(format!("cannot read field '{name}' of {}", other.name())),
+        }
+    }
+}
+
+/// Infers the result type of an expression for an input type.
+pub(super) fn check(expr: &Expr, input: &Type) -> Result<Type, ExpressionError> {
+    Checker {
+        input,
+        bindings: Vec::new(),
+    }
+    .check(expr)
+}
+
+struct Checker<'a> {
+    input: &'a Type,
+    bindings: Vec<(&'a str, Type)>,
+}
+
+impl<'a> Checker<'a> {
+    fn check(&mut self, expr: &'a Expr) -> Result<Type, ExpressionError> {
+        match expr {
+            Expr::Literal(value) => Ok(Type::of(value)),
+            Expr::Variable { name, position } => {
+                if let Some((_, ty)) = self.bindings.iter().rev().find(|(n, _)| n == name) {
+                    return Ok(ty.clone());
+                }
+                let has_input_field =
+                    matches!(self.input, Type::Object(fields) if fields.contains_key("input"));
+                if name == "input" && !has_input_field {
+                    return Ok(self.input.clone());
+                }
+                self.input
+                    .field(name)
+                    .map_err(|message| mismatch(message, *position))
+            }
+            Expr::Field {
+                target,
+                name,
+                position,
+            } => self
+                .check(target)?
+                .field(name)
+                .map_err(|message| mismatch(message, *position)),
+            Expr::Index {
+                target,
+                index,
+                position,
+            } => {
+                let target = self.check(target)?;
+                let index = self.check(index)?;
+                let key = match &target {
+                    Type::Any | Type::Null => return Ok(target),
+                    Type::Array(_) => Type::Number,
+                    Type::Object(_) => Type::String,
+                    other => {
+                        return Err(mismatch(
+                            format!("cannot index {}", other.name()),
+                            *position,
+                        ));
+                    }
+                };
+                if !index.may_be(&key) {
+                    return Err(mismatch(
+                        format!("cannot index {} with {}", target.name(), index.name()),
+                        *position,
+                    ));
+                }
+                Ok(match target {
+                    Type::Array(element) => *element,
+                    _ => Type::Any,
+                })
+            }
+            Expr::Array(items) => {
+                let mut element: Option<Type> = None;
+                for item in items {
+                    let ty = self.check(item)?;
+                    element = Some(match element {
+                        Some(element) => element.union(&ty),
+                        None => ty,
+                    });
+                }
+                Ok(Type::Array(Box::new(element.unwrap_or(Type::Any))))
+            }
+            Expr::Object(entries) => {
+                let mut fields = BTreeMap::new();
+                for (key, value) in entries {
+                    fields.insert(key.clone(), self.check(value)?);
+                }
+                Ok(Type::Object(fields))
+            }
+            Expr::Negate { operand, position } => {
+                let operand = self.check(operand)?;
+                if operand.may_be(&Type::Number) {
+                    Ok(Type::Number)
+                } else {
+                    Err(mismatch(
+                        format!("cannot negate {}", operand.name()),
+                        *position,
+                    ))
+                }
+            }
+            Expr::Not(operand) => {
+                self.check(operand)?;
+                Ok(Type::Bool)
+            }
+            Expr::Arithmetic {
+                op,
+                left,
+                right,
+                position,
+            } => {
+                let left = self.check(left)?;
+                let right = self.check(right)?;
+                arithmetic(*op, &left, &right).ok_or_else(|| {
+                    mismatch(
+                        format!("cannot {} {} and {}", op.verb(), left.name(), right.name()),
+                        *position,
+                    )
+                })
+            }
+            Expr::Compare {
+                op,
+                left,
+                right,
+                position,
+            } => {
+                let left = self.check(left)?;
+                let right = self.check(right)?;
+                let orderable = matches!(
+                    (&left, &right),
+                    (Type::Any, _)
+                        | (_, Type::Any)
+                        | (Type::Number, Type::Number)
+                        | (Type::String, Type::String)
+                );
+                if !matches!(op, CompareOp::Eq | CompareOp::Ne) && !orderable {
+                    return Err(mismatch(
+                        format!("cannot order {} and {}", left.name(), right.name()),
+                        *position,
+                    ));
+                }
+                Ok(Type::Bool)
+            }
+            Expr::And(left, right) | Expr::Or(left, right) => {
+                self.check(left)?;
+                self.check(right)?;
+                Ok(Type::Bool)
+            }
+            Expr::Conditional {
+                condition,
+                then,
+                otherwise,
+            } => {
+                self.check(condition)?;
+                Ok(self.check(then)?.union(&self.check(otherwise)?))
+            }
+            Expr::Call {
+                function,
+                args,
+                position,
+            } => self.call(*function, args, *position),
+            Expr::Lambda { .. } => Err(mismatch(
+                "a lambda can only be passed to a function".to_string(),
+                0,
+            )),
+        }
+    }
+
+    fn call(
+        &mut self,
+        function: Function,
+        args: &'a [Expr],
+        position: usize,
+    ) -> Result<Type, ExpressionError> {
+        let Some(Expr::Lambda { param, body }) = args.get(1).filter(|_| function.takes_lambda())
+        else {
+            let types = args
+                .iter()
+                .map(|arg| self.check(arg))
+                .collect::<Result<Vec<_>, _>>()?;
+            return function
+                .return_type(&types)
+                .map_err(|message| mismatch(message, position));
+        };
+
+        let items = self.check(&args[0])?;
+        if !items.may_be(&Type::array()) {
+            return Err(mismatch(
+                format!(
+                    "{}() expects an array, found {}",
+                    function.name(),
+                    items.name()
+                ),
+                position,
+            ));
+        }
+        let element = items.element();
+        self.bindings.push((param, element.clone()));
+        let result = self.check(body);
+        self.bindings.pop();
+        let result = result?;
+
+        Ok(match function {
+            Function::Map => Type::Array(Box::new(result)),
+            Function::Find => element,
+            Function::Any | Function::All => Type::Bool,
+            _ => Type::Array(Box::new(element)),
+        })
+    }
+}
+
+fn arithmetic(op: ArithmeticOp, left: &Type, right: &Type) -> Option<Type> {
+    match (op, left, right) {
+        (ArithmeticOp::Add, Type::String, Type::String) => Some(Type::String),
+        (ArithmeticOp::Add, Type::Array(l), Type::Array(r)) => {
+            Some(Type::Array(Box::new(l.union(r))))
+        }
+        (ArithmeticOp::Add, Type::Any, Type::Any) => Some(Type::Any),
+        (ArithmeticOp::Add, Type::Any, known) | (ArithmeticOp::Add, known, Type::Any) => {
+            matches!(known, Type::Number | Type::String | Type::Array(_)).then(|| known.clone())
+        }
+        (_, left, right) => {
+            (left.may_be(&Type::Number) && right.may_be(&Type::Number)).then_some(Type::Number)
+        }
+    }
+}
+
+fn mismatch(message: String, position: usize) -> ExpressionError {
+    ExpressionError::TypeMismatch { message, position }
+}
@@ -9,12 +9,13 @@
 
 use crate::edge::Edge;
 use crate::error::GraphError;
-use crate::node::{Node, NodeId};
+use crate::expression::{Expression, Type};
+use crate::node::{ControlFlowNodeConfig, DelayUntil, Node, NodeConfig, NodeId};
 use petgraph::Direction;
 use petgraph::graph::{DiGraph, NodeIndex};
 use petgraph::visit::EdgeRef;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 /// A workflow graph using petgraph's directed graph.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -96,30 +97,7 @@ impl WorkflowGraph {
         // Validate ports exist and schemas are compatible
         let source_node = self.graph.node_weight(*source_index).unwrap();
         let target_node = self.graph.node_weight(*target_index).unwrap();
-
-        let source_port = source_node.output_port(&edge.source_port).ok_or_else(|| {
-            GraphError::SourcePortNotFound {
-                node_id: source_id,
-                port_name: edge.source_port.clone(),
-            }
-        })?;
-
-        let target_port = target_node.input_port(&edge.target_port).ok_or_else(|| {
-            GraphError::TargetPortNotFound {
-                node_id: target_id,
-                port_name: edge.target_port.clone(),
-            }
-        })?;
-
-        // Check schema compatibility
-        if !source_port.schema.is_compatible_with(&target_port.schema) {
-            return Err(GraphError::IncompatibleSchemas {
-                source_node: source_id,
-                source_port: edge.source_port.clone(),
-                target_node: target_id,
-                target_port: edge.target_port.clone(),
-            });
-        }
+        check_edge(source_node, target_node, &edge)?;
 
         self.graph.add_edge(*source_index, *target_index, edge);
         Ok(())
@@ -130,6 +108,16 @@ impl WorkflowGraph {
         self.graph.node_weights()
     }
 
+    /// Returns all edges in the graph, in the order they were added, with
+    /// their source and target nodes.
+    pub fn edges(&self) -> impl Iterator<Item = (&Node, &Edge, &Node)> {
+        self.graph.edge_references().filter_map(|edge| {
+            let source = self.graph.node_weight(edge.source())?;
+            let target = self.graph.node_weight(edge.target())?;
+            Some((source, edge.weight(), target))
+        })
+    }
+
     /// Returns the number of nodes in the graph.
     #[must_use]
     pub fn node_count(&self) -> usize {
@@ -190,16 +178,131 @@ impl WorkflowGraph {
             .collect()
     }
 
+    /// Returns the nodes executed once per item of a fan-out node.
+    ///
+    /// The scope is everything downstream of the FanOut node that is not
+    /// its matching FanIn node or downstream of it. Nodes are returned in
+    /// topological order.
+    #[must_use]
+    pub fn fan_out_scope(&self, fan_out: NodeId) -> Vec<NodeId> {
+        let Some(&start) = self.node_index_map.get(&fan_out) else {
+            return Vec::new();
+        };
+
+        // Nodes from the FanIn onwards run once, after all items
+        let mut collected = HashSet::new();
+        let mut to_visit: Vec<_> = self
+            .graph
+            .node_indices()
+            .filter(|&idx| is_fan_in_of(&self.graph[idx], fan_out))
+            .collect();
+        while let Some(idx) = to_visit.pop() {
+            if collected.insert(idx) {
+                to_visit.extend(self.graph.neighbors_directed(idx, Direction::Outgoing));
+            }
+        }
+
+        let mut in_scope = HashSet::new();
+        let mut to_visit = vec![start];
+        while let Some(idx) = to_visit.pop() {
+            for target in self.graph.neighbors_directed(idx, Direction::Outgoing) {
+                if target != start && !collected.contains(&target) && in_scope.insert(target) {
+                    to_visit.push(target);
+                }
+            }
+        }
+
+        let order = petgraph::algo::toposort(&self.graph, None)
+            .unwrap_or_else(|_| self.graph.node_indices().collect());
+        order
+            .into_iter()
+            .filter(|idx| in_scope.contains(idx))
+            .map(|idx| self.graph[idx].id)
+            .collect()
+    }
+
+    /// Returns a node and every node downstream of it.
+    #[must_use]
+    pub fn descendants(&self, node_id: NodeId) -> HashSet<NodeId> {
+        let Some(&start) = self.node_index_map.get(&node_id) else {
+            return HashSet::new();
+        };
+
+        let mut visited = HashSet::new();
+        let mut to_visit = vec![start];
+        while let Some(idx) = to_visit.pop() {
+            if visited.insert(idx) {
+                to_visit.extend(self.graph.neighbors_directed(idx, Direction::Outgoing));
+            }
+        }
+        visited.into_iter().map(|idx| self.graph[idx].id).collect()
+    }
+
+    /// Returns a copy of the graph with new IDs for all of its nodes, and a
+    /// map from each old ID to the new one.
+    ///
+    /// Edges and the FanOut node a FanIn node refers to follow the new IDs.
+    #[must_use]
+    pub fn with_new_node_ids(&self) -> (Self, HashMap<NodeId, NodeId>) {
+        let ids: HashMap<NodeId, NodeId> = self
+            .graph
+            .node_weights()
+            .map(|node| (node.id, NodeId::new()))
+            .collect();
+        let graph = self.graph.map(
+            |_, node| {
+                let mut node = node.clone();
+                node.id = ids[&node.id];
+                if let NodeConfig::ControlFlow(ControlFlowNodeConfig::FanIn { fan_out_node }) =
+                    &mut node.config
+                    && let Some(new_id) = ids.get(fan_out_node)
+                {
+                    *fan_out_node = *new_id;
+                }
+                node
+            },
+            |_, edge| edge.clone(),
+        );
+
+        let mut copy = Self {
+            graph,
+            node_index_map: HashMap::new(),
+        };
+        copy.rebuild_index_map();
+        (copy, ids)
+    }
+
+    /// Returns the FanIn node that collects the items of a fan-out, if any.
+    #[must_use]
+    pub fn fan_in_of(&self, fan_out: NodeId) -> Option<NodeId> {
+        self.nodes()
+            .find(|node| is_fan_in_of(node, fan_out))
+            .map(|node| node.id)
+    }
+
     /// Validates the workflow graph.
     ///
     /// Checks:
     /// - All required input ports have incoming edges
+    /// - Edges connect existing ports with compatible schemas
     /// - No cycles (DAG validation)
+    /// - Fan-out scopes are closed: per-item data only reaches the matching
+    ///   FanIn node, and fan-outs are not nested
     ///
     /// # Errors
     ///
     /// Returns an error describing the validation failure.
     pub fn validate(&self) -> Result<(), GraphError> {
+        // Check edges, which may not have gone through `add_edge` when the
+        // graph was deserialized
+        for edge in self.graph.edge_references() {
+            check_edge(
+                &self.graph[edge.source()],
+                &self.graph[edge.target()],
+                edge.weight(),
+            )?;
+        }
+
         // Check required inputs
         for node in self.nodes() {
             let incoming_ports: Vec<_> = self
@@ -219,10 +322,140 @@ impl WorkflowGraph {
         }
 
         // Check for cycles using DFS
-        if petgraph::algo::is_cyclic_directed(&self.graph) {
+        if self.has_cycle() {
             return Err(GraphError::CycleDetected);
         }
 
+        for node in self.nodes() {
+            self.validate_expressions(node)?;
+            match &node.config {
+                NodeConfig::ControlFlow(ControlFlowNodeConfig::FanOut) => {
+                    self.validate_fan_out(node.id)?;
+                }
+                NodeConfig::ControlFlow(ControlFlowNodeConfig::FanIn { fan_out_node }) => {
+                    let is_fan_out = self.get_node(*fan_out_node).is_some_and(|fan_out| {
+                        matches!(
+                            fan_out.config,
+                            NodeConfig::ControlFlow(ControlFlowNodeConfig::FanOut)
+                        )
+                    });
+                    if !is_fan_out {
+                        return Err(GraphError::InvalidFanOut {
+                            node_id: node.id,
+                            reason: format!(
+                                "fan-in collects from {fan_out_node}, which is not a fan-out node"
+                            ),
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns true if the graph contains a cycle.
+    #[must_use]
+    pub fn has_cycle(&self) -> bool {
+        petgraph::algo::is_cyclic_directed(&self.graph)
+    }
+
+    /// Checks that a node's transform expression, branch conditions, or
+    /// delay time parse and type check against the type of its input.
+    pub(crate) fn validate_expressions(&self, node: &Node) -> Result<(), GraphError> {
+        let sources: Vec<&str> = match &node.config {
+            NodeConfig::Transform(config) => vec![config.expression.as_str()],
+            NodeConfig::ControlFlow(ControlFlowNodeConfig::Branch { conditions }) => conditions
+                .iter()
+                .map(|branch| branch.condition.as_str())
+                .collect(),
+            NodeConfig::ControlFlow(ControlFlowNodeConfig::Delay {
+                until: DelayUntil::Time { expression },
+            }) => vec![expression.as_str()],
+            _ => return Ok(()),
+        };
+
+        let input = self.input_type(node.id, "input");
+        for source in sources {
+            Expression::parse(source)
+                .and_then(|expression| expression.check(&input))
+                .map_err(|e| GraphError::InvalidExpression {
+                    node_id: node.id,
+                    reason: format!("'{source}': {e}"),
+                })?;
+        }
+        Ok(())
+    }
+
+    /// Returns the type of the value arriving at an input port, derived
+    /// from the schema of the output port that feeds it.
+    fn input_type(&self, node_id: NodeId, port_name: &str) -> Type {
+        let mut sources = self
+            .predecessors(node_id)
+            .into_iter()
+            .filter(|(_, edge)| edge.target_port == port_name);
+        match (sources.next(), sources.next()) {
+            (Some((source, edge)), None) => source
+                .outputs
+                .iter()
+                .find(|port| port.name == edge.source_port)
+                .map_or(Type::Any, |port| Type::from_schema(&port.schema.schema)),
+            _ => Type::Any,
+        }
+    }
+
+    /// Checks that a fan-out's per-item data stays within its scope.
+    fn validate_fan_out(&self, fan_out: NodeId) -> Result<(), GraphError> {
+        let invalid = |reason: String| GraphError::InvalidFanOut {
+            node_id: fan_out,
+            reason,
+        };
+
+        let fan_ins: Vec<_> = self
+            .nodes()
+            .filter(|node| is_fan_in_of(node, fan_out))
+            .collect();
+        if fan_ins.len() > 1 {
+            return Err(invalid(
+                "more than one fan-in collects its items".to_string(),
+            ));
+        }
+        let fan_in = fan_ins.first().map(|node| node.id);
+
+        let scope: HashSet<NodeId> = self.fan_out_scope(fan_out).into_iter().collect();
+        for &node_id in &scope {
+            if let Some(node) = self.get_node(node_id)
+                && matches!(
+                    node.config,
+                    NodeConfig::ControlFlow(ControlFlowNodeConfig::FanOut)
+                )
+            {
+                return Err(invalid(format!(
+                    "nested fan-out {node_id} is not supported"
+                )));
+            }
+            for (successor, _) in self.successors(node_id) {
+                if !scope.contains(&successor.id) && Some(successor.id) != fan_in {
+                    return Err(invalid(format!(
+                        "node {node_id} passes per-item data to {} outside the fan-out",
+                        successor.id
+                    )));
+                }
+            }
+        }
+
+        if let Some(fan_in) = fan_in {
+            for (predecessor, _) in self.predecessors(fan_in) {
+                if predecessor.id != fan_out && !scope.contains(&predecessor.id) {
+                    return Err(invalid(format!(
+                        "fan-in {fan_in} receives data from {}, which is outside the fan-out",
+                        predecessor.id
+                    )));
+                }
+            }
+        }
+
         Ok(())
     }
 
@@ -237,12 +470,51 @@ impl WorkflowGraph {
     }
 }
 
+/// Returns true if the node is the FanIn node of the given fan-out.
+fn is_fan_in_of(node: &Node, fan_out: NodeId) -> bool {
+    matches!(
+        node.config,
+        NodeConfig::ControlFlow(ControlFlowNodeConfig::FanIn { fan_out_node })
+            if fan_out_node == fan_out
+    )
+}
+
 impl Default for WorkflowGraph {
     fn default() -> Self {
         Self::new()
     }
 }
 
+/// Checks that an edge connects existing ports with compatible schemas.
+pub(crate) fn check_edge(source: &Node, target: &Node, edge: &Edge) -> Result<(), GraphError> {
+    let source_port =
+        source
+            .output_port(&edge.source_port)
+            .ok_or_else(|| GraphError::SourcePortNotFound {
+                node_id: source.id,
+                port_name: edge.source_port.clone(),
+            })?;
+
+    let target_port =
+        target
+            .input_port(&edge.target_port)
+            .ok_or_else(|| GraphError::TargetPortNotFound {
+                node_id: target.id,
+                port_name: edge.target_port.clone(),
+            })?;
+
+    source_port
+        .schema
+        .check_compatible_with(&target_port.schema)
+        .map_err(|e| GraphError::IncompatibleSchemas {
+            source_node: source.id,
+            source_port: edge.source_port.clone(),
+            target_node: target.id,
+            target_port: edge.target_port.clone(),
+            reason: e.to_string(),
+        })
+}
+
 /// Custom serde for petgraph DiGraph.
 mod graph_serde {
     use super::*;
@@ -336,7 +608,8 @@ mod graph_serde {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::node::{AiLayerNodeConfig, NodeConfig, TriggerNodeConfig};
+    use crate::node::{AiLayerNodeConfig, TransformNodeConfig, TriggerNodeConfig};
+    use crate::port::PortSchema;
 
     fn create_trigger_node(name: &str) -> Node {
         Node::new(
@@ -357,6 +630,49 @@ mod tests {
         )
     }
 
+    fn create_transform_node(name: &str) -> Node {
+        Node::new(
+            name,
+            NodeConfig::Transform(TransformNodeConfig {
+                expression: "input".to_string(),
+            }),
+        )
+    }
+
+    /// Trigger -> FanOut -> Per Item -> FanIn -> After, returning the IDs of
+    /// the fan-out, the per-item node, the fan-in, and the node after it.
+    fn create_fan_out_graph() -> (WorkflowGraph, NodeId, NodeId, NodeId, NodeId) {
+        let mut graph = WorkflowGraph::new();
+        let trigger_id = graph.add_node(create_trigger_node("Trigger"));
+        let fan_out_id = graph.add_node(Node::new(
+            "FanOut",
+            NodeConfig::ControlFlow(ControlFlowNodeConfig::FanOut),
+        ));
+        let per_item_id = graph.add_node(create_transform_node("Per Item"));
+        let fan_in_id = graph.add_node(Node::new(
+            "FanIn",
+            NodeConfig::ControlFlow(ControlFlowNodeConfig::FanIn {
+                fan_out_node: fan_out_id,
+            }),
+        ));
+        let after_id = graph.add_node(create_transform_node("After"));
+
+        graph
+            .add_edge(trigger_id, fan_out_id, Edge::new("output", "items"))
+            .unwrap();
+        graph
+            .add_edge(fan_out_id, per_item_id, Edge::new("item", "input"))
+            .unwrap();
+        graph
+            .add_edge(per_item_id, fan_in_id, Edge::new("output", "item"))
+            .unwrap();
+        graph
+            .add_edge(fan_in_id, after_id, Edge::new("items", "input"))
+            .unwrap();
+
+        (graph, fan_out_id, per_item_id, fan_in_id, after_id)
+    }
+
     #[test]
     fn add_and_get_node() {
         let mut graph = WorkflowGraph::new();
@@ -443,6 +759,54 @@ mod tests {
         }
     }
 
+    #[test]
+    fn add_edge_rejects_incompatible_schemas() {
+        let mut graph = WorkflowGraph::new();
+        let summary_id = graph.add_node(Node::new(
+            "Summarize",
+            NodeConfig::AiLayer(AiLayerNodeConfig::Summarize { max_length: None }),
+        ));
+        let dedup_id = graph.add_node(Node::new(
+            "Dedup",
+            NodeConfig::AiLayer(AiLayerNodeConfig::Deduplicate {
+                comparison_method: "semantic".to_string(),
+            }),
+        ));
+
+        match graph
+            .add_edge(summary_id, dedup_id, Edge::new("summary", "recent_items"))
+            .unwrap_err()
+        {
+            GraphError::IncompatibleSchemas {
+                source_port,
+                target_port,
+                reason,
+                ..
+            } => {
+                assert_eq!(source_port, "summary");
+                assert_eq!(target_port, "recent_items");
+                assert_eq!(reason, "$: produces string but array is expected");
+            }
+            other => panic!("unexpected error: {other}"),
+        }
+        assert_eq!(graph.edge_count(), 0);
+    }
+
+    #[test]
+    fn validate_checks_edge_schemas() {
+        let (mut graph, _fan_out_id, _per_item_id, _fan_in_id, after_id) = create_fan_out_graph();
+        assert!(graph.validate().is_ok());
+
+        // Graphs loaded from storage skip `add_edge`, so their edges are
+        // checked again
+        let index = graph.node_index_map[&after_id];
+        graph.graph[index].inputs[0].schema = PortSchema::string();
+        assert!(matches!(
+            graph.validate(),
+            Err(GraphError::IncompatibleSchemas { target_node, .. }) if target_node == after_id
+        ));
+    }
+
     #[test]
     fn graph_serde_roundtrip() {
         let mut graph = WorkflowGraph::new();
@@ -465,4 +829,125 @@ mod tests {
         assert_eq!(parsed.edge_count(), 1);
         assert!(parsed.get_node(trigger_id).is_some());
     }
+
+    #[test]
+    fn fan_out_scope_stops_at_fan_in() {
+        let (mut graph, fan_out_id, per_item_id, fan_in_id, _after_id) = create_fan_out_graph();
+        let second_id = graph.add_node(create_transform_node("Second"));
+        graph
+            .add_edge(per_item_id, second_id, Edge::new("output", "input"))
+            .unwrap();
+
+        assert_eq!(
+            graph.fan_out_scope(fan_out_id),
+            vec![per_item_id, second_id]
+        );
+        assert_eq!(graph.fan_in_of(fan_out_id), Some(fan_in_id));
+        assert!(graph.validate().is_ok());
+    }
+
+    #[test]
+    fn descendants_include_the_node_and_everything_downstream() {
+        let (graph, fan_out_id, per_item_id, fan_in_id, after_id) = create_fan_out_graph();
+
+        assert_eq!(
+            graph.descendants(per_item_id),
+            HashSet::from([per_item_id, fan_in_id, after_id])
+        );
+        assert_eq!(graph.descendants(after_id), HashSet::from([after_id]));
+        assert_eq!(graph.descendants(fan_out_id).len(), 4);
+        assert!(graph.descendants(NodeId::new()).is_empty());
+    }
+
+    #[test]
+    fn new_node_ids_keep_the_graph_structure() {
+        let (graph, fan_out_id, per_item_id, fan_in_id, after_id) = create_fan_out_graph();
+
+        let (copy, ids) = graph.with_new_node_ids();
+
+        assert_eq!(ids.len(), graph.node_count());
+        assert!(ids.iter().all(|(old, new)| old != new));
+        assert!(copy.get_node(fan_out_id).is_none());
+        assert_eq!(copy.edge_count(), graph.edge_count());
+        assert_eq!(
+            copy.descendants(ids[&per_item_id]),
+            HashSet::from([ids[&per_item_id], ids[&fan_in_id], ids[&after_id]])
+        );
+        assert_eq!(copy.fan_in_of(ids[&fan_out_id]), Some(ids[&fan_in_id]));
+        assert!(copy.validate().is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_per_item_data_leaving_the_scope() {
+        let (mut graph, fan_out_id, per_item_id, _fan_in_id, after_id) = create_fan_out_graph();
+        let extra_id = graph.add_node(create_transform_node("Extra"));
+        graph
+            .add_edge(per_item_id, extra_id, Edge::new("output", "input"))
+            .unwrap();
+        graph
+            .add_edge(extra_id, after_id, Edge::new("output", "input"))
+            .unwrap();
+
+        match graph.validate().unwrap_err() {
+            GraphError::InvalidFanOut { node_id, reason } => {
+                assert_eq!(node_id, fan_out_id);
+                assert!(reason.contains("outside the fan-out"));
+            }
+            other => panic!("unexpected error: {other}"),
+        }
+    }
+
+    #[test]
+    fn validate_checks_transform_expressions() {
+        let (mut graph, _fan_out_id, _per_item_id, _fan_in_id, after_id) = create_fan_out_graph();
+        assert!(graph.validate().is_ok());
+
+        let set_expression = |graph: &mut WorkflowGraph, expression: &str| {
+            let index = graph.node_index_map[&after_id];
+            graph.graph[index].config = NodeConfig::Transform(TransformNodeConfig {
+                expression: expression.to_string(),
+            });
+        };
+
+        set_expression(&mut graph, "map(input, item => item.name)");
+        assert!(graph.validate().is_ok());
+
+        set_expression(&mut graph, "map(input, item =>");
+        assert!(matches!(
+            graph.validate(),
+            Err(GraphError::InvalidExpression { node_id, .. }) if node_id == after_id
+        ));
+
+        // The fan-in produces an array, so string functions cannot apply.
+        set_expression(&mut graph, "lower(input)");
+        match graph.validate().unwrap_err() {
+            GraphError::InvalidExpression { node_id, reason } => {
+                assert_eq!(node_id, after_id);
+                assert!(reason.contains("expects a string, found an array"));
+            }
+            other => panic!("unexpected error: {other}"),
+        }
+    }
+
+    #[test]
+    fn validate_checks_delay_time_expressions() {
+        let (mut graph, _fan_out_id, _per_item_id, _fan_in_id, after_id) = create_fan_out_graph();
+        let set_time = |graph: &mut WorkflowGraph, expression: &str| {
+            let index = graph.node_index_map[&after_id];
+            graph.graph[index].config = NodeConfig::ControlFlow(ControlFlowNodeConfig::Delay {
+                until: DelayUntil::Time {
+                    expression: expression.to_string(),
+                },
+            });
+        };
+
+        set_time(&mut graph, "first(input).due");
+        assert!(graph.validate().is_ok());
+
+        set_time(&mut graph, "add_days(");
+        assert!(matches!(
+            graph.validate(),
+            Err(GraphError::InvalidExpression { node_id, .. }) if node_id == after_id
+        ));
+    }
 }
@@ -8,37 +8,92 @@
 //! - **Execution**: State machine for tracking workflow runs
 //! - **Triggers**: Schedule, event, and manual trigger management
 //! - **Envelope**: Versioned serialization wrapper for schema evolution
+//! - **Sub-workflows**: Nodes that run another workflow as a child run
+//! - **Approvals**: Nodes that pause a run until a user approves or rejects
+//! - **Delays**: Nodes that pause their path until a duration passes or a
+//!   time is reached
+//! - **Retries**: Re-running a failed run from a failed node, reusing the
+//!   outputs of the nodes upstream of it
+//! - **Dry runs**: Runs that substitute integration writes (and optionally
+//!   LLM calls) with fixtures instead of executing them
+//! - **Import/export**: Portable workflow documents for moving workflows
+//!   between instances
+//! - **Rendering**: Graphviz DOT and Mermaid renderings of workflow graphs
+//! - **Concurrency**: Per-workflow limits on runs in progress, with a policy
+//!   for runs that would overlap
+//! - **Worker runtime**: Workers pulling work items from JetStream, with
+//!   heartbeats, redelivery, and a dead-letter stream
+//! - **Capabilities**: Routing work items to the workers that serve the
+//!   capability a node requires
+//! - **Retention**: Global and per-workflow policies for how long node
+//!   outputs are kept
 
+pub mod capability;
+pub mod concurrency;
+pub mod condition;
 pub mod definition;
+pub mod dry_run;
 pub mod edge;
 pub mod envelope;
 pub mod error;
 pub mod execution;
+pub mod expression;
 pub mod graph;
+pub mod lint;
 pub mod nats;
 pub mod node;
 pub mod orchestrator;
 pub mod port;
+pub mod portable;
 pub mod remaining_work;
+pub mod render;
+pub mod retention;
+pub mod retry;
 pub mod run_state;
+pub mod sub_workflow;
 pub mod trigger;
 pub mod worker;
+pub mod worker_runtime;
 
+pub use capability::{Capability, InvalidCapability};
+pub use concurrency::{Admission, ConcurrencyPolicy, OverlapPolicy};
+pub use condition::{Condition, ConditionError};
 pub use definition::{Workflow, WorkflowMetadata};
+pub use dry_run::DryRunConfig;
 pub use edge::Edge;
-pub use envelope::{CURRENT_VERSION, Envelope, RawEnvelope};
+pub use envelope::{
+    CURRENT_VERSION, Envelope, EnvelopeError, RawEnvelope, Upcaster, UpcasterRegistry, Versioned,
+};
 pub use error::{ExecutionError, GraphError, WorkflowError};
-pub use execution::{ExecutionState, NodeExecutionState, WorkflowRun};
+pub use execution::{ExecutionState, NodeExecutionState, SeededNode, WorkflowRun};
+pub use expression::{Expression, ExpressionError};
 pub use graph::WorkflowGraph;
-pub use nats::{NatsConfig, NatsEventStore, NatsObjectStore, NatsSetupError, create_nats_stores};
-pub use node::{Node, NodeCategory, NodeConfig, NodeId, NodePorts};
+pub use lint::{Diagnostic, LintContext, LintRule, Severity, lint};
+pub use nats::{
+    DeadLetter, NatsConfig, NatsEventStore, NatsObjectStore, NatsSetupError, create_nats_stores,
+    listen_for_cancellations,
+};
+pub use node::{
+    ApprovalDecision, DelayUntil, Node, NodeCategory, NodeConfig, NodeId, NodePorts,
+    SubWorkflowNodeConfig,
+};
 pub use orchestrator::{
-    EventStore, EventStoreError, Orchestrator, OrchestratorError, WorkItem, WorkItemResult,
+    EventStore, EventStoreError, Orchestrator, OrchestratorError, RunLocks, WorkItem,
+    WorkItemResult,
+};
+pub use port::{InputPort, OutputPort, PortSchema, SchemaMismatch};
+pub use portable::{
+    DocumentFormat, ExternalReference, ImportMapping, ModelMapping, PortableError, WorkflowDocument,
 };
-pub use port::{InputPort, OutputPort, PortSchema};
 pub use remaining_work::RemainingWorkGraph;
-pub use run_state::{RunState, RunStateBuilder, RunStateError};
+pub use render::{RenderFormat, StateOverlay, render, to_dot, to_mermaid};
+pub use retention::{RetentionPolicy, run_outputs};
+pub use retry::RetryPolicy;
+pub use run_state::{FanOutState, NodeOutcome, RunState, RunStateBuilder, RunStateError};
+pub use sub_workflow::{SubWorkflowRequest, SubWorkflowRunner, check_call_chain, child_run_id};
 pub use trigger::{Trigger, TriggerConfig, TriggerType};
 pub use worker::{
-    NodeExecutionError, NodeExecutor, ObjectStore, ObjectStoreError, Worker, WorkerError,
+    NodeErrorKind, NodeExecutionError, NodeExecutor, ObjectStore, ObjectStoreError,
+    TransformExecutor, Worker, WorkerError,
 };
+pub use worker_runtime::{NodeResolver, ResolveError, WorkerRuntime, WorkerRuntimeConfig};
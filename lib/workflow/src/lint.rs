@@ -0,0 +1,582 @@
+//! Workflow linting.
+//!
+//! [`WorkflowGraph::validate`] stops at the first problem that makes a
+//! graph impossible to execute. The linter instead reports every issue it
+//! finds, each with a [`Severity`] and the node it applies to, so an editor
+//! can show them all at once. Errors make the workflow fail or never run;
+//! warnings point at parts of the graph that will not do anything.
+//!
+//! Some checks need to know about the world outside the graph, like which
+//! integration accounts exist. Those go through a [`LintContext`].
+
+use crate::graph::{WorkflowGraph, check_edge};
+use crate::node::{
+    ConfigurationNodeConfig, ControlFlowNodeConfig, Node, NodeCategory, NodeConfig, NodeId,
+    TriggerNodeConfig,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+
+/// How serious a lint finding is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    /// The workflow will not run as intended.
+    Error,
+    /// Part of the workflow has no effect.
+    Warning,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Error => write!(f, "error"),
+            Self::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// The check that produced a lint finding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LintRule {
+    /// The workflow has no trigger node.
+    NoTrigger,
+    /// No trigger leads to the node.
+    UnreachableNode,
+    /// A fan-in points at a missing or non-fan-out node.
+    InvalidFanIn,
+    /// A branch port has no outgoing edge.
+    UnconnectedBranch,
+    /// An AI node has no model configuration connected.
+    MissingModel,
+    /// A node references an integration account that does not exist.
+    UnknownIntegration,
+    /// A schedule trigger's cron expression or timezone does not parse.
+    InvalidSchedule,
+    /// A required input port has no incoming edge.
+    MissingInput,
+    /// An edge connects missing ports or incompatible schemas.
+    InvalidEdge,
+    /// A transform expression or branch condition is invalid.
+    InvalidExpression,
+    /// The graph contains a cycle.
+    Cycle,
+}
+
+/// A single lint finding.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    /// How serious the finding is.
+    pub severity: Severity,
+    /// The check that produced it.
+    pub rule: LintRule,
+    /// The node it applies to, if it applies to one.
+    pub node_id: Option<NodeId>,
+    /// What is wrong, phrased so that it says what to fix.
+    pub message: String,
+}
+
+impl Diagnostic {
+    fn error(rule: LintRule, node_id: Option<NodeId>, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            rule,
+            node_id,
+            message: message.into(),
+        }
+    }
+
+    fn warning(rule: LintRule, node_id: Option<NodeId>, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            rule,
+            node_id,
+            message: message.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.severity, self.message)
+    }
+}
+
+/// What the linter knows about the world outside the graph.
+///
+/// The defaults assume everything outside the graph is fine, so `()` lints
+/// the graph alone.
+pub trait LintContext {
+    /// Returns true if the integration account exists.
+    fn integration_exists(&self, _integration_id: &str) -> bool {
+        true
+    }
+
+    /// Checks a schedule trigger's cron expression and timezone.
+    ///
+    /// # Errors
+    ///
+    /// Returns why the schedule is invalid.
+    fn check_schedule(&self, _cron: &str, _timezone: Option<&str>) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+impl LintContext for () {}
+
+/// Lints a workflow graph, returning every finding.
+///
+/// Errors are listed before warnings; within a severity, findings follow
+/// the order of the nodes in the graph.
+#[must_use]
+pub fn lint(graph: &WorkflowGraph, context: &impl LintContext) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    let triggers: Vec<&Node> = graph
+        .nodes()
+        .filter(|node| node.category() == NodeCategory::Trigger)
+        .collect();
+    if triggers.is_empty() {
+        diagnostics.push(Diagnostic::error(
+            LintRule::NoTrigger,
+            None,
+            "the workflow has no trigger, so it never runs; add a trigger node",
+        ));
+    } else {
+        lint_reachability(graph, &mut diagnostics);
+    }
+
+    if graph.has_cycle() {
+        diagnostics.push(Diagnostic::error(
+            LintRule::Cycle,
+            None,
+            "the graph contains a cycle; remove one of the edges that loop back",
+        ));
+    }
+
+    for node in graph.nodes() {
+        lint_inputs(graph, node, &mut diagnostics);
+        lint_node_config(graph, node, context, &mut diagnostics);
+
+        for (target, edge) in graph.successors(node.id) {
+            if let Err(e) = check_edge(node, target, edge) {
+                diagnostics.push(Diagnostic::error(
+                    LintRule::InvalidEdge,
+                    Some(target.id),
+                    format!("'{}': {e}", target.name),
+                ));
+            }
+        }
+
+        if let Err(e) = graph.validate_expressions(node) {
+            diagnostics.push(Diagnostic::error(
+                LintRule::InvalidExpression,
+                Some(node.id),
+                format!("'{}': {e}", node.name),
+            ));
+        }
+    }
+
+    // Stable, so nodes keep their order within a severity
+    diagnostics.sort_by_key(|diagnostic| diagnostic.severity);
+    diagnostics
+}
+
+/// Warns about nodes that no trigger leads to.
+///
+/// Nodes without input ports, like model configurations, are sources that
+/// do not need a trigger.
+fn lint_reachability(graph: &WorkflowGraph, diagnostics: &mut Vec<Diagnostic>) {
+    let mut reached: HashSet<NodeId> = HashSet::new();
+    let mut queue: VecDeque<NodeId> = graph
+        .nodes()
+        .filter(|node| node.category() == NodeCategory::Trigger || node.inputs.is_empty())
+        .map(|node| node.id)
+        .collect();
+    while let Some(node_id) = queue.pop_front() {
+        if reached.insert(node_id) {
+            queue.extend(graph.successors(node_id).into_iter().map(|(n, _)| n.id));
+        }
+    }
+
+    for node in graph.nodes().filter(|node| !reached.contains(&node.id)) {
+        diagnostics.push(Diagnostic::warning(
+            LintRule::UnreachableNode,
+            Some(node.id),
+            format!(
+                "'{}' is not connected to a trigger, so it never runs",
+                node.name
+            ),
+        ));
+    }
+}
+
+/// Reports required inputs without an incoming edge.
+///
+/// An AI node's model input is reported as a missing model.
+fn lint_inputs(graph: &WorkflowGraph, node: &Node, diagnostics: &mut Vec<Diagnostic>) {
+    let predecessors = graph.predecessors(node.id);
+    let is_ai = node.category() == NodeCategory::AiLayer;
+
+    for input in node.inputs.iter().filter(|input| input.required) {
+        let sources: Vec<&Node> = predecessors
+            .iter()
+            .filter(|(_, edge)| edge.target_port == input.name)
+            .map(|(source, _)| *source)
+            .collect();
+
+        if is_ai && input.schema.is_model_reference() {
+            let has_model = sources.iter().any(|source| {
+                matches!(
+                    source.config,
+                    NodeConfig::Configuration(ConfigurationNodeConfig::OpenAiModel { .. })
+                )
+            });
+            if !has_model {
+                diagnostics.push(Diagnostic::error(
+                    LintRule::MissingModel,
+                    Some(node.id),
+                    format!(
+                        "'{}' has no model configuration connected to its '{}' input",
+                        node.name, input.name
+                    ),
+                ));
+            }
+        } else if sources.is_empty() {
+            diagnostics.push(Diagnostic::error(
+                LintRule::MissingInput,
+                Some(node.id),
+                format!(
+                    "'{}' needs an edge into its '{}' input",
+                    node.name, input.name
+                ),
+            ));
+        }
+    }
+}
+
+/// Checks the parts of a node's configuration that refer to other nodes or
+/// to the world outside the graph.
+fn lint_node_config(
+    graph: &WorkflowGraph,
+    node: &Node,
+    context: &impl LintContext,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let mut check_integration = |integration_id: &str| {
+        if !context.integration_exists(integration_id) {
+            diagnostics.push(Diagnostic::error(
+                LintRule::UnknownIntegration,
+                Some(node.id),
+                format!(
+                    "'{}' uses integration account {integration_id}, which no longer exists",
+                    node.name
+                ),
+            ));
+        }
+    };
+
+    match &node.config {
+        NodeConfig::Trigger(TriggerNodeConfig::IntegrationEvent { integration_id, .. })
+        | NodeConfig::Configuration(ConfigurationNodeConfig::OpenAiModel {
+            integration_id, ..
+        }) => check_integration(integration_id),
+        NodeConfig::Integration(config) => {
+            if let Some(integration_id) = config
+                .parameters
+                .get("integration_id")
+                .and_then(|id| id.as_str())
+            {
+                check_integration(integration_id);
+            }
+        }
+        NodeConfig::Trigger(TriggerNodeConfig::Schedule { cron, timezone }) => {
+            if let Err(reason) = context.check_schedule(cron, timezone.as_deref()) {
+                diagnostics.push(Diagnostic::error(
+                    LintRule::InvalidSchedule,
+                    Some(node.id),
+                    format!("'{}' has an invalid schedule: {reason}", node.name),
+                ));
+            }
+        }
+        NodeConfig::ControlFlow(ControlFlowNodeConfig::FanIn { fan_out_node }) => {
+            let reason = match graph.get_node(*fan_out_node) {
+                None => Some("does not exist"),
+                Some(target) => (!matches!(
+                    target.config,
+                    NodeConfig::ControlFlow(ControlFlowNodeConfig::FanOut)
+                ))
+                .then_some("is not a fan-out node"),
+            };
+            if let Some(reason) = reason {
+                diagnostics.push(Diagnostic::error(
+                    LintRule::InvalidFanIn,
+                    Some(node.id),
+                    format!(
+                        "'{}' collects items from node {fan_out_node}, which {reason}",
+                        node.name
+                    ),
+                ));
+            }
+        }
+        NodeConfig::ControlFlow(ControlFlowNodeConfig::Branch { conditions }) => {
+            let connected: HashSet<&str> = graph
+                .successors(node.id)
+                .into_iter()
+                .map(|(_, edge)| edge.source_port.as_str())
+                .collect();
+            for condition in conditions
+                .iter()
+                .filter(|condition| !connected.contains(condition.port.as_str()))
+            {
+                diagnostics.push(Diagnostic::warning(
+                    LintRule::UnconnectedBranch,
+                    Some(node.id),
+                    format!(
+                        "branch '{}' of '{}' has no outgoing edge, so items taking it are dropped",
+                        condition.port, node.name
+                    ),
+                ));
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::edge::Edge;
+    use crate::node::{
+        AiLayerNodeConfig, BranchCondition, IntegrationNodeConfig, TransformNodeConfig,
+    };
+
+    fn trigger(cron: &str) -> Node {
+        Node::new(
+            "Trigger",
+            NodeConfig::Trigger(TriggerNodeConfig::Schedule {
+                cron: cron.to_string(),
+                timezone: None,
+            }),
+        )
+    }
+
+    fn transform(name: &str) -> Node {
+        Node::new(
+            name,
+            NodeConfig::Transform(TransformNodeConfig {
+                expression: "input".to_string(),
+            }),
+        )
+    }
+
+    fn model(integration_id: &str) -> Node {
+        Node::new(
+            "Model",
+            NodeConfig::Configuration(ConfigurationNodeConfig::OpenAiModel {
+                integration_id: integration_id.to_string(),
+                model_id: "gpt-4".to_string(),
+            }),
+        )
+    }
+
+    fn summarize() -> Node {
+        Node::new(
+            "Summarize",
+            NodeConfig::AiLayer(AiLayerNodeConfig::Summarize { max_length: None }),
+        )
+    }
+
+    fn rules(diagnostics: &[Diagnostic]) -> Vec<LintRule> {
+        diagnostics.iter().map(|d| d.rule).collect()
+    }
+
+    /// Knows one integration account and rejects schedules with too few
+    /// fields.
+    struct TestContext;
+
+    impl LintContext for TestContext {
+        fn integration_exists(&self, integration_id: &str) -> bool {
+            integration_id == "int_known"
+        }
+
+        fn check_schedule(&self, cron: &str, _timezone: Option<&str>) -> Result<(), String> {
+            if cron.split_whitespace().count() == 5 {
+                Ok(())
+            } else {
+                Err("expected 5 fields".to_string())
+            }
+        }
+    }
+
+    #[test]
+    fn clean_workflow_has_no_findings() {
+        let mut graph = WorkflowGraph::new();
+        let trigger_id = graph.add_node(trigger("0 7 * * *"));
+        let model_id = graph.add_node(model("int_known"));
+        let summarize_id = graph.add_node(summarize());
+        graph
+            .add_edge(trigger_id, summarize_id, Edge::new("output", "content"))
+            .unwrap();
+        graph
+            .add_edge(model_id, summarize_id, Edge::new("model", "model"))
+            .unwrap();
+
+        assert_eq!(lint(&graph, &TestContext), vec![]);
+    }
+
+    #[test]
+    fn reports_every_issue_with_its_node() {
+        let mut graph = WorkflowGraph::new();
+        let trigger_id = graph.add_node(trigger("every morning"));
+        let model_id = graph.add_node(model("int_deleted"));
+        let summarize_id = graph.add_node(summarize());
+        let orphan_id = graph.add_node(transform("Orphan"));
+        let unmodelled_id = graph.add_node(Node::new(
+            "Generate",
+            NodeConfig::AiLayer(AiLayerNodeConfig::Generate {
+                instructions: "Write".to_string(),
+            }),
+        ));
+        graph
+            .add_edge(trigger_id, summarize_id, Edge::new("output", "content"))
+            .unwrap();
+        graph
+            .add_edge(model_id, summarize_id, Edge::new("model", "model"))
+            .unwrap();
+        graph
+            .add_edge(summarize_id, unmodelled_id, Edge::new("summary", "context"))
+            .unwrap();
+
+        let diagnostics = lint(&graph, &TestContext);
+        let found: Vec<(Severity, LintRule, Option<NodeId>)> = diagnostics
+            .iter()
+            .map(|d| (d.severity, d.rule, d.node_id))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (Severity::Error, LintRule::InvalidSchedule, Some(trigger_id)),
+                (
+                    Severity::Error,
+                    LintRule::UnknownIntegration,
+                    Some(model_id)
+                ),
+                (Severity::Error, LintRule::MissingInput, Some(orphan_id)),
+                (Severity::Error, LintRule::MissingModel, Some(unmodelled_id)),
+                (
+                    Severity::Warning,
+                    LintRule::UnreachableNode,
+                    Some(orphan_id)
+                ),
+            ]
+        );
+        assert!(diagnostics[0].message.contains("expected 5 fields"));
+    }
+
+    #[test]
+    fn reports_a_missing_trigger() {
+        let mut graph = WorkflowGraph::new();
+        let first_id = graph.add_node(Node::new(
+            "Load",
+            NodeConfig::Memory(crate::node::MemoryNodeConfig::LoadMemory),
+        ));
+        let second_id = graph.add_node(transform("Second"));
+        graph
+            .add_edge(first_id, second_id, Edge::new("memory", "input"))
+            .unwrap();
+
+        assert_eq!(rules(&lint(&graph, &())), vec![LintRule::NoTrigger]);
+    }
+
+    #[test]
+    fn reports_fan_ins_without_a_fan_out() {
+        let mut graph = WorkflowGraph::new();
+        let trigger_id = graph.add_node(trigger("0 7 * * *"));
+        let other_id = graph.add_node(transform("Not A Fan-Out"));
+        let fan_in_id = graph.add_node(Node::new(
+            "FanIn",
+            NodeConfig::ControlFlow(ControlFlowNodeConfig::FanIn {
+                fan_out_node: other_id,
+            }),
+        ));
+        let missing_id = graph.add_node(Node::new(
+            "Dangling FanIn",
+            NodeConfig::ControlFlow(ControlFlowNodeConfig::FanIn {
+                fan_out_node: NodeId::new(),
+            }),
+        ));
+        graph
+            .add_edge(trigger_id, other_id, Edge::new("output", "input"))
+            .unwrap();
+        graph
+            .add_edge(other_id, fan_in_id, Edge::new("output", "item"))
+            .unwrap();
+        graph
+            .add_edge(other_id, missing_id, Edge::new("output", "item"))
+            .unwrap();
+
+        let diagnostics = lint(&graph, &());
+        assert_eq!(
+            rules(&diagnostics),
+            vec![LintRule::InvalidFanIn, LintRule::InvalidFanIn]
+        );
+        assert!(diagnostics[0].message.contains("is not a fan-out node"));
+        assert!(diagnostics[1].message.contains("does not exist"));
+    }
+
+    #[test]
+    fn warns_about_unconnected_branches() {
+        let mut graph = WorkflowGraph::new();
+        let trigger_id = graph.add_node(trigger("0 7 * * *"));
+        let branch_id = graph.add_node(Node::new(
+            "Branch",
+            NodeConfig::ControlFlow(ControlFlowNodeConfig::Branch {
+                conditions: vec![
+                    BranchCondition {
+                        port: "urgent".to_string(),
+                        condition: "input.urgent".to_string(),
+                    },
+                    BranchCondition {
+                        port: "later".to_string(),
+                        condition: "!input.urgent".to_string(),
+                    },
+                ],
+            }),
+        ));
+        let notify_id = graph.add_node(transform("Notify"));
+        graph
+            .add_edge(trigger_id, branch_id, Edge::new("output", "input"))
+            .unwrap();
+        graph
+            .add_edge(branch_id, notify_id, Edge::new("urgent", "input"))
+            .unwrap();
+
+        let diagnostics = lint(&graph, &());
+        assert_eq!(rules(&diagnostics), vec![LintRule::UnconnectedBranch]);
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+        assert!(diagnostics[0].message.contains("'later'"));
+    }
+
+    #[test]
+    fn checks_integration_node_accounts() {
+        let mut graph = WorkflowGraph::new();
+        let trigger_id = graph.add_node(trigger("0 7 * * *"));
+        let fetch_id = graph.add_node(Node::new(
+            "Fetch",
+            NodeConfig::Integration(IntegrationNodeConfig {
+                integration_type: "email".to_string(),
+                operation: "fetch".to_string(),
+                parameters: serde_json::json!({ "integration_id": "int_deleted" }),
+            }),
+        ));
+        graph
+            .add_edge(trigger_id, fetch_id, Edge::new("output", "input"))
+            .unwrap();
+
+        let diagnostics = lint(&graph, &TestContext);
+        assert_eq!(rules(&diagnostics), vec![LintRule::UnknownIntegration]);
+        assert_eq!(diagnostics[0].node_id, Some(fetch_id));
+    }
+}
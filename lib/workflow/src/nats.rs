@@ -8,30 +8,82 @@
 //! This module provides NATS-backed implementations of:
 //! - `EventStore`: JetStream-based event persistence
 //! - `ObjectStore`: NATS Object Store for node outputs
+//!
+//! Workers publish the results of work items to a results stream, which the
+//! orchestrator host follows with [`NatsEventStore::follow_work_results`].
+//! Work items that workers give up on are kept in a dead-letter stream for
+//! operators to inspect (see [`NatsEventStore::dead_letters`]).
+//!
+//! Work items are published to a subject per capability, e.g.
+//! `workflow.work.ai_layer`, and workers advertise the capabilities they
+//! serve in a key-value bucket whose entries expire unless refreshed.
+//!
+//! Once a run's outputs expire (see [`crate::retention`]), its events are
+//! purged with [`NatsEventStore::purge_run`].
+//!
+//! Cancelled and failed runs are also announced on a plain NATS subject,
+//! which workers follow with [`listen_for_cancellations`], so executions
+//! that can no longer affect the run are aborted.
 
+use crate::capability::Capability;
 use crate::envelope::Envelope;
 use crate::execution::ExecutionEvent;
-use crate::orchestrator::{EventStore, EventStoreError, WorkItem};
-use crate::worker::{ObjectStore, ObjectStoreError};
+use crate::orchestrator::{EventStore, EventStoreError, WorkItem, WorkItemResult};
+use crate::worker::{ObjectStore, ObjectStoreError, RunCancellations};
 use async_nats::jetstream;
 use async_nats::jetstream::object_store;
 use async_trait::async_trait;
+use futures::{StreamExt, TryStreamExt};
 use silver_telegram_core::WorkflowRunId;
+use std::str::FromStr;
 use std::sync::Arc;
 use ulid::Ulid;
 
 /// Subject prefix for workflow run events.
 const RUN_EVENTS_SUBJECT_PREFIX: &str = "workflow.run";
 
-/// Subject for work items.
+/// Subject prefix for work items, followed by the capability they require.
 const WORK_ITEMS_SUBJECT: &str = "workflow.work";
 
+/// Subject prefix for work item results.
+const WORK_RESULTS_SUBJECT_PREFIX: &str = "workflow.result";
+
+/// Subject prefix for dead-lettered work items.
+const DEAD_LETTER_SUBJECT_PREFIX: &str = "workflow.dead";
+
+/// Subject prefix for run cancellation notices.
+const CANCEL_SUBJECT_PREFIX: &str = "workflow.cancel";
+
 /// Stream name for workflow events.
 const EVENTS_STREAM_NAME: &str = "WORKFLOW_EVENTS";
 
 /// Stream name for work items.
 const WORK_STREAM_NAME: &str = "WORKFLOW_WORK";
 
+/// Stream name for work item results.
+const RESULTS_STREAM_NAME: &str = "WORKFLOW_RESULTS";
+
+/// Stream name for dead-lettered work items.
+const DEAD_LETTER_STREAM_NAME: &str = "WORKFLOW_DEAD_LETTERS";
+
+/// Key-value bucket in which workers advertise their capabilities.
+const WORKERS_BUCKET_NAME: &str = "WORKFLOW_WORKERS";
+
+/// How long a worker's advertisement lasts unless the worker refreshes it.
+pub(crate) const WORKER_ADVERTISEMENT_TTL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Header with the reason a work item was dead-lettered.
+pub const DEAD_LETTER_REASON_HEADER: &str = "Workflow-Dead-Letter-Reason";
+
+/// Header with the number of times a dead-lettered work item was delivered.
+pub const DEAD_LETTER_DELIVERIES_HEADER: &str = "Workflow-Dead-Letter-Deliveries";
+
+/// Header with the subject a dead-lettered work item was published to.
+pub const DEAD_LETTER_SUBJECT_HEADER: &str = "Workflow-Dead-Letter-Subject";
+
+/// Delay before an event whose handling failed is delivered again.
+const RETRY_HANDLING_AFTER: std::time::Duration = std::time::Duration::from_secs(5);
+
 /// Object store bucket name for node outputs.
 const OUTPUTS_BUCKET_NAME: &str = "workflow-outputs";
 
@@ -44,8 +96,16 @@ pub struct NatsConfig {
     pub events_stream_name: Option<String>,
     /// Stream name for work items (defaults to WORKFLOW_WORK).
     pub work_stream_name: Option<String>,
+    /// Stream name for work item results (defaults to WORKFLOW_RESULTS).
+    pub results_stream_name: Option<String>,
+    /// Stream name for dead-lettered work items (defaults to
+    /// WORKFLOW_DEAD_LETTERS).
+    pub dead_letter_stream_name: Option<String>,
     /// Object store bucket name (defaults to workflow-outputs).
     pub outputs_bucket_name: Option<String>,
+    /// Key-value bucket in which workers advertise their capabilities
+    /// (defaults to WORKFLOW_WORKERS).
+    pub workers_bucket_name: Option<String>,
 }
 
 impl NatsConfig {
@@ -56,7 +116,10 @@ impl NatsConfig {
             url: url.into(),
             events_stream_name: None,
             work_stream_name: None,
+            results_stream_name: None,
+            dead_letter_stream_name: None,
             outputs_bucket_name: None,
+            workers_bucket_name: None,
         }
     }
 
@@ -66,15 +129,33 @@ impl NatsConfig {
             .unwrap_or(EVENTS_STREAM_NAME)
     }
 
-    fn work_stream(&self) -> &str {
+    pub(crate) fn work_stream(&self) -> &str {
         self.work_stream_name.as_deref().unwrap_or(WORK_STREAM_NAME)
     }
 
+    fn results_stream(&self) -> &str {
+        self.results_stream_name
+            .as_deref()
+            .unwrap_or(RESULTS_STREAM_NAME)
+    }
+
+    fn dead_letter_stream(&self) -> &str {
+        self.dead_letter_stream_name
+            .as_deref()
+            .unwrap_or(DEAD_LETTER_STREAM_NAME)
+    }
+
     fn outputs_bucket(&self) -> &str {
         self.outputs_bucket_name
             .as_deref()
             .unwrap_or(OUTPUTS_BUCKET_NAME)
     }
+
+    fn workers_bucket(&self) -> &str {
+        self.workers_bucket_name
+            .as_deref()
+            .unwrap_or(WORKERS_BUCKET_NAME)
+    }
 }
 
 /// NATS JetStream-based event store.
@@ -82,6 +163,7 @@ impl NatsConfig {
 /// Events are published to subjects like `workflow.run.<run_id>`.
 /// Each run has its own subject for easy replay.
 pub struct NatsEventStore {
+    client: async_nats::Client,
     jetstream: Arc<jetstream::Context>,
     config: NatsConfig,
 }
@@ -99,12 +181,13 @@ impl NatsEventStore {
             }
         })?;
 
-        let jetstream = async_nats::jetstream::new(client);
+        let jetstream = async_nats::jetstream::new(client.clone());
 
         // Ensure streams exist
         Self::ensure_streams(&jetstream, &config).await?;
 
         Ok(Self {
+            client,
             jetstream: Arc::new(jetstream),
             config,
         })
@@ -147,17 +230,448 @@ impl NatsEventStore {
                 message: format!("failed to create work stream: {e}"),
             })?;
 
+        // Results stream, consumed by the orchestrator host
+        let results_stream_config = jetstream::stream::Config {
+            name: config.results_stream().to_string(),
+            subjects: vec![format!("{WORK_RESULTS_SUBJECT_PREFIX}.>")],
+            storage: jetstream::stream::StorageType::File,
+            retention: jetstream::stream::RetentionPolicy::WorkQueue,
+            ..Default::default()
+        };
+
+        jetstream
+            .get_or_create_stream(results_stream_config)
+            .await
+            .map_err(|e| EventStoreError::ConnectionFailed {
+                message: format!("failed to create results stream: {e}"),
+            })?;
+
+        // Dead-letter stream, kept until operators purge it
+        let dead_letter_stream_config = jetstream::stream::Config {
+            name: config.dead_letter_stream().to_string(),
+            subjects: vec![format!("{DEAD_LETTER_SUBJECT_PREFIX}.>")],
+            storage: jetstream::stream::StorageType::File,
+            retention: jetstream::stream::RetentionPolicy::Limits,
+            ..Default::default()
+        };
+
+        jetstream
+            .get_or_create_stream(dead_letter_stream_config)
+            .await
+            .map_err(|e| EventStoreError::ConnectionFailed {
+                message: format!("failed to create dead-letter stream: {e}"),
+            })?;
+
+        // Worker advertisements, which expire unless refreshed
+        let workers_bucket_config = jetstream::kv::Config {
+            bucket: config.workers_bucket().to_string(),
+            max_age: WORKER_ADVERTISEMENT_TTL,
+            storage: jetstream::stream::StorageType::Memory,
+            ..Default::default()
+        };
+
+        jetstream
+            .create_key_value(workers_bucket_config)
+            .await
+            .map_err(|e| EventStoreError::ConnectionFailed {
+                message: format!("failed to create workers bucket: {e}"),
+            })?;
+
+        Ok(())
+    }
+
+    /// Returns the connection configuration.
+    #[must_use]
+    pub fn config(&self) -> &NatsConfig {
+        &self.config
+    }
+
+    /// Returns the NATS client.
+    pub(crate) fn client(&self) -> &async_nats::Client {
+        &self.client
+    }
+
+    /// Returns the JetStream context.
+    pub(crate) fn jetstream(&self) -> &jetstream::Context {
+        &self.jetstream
+    }
+
+    /// Passes the events of all runs to `handle`, through the durable
+    /// consumer `consumer_name`.
+    ///
+    /// An event is acknowledged once `handle` succeeds; after an error it is
+    /// redelivered a few seconds later, and events that were not handled
+    /// before a restart are delivered again. Events that cannot be decoded
+    /// are dropped. Returns when the subscription ends.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the consumer cannot be created or fails.
+    pub async fn follow_run_events<F, Fut, E>(
+        &self,
+        consumer_name: &str,
+        mut handle: F,
+    ) -> Result<(), EventStoreError>
+    where
+        F: FnMut(ExecutionEvent) -> Fut,
+        Fut: std::future::Future<Output = Result<(), E>>,
+    {
+        let stream = self
+            .jetstream
+            .get_stream(self.config.events_stream())
+            .await
+            .map_err(|e| EventStoreError::LoadFailed {
+                message: format!("failed to get stream: {e}"),
+            })?;
+
+        let consumer_config = jetstream::consumer::pull::Config {
+            durable_name: Some(consumer_name.to_string()),
+            deliver_policy: jetstream::consumer::DeliverPolicy::All,
+            ..Default::default()
+        };
+        let consumer: jetstream::consumer::PullConsumer = stream
+            .get_or_create_consumer(consumer_name, consumer_config)
+            .await
+            .map_err(|e| EventStoreError::LoadFailed {
+                message: format!("failed to create consumer: {e}"),
+            })?;
+
+        let mut messages = consumer
+            .messages()
+            .await
+            .map_err(|e| EventStoreError::LoadFailed {
+                message: format!("failed to get messages: {e}"),
+            })?;
+
+        while let Some(message) = messages.next().await {
+            let message = message.map_err(|e| EventStoreError::LoadFailed {
+                message: e.to_string(),
+            })?;
+
+            let ack = match Envelope::<ExecutionEvent>::decode(&message.payload) {
+                Ok(envelope) => match handle(envelope.into_payload()).await {
+                    Ok(()) => jetstream::AckKind::Ack,
+                    Err(_) => jetstream::AckKind::Nak(Some(RETRY_HANDLING_AFTER)),
+                },
+                Err(_) => jetstream::AckKind::Term,
+            };
+            message
+                .ack_with(ack)
+                .await
+                .map_err(|e| EventStoreError::LoadFailed {
+                    message: format!("failed to ack message: {e}"),
+                })?;
+        }
+
         Ok(())
     }
 
+    /// Publishes the result of a work item for the orchestrator.
+    ///
+    /// Returns once JetStream has stored the result, so a worker can
+    /// acknowledge the work item afterwards. A result published again for
+    /// the same attempt within the duplicate window is dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the result cannot be serialized or stored.
+    pub async fn publish_work_result(
+        &self,
+        result: Envelope<WorkItemResult>,
+    ) -> Result<(), EventStoreError> {
+        let subject = Self::result_subject(result.payload.run_id());
+        let bytes = result
+            .to_json_bytes()
+            .map_err(|e| EventStoreError::PublishFailed {
+                message: format!("failed to serialize work item result: {e}"),
+            })?;
+
+        let mut headers = async_nats::HeaderMap::new();
+        headers.insert(
+            async_nats::header::NATS_MESSAGE_ID,
+            format!("{}.result", result.payload.dispatch_id()).as_str(),
+        );
+
+        self.jetstream
+            .publish_with_headers(subject, headers, bytes.into())
+            .await
+            .map_err(|e| EventStoreError::PublishFailed {
+                message: e.to_string(),
+            })?
+            .await
+            .map_err(|e| EventStoreError::PublishFailed {
+                message: e.to_string(),
+            })?;
+
+        Ok(())
+    }
+
+    /// Passes the results workers publish to `handle`, through the durable
+    /// consumer `consumer_name`.
+    ///
+    /// Like [`Self::follow_run_events`], a result is acknowledged once
+    /// `handle` succeeds and redelivered a few seconds after an error.
+    /// Results that cannot be decoded are dropped. Returns when the
+    /// subscription ends.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the consumer cannot be created or fails.
+    pub async fn follow_work_results<F, Fut, E>(
+        &self,
+        consumer_name: &str,
+        mut handle: F,
+    ) -> Result<(), EventStoreError>
+    where
+        F: FnMut(WorkItemResult) -> Fut,
+        Fut: std::future::Future<Output = Result<(), E>>,
+    {
+        let stream = self
+            .jetstream
+            .get_stream(self.config.results_stream())
+            .await
+            .map_err(|e| EventStoreError::LoadFailed {
+                message: format!("failed to get stream: {e}"),
+            })?;
+
+        let consumer_config = jetstream::consumer::pull::Config {
+            durable_name: Some(consumer_name.to_string()),
+            ..Default::default()
+        };
+        let consumer: jetstream::consumer::PullConsumer = stream
+            .get_or_create_consumer(consumer_name, consumer_config)
+            .await
+            .map_err(|e| EventStoreError::LoadFailed {
+                message: format!("failed to create consumer: {e}"),
+            })?;
+
+        let mut messages = consumer
+            .messages()
+            .await
+            .map_err(|e| EventStoreError::LoadFailed {
+                message: format!("failed to get messages: {e}"),
+            })?;
+
+        while let Some(message) = messages.next().await {
+            let message = message.map_err(|e| EventStoreError::LoadFailed {
+                message: e.to_string(),
+            })?;
+
+            let ack = match Envelope::<WorkItemResult>::decode(&message.payload) {
+                Ok(envelope) => match handle(envelope.into_payload()).await {
+                    Ok(()) => jetstream::AckKind::Ack,
+                    Err(_) => jetstream::AckKind::Nak(Some(RETRY_HANDLING_AFTER)),
+                },
+                Err(_) => jetstream::AckKind::Term,
+            };
+            message
+                .ack_with(ack)
+                .await
+                .map_err(|e| EventStoreError::LoadFailed {
+                    message: format!("failed to ack message: {e}"),
+                })?;
+        }
+
+        Ok(())
+    }
+
+    /// Stores a work item a worker gave up on in the dead-letter stream.
+    ///
+    /// The payload is kept as it was published, with the reason, the
+    /// number of deliveries, and the original subject in headers.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the dead letter cannot be stored.
+    pub async fn dead_letter(
+        &self,
+        subject: &str,
+        payload: &[u8],
+        deliveries: i64,
+        reason: &str,
+    ) -> Result<(), EventStoreError> {
+        let mut headers = async_nats::HeaderMap::new();
+        headers.insert(DEAD_LETTER_REASON_HEADER, reason);
+        headers.insert(
+            DEAD_LETTER_DELIVERIES_HEADER,
+            deliveries.to_string().as_str(),
+        );
+        headers.insert(DEAD_LETTER_SUBJECT_HEADER, subject);
+
+        self.jetstream
+            .publish_with_headers(
+                Self::dead_letter_subject(subject),
+                headers,
+                payload.to_vec().into(),
+            )
+            .await
+            .map_err(|e| EventStoreError::PublishFailed {
+                message: e.to_string(),
+            })?
+            .await
+            .map_err(|e| EventStoreError::PublishFailed {
+                message: format!("failed to store dead letter: {e}"),
+            })?;
+
+        Ok(())
+    }
+
+    /// Returns up to `limit` dead-lettered work items, oldest first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the dead-letter stream cannot be read.
+    pub async fn dead_letters(&self, limit: usize) -> Result<Vec<DeadLetter>, EventStoreError> {
+        let load_failed = |e: String| EventStoreError::LoadFailed { message: e };
+        let mut stream = self
+            .jetstream
+            .get_stream(self.config.dead_letter_stream())
+            .await
+            .map_err(|e| load_failed(format!("failed to get stream: {e}")))?;
+        let info = stream
+            .info()
+            .await
+            .map_err(|e| load_failed(format!("failed to get stream info: {e}")))?;
+        if info.state.messages == 0 {
+            return Ok(Vec::new());
+        }
+        let (first, last) = (info.state.first_sequence, info.state.last_sequence);
+
+        let mut dead_letters = Vec::new();
+        for sequence in first..=last {
+            if dead_letters.len() >= limit {
+                break;
+            }
+            // Messages purged from the middle of the stream leave gaps
+            let Ok(message) = stream.get_raw_message(sequence).await else {
+                continue;
+            };
+            let header = |name: &str| {
+                message
+                    .headers
+                    .get(name)
+                    .map(|value| value.as_str().to_string())
+            };
+            dead_letters.push(DeadLetter {
+                sequence,
+                subject: header(DEAD_LETTER_SUBJECT_HEADER).unwrap_or_default(),
+                reason: header(DEAD_LETTER_REASON_HEADER).unwrap_or_default(),
+                deliveries: header(DEAD_LETTER_DELIVERIES_HEADER)
+                    .and_then(|value| value.parse().ok())
+                    .unwrap_or_default(),
+                work_item: Envelope::<WorkItem>::decode(&message.payload)
+                    .ok()
+                    .map(Envelope::into_payload),
+                payload: message.payload.to_vec(),
+            });
+        }
+
+        Ok(dead_letters)
+    }
+
+    /// Advertises that the worker `worker_id` serves `capabilities`.
+    ///
+    /// The advertisement expires after [`WORKER_ADVERTISEMENT_TTL`], so the
+    /// worker repeats it while it runs. `worker_id` may only contain ASCII
+    /// letters, digits, `_`, and `-`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the advertisement cannot be stored.
+    pub async fn advertise_worker(
+        &self,
+        worker_id: &str,
+        capabilities: &[Capability],
+    ) -> Result<(), EventStoreError> {
+        let publish_failed = |message: String| EventStoreError::PublishFailed { message };
+        let workers = self.workers_bucket().await?;
+        for capability in capabilities {
+            workers
+                .put(
+                    Self::worker_key(capability, worker_id),
+                    worker_id.to_string().into(),
+                )
+                .await
+                .map_err(|e| publish_failed(format!("failed to advertise worker: {e}")))?;
+        }
+        Ok(())
+    }
+
+    /// Deletes the events of a run whose outputs expired.
+    ///
+    /// The run cannot be replayed afterwards, so only call this for runs
+    /// that finished.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the events stream cannot be purged.
+    pub async fn purge_run(&self, run_id: WorkflowRunId) -> Result<(), EventStoreError> {
+        let purge_failed = |message: String| EventStoreError::PurgeFailed { message };
+        let stream = self
+            .jetstream
+            .get_stream(self.config.events_stream())
+            .await
+            .map_err(|e| purge_failed(format!("failed to get stream: {e}")))?;
+        stream
+            .purge()
+            .filter(Self::run_subject(run_id))
+            .await
+            .map_err(|e| purge_failed(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Returns the bucket in which workers advertise their capabilities.
+    async fn workers_bucket(&self) -> Result<jetstream::kv::Store, EventStoreError> {
+        self.jetstream
+            .get_key_value(self.config.workers_bucket())
+            .await
+            .map_err(|e| EventStoreError::ConnectionFailed {
+                message: format!("failed to get workers bucket: {e}"),
+            })
+    }
+
     /// Returns the subject for a run's events.
     fn run_subject(run_id: WorkflowRunId) -> String {
         format!("{RUN_EVENTS_SUBJECT_PREFIX}.{run_id}")
     }
 
-    /// Returns the subject for work items.
-    fn work_subject() -> String {
-        format!("{WORK_ITEMS_SUBJECT}.items")
+    /// Returns the subject for work items that require `capability`.
+    pub(crate) fn work_subject(capability: &Capability) -> String {
+        format!("{WORK_ITEMS_SUBJECT}.{capability}")
+    }
+
+    /// Returns the subject a work item is published on, that of the
+    /// capability it requires.
+    pub(crate) fn work_item_subject(item: &WorkItem) -> Result<String, EventStoreError> {
+        item.capability
+            .as_ref()
+            .map(Self::work_subject)
+            .ok_or_else(|| EventStoreError::PublishFailed {
+                message: "work item does not name the capability it requires".to_string(),
+            })
+    }
+
+    /// Returns the key under which a worker advertises a capability.
+    fn worker_key(capability: &Capability, worker_id: &str) -> String {
+        format!("{capability}.{worker_id}")
+    }
+
+    /// Returns the subject for a run's work item results.
+    fn result_subject(run_id: WorkflowRunId) -> String {
+        format!("{WORK_RESULTS_SUBJECT_PREFIX}.{run_id}")
+    }
+
+    /// Returns the dead-letter subject for a work item subject, e.g.
+    /// `workflow.dead.work.ai_layer` for `workflow.work.ai_layer`.
+    fn dead_letter_subject(subject: &str) -> String {
+        let suffix = subject.strip_prefix("workflow.").unwrap_or(subject);
+        format!("{DEAD_LETTER_SUBJECT_PREFIX}.{suffix}")
+    }
+
+    /// Returns the subject on which a run's cancellation is announced.
+    fn cancel_subject(run_id: WorkflowRunId) -> String {
+        format!("{CANCEL_SUBJECT_PREFIX}.{run_id}")
     }
 }
 
@@ -182,6 +696,18 @@ impl EventStore for NatsEventStore {
                 message: e.to_string(),
             })?;
 
+        // Tell workers to abort the executions of a run that ended early
+        if let ExecutionEvent::RunCancelled { run_id, .. }
+        | ExecutionEvent::RunFailed { run_id, .. } = &event.payload
+        {
+            self.client
+                .publish(Self::cancel_subject(*run_id), Vec::new().into())
+                .await
+                .map_err(|e| EventStoreError::PublishFailed {
+                    message: format!("failed to announce cancellation: {e}"),
+                })?;
+        }
+
         Ok(())
     }
 
@@ -220,7 +746,6 @@ impl EventStore for NatsEventStore {
                 message: format!("failed to get messages: {e}"),
             })?;
 
-        use futures::StreamExt;
         while let Ok(Some(message)) =
             tokio::time::timeout(std::time::Duration::from_millis(100), messages.next()).await
         {
@@ -228,8 +753,8 @@ impl EventStore for NatsEventStore {
                 message: e.to_string(),
             })?;
 
-            let envelope: Envelope<ExecutionEvent> = Envelope::from_json_bytes(&message.payload)
-                .map_err(|e| EventStoreError::LoadFailed {
+            let envelope: Envelope<ExecutionEvent> =
+                Envelope::decode(&message.payload).map_err(|e| EventStoreError::LoadFailed {
                     message: format!("failed to deserialize event: {e}"),
                 })?;
 
@@ -250,13 +775,21 @@ impl EventStore for NatsEventStore {
     }
 
     async fn publish_work_item(&self, item: Envelope<WorkItem>) -> Result<(), EventStoreError> {
-        let subject = Self::work_subject();
+        let subject = Self::work_item_subject(&item.payload)?;
         let bytes = serde_json::to_vec(&item).map_err(|e| EventStoreError::PublishFailed {
             message: format!("failed to serialize work item: {e}"),
         })?;
 
+        // JetStream drops a work item published again within its duplicate
+        // window
+        let mut headers = async_nats::HeaderMap::new();
+        headers.insert(
+            async_nats::header::NATS_MESSAGE_ID,
+            item.payload.dispatch_id().as_str(),
+        );
+
         self.jetstream
-            .publish(subject, bytes.into())
+            .publish_with_headers(subject, headers, bytes.into())
             .await
             .map_err(|e| EventStoreError::PublishFailed {
                 message: e.to_string(),
@@ -268,6 +801,45 @@ impl EventStore for NatsEventStore {
 
         Ok(())
     }
+
+    async fn has_worker_for(&self, capability: &Capability) -> Result<bool, EventStoreError> {
+        let load_failed = |message: String| EventStoreError::LoadFailed { message };
+        let prefix = Self::worker_key(capability, "");
+        let mut keys = self
+            .workers_bucket()
+            .await?
+            .keys()
+            .await
+            .map_err(|e| load_failed(format!("failed to list workers: {e}")))?;
+
+        while let Some(key) = keys
+            .try_next()
+            .await
+            .map_err(|e| load_failed(format!("failed to list workers: {e}")))?
+        {
+            if key.starts_with(&prefix) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+}
+
+/// A work item that a worker gave up on.
+#[derive(Debug, Clone)]
+pub struct DeadLetter {
+    /// Sequence in the dead-letter stream.
+    pub sequence: u64,
+    /// Subject the work item was published to.
+    pub subject: String,
+    /// Why the worker gave up.
+    pub reason: String,
+    /// How many times the work item was delivered.
+    pub deliveries: i64,
+    /// The work item, if the payload could be decoded.
+    pub work_item: Option<WorkItem>,
+    /// The payload as published.
+    pub payload: Vec<u8>,
 }
 
 /// NATS Object Store-based output storage.
@@ -355,15 +927,63 @@ impl ObjectStore for NatsObjectStore {
     }
 
     async fn delete(&self, key: &str) -> Result<(), ObjectStoreError> {
-        self.store
-            .delete(key)
+        self.store.delete(key).await.map_err(|e| {
+            if e.to_string().contains("not found") {
+                ObjectStoreError::NotFound {
+                    key: key.to_string(),
+                }
+            } else {
+                ObjectStoreError::DeleteFailed {
+                    message: e.to_string(),
+                }
+            }
+        })?;
+
+        Ok(())
+    }
+}
+
+/// Records cancelled runs announced over NATS.
+///
+/// Workers run this in a background task with the cancellations they were
+/// built with, so executions of a cancelled run are aborted. Returns when
+/// the subscription ends.
+///
+/// # Errors
+///
+/// Returns an error if connecting or subscribing fails.
+pub async fn listen_for_cancellations(
+    config: &NatsConfig,
+    cancellations: RunCancellations,
+) -> Result<(), EventStoreError> {
+    let client =
+        async_nats::connect(&config.url)
             .await
-            .map_err(|e| ObjectStoreError::DeleteFailed {
+            .map_err(|e| EventStoreError::ConnectionFailed {
                 message: e.to_string(),
             })?;
+    let mut subscription = client
+        .subscribe(format!("{CANCEL_SUBJECT_PREFIX}.*"))
+        .await
+        .map_err(|e| EventStoreError::ConnectionFailed {
+            message: format!("failed to subscribe to cancellations: {e}"),
+        })?;
 
-        Ok(())
+    while let Some(message) = subscription.next().await {
+        if let Some(run_id) = cancelled_run(&message.subject) {
+            cancellations.cancel(run_id);
+        }
     }
+
+    Ok(())
+}
+
+/// Extracts the run ID from a cancellation subject.
+fn cancelled_run(subject: &str) -> Option<WorkflowRunId> {
+    subject
+        .strip_prefix(CANCEL_SUBJECT_PREFIX)?
+        .strip_prefix('.')
+        .and_then(|id| WorkflowRunId::from_str(id).ok())
 }
 
 /// Creates both event store and object store from the same config.
@@ -417,7 +1037,10 @@ mod tests {
 
         assert_eq!(config.events_stream(), EVENTS_STREAM_NAME);
         assert_eq!(config.work_stream(), WORK_STREAM_NAME);
+        assert_eq!(config.results_stream(), RESULTS_STREAM_NAME);
+        assert_eq!(config.dead_letter_stream(), DEAD_LETTER_STREAM_NAME);
         assert_eq!(config.outputs_bucket(), OUTPUTS_BUCKET_NAME);
+        assert_eq!(config.workers_bucket(), WORKERS_BUCKET_NAME);
     }
 
     #[test]
@@ -426,12 +1049,18 @@ mod tests {
             url: "nats://localhost:4222".to_string(),
             events_stream_name: Some("CUSTOM_EVENTS".to_string()),
             work_stream_name: Some("CUSTOM_WORK".to_string()),
+            results_stream_name: Some("CUSTOM_RESULTS".to_string()),
+            dead_letter_stream_name: Some("CUSTOM_DEAD_LETTERS".to_string()),
             outputs_bucket_name: Some("custom-outputs".to_string()),
+            workers_bucket_name: Some("CUSTOM_WORKERS".to_string()),
         };
 
         assert_eq!(config.events_stream(), "CUSTOM_EVENTS");
         assert_eq!(config.work_stream(), "CUSTOM_WORK");
+        assert_eq!(config.results_stream(), "CUSTOM_RESULTS");
+        assert_eq!(config.dead_letter_stream(), "CUSTOM_DEAD_LETTERS");
         assert_eq!(config.outputs_bucket(), "custom-outputs");
+        assert_eq!(config.workers_bucket(), "CUSTOM_WORKERS");
     }
 
     #[test]
@@ -441,6 +1070,49 @@ mod tests {
         assert!(subject.starts_with("workflow.run."));
     }
 
+    #[test]
+    fn result_and_dead_letter_subjects() {
+        let run_id = WorkflowRunId::new();
+        assert_eq!(
+            NatsEventStore::result_subject(run_id),
+            format!("workflow.result.{run_id}")
+        );
+        let ai_layer = Capability::for_category(&crate::node::NodeCategory::AiLayer);
+        assert_eq!(
+            NatsEventStore::dead_letter_subject(&NatsEventStore::work_subject(&ai_layer)),
+            "workflow.dead.work.ai_layer"
+        );
+    }
+
+    #[test]
+    fn worker_keys_are_grouped_by_capability() {
+        let ollama = Capability::new("ollama").unwrap();
+        assert_eq!(
+            NatsEventStore::work_subject(&ollama),
+            "workflow.work.ollama"
+        );
+        assert_eq!(
+            NatsEventStore::worker_key(&ollama, "gpu-box"),
+            "ollama.gpu-box"
+        );
+        // A capability's key prefix does not match longer capability names
+        let prefix = NatsEventStore::worker_key(&Capability::new("ai").unwrap(), "");
+        assert!(!NatsEventStore::worker_key(&ollama, "ai").starts_with(&prefix));
+        assert!(
+            !NatsEventStore::worker_key(&Capability::new("ai_layer").unwrap(), "w")
+                .starts_with(&prefix)
+        );
+    }
+
+    #[test]
+    fn cancel_subject_roundtrip() {
+        let run_id = WorkflowRunId::new();
+        let subject = NatsEventStore::cancel_subject(run_id);
+        assert_eq!(cancelled_run(&subject), Some(run_id));
+        assert_eq!(cancelled_run("workflow.cancel.invalid"), None);
+        assert_eq!(cancelled_run(&NatsEventStore::run_subject(run_id)), None);
+    }
+
     #[test]
     fn key_generation() {
         let key1 = NatsObjectStore::generate_key();
@@ -6,9 +6,13 @@
 //! - Configuration specific to its type
 //! - Input and output ports
 
+use crate::capability::Capability;
 use crate::port::{InputPort, OutputPort, PortSchema};
+use crate::retry::RetryPolicy;
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
+use silver_telegram_core::WorkflowId;
+use std::collections::{BTreeMap, HashMap};
 use ulid::Ulid;
 
 /// A unique identifier for a node within a workflow.
@@ -42,6 +46,16 @@ impl std::fmt::Display for NodeId {
     }
 }
 
+impl std::str::FromStr for NodeId {
+    type Err = ulid::DecodeError;
+
+    /// Parses a node ID in its display form (`node_<ulid>`) or as a bare
+    /// ULID.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ulid::from_string(s.strip_prefix("node_").unwrap_or(s)).map(Self)
+    }
+}
+
 /// The category of a workflow node.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -62,6 +76,8 @@ pub enum NodeCategory {
     Output,
     /// Configuration nodes (model selection, etc.).
     Configuration,
+    /// Invocation of another workflow.
+    SubWorkflow,
 }
 
 /// Configuration for trigger nodes.
@@ -168,8 +184,8 @@ pub struct IntegrationNodeConfig {
 /// Configuration for transform nodes.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TransformNodeConfig {
-    /// Expression for data transformation.
-    /// Note: Expression language is deferred per ADR-005.
+    /// Expression evaluated against the `input` port value; its result is
+    /// the node's output. See [`crate::expression`] for the language.
     pub expression: String,
 }
 
@@ -193,6 +209,71 @@ pub enum ControlFlowNodeConfig {
     Parallel,
     /// Join parallel branches.
     Join,
+    /// Pause the run until a user approves or rejects the input.
+    ///
+    /// The input passes through unchanged on the `approved` or `rejected`
+    /// port, depending on the decision.
+    Approval {
+        /// What the approver is asked to check.
+        #[serde(default)]
+        instructions: String,
+        /// How long the approval stays pending, in milliseconds; None to
+        /// wait indefinitely.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        expires_after_ms: Option<u64>,
+        /// The decision taken when the approval expires.
+        #[serde(default)]
+        on_expiry: ApprovalDecision,
+    },
+    /// Pause the path through this node until a time is reached.
+    ///
+    /// The input passes through unchanged on the `output` port once the
+    /// delay ends. Other paths of the run carry on meanwhile.
+    Delay {
+        /// When the delay ends.
+        until: DelayUntil,
+    },
+}
+
+/// When a delay node's delay ends.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DelayUntil {
+    /// A fixed duration after the node is reached.
+    Duration {
+        /// The duration, in milliseconds.
+        duration_ms: u64,
+    },
+    /// A time taken from the input.
+    ///
+    /// The expression (see [`crate::expression`]) evaluates to a date in a
+    /// form the date functions accept, such as an RFC 3339 timestamp.
+    Time {
+        /// The expression evaluated against the node's input.
+        expression: String,
+    },
+}
+
+/// The decision on an approval node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalDecision {
+    /// The input was approved.
+    Approved,
+    /// The input was rejected.
+    #[default]
+    Rejected,
+}
+
+impl ApprovalDecision {
+    /// The output port the input passes through for this decision.
+    #[must_use]
+    pub fn port(self) -> &'static str {
+        match self {
+            Self::Approved => "approved",
+            Self::Rejected => "rejected",
+        }
+    }
 }
 
 /// A condition for a branch.
@@ -200,7 +281,7 @@ pub enum ControlFlowNodeConfig {
 pub struct BranchCondition {
     /// The output port name for this branch.
     pub port: String,
-    /// The condition expression.
+    /// The condition expression (see [`crate::expression`]).
     pub condition: String,
 }
 
@@ -267,6 +348,42 @@ pub enum ConfigurationNodeConfig {
     },
 }
 
+/// Configuration for sub-workflow nodes.
+///
+/// The node starts a run of another workflow, passing its inputs as that
+/// run's manual trigger input, and outputs the child run's final output.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubWorkflowNodeConfig {
+    /// The workflow to invoke.
+    pub workflow_id: WorkflowId,
+    /// Input port name -> field of the child run's trigger input.
+    ///
+    /// Each entry becomes a required input port. Without entries, the node
+    /// has a single `input` port whose value is the trigger input as is.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub input_mapping: BTreeMap<String, String>,
+}
+
+impl SubWorkflowNodeConfig {
+    /// Builds the child run's trigger input from the node's inputs.
+    ///
+    /// Inputs without a value become null.
+    #[must_use]
+    pub fn trigger_input(&self, inputs: &HashMap<String, JsonValue>) -> JsonValue {
+        if self.input_mapping.is_empty() {
+            return inputs.get("input").cloned().unwrap_or(JsonValue::Null);
+        }
+        self.input_mapping
+            .iter()
+            .map(|(port, field)| {
+                let value = inputs.get(port).cloned().unwrap_or(JsonValue::Null);
+                (field.clone(), value)
+            })
+            .collect::<serde_json::Map<_, _>>()
+            .into()
+    }
+}
+
 /// Configuration for a node, varying by category.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "category", rename_all = "snake_case")]
@@ -287,6 +404,8 @@ pub enum NodeConfig {
     Output(OutputNodeConfig),
     /// Configuration node configuration.
     Configuration(ConfigurationNodeConfig),
+    /// Sub-workflow node configuration.
+    SubWorkflow(SubWorkflowNodeConfig),
 }
 
 impl NodeConfig {
@@ -302,6 +421,7 @@ impl NodeConfig {
             Self::Memory(_) => NodeCategory::Memory,
             Self::Output(_) => NodeCategory::Output,
             Self::Configuration(_) => NodeCategory::Configuration,
+            Self::SubWorkflow(_) => NodeCategory::SubWorkflow,
         }
     }
 }
@@ -354,6 +474,17 @@ pub struct Node {
     pub inputs: Vec<InputPort>,
     /// Output ports for this node.
     pub outputs: Vec<OutputPort>,
+    /// How failed executions of this node are retried.
+    #[serde(default)]
+    pub retry_policy: RetryPolicy,
+    /// Maximum execution time of one attempt, in milliseconds. Attempts
+    /// that take longer fail with a timeout.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+    /// Capability a worker must serve to execute this node; None for the
+    /// capability of the node's category.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capability: Option<Capability>,
 }
 
 impl Node {
@@ -367,6 +498,9 @@ impl Node {
             config,
             inputs: ports.inputs,
             outputs: ports.outputs,
+            retry_policy: RetryPolicy::default(),
+            timeout_ms: None,
+            capability: None,
         }
     }
 
@@ -380,15 +514,48 @@ impl Node {
             config,
             inputs: ports.inputs,
             outputs: ports.outputs,
+            retry_policy: RetryPolicy::default(),
+            timeout_ms: None,
+            capability: None,
         }
     }
 
+    /// Sets the retry policy for this node.
+    #[must_use]
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Sets the maximum execution time of one attempt, in milliseconds.
+    #[must_use]
+    pub fn with_timeout_ms(mut self, timeout_ms: u64) -> Self {
+        self.timeout_ms = Some(timeout_ms);
+        self
+    }
+
+    /// Sets the capability a worker must serve to execute this node.
+    #[must_use]
+    pub fn with_capability(mut self, capability: Capability) -> Self {
+        self.capability = Some(capability);
+        self
+    }
+
     /// Returns the category of this node.
     #[must_use]
     pub fn category(&self) -> NodeCategory {
         self.config.category()
     }
 
+    /// Returns the capability a worker must serve to execute this node:
+    /// the declared one, or else the one of the node's category.
+    #[must_use]
+    pub fn required_capability(&self) -> Capability {
+        self.capability
+            .clone()
+            .unwrap_or_else(|| Capability::for_category(&self.category()))
+    }
+
     /// Returns the input port with the given name, if any.
     #[must_use]
     pub fn input_port(&self, name: &str) -> Option<&InputPort> {
@@ -519,6 +686,17 @@ impl Node {
                     vec![InputPort::required("input", PortSchema::any())],
                     vec![OutputPort::new("output", PortSchema::any())],
                 ),
+                ControlFlowNodeConfig::Approval { .. } => NodePorts::new(
+                    vec![InputPort::required("input", PortSchema::any())],
+                    vec![
+                        OutputPort::new(ApprovalDecision::Approved.port(), PortSchema::any()),
+                        OutputPort::new(ApprovalDecision::Rejected.port(), PortSchema::any()),
+                    ],
+                ),
+                ControlFlowNodeConfig::Delay { .. } => NodePorts::new(
+                    vec![InputPort::required("input", PortSchema::any())],
+                    vec![OutputPort::new("output", PortSchema::any())],
+                ),
             },
             NodeConfig::Memory(mem_config) => match mem_config {
                 MemoryNodeConfig::LoadMemory => {
@@ -541,6 +719,18 @@ impl Node {
                     )])
                 }
             },
+            NodeConfig::SubWorkflow(config) => {
+                let inputs = if config.input_mapping.is_empty() {
+                    vec![InputPort::required("input", PortSchema::any())]
+                } else {
+                    config
+                        .input_mapping
+                        .keys()
+                        .map(|port| InputPort::required(port, PortSchema::any()))
+                        .collect()
+                };
+                NodePorts::new(inputs, vec![OutputPort::new("output", PortSchema::any())])
+            }
         }
     }
 }
@@ -556,6 +746,27 @@ mod tests {
         assert!(display.starts_with("node_"));
     }
 
+    #[test]
+    fn node_id_parses_its_display_form() {
+        let id = NodeId::new();
+        assert_eq!(id.to_string().parse::<NodeId>(), Ok(id));
+        assert!("node_invalid".parse::<NodeId>().is_err());
+    }
+
+    #[test]
+    fn approval_node_has_a_port_per_decision() {
+        let node = Node::new(
+            "Check Reply",
+            NodeConfig::ControlFlow(ControlFlowNodeConfig::Approval {
+                instructions: "Check the reply before it is sent".to_string(),
+                expires_after_ms: None,
+                on_expiry: ApprovalDecision::default(),
+            }),
+        );
+        let outputs: Vec<_> = node.outputs.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(outputs, vec!["approved", "rejected"]);
+    }
+
     #[test]
     fn trigger_node_has_no_inputs() {
         let node = Node::new(
@@ -675,4 +886,75 @@ mod tests {
         assert_eq!(gen_node.inputs[0].name, "model");
         assert!(gen_node.inputs[0].required);
     }
+
+    #[test]
+    fn declared_capability_overrides_the_category() {
+        let node = Node::new(
+            "LLM",
+            NodeConfig::AiLayer(AiLayerNodeConfig::LlmCall {
+                prompt: "Test".to_string(),
+                output_schema: None,
+            }),
+        );
+        assert_eq!(node.required_capability().as_str(), "ai_layer");
+        assert!(
+            serde_json::to_value(&node)
+                .unwrap()
+                .get("capability")
+                .is_none()
+        );
+
+        let ollama = Capability::new("ollama").unwrap();
+        let node = node.with_capability(ollama.clone());
+        assert_eq!(node.required_capability(), ollama);
+        let json = serde_json::to_value(&node).unwrap();
+        assert_eq!(json["capability"], "ollama");
+        assert_eq!(serde_json::from_value::<Node>(json).unwrap(), node);
+    }
+
+    fn sub_workflow_config(mapping: &[(&str, &str)]) -> SubWorkflowNodeConfig {
+        SubWorkflowNodeConfig {
+            workflow_id: WorkflowId::new(),
+            input_mapping: mapping
+                .iter()
+                .map(|(port, field)| ((*port).to_string(), (*field).to_string()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn sub_workflow_node_has_a_port_per_mapped_input() {
+        let node = Node::new(
+            "Summarize Priority Emails",
+            NodeConfig::SubWorkflow(sub_workflow_config(&[
+                ("emails", "messages"),
+                ("limit", "max"),
+            ])),
+        );
+        let inputs: Vec<&str> = node.inputs.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(inputs, vec!["emails", "limit"]);
+        assert_eq!(node.outputs[0].name, "output");
+        assert_eq!(node.category(), NodeCategory::SubWorkflow);
+
+        let unmapped = Node::new("Sub", NodeConfig::SubWorkflow(sub_workflow_config(&[])));
+        assert_eq!(unmapped.inputs[0].name, "input");
+    }
+
+    #[test]
+    fn sub_workflow_trigger_input_maps_ports_to_fields() {
+        let inputs = HashMap::from([
+            ("emails".to_string(), serde_json::json!(["a", "b"])),
+            ("input".to_string(), serde_json::json!({"raw": true})),
+        ]);
+
+        let mapped = sub_workflow_config(&[("emails", "messages"), ("limit", "max")]);
+        assert_eq!(
+            mapped.trigger_input(&inputs),
+            serde_json::json!({"messages": ["a", "b"], "max": null})
+        );
+        assert_eq!(
+            sub_workflow_config(&[]).trigger_input(&inputs),
+            serde_json::json!({"raw": true})
+        );
+    }
 }
@@ -4,26 +4,49 @@
 //! - One orchestrator per run
 //! - Determines ready nodes, publishes work items
 //! - Handles graph logic (workers handle execution)
-//! - JetStream ack handles crash recovery
+//! - JetStream ack handles crash recovery; a new orchestrator picks up
+//!   an interrupted run with [`Orchestrator::recover`]
 //!
 //! The orchestrator runs the execution loop:
 //! 1. Load/reconstruct run state from events
-//! 2. Determine ready nodes
-//! 3. Publish work items for workers
-//! 4. Process completion/failure events
-//! 5. Finalize the run when complete
+//! 2. Determine ready nodes (skipping untaken branch paths), running nodes
+//!    inside a fan-out once per item
+//! 3. Evaluate control flow nodes, substitute the nodes a dry run does not
+//!    execute, publish work items for other nodes, routed to the workers
+//!    that serve the capability the node requires (failing nodes no worker
+//!    serves)
+//! 4. Process completion/failure events, and decisions on approval nodes,
+//!    which pause the run until a user acts or the approval expires; delay
+//!    nodes pause their path until their delay ends, and sub-workflow nodes
+//!    until their child run ends
+//! 5. Finalize the run when complete, collecting its output from the
+//!    terminal nodes (or the HttpResponse node, if one ran)
 
+use crate::capability::Capability;
+use crate::condition::Condition;
 use crate::definition::Workflow;
+use crate::dry_run::{DryRunConfig, substituted_request};
 use crate::envelope::Envelope;
-use crate::execution::{ExecutionEvent, ExecutionState};
-use crate::node::NodeId;
-use crate::run_state::{RunState, RunStateBuilder, RunStateError};
+use crate::error::ExecutionError;
+use crate::execution::{
+    ExecutionEvent, ExecutionState, NodeExecutionState, SeededNode, first_attempt,
+};
+use crate::expression::{Expression, parse_date};
+use crate::node::{
+    ApprovalDecision, BranchCondition, ControlFlowNodeConfig, DelayUntil, Node, NodeCategory,
+    NodeConfig, NodeId, OutputNodeConfig,
+};
+use crate::run_state::{FanOutState, NodeOutcome, RunState, RunStateBuilder, RunStateError};
+use crate::sub_workflow;
+use crate::worker::{NodeErrorKind, NodeExecutionError, ObjectStore, WorkerError};
 use async_trait::async_trait;
-use chrono::Utc;
+use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
-use silver_telegram_core::WorkflowRunId;
-use std::collections::HashMap;
+use silver_telegram_core::{TriggerId, WorkflowRunId};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, Mutex, PoisonError, Weak};
+use tokio::sync::OwnedMutexGuard;
 
 /// A work item to be executed by a worker.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -32,8 +55,38 @@ pub struct WorkItem {
     pub run_id: WorkflowRunId,
     /// The node to execute.
     pub node_id: NodeId,
+    /// Capability a worker must serve to execute the node; None only for
+    /// work items published before work was routed by capability.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capability: Option<Capability>,
+    /// Item index, for nodes executed per item of a fan-out.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub item_index: Option<usize>,
     /// Input data for the node (collected from predecessor outputs).
     pub inputs: HashMap<String, String>, // port_name -> object_store_key
+    /// The attempt this work item runs, starting at 1.
+    #[serde(default = "first_attempt")]
+    pub attempt: u32,
+    /// Earliest time a retry may start; None to start immediately.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub not_before: Option<DateTime<Utc>>,
+    /// Set for work items of a dry run, without the run's fixtures.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dry_run: Option<DryRunConfig>,
+}
+
+impl WorkItem {
+    /// Returns an ID that is the same for every publication of this attempt.
+    ///
+    /// The event store uses it to drop duplicates, e.g. when a recovered
+    /// orchestrator dispatches an attempt again.
+    #[must_use]
+    pub fn dispatch_id(&self) -> String {
+        match self.item_index {
+            Some(index) => format!("{}.{}.{index}.{}", self.run_id, self.node_id, self.attempt),
+            None => format!("{}.{}.{}", self.run_id, self.node_id, self.attempt),
+        }
+    }
 }
 
 /// Result of a work item execution.
@@ -46,6 +99,12 @@ pub enum WorkItemResult {
         run_id: WorkflowRunId,
         /// The node ID.
         node_id: NodeId,
+        /// The item index, for nodes executed per item of a fan-out.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        item_index: Option<usize>,
+        /// The attempt that produced the output.
+        #[serde(default = "first_attempt")]
+        attempt: u32,
         /// Object store key for the output.
         output_key: String,
     },
@@ -55,11 +114,77 @@ pub enum WorkItemResult {
         run_id: WorkflowRunId,
         /// The node ID.
         node_id: NodeId,
+        /// The item index, for nodes executed per item of a fan-out.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        item_index: Option<usize>,
+        /// The attempt that failed.
+        #[serde(default = "first_attempt")]
+        attempt: u32,
         /// Error message.
         error: String,
+        /// Kind of the error, which decides whether it is retried.
+        #[serde(default)]
+        error_kind: NodeErrorKind,
+    },
+    /// Sub-workflow node started a child run, whose end resumes the node.
+    Waiting {
+        /// The run ID.
+        run_id: WorkflowRunId,
+        /// The node ID.
+        node_id: NodeId,
+        /// The item index, for nodes executed per item of a fan-out.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        item_index: Option<usize>,
+        /// The attempt that started the child run.
+        attempt: u32,
+        /// The child run.
+        child_run_id: WorkflowRunId,
     },
 }
 
+impl WorkItemResult {
+    /// Returns the run the result belongs to.
+    #[must_use]
+    pub fn run_id(&self) -> WorkflowRunId {
+        match self {
+            Self::Completed { run_id, .. }
+            | Self::Failed { run_id, .. }
+            | Self::Waiting { run_id, .. } => *run_id,
+        }
+    }
+
+    /// Returns the [`WorkItem::dispatch_id`] of the attempt that produced
+    /// the result.
+    #[must_use]
+    pub fn dispatch_id(&self) -> String {
+        let (Self::Completed {
+            run_id,
+            node_id,
+            item_index,
+            attempt,
+            ..
+        }
+        | Self::Failed {
+            run_id,
+            node_id,
+            item_index,
+            attempt,
+            ..
+        }
+        | Self::Waiting {
+            run_id,
+            node_id,
+            item_index,
+            attempt,
+            ..
+        }) = self;
+        match item_index {
+            Some(index) => format!("{run_id}.{node_id}.{index}.{attempt}"),
+            None => format!("{run_id}.{node_id}.{attempt}"),
+        }
+    }
+}
+
 /// Trait for event persistence and messaging.
 ///
 /// This abstraction allows the orchestrator to be tested without NATS
@@ -77,6 +202,75 @@ pub trait EventStore: Send + Sync {
 
     /// Publishes a work item for workers to process.
     async fn publish_work_item(&self, item: Envelope<WorkItem>) -> Result<(), EventStoreError>;
+
+    /// Returns whether any worker serves `capability`.
+    ///
+    /// Event stores that cannot tell assume one does.
+    async fn has_worker_for(&self, capability: &Capability) -> Result<bool, EventStoreError> {
+        let _ = capability;
+        Ok(true)
+    }
+}
+
+#[async_trait]
+impl<T: EventStore + ?Sized> EventStore for Arc<T> {
+    async fn publish(&self, event: Envelope<ExecutionEvent>) -> Result<(), EventStoreError> {
+        (**self).publish(event).await
+    }
+
+    async fn load_events(
+        &self,
+        run_id: WorkflowRunId,
+    ) -> Result<Vec<ExecutionEvent>, EventStoreError> {
+        (**self).load_events(run_id).await
+    }
+
+    async fn publish_work_item(&self, item: Envelope<WorkItem>) -> Result<(), EventStoreError> {
+        (**self).publish_work_item(item).await
+    }
+
+    async fn has_worker_for(&self, capability: &Capability) -> Result<bool, EventStoreError> {
+        (**self).has_worker_for(capability).await
+    }
+}
+
+/// Per-run locks that serialize the changes made to each run.
+///
+/// An orchestrator publishes from the state it replayed, so two
+/// orchestrators changing the same run at once each miss the other's
+/// events: ready nodes are dispatched twice, or events follow the run's
+/// end. Whoever replays a run to change it holds the run's lock from
+/// before the replay until its last publish. Clones share their locks.
+#[derive(Debug, Clone, Default)]
+pub struct RunLocks {
+    locks: Arc<Mutex<HashMap<WorkflowRunId, Weak<tokio::sync::Mutex<()>>>>>,
+}
+
+impl RunLocks {
+    /// Creates a set of run locks.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Waits until no one else holds the run's lock, then holds it until
+    /// the returned guard is dropped.
+    pub async fn lock(&self, run_id: WorkflowRunId) -> OwnedMutexGuard<()> {
+        let lock = {
+            let mut locks = self.locks.lock().unwrap_or_else(PoisonError::into_inner);
+            // Locks no one holds or waits for are created again when needed
+            locks.retain(|_, lock| lock.strong_count() > 0);
+            match locks.get(&run_id).and_then(Weak::upgrade) {
+                Some(lock) => lock,
+                None => {
+                    let lock = Arc::new(tokio::sync::Mutex::new(()));
+                    locks.insert(run_id, Arc::downgrade(&lock));
+                    lock
+                }
+            }
+        };
+        lock.lock_owned().await
+    }
 }
 
 /// Errors from event store operations.
@@ -88,6 +282,8 @@ pub enum EventStoreError {
     PublishFailed { message: String },
     /// Failed to load events.
     LoadFailed { message: String },
+    /// Failed to delete events.
+    PurgeFailed { message: String },
 }
 
 impl std::fmt::Display for EventStoreError {
@@ -98,6 +294,7 @@ impl std::fmt::Display for EventStoreError {
             }
             Self::PublishFailed { message } => write!(f, "event publish failed: {message}"),
             Self::LoadFailed { message } => write!(f, "event load failed: {message}"),
+            Self::PurgeFailed { message } => write!(f, "event purge failed: {message}"),
         }
     }
 }
@@ -115,6 +312,19 @@ pub enum OrchestratorError {
     RunNotFound { run_id: WorkflowRunId },
     /// Run already in terminal state.
     RunAlreadyTerminal { run_id: WorkflowRunId },
+    /// No approval is pending for the node (or item).
+    ApprovalNotPending {
+        run_id: WorkflowRunId,
+        node_id: NodeId,
+        item_index: Option<usize>,
+    },
+    /// A run to retry did not fail.
+    RunNotFailed { run_id: WorkflowRunId },
+    /// A node to retry a run from did not fail in the run.
+    NodeNotFailed {
+        run_id: WorkflowRunId,
+        node_id: NodeId,
+    },
 }
 
 impl std::fmt::Display for OrchestratorError {
@@ -126,6 +336,21 @@ impl std::fmt::Display for OrchestratorError {
             Self::RunAlreadyTerminal { run_id } => {
                 write!(f, "run already in terminal state: {run_id}")
             }
+            Self::ApprovalNotPending {
+                run_id,
+                node_id,
+                item_index: Some(index),
+            } => write!(
+                f,
+                "no approval pending for node {node_id} item {index} in run {run_id}"
+            ),
+            Self::ApprovalNotPending {
+                run_id, node_id, ..
+            } => write!(f, "no approval pending for node {node_id} in run {run_id}"),
+            Self::RunNotFailed { run_id } => write!(f, "run {run_id} did not fail"),
+            Self::NodeNotFailed { run_id, node_id } => {
+                write!(f, "node {node_id} did not fail in run {run_id}")
+            }
         }
     }
 }
@@ -144,21 +369,72 @@ impl From<RunStateError> for OrchestratorError {
     }
 }
 
+/// What to do with a node that became ready.
+enum ReadyNode {
+    /// Execute the node with the given inputs (port_name -> object_store_key).
+    Execute { inputs: HashMap<String, String> },
+    /// Skip the node: the named input port has no active upstream path.
+    Skip { port: String },
+}
+
+/// Whether a node's upstream outputs are available.
+enum Readiness {
+    /// Every predecessor finished.
+    Ready(ReadyNode),
+    /// A predecessor has not finished yet.
+    Waiting,
+    /// A predecessor failed, so the node can never run.
+    Blocked,
+}
+
+/// One item of an expanded fan-out.
+struct ItemContext<'a> {
+    /// The FanOut node.
+    fan_out: NodeId,
+    /// The fan-out's items and scope.
+    fan_out_state: &'a FanOutState,
+    /// The item index.
+    index: usize,
+    /// Scope nodes whose execution of this item can never run.
+    blocked: &'a HashSet<(NodeId, usize)>,
+}
+
+/// Nodes the orchestrator evaluates itself instead of sending to workers.
+enum InlineNode {
+    /// Branch node with its conditions.
+    Branch(Vec<BranchCondition>),
+    /// FanOut node, which splits its input into items.
+    FanOut,
+    /// FanIn node, which collects the items of a fan-out.
+    FanIn { fan_out_node: NodeId },
+    /// Approval node, which waits for a decision on its input.
+    Approval { expires_after_ms: Option<u64> },
+    /// Delay node, which passes its input on once its delay ends.
+    Delay(DelayUntil),
+    /// Node substituted with a fixture response in a dry run.
+    Substitute,
+}
+
 /// The workflow orchestrator.
 ///
 /// Coordinates execution of a single workflow run.
-pub struct Orchestrator<E: EventStore> {
+pub struct Orchestrator<E: EventStore, O: ObjectStore> {
     workflow: Workflow,
     event_store: E,
+    object_store: O,
     state: Option<RunState>,
 }
 
-impl<E: EventStore> Orchestrator<E> {
+impl<E: EventStore, O: ObjectStore> Orchestrator<E, O> {
     /// Creates a new orchestrator for the given workflow.
-    pub fn new(workflow: Workflow, event_store: E) -> Self {
+    ///
+    /// The object store is used to read the inputs of nodes the orchestrator
+    /// evaluates itself, such as branch conditions.
+    pub fn new(workflow: Workflow, event_store: E, object_store: O) -> Self {
         Self {
             workflow,
             event_store,
+            object_store,
             state: None,
         }
     }
@@ -179,7 +455,45 @@ impl<E: EventStore> Orchestrator<E> {
 
     /// Starts a new run.
     async fn start_new_run(&mut self) -> Result<(), OrchestratorError> {
-        let run_id = WorkflowRunId::new();
+        self.queue_run(WorkflowRunId::new(), None, None).await
+    }
+
+    /// Queues a new run with a caller-chosen ID.
+    ///
+    /// Used when the run record is created elsewhere first (e.g., by the
+    /// scheduler) and the event stream must use the same run ID.
+    pub async fn queue_run(
+        &mut self,
+        run_id: WorkflowRunId,
+        trigger_id: Option<TriggerId>,
+        input: Option<JsonValue>,
+    ) -> Result<(), OrchestratorError> {
+        self.queue(run_id, trigger_id, input, None).await
+    }
+
+    /// Queues a dry run with a caller-chosen ID.
+    ///
+    /// Integration nodes with write operations, and AI layer nodes if the
+    /// configuration stubs LLM calls, are substituted with fixture responses
+    /// instead of being sent to workers (see [`crate::dry_run`]).
+    pub async fn queue_dry_run(
+        &mut self,
+        run_id: WorkflowRunId,
+        trigger_id: Option<TriggerId>,
+        input: Option<JsonValue>,
+        dry_run: DryRunConfig,
+    ) -> Result<(), OrchestratorError> {
+        self.queue(run_id, trigger_id, input, Some(dry_run)).await
+    }
+
+    /// Publishes RunQueued and builds the run's initial state.
+    async fn queue(
+        &mut self,
+        run_id: WorkflowRunId,
+        trigger_id: Option<TriggerId>,
+        input: Option<JsonValue>,
+        dry_run: Option<DryRunConfig>,
+    ) -> Result<(), OrchestratorError> {
         let workflow_id = self.workflow.id;
         let timestamp = Utc::now();
 
@@ -187,8 +501,10 @@ impl<E: EventStore> Orchestrator<E> {
         let event = ExecutionEvent::RunQueued {
             run_id,
             workflow_id,
-            trigger_id: None,
-            input: None,
+            workflow_version: self.workflow.version,
+            trigger_id,
+            input,
+            dry_run,
             timestamp,
         };
         self.event_store
@@ -203,6 +519,134 @@ impl<E: EventStore> Orchestrator<E> {
         Ok(())
     }
 
+    /// Queues a run that retries a failed run from one of its failed nodes.
+    ///
+    /// The new run takes over the outputs of the nodes that completed in
+    /// `failed`, except `node_id` and the nodes downstream of it, which run
+    /// again along with every node that did not complete. Nodes inside a
+    /// fan-out run per item, so retrying one of them, or the FanIn node,
+    /// runs the whole fan-out again. The new run records the link in
+    /// `RunSeeded`, and the failed run in `RunRetried`. Call [`Self::start`]
+    /// next.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrchestratorError::RunNotFailed`] if `failed` did not fail,
+    /// [`OrchestratorError::NodeNotFailed`] if the node did not fail in it,
+    /// or an error if publishing the events fails.
+    pub async fn queue_retry(
+        &mut self,
+        run_id: WorkflowRunId,
+        failed: &RunState,
+        node_id: NodeId,
+    ) -> Result<(), OrchestratorError> {
+        if failed.execution_state != ExecutionState::Failed {
+            return Err(OrchestratorError::RunNotFailed {
+                run_id: failed.run_id,
+            });
+        }
+        if failed
+            .node_states
+            .get(&node_id)
+            .is_none_or(|exec| exec.state != NodeExecutionState::Failed)
+        {
+            return Err(OrchestratorError::NodeNotFailed {
+                run_id: failed.run_id,
+                node_id,
+            });
+        }
+
+        let from_node = self.retry_start(node_id);
+        let rerun = self.workflow.graph.descendants(from_node);
+        let nodes: Vec<SeededNode> = self
+            .workflow
+            .graph
+            .nodes()
+            .filter(|node| !rerun.contains(&node.id))
+            .filter_map(|node| failed.node_states.get(&node.id))
+            .filter(|exec| exec.state == NodeExecutionState::Completed)
+            .map(|exec| SeededNode {
+                node_id: exec.node_id,
+                output_key: exec.output_key.clone(),
+                taken_ports: exec.taken_ports.clone(),
+            })
+            .collect();
+
+        self.queue(
+            run_id,
+            failed.trigger_id,
+            failed.input.clone(),
+            failed.dry_run.clone(),
+        )
+        .await?;
+        let timestamp = Utc::now();
+        let event = ExecutionEvent::RunSeeded {
+            run_id,
+            source_run_id: failed.run_id,
+            from_node,
+            nodes: nodes.clone(),
+            timestamp,
+        };
+        self.event_store.publish(Envelope::new(event)).await?;
+        if let Some(state) = self.state.as_mut() {
+            state.retry_of = Some(failed.run_id);
+            for seeded in &nodes {
+                state.mark_node_seeded(seeded);
+            }
+        }
+
+        let event = ExecutionEvent::RunRetried {
+            run_id: failed.run_id,
+            retry_run_id: run_id,
+            from_node,
+            timestamp,
+        };
+        self.event_store.publish(Envelope::new(event)).await?;
+
+        Ok(())
+    }
+
+    /// Returns the node a retry from `node_id` starts at: the FanOut node
+    /// if the node runs per item of a fan-out or collects its items, or the
+    /// node itself.
+    fn retry_start(&self, node_id: NodeId) -> NodeId {
+        let graph = &self.workflow.graph;
+        if let Some(NodeConfig::ControlFlow(ControlFlowNodeConfig::FanIn { fan_out_node })) =
+            graph.get_node(node_id).map(|node| &node.config)
+        {
+            return *fan_out_node;
+        }
+        graph
+            .nodes()
+            .filter(|node| {
+                matches!(
+                    node.config,
+                    NodeConfig::ControlFlow(ControlFlowNodeConfig::FanOut)
+                )
+            })
+            .find(|node| graph.fan_out_scope(node.id).contains(&node_id))
+            .map_or(node_id, |node| node.id)
+    }
+
+    /// Loads the state of a run of the workflow from its events, including
+    /// a run that ended, without driving it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrchestratorError::RunNotFound`] if the run has no events,
+    /// or an error if loading or replaying them fails.
+    pub async fn load_run_state(
+        &self,
+        run_id: WorkflowRunId,
+    ) -> Result<RunState, OrchestratorError> {
+        let events = self.event_store.load_events(run_id).await?;
+        if events.is_empty() {
+            return Err(OrchestratorError::RunNotFound { run_id });
+        }
+        let builder = RunStateBuilder::new(self.workflow.graph.clone());
+        Ok(builder.build_from_events(events)?)
+    }
+
     /// Resumes an existing run from events.
     async fn resume(&mut self, run_id: WorkflowRunId) -> Result<(), OrchestratorError> {
         let events = self.event_store.load_events(run_id).await?;
@@ -223,7 +667,8 @@ impl<E: EventStore> Orchestrator<E> {
 
     /// Starts execution of the run.
     ///
-    /// Publishes RunStarted event and schedules ready nodes.
+    /// Publishes RunStarted event and schedules ready nodes. If the workflow
+    /// has a maximum run duration, the event records the run's deadline.
     pub async fn start(&mut self) -> Result<(), OrchestratorError> {
         let state = self.state.as_mut().ok_or(OrchestratorError::RunNotFound {
             run_id: WorkflowRunId::new(), // placeholder
@@ -235,12 +680,22 @@ impl<E: EventStore> Orchestrator<E> {
 
         let run_id = state.run_id;
         let timestamp = Utc::now();
+        let deadline = self
+            .workflow
+            .max_duration_ms
+            .and_then(|ms| i64::try_from(ms).ok())
+            .map(|ms| timestamp + Duration::milliseconds(ms));
 
         // Publish RunStarted event
-        let event = ExecutionEvent::RunStarted { run_id, timestamp };
+        let event = ExecutionEvent::RunStarted {
+            run_id,
+            deadline,
+            timestamp,
+        };
         self.event_store.publish(Envelope::new(event)).await?;
         state.execution_state = ExecutionState::Running;
         state.started_at = Some(timestamp);
+        state.deadline = deadline;
 
         // Schedule ready nodes
         self.schedule_ready_nodes().await?;
@@ -248,428 +703,4545 @@ impl<E: EventStore> Orchestrator<E> {
         Ok(())
     }
 
-    /// Schedules all ready nodes for execution.
-    async fn schedule_ready_nodes(&mut self) -> Result<(), OrchestratorError> {
-        // First, collect all the information we need while borrowing immutably
-        let (run_id, nodes_to_schedule) = {
-            let state = self.state.as_ref().ok_or(OrchestratorError::RunNotFound {
-                run_id: WorkflowRunId::new(),
-            })?;
-
-            let run_id = state.run_id;
-            let ready = state.ready_nodes();
-
-            // Collect inputs for each ready node
-            let nodes_to_schedule: Vec<(NodeId, HashMap<String, String>)> = ready
-                .into_iter()
-                .map(|node_id| {
-                    let inputs = self.collect_inputs_immutable(state, node_id);
-                    (node_id, inputs)
-                })
-                .collect();
-
-            (run_id, nodes_to_schedule)
-        };
+    /// Picks up a run after the process that drove it stopped.
+    ///
+    /// Call after [`Self::initialize`] has loaded the run. A queued run is
+    /// started. For a running run, the deadline is enforced, executions that
+    /// started but never finished are dispatched again, and nodes that
+    /// became ready are scheduled (or the run finalized) as usual. Pending
+    /// approvals stay pending.
+    ///
+    /// Only executions still running in the event log are dispatched again,
+    /// so a node recorded as finished never runs twice. A pending retry keeps
+    /// the time recorded when it was scheduled. A dispatched work
+    /// item has the same [`WorkItem::dispatch_id`] as the one published
+    /// before the crash, so the event store can drop it if the original is
+    /// still queued.
+    pub async fn recover(&mut self) -> Result<(), OrchestratorError> {
+        let state = self.state.as_ref().ok_or(OrchestratorError::RunNotFound {
+            run_id: WorkflowRunId::new(),
+        })?;
 
-        // Now process each node
-        let timestamp = Utc::now();
-        for (node_id, inputs) in nodes_to_schedule {
-            let input_json = serde_json::to_value(&inputs).unwrap_or(JsonValue::Null);
+        match state.execution_state {
+            ExecutionState::Queued => return self.start().await,
+            ExecutionState::Running | ExecutionState::Waiting => {}
+            _ => return Ok(()),
+        }
+        if self.enforce_deadline().await? {
+            return Ok(());
+        }
 
-            // Publish NodeStarted event
-            let event = ExecutionEvent::NodeStarted {
+        let state = self.state.as_ref().ok_or(OrchestratorError::RunNotFound {
+            run_id: WorkflowRunId::new(),
+        })?;
+        let run_id = state.run_id;
+        let interrupted: Vec<WorkItem> = state
+            .node_states
+            .values()
+            .chain(state.item_states.values())
+            .filter(|exec| exec.state == NodeExecutionState::Running)
+            .map(|exec| WorkItem {
                 run_id,
-                node_id,
-                input: Some(input_json.clone()),
-                timestamp,
-            };
-            self.event_store.publish(Envelope::new(event)).await?;
-
-            // Update state
-            if let Some(state) = self.state.as_mut() {
-                state.mark_node_executing(node_id, Some(input_json));
-            }
+                node_id: exec.node_id,
+                capability: Some(self.required_capability(exec.node_id)),
+                item_index: exec.item_index,
+                inputs: exec
+                    .input
+                    .clone()
+                    .and_then(|input| serde_json::from_value(input).ok())
+                    .unwrap_or_default(),
+                attempt: exec.attempt,
+                not_before: exec.retry_at,
+                dry_run: state.dry_run.as_ref().map(DryRunConfig::without_fixtures),
+            })
+            .collect();
 
-            // Publish work item for workers
-            let work_item = WorkItem {
-                run_id,
-                node_id,
-                inputs,
-            };
-            self.event_store
-                .publish_work_item(Envelope::new(work_item))
-                .await?;
+        for work_item in interrupted {
+            self.dispatch(work_item).await?;
         }
-
-        Ok(())
+        self.schedule_ready_nodes().await
     }
 
-    /// Collects inputs for a node from predecessor outputs (immutable borrow version).
-    fn collect_inputs_immutable(
-        &self,
-        state: &RunState,
-        node_id: NodeId,
-    ) -> HashMap<String, String> {
-        let mut inputs = HashMap::new();
-
-        // Get predecessors from workflow graph
-        for (predecessor, edge) in self.workflow.graph.predecessors(node_id) {
-            if let Some(exec) = state.node_states.get(&predecessor.id)
-                && let Some(output_key) = &exec.output_key
-            {
-                // Map output port to input port
-                inputs.insert(edge.target_port.clone(), output_key.clone());
-            }
+    /// Schedules all ready nodes for execution.
+    ///
+    /// Ready nodes with an input port whose upstream paths were all skipped
+    /// are skipped themselves, and control flow nodes are evaluated in
+    /// place. Nodes inside an expanded fan-out are scheduled once per item.
+    /// Since skipping and evaluating nodes can make further nodes ready, this
+    /// repeats until only worker nodes were scheduled. The run is finalized
+    /// once no work remains.
+    async fn schedule_ready_nodes(&mut self) -> Result<(), OrchestratorError> {
+        // A cancelled or finished run schedules nothing
+        if self
+            .state
+            .as_ref()
+            .is_some_and(|state| !state.execution_state.is_active())
+        {
+            return Ok(());
         }
 
-        inputs
-    }
+        loop {
+            // First, collect all the information we need while borrowing immutably
+            let (run_id, nodes_to_schedule, finished_fan_outs) = {
+                let state = self.state.as_ref().ok_or(OrchestratorError::RunNotFound {
+                    run_id: WorkflowRunId::new(),
+                })?;
 
-    /// Handles a work item result (completion or failure).
-    pub async fn handle_result(&mut self, result: WorkItemResult) -> Result<(), OrchestratorError> {
-        let state = self.state.as_mut().ok_or(OrchestratorError::RunNotFound {
-            run_id: WorkflowRunId::new(),
-        })?;
+                // Collect inputs (or the reason to skip) for each ready node
+                let mut nodes_to_schedule: Vec<(NodeId, Option<usize>, ReadyNode)> = state
+                    .ready_nodes()
+                    .into_iter()
+                    .filter_map(|node_id| match self.readiness(state, node_id, None) {
+                        Readiness::Ready(ready_node) => Some((node_id, None, ready_node)),
+                        Readiness::Waiting | Readiness::Blocked => None,
+                    })
+                    .collect();
 
-        let timestamp = Utc::now();
+                // Then the same for each item of the expanded fan-outs
+                let mut finished_fan_outs = Vec::new();
+                for (&fan_out, fan_out_state) in &state.fan_outs {
+                    if fan_out_state.finished {
+                        continue;
+                    }
+                    let (ready_items, in_progress) =
+                        self.ready_items(state, fan_out, fan_out_state);
+                    nodes_to_schedule.extend(ready_items);
+                    if !in_progress {
+                        finished_fan_outs.push(fan_out);
+                    }
+                }
 
-        match result {
-            WorkItemResult::Completed {
-                run_id,
-                node_id,
-                output_key,
-            } => {
-                // Publish NodeCompleted event
-                let event = ExecutionEvent::NodeCompleted {
+                (state.run_id, nodes_to_schedule, finished_fan_outs)
+            };
+
+            // Fan-outs with no item left to run hand over to their FanIn
+            let mut graph_changed = !finished_fan_outs.is_empty();
+            for node_id in finished_fan_outs {
+                let event = ExecutionEvent::FanOutFinished {
                     run_id,
                     node_id,
-                    output_key: output_key.clone(),
-                    timestamp,
+                    timestamp: Utc::now(),
                 };
                 self.event_store.publish(Envelope::new(event)).await?;
-                state.mark_node_completed(node_id, output_key);
+                if let Some(state) = self.state.as_mut() {
+                    state.mark_fan_out_finished(node_id);
+                }
             }
-            WorkItemResult::Failed {
-                run_id,
-                node_id,
-                error,
-            } => {
-                // Publish NodeFailed event
-                let event = ExecutionEvent::NodeFailed {
+
+            // Now process each node
+            let timestamp = Utc::now();
+            for (node_id, item_index, ready_node) in nodes_to_schedule {
+                let inputs = match ready_node {
+                    ReadyNode::Execute { inputs } => inputs,
+                    ReadyNode::Skip { port } => {
+                        self.skip_node(
+                            run_id,
+                            node_id,
+                            item_index,
+                            format!("no active upstream path for input port '{port}'"),
+                        )
+                        .await?;
+                        graph_changed = true;
+                        continue;
+                    }
+                };
+
+                let input_json = serde_json::to_value(&inputs).unwrap_or(JsonValue::Null);
+
+                // Publish NodeStarted event
+                let event = ExecutionEvent::NodeStarted {
                     run_id,
                     node_id,
-                    error: error.clone(),
+                    item_index,
+                    input: Some(input_json.clone()),
                     timestamp,
                 };
                 self.event_store.publish(Envelope::new(event)).await?;
-                state.mark_node_failed(node_id, error);
+
+                // Update state
+                if let Some(state) = self.state.as_mut() {
+                    state.record_outcome(
+                        node_id,
+                        item_index,
+                        NodeOutcome::Started(Some(input_json)),
+                    );
+                }
+
+                let work_item = WorkItem {
+                    run_id,
+                    node_id,
+                    capability: Some(self.required_capability(node_id)),
+                    item_index,
+                    inputs,
+                    attempt: first_attempt(),
+                    not_before: None,
+                    dry_run: self
+                        .state
+                        .as_ref()
+                        .and_then(|state| state.dry_run.as_ref())
+                        .map(DryRunConfig::without_fixtures),
+                };
+                graph_changed |= self.dispatch(work_item).await?;
+            }
+
+            if !graph_changed {
+                break;
             }
         }
 
-        // Check if run is complete
-        if state.remaining_work().is_complete() {
+        if self.state.as_ref().is_some_and(|state| {
+            state.execution_state.is_active() && state.remaining_work().is_complete()
+        }) {
             self.finalize_run().await?;
-        } else {
-            // Schedule any newly ready nodes
-            self.schedule_ready_nodes().await?;
         }
 
         Ok(())
     }
 
-    /// Finalizes the run (marks as completed or failed).
-    async fn finalize_run(&mut self) -> Result<(), OrchestratorError> {
-        let state = self.state.as_mut().ok_or(OrchestratorError::RunNotFound {
-            run_id: WorkflowRunId::new(),
-        })?;
-
-        let run_id = state.run_id;
-        let timestamp = Utc::now();
+    /// Executes a started node (or one item of it).
+    ///
+    /// Control flow nodes are evaluated in place; other nodes are published
+    /// as work items for workers. Returns true if the node was evaluated in
+    /// place, which may make further nodes ready.
+    async fn dispatch(&mut self, work_item: WorkItem) -> Result<bool, OrchestratorError> {
+        let WorkItem {
+            run_id,
+            node_id,
+            item_index,
+            ref inputs,
+            ..
+        } = work_item;
 
-        if state.has_failures() {
-            // Run failed due to node failures
-            let event = ExecutionEvent::RunFailed {
-                run_id,
-                error: "workflow failed due to node failures".to_string(),
-                timestamp,
-            };
-            self.event_store.publish(Envelope::new(event)).await?;
-            state.fail(
-                "workflow failed due to node failures".to_string(),
-                timestamp,
-            );
-        } else {
-            // Run completed successfully
-            let event = ExecutionEvent::RunCompleted {
-                run_id,
-                output: None, // TODO: collect final output
-                timestamp,
-            };
-            self.event_store.publish(Envelope::new(event)).await?;
-            state.complete(None, timestamp);
+        // Control flow is graph logic, so it is not sent to workers
+        match self.inline_node(node_id) {
+            Some(InlineNode::Branch(conditions)) => {
+                self.evaluate_branch(run_id, node_id, item_index, inputs, &conditions)
+                    .await?;
+            }
+            Some(InlineNode::FanOut) => {
+                self.expand_fan_out(run_id, node_id, inputs).await?;
+            }
+            Some(InlineNode::FanIn { fan_out_node }) => {
+                self.collect_fan_in(run_id, node_id, fan_out_node).await?;
+            }
+            Some(InlineNode::Approval { expires_after_ms }) => {
+                self.request_approval(run_id, node_id, item_index, inputs, expires_after_ms)
+                    .await?;
+            }
+            Some(InlineNode::Delay(until)) => {
+                self.start_delay(run_id, node_id, item_index, inputs, &until)
+                    .await?;
+            }
+            Some(InlineNode::Substitute) => {
+                self.substitute_node(run_id, node_id, item_index, inputs)
+                    .await?;
+            }
+            None => {
+                // Work that no worker would ever pick up fails instead
+                if let Some(capability) = &work_item.capability
+                    && !self.event_store.has_worker_for(capability).await?
+                {
+                    self.fail_unserved(&work_item, capability).await?;
+                    return Ok(true);
+                }
+                self.event_store
+                    .publish_work_item(Envelope::new(work_item))
+                    .await?;
+                return Ok(false);
+            }
         }
-
-        Ok(())
+        Ok(true)
     }
 
-    /// Returns the current run state.
-    #[must_use]
-    pub fn state(&self) -> Option<&RunState> {
-        self.state.as_ref()
+    /// Fails a work item whose capability no worker serves.
+    async fn fail_unserved(
+        &mut self,
+        work_item: &WorkItem,
+        capability: &Capability,
+    ) -> Result<(), OrchestratorError> {
+        let WorkItem {
+            run_id,
+            node_id,
+            item_index,
+            attempt,
+            ..
+        } = *work_item;
+        let error = ExecutionError::NodeFailed {
+            node_id,
+            reason: format!("no worker serves the capability '{capability}'"),
+        }
+        .to_string();
+
+        let event = ExecutionEvent::NodeFailed {
+            run_id,
+            node_id,
+            item_index,
+            attempt,
+            error: error.clone(),
+            timestamp: Utc::now(),
+        };
+        self.event_store.publish(Envelope::new(event)).await?;
+
+        if let Some(state) = self.state.as_mut() {
+            state.record_outcome(node_id, item_index, NodeOutcome::Failed(error));
+        }
+        Ok(())
     }
 
-    /// Returns the run ID.
-    #[must_use]
-    pub fn run_id(&self) -> Option<WorkflowRunId> {
-        self.state.as_ref().map(|s| s.run_id)
+    /// Returns the capability a worker must serve to execute a node.
+    ///
+    /// Nodes missing from the graph fail on any worker, so they get the
+    /// capability of transforms, which every general worker serves.
+    fn required_capability(&self, node_id: NodeId) -> Capability {
+        self.workflow.graph.get_node(node_id).map_or_else(
+            || Capability::for_category(&NodeCategory::Transform),
+            Node::required_capability,
+        )
     }
 
-    /// Returns true if the run is complete.
-    #[must_use]
-    pub fn is_complete(&self) -> bool {
-        self.state.as_ref().is_some_and(|s| s.is_complete())
+    /// Returns how the orchestrator evaluates a node, or None for worker nodes.
+    fn inline_node(&self, node_id: NodeId) -> Option<InlineNode> {
+        let node = self.workflow.graph.get_node(node_id)?;
+        // Nodes a dry run substitutes must never reach a worker
+        if self
+            .state
+            .as_ref()
+            .and_then(|state| state.dry_run.as_ref())
+            .is_some_and(|dry_run| dry_run.substitutes(node))
+        {
+            return Some(InlineNode::Substitute);
+        }
+        match &node.config {
+            NodeConfig::ControlFlow(ControlFlowNodeConfig::Branch { conditions }) => {
+                Some(InlineNode::Branch(conditions.clone()))
+            }
+            NodeConfig::ControlFlow(ControlFlowNodeConfig::FanOut) => Some(InlineNode::FanOut),
+            NodeConfig::ControlFlow(ControlFlowNodeConfig::FanIn { fan_out_node }) => {
+                Some(InlineNode::FanIn {
+                    fan_out_node: *fan_out_node,
+                })
+            }
+            NodeConfig::ControlFlow(ControlFlowNodeConfig::Approval {
+                expires_after_ms, ..
+            }) => Some(InlineNode::Approval {
+                expires_after_ms: *expires_after_ms,
+            }),
+            NodeConfig::ControlFlow(ControlFlowNodeConfig::Delay { until }) => {
+                Some(InlineNode::Delay(until.clone()))
+            }
+            _ => None,
+        }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::edge::Edge;
-    use crate::node::{AiLayerNodeConfig, Node, NodeConfig, TriggerNodeConfig};
-    use std::sync::{Arc, Mutex};
+    /// Returns the items of a fan-out that are ready to run.
+    ///
+    /// Also returns whether any item is still in progress: running, ready,
+    /// waiting for an approval, or waiting on an upstream node. Items
+    /// downstream of a failed item or node are blocked and never run.
+    fn ready_items(
+        &self,
+        state: &RunState,
+        fan_out: NodeId,
+        fan_out_state: &FanOutState,
+    ) -> (Vec<(NodeId, Option<usize>, ReadyNode)>, bool) {
+        let mut ready = Vec::new();
+        let mut blocked = HashSet::new();
+        let mut in_progress = false;
 
-    /// In-memory event store for testing.
-    struct InMemoryEventStore {
-        events: Arc<Mutex<Vec<Envelope<ExecutionEvent>>>>,
-        work_items: Arc<Mutex<Vec<Envelope<WorkItem>>>>,
+        for index in 0..fan_out_state.item_keys.len() {
+            // Scope nodes are in topological order, so upstream items are
+            // known to be blocked before their successors are checked
+            for &node_id in &fan_out_state.scope {
+                match state.item_states.get(&(node_id, index)).map(|e| e.state) {
+                    Some(NodeExecutionState::Pending) => {}
+                    Some(
+                        NodeExecutionState::Ready
+                        | NodeExecutionState::Running
+                        | NodeExecutionState::Waiting,
+                    ) => {
+                        in_progress = true;
+                        continue;
+                    }
+                    _ => continue,
+                }
+
+                let item = ItemContext {
+                    fan_out,
+                    fan_out_state,
+                    index,
+                    blocked: &blocked,
+                };
+                match self.readiness(state, node_id, Some(&item)) {
+                    Readiness::Ready(ready_node) => {
+                        in_progress = true;
+                        ready.push((node_id, Some(index), ready_node));
+                    }
+                    Readiness::Waiting => in_progress = true,
+                    Readiness::Blocked => {
+                        blocked.insert((node_id, index));
+                    }
+                }
+            }
+        }
+
+        (ready, in_progress)
     }
 
-    impl InMemoryEventStore {
-        fn new() -> Self {
-            Self {
-                events: Arc::new(Mutex::new(Vec::new())),
-                work_items: Arc::new(Mutex::new(Vec::new())),
+    /// Determines whether a node (or one item of it) can run, and its inputs.
+    ///
+    /// An incoming edge is inactive when its source was skipped or is a
+    /// branch that did not take the edge's port. A node with a connected
+    /// input port whose edges are all inactive is on an untaken path and is
+    /// skipped. Join nodes have a single input port, so they run as long as
+    /// any incoming path was taken.
+    ///
+    /// For an item, predecessors inside the fan-out scope provide their
+    /// output for the same item, and the FanOut node provides the item.
+    fn readiness(
+        &self,
+        state: &RunState,
+        node_id: NodeId,
+        item: Option<&ItemContext<'_>>,
+    ) -> Readiness {
+        let mut ports: BTreeMap<&str, bool> = BTreeMap::new();
+        let mut inputs = HashMap::new();
+
+        for (predecessor, edge) in self.workflow.graph.predecessors(node_id) {
+            let source = match item {
+                Some(item) if predecessor.id == item.fan_out => {
+                    if let Some(key) = item.fan_out_state.item_keys.get(item.index) {
+                        inputs.insert(edge.target_port.clone(), key.clone());
+                    }
+                    ports.insert(edge.target_port.as_str(), true);
+                    continue;
+                }
+                Some(item) if item.fan_out_state.scope.contains(&predecessor.id) => {
+                    if item.blocked.contains(&(predecessor.id, item.index)) {
+                        return Readiness::Blocked;
+                    }
+                    state.item_states.get(&(predecessor.id, item.index))
+                }
+                _ => state.node_states.get(&predecessor.id),
+            };
+
+            let active = match source {
+                Some(exec) => match exec.state {
+                    NodeExecutionState::Completed => exec.is_port_active(&edge.source_port),
+                    NodeExecutionState::Skipped => false,
+                    NodeExecutionState::Failed => return Readiness::Blocked,
+                    NodeExecutionState::Pending
+                        if exec.item_index.is_none()
+                            && state
+                                .remaining_work()
+                                .blocked_nodes()
+                                .contains(&exec.node_id) =>
+                    {
+                        return Readiness::Blocked;
+                    }
+                    NodeExecutionState::Pending
+                    | NodeExecutionState::Ready
+                    | NodeExecutionState::Running
+                    | NodeExecutionState::Waiting => return Readiness::Waiting,
+                },
+                None => true,
+            };
+
+            *ports.entry(edge.target_port.as_str()).or_default() |= active;
+            if active && let Some(output_key) = source.and_then(|exec| exec.output_key.as_ref()) {
+                // Map output port to input port
+                inputs.insert(edge.target_port.clone(), output_key.clone());
             }
         }
 
-        fn events(&self) -> Vec<ExecutionEvent> {
-            self.events
-                .lock()
-                .unwrap()
-                .iter()
-                .map(|e| e.payload.clone())
-                .collect()
+        match ports.into_iter().find(|(_, active)| !active) {
+            Some((port, _)) => Readiness::Ready(ReadyNode::Skip {
+                port: port.to_string(),
+            }),
+            None => Readiness::Ready(ReadyNode::Execute { inputs }),
         }
+    }
 
-        fn work_items(&self) -> Vec<WorkItem> {
-            self.work_items
-                .lock()
-                .unwrap()
-                .iter()
-                .map(|w| w.payload.clone())
-                .collect()
+    /// Evaluates a branch node's conditions against its input.
+    ///
+    /// The branch passes its input through unchanged on every port whose
+    /// condition matches. Nodes that only receive data from the other ports
+    /// are skipped as they become ready.
+    async fn evaluate_branch(
+        &mut self,
+        run_id: WorkflowRunId,
+        node_id: NodeId,
+        item_index: Option<usize>,
+        inputs: &HashMap<String, String>,
+        conditions: &[BranchCondition],
+    ) -> Result<(), OrchestratorError> {
+        let timestamp = Utc::now();
+
+        let taken = self.taken_ports(node_id, inputs, conditions).await;
+        let Some(state) = self.state.as_mut() else {
+            return Ok(());
+        };
+
+        match taken {
+            Ok((ports, output_key)) => {
+                let event = ExecutionEvent::BranchTaken {
+                    run_id,
+                    node_id,
+                    item_index,
+                    ports: ports.clone(),
+                    timestamp,
+                };
+                self.event_store.publish(Envelope::new(event)).await?;
+
+                let event = ExecutionEvent::NodeCompleted {
+                    run_id,
+                    node_id,
+                    item_index,
+                    attempt: 1,
+                    output_key: output_key.clone(),
+                    timestamp,
+                };
+                self.event_store.publish(Envelope::new(event)).await?;
+
+                state.mark_branch_taken(node_id, item_index, ports);
+                state.record_outcome(node_id, item_index, NodeOutcome::Completed(output_key));
+            }
+            Err(e) => {
+                let event = ExecutionEvent::NodeFailed {
+                    run_id,
+                    node_id,
+                    item_index,
+                    attempt: 1,
+                    error: e.to_string(),
+                    timestamp,
+                };
+                self.event_store.publish(Envelope::new(event)).await?;
+                state.record_outcome(node_id, item_index, NodeOutcome::Failed(e.to_string()));
+            }
         }
+
+        Ok(())
     }
 
-    #[async_trait]
-    impl EventStore for InMemoryEventStore {
-        async fn publish(&self, event: Envelope<ExecutionEvent>) -> Result<(), EventStoreError> {
-            self.events.lock().unwrap().push(event);
-            Ok(())
+    /// Returns the ports whose condition matches and the input's output key.
+    async fn taken_ports(
+        &self,
+        node_id: NodeId,
+        inputs: &HashMap<String, String>,
+        conditions: &[BranchCondition],
+    ) -> Result<(Vec<String>, String), ExecutionError> {
+        let input_key = inputs
+            .get("input")
+            .ok_or_else(|| ExecutionError::MissingInput {
+                node_id,
+                port_name: "input".to_string(),
+            })?;
+        let input = self.read_json(node_id, input_key).await?;
+
+        let mut ports = Vec::new();
+        for branch in conditions {
+            let condition =
+                Condition::parse(&branch.condition).map_err(|e| ExecutionError::NodeFailed {
+                    node_id,
+                    reason: format!("invalid condition for port '{}': {e}", branch.port),
+                })?;
+            let taken = condition
+                .try_evaluate(&input)
+                .map_err(|e| ExecutionError::NodeFailed {
+                    node_id,
+                    reason: format!("condition for port '{}' failed: {e}", branch.port),
+                })?;
+            if taken {
+                ports.push(branch.port.clone());
+            }
         }
 
-        async fn load_events(
-            &self,
-            run_id: WorkflowRunId,
-        ) -> Result<Vec<ExecutionEvent>, EventStoreError> {
-            Ok(self
-                .events
-                .lock()
-                .unwrap()
-                .iter()
-                .filter(|e| e.payload.run_id() == run_id)
-                .map(|e| e.payload.clone())
-                .collect())
+        Ok((ports, input_key.clone()))
+    }
+
+    /// Splits a FanOut node's input array into items.
+    ///
+    /// Each item is stored on its own, and the nodes in the fan-out's scope
+    /// then run once per item. The FanOut node itself passes the array
+    /// through.
+    async fn expand_fan_out(
+        &mut self,
+        run_id: WorkflowRunId,
+        node_id: NodeId,
+        inputs: &HashMap<String, String>,
+    ) -> Result<(), OrchestratorError> {
+        let timestamp = Utc::now();
+
+        let items = self.store_items(node_id, inputs).await;
+        let Some(state) = self.state.as_mut() else {
+            return Ok(());
+        };
+
+        match items {
+            Ok((item_keys, output_key)) => {
+                let event = ExecutionEvent::FanOutExpanded {
+                    run_id,
+                    node_id,
+                    item_keys: item_keys.clone(),
+                    timestamp,
+                };
+                self.event_store.publish(Envelope::new(event)).await?;
+                state.mark_fan_out_expanded(node_id, item_keys);
+
+                let event = ExecutionEvent::NodeCompleted {
+                    run_id,
+                    node_id,
+                    item_index: None,
+                    attempt: 1,
+                    output_key: output_key.clone(),
+                    timestamp,
+                };
+                self.event_store.publish(Envelope::new(event)).await?;
+                state.record_outcome(node_id, None, NodeOutcome::Completed(output_key));
+            }
+            Err(e) => {
+                let event = ExecutionEvent::NodeFailed {
+                    run_id,
+                    node_id,
+                    item_index: None,
+                    attempt: 1,
+                    error: e.to_string(),
+                    timestamp,
+                };
+                self.event_store.publish(Envelope::new(event)).await?;
+                state.record_outcome(node_id, None, NodeOutcome::Failed(e.to_string()));
+            }
         }
 
-        async fn publish_work_item(&self, item: Envelope<WorkItem>) -> Result<(), EventStoreError> {
-            self.work_items.lock().unwrap().push(item);
-            Ok(())
+        Ok(())
+    }
+
+    /// Stores each element of a FanOut node's input array.
+    ///
+    /// Returns the item keys in order and the input's output key.
+    async fn store_items(
+        &self,
+        node_id: NodeId,
+        inputs: &HashMap<String, String>,
+    ) -> Result<(Vec<String>, String), ExecutionError> {
+        let input_key = inputs
+            .get("items")
+            .ok_or_else(|| ExecutionError::MissingInput {
+                node_id,
+                port_name: "items".to_string(),
+            })?;
+        let failed = |reason: String| ExecutionError::NodeFailed { node_id, reason };
+
+        let JsonValue::Array(items) = self.read_json(node_id, input_key).await? else {
+            return Err(failed("fan-out input is not an array".to_string()));
+        };
+
+        let mut item_keys = Vec::with_capacity(items.len());
+        for item in &items {
+            let bytes = serde_json::to_vec(item).map_err(|e| failed(e.to_string()))?;
+            let key = self
+                .object_store
+                .put(&bytes)
+                .await
+                .map_err(|e| failed(e.to_string()))?;
+            item_keys.push(key);
+        }
+
+        Ok((item_keys, input_key.clone()))
+    }
+
+    /// Collects the per-item results of a fan-out into an array.
+    ///
+    /// Items are collected in their original order. Items whose path to the
+    /// FanIn node was skipped (e.g., by a branch) are left out.
+    async fn collect_fan_in(
+        &mut self,
+        run_id: WorkflowRunId,
+        node_id: NodeId,
+        fan_out_node: NodeId,
+    ) -> Result<(), OrchestratorError> {
+        let timestamp = Utc::now();
+
+        let collected = self.store_collected_items(node_id, fan_out_node).await;
+        let Some(state) = self.state.as_mut() else {
+            return Ok(());
+        };
+
+        match collected {
+            Ok(output_key) => {
+                let event = ExecutionEvent::NodeCompleted {
+                    run_id,
+                    node_id,
+                    item_index: None,
+                    attempt: 1,
+                    output_key: output_key.clone(),
+                    timestamp,
+                };
+                self.event_store.publish(Envelope::new(event)).await?;
+                state.record_outcome(node_id, None, NodeOutcome::Completed(output_key));
+            }
+            Err(e) => {
+                let event = ExecutionEvent::NodeFailed {
+                    run_id,
+                    node_id,
+                    item_index: None,
+                    attempt: 1,
+                    error: e.to_string(),
+                    timestamp,
+                };
+                self.event_store.publish(Envelope::new(event)).await?;
+                state.record_outcome(node_id, None, NodeOutcome::Failed(e.to_string()));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads each item's result for a FanIn node and stores them as an array.
+    async fn store_collected_items(
+        &self,
+        node_id: NodeId,
+        fan_out_node: NodeId,
+    ) -> Result<String, ExecutionError> {
+        let failed = |reason: String| ExecutionError::NodeFailed { node_id, reason };
+        let state = self
+            .state
+            .as_ref()
+            .ok_or_else(|| failed("run state not loaded".to_string()))?;
+        let fan_out_state = state
+            .fan_outs
+            .get(&fan_out_node)
+            .ok_or_else(|| failed(format!("fan-out {fan_out_node} was not expanded")))?;
+
+        let blocked = HashSet::new();
+        let mut items = Vec::with_capacity(fan_out_state.item_keys.len());
+        for index in 0..fan_out_state.item_keys.len() {
+            let item = ItemContext {
+                fan_out: fan_out_node,
+                fan_out_state,
+                index,
+                blocked: &blocked,
+            };
+            if let Readiness::Ready(ReadyNode::Execute { inputs }) =
+                self.readiness(state, node_id, Some(&item))
+                && let Some(key) = inputs.get("item")
+            {
+                items.push(self.read_json(node_id, key).await?);
+            }
+        }
+
+        let bytes = serde_json::to_vec(&items).map_err(|e| failed(e.to_string()))?;
+        self.object_store
+            .put(&bytes)
+            .await
+            .map_err(|e| failed(e.to_string()))
+    }
+
+    /// Pauses an approval node (or one item of it) until a decision is made.
+    ///
+    /// The node's input is recorded as the approval's payload, so approvers
+    /// see what they decide on. Nodes downstream of the approval wait, while
+    /// other paths of the run carry on.
+    async fn request_approval(
+        &mut self,
+        run_id: WorkflowRunId,
+        node_id: NodeId,
+        item_index: Option<usize>,
+        inputs: &HashMap<String, String>,
+        expires_after_ms: Option<u64>,
+    ) -> Result<(), OrchestratorError> {
+        let timestamp = Utc::now();
+
+        let payload = match inputs.get("input") {
+            Some(key) => self.read_json(node_id, key).await,
+            None => Err(ExecutionError::MissingInput {
+                node_id,
+                port_name: "input".to_string(),
+            }),
+        };
+        let Some(state) = self.state.as_mut() else {
+            return Ok(());
+        };
+
+        match payload {
+            Ok(payload) => {
+                let expires_at = expires_after_ms
+                    .and_then(|ms| i64::try_from(ms).ok())
+                    .map(|ms| timestamp + Duration::milliseconds(ms));
+                let event = ExecutionEvent::ApprovalRequested {
+                    run_id,
+                    node_id,
+                    item_index,
+                    payload,
+                    expires_at,
+                    timestamp,
+                };
+                self.event_store.publish(Envelope::new(event)).await?;
+                state.mark_waiting(node_id, item_index, expires_at);
+            }
+            Err(e) => {
+                let event = ExecutionEvent::NodeFailed {
+                    run_id,
+                    node_id,
+                    item_index,
+                    attempt: 1,
+                    error: e.to_string(),
+                    timestamp,
+                };
+                self.event_store.publish(Envelope::new(event)).await?;
+                state.record_outcome(node_id, item_index, NodeOutcome::Failed(e.to_string()));
+            }
         }
+
+        Ok(())
+    }
+
+    /// Pauses a delay node (or one item of it) until its delay ends.
+    ///
+    /// Nodes downstream of the delay wait, while other paths of the run
+    /// carry on. No worker is involved: whoever drives the orchestrator
+    /// calls [`Orchestrator::end_delays`] once the time is reached. A time
+    /// that already passed ends the delay right away.
+    async fn start_delay(
+        &mut self,
+        run_id: WorkflowRunId,
+        node_id: NodeId,
+        item_index: Option<usize>,
+        inputs: &HashMap<String, String>,
+        until: &DelayUntil,
+    ) -> Result<(), OrchestratorError> {
+        let timestamp = Utc::now();
+
+        let until = self.delay_end(node_id, inputs, until, timestamp).await;
+        let Some(state) = self.state.as_mut() else {
+            return Ok(());
+        };
+
+        match until {
+            Ok(until) => {
+                let event = ExecutionEvent::DelayStarted {
+                    run_id,
+                    node_id,
+                    item_index,
+                    until,
+                    timestamp,
+                };
+                self.event_store.publish(Envelope::new(event)).await?;
+                state.mark_waiting(node_id, item_index, Some(until));
+                if until <= timestamp {
+                    self.end_delay(node_id, item_index).await?;
+                }
+            }
+            Err(e) => {
+                let event = ExecutionEvent::NodeFailed {
+                    run_id,
+                    node_id,
+                    item_index,
+                    attempt: 1,
+                    error: e.to_string(),
+                    timestamp,
+                };
+                self.event_store.publish(Envelope::new(event)).await?;
+                state.record_outcome(node_id, item_index, NodeOutcome::Failed(e.to_string()));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns when a delay node reached at `now` ends.
+    async fn delay_end(
+        &self,
+        node_id: NodeId,
+        inputs: &HashMap<String, String>,
+        until: &DelayUntil,
+        now: DateTime<Utc>,
+    ) -> Result<DateTime<Utc>, ExecutionError> {
+        let failed = |reason: String| ExecutionError::NodeFailed { node_id, reason };
+        match until {
+            DelayUntil::Duration { duration_ms } => i64::try_from(*duration_ms)
+                .ok()
+                .and_then(Duration::try_milliseconds)
+                .and_then(|duration| now.checked_add_signed(duration))
+                .ok_or_else(|| failed(format!("delay of {duration_ms}ms is too long"))),
+            DelayUntil::Time { expression } => {
+                let input_key =
+                    inputs
+                        .get("input")
+                        .ok_or_else(|| ExecutionError::MissingInput {
+                            node_id,
+                            port_name: "input".to_string(),
+                        })?;
+                let input = self.read_json(node_id, input_key).await?;
+                let value = Expression::parse(expression)
+                    .and_then(|expression| expression.evaluate(&input))
+                    .map_err(|e| failed(format!("delay time '{expression}' failed: {e}")))?;
+                parse_date(&value).ok_or_else(|| {
+                    failed(format!(
+                        "delay time '{expression}' evaluated to {value}, which is not a date"
+                    ))
+                })
+            }
+        }
+    }
+
+    /// Ends the delay of a waiting delay node (or one item of it), passing
+    /// its input through.
+    ///
+    /// Does not schedule the nodes that become ready.
+    async fn end_delay(
+        &mut self,
+        node_id: NodeId,
+        item_index: Option<usize>,
+    ) -> Result<(), OrchestratorError> {
+        let state = self.state.as_mut().ok_or(OrchestratorError::RunNotFound {
+            run_id: WorkflowRunId::new(),
+        })?;
+        let run_id = state.run_id;
+        let Some(output_key) = waiting_input_key(state, node_id, item_index) else {
+            return Ok(());
+        };
+
+        let timestamp = Utc::now();
+        let events = [
+            ExecutionEvent::DelayEnded {
+                run_id,
+                node_id,
+                item_index,
+                timestamp,
+            },
+            ExecutionEvent::NodeCompleted {
+                run_id,
+                node_id,
+                item_index,
+                attempt: 1,
+                output_key: output_key.clone(),
+                timestamp,
+            },
+        ];
+        for event in events {
+            self.event_store.publish(Envelope::new(event)).await?;
+        }
+
+        state.mark_resumed(node_id, item_index);
+        state.record_outcome(node_id, item_index, NodeOutcome::Completed(output_key));
+        Ok(())
+    }
+
+    /// Completes a node (or one item of it) that a dry run substitutes with
+    /// its fixture response, recording what it would have sent.
+    async fn substitute_node(
+        &mut self,
+        run_id: WorkflowRunId,
+        node_id: NodeId,
+        item_index: Option<usize>,
+        inputs: &HashMap<String, String>,
+    ) -> Result<(), OrchestratorError> {
+        let timestamp = Utc::now();
+
+        let substitution = self.substitution(node_id, inputs).await;
+        let substitution = match substitution {
+            Ok((request, response)) => match serde_json::to_vec(&response) {
+                Ok(bytes) => self
+                    .object_store
+                    .put(&bytes)
+                    .await
+                    .map(|output_key| (request, response, output_key))
+                    .map_err(|e| ExecutionError::NodeFailed {
+                        node_id,
+                        reason: e.to_string(),
+                    }),
+                Err(e) => Err(ExecutionError::NodeFailed {
+                    node_id,
+                    reason: e.to_string(),
+                }),
+            },
+            Err(e) => Err(e),
+        };
+        let Some(state) = self.state.as_mut() else {
+            return Ok(());
+        };
+
+        match substitution {
+            Ok((request, response, output_key)) => {
+                let events = [
+                    ExecutionEvent::NodeSubstituted {
+                        run_id,
+                        node_id,
+                        item_index,
+                        request,
+                        response,
+                        timestamp,
+                    },
+                    ExecutionEvent::NodeCompleted {
+                        run_id,
+                        node_id,
+                        item_index,
+                        attempt: 1,
+                        output_key: output_key.clone(),
+                        timestamp,
+                    },
+                ];
+                for event in events {
+                    self.event_store.publish(Envelope::new(event)).await?;
+                }
+                state.record_outcome(node_id, item_index, NodeOutcome::Completed(output_key));
+            }
+            Err(e) => {
+                let event = ExecutionEvent::NodeFailed {
+                    run_id,
+                    node_id,
+                    item_index,
+                    attempt: 1,
+                    error: e.to_string(),
+                    timestamp,
+                };
+                self.event_store.publish(Envelope::new(event)).await?;
+                state.record_outcome(node_id, item_index, NodeOutcome::Failed(e.to_string()));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns what a substituted node would have sent and its response,
+    /// which must match the node's output ports like a worker's output.
+    async fn substitution(
+        &self,
+        node_id: NodeId,
+        inputs: &HashMap<String, String>,
+    ) -> Result<(JsonValue, JsonValue), ExecutionError> {
+        let node =
+            self.workflow
+                .graph
+                .get_node(node_id)
+                .ok_or_else(|| ExecutionError::NodeFailed {
+                    node_id,
+                    reason: "node not found in the workflow".to_string(),
+                })?;
+        let dry_run = self
+            .state
+            .as_ref()
+            .and_then(|state| state.dry_run.as_ref())
+            .ok_or_else(|| ExecutionError::NodeFailed {
+                node_id,
+                reason: "the run is not a dry run".to_string(),
+            })?;
+
+        let mut values = HashMap::new();
+        for (port_name, key) in inputs {
+            values.insert(port_name.clone(), self.read_json(node_id, key).await?);
+        }
+        let request = substituted_request(node, &values);
+        let response = dry_run.response(node);
+        for port in &node.outputs {
+            port.schema.validate(&response).map_err(|mismatch| {
+                ExecutionError::OutputValidationFailed {
+                    node_id,
+                    reason: format!(
+                        "dry run response does not match port '{}' at {mismatch}",
+                        port.name
+                    ),
+                }
+            })?;
+        }
+        Ok((request, response))
+    }
+
+    /// Reads a JSON value from the object store.
+    async fn read_json(&self, node_id: NodeId, key: &str) -> Result<JsonValue, ExecutionError> {
+        let failed = |reason: String| ExecutionError::NodeFailed { node_id, reason };
+        let bytes = self
+            .object_store
+            .get(key)
+            .await
+            .map_err(|e| failed(e.to_string()))?;
+        serde_json::from_slice(&bytes).map_err(|e| failed(e.to_string()))
+    }
+
+    /// Publishes NodeSkipped for a node (or one item of it).
+    ///
+    /// A skipped node is removed from the remaining work.
+    async fn skip_node(
+        &mut self,
+        run_id: WorkflowRunId,
+        node_id: NodeId,
+        item_index: Option<usize>,
+        reason: String,
+    ) -> Result<(), OrchestratorError> {
+        let event = ExecutionEvent::NodeSkipped {
+            run_id,
+            node_id,
+            item_index,
+            reason,
+            timestamp: Utc::now(),
+        };
+        self.event_store.publish(Envelope::new(event)).await?;
+        if let Some(state) = self.state.as_mut() {
+            state.record_outcome(node_id, item_index, NodeOutcome::Skipped);
+        }
+        Ok(())
+    }
+
+    /// Handles a work item result (completion, failure, or a sub-workflow
+    /// node waiting for its child run).
+    ///
+    /// Failures the node's retry policy covers schedule another attempt
+    /// instead of failing the node. Results for another run, for earlier
+    /// attempts, or for executions that already finished, such as
+    /// redelivered results, are ignored, as are results for runs that have
+    /// ended or are past their deadline.
+    pub async fn handle_result(&mut self, result: WorkItemResult) -> Result<(), OrchestratorError> {
+        let state = self.state.as_mut().ok_or(OrchestratorError::RunNotFound {
+            run_id: WorkflowRunId::new(),
+        })?;
+
+        // Results that arrive after the run ended change nothing
+        if state.execution_state.is_terminal() || self.enforce_deadline().await? {
+            return Ok(());
+        }
+        let state = self.state.as_mut().ok_or(OrchestratorError::RunNotFound {
+            run_id: WorkflowRunId::new(),
+        })?;
+
+        let timestamp = Utc::now();
+
+        let (WorkItemResult::Completed {
+            node_id,
+            item_index,
+            attempt,
+            ..
+        }
+        | WorkItemResult::Failed {
+            node_id,
+            item_index,
+            attempt,
+            ..
+        }
+        | WorkItemResult::Waiting {
+            node_id,
+            item_index,
+            attempt,
+            ..
+        }) = &result;
+        let in_flight = state.execution(*node_id, *item_index).is_some_and(|exec| {
+            exec.attempt == *attempt
+                && matches!(
+                    exec.state,
+                    NodeExecutionState::Running | NodeExecutionState::Waiting
+                )
+        });
+        if result.run_id() != state.run_id || !in_flight {
+            return Ok(());
+        }
+
+        match result {
+            WorkItemResult::Completed {
+                run_id,
+                node_id,
+                item_index,
+                attempt,
+                output_key,
+            } => {
+                // Publish NodeCompleted event
+                let event = ExecutionEvent::NodeCompleted {
+                    run_id,
+                    node_id,
+                    item_index,
+                    attempt,
+                    output_key: output_key.clone(),
+                    timestamp,
+                };
+                self.event_store.publish(Envelope::new(event)).await?;
+                state.record_outcome(node_id, item_index, NodeOutcome::Completed(output_key));
+            }
+            WorkItemResult::Failed {
+                run_id,
+                node_id,
+                item_index,
+                attempt,
+                error,
+                error_kind,
+            } => {
+                // Retry with the inputs the node started with, if allowed
+                let retry = self
+                    .workflow
+                    .graph
+                    .get_node(node_id)
+                    .filter(|node| node.retry_policy.should_retry(attempt, error_kind))
+                    .and_then(|node| {
+                        let inputs = state
+                            .execution(node_id, item_index)?
+                            .input
+                            .clone()
+                            .and_then(|input| serde_json::from_value(input).ok())?;
+                        let jitter = retry_jitter(run_id, node_id, item_index, attempt);
+                        let retry_at = timestamp + node.retry_policy.delay(attempt, jitter);
+                        Some((inputs, retry_at, Some(node.required_capability())))
+                    });
+                if let Some((inputs, retry_at, capability)) = retry {
+                    let work_item = WorkItem {
+                        run_id,
+                        node_id,
+                        capability,
+                        item_index,
+                        inputs,
+                        attempt: attempt + 1,
+                        not_before: Some(retry_at),
+                        dry_run: state.dry_run.as_ref().map(DryRunConfig::without_fixtures),
+                    };
+                    return self.retry_node(work_item, error).await;
+                }
+
+                // Publish NodeFailed event
+                let event = ExecutionEvent::NodeFailed {
+                    run_id,
+                    node_id,
+                    item_index,
+                    attempt,
+                    error: error.clone(),
+                    timestamp,
+                };
+                self.event_store.publish(Envelope::new(event)).await?;
+                state.record_outcome(node_id, item_index, NodeOutcome::Failed(error));
+            }
+            WorkItemResult::Waiting {
+                run_id,
+                node_id,
+                item_index,
+                attempt,
+                child_run_id,
+            } => {
+                // The child run may have ended and resumed the node already
+                if state
+                    .execution(node_id, item_index)
+                    .is_some_and(|exec| exec.state == NodeExecutionState::Waiting)
+                {
+                    return Ok(());
+                }
+                let event = ExecutionEvent::SubWorkflowStarted {
+                    run_id,
+                    node_id,
+                    item_index,
+                    attempt,
+                    child_run_id,
+                    timestamp,
+                };
+                self.event_store.publish(Envelope::new(event)).await?;
+                state.mark_waiting(node_id, item_index, None);
+                return Ok(());
+            }
+        }
+
+        // Schedule any newly ready nodes, finalizing the run if none remain
+        self.schedule_ready_nodes().await?;
+
+        Ok(())
+    }
+
+    /// Resumes the sub-workflow node (or item) that started a child run, now
+    /// that the child run ended with `outcome`.
+    ///
+    /// The child run's output completes the node once it matches the node's
+    /// output ports. Otherwise the node fails, or another attempt, with a
+    /// child run of its own, is scheduled if the node's retry policy covers
+    /// the error. A node whose report of the child run's start was not
+    /// handled yet is resumed all the same. Child runs of earlier attempts,
+    /// of executions that finished, or of runs that ended are ignored.
+    ///
+    /// Returns true if a node was resumed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the run is not loaded or publishing fails.
+    pub async fn end_sub_workflow(
+        &mut self,
+        node_id: NodeId,
+        child_run_id: WorkflowRunId,
+        outcome: Result<JsonValue, NodeExecutionError>,
+    ) -> Result<bool, OrchestratorError> {
+        let state = self.state.as_ref().ok_or(OrchestratorError::RunNotFound {
+            run_id: WorkflowRunId::new(),
+        })?;
+        if state.execution_state.is_terminal() {
+            return Ok(false);
+        }
+        let run_id = state.run_id;
+        let Some((item_index, attempt, waiting)) = state
+            .node_states
+            .values()
+            .chain(state.item_states.values())
+            .find(|exec| {
+                exec.node_id == node_id
+                    && matches!(
+                        exec.state,
+                        NodeExecutionState::Running | NodeExecutionState::Waiting
+                    )
+                    && sub_workflow::child_run_id(run_id, node_id, exec.item_index, exec.attempt)
+                        == child_run_id
+            })
+            .map(|exec| {
+                (
+                    exec.item_index,
+                    exec.attempt,
+                    exec.state == NodeExecutionState::Waiting,
+                )
+            })
+        else {
+            return Ok(false);
+        };
+
+        let result = match self.store_sub_workflow_output(node_id, outcome).await {
+            Ok(output_key) => WorkItemResult::Completed {
+                run_id,
+                node_id,
+                item_index,
+                attempt,
+                output_key,
+            },
+            Err(e) => WorkItemResult::Failed {
+                run_id,
+                node_id,
+                item_index,
+                attempt,
+                error: e.to_string(),
+                error_kind: e.kind(),
+            },
+        };
+
+        if waiting {
+            let event = ExecutionEvent::SubWorkflowEnded {
+                run_id,
+                node_id,
+                item_index,
+                child_run_id,
+                timestamp: Utc::now(),
+            };
+            self.event_store.publish(Envelope::new(event)).await?;
+            if let Some(state) = self.state.as_mut() {
+                state.mark_resumed(node_id, item_index);
+            }
+        }
+        self.handle_result(result).await?;
+        Ok(true)
+    }
+
+    /// Checks a child run's output against the sub-workflow node's output
+    /// ports and stores it as the node's output.
+    async fn store_sub_workflow_output(
+        &self,
+        node_id: NodeId,
+        outcome: Result<JsonValue, NodeExecutionError>,
+    ) -> Result<String, WorkerError> {
+        let output = outcome?;
+        if let Some(node) = self.workflow.graph.get_node(node_id) {
+            for port in &node.outputs {
+                port.schema
+                    .validate(&output)
+                    .map_err(|mismatch| WorkerError::InvalidOutput {
+                        port_name: port.name.clone(),
+                        mismatch,
+                    })?;
+            }
+        }
+        let bytes =
+            serde_json::to_vec(&output).map_err(|e| WorkerError::DeserializationFailed {
+                message: e.to_string(),
+            })?;
+        Ok(self.object_store.put(&bytes).await?)
+    }
+
+    /// Schedules the next attempt of a failed node.
+    ///
+    /// The node stays running, so nothing new becomes ready.
+    async fn retry_node(
+        &mut self,
+        work_item: WorkItem,
+        error: String,
+    ) -> Result<(), OrchestratorError> {
+        let WorkItem {
+            run_id,
+            node_id,
+            item_index,
+            attempt,
+            not_before,
+            ..
+        } = work_item;
+        let retry_at = not_before.unwrap_or_else(Utc::now);
+        let event = ExecutionEvent::NodeRetryScheduled {
+            run_id,
+            node_id,
+            item_index,
+            attempt,
+            error: error.clone(),
+            retry_at,
+            timestamp: Utc::now(),
+        };
+        self.event_store.publish(Envelope::new(event)).await?;
+
+        if let Some(state) = self.state.as_mut() {
+            state.record_outcome(
+                node_id,
+                item_index,
+                NodeOutcome::Retrying {
+                    attempt,
+                    error,
+                    retry_at,
+                },
+            );
+        }
+
+        self.event_store
+            .publish_work_item(Envelope::new(work_item))
+            .await?;
+        Ok(())
+    }
+
+    /// Records a decision on a pending approval and resumes the run.
+    ///
+    /// The approval node passes its input through on the decision's port, so
+    /// only the nodes on that path run; the others are skipped. `decided_by`
+    /// names the user who decided, or is None for an expired approval.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrchestratorError::RunAlreadyTerminal`] if the run has
+    /// ended (or just passed its deadline), and
+    /// [`OrchestratorError::ApprovalNotPending`] if the node (or item) is not
+    /// waiting for a decision.
+    pub async fn resolve_approval(
+        &mut self,
+        node_id: NodeId,
+        item_index: Option<usize>,
+        decision: ApprovalDecision,
+        decided_by: Option<String>,
+    ) -> Result<(), OrchestratorError> {
+        let state = self.state.as_ref().ok_or(OrchestratorError::RunNotFound {
+            run_id: WorkflowRunId::new(),
+        })?;
+        let run_id = state.run_id;
+        if state.execution_state.is_terminal() || self.enforce_deadline().await? {
+            return Err(OrchestratorError::RunAlreadyTerminal { run_id });
+        }
+        let state = self.state.as_mut().ok_or(OrchestratorError::RunNotFound {
+            run_id: WorkflowRunId::new(),
+        })?;
+
+        // The input passes through unchanged
+        let output_key = waiting_input_key(state, node_id, item_index).ok_or(
+            OrchestratorError::ApprovalNotPending {
+                run_id,
+                node_id,
+                item_index,
+            },
+        )?;
+
+        let timestamp = Utc::now();
+        let ports = vec![decision.port().to_string()];
+        let events = [
+            ExecutionEvent::ApprovalResolved {
+                run_id,
+                node_id,
+                item_index,
+                decision,
+                decided_by,
+                timestamp,
+            },
+            ExecutionEvent::BranchTaken {
+                run_id,
+                node_id,
+                item_index,
+                ports: ports.clone(),
+                timestamp,
+            },
+            ExecutionEvent::NodeCompleted {
+                run_id,
+                node_id,
+                item_index,
+                attempt: 1,
+                output_key: output_key.clone(),
+                timestamp,
+            },
+        ];
+        for event in events {
+            self.event_store.publish(Envelope::new(event)).await?;
+        }
+
+        state.mark_resumed(node_id, item_index);
+        state.mark_branch_taken(node_id, item_index, ports);
+        state.record_outcome(node_id, item_index, NodeOutcome::Completed(output_key));
+
+        // Schedule the chosen path, finalizing the run if nothing remains
+        self.schedule_ready_nodes().await
+    }
+
+    /// Resolves the approvals that expired by `now` with their node's
+    /// expiry decision.
+    ///
+    /// The orchestrator only acts on events, so whoever drives it calls this
+    /// once an approval's expiry passes (see [`RunState::waiting_nodes`]).
+    ///
+    /// Returns the number of approvals resolved. A run past its deadline
+    /// fails instead, resolving none.
+    pub async fn expire_approvals(
+        &mut self,
+        now: DateTime<Utc>,
+    ) -> Result<usize, OrchestratorError> {
+        let state = self.state.as_ref().ok_or(OrchestratorError::RunNotFound {
+            run_id: WorkflowRunId::new(),
+        })?;
+        if !state.execution_state.is_active() || self.enforce_deadline().await? {
+            return Ok(0);
+        }
+        let state = self.state.as_ref().ok_or(OrchestratorError::RunNotFound {
+            run_id: WorkflowRunId::new(),
+        })?;
+        let expired: Vec<(NodeId, Option<usize>, ApprovalDecision)> = state
+            .waiting_nodes()
+            .filter(|exec| exec.expires_at.is_some_and(|expires_at| now >= expires_at))
+            .filter_map(
+                |exec| match &self.workflow.graph.get_node(exec.node_id)?.config {
+                    NodeConfig::ControlFlow(ControlFlowNodeConfig::Approval {
+                        on_expiry, ..
+                    }) => Some((exec.node_id, exec.item_index, *on_expiry)),
+                    _ => None,
+                },
+            )
+            .collect();
+
+        let mut resolved = 0;
+        for (node_id, item_index, decision) in expired {
+            self.resolve_approval(node_id, item_index, decision, None)
+                .await?;
+            resolved += 1;
+        }
+        Ok(resolved)
+    }
+
+    /// Ends the delays that are over by `now`, passing each delay node's
+    /// input on to its path.
+    ///
+    /// The orchestrator only acts on events, so whoever drives it calls this
+    /// once a delay's end passes (see [`RunState::waiting_nodes`]).
+    ///
+    /// Returns the number of delays ended. A run past its deadline fails
+    /// instead, ending none.
+    pub async fn end_delays(&mut self, now: DateTime<Utc>) -> Result<usize, OrchestratorError> {
+        let state = self.state.as_ref().ok_or(OrchestratorError::RunNotFound {
+            run_id: WorkflowRunId::new(),
+        })?;
+        if !state.execution_state.is_active() || self.enforce_deadline().await? {
+            return Ok(0);
+        }
+        let state = self.state.as_ref().ok_or(OrchestratorError::RunNotFound {
+            run_id: WorkflowRunId::new(),
+        })?;
+        let over: Vec<(NodeId, Option<usize>)> = state
+            .waiting_nodes()
+            .filter(|exec| exec.expires_at.is_some_and(|until| now >= until))
+            .filter(|exec| {
+                self.workflow
+                    .graph
+                    .get_node(exec.node_id)
+                    .is_some_and(|node| {
+                        matches!(
+                            node.config,
+                            NodeConfig::ControlFlow(ControlFlowNodeConfig::Delay { .. })
+                        )
+                    })
+            })
+            .map(|exec| (exec.node_id, exec.item_index))
+            .collect();
+        if over.is_empty() {
+            return Ok(0);
+        }
+
+        for &(node_id, item_index) in &over {
+            self.end_delay(node_id, item_index).await?;
+        }
+
+        // Schedule the paths that waited, finalizing the run if nothing remains
+        self.schedule_ready_nodes().await?;
+        Ok(over.len())
+    }
+
+    /// Cancels the run.
+    ///
+    /// Publishes `RunCancelled` and skips the nodes still running or waiting
+    /// for an approval, so no further work is scheduled and late results are
+    /// ignored. Workers learn of the cancellation through the event store
+    /// (see [`crate::worker::RunCancellations`]).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OrchestratorError::RunAlreadyTerminal`] if the run has
+    /// already ended.
+    pub async fn cancel(&mut self, reason: impl Into<String>) -> Result<(), OrchestratorError> {
+        let state = self.state.as_mut().ok_or(OrchestratorError::RunNotFound {
+            run_id: WorkflowRunId::new(),
+        })?;
+
+        let run_id = state.run_id;
+        if state.execution_state.is_terminal() {
+            return Err(OrchestratorError::RunAlreadyTerminal { run_id });
+        }
+
+        let timestamp = Utc::now();
+        let running: Vec<(NodeId, Option<usize>)> = state
+            .node_states
+            .values()
+            .chain(state.item_states.values())
+            .filter(|exec| {
+                matches!(
+                    exec.state,
+                    NodeExecutionState::Running | NodeExecutionState::Waiting
+                )
+            })
+            .map(|exec| (exec.node_id, exec.item_index))
+            .collect();
+
+        for (node_id, item_index) in running {
+            let event = ExecutionEvent::NodeSkipped {
+                run_id,
+                node_id,
+                item_index,
+                reason: "run cancelled".to_string(),
+                timestamp,
+            };
+            self.event_store.publish(Envelope::new(event)).await?;
+            state.record_outcome(node_id, item_index, NodeOutcome::Skipped);
+        }
+
+        let event = ExecutionEvent::RunCancelled {
+            run_id,
+            reason: reason.into(),
+            timestamp,
+        };
+        self.event_store.publish(Envelope::new(event)).await?;
+        state.cancel(timestamp);
+
+        Ok(())
+    }
+
+    /// Fails the run if it is still going past its deadline.
+    ///
+    /// Running nodes and items, including those waiting for an approval,
+    /// are marked failed, then the run fails. The orchestrator only acts on
+    /// events, so whoever drives it calls this once [`RunState::deadline`]
+    /// passes; results are checked against the deadline as they arrive.
+    ///
+    /// Returns true if the run was failed.
+    pub async fn enforce_deadline(&mut self) -> Result<bool, OrchestratorError> {
+        let state = self.state.as_mut().ok_or(OrchestratorError::RunNotFound {
+            run_id: WorkflowRunId::new(),
+        })?;
+
+        let timestamp = Utc::now();
+        if !state.is_past_deadline(timestamp) {
+            return Ok(false);
+        }
+
+        let run_id = state.run_id;
+        let error = match (state.started_at, state.deadline) {
+            (Some(started_at), Some(deadline)) => format!(
+                "run exceeded its maximum duration of {} ms",
+                (deadline - started_at).num_milliseconds()
+            ),
+            _ => "run exceeded its deadline".to_string(),
+        };
+
+        let running: Vec<(NodeId, Option<usize>, u32)> = state
+            .node_states
+            .values()
+            .chain(state.item_states.values())
+            .filter(|exec| {
+                matches!(
+                    exec.state,
+                    NodeExecutionState::Running | NodeExecutionState::Waiting
+                )
+            })
+            .map(|exec| (exec.node_id, exec.item_index, exec.attempt))
+            .collect();
+
+        for (node_id, item_index, attempt) in running {
+            let node_error = "run deadline exceeded".to_string();
+            let event = ExecutionEvent::NodeFailed {
+                run_id,
+                node_id,
+                item_index,
+                attempt,
+                error: node_error.clone(),
+                timestamp,
+            };
+            self.event_store.publish(Envelope::new(event)).await?;
+            state.record_outcome(node_id, item_index, NodeOutcome::Failed(node_error));
+        }
+
+        let event = ExecutionEvent::RunFailed {
+            run_id,
+            error: error.clone(),
+            timestamp,
+        };
+        self.event_store.publish(Envelope::new(event)).await?;
+        state.fail(error, timestamp);
+
+        Ok(true)
+    }
+
+    /// Finalizes the run (marks as completed or failed).
+    async fn finalize_run(&mut self) -> Result<(), OrchestratorError> {
+        let Some(state) = self.state.as_ref() else {
+            return Err(OrchestratorError::RunNotFound {
+                run_id: WorkflowRunId::new(),
+            });
+        };
+
+        let run_id = state.run_id;
+        let result = if state.has_failures() {
+            Err("workflow failed due to node failures".to_string())
+        } else {
+            self.collect_output()
+                .await
+                .map_err(|e| format!("failed to collect run output: {e}"))
+        };
+
+        let state = self.state.as_mut().ok_or(OrchestratorError::RunNotFound {
+            run_id: WorkflowRunId::new(),
+        })?;
+        let timestamp = Utc::now();
+
+        match result {
+            Ok(output) => {
+                let event = ExecutionEvent::RunCompleted {
+                    run_id,
+                    output: output.clone(),
+                    timestamp,
+                };
+                self.event_store.publish(Envelope::new(event)).await?;
+                state.complete(output, timestamp);
+            }
+            Err(error) => {
+                let event = ExecutionEvent::RunFailed {
+                    run_id,
+                    error: error.clone(),
+                    timestamp,
+                };
+                self.event_store.publish(Envelope::new(event)).await?;
+                state.fail(error, timestamp);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Assembles the output of a completed run.
+    ///
+    /// If an HttpResponse output node completed, its payload is the run's
+    /// output, so webhook callers get exactly what the workflow responds
+    /// with. Otherwise the output is an object holding each completed
+    /// terminal node's output under the node's name (or its ID, if several
+    /// terminal nodes share a name). Returns None if no node produced
+    /// output.
+    async fn collect_output(&self) -> Result<Option<JsonValue>, ExecutionError> {
+        let Some(state) = self.state.as_ref() else {
+            return Ok(None);
+        };
+        let output_key = |node_id: NodeId| {
+            state
+                .node_states
+                .get(&node_id)
+                .filter(|exec| exec.state == NodeExecutionState::Completed)
+                .and_then(|exec| exec.output_key.as_deref())
+        };
+
+        let graph = &self.workflow.graph;
+        let response = graph.nodes().find_map(|node| match node.config {
+            NodeConfig::Output(OutputNodeConfig::HttpResponse { .. }) => {
+                output_key(node.id).map(|key| (node.id, key))
+            }
+            _ => None,
+        });
+        if let Some((node_id, key)) = response {
+            return self.read_json(node_id, key).await.map(Some);
+        }
+
+        let terminal = graph.terminal_nodes();
+        let mut output = serde_json::Map::new();
+        for node in &terminal {
+            let Some(key) = output_key(node.id) else {
+                continue;
+            };
+            let shared_name = terminal
+                .iter()
+                .filter(|other| other.name == node.name)
+                .count()
+                > 1;
+            let name = if shared_name {
+                node.id.to_string()
+            } else {
+                node.name.clone()
+            };
+            output.insert(name, self.read_json(node.id, key).await?);
+        }
+
+        Ok((!output.is_empty()).then_some(JsonValue::Object(output)))
+    }
+
+    /// Returns the current run state.
+    #[must_use]
+    pub fn state(&self) -> Option<&RunState> {
+        self.state.as_ref()
+    }
+
+    /// Returns the run ID.
+    #[must_use]
+    pub fn run_id(&self) -> Option<WorkflowRunId> {
+        self.state.as_ref().map(|s| s.run_id)
+    }
+
+    /// Returns true if the run is complete.
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        self.state.as_ref().is_some_and(|s| s.is_complete())
+    }
+}
+
+/// Returns the output key of a waiting node's input, which approval and
+/// delay nodes pass through unchanged.
+fn waiting_input_key(
+    state: &RunState,
+    node_id: NodeId,
+    item_index: Option<usize>,
+) -> Option<String> {
+    state
+        .execution(node_id, item_index)
+        .filter(|exec| exec.state == NodeExecutionState::Waiting)
+        .and_then(|exec| exec.input.clone())
+        .and_then(|input| serde_json::from_value::<HashMap<String, String>>(input).ok())
+        .and_then(|mut inputs| inputs.remove("input"))
+}
+
+/// Picks the jitter of a retry delay, in `[0, 1)`.
+///
+/// Derived from the failed attempt instead of drawn at random, so the
+/// delay is reproducible; replay uses the recorded retry time anyway. The
+/// attempt is hashed with 64-bit FNV-1a, which, unlike the standard
+/// library's hashers, gives the same value on every Rust version.
+fn retry_jitter(
+    run_id: WorkflowRunId,
+    node_id: NodeId,
+    item_index: Option<usize>,
+    attempt: u32,
+) -> f64 {
+    const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    let key = match item_index {
+        Some(index) => format!("{run_id}.{node_id}.{index}.{attempt}"),
+        None => format!("{run_id}.{node_id}.{attempt}"),
+    };
+    let hash = key.bytes().fold(FNV_OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+    });
+    (hash >> 11) as f64 / (1_u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::edge::Edge;
+    use crate::node::{
+        AiLayerNodeConfig, IntegrationNodeConfig, Node, NodeConfig, OutputNodeConfig,
+        SubWorkflowNodeConfig, TransformNodeConfig, TriggerNodeConfig,
+    };
+    use crate::retry::RetryPolicy;
+    use crate::worker::ObjectStoreError;
+    use std::sync::Mutex;
+
+    /// In-memory event store for testing.
+    struct InMemoryEventStore {
+        events: Arc<Mutex<Vec<Envelope<ExecutionEvent>>>>,
+        work_items: Arc<Mutex<Vec<Envelope<WorkItem>>>>,
+        unserved: HashSet<Capability>,
+    }
+
+    impl InMemoryEventStore {
+        fn new() -> Self {
+            Self {
+                events: Arc::new(Mutex::new(Vec::new())),
+                work_items: Arc::new(Mutex::new(Vec::new())),
+                unserved: HashSet::new(),
+            }
+        }
+
+        /// Makes the store report that no worker serves `capability`.
+        fn without_worker_for(mut self, capability: Capability) -> Self {
+            self.unserved.insert(capability);
+            self
+        }
+
+        fn events(&self) -> Vec<ExecutionEvent> {
+            self.events
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|e| e.payload.clone())
+                .collect()
+        }
+
+        fn work_items(&self) -> Vec<WorkItem> {
+            self.work_items
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|w| w.payload.clone())
+                .collect()
+        }
+    }
+
+    #[async_trait]
+    impl EventStore for InMemoryEventStore {
+        async fn publish(&self, event: Envelope<ExecutionEvent>) -> Result<(), EventStoreError> {
+            self.events.lock().unwrap().push(event);
+            // Let other tasks run in between, like a network round trip would
+            tokio::task::yield_now().await;
+            Ok(())
+        }
+
+        async fn load_events(
+            &self,
+            run_id: WorkflowRunId,
+        ) -> Result<Vec<ExecutionEvent>, EventStoreError> {
+            Ok(self
+                .events
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|e| e.payload.run_id() == run_id)
+                .map(|e| e.payload.clone())
+                .collect())
+        }
+
+        async fn publish_work_item(&self, item: Envelope<WorkItem>) -> Result<(), EventStoreError> {
+            self.work_items.lock().unwrap().push(item);
+            Ok(())
+        }
+
+        async fn has_worker_for(&self, capability: &Capability) -> Result<bool, EventStoreError> {
+            Ok(!self.unserved.contains(capability))
+        }
+    }
+
+    /// In-memory object store for testing.
+    struct InMemoryObjectStore {
+        data: Mutex<HashMap<String, Vec<u8>>>,
+    }
+
+    impl InMemoryObjectStore {
+        fn new() -> Self {
+            Self {
+                data: Mutex::new(HashMap::new()),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl ObjectStore for InMemoryObjectStore {
+        async fn put(&self, data: &[u8]) -> Result<String, ObjectStoreError> {
+            let mut store = self.data.lock().unwrap();
+            let key = format!("obj_{}", store.len() + 1);
+            store.insert(key.clone(), data.to_vec());
+            Ok(key)
+        }
+
+        async fn get(&self, key: &str) -> Result<Vec<u8>, ObjectStoreError> {
+            self.data
+                .lock()
+                .unwrap()
+                .get(key)
+                .cloned()
+                .ok_or_else(|| ObjectStoreError::NotFound {
+                    key: key.to_string(),
+                })
+        }
+
+        async fn delete(&self, key: &str) -> Result<(), ObjectStoreError> {
+            self.data.lock().unwrap().remove(key);
+            Ok(())
+        }
+    }
+
+    fn create_trigger_node(name: &str) -> Node {
+        Node::new(
+            name,
+            NodeConfig::Trigger(TriggerNodeConfig::Schedule {
+                cron: "0 7 * * *".to_string(),
+                timezone: None,
+            }),
+        )
+    }
+
+    fn create_ai_node(name: &str) -> Node {
+        Node::new(
+            name,
+            NodeConfig::AiLayer(AiLayerNodeConfig::Generate {
+                instructions: "Test".to_string(),
+            }),
+        )
+    }
+
+    fn create_transform_node(name: &str) -> Node {
+        Node::new(
+            name,
+            NodeConfig::Transform(TransformNodeConfig {
+                expression: "input".to_string(),
+            }),
+        )
+    }
+
+    fn create_branch_node(conditions: &[(&str, &str)]) -> Node {
+        Node::new(
+            "Branch",
+            NodeConfig::ControlFlow(ControlFlowNodeConfig::Branch {
+                conditions: conditions
+                    .iter()
+                    .map(|(port, condition)| BranchCondition {
+                        port: port.to_string(),
+                        condition: condition.to_string(),
+                    })
+                    .collect(),
+            }),
+        )
+    }
+
+    /// Trigger -> Branch, with `high` and `low` ports on the branch.
+    fn create_branch_workflow(low_condition: &str) -> (Workflow, NodeId, NodeId) {
+        let mut workflow = Workflow::new("Branch Workflow");
+        let trigger = create_trigger_node("Trigger");
+        let branch = create_branch_node(&[("high", "confidence > 0.8"), ("low", low_condition)]);
+        let trigger_id = workflow.graph.add_node(trigger);
+        let branch_id = workflow.graph.add_node(branch);
+        workflow
+            .graph
+            .add_edge(trigger_id, branch_id, Edge::new("output", "input"))
+            .unwrap();
+        (workflow, trigger_id, branch_id)
+    }
+
+    fn add_transform_after(
+        workflow: &mut Workflow,
+        source: NodeId,
+        port: &str,
+        name: &str,
+    ) -> NodeId {
+        let id = workflow.graph.add_node(create_transform_node(name));
+        workflow
+            .graph
+            .add_edge(source, id, Edge::new(port, "input"))
+            .unwrap();
+        id
+    }
+
+    /// Starts the run and completes the trigger with the given output.
+    async fn complete_trigger(
+        orchestrator: &mut Orchestrator<InMemoryEventStore, InMemoryObjectStore>,
+        trigger_id: NodeId,
+        output: JsonValue,
+    ) -> WorkflowRunId {
+        orchestrator.initialize(None).await.unwrap();
+        orchestrator.start().await.unwrap();
+        let run_id = orchestrator.run_id().unwrap();
+        let output_key = orchestrator
+            .object_store
+            .put(&serde_json::to_vec(&output).unwrap())
+            .await
+            .unwrap();
+        orchestrator
+            .handle_result(WorkItemResult::Completed {
+                run_id,
+                node_id: trigger_id,
+                item_index: None,
+                attempt: 1,
+                output_key,
+            })
+            .await
+            .unwrap();
+        run_id
+    }
+
+    /// Adds a FanOut node after `source` and a FanIn node collecting its items.
+    fn add_fan_out(workflow: &mut Workflow, source: NodeId) -> (NodeId, NodeId) {
+        let fan_out_id = workflow.graph.add_node(Node::new(
+            "FanOut",
+            NodeConfig::ControlFlow(ControlFlowNodeConfig::FanOut),
+        ));
+        let fan_in_id = workflow.graph.add_node(Node::new(
+            "FanIn",
+            NodeConfig::ControlFlow(ControlFlowNodeConfig::FanIn {
+                fan_out_node: fan_out_id,
+            }),
+        ));
+        workflow
+            .graph
+            .add_edge(source, fan_out_id, Edge::new("output", "items"))
+            .unwrap();
+        (fan_out_id, fan_in_id)
+    }
+
+    /// Trigger -> FanOut -> Per Item -> FanIn -> After.
+    fn create_fan_out_workflow() -> (Workflow, NodeId, NodeId, NodeId, NodeId) {
+        let mut workflow = Workflow::new("Fan-out Workflow");
+        let trigger_id = workflow.graph.add_node(create_trigger_node("Trigger"));
+        let (fan_out_id, fan_in_id) = add_fan_out(&mut workflow, trigger_id);
+        let per_item_id = workflow.graph.add_node(create_transform_node("Per Item"));
+        let after_id = workflow.graph.add_node(create_transform_node("After"));
+        workflow
+            .graph
+            .add_edge(fan_out_id, per_item_id, Edge::new("item", "input"))
+            .unwrap();
+        workflow
+            .graph
+            .add_edge(per_item_id, fan_in_id, Edge::new("output", "item"))
+            .unwrap();
+        workflow
+            .graph
+            .add_edge(fan_in_id, after_id, Edge::new("items", "input"))
+            .unwrap();
+        workflow.validate().unwrap();
+        (workflow, trigger_id, per_item_id, fan_in_id, after_id)
+    }
+
+    /// Completes one item of a node with the given output.
+    async fn complete_item(
+        orchestrator: &mut Orchestrator<InMemoryEventStore, InMemoryObjectStore>,
+        node_id: NodeId,
+        index: usize,
+        output: JsonValue,
+    ) {
+        let run_id = orchestrator.run_id().unwrap();
+        let output_key = orchestrator
+            .object_store
+            .put(&serde_json::to_vec(&output).unwrap())
+            .await
+            .unwrap();
+        orchestrator
+            .handle_result(WorkItemResult::Completed {
+                run_id,
+                node_id,
+                item_index: Some(index),
+                attempt: 1,
+                output_key,
+            })
+            .await
+            .unwrap();
+    }
+
+    /// Stores a node output and returns its key.
+    async fn store_output(
+        orchestrator: &Orchestrator<InMemoryEventStore, InMemoryObjectStore>,
+        output: JsonValue,
+    ) -> String {
+        orchestrator
+            .object_store
+            .put(&serde_json::to_vec(&output).unwrap())
+            .await
+            .unwrap()
+    }
+
+    async fn read_output(
+        orchestrator: &Orchestrator<InMemoryEventStore, InMemoryObjectStore>,
+        key: &str,
+    ) -> JsonValue {
+        let bytes = orchestrator.object_store.get(key).await.unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    fn skipped_nodes(events: &[ExecutionEvent]) -> Vec<NodeId> {
+        events
+            .iter()
+            .filter_map(|e| match e {
+                ExecutionEvent::NodeSkipped { node_id, .. } => Some(*node_id),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn create_simple_workflow() -> (Workflow, NodeId, NodeId) {
+        let mut workflow = Workflow::new("Test Workflow");
+
+        // A -> B
+        let node_a = create_trigger_node("A");
+        let node_b = create_ai_node("B");
+        let id_a = node_a.id;
+        let id_b = node_b.id;
+
+        workflow.graph.add_node(node_a);
+        workflow.graph.add_node(node_b);
+        workflow
+            .graph
+            .add_edge(id_a, id_b, Edge::new("output", "context"))
+            .unwrap();
+
+        (workflow, id_a, id_b)
+    }
+
+    #[tokio::test]
+    async fn orchestrator_starts_new_run() {
+        let (workflow, id_a, _id_b) = create_simple_workflow();
+        let event_store = InMemoryEventStore::new();
+        let mut orchestrator = Orchestrator::new(workflow, event_store, InMemoryObjectStore::new());
+
+        orchestrator.initialize(None).await.unwrap();
+        orchestrator.start().await.unwrap();
+
+        let events = orchestrator.event_store.events();
+        assert_eq!(events.len(), 3); // RunQueued, RunStarted, NodeStarted
+
+        match &events[0] {
+            ExecutionEvent::RunQueued { .. } => {}
+            _ => panic!("expected RunQueued"),
+        }
+        match &events[1] {
+            ExecutionEvent::RunStarted { .. } => {}
+            _ => panic!("expected RunStarted"),
+        }
+        match &events[2] {
+            ExecutionEvent::NodeStarted { node_id, .. } => {
+                assert_eq!(*node_id, id_a);
+            }
+            _ => panic!("expected NodeStarted"),
+        }
+
+        // Should have published work item for node A
+        let work_items = orchestrator.event_store.work_items();
+        assert_eq!(work_items.len(), 1);
+        assert_eq!(work_items[0].node_id, id_a);
+    }
+
+    #[tokio::test]
+    async fn orchestrator_queues_run_with_given_id() {
+        let (workflow, _id_a, _id_b) = create_simple_workflow();
+        let event_store = InMemoryEventStore::new();
+        let mut orchestrator = Orchestrator::new(workflow, event_store, InMemoryObjectStore::new());
+
+        let run_id = WorkflowRunId::new();
+        let trigger_id = TriggerId::new();
+        orchestrator
+            .queue_run(run_id, Some(trigger_id), Some(serde_json::json!({"k": 1})))
+            .await
+            .unwrap();
+
+        assert_eq!(orchestrator.run_id(), Some(run_id));
+        match &orchestrator.event_store.events()[0] {
+            ExecutionEvent::RunQueued {
+                run_id: queued,
+                trigger_id: queued_trigger,
+                input,
+                ..
+            } => {
+                assert_eq!(*queued, run_id);
+                assert_eq!(*queued_trigger, Some(trigger_id));
+                assert_eq!(input, &Some(serde_json::json!({"k": 1})));
+            }
+            other => panic!("expected RunQueued, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn queued_run_is_pinned_to_the_workflow_version() {
+        let (workflow, _id_a, _id_b) = create_simple_workflow();
+        let graph = workflow.graph.clone();
+        let mut orchestrator = Orchestrator::new(
+            workflow.with_version(3),
+            InMemoryEventStore::new(),
+            InMemoryObjectStore::new(),
+        );
+        orchestrator.initialize(None).await.unwrap();
+
+        assert_eq!(orchestrator.state().unwrap().workflow_version, Some(3));
+        let events = orchestrator.event_store.events();
+        assert!(matches!(
+            events[0],
+            ExecutionEvent::RunQueued {
+                workflow_version: Some(3),
+                ..
+            }
+        ));
+        let replayed = RunStateBuilder::new(graph)
+            .build_from_events(events)
+            .unwrap();
+        assert_eq!(replayed.workflow_version, Some(3));
+    }
+
+    #[tokio::test]
+    async fn orchestrator_handles_completion() {
+        let (workflow, id_a, id_b) = create_simple_workflow();
+        let event_store = InMemoryEventStore::new();
+        let mut orchestrator = Orchestrator::new(workflow, event_store, InMemoryObjectStore::new());
+
+        orchestrator.initialize(None).await.unwrap();
+        orchestrator.start().await.unwrap();
+
+        let run_id = orchestrator.run_id().unwrap();
+
+        // Complete node A
+        orchestrator
+            .handle_result(WorkItemResult::Completed {
+                run_id,
+                node_id: id_a,
+                item_index: None,
+                attempt: 1,
+                output_key: "output_a".to_string(),
+            })
+            .await
+            .unwrap();
+
+        // Node B should now be scheduled
+        let work_items = orchestrator.event_store.work_items();
+        assert_eq!(work_items.len(), 2); // A and B
+        assert_eq!(work_items[1].node_id, id_b);
+
+        // Complete node B
+        let output_key = store_output(&orchestrator, serde_json::json!("summary")).await;
+        orchestrator
+            .handle_result(WorkItemResult::Completed {
+                run_id,
+                node_id: id_b,
+                item_index: None,
+                attempt: 1,
+                output_key,
+            })
+            .await
+            .unwrap();
+
+        // Run should be complete
+        assert!(orchestrator.is_complete());
+        let events = orchestrator.event_store.events();
+        let last_event = events.last().unwrap();
+        match last_event {
+            ExecutionEvent::RunCompleted { .. } => {}
+            _ => panic!("expected RunCompleted"),
+        }
+    }
+
+    #[tokio::test]
+    async fn orchestrator_handles_failure() {
+        let (workflow, id_a, _id_b) = create_simple_workflow();
+        let event_store = InMemoryEventStore::new();
+        let mut orchestrator = Orchestrator::new(workflow, event_store, InMemoryObjectStore::new());
+
+        orchestrator.initialize(None).await.unwrap();
+        orchestrator.start().await.unwrap();
+
+        let run_id = orchestrator.run_id().unwrap();
+
+        // Fail node A
+        orchestrator
+            .handle_result(WorkItemResult::Failed {
+                run_id,
+                node_id: id_a,
+                item_index: None,
+                attempt: 1,
+                error: "test error".to_string(),
+                error_kind: NodeErrorKind::ExecutionFailed,
+            })
+            .await
+            .unwrap();
+
+        // Run should be complete (with failure)
+        assert!(orchestrator.is_complete());
+        let state = orchestrator.state().unwrap();
+        assert!(state.has_failures());
+        assert_eq!(state.execution_state, ExecutionState::Failed);
+    }
+
+    #[tokio::test]
+    async fn work_no_worker_serves_fails_instead_of_waiting() {
+        let mut workflow = Workflow::new("Local Model Workflow");
+        let trigger_id = workflow.graph.add_node(create_trigger_node("Trigger"));
+        let ollama = Capability::new("ollama").unwrap();
+        let ai_id = workflow
+            .graph
+            .add_node(create_ai_node("Summarize").with_capability(ollama.clone()));
+        workflow
+            .graph
+            .add_edge(trigger_id, ai_id, Edge::new("output", "context"))
+            .unwrap();
+
+        let event_store = InMemoryEventStore::new().without_worker_for(ollama.clone());
+        let mut orchestrator = Orchestrator::new(workflow, event_store, InMemoryObjectStore::new());
+        orchestrator.initialize(None).await.unwrap();
+        orchestrator.start().await.unwrap();
+
+        // Work items are routed by the capability their node requires
+        let work_items = orchestrator.event_store.work_items();
+        assert_eq!(
+            work_items[0].capability,
+            Some(Capability::for_category(&NodeCategory::Trigger))
+        );
+
+        complete_node(&mut orchestrator, trigger_id, serde_json::json!({})).await;
+
+        // The AI node never reaches the queue; the run fails with the reason
+        assert_eq!(orchestrator.event_store.work_items().len(), 1);
+        let state = orchestrator.state().unwrap();
+        assert_eq!(state.execution_state, ExecutionState::Failed);
+        let error = state.node_states[&ai_id].error.clone().unwrap();
+        assert!(error.contains("no worker serves the capability 'ollama'"));
+    }
+
+    /// Completes a node with the given output.
+    async fn complete_node(
+        orchestrator: &mut Orchestrator<InMemoryEventStore, InMemoryObjectStore>,
+        node_id: NodeId,
+        output: JsonValue,
+    ) {
+        let run_id = orchestrator.run_id().unwrap();
+        let output_key = store_output(orchestrator, output).await;
+        orchestrator
+            .handle_result(WorkItemResult::Completed {
+                run_id,
+                node_id,
+                item_index: None,
+                attempt: 1,
+                output_key,
+            })
+            .await
+            .unwrap();
+    }
+
+    fn run_output(events: &[ExecutionEvent]) -> Option<JsonValue> {
+        events.iter().find_map(|e| match e {
+            ExecutionEvent::RunCompleted { output, .. } => output.clone(),
+            _ => None,
+        })
+    }
+
+    #[tokio::test]
+    async fn run_output_is_keyed_by_terminal_node_name() {
+        let (mut workflow, trigger_id, branch_id) = create_branch_workflow("confidence <= 0.8");
+        let high_id = add_transform_after(&mut workflow, branch_id, "high", "High");
+        let low_id = add_transform_after(&mut workflow, branch_id, "low", "Low");
+        let first_id = add_transform_after(&mut workflow, high_id, "output", "Report");
+        let second_id = add_transform_after(&mut workflow, high_id, "output", "Report");
+        let graph = workflow.graph.clone();
+
+        let mut orchestrator = Orchestrator::new(
+            workflow,
+            InMemoryEventStore::new(),
+            InMemoryObjectStore::new(),
+        );
+        complete_trigger(
+            &mut orchestrator,
+            trigger_id,
+            serde_json::json!({"confidence": 0.9}),
+        )
+        .await;
+        complete_node(&mut orchestrator, high_id, serde_json::json!("high")).await;
+        complete_node(&mut orchestrator, first_id, serde_json::json!(1)).await;
+        complete_node(&mut orchestrator, second_id, serde_json::json!(2)).await;
+
+        // Skipped terminal nodes are left out; shared names fall back to IDs
+        let expected = serde_json::json!({
+            first_id.to_string(): 1,
+            second_id.to_string(): 2,
+        });
+        let events = orchestrator.event_store.events();
+        assert_eq!(run_output(&events), Some(expected.clone()));
+        assert!(skipped_nodes(&events).contains(&low_id));
+        let state = orchestrator.state().unwrap();
+        assert_eq!(state.execution_state, ExecutionState::Completed);
+        assert_eq!(state.output, Some(expected.clone()));
+
+        let replayed = RunStateBuilder::new(graph)
+            .build_from_events(events)
+            .unwrap();
+        assert_eq!(replayed.output, Some(expected));
+    }
+
+    #[tokio::test]
+    async fn http_response_payload_is_the_run_output() {
+        let (mut workflow, id_a, id_b) = create_simple_workflow();
+        let response_id = workflow.graph.add_node(Node::new(
+            "Respond",
+            NodeConfig::Output(OutputNodeConfig::HttpResponse { status_code: 200 }),
+        ));
+        workflow
+            .graph
+            .add_edge(id_b, response_id, Edge::new("generated", "input"))
+            .unwrap();
+        let log_id = add_transform_after(&mut workflow, id_b, "generated", "Log");
+
+        let mut orchestrator = Orchestrator::new(
+            workflow,
+            InMemoryEventStore::new(),
+            InMemoryObjectStore::new(),
+        );
+        complete_trigger(&mut orchestrator, id_a, serde_json::json!({})).await;
+        complete_node(&mut orchestrator, id_b, serde_json::json!("summary")).await;
+        complete_node(&mut orchestrator, log_id, serde_json::json!("logged")).await;
+        complete_node(
+            &mut orchestrator,
+            response_id,
+            serde_json::json!({"message": "done"}),
+        )
+        .await;
+
+        assert_eq!(
+            orchestrator.state().unwrap().output,
+            Some(serde_json::json!({"message": "done"}))
+        );
+    }
+
+    #[tokio::test]
+    async fn unreadable_output_fails_the_run() {
+        let (workflow, id_a, id_b) = create_simple_workflow();
+        let mut orchestrator = Orchestrator::new(
+            workflow,
+            InMemoryEventStore::new(),
+            InMemoryObjectStore::new(),
+        );
+        let run_id = complete_trigger(&mut orchestrator, id_a, serde_json::json!({})).await;
+        orchestrator
+            .handle_result(WorkItemResult::Completed {
+                run_id,
+                node_id: id_b,
+                item_index: None,
+                attempt: 1,
+                output_key: "missing".to_string(),
+            })
+            .await
+            .unwrap();
+
+        let state = orchestrator.state().unwrap();
+        assert_eq!(state.execution_state, ExecutionState::Failed);
+        assert!(
+            state
+                .error
+                .as_deref()
+                .unwrap()
+                .starts_with("failed to collect run output")
+        );
+    }
+
+    #[tokio::test]
+    async fn orchestrator_collects_inputs() {
+        let (workflow, id_a, id_b) = create_simple_workflow();
+        let event_store = InMemoryEventStore::new();
+        let mut orchestrator = Orchestrator::new(workflow, event_store, InMemoryObjectStore::new());
+
+        orchestrator.initialize(None).await.unwrap();
+        orchestrator.start().await.unwrap();
+
+        let run_id = orchestrator.run_id().unwrap();
+
+        // Complete node A
+        orchestrator
+            .handle_result(WorkItemResult::Completed {
+                run_id,
+                node_id: id_a,
+                item_index: None,
+                attempt: 1,
+                output_key: "output_key_123".to_string(),
+            })
+            .await
+            .unwrap();
+
+        // B's work item should have A's output as input
+        let work_items = orchestrator.event_store.work_items();
+        let b_work_item = work_items.iter().find(|w| w.node_id == id_b).unwrap();
+        assert_eq!(
+            b_work_item.inputs.get("context"),
+            Some(&"output_key_123".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn branch_routes_to_matching_port_and_skips_transitively() {
+        let (mut workflow, trigger_id, branch_id) = create_branch_workflow("confidence <= 0.8");
+        let high_id = add_transform_after(&mut workflow, branch_id, "high", "High");
+        let low_id = add_transform_after(&mut workflow, branch_id, "low", "Low");
+        let after_low_id = add_transform_after(&mut workflow, low_id, "output", "After Low");
+
+        let mut orchestrator = Orchestrator::new(
+            workflow,
+            InMemoryEventStore::new(),
+            InMemoryObjectStore::new(),
+        );
+        let run_id = complete_trigger(
+            &mut orchestrator,
+            trigger_id,
+            serde_json::json!({"confidence": 0.9}),
+        )
+        .await;
+
+        let events = orchestrator.event_store.events();
+        assert!(events.iter().any(|e| matches!(
+            e,
+            ExecutionEvent::BranchTaken { node_id, ports, .. }
+                if *node_id == branch_id && ports == &vec!["high".to_string()]
+        )));
+        let skipped = skipped_nodes(&events);
+        assert_eq!(skipped.len(), 2);
+        assert!(skipped.contains(&low_id));
+        assert!(skipped.contains(&after_low_id));
+
+        // Only the taken path goes to workers; the branch passes its input through
+        let work_items = orchestrator.event_store.work_items();
+        assert_eq!(work_items.len(), 2);
+        assert_eq!(work_items[1].node_id, high_id);
+        assert_eq!(
+            work_items[1].inputs.get("input"),
+            Some(&"obj_1".to_string())
+        );
+        assert!(!work_items.iter().any(|w| w.node_id == branch_id));
+
+        let output_key = store_output(&orchestrator, serde_json::json!({})).await;
+        orchestrator
+            .handle_result(WorkItemResult::Completed {
+                run_id,
+                node_id: high_id,
+                item_index: None,
+                attempt: 1,
+                output_key,
+            })
+            .await
+            .unwrap();
+
+        assert!(orchestrator.is_complete());
+        let state = orchestrator.state().unwrap();
+        assert_eq!(state.execution_state, ExecutionState::Completed);
+        assert_eq!(
+            state.node_states[&low_id].state,
+            NodeExecutionState::Skipped
+        );
+        assert_eq!(
+            state.node_states[&branch_id].taken_ports,
+            Some(vec!["high".to_string()])
+        );
+    }
+
+    #[tokio::test]
+    async fn join_runs_when_any_branch_path_is_taken() {
+        let (mut workflow, trigger_id, branch_id) = create_branch_workflow("confidence <= 0.8");
+        let high_id = add_transform_after(&mut workflow, branch_id, "high", "High");
+        let low_id = add_transform_after(&mut workflow, branch_id, "low", "Low");
+        let join = Node::new("Join", NodeConfig::ControlFlow(ControlFlowNodeConfig::Join));
+        let join_id = workflow.graph.add_node(join);
+        for source in [high_id, low_id] {
+            workflow
+                .graph
+                .add_edge(source, join_id, Edge::new("output", "input"))
+                .unwrap();
+        }
+
+        let mut orchestrator = Orchestrator::new(
+            workflow,
+            InMemoryEventStore::new(),
+            InMemoryObjectStore::new(),
+        );
+        let run_id = complete_trigger(
+            &mut orchestrator,
+            trigger_id,
+            serde_json::json!({"confidence": 0.2}),
+        )
+        .await;
+        assert_eq!(
+            skipped_nodes(&orchestrator.event_store.events()),
+            vec![high_id]
+        );
+
+        orchestrator
+            .handle_result(WorkItemResult::Completed {
+                run_id,
+                node_id: low_id,
+                item_index: None,
+                attempt: 1,
+                output_key: "output_low".to_string(),
+            })
+            .await
+            .unwrap();
+
+        // The join is not skipped and receives the taken path's output
+        let work_items = orchestrator.event_store.work_items();
+        let join_item = work_items.iter().find(|w| w.node_id == join_id).unwrap();
+        assert_eq!(
+            join_item.inputs.get("input"),
+            Some(&"output_low".to_string())
+        );
+        assert!(!orchestrator.is_complete());
+
+        let output_key = store_output(&orchestrator, serde_json::json!({})).await;
+        orchestrator
+            .handle_result(WorkItemResult::Completed {
+                run_id,
+                node_id: join_id,
+                item_index: None,
+                attempt: 1,
+                output_key,
+            })
+            .await
+            .unwrap();
+        assert_eq!(
+            orchestrator.state().unwrap().execution_state,
+            ExecutionState::Completed
+        );
+    }
+
+    #[tokio::test]
+    async fn join_is_skipped_when_no_path_is_taken() {
+        let (mut workflow, trigger_id, branch_id) = create_branch_workflow("confidence < 0");
+        let high_id = add_transform_after(&mut workflow, branch_id, "high", "High");
+        let low_id = add_transform_after(&mut workflow, branch_id, "low", "Low");
+        let join = Node::new("Join", NodeConfig::ControlFlow(ControlFlowNodeConfig::Join));
+        let join_id = workflow.graph.add_node(join);
+        for source in [high_id, low_id] {
+            workflow
+                .graph
+                .add_edge(source, join_id, Edge::new("output", "input"))
+                .unwrap();
+        }
+
+        let mut orchestrator = Orchestrator::new(
+            workflow,
+            InMemoryEventStore::new(),
+            InMemoryObjectStore::new(),
+        );
+        complete_trigger(
+            &mut orchestrator,
+            trigger_id,
+            serde_json::json!({"confidence": 0.5}),
+        )
+        .await;
+
+        let skipped = skipped_nodes(&orchestrator.event_store.events());
+        assert_eq!(skipped.len(), 3);
+        assert!(skipped.contains(&join_id));
+
+        // Nothing left to run: the run completes without failures
+        assert!(orchestrator.is_complete());
+        assert_eq!(
+            orchestrator.state().unwrap().execution_state,
+            ExecutionState::Completed
+        );
+    }
+
+    #[tokio::test]
+    async fn invalid_branch_condition_fails_the_branch() {
+        let (mut workflow, trigger_id, branch_id) = create_branch_workflow("confidence <");
+        add_transform_after(&mut workflow, branch_id, "high", "High");
+
+        let mut orchestrator = Orchestrator::new(
+            workflow,
+            InMemoryEventStore::new(),
+            InMemoryObjectStore::new(),
+        );
+        complete_trigger(
+            &mut orchestrator,
+            trigger_id,
+            serde_json::json!({"confidence": 0.9}),
+        )
+        .await;
+
+        let state = orchestrator.state().unwrap();
+        assert_eq!(state.execution_state, ExecutionState::Failed);
+        let error = state.node_states[&branch_id].error.as_deref().unwrap();
+        assert!(error.contains("invalid condition for port 'low'"));
+    }
+
+    #[tokio::test]
+    async fn failing_branch_condition_fails_the_branch() {
+        let (mut workflow, trigger_id, branch_id) =
+            create_branch_workflow("lower(confidence) == 'high'");
+        add_transform_after(&mut workflow, branch_id, "high", "High");
+
+        let mut orchestrator = Orchestrator::new(
+            workflow,
+            InMemoryEventStore::new(),
+            InMemoryObjectStore::new(),
+        );
+        complete_trigger(
+            &mut orchestrator,
+            trigger_id,
+            serde_json::json!({"confidence": 0.9}),
+        )
+        .await;
+
+        let state = orchestrator.state().unwrap();
+        assert_eq!(state.execution_state, ExecutionState::Failed);
+        let error = state.node_states[&branch_id].error.as_deref().unwrap();
+        assert!(error.contains("condition for port 'low' failed"));
+    }
+
+    #[tokio::test]
+    async fn branch_replay_restores_taken_ports() {
+        let (mut workflow, trigger_id, branch_id) = create_branch_workflow("confidence <= 0.8");
+        let high_id = add_transform_after(&mut workflow, branch_id, "high", "High");
+        let low_id = add_transform_after(&mut workflow, branch_id, "low", "Low");
+        let graph = workflow.graph.clone();
+
+        let mut orchestrator = Orchestrator::new(
+            workflow,
+            InMemoryEventStore::new(),
+            InMemoryObjectStore::new(),
+        );
+        complete_trigger(
+            &mut orchestrator,
+            trigger_id,
+            serde_json::json!({"confidence": 0.9}),
+        )
+        .await;
+
+        let replayed = RunStateBuilder::new(graph)
+            .build_from_events(orchestrator.event_store.events())
+            .unwrap();
+        assert_eq!(
+            replayed.node_states[&branch_id].taken_ports,
+            Some(vec!["high".to_string()])
+        );
+        assert_eq!(
+            replayed.node_states[&low_id].state,
+            NodeExecutionState::Skipped
+        );
+        assert!(
+            replayed
+                .remaining_work()
+                .executing_nodes()
+                .contains(&high_id)
+        );
+        assert!(replayed.ready_nodes().is_empty());
+    }
+
+    /// Trigger -> Approval, with `Send` on the approved port and `Log` on
+    /// the rejected port.
+    fn create_approval_workflow(
+        expires_after_ms: Option<u64>,
+    ) -> (Workflow, NodeId, NodeId, NodeId, NodeId) {
+        let mut workflow = Workflow::new("Approval Workflow");
+        let trigger_id = workflow.graph.add_node(create_trigger_node("Trigger"));
+        let approval_id = workflow.graph.add_node(Node::new(
+            "Approval",
+            NodeConfig::ControlFlow(ControlFlowNodeConfig::Approval {
+                instructions: "Check the reply".to_string(),
+                expires_after_ms,
+                on_expiry: ApprovalDecision::Rejected,
+            }),
+        ));
+        workflow
+            .graph
+            .add_edge(trigger_id, approval_id, Edge::new("output", "input"))
+            .unwrap();
+        let send_id = add_transform_after(&mut workflow, approval_id, "approved", "Send");
+        let log_id = add_transform_after(&mut workflow, approval_id, "rejected", "Log");
+        workflow.validate().unwrap();
+        (workflow, trigger_id, approval_id, send_id, log_id)
+    }
+
+    #[tokio::test]
+    async fn approval_pauses_the_run_until_a_decision() {
+        let (workflow, trigger_id, approval_id, send_id, log_id) = create_approval_workflow(None);
+        let mut orchestrator = Orchestrator::new(
+            workflow,
+            InMemoryEventStore::new(),
+            InMemoryObjectStore::new(),
+        );
+        complete_trigger(
+            &mut orchestrator,
+            trigger_id,
+            serde_json::json!({"to": "a@example.com"}),
+        )
+        .await;
+
+        let state = orchestrator.state().unwrap();
+        assert_eq!(state.execution_state, ExecutionState::Waiting);
+        assert_eq!(
+            state.node_states[&approval_id].state,
+            NodeExecutionState::Waiting
+        );
+        assert!(orchestrator.event_store.events().iter().any(|e| matches!(
+            e,
+            ExecutionEvent::ApprovalRequested {
+                node_id,
+                payload,
+                expires_at: None,
+                ..
+            } if *node_id == approval_id && *payload == serde_json::json!({"to": "a@example.com"})
+        )));
+        // Approvals are not sent to workers
+        assert!(
+            orchestrator
+                .event_store
+                .work_items()
+                .iter()
+                .all(|w| w.node_id == trigger_id)
+        );
+
+        orchestrator
+            .resolve_approval(
+                approval_id,
+                None,
+                ApprovalDecision::Approved,
+                Some("alice".to_string()),
+            )
+            .await
+            .unwrap();
+
+        let state = orchestrator.state().unwrap();
+        assert_eq!(state.execution_state, ExecutionState::Running);
+        assert_eq!(
+            state.node_states[&approval_id].taken_ports,
+            Some(vec!["approved".to_string()])
+        );
+        assert_eq!(
+            state.node_states[&send_id].state,
+            NodeExecutionState::Running
+        );
+        assert_eq!(
+            skipped_nodes(&orchestrator.event_store.events()),
+            vec![log_id]
+        );
+        let send = orchestrator
+            .event_store
+            .work_items()
+            .into_iter()
+            .find(|w| w.node_id == send_id)
+            .unwrap();
+        assert_eq!(
+            read_output(&orchestrator, &send.inputs["input"]).await,
+            serde_json::json!({"to": "a@example.com"})
+        );
+    }
+
+    #[tokio::test]
+    async fn waiting_approval_survives_recovery() {
+        let (workflow, trigger_id, approval_id, _, log_id) = create_approval_workflow(None);
+        let graph = workflow.graph.clone();
+        let mut orchestrator = Orchestrator::new(
+            workflow.clone(),
+            InMemoryEventStore::new(),
+            InMemoryObjectStore::new(),
+        );
+        let run_id = complete_trigger(&mut orchestrator, trigger_id, serde_json::json!({})).await;
+
+        let replayed = RunStateBuilder::new(graph)
+            .build_from_events(orchestrator.event_store.events())
+            .unwrap();
+        assert_eq!(replayed.execution_state, ExecutionState::Waiting);
+        assert_eq!(
+            replayed
+                .waiting_nodes()
+                .map(|exec| exec.node_id)
+                .collect::<Vec<_>>(),
+            vec![approval_id]
+        );
+
+        // A new orchestrator leaves the approval pending, then resolves it
+        let event_count = orchestrator.event_store.events().len();
+        let mut recovered = Orchestrator::new(
+            workflow,
+            orchestrator.event_store,
+            orchestrator.object_store,
+        );
+        recovered.initialize(Some(run_id)).await.unwrap();
+        recovered.recover().await.unwrap();
+        assert_eq!(recovered.event_store.events().len(), event_count);
+
+        recovered
+            .resolve_approval(approval_id, None, ApprovalDecision::Rejected, None)
+            .await
+            .unwrap();
+        assert_eq!(
+            recovered.state().unwrap().node_states[&log_id].state,
+            NodeExecutionState::Running
+        );
+    }
+
+    #[tokio::test]
+    async fn expired_approval_takes_the_expiry_decision() {
+        let (workflow, trigger_id, approval_id, send_id, log_id) =
+            create_approval_workflow(Some(60_000));
+        let mut orchestrator = Orchestrator::new(
+            workflow,
+            InMemoryEventStore::new(),
+            InMemoryObjectStore::new(),
+        );
+        complete_trigger(&mut orchestrator, trigger_id, serde_json::json!({})).await;
+        let expires_at = orchestrator.state().unwrap().node_states[&approval_id]
+            .expires_at
+            .unwrap();
+
+        assert_eq!(orchestrator.expire_approvals(Utc::now()).await.unwrap(), 0);
+        assert_eq!(orchestrator.expire_approvals(expires_at).await.unwrap(), 1);
+
+        let state = orchestrator.state().unwrap();
+        assert_eq!(
+            state.node_states[&log_id].state,
+            NodeExecutionState::Running
+        );
+        assert_eq!(
+            skipped_nodes(&orchestrator.event_store.events()),
+            vec![send_id]
+        );
+        assert!(orchestrator.event_store.events().iter().any(|e| matches!(
+            e,
+            ExecutionEvent::ApprovalResolved {
+                decision: ApprovalDecision::Rejected,
+                decided_by: None,
+                ..
+            }
+        )));
+    }
+
+    #[tokio::test]
+    async fn resolving_an_approval_twice_fails() {
+        let (workflow, trigger_id, approval_id, _, _) = create_approval_workflow(None);
+        let mut orchestrator = Orchestrator::new(
+            workflow,
+            InMemoryEventStore::new(),
+            InMemoryObjectStore::new(),
+        );
+        let run_id = complete_trigger(&mut orchestrator, trigger_id, serde_json::json!({})).await;
+        orchestrator
+            .resolve_approval(approval_id, None, ApprovalDecision::Approved, None)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            orchestrator
+                .resolve_approval(approval_id, None, ApprovalDecision::Rejected, None)
+                .await,
+            Err(OrchestratorError::ApprovalNotPending {
+                run_id,
+                node_id: approval_id,
+                item_index: None,
+            })
+        );
+    }
+
+    /// Trigger -> Delay -> Send, with `Log` on a parallel path from the
+    /// trigger.
+    fn create_delay_workflow(until: DelayUntil) -> (Workflow, NodeId, NodeId, NodeId, NodeId) {
+        let mut workflow = Workflow::new("Delay Workflow");
+        let trigger_id = workflow.graph.add_node(create_trigger_node("Trigger"));
+        let delay_id = workflow.graph.add_node(Node::new(
+            "Delay",
+            NodeConfig::ControlFlow(ControlFlowNodeConfig::Delay { until }),
+        ));
+        workflow
+            .graph
+            .add_edge(trigger_id, delay_id, Edge::new("output", "input"))
+            .unwrap();
+        let send_id = add_transform_after(&mut workflow, delay_id, "output", "Send");
+        let log_id = add_transform_after(&mut workflow, trigger_id, "output", "Log");
+        workflow.validate().unwrap();
+        (workflow, trigger_id, delay_id, send_id, log_id)
+    }
+
+    #[tokio::test]
+    async fn delay_pauses_only_its_path() {
+        let (workflow, trigger_id, delay_id, send_id, log_id) =
+            create_delay_workflow(DelayUntil::Duration {
+                duration_ms: 7_200_000,
+            });
+        let mut orchestrator = Orchestrator::new(
+            workflow,
+            InMemoryEventStore::new(),
+            InMemoryObjectStore::new(),
+        );
+        complete_trigger(&mut orchestrator, trigger_id, serde_json::json!({"id": 1})).await;
+
+        let state = orchestrator.state().unwrap();
+        assert_eq!(state.execution_state, ExecutionState::Waiting);
+        assert_eq!(
+            state.node_states[&delay_id].state,
+            NodeExecutionState::Waiting
+        );
+        assert_eq!(
+            state.node_states[&log_id].state,
+            NodeExecutionState::Running
+        );
+        assert_eq!(
+            state.node_states[&send_id].state,
+            NodeExecutionState::Pending
+        );
+        let until = state.node_states[&delay_id].expires_at.unwrap();
+        assert!(orchestrator.event_store.events().iter().any(|e| matches!(
+            e,
+            ExecutionEvent::DelayStarted { node_id, until: at, .. }
+                if *node_id == delay_id && *at == until
+        )));
+
+        // The other path finishes while the delay holds the run open
+        complete_node(&mut orchestrator, log_id, serde_json::json!({})).await;
+        assert_eq!(
+            orchestrator.state().unwrap().execution_state,
+            ExecutionState::Waiting
+        );
+        assert_eq!(orchestrator.end_delays(Utc::now()).await.unwrap(), 0);
+
+        assert_eq!(orchestrator.end_delays(until).await.unwrap(), 1);
+        let state = orchestrator.state().unwrap();
+        assert_eq!(state.execution_state, ExecutionState::Running);
+        assert_eq!(
+            state.node_states[&delay_id].state,
+            NodeExecutionState::Completed
+        );
+        let send = orchestrator
+            .event_store
+            .work_items()
+            .into_iter()
+            .find(|w| w.node_id == send_id)
+            .unwrap();
+        assert_eq!(
+            read_output(&orchestrator, &send.inputs["input"]).await,
+            serde_json::json!({"id": 1})
+        );
+        // Delays are not sent to workers
+        assert!(
+            orchestrator
+                .event_store
+                .work_items()
+                .iter()
+                .all(|w| w.node_id != delay_id)
+        );
+    }
+
+    #[tokio::test]
+    async fn delay_until_a_time_from_the_input() {
+        let (workflow, trigger_id, delay_id, _, _) = create_delay_workflow(DelayUntil::Time {
+            expression: "add_seconds(meeting.start, -600)".to_string(),
+        });
+        let mut orchestrator = Orchestrator::new(
+            workflow,
+            InMemoryEventStore::new(),
+            InMemoryObjectStore::new(),
+        );
+        complete_trigger(
+            &mut orchestrator,
+            trigger_id,
+            serde_json::json!({"meeting": {"start": "2100-01-01T10:00:00Z"}}),
+        )
+        .await;
+
+        assert_eq!(
+            orchestrator.state().unwrap().node_states[&delay_id].expires_at,
+            Some("2100-01-01T09:50:00Z".parse().unwrap())
+        );
+    }
+
+    #[tokio::test]
+    async fn delay_until_a_past_time_ends_right_away() {
+        let (workflow, trigger_id, delay_id, send_id, _) =
+            create_delay_workflow(DelayUntil::Time {
+                expression: "at".to_string(),
+            });
+        let mut orchestrator = Orchestrator::new(
+            workflow,
+            InMemoryEventStore::new(),
+            InMemoryObjectStore::new(),
+        );
+        complete_trigger(
+            &mut orchestrator,
+            trigger_id,
+            serde_json::json!({"at": "2000-01-01"}),
+        )
+        .await;
+
+        let state = orchestrator.state().unwrap();
+        assert_eq!(
+            state.node_states[&delay_id].state,
+            NodeExecutionState::Completed
+        );
+        assert_eq!(
+            state.node_states[&send_id].state,
+            NodeExecutionState::Running
+        );
+    }
+
+    #[tokio::test]
+    async fn delay_time_that_is_not_a_date_fails_the_node() {
+        let (workflow, trigger_id, delay_id, _, _) = create_delay_workflow(DelayUntil::Time {
+            expression: "at".to_string(),
+        });
+        let mut orchestrator = Orchestrator::new(
+            workflow,
+            InMemoryEventStore::new(),
+            InMemoryObjectStore::new(),
+        );
+        complete_trigger(
+            &mut orchestrator,
+            trigger_id,
+            serde_json::json!({"at": "tomorrow"}),
+        )
+        .await;
+
+        let delay = &orchestrator.state().unwrap().node_states[&delay_id];
+        assert_eq!(delay.state, NodeExecutionState::Failed);
+        assert!(delay.error.as_ref().unwrap().contains("not a date"));
+    }
+
+    #[tokio::test]
+    async fn waiting_delay_survives_recovery() {
+        let (workflow, trigger_id, delay_id, send_id, _) =
+            create_delay_workflow(DelayUntil::Duration {
+                duration_ms: 60_000,
+            });
+        let graph = workflow.graph.clone();
+        let mut orchestrator = Orchestrator::new(
+            workflow.clone(),
+            InMemoryEventStore::new(),
+            InMemoryObjectStore::new(),
+        );
+        let run_id = complete_trigger(&mut orchestrator, trigger_id, serde_json::json!({})).await;
+        let until = orchestrator.state().unwrap().node_states[&delay_id]
+            .expires_at
+            .unwrap();
+
+        let replayed = RunStateBuilder::new(graph)
+            .build_from_events(orchestrator.event_store.events())
+            .unwrap();
+        assert_eq!(replayed.node_states[&delay_id].expires_at, Some(until));
+
+        // A new orchestrator keeps waiting, then ends the delay
+        let mut recovered = Orchestrator::new(
+            workflow,
+            orchestrator.event_store,
+            orchestrator.object_store,
+        );
+        recovered.initialize(Some(run_id)).await.unwrap();
+        recovered.recover().await.unwrap();
+        assert_eq!(
+            recovered.state().unwrap().node_states[&delay_id].state,
+            NodeExecutionState::Waiting
+        );
+
+        assert_eq!(recovered.end_delays(until).await.unwrap(), 1);
+        assert_eq!(
+            recovered.state().unwrap().node_states[&send_id].state,
+            NodeExecutionState::Running
+        );
+    }
+
+    /// Trigger -> Fetch -> Summarize -> Send, with Fetch completed and
+    /// Summarize failed.
+    async fn create_failed_run() -> (
+        Orchestrator<InMemoryEventStore, InMemoryObjectStore>,
+        [NodeId; 4],
+    ) {
+        let mut workflow = Workflow::new("Retry Workflow");
+        let trigger_id = workflow.graph.add_node(create_trigger_node("Trigger"));
+        let fetch_id = add_transform_after(&mut workflow, trigger_id, "output", "Fetch");
+        let summarize_id = add_transform_after(&mut workflow, fetch_id, "output", "Summarize");
+        let send_id = add_transform_after(&mut workflow, summarize_id, "output", "Send");
+        let mut orchestrator = Orchestrator::new(
+            workflow,
+            InMemoryEventStore::new(),
+            InMemoryObjectStore::new(),
+        );
+        let run_id = complete_trigger(&mut orchestrator, trigger_id, serde_json::json!({})).await;
+        complete_node(&mut orchestrator, fetch_id, serde_json::json!({"mail": 3})).await;
+        orchestrator
+            .handle_result(failure(
+                run_id,
+                summarize_id,
+                1,
+                NodeErrorKind::ExecutionFailed,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(
+            orchestrator.state().unwrap().execution_state,
+            ExecutionState::Failed
+        );
+        (orchestrator, [trigger_id, fetch_id, summarize_id, send_id])
+    }
+
+    #[tokio::test]
+    async fn retry_reruns_only_the_failed_node_and_its_descendants() {
+        let (failed, [trigger_id, fetch_id, summarize_id, send_id]) = create_failed_run().await;
+        let failed_state = failed.state().unwrap().clone();
+        let fetch_key = failed_state.node_states[&fetch_id].output_key.clone();
+        let work_item_count = failed.event_store.work_items().len();
+
+        let mut retry = Orchestrator::new(
+            failed.workflow.clone(),
+            failed.event_store,
+            failed.object_store,
+        );
+        let retry_id = WorkflowRunId::new();
+        retry
+            .queue_retry(retry_id, &failed_state, summarize_id)
+            .await
+            .unwrap();
+        retry.start().await.unwrap();
+
+        let state = retry.state().unwrap();
+        assert_eq!(state.retry_of, Some(failed_state.run_id));
+        assert_eq!(
+            state.node_states[&trigger_id].state,
+            NodeExecutionState::Completed
+        );
+        assert_eq!(state.node_states[&fetch_id].output_key, fetch_key);
+        assert_eq!(
+            state.node_states[&summarize_id].state,
+            NodeExecutionState::Running
+        );
+        assert_eq!(
+            state.node_states[&send_id].state,
+            NodeExecutionState::Pending
+        );
+
+        // Only the failed node is dispatched, with the seeded output as input
+        let new_items: Vec<WorkItem> = retry.event_store.work_items()[work_item_count..].to_vec();
+        assert_eq!(new_items.len(), 1);
+        assert_eq!(new_items[0].run_id, retry_id);
+        assert_eq!(new_items[0].node_id, summarize_id);
+        assert_eq!(Some(&new_items[0].inputs["input"]), fetch_key.as_ref());
+
+        complete_node(&mut retry, summarize_id, serde_json::json!("summary")).await;
+        complete_node(&mut retry, send_id, serde_json::json!({})).await;
+        assert_eq!(
+            retry.state().unwrap().execution_state,
+            ExecutionState::Completed
+        );
+
+        // The failed run records the retry
+        let failed_state = retry.load_run_state(failed_state.run_id).await.unwrap();
+        assert_eq!(failed_state.execution_state, ExecutionState::Failed);
+        assert_eq!(failed_state.retried_by, vec![retry_id]);
+    }
+
+    #[tokio::test]
+    async fn retry_requires_a_failed_run_and_node() {
+        let (failed, [_, fetch_id, summarize_id, _]) = create_failed_run().await;
+        let failed_state = failed.state().unwrap().clone();
+        let mut retry = Orchestrator::new(
+            failed.workflow.clone(),
+            InMemoryEventStore::new(),
+            InMemoryObjectStore::new(),
+        );
+
+        assert_eq!(
+            retry
+                .queue_retry(WorkflowRunId::new(), &failed_state, fetch_id)
+                .await,
+            Err(OrchestratorError::NodeNotFailed {
+                run_id: failed_state.run_id,
+                node_id: fetch_id,
+            })
+        );
+
+        let mut running_state = failed_state.clone();
+        running_state.execution_state = ExecutionState::Running;
+        assert_eq!(
+            retry
+                .queue_retry(WorkflowRunId::new(), &running_state, summarize_id)
+                .await,
+            Err(OrchestratorError::RunNotFailed {
+                run_id: failed_state.run_id,
+            })
+        );
+        assert!(retry.event_store.events().is_empty());
+    }
+
+    #[tokio::test]
+    async fn retry_inside_a_fan_out_reruns_the_fan_out() {
+        let (workflow, trigger_id, per_item_id, fan_in_id, _) = create_fan_out_workflow();
+        let fan_out_id = workflow
+            .graph
+            .predecessors(per_item_id)
+            .first()
+            .map(|(node, _)| node.id)
+            .unwrap();
+        let mut orchestrator = Orchestrator::new(
+            workflow.clone(),
+            InMemoryEventStore::new(),
+            InMemoryObjectStore::new(),
+        );
+        let run_id = complete_trigger(&mut orchestrator, trigger_id, serde_json::json!([1])).await;
+        orchestrator
+            .handle_result(WorkItemResult::Failed {
+                run_id,
+                node_id: per_item_id,
+                item_index: Some(0),
+                attempt: 1,
+                error: "boom".to_string(),
+                error_kind: NodeErrorKind::ExecutionFailed,
+            })
+            .await
+            .unwrap();
+        let failed_state = orchestrator.state().unwrap().clone();
+
+        let mut retry = Orchestrator::new(
+            workflow,
+            orchestrator.event_store,
+            orchestrator.object_store,
+        );
+        retry
+            .queue_retry(WorkflowRunId::new(), &failed_state, per_item_id)
+            .await
+            .unwrap();
+        retry.start().await.unwrap();
+
+        assert!(retry.event_store.events().iter().any(|e| matches!(
+            e,
+            ExecutionEvent::RunSeeded { from_node, nodes, .. }
+                if *from_node == fan_out_id && nodes.len() == 1 && nodes[0].node_id == trigger_id
+        )));
+        let state = retry.state().unwrap();
+        assert_eq!(
+            state.item_states[&(per_item_id, 0)].state,
+            NodeExecutionState::Running
+        );
+        assert_eq!(
+            state.node_states[&fan_in_id].state,
+            NodeExecutionState::Pending
+        );
+    }
+
+    fn create_integration_node(name: &str, operation: &str) -> Node {
+        Node::new(
+            name,
+            NodeConfig::Integration(IntegrationNodeConfig {
+                integration_type: "email".to_string(),
+                operation: operation.to_string(),
+                parameters: serde_json::json!({"to": "me@example.com"}),
+            }),
+        )
+    }
+
+    #[tokio::test]
+    async fn dry_run_substitutes_integration_writes_with_fixtures() {
+        // Trigger -> Fetch -> Send -> Report
+        let mut workflow = Workflow::new("Dry Run Workflow");
+        let trigger_id = workflow.graph.add_node(create_trigger_node("Trigger"));
+        let fetch_id = workflow
+            .graph
+            .add_node(create_integration_node("Fetch", "fetch"));
+        let send_id = workflow
+            .graph
+            .add_node(create_integration_node("Send", "send"));
+        workflow
+            .graph
+            .add_edge(trigger_id, fetch_id, Edge::new("output", "input"))
+            .unwrap();
+        workflow
+            .graph
+            .add_edge(fetch_id, send_id, Edge::new("output", "input"))
+            .unwrap();
+        let report_id = add_transform_after(&mut workflow, send_id, "output", "Report");
+        let graph = workflow.graph.clone();
+
+        let mut orchestrator = Orchestrator::new(
+            workflow,
+            InMemoryEventStore::new(),
+            InMemoryObjectStore::new(),
+        );
+        let dry_run =
+            DryRunConfig::new().with_fixture(send_id, serde_json::json!({"message_id": "m1"}));
+        orchestrator
+            .queue_dry_run(WorkflowRunId::new(), None, None, dry_run)
+            .await
+            .unwrap();
+        orchestrator.start().await.unwrap();
+        complete_node(&mut orchestrator, trigger_id, serde_json::json!({})).await;
+        complete_node(&mut orchestrator, fetch_id, serde_json::json!({"mail": 1})).await;
+
+        // Reads go to workers, marked as a dry run; the write never does
+        let work_items = orchestrator.event_store.work_items();
+        let dispatched: Vec<NodeId> = work_items.iter().map(|item| item.node_id).collect();
+        assert_eq!(dispatched, vec![trigger_id, fetch_id, report_id]);
+        assert!(work_items.iter().all(|item| item.dry_run.is_some()));
+
+        let events = orchestrator.event_store.events();
+        let (request, response) = events
+            .iter()
+            .find_map(|e| match e {
+                ExecutionEvent::NodeSubstituted {
+                    node_id,
+                    request,
+                    response,
+                    ..
+                } if *node_id == send_id => Some((request.clone(), response.clone())),
+                _ => None,
+            })
+            .expect("Send is substituted");
+        assert_eq!(request["inputs"]["input"], serde_json::json!({"mail": 1}));
+        assert_eq!(request["config"]["operation"], "send");
+        assert_eq!(response, serde_json::json!({"message_id": "m1"}));
+
+        // Downstream nodes get the fixture response
+        let report_input = work_items.last().unwrap().inputs["input"].clone();
+        assert_eq!(
+            read_output(&orchestrator, &report_input).await,
+            serde_json::json!({"message_id": "m1"})
+        );
+        complete_node(&mut orchestrator, report_id, serde_json::json!("sent")).await;
+        assert_eq!(
+            orchestrator.state().unwrap().execution_state,
+            ExecutionState::Completed
+        );
+
+        // The dry run is part of the run's history
+        let replayed = RunStateBuilder::new(graph)
+            .build_from_events(orchestrator.event_store.events())
+            .unwrap();
+        assert!(replayed.dry_run.is_some());
+    }
+
+    #[tokio::test]
+    async fn dry_run_stubs_llm_calls_only_when_asked() {
+        let (workflow, trigger_id, ai_id) = create_simple_workflow();
+
+        let mut orchestrator = Orchestrator::new(
+            workflow.clone(),
+            InMemoryEventStore::new(),
+            InMemoryObjectStore::new(),
+        );
+        orchestrator
+            .queue_dry_run(WorkflowRunId::new(), None, None, DryRunConfig::new())
+            .await
+            .unwrap();
+        orchestrator.start().await.unwrap();
+        complete_node(&mut orchestrator, trigger_id, serde_json::json!("hi")).await;
+        assert_eq!(
+            orchestrator
+                .event_store
+                .work_items()
+                .last()
+                .unwrap()
+                .node_id,
+            ai_id
+        );
+
+        let mut orchestrator = Orchestrator::new(
+            workflow,
+            InMemoryEventStore::new(),
+            InMemoryObjectStore::new(),
+        );
+        orchestrator
+            .queue_dry_run(
+                WorkflowRunId::new(),
+                None,
+                None,
+                DryRunConfig::new().with_stub_llm(),
+            )
+            .await
+            .unwrap();
+        orchestrator.start().await.unwrap();
+        complete_node(&mut orchestrator, trigger_id, serde_json::json!("hi")).await;
+
+        assert_eq!(orchestrator.event_store.work_items().len(), 1);
+        let state = orchestrator.state().unwrap();
+        assert_eq!(state.execution_state, ExecutionState::Completed);
+        let output_key = state.node_states[&ai_id].output_key.clone().unwrap();
+        assert_eq!(
+            read_output(&orchestrator, &output_key).await,
+            serde_json::json!("dry run")
+        );
+    }
+
+    #[tokio::test]
+    async fn dry_run_fixture_must_match_the_output_port() {
+        let (workflow, trigger_id, ai_id) = create_simple_workflow();
+        let mut orchestrator = Orchestrator::new(
+            workflow,
+            InMemoryEventStore::new(),
+            InMemoryObjectStore::new(),
+        );
+        let dry_run = DryRunConfig::new()
+            .with_stub_llm()
+            .with_fixture(ai_id, serde_json::json!({"text": "hi"}));
+        orchestrator
+            .queue_dry_run(WorkflowRunId::new(), None, None, dry_run)
+            .await
+            .unwrap();
+        orchestrator.start().await.unwrap();
+        complete_node(&mut orchestrator, trigger_id, serde_json::json!("hi")).await;
+
+        let state = orchestrator.state().unwrap();
+        assert_eq!(state.node_states[&ai_id].state, NodeExecutionState::Failed);
+        assert!(
+            state.node_states[&ai_id]
+                .error
+                .as_deref()
+                .is_some_and(|e| e.contains("does not match port 'generated'"))
+        );
+    }
+
+    #[tokio::test]
+    async fn fan_out_runs_scope_per_item_and_collects_in_order() {
+        let (workflow, trigger_id, per_item_id, fan_in_id, after_id) = create_fan_out_workflow();
+        let mut orchestrator = Orchestrator::new(
+            workflow,
+            InMemoryEventStore::new(),
+            InMemoryObjectStore::new(),
+        );
+        complete_trigger(
+            &mut orchestrator,
+            trigger_id,
+            serde_json::json!(["a", "b", "c"]),
+        )
+        .await;
+
+        // One work item per element, each receiving its own item
+        let work_items = orchestrator.event_store.work_items();
+        let per_item: Vec<_> = work_items
+            .iter()
+            .filter(|w| w.node_id == per_item_id)
+            .collect();
+        assert_eq!(per_item.len(), 3);
+        for (index, work_item) in per_item.iter().enumerate() {
+            assert_eq!(work_item.item_index, Some(index));
+            let item = read_output(&orchestrator, &work_item.inputs["input"]).await;
+            assert_eq!(item, serde_json::json!(["a", "b", "c"][index]));
+        }
+
+        // Items finish out of order; the FanIn waits for all of them
+        complete_item(&mut orchestrator, per_item_id, 2, serde_json::json!("C")).await;
+        complete_item(&mut orchestrator, per_item_id, 0, serde_json::json!("A")).await;
+        assert!(
+            !orchestrator
+                .event_store
+                .events()
+                .iter()
+                .any(|e| matches!(e, ExecutionEvent::FanOutFinished { .. }))
+        );
+        complete_item(&mut orchestrator, per_item_id, 1, serde_json::json!("B")).await;
+
+        let state = orchestrator.state().unwrap();
+        assert_eq!(
+            state.node_states[&per_item_id].state,
+            NodeExecutionState::Completed
+        );
+        let fan_in_key = state.node_states[&fan_in_id].output_key.clone().unwrap();
+        assert_eq!(
+            read_output(&orchestrator, &fan_in_key).await,
+            serde_json::json!(["A", "B", "C"])
+        );
+
+        let work_items = orchestrator.event_store.work_items();
+        let after_item = work_items.iter().find(|w| w.node_id == after_id).unwrap();
+        assert_eq!(after_item.item_index, None);
+        assert_eq!(after_item.inputs.get("input"), Some(&fan_in_key));
+    }
+
+    #[tokio::test]
+    async fn failed_item_fails_the_scope_node_and_blocks_fan_in() {
+        let (workflow, trigger_id, per_item_id, fan_in_id, _after_id) = create_fan_out_workflow();
+        let mut orchestrator = Orchestrator::new(
+            workflow,
+            InMemoryEventStore::new(),
+            InMemoryObjectStore::new(),
+        );
+        let run_id =
+            complete_trigger(&mut orchestrator, trigger_id, serde_json::json!([1, 2])).await;
+
+        orchestrator
+            .handle_result(WorkItemResult::Failed {
+                run_id,
+                node_id: per_item_id,
+                item_index: Some(1),
+                attempt: 1,
+                error: "boom".to_string(),
+                error_kind: NodeErrorKind::ExecutionFailed,
+            })
+            .await
+            .unwrap();
+        // The other item still runs to completion
+        assert!(!orchestrator.is_complete());
+        complete_item(&mut orchestrator, per_item_id, 0, serde_json::json!(1)).await;
+
+        let state = orchestrator.state().unwrap();
+        assert_eq!(state.execution_state, ExecutionState::Failed);
+        let per_item = &state.node_states[&per_item_id];
+        assert_eq!(per_item.state, NodeExecutionState::Failed);
+        assert_eq!(per_item.error.as_deref(), Some("item 1 failed: boom"));
+        assert_eq!(
+            state.node_states[&fan_in_id].state,
+            NodeExecutionState::Pending
+        );
+    }
+
+    #[tokio::test]
+    async fn branch_inside_fan_out_filters_items() {
+        let mut workflow = Workflow::new("Filter Workflow");
+        let trigger_id = workflow.graph.add_node(create_trigger_node("Trigger"));
+        let (fan_out_id, fan_in_id) = add_fan_out(&mut workflow, trigger_id);
+        let branch_id = workflow
+            .graph
+            .add_node(create_branch_node(&[("keep", "input > 1")]));
+        workflow
+            .graph
+            .add_edge(fan_out_id, branch_id, Edge::new("item", "input"))
+            .unwrap();
+        let kept_id = add_transform_after(&mut workflow, branch_id, "keep", "Kept");
+        workflow
+            .graph
+            .add_edge(kept_id, fan_in_id, Edge::new("output", "item"))
+            .unwrap();
+        workflow.validate().unwrap();
+
+        let mut orchestrator = Orchestrator::new(
+            workflow,
+            InMemoryEventStore::new(),
+            InMemoryObjectStore::new(),
+        );
+        complete_trigger(&mut orchestrator, trigger_id, serde_json::json!([1, 2, 3])).await;
+
+        // Branches are evaluated per item; the first item is filtered out
+        let events = orchestrator.event_store.events();
+        assert!(events.iter().any(|e| matches!(
+            e,
+            ExecutionEvent::NodeSkipped { node_id, item_index: Some(0), .. } if *node_id == kept_id
+        )));
+        let kept_items: Vec<_> = orchestrator
+            .event_store
+            .work_items()
+            .iter()
+            .filter(|w| w.node_id == kept_id)
+            .map(|w| w.item_index)
+            .collect();
+        assert_eq!(kept_items, vec![Some(1), Some(2)]);
+
+        complete_item(&mut orchestrator, kept_id, 1, serde_json::json!(20)).await;
+        complete_item(&mut orchestrator, kept_id, 2, serde_json::json!(30)).await;
+
+        let state = orchestrator.state().unwrap();
+        assert_eq!(state.execution_state, ExecutionState::Completed);
+        let fan_in_key = state.node_states[&fan_in_id].output_key.clone().unwrap();
+        assert_eq!(
+            read_output(&orchestrator, &fan_in_key).await,
+            serde_json::json!([20, 30])
+        );
+    }
+
+    #[tokio::test]
+    async fn empty_fan_out_collects_an_empty_array() {
+        let (workflow, trigger_id, per_item_id, fan_in_id, after_id) = create_fan_out_workflow();
+        let mut orchestrator = Orchestrator::new(
+            workflow,
+            InMemoryEventStore::new(),
+            InMemoryObjectStore::new(),
+        );
+        complete_trigger(&mut orchestrator, trigger_id, serde_json::json!([])).await;
+
+        let work_items = orchestrator.event_store.work_items();
+        assert!(!work_items.iter().any(|w| w.node_id == per_item_id));
+        let after_item = work_items.iter().find(|w| w.node_id == after_id).unwrap();
+        assert_eq!(
+            read_output(&orchestrator, &after_item.inputs["input"]).await,
+            serde_json::json!([])
+        );
+        assert_eq!(
+            orchestrator.state().unwrap().node_states[&fan_in_id].state,
+            NodeExecutionState::Completed
+        );
+    }
+
+    #[tokio::test]
+    async fn fan_out_rejects_non_array_input() {
+        let (workflow, trigger_id, per_item_id, _fan_in_id, _after_id) = create_fan_out_workflow();
+        let mut orchestrator = Orchestrator::new(
+            workflow,
+            InMemoryEventStore::new(),
+            InMemoryObjectStore::new(),
+        );
+        complete_trigger(&mut orchestrator, trigger_id, serde_json::json!({"a": 1})).await;
+
+        let state = orchestrator.state().unwrap();
+        assert_eq!(state.execution_state, ExecutionState::Failed);
+        assert!(state.item_states.is_empty());
+        assert_eq!(
+            state.node_states[&per_item_id].state,
+            NodeExecutionState::Pending
+        );
+    }
+
+    #[tokio::test]
+    async fn fan_out_replay_restores_item_states() {
+        let (workflow, trigger_id, per_item_id, _fan_in_id, _after_id) = create_fan_out_workflow();
+        let graph = workflow.graph.clone();
+        let mut orchestrator = Orchestrator::new(
+            workflow,
+            InMemoryEventStore::new(),
+            InMemoryObjectStore::new(),
+        );
+        complete_trigger(&mut orchestrator, trigger_id, serde_json::json!([1, 2])).await;
+        complete_item(&mut orchestrator, per_item_id, 0, serde_json::json!(10)).await;
+
+        let replayed = RunStateBuilder::new(graph)
+            .build_from_events(orchestrator.event_store.events())
+            .unwrap();
+        assert_eq!(
+            replayed.item_states[&(per_item_id, 0)].state,
+            NodeExecutionState::Completed
+        );
+        assert_eq!(
+            replayed.item_states[&(per_item_id, 1)].state,
+            NodeExecutionState::Running
+        );
+        assert_eq!(
+            replayed.item_states[&(per_item_id, 0)].output_key,
+            orchestrator.state().unwrap().item_states[&(per_item_id, 0)].output_key
+        );
+        assert!(
+            replayed
+                .remaining_work()
+                .executing_nodes()
+                .contains(&per_item_id)
+        );
+        assert!(replayed.ready_nodes().is_empty());
+    }
+
+    /// Trigger -> sub-workflow node, which retries failed child runs once.
+    fn create_sub_workflow_workflow() -> (Workflow, NodeId, NodeId) {
+        let mut workflow = Workflow::new("Parent Workflow");
+        let trigger = create_trigger_node("Trigger");
+        let sub_workflow = Node::new(
+            "Summarize",
+            NodeConfig::SubWorkflow(SubWorkflowNodeConfig {
+                workflow_id: silver_telegram_core::WorkflowId::new(),
+                input_mapping: BTreeMap::new(),
+            }),
+        )
+        .with_retry_policy(RetryPolicy {
+            retry_on: vec![NodeErrorKind::ExecutionFailed],
+            ..RetryPolicy::with_max_attempts(2)
+        });
+        let (trigger_id, sub_workflow_id) = (trigger.id, sub_workflow.id);
+        workflow.graph.add_node(trigger);
+        workflow.graph.add_node(sub_workflow);
+        workflow
+            .graph
+            .add_edge(trigger_id, sub_workflow_id, Edge::new("output", "input"))
+            .unwrap();
+        (workflow, trigger_id, sub_workflow_id)
+    }
+
+    fn waiting(run_id: WorkflowRunId, node_id: NodeId, attempt: u32) -> WorkItemResult {
+        WorkItemResult::Waiting {
+            run_id,
+            node_id,
+            item_index: None,
+            attempt,
+            child_run_id: sub_workflow::child_run_id(run_id, node_id, None, attempt),
+        }
+    }
+
+    #[tokio::test]
+    async fn sub_workflow_node_waits_for_its_child_run() {
+        let (workflow, trigger_id, sub_workflow_id) = create_sub_workflow_workflow();
+        let graph = workflow.graph.clone();
+        let mut orchestrator = Orchestrator::new(
+            workflow,
+            InMemoryEventStore::new(),
+            InMemoryObjectStore::new(),
+        );
+        let run_id = complete_trigger(&mut orchestrator, trigger_id, serde_json::json!({})).await;
+        let child_run_id = sub_workflow::child_run_id(run_id, sub_workflow_id, None, 1);
+
+        orchestrator
+            .handle_result(waiting(run_id, sub_workflow_id, 1))
+            .await
+            .unwrap();
+        let state = orchestrator.state().unwrap();
+        assert_eq!(state.execution_state, ExecutionState::Waiting);
+        assert_eq!(
+            state.node_states[&sub_workflow_id].state,
+            NodeExecutionState::Waiting
+        );
+        assert!(orchestrator.event_store.events().iter().any(|e| matches!(
+            e,
+            ExecutionEvent::SubWorkflowStarted { child_run_id: id, .. } if *id == child_run_id
+        )));
+
+        // A redelivered result and other runs' ends change nothing
+        let event_count = orchestrator.event_store.events().len();
+        orchestrator
+            .handle_result(waiting(run_id, sub_workflow_id, 1))
+            .await
+            .unwrap();
+        let other_child = sub_workflow::child_run_id(run_id, sub_workflow_id, None, 2);
+        assert!(
+            !orchestrator
+                .end_sub_workflow(sub_workflow_id, other_child, Ok(JsonValue::Null))
+                .await
+                .unwrap()
+        );
+        assert_eq!(orchestrator.event_store.events().len(), event_count);
+
+        // Replay restores the waiting node
+        let replayed = RunStateBuilder::new(graph)
+            .build_from_events(orchestrator.event_store.events())
+            .unwrap();
+        assert_eq!(replayed.execution_state, ExecutionState::Waiting);
+
+        assert!(
+            orchestrator
+                .end_sub_workflow(
+                    sub_workflow_id,
+                    child_run_id,
+                    Ok(serde_json::json!({"summary": "3 new mails"}))
+                )
+                .await
+                .unwrap()
+        );
+        let state = orchestrator.state().unwrap();
+        assert_eq!(state.execution_state, ExecutionState::Completed);
+        let output_key = state.node_states[&sub_workflow_id]
+            .output_key
+            .clone()
+            .unwrap();
+        assert_eq!(
+            read_output(&orchestrator, &output_key).await,
+            serde_json::json!({"summary": "3 new mails"})
+        );
+        assert!(orchestrator.event_store.events().iter().any(|e| matches!(
+            e,
+            ExecutionEvent::SubWorkflowEnded { child_run_id: id, .. } if *id == child_run_id
+        )));
+    }
+
+    #[tokio::test]
+    async fn child_run_that_ends_first_resumes_the_running_node() {
+        let (workflow, trigger_id, sub_workflow_id) = create_sub_workflow_workflow();
+        let mut orchestrator = Orchestrator::new(
+            workflow,
+            InMemoryEventStore::new(),
+            InMemoryObjectStore::new(),
+        );
+        let run_id = complete_trigger(&mut orchestrator, trigger_id, serde_json::json!({})).await;
+        let child_run_id = sub_workflow::child_run_id(run_id, sub_workflow_id, None, 1);
+
+        assert!(
+            orchestrator
+                .end_sub_workflow(sub_workflow_id, child_run_id, Ok(serde_json::json!(1)))
+                .await
+                .unwrap()
+        );
+        assert!(orchestrator.is_complete());
+
+        // The late report of the child run's start is ignored
+        orchestrator
+            .handle_result(waiting(run_id, sub_workflow_id, 1))
+            .await
+            .unwrap();
+        assert!(
+            !orchestrator
+                .event_store
+                .events()
+                .iter()
+                .any(|e| matches!(e, ExecutionEvent::SubWorkflowStarted { .. }))
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_child_run_retries_the_node_with_a_new_child_run() {
+        let (workflow, trigger_id, sub_workflow_id) = create_sub_workflow_workflow();
+        let mut orchestrator = Orchestrator::new(
+            workflow,
+            InMemoryEventStore::new(),
+            InMemoryObjectStore::new(),
+        );
+        let run_id = complete_trigger(&mut orchestrator, trigger_id, serde_json::json!({})).await;
+        orchestrator
+            .handle_result(waiting(run_id, sub_workflow_id, 1))
+            .await
+            .unwrap();
+
+        let failed = NodeExecutionError::ExecutionFailed {
+            message: "child run failed".to_string(),
+        };
+        let first_child = sub_workflow::child_run_id(run_id, sub_workflow_id, None, 1);
+        assert!(
+            orchestrator
+                .end_sub_workflow(sub_workflow_id, first_child, Err(failed.clone()))
+                .await
+                .unwrap()
+        );
+        let retry = orchestrator.event_store.work_items().pop().unwrap();
+        assert_eq!(retry.node_id, sub_workflow_id);
+        assert_eq!(retry.attempt, 2);
+        let state = orchestrator.state().unwrap();
+        assert_eq!(state.execution_state, ExecutionState::Running);
+
+        // The second attempt's child run fails the node for good
+        orchestrator
+            .handle_result(waiting(run_id, sub_workflow_id, 2))
+            .await
+            .unwrap();
+        let second_child = sub_workflow::child_run_id(run_id, sub_workflow_id, None, 2);
+        orchestrator
+            .end_sub_workflow(sub_workflow_id, second_child, Err(failed))
+            .await
+            .unwrap();
+        let state = orchestrator.state().unwrap();
+        assert_eq!(state.execution_state, ExecutionState::Failed);
+        assert_eq!(
+            state.node_states[&sub_workflow_id].error.as_deref(),
+            Some("execution error: execution failed: child run failed")
+        );
     }
 
-    fn create_trigger_node(name: &str) -> Node {
-        Node::new(
-            name,
-            NodeConfig::Trigger(TriggerNodeConfig::Schedule {
-                cron: "0 7 * * *".to_string(),
-                timezone: None,
-            }),
-        )
+    /// Trigger -> AI node with the given number of attempts.
+    fn create_retry_workflow(max_attempts: u32) -> (Workflow, NodeId, NodeId) {
+        let mut workflow = Workflow::new("Retry Workflow");
+        let trigger = create_trigger_node("Trigger");
+        let ai =
+            create_ai_node("AI").with_retry_policy(RetryPolicy::with_max_attempts(max_attempts));
+        let (trigger_id, ai_id) = (trigger.id, ai.id);
+        workflow.graph.add_node(trigger);
+        workflow.graph.add_node(ai);
+        workflow
+            .graph
+            .add_edge(trigger_id, ai_id, Edge::new("output", "context"))
+            .unwrap();
+        (workflow, trigger_id, ai_id)
     }
 
-    fn create_ai_node(name: &str) -> Node {
-        Node::new(
-            name,
-            NodeConfig::AiLayer(AiLayerNodeConfig::Generate {
-                instructions: "Test".to_string(),
-            }),
-        )
+    fn failure(
+        run_id: WorkflowRunId,
+        node_id: NodeId,
+        attempt: u32,
+        error_kind: NodeErrorKind,
+    ) -> WorkItemResult {
+        WorkItemResult::Failed {
+            run_id,
+            node_id,
+            item_index: None,
+            attempt,
+            error: format!("attempt {attempt} failed"),
+            error_kind,
+        }
     }
 
-    fn create_simple_workflow() -> (Workflow, NodeId, NodeId) {
-        let mut workflow = Workflow::new("Test Workflow");
+    #[tokio::test]
+    async fn retryable_failure_schedules_next_attempt() {
+        let (workflow, trigger_id, ai_id) = create_retry_workflow(3);
+        let graph = workflow.graph.clone();
+        let mut orchestrator = Orchestrator::new(
+            workflow,
+            InMemoryEventStore::new(),
+            InMemoryObjectStore::new(),
+        );
+        let run_id = complete_trigger(&mut orchestrator, trigger_id, serde_json::json!({})).await;
+        let first = orchestrator.event_store.work_items().pop().unwrap();
+        assert_eq!(first.attempt, 1);
+        assert_eq!(first.not_before, None);
 
-        // A -> B
-        let node_a = create_trigger_node("A");
-        let node_b = create_ai_node("B");
-        let id_a = node_a.id;
-        let id_b = node_b.id;
+        orchestrator
+            .handle_result(failure(run_id, ai_id, 1, NodeErrorKind::Timeout))
+            .await
+            .unwrap();
 
-        workflow.graph.add_node(node_a);
-        workflow.graph.add_node(node_b);
-        workflow
-            .graph
-            .add_edge(id_a, id_b, Edge::new("output", "context"))
+        // The node is retried with the same inputs after a delay
+        let retry = orchestrator.event_store.work_items().pop().unwrap();
+        assert_eq!(retry.node_id, ai_id);
+        assert_eq!(retry.attempt, 2);
+        assert_eq!(retry.inputs, first.inputs);
+        assert!(retry.not_before.is_some());
+        assert!(orchestrator.event_store.events().iter().any(|event| matches!(
+            event,
+            ExecutionEvent::NodeRetryScheduled { attempt: 2, error, .. } if error == "attempt 1 failed"
+        )));
+        let state = orchestrator.state().unwrap();
+        assert_eq!(state.node_states[&ai_id].state, NodeExecutionState::Running);
+        assert_eq!(state.node_states[&ai_id].attempt, 2);
+        assert!(!orchestrator.is_complete());
+
+        // Replay restores the attempt
+        let replayed = RunStateBuilder::new(graph)
+            .build_from_events(orchestrator.event_store.events())
             .unwrap();
+        assert_eq!(replayed.node_states[&ai_id].attempt, 2);
+        assert!(replayed.remaining_work().executing_nodes().contains(&ai_id));
 
-        (workflow, id_a, id_b)
+        // A late result of the first attempt is ignored
+        let event_count = orchestrator.event_store.events().len();
+        orchestrator
+            .handle_result(failure(run_id, ai_id, 1, NodeErrorKind::ExecutionFailed))
+            .await
+            .unwrap();
+        assert_eq!(orchestrator.event_store.events().len(), event_count);
+
+        let output_key = store_output(&orchestrator, serde_json::json!({})).await;
+        orchestrator
+            .handle_result(WorkItemResult::Completed {
+                run_id,
+                node_id: ai_id,
+                item_index: None,
+                attempt: 2,
+                output_key,
+            })
+            .await
+            .unwrap();
+        let state = orchestrator.state().unwrap();
+        assert_eq!(state.execution_state, ExecutionState::Completed);
+        assert_eq!(state.node_states[&ai_id].error, None);
     }
 
     #[tokio::test]
-    async fn orchestrator_starts_new_run() {
-        let (workflow, id_a, _id_b) = create_simple_workflow();
-        let event_store = InMemoryEventStore::new();
-        let mut orchestrator = Orchestrator::new(workflow, event_store);
+    async fn recover_keeps_the_delay_of_a_pending_retry() {
+        let (workflow, trigger_id, ai_id) = create_retry_workflow(3);
+        let mut orchestrator = Orchestrator::new(
+            workflow.clone(),
+            InMemoryEventStore::new(),
+            InMemoryObjectStore::new(),
+        );
+        let run_id = complete_trigger(&mut orchestrator, trigger_id, serde_json::json!({})).await;
+        orchestrator
+            .handle_result(failure(run_id, ai_id, 1, NodeErrorKind::Timeout))
+            .await
+            .unwrap();
+        let retry = orchestrator.event_store.work_items().pop().unwrap();
 
-        orchestrator.initialize(None).await.unwrap();
-        orchestrator.start().await.unwrap();
+        let mut recovered = Orchestrator::new(
+            workflow,
+            orchestrator.event_store,
+            orchestrator.object_store,
+        );
+        recovered.initialize(Some(run_id)).await.unwrap();
+        recovered.recover().await.unwrap();
 
-        let events = orchestrator.event_store.events();
-        assert_eq!(events.len(), 3); // RunQueued, RunStarted, NodeStarted
+        let redispatched = recovered.event_store.work_items().pop().unwrap();
+        assert_eq!(redispatched.attempt, 2);
+        assert!(redispatched.not_before.is_some());
+        assert_eq!(redispatched, retry);
+    }
 
-        match &events[0] {
-            ExecutionEvent::RunQueued { .. } => {}
-            _ => panic!("expected RunQueued"),
-        }
-        match &events[1] {
-            ExecutionEvent::RunStarted { .. } => {}
-            _ => panic!("expected RunStarted"),
-        }
-        match &events[2] {
-            ExecutionEvent::NodeStarted { node_id, .. } => {
-                assert_eq!(*node_id, id_a);
-            }
-            _ => panic!("expected NodeStarted"),
+    #[tokio::test]
+    async fn exhausted_retries_fail_the_node() {
+        let (workflow, trigger_id, ai_id) = create_retry_workflow(2);
+        let mut orchestrator = Orchestrator::new(
+            workflow,
+            InMemoryEventStore::new(),
+            InMemoryObjectStore::new(),
+        );
+        let run_id = complete_trigger(&mut orchestrator, trigger_id, serde_json::json!({})).await;
+
+        for attempt in 1..=2 {
+            orchestrator
+                .handle_result(failure(
+                    run_id,
+                    ai_id,
+                    attempt,
+                    NodeErrorKind::ExternalService,
+                ))
+                .await
+                .unwrap();
         }
 
-        // Should have published work item for node A
-        let work_items = orchestrator.event_store.work_items();
-        assert_eq!(work_items.len(), 1);
-        assert_eq!(work_items[0].node_id, id_a);
+        let events = orchestrator.event_store.events();
+        assert!(
+            events
+                .iter()
+                .any(|event| matches!(event, ExecutionEvent::NodeFailed { attempt: 2, .. }))
+        );
+        let state = orchestrator.state().unwrap();
+        assert_eq!(state.node_states[&ai_id].state, NodeExecutionState::Failed);
+        assert_eq!(state.execution_state, ExecutionState::Failed);
     }
 
     #[tokio::test]
-    async fn orchestrator_handles_completion() {
-        let (workflow, id_a, id_b) = create_simple_workflow();
-        let event_store = InMemoryEventStore::new();
-        let mut orchestrator = Orchestrator::new(workflow, event_store);
+    async fn non_retryable_failure_fails_immediately() {
+        let (workflow, trigger_id, ai_id) = create_retry_workflow(3);
+        let mut orchestrator = Orchestrator::new(
+            workflow,
+            InMemoryEventStore::new(),
+            InMemoryObjectStore::new(),
+        );
+        let run_id = complete_trigger(&mut orchestrator, trigger_id, serde_json::json!({})).await;
+
+        orchestrator
+            .handle_result(failure(run_id, ai_id, 1, NodeErrorKind::InvalidInput))
+            .await
+            .unwrap();
+
+        assert!(
+            !orchestrator
+                .event_store
+                .events()
+                .iter()
+                .any(|event| matches!(event, ExecutionEvent::NodeRetryScheduled { .. }))
+        );
+        assert_eq!(
+            orchestrator.state().unwrap().execution_state,
+            ExecutionState::Failed
+        );
+    }
+
+    #[test]
+    fn retry_jitter_is_reproducible_and_in_range() {
+        let run_id = WorkflowRunId::new();
+        let node_id = NodeId::new();
+        let jitter = retry_jitter(run_id, node_id, None, 1);
+        assert!((0.0..1.0).contains(&jitter));
+        assert_eq!(jitter, retry_jitter(run_id, node_id, None, 1));
+
+        // The hash must not change between builds
+        let run_id = WorkflowRunId::from_ulid(ulid::Ulid::from(1_u128));
+        let node_id = NodeId::from_ulid(ulid::Ulid::from(2_u128));
+        assert_eq!(
+            retry_jitter(run_id, node_id, Some(3), 4),
+            0.056_401_781_875_400_014
+        );
+    }
 
+    #[tokio::test]
+    async fn deadline_fails_running_nodes_and_the_run() {
+        let (workflow, trigger_id, ai_id) = create_simple_workflow();
+        let graph = workflow.graph.clone();
+        let mut orchestrator = Orchestrator::new(
+            workflow.with_max_duration_ms(0),
+            InMemoryEventStore::new(),
+            InMemoryObjectStore::new(),
+        );
         orchestrator.initialize(None).await.unwrap();
         orchestrator.start().await.unwrap();
-
         let run_id = orchestrator.run_id().unwrap();
 
-        // Complete node A
-        orchestrator
-            .handle_result(WorkItemResult::Completed {
-                run_id,
-                node_id: id_a,
-                output_key: "output_a".to_string(),
-            })
-            .await
-            .unwrap();
+        assert!(orchestrator.enforce_deadline().await.unwrap());
 
-        // Node B should now be scheduled
-        let work_items = orchestrator.event_store.work_items();
-        assert_eq!(work_items.len(), 2); // A and B
-        assert_eq!(work_items[1].node_id, id_b);
+        let state = orchestrator.state().unwrap();
+        assert_eq!(state.execution_state, ExecutionState::Failed);
+        assert_eq!(
+            state.error.as_deref(),
+            Some("run exceeded its maximum duration of 0 ms")
+        );
+        assert_eq!(
+            state.node_states[&trigger_id].state,
+            NodeExecutionState::Failed
+        );
+        assert_eq!(state.node_states[&ai_id].state, NodeExecutionState::Pending);
 
-        // Complete node B
+        // Late results are ignored
+        let event_count = orchestrator.event_store.events().len();
         orchestrator
             .handle_result(WorkItemResult::Completed {
                 run_id,
-                node_id: id_b,
-                output_key: "output_b".to_string(),
+                node_id: trigger_id,
+                item_index: None,
+                attempt: 1,
+                output_key: "late".to_string(),
             })
             .await
             .unwrap();
+        assert_eq!(orchestrator.event_store.events().len(), event_count);
 
-        // Run should be complete
-        assert!(orchestrator.is_complete());
-        let events = orchestrator.event_store.events();
-        let last_event = events.last().unwrap();
-        match last_event {
-            ExecutionEvent::RunCompleted { .. } => {}
-            _ => panic!("expected RunCompleted"),
-        }
+        let replayed = RunStateBuilder::new(graph)
+            .build_from_events(orchestrator.event_store.events())
+            .unwrap();
+        assert!(replayed.deadline.is_some());
+        assert_eq!(replayed.execution_state, ExecutionState::Failed);
+        assert_eq!(
+            replayed.node_states[&trigger_id].state,
+            NodeExecutionState::Failed
+        );
     }
 
     #[tokio::test]
-    async fn orchestrator_handles_failure() {
-        let (workflow, id_a, _id_b) = create_simple_workflow();
-        let event_store = InMemoryEventStore::new();
-        let mut orchestrator = Orchestrator::new(workflow, event_store);
-
+    async fn deadline_is_not_enforced_early() {
+        let (workflow, _id_a, _id_b) = create_simple_workflow();
+        let mut orchestrator = Orchestrator::new(
+            workflow.with_max_duration_ms(60_000),
+            InMemoryEventStore::new(),
+            InMemoryObjectStore::new(),
+        );
         orchestrator.initialize(None).await.unwrap();
         orchestrator.start().await.unwrap();
 
+        assert!(!orchestrator.enforce_deadline().await.unwrap());
+        let state = orchestrator.state().unwrap();
+        assert_eq!(state.execution_state, ExecutionState::Running);
+        assert!(state.deadline.is_some());
+    }
+
+    #[tokio::test]
+    async fn cancel_stops_the_run_and_survives_replay() {
+        let (workflow, trigger_id, ai_id) = create_simple_workflow();
+        let graph = workflow.graph.clone();
+        let mut orchestrator = Orchestrator::new(
+            workflow.clone(),
+            InMemoryEventStore::new(),
+            InMemoryObjectStore::new(),
+        );
+        orchestrator.initialize(None).await.unwrap();
+        orchestrator.start().await.unwrap();
         let run_id = orchestrator.run_id().unwrap();
 
-        // Fail node A
+        orchestrator.cancel("cancelled by admin").await.unwrap();
+
+        let state = orchestrator.state().unwrap();
+        assert_eq!(state.execution_state, ExecutionState::Cancelled);
+        assert_eq!(
+            state.node_states[&trigger_id].state,
+            NodeExecutionState::Skipped
+        );
+        assert!(matches!(
+            orchestrator.event_store.events().last(),
+            Some(ExecutionEvent::RunCancelled { reason, .. }) if reason == "cancelled by admin"
+        ));
+
+        // The trigger's late result schedules nothing
+        let work_item_count = orchestrator.event_store.work_items().len();
         orchestrator
-            .handle_result(WorkItemResult::Failed {
+            .handle_result(WorkItemResult::Completed {
                 run_id,
-                node_id: id_a,
-                error: "test error".to_string(),
+                node_id: trigger_id,
+                item_index: None,
+                attempt: 1,
+                output_key: "late".to_string(),
             })
             .await
             .unwrap();
+        assert_eq!(orchestrator.event_store.work_items().len(), work_item_count);
+        assert_eq!(
+            orchestrator.state().unwrap().node_states[&ai_id].state,
+            NodeExecutionState::Pending
+        );
+        assert!(matches!(
+            orchestrator.cancel("again").await,
+            Err(OrchestratorError::RunAlreadyTerminal { .. })
+        ));
 
-        // Run should be complete (with failure)
-        assert!(orchestrator.is_complete());
-        let state = orchestrator.state().unwrap();
-        assert!(state.has_failures());
-        assert_eq!(state.execution_state, ExecutionState::Failed);
+        // A restarted orchestrator sees the cancellation
+        let replayed = RunStateBuilder::new(graph)
+            .build_from_events(orchestrator.event_store.events())
+            .unwrap();
+        assert_eq!(replayed.execution_state, ExecutionState::Cancelled);
+        let mut restarted = Orchestrator::new(
+            workflow,
+            orchestrator.event_store,
+            InMemoryObjectStore::new(),
+        );
+        assert!(matches!(
+            restarted.initialize(Some(run_id)).await,
+            Err(OrchestratorError::RunAlreadyTerminal { .. })
+        ));
     }
 
     #[tokio::test]
-    async fn orchestrator_collects_inputs() {
-        let (workflow, id_a, id_b) = create_simple_workflow();
-        let event_store = InMemoryEventStore::new();
-        let mut orchestrator = Orchestrator::new(workflow, event_store);
-
+    async fn run_locks_serialize_concurrent_changes_to_a_run() {
+        let (workflow, trigger_id, _) = create_simple_workflow();
+        let event_store = Arc::new(InMemoryEventStore::new());
+        let object_store = Arc::new(InMemoryObjectStore::new());
+        let mut orchestrator =
+            Orchestrator::new(workflow.clone(), event_store.clone(), object_store.clone());
         orchestrator.initialize(None).await.unwrap();
         orchestrator.start().await.unwrap();
-
         let run_id = orchestrator.run_id().unwrap();
+        let output_key = object_store.put(b"{}").await.unwrap();
 
-        // Complete node A
+        // A worker's result and a cancellation arrive at the same time, each
+        // handled by an orchestrator that replays the run
+        let locks = RunLocks::new();
+        let apply_result = async {
+            let _run_lock = locks.lock(run_id).await;
+            let mut orchestrator =
+                Orchestrator::new(workflow.clone(), event_store.clone(), object_store.clone());
+            match orchestrator.initialize(Some(run_id)).await {
+                Ok(()) => {}
+                Err(OrchestratorError::RunAlreadyTerminal { .. }) => return,
+                Err(e) => panic!("{e}"),
+            }
+            orchestrator
+                .handle_result(WorkItemResult::Completed {
+                    run_id,
+                    node_id: trigger_id,
+                    item_index: None,
+                    attempt: 1,
+                    output_key,
+                })
+                .await
+                .unwrap();
+        };
+        let cancel = async {
+            let _run_lock = locks.lock(run_id).await;
+            let mut orchestrator =
+                Orchestrator::new(workflow.clone(), event_store.clone(), object_store.clone());
+            orchestrator.initialize(Some(run_id)).await.unwrap();
+            orchestrator.cancel("cancelled by admin").await.unwrap();
+        };
+        tokio::join!(apply_result, cancel);
+
+        // Nothing follows the cancellation, and the run did not go on
+        let events = event_store.events();
+        assert!(matches!(
+            events.last(),
+            Some(ExecutionEvent::RunCancelled { .. })
+        ));
+        let replayed = RunStateBuilder::new(workflow.graph)
+            .build_from_events(events)
+            .unwrap();
+        assert_eq!(replayed.execution_state, ExecutionState::Cancelled);
+    }
+
+    #[tokio::test]
+    async fn recover_redispatches_only_unfinished_executions() {
+        let mut workflow = Workflow::new("Parallel Workflow");
+        let trigger_id = workflow.graph.add_node(create_trigger_node("Trigger"));
+        let first_id = add_transform_after(&mut workflow, trigger_id, "output", "First");
+        let second_id = add_transform_after(&mut workflow, trigger_id, "output", "Second");
+
+        let mut orchestrator = Orchestrator::new(
+            workflow.clone(),
+            InMemoryEventStore::new(),
+            InMemoryObjectStore::new(),
+        );
+        let run_id = complete_trigger(&mut orchestrator, trigger_id, serde_json::json!({})).await;
+        complete_node(&mut orchestrator, first_id, serde_json::json!(1)).await;
+        let original = orchestrator
+            .event_store
+            .work_items()
+            .into_iter()
+            .find(|w| w.node_id == second_id)
+            .unwrap();
+
+        // The orchestrator stops; a new one picks up the run from its events
+        let dispatched = orchestrator.event_store.work_items().len();
+        let mut recovered = Orchestrator::new(
+            workflow,
+            orchestrator.event_store,
+            orchestrator.object_store,
+        );
+        recovered.initialize(Some(run_id)).await.unwrap();
+        recovered.recover().await.unwrap();
+
+        let work_items = recovered.event_store.work_items();
+        assert_eq!(work_items.len(), dispatched + 1);
+        let redispatched = work_items.last().unwrap();
+        assert_eq!(redispatched, &original);
+        assert_eq!(redispatched.dispatch_id(), original.dispatch_id());
+
+        complete_node(&mut recovered, second_id, serde_json::json!(2)).await;
+        assert_eq!(
+            recovered.state().unwrap().output,
+            Some(serde_json::json!({"First": 1, "Second": 2}))
+        );
+    }
+
+    #[tokio::test]
+    async fn duplicate_and_foreign_results_are_ignored() {
+        let (workflow, trigger_id, ai_id) = create_simple_workflow();
+        let mut orchestrator = Orchestrator::new(
+            workflow,
+            InMemoryEventStore::new(),
+            InMemoryObjectStore::new(),
+        );
+        let run_id = complete_trigger(&mut orchestrator, trigger_id, serde_json::json!({})).await;
+
+        // The trigger's result is delivered a second time
+        let output_key = store_output(&orchestrator, serde_json::json!({})).await;
         orchestrator
             .handle_result(WorkItemResult::Completed {
                 run_id,
-                node_id: id_a,
-                output_key: "output_key_123".to_string(),
+                node_id: trigger_id,
+                item_index: None,
+                attempt: 1,
+                output_key,
             })
             .await
             .unwrap();
 
-        // B's work item should have A's output as input
-        let work_items = orchestrator.event_store.work_items();
-        let b_work_item = work_items.iter().find(|w| w.node_id == id_b).unwrap();
+        let completions = orchestrator
+            .event_store
+            .events()
+            .iter()
+            .filter(|event| {
+                matches!(event, ExecutionEvent::NodeCompleted { node_id, .. } if *node_id == trigger_id)
+            })
+            .count();
+        assert_eq!(completions, 1);
+        let dispatches = orchestrator
+            .event_store
+            .work_items()
+            .iter()
+            .filter(|w| w.node_id == ai_id)
+            .count();
+        assert_eq!(dispatches, 1);
+
+        // A result naming another run is ignored
+        let event_count = orchestrator.event_store.events().len();
+        let output_key = store_output(&orchestrator, serde_json::json!({})).await;
+        orchestrator
+            .handle_result(WorkItemResult::Completed {
+                run_id: WorkflowRunId::new(),
+                node_id: ai_id,
+                item_index: None,
+                attempt: 1,
+                output_key,
+            })
+            .await
+            .unwrap();
+        assert_eq!(orchestrator.event_store.events().len(), event_count);
         assert_eq!(
-            b_work_item.inputs.get("context"),
-            Some(&"output_key_123".to_string())
+            orchestrator.state().unwrap().node_states[&ai_id].state,
+            NodeExecutionState::Running
+        );
+    }
+
+    #[tokio::test]
+    async fn recover_starts_a_queued_run() {
+        let (workflow, trigger_id, _) = create_simple_workflow();
+        let mut orchestrator = Orchestrator::new(
+            workflow.clone(),
+            InMemoryEventStore::new(),
+            InMemoryObjectStore::new(),
+        );
+        orchestrator.initialize(None).await.unwrap();
+        let run_id = orchestrator.run_id().unwrap();
+
+        let mut recovered = Orchestrator::new(
+            workflow,
+            orchestrator.event_store,
+            InMemoryObjectStore::new(),
         );
+        recovered.initialize(Some(run_id)).await.unwrap();
+        recovered.recover().await.unwrap();
+
+        assert_eq!(
+            recovered.state().unwrap().execution_state,
+            ExecutionState::Running
+        );
+        let work_items = recovered.event_store.work_items();
+        assert_eq!(work_items.len(), 1);
+        assert_eq!(work_items[0].node_id, trigger_id);
+    }
+
+    #[test]
+    fn dispatch_id_identifies_the_attempt() {
+        let work_item = WorkItem {
+            run_id: WorkflowRunId::new(),
+            node_id: NodeId::new(),
+            capability: Some(Capability::for_category(&NodeCategory::Transform)),
+            item_index: None,
+            inputs: HashMap::new(),
+            attempt: 1,
+            not_before: None,
+            dry_run: None,
+        };
+        let retry = WorkItem {
+            attempt: 2,
+            ..work_item.clone()
+        };
+        let item = WorkItem {
+            item_index: Some(0),
+            ..work_item.clone()
+        };
+        assert_eq!(work_item.dispatch_id(), work_item.clone().dispatch_id());
+        assert_ne!(work_item.dispatch_id(), retry.dispatch_id());
+        assert_ne!(work_item.dispatch_id(), item.dispatch_id());
+
+        // A result names the attempt that produced it
+        let result = WorkItemResult::Failed {
+            run_id: item.run_id,
+            node_id: item.node_id,
+            item_index: item.item_index,
+            attempt: item.attempt,
+            error: "failed".to_string(),
+            error_kind: NodeErrorKind::ExecutionFailed,
+        };
+        assert_eq!(result.dispatch_id(), item.dispatch_id());
+        assert_eq!(result.run_id(), item.run_id);
     }
 }
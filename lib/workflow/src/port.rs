@@ -104,34 +104,300 @@ impl PortSchema {
 
     /// Checks if this schema is compatible with another schema.
     ///
-    /// For now, this is a simplified check. A full implementation would
-    /// perform proper JSON Schema compatibility analysis.
+    /// See [`PortSchema::check_compatible_with`].
     #[must_use]
     pub fn is_compatible_with(&self, other: &Self) -> bool {
-        // Empty schema (any) is compatible with everything
-        if self.schema == serde_json::json!({}) || other.schema == serde_json::json!({}) {
-            return true;
-        }
+        self.check_compatible_with(other).is_ok()
+    }
 
+    /// Checks that every value this (output) schema describes is accepted
+    /// by the other (input) schema.
+    ///
+    /// Types, enums and consts, required properties, nested object
+    /// properties and array items are compared structurally. A schema
+    /// without constraints (like [`PortSchema::any`]) on either side is
+    /// compatible with everything; values from unconstrained outputs are
+    /// checked when they are produced instead (see [`PortSchema::validate`]).
+    ///
+    /// # Errors
+    ///
+    /// Returns the path to the first part of the schemas that does not
+    /// match, and why.
+    pub fn check_compatible_with(&self, other: &Self) -> Result<(), SchemaMismatch> {
         // Model references must match on both sides
+        if !is_unconstrained(&self.schema)
+            && !is_unconstrained(&other.schema)
+            && self.is_model_reference() != other.is_model_reference()
+        {
+            return Err(SchemaMismatch::new(
+                "$",
+                if self.is_model_reference() {
+                    "a model reference is not accepted here"
+                } else {
+                    "a model reference is expected"
+                },
+            ));
+        }
         if self.is_model_reference() && other.is_model_reference() {
-            return true;
+            return Ok(());
+        }
+
+        check_subset(&self.schema, &other.schema, "$")
+    }
+
+    /// Validates a value against this schema.
+    ///
+    /// Covers `type`, `enum`, `const`, `required`, `properties`,
+    /// `additionalProperties: false` and `items`. Other keywords are not
+    /// enforced.
+    ///
+    /// # Errors
+    ///
+    /// Returns the path to the first part of the value that does not match,
+    /// and why.
+    pub fn validate(&self, value: &JsonValue) -> Result<(), SchemaMismatch> {
+        validate_value(&self.schema, value, "$")
+    }
+}
+
+/// Where and why a value or schema does not match a schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaMismatch {
+    /// Path to the mismatch, e.g. `$.items[2].name` (`[*]` for array items
+    /// when comparing schemas).
+    pub path: String,
+    /// What does not match.
+    pub reason: String,
+}
+
+impl SchemaMismatch {
+    fn new(path: impl Into<String>, reason: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            reason: reason.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for SchemaMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path, self.reason)
+    }
+}
+
+impl std::error::Error for SchemaMismatch {}
+
+/// Keywords that restrict which values a schema accepts.
+const CONSTRAINTS: &[&str] = &[
+    "type",
+    "enum",
+    "const",
+    "required",
+    "properties",
+    "additionalProperties",
+    "items",
+];
+
+/// Returns true if a schema accepts any value.
+fn is_unconstrained(schema: &JsonValue) -> bool {
+    schema.as_object().is_none_or(|schema| {
+        !CONSTRAINTS
+            .iter()
+            .any(|keyword| schema.contains_key(*keyword))
+    })
+}
+
+/// The types a schema allows, if it restricts them.
+fn types(schema: &JsonValue) -> Option<Vec<&str>> {
+    match schema.get("type")? {
+        JsonValue::String(name) => Some(vec![name.as_str()]),
+        JsonValue::Array(names) => Some(names.iter().filter_map(JsonValue::as_str).collect()),
+        _ => None,
+    }
+}
+
+/// The values a schema allows, if it lists them with `enum` or `const`.
+fn allowed_values(schema: &JsonValue) -> Option<Vec<&JsonValue>> {
+    if let Some(value) = schema.get("const") {
+        return Some(vec![value]);
+    }
+    schema
+        .get("enum")
+        .and_then(JsonValue::as_array)
+        .map(|values| values.iter().collect())
+}
+
+fn properties(schema: &JsonValue) -> Option<&serde_json::Map<String, JsonValue>> {
+    schema.get("properties").and_then(JsonValue::as_object)
+}
+
+fn required(schema: &JsonValue) -> Vec<&str> {
+    schema
+        .get("required")
+        .and_then(JsonValue::as_array)
+        .map(|names| names.iter().filter_map(JsonValue::as_str).collect())
+        .unwrap_or_default()
+}
+
+fn type_list(types: &[&str]) -> String {
+    types.join(" or ")
+}
+
+/// Checks that every value `source` describes is accepted by `target`.
+fn check_subset(source: &JsonValue, target: &JsonValue, path: &str) -> Result<(), SchemaMismatch> {
+    if is_unconstrained(source) || is_unconstrained(target) {
+        return Ok(());
+    }
+
+    if let (Some(source_types), Some(target_types)) = (types(source), types(target)) {
+        let accepted = |name: &str| {
+            target_types.contains(&name) || (name == "integer" && target_types.contains(&"number"))
+        };
+        if !source_types.iter().all(|name| accepted(name)) {
+            return Err(SchemaMismatch::new(
+                path,
+                format!(
+                    "produces {} but {} is expected",
+                    type_list(&source_types),
+                    type_list(&target_types)
+                ),
+            ));
         }
-        if self.is_model_reference() != other.is_model_reference() {
-            return false;
+    }
+
+    if let Some(target_values) = allowed_values(target) {
+        let Some(source_values) = allowed_values(source) else {
+            return Err(SchemaMismatch::new(
+                path,
+                format!("produces any value but only {target_values:?} are accepted"),
+            ));
+        };
+        if let Some(value) = source_values.iter().find(|v| !target_values.contains(v)) {
+            return Err(SchemaMismatch::new(
+                path,
+                format!("produces {value} but only {target_values:?} are accepted"),
+            ));
         }
+    }
 
-        // Simple type equality check for basic types
-        if let (Some(self_type), Some(other_type)) =
-            (self.schema.get("type"), other.schema.get("type"))
+    let source_required = required(source);
+    if let Some(name) = required(target)
+        .into_iter()
+        .find(|name| !source_required.contains(name))
+    {
+        return Err(SchemaMismatch::new(
+            path,
+            format!("required property '{name}' is not always produced"),
+        ));
+    }
+
+    let source_properties = properties(source);
+    if let Some(target_properties) = properties(target) {
+        for (name, target_property) in target_properties {
+            if let Some(source_property) = source_properties.and_then(|p| p.get(name)) {
+                check_subset(source_property, target_property, &format!("{path}.{name}"))?;
+            }
+        }
+    }
+    if target.get("additionalProperties") == Some(&JsonValue::Bool(false)) {
+        let known = properties(target);
+        if let Some(name) = source_properties
+            .into_iter()
+            .flat_map(|p| p.keys())
+            .find(|name| !known.is_some_and(|known| known.contains_key(*name)))
         {
-            return self_type == other_type;
+            return Err(SchemaMismatch::new(
+                path,
+                format!("property '{name}' is not accepted"),
+            ));
+        }
+    }
+
+    if let (Some(source_items), Some(target_items)) = (source.get("items"), target.get("items")) {
+        check_subset(source_items, target_items, &format!("{path}[*]"))?;
+    }
+
+    Ok(())
+}
+
+/// The JSON Schema type name of a value.
+fn type_of(value: &JsonValue) -> &'static str {
+    match value {
+        JsonValue::Null => "null",
+        JsonValue::Bool(_) => "boolean",
+        JsonValue::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        JsonValue::Number(_) => "number",
+        JsonValue::String(_) => "string",
+        JsonValue::Array(_) => "array",
+        JsonValue::Object(_) => "object",
+    }
+}
+
+/// Checks a value against a schema.
+fn validate_value(schema: &JsonValue, value: &JsonValue, path: &str) -> Result<(), SchemaMismatch> {
+    if let Some(expected) = types(schema) {
+        let actual = type_of(value);
+        let matches = |name: &str| {
+            name == actual
+                || (name == "number" && actual == "integer")
+                || (name == "integer" && value.as_f64().is_some_and(|n| n.fract() == 0.0))
+        };
+        if !expected.iter().any(|name| matches(name)) {
+            return Err(SchemaMismatch::new(
+                path,
+                format!("expected {}, found {actual}", type_list(&expected)),
+            ));
         }
+    }
+
+    if let Some(allowed) = allowed_values(schema)
+        && !allowed.contains(&value)
+    {
+        return Err(SchemaMismatch::new(
+            path,
+            format!("{value} is not one of {allowed:?}"),
+        ));
+    }
 
-        // For complex schemas, assume compatible for now
-        // A full implementation would use a proper JSON Schema validator
-        true
+    match value {
+        JsonValue::Object(object) => {
+            if let Some(name) = required(schema)
+                .into_iter()
+                .find(|name| !object.contains_key(*name))
+            {
+                return Err(SchemaMismatch::new(
+                    path,
+                    format!("missing required property '{name}'"),
+                ));
+            }
+            let known = properties(schema);
+            for (name, property) in object {
+                let property_path = format!("{path}.{name}");
+                match known.and_then(|known| known.get(name)) {
+                    Some(property_schema) => {
+                        validate_value(property_schema, property, &property_path)?;
+                    }
+                    None if schema.get("additionalProperties") == Some(&JsonValue::Bool(false)) => {
+                        return Err(SchemaMismatch::new(
+                            property_path,
+                            "property is not allowed",
+                        ));
+                    }
+                    None => {}
+                }
+            }
+        }
+        JsonValue::Array(items) => {
+            if let Some(item_schema) = schema.get("items") {
+                for (index, item) in items.iter().enumerate() {
+                    validate_value(item_schema, item, &format!("{path}[{index}]"))?;
+                }
+            }
+        }
+        _ => {}
     }
+
+    Ok(())
 }
 
 impl Default for PortSchema {
@@ -224,6 +490,150 @@ mod tests {
         assert!(!string.is_compatible_with(&number));
     }
 
+    #[test]
+    fn integer_output_feeds_number_input() {
+        let integer = PortSchema::from_json(serde_json::json!({ "type": "integer" }));
+        assert!(integer.is_compatible_with(&PortSchema::number()));
+        assert!(!PortSchema::number().is_compatible_with(&integer));
+    }
+
+    #[test]
+    fn required_properties_must_always_be_produced() {
+        let target = PortSchema::from_json(serde_json::json!({
+            "type": "object",
+            "properties": { "subject": { "type": "string" } },
+            "required": ["subject"]
+        }));
+        let optional = PortSchema::from_json(serde_json::json!({
+            "type": "object",
+            "properties": { "subject": { "type": "string" } }
+        }));
+        let required = PortSchema::from_json(serde_json::json!({
+            "type": "object",
+            "properties": { "subject": { "type": "string" }, "body": { "type": "string" } },
+            "required": ["subject", "body"]
+        }));
+
+        let mismatch = optional.check_compatible_with(&target).unwrap_err();
+        assert_eq!(mismatch.path, "$");
+        assert!(mismatch.reason.contains("'subject'"));
+        assert!(required.is_compatible_with(&target));
+    }
+
+    #[test]
+    fn nested_properties_and_array_items_are_compared() {
+        let source = PortSchema::from_json(serde_json::json!({
+            "type": "object",
+            "properties": {
+                "emails": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": { "size": { "type": "string" } }
+                    }
+                }
+            }
+        }));
+        let target = PortSchema::from_json(serde_json::json!({
+            "type": "object",
+            "properties": {
+                "emails": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": { "size": { "type": "number" } }
+                    }
+                }
+            }
+        }));
+
+        let mismatch = source.check_compatible_with(&target).unwrap_err();
+        assert_eq!(mismatch.path, "$.emails[*].size");
+        assert_eq!(mismatch.reason, "produces string but number is expected");
+    }
+
+    #[test]
+    fn enums_must_be_a_subset() {
+        let target = PortSchema::from_json(serde_json::json!({
+            "type": "string",
+            "enum": ["low", "medium", "high"]
+        }));
+        let subset = PortSchema::from_json(serde_json::json!({
+            "type": "string",
+            "enum": ["low", "high"]
+        }));
+        let wider = PortSchema::from_json(serde_json::json!({
+            "type": "string",
+            "enum": ["low", "urgent"]
+        }));
+
+        assert!(subset.is_compatible_with(&target));
+        assert!(!wider.is_compatible_with(&target));
+        assert!(!PortSchema::string().is_compatible_with(&target));
+    }
+
+    #[test]
+    fn closed_objects_reject_extra_properties() {
+        let target = PortSchema::from_json(serde_json::json!({
+            "type": "object",
+            "properties": { "id": { "type": "string" } },
+            "additionalProperties": false
+        }));
+        let source = PortSchema::from_json(serde_json::json!({
+            "type": "object",
+            "properties": { "id": { "type": "string" }, "extra": { "type": "string" } }
+        }));
+
+        let mismatch = source.check_compatible_with(&target).unwrap_err();
+        assert!(mismatch.reason.contains("'extra'"));
+    }
+
+    #[test]
+    fn validate_reports_the_path_to_the_mismatch() {
+        let schema = PortSchema::from_json(serde_json::json!({
+            "type": "object",
+            "properties": {
+                "items": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": { "name": { "type": "string" } },
+                        "required": ["name"]
+                    }
+                }
+            }
+        }));
+
+        let value = serde_json::json!({ "items": [{ "name": "a" }, { "name": 2 }] });
+        let mismatch = schema.validate(&value).unwrap_err();
+        assert_eq!(
+            mismatch.to_string(),
+            "$.items[1].name: expected string, found integer"
+        );
+
+        let value = serde_json::json!({ "items": [{}] });
+        let mismatch = schema.validate(&value).unwrap_err();
+        assert_eq!(mismatch.path, "$.items[0]");
+        assert_eq!(mismatch.reason, "missing required property 'name'");
+
+        let value = serde_json::json!({ "items": [{ "name": "a" }], "other": true });
+        assert!(schema.validate(&value).is_ok());
+    }
+
+    #[test]
+    fn validate_checks_enums_and_number_types() {
+        let level = PortSchema::from_json(serde_json::json!({ "enum": ["low", "high"] }));
+        assert!(level.validate(&serde_json::json!("low")).is_ok());
+        assert!(level.validate(&serde_json::json!("medium")).is_err());
+
+        let integer = PortSchema::from_json(serde_json::json!({ "type": "integer" }));
+        assert!(integer.validate(&serde_json::json!(3)).is_ok());
+        assert!(integer.validate(&serde_json::json!(3.0)).is_ok());
+        assert!(integer.validate(&serde_json::json!(3.5)).is_err());
+        assert!(PortSchema::number().validate(&serde_json::json!(3)).is_ok());
+        assert!(PortSchema::any().validate(&serde_json::json!(null)).is_ok());
+    }
+
     #[test]
     fn input_port_required() {
         let port = InputPort::required("data", PortSchema::string());
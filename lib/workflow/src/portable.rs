@@ -0,0 +1,552 @@
+//! Portable workflow documents, for moving workflows between instances.
+//!
+//! A [`WorkflowDocument`] holds what it takes to recreate a workflow on
+//! another instance: its metadata, graph, memory configuration, and
+//! optionally its memory contents. It is written as a human-readable YAML
+//! or JSON document wrapped in an [`Envelope`], so documents exported by an
+//! older version are upcast on import like any other payload.
+//!
+//! Integration accounts only exist on the instance that exported the
+//! document. Importing lists the nodes that refer to them as
+//! [`ExternalReference`]s, which the importer maps onto their own accounts
+//! with an [`ImportMapping`]. The imported workflow gets new node IDs, so a
+//! document can be imported any number of times.
+
+use crate::concurrency::ConcurrencyPolicy;
+use crate::definition::{Workflow, WorkflowMemoryConfig};
+use crate::envelope::{Envelope, EnvelopeError, RawEnvelope};
+use crate::error::GraphError;
+use crate::graph::WorkflowGraph;
+use crate::node::{ConfigurationNodeConfig, NodeConfig, NodeId, TriggerNodeConfig};
+use crate::retention::RetentionPolicy;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+
+/// Serialization format of a workflow document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DocumentFormat {
+    /// YAML.
+    Yaml,
+    /// Pretty-printed JSON.
+    Json,
+}
+
+impl DocumentFormat {
+    /// Returns the file extension for documents in this format.
+    #[must_use]
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Yaml => "yaml",
+            Self::Json => "json",
+        }
+    }
+
+    /// Returns the media type of documents in this format.
+    #[must_use]
+    pub fn media_type(self) -> &'static str {
+        match self {
+            Self::Yaml => "application/yaml",
+            Self::Json => "application/json",
+        }
+    }
+}
+
+/// Errors from exporting or importing workflow documents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PortableError {
+    /// The document could not be written.
+    Encode { message: String },
+    /// The text is not a YAML or JSON envelope.
+    Parse { message: String },
+    /// The envelope's payload is not a workflow document of a supported
+    /// version.
+    Envelope(EnvelopeError),
+    /// The document's graph is invalid.
+    InvalidGraph(GraphError),
+    /// An integration account the document refers to is not mapped.
+    UnmappedIntegration { integration_id: String },
+    /// A model configuration node is not mapped.
+    UnmappedModel { node_id: NodeId },
+}
+
+impl std::fmt::Display for PortableError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Encode { message } => write!(f, "failed to write workflow document: {message}"),
+            Self::Parse { message } => write!(f, "malformed workflow document: {message}"),
+            Self::Envelope(e) => write!(f, "unsupported workflow document: {e}"),
+            Self::InvalidGraph(e) => write!(f, "workflow document has an invalid graph: {e}"),
+            Self::UnmappedIntegration { integration_id } => {
+                write!(f, "integration account {integration_id} is not mapped")
+            }
+            Self::UnmappedModel { node_id } => {
+                write!(f, "model configuration node {node_id} is not mapped")
+            }
+        }
+    }
+}
+
+impl std::error::Error for PortableError {}
+
+impl From<EnvelopeError> for PortableError {
+    fn from(e: EnvelopeError) -> Self {
+        Self::Envelope(e)
+    }
+}
+
+impl From<GraphError> for PortableError {
+    fn from(e: GraphError) -> Self {
+        Self::InvalidGraph(e)
+    }
+}
+
+/// A workflow in portable form.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkflowDocument {
+    /// The workflow's name.
+    pub name: String,
+    /// The workflow's description.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// The workflow's tags.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// The workflow graph, with the node IDs of the exporting instance.
+    pub graph: WorkflowGraph,
+    /// Memory configuration.
+    #[serde(default)]
+    pub memory: WorkflowMemoryConfig,
+    /// Maximum duration of a run, in milliseconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_duration_ms: Option<u64>,
+    /// How many runs may be in progress at once.
+    #[serde(default, skip_serializing_if = "ConcurrencyPolicy::is_unlimited")]
+    pub concurrency: ConcurrencyPolicy,
+    /// How long the outputs of the workflow's runs are kept.
+    #[serde(default, skip_serializing_if = "RetentionPolicy::is_unlimited")]
+    pub retention: RetentionPolicy,
+    /// The workflow's memory contents, if exported with the workflow.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_contents: Option<String>,
+}
+
+/// A node that refers to something that only exists on the exporting
+/// instance.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ExternalReference {
+    /// An integration trigger or integration node using an account.
+    IntegrationAccount {
+        node_id: NodeId,
+        node_name: String,
+        integration_id: String,
+    },
+    /// A model configuration node selecting a provider account and model.
+    ModelConfig {
+        node_id: NodeId,
+        node_name: String,
+        integration_id: String,
+        model_id: String,
+    },
+}
+
+/// The model a model configuration node uses after import.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelMapping {
+    /// The importer's integration account for the provider.
+    pub integration_id: String,
+    /// The model ID.
+    pub model_id: String,
+}
+
+/// How the external references of a document map onto the importing
+/// instance.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportMapping {
+    /// Exported integration account ID -> the importer's account ID.
+    #[serde(default)]
+    pub integrations: HashMap<String, String>,
+    /// Exported model configuration node -> the model it uses.
+    #[serde(default)]
+    pub models: HashMap<NodeId, ModelMapping>,
+}
+
+impl ImportMapping {
+    /// Creates an empty mapping.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maps an exported integration account onto one of the importer's.
+    #[must_use]
+    pub fn with_integration(
+        mut self,
+        exported_id: impl Into<String>,
+        integration_id: impl Into<String>,
+    ) -> Self {
+        self.integrations
+            .insert(exported_id.into(), integration_id.into());
+        self
+    }
+
+    /// Sets the model an exported model configuration node uses.
+    #[must_use]
+    pub fn with_model(
+        mut self,
+        node_id: NodeId,
+        integration_id: impl Into<String>,
+        model_id: impl Into<String>,
+    ) -> Self {
+        self.models.insert(
+            node_id,
+            ModelMapping {
+                integration_id: integration_id.into(),
+                model_id: model_id.into(),
+            },
+        );
+        self
+    }
+
+    /// Returns the importer's account for an exported one.
+    fn integration(&self, exported_id: &str) -> Result<String, PortableError> {
+        self.integrations.get(exported_id).cloned().ok_or_else(|| {
+            PortableError::UnmappedIntegration {
+                integration_id: exported_id.to_string(),
+            }
+        })
+    }
+}
+
+impl WorkflowDocument {
+    /// Creates a document for a workflow, without its memory contents.
+    #[must_use]
+    pub fn from_workflow(workflow: &Workflow) -> Self {
+        Self {
+            name: workflow.metadata.name.clone(),
+            description: workflow.metadata.description.clone(),
+            tags: workflow.metadata.tags.clone(),
+            graph: workflow.graph.clone(),
+            memory: workflow.memory.clone(),
+            max_duration_ms: workflow.max_duration_ms,
+            concurrency: workflow.concurrency,
+            retention: workflow.retention,
+            memory_contents: None,
+        }
+    }
+
+    /// Includes the workflow's memory contents.
+    #[must_use]
+    pub fn with_memory_contents(mut self, contents: impl Into<String>) -> Self {
+        self.memory_contents = Some(contents.into());
+        self
+    }
+
+    /// Writes the document, wrapped in an envelope.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if serialization fails.
+    pub fn encode(&self, format: DocumentFormat) -> Result<String, PortableError> {
+        let envelope = Envelope::new(self);
+        let encoded = match format {
+            DocumentFormat::Yaml => serde_yaml_ng::to_string(&envelope).map_err(|e| e.to_string()),
+            DocumentFormat::Json => {
+                serde_json::to_string_pretty(&envelope).map_err(|e| e.to_string())
+            }
+        };
+        encoded.map_err(|message| PortableError::Encode { message })
+    }
+
+    /// Reads a YAML or JSON document and validates its graph.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the text is not an envelope, its payload cannot
+    /// be upcast to a workflow document, or the graph is invalid.
+    pub fn decode(text: &str) -> Result<Self, PortableError> {
+        // JSON is read as YAML too, but its own parser reports errors in
+        // JSON terms
+        let raw: RawEnvelope = if text.trim_start().starts_with('{') {
+            serde_json::from_str(text).map_err(|e| e.to_string())
+        } else {
+            serde_yaml_ng::from_str(text).map_err(|e| e.to_string())
+        }
+        .map_err(|message| PortableError::Parse { message })?;
+
+        let mut document = raw.deserialize_payload::<Self>()?.into_payload();
+        document.graph.rebuild_index_map();
+        document.graph.validate()?;
+        Ok(document)
+    }
+
+    /// Lists the nodes that refer to integration accounts or select models
+    /// of the exporting instance, in graph order.
+    #[must_use]
+    pub fn references(&self) -> Vec<ExternalReference> {
+        self.graph
+            .nodes()
+            .filter_map(|node| {
+                let integration_account = |integration_id: &str| {
+                    Some(ExternalReference::IntegrationAccount {
+                        node_id: node.id,
+                        node_name: node.name.clone(),
+                        integration_id: integration_id.to_string(),
+                    })
+                };
+                match &node.config {
+                    NodeConfig::Trigger(TriggerNodeConfig::IntegrationEvent {
+                        integration_id,
+                        ..
+                    }) => integration_account(integration_id),
+                    NodeConfig::Integration(config) => config
+                        .parameters
+                        .get("integration_id")
+                        .and_then(JsonValue::as_str)
+                        .and_then(integration_account),
+                    NodeConfig::Configuration(ConfigurationNodeConfig::OpenAiModel {
+                        integration_id,
+                        model_id,
+                    }) => Some(ExternalReference::ModelConfig {
+                        node_id: node.id,
+                        node_name: node.name.clone(),
+                        integration_id: integration_id.clone(),
+                        model_id: model_id.clone(),
+                    }),
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+
+    /// Creates the workflow the document describes, with its external
+    /// references mapped onto the importing instance.
+    ///
+    /// The workflow gets a new ID, its nodes get new IDs, and it starts
+    /// disabled so it does not run on its triggers before it is reviewed.
+    /// The memory contents are not part of the workflow; the caller stores
+    /// them.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a reference is not mapped.
+    pub fn import(&self, mapping: &ImportMapping) -> Result<Workflow, PortableError> {
+        let mut graph = self.graph.clone();
+        graph.rebuild_index_map();
+        let node_ids: Vec<NodeId> = graph.nodes().map(|node| node.id).collect();
+        for node_id in node_ids {
+            let Some(node) = graph.get_node_mut(node_id) else {
+                continue;
+            };
+            match &mut node.config {
+                NodeConfig::Trigger(TriggerNodeConfig::IntegrationEvent {
+                    integration_id, ..
+                }) => *integration_id = mapping.integration(integration_id)?,
+                NodeConfig::Integration(config) => {
+                    if let Some(id) = config
+                        .parameters
+                        .get_mut("integration_id")
+                        .filter(|id| id.is_string())
+                    {
+                        let exported_id = id.as_str().unwrap_or_default();
+                        *id = JsonValue::String(mapping.integration(exported_id)?);
+                    }
+                }
+                NodeConfig::Configuration(ConfigurationNodeConfig::OpenAiModel {
+                    integration_id,
+                    model_id,
+                }) => {
+                    let model = mapping
+                        .models
+                        .get(&node_id)
+                        .ok_or(PortableError::UnmappedModel { node_id })?;
+                    integration_id.clone_from(&model.integration_id);
+                    model_id.clone_from(&model.model_id);
+                }
+                _ => {}
+            }
+        }
+
+        let (graph, _) = graph.with_new_node_ids();
+        let mut workflow = Workflow::new(self.name.clone());
+        workflow.metadata.description = self.description.clone();
+        workflow.metadata.tags = self.tags.clone();
+        workflow.metadata.enabled = false;
+        workflow.graph = graph;
+        workflow.memory = self.memory.clone();
+        workflow.max_duration_ms = self.max_duration_ms;
+        workflow.concurrency = self.concurrency;
+        workflow.retention = self.retention;
+        Ok(workflow)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::edge::Edge;
+    use crate::envelope::CURRENT_VERSION;
+    use crate::node::{AiLayerNodeConfig, IntegrationNodeConfig, Node};
+
+    /// Trigger -> Fetch -> Summarize, with a model configuration node.
+    fn create_workflow() -> (Workflow, NodeId, NodeId) {
+        let mut workflow = Workflow::new("Morning Digest");
+        workflow.metadata.description = Some("Summarizes new mail".to_string());
+        workflow.metadata.tags = vec!["mail".to_string()];
+        workflow.max_duration_ms = Some(60_000);
+
+        let trigger_id = workflow.graph.add_node(Node::new(
+            "New Mail",
+            NodeConfig::Trigger(TriggerNodeConfig::IntegrationEvent {
+                integration_id: "int_mail".to_string(),
+                event_type: "message_received".to_string(),
+            }),
+        ));
+        let fetch_id = workflow.graph.add_node(Node::new(
+            "Fetch",
+            NodeConfig::Integration(IntegrationNodeConfig {
+                integration_type: "email".to_string(),
+                operation: "fetch".to_string(),
+                parameters: serde_json::json!({"integration_id": "int_mail"}),
+            }),
+        ));
+        let model_id = workflow.graph.add_node(Node::new(
+            "Model",
+            NodeConfig::Configuration(ConfigurationNodeConfig::OpenAiModel {
+                integration_id: "int_openai".to_string(),
+                model_id: "gpt-4".to_string(),
+            }),
+        ));
+        let summarize_id = workflow.graph.add_node(Node::new(
+            "Summarize",
+            NodeConfig::AiLayer(AiLayerNodeConfig::LlmCall {
+                prompt: "Summarize".to_string(),
+                output_schema: None,
+            }),
+        ));
+        workflow
+            .graph
+            .add_edge(trigger_id, fetch_id, Edge::new("output", "input"))
+            .unwrap();
+        workflow
+            .graph
+            .add_edge(fetch_id, summarize_id, Edge::new("output", "input"))
+            .unwrap();
+        workflow
+            .graph
+            .add_edge(model_id, summarize_id, Edge::new("model", "model"))
+            .unwrap();
+        (workflow, fetch_id, model_id)
+    }
+
+    #[test]
+    fn documents_roundtrip_through_yaml_and_json() {
+        let (workflow, _, _) = create_workflow();
+        let document = WorkflowDocument::from_workflow(&workflow).with_memory_contents("notes");
+
+        for format in [DocumentFormat::Yaml, DocumentFormat::Json] {
+            let text = document.encode(format).unwrap();
+            let decoded = WorkflowDocument::decode(&text).unwrap();
+
+            assert_eq!(decoded.name, "Morning Digest");
+            assert_eq!(decoded.tags, vec!["mail".to_string()]);
+            assert_eq!(decoded.max_duration_ms, Some(60_000));
+            assert_eq!(decoded.memory_contents.as_deref(), Some("notes"));
+            assert_eq!(decoded.graph.node_count(), 4);
+            assert_eq!(decoded.graph.edge_count(), 3);
+        }
+
+        // The document is an envelope
+        let text = document.encode(DocumentFormat::Json).unwrap();
+        let json: JsonValue = serde_json::from_str(&text).unwrap();
+        assert_eq!(json["version"], CURRENT_VERSION);
+        assert_eq!(json["payload"]["name"], "Morning Digest");
+    }
+
+    #[test]
+    fn decoding_rejects_invalid_documents() {
+        assert!(matches!(
+            WorkflowDocument::decode("version: [unclosed"),
+            Err(PortableError::Parse { .. })
+        ));
+        assert!(matches!(
+            WorkflowDocument::decode(r#"{"version": 99, "payload": {}}"#),
+            Err(PortableError::Envelope(
+                EnvelopeError::UnsupportedVersion { .. }
+            ))
+        ));
+
+        // A required input without an edge fails validation
+        let mut workflow = Workflow::new("Broken");
+        workflow.graph.add_node(Node::new(
+            "Summarize",
+            NodeConfig::AiLayer(AiLayerNodeConfig::LlmCall {
+                prompt: "Summarize".to_string(),
+                output_schema: None,
+            }),
+        ));
+        let text = WorkflowDocument::from_workflow(&workflow)
+            .encode(DocumentFormat::Yaml)
+            .unwrap();
+        assert!(matches!(
+            WorkflowDocument::decode(&text),
+            Err(PortableError::InvalidGraph(_))
+        ));
+    }
+
+    #[test]
+    fn import_maps_references_and_regenerates_node_ids() {
+        let (workflow, fetch_id, model_id) = create_workflow();
+        let document = WorkflowDocument::from_workflow(&workflow);
+
+        let references = document.references();
+        assert_eq!(references.len(), 3);
+        assert!(references.contains(&ExternalReference::ModelConfig {
+            node_id: model_id,
+            node_name: "Model".to_string(),
+            integration_id: "int_openai".to_string(),
+            model_id: "gpt-4".to_string(),
+        }));
+
+        // Every reference must be mapped
+        assert_eq!(
+            document.import(&ImportMapping::new()).unwrap_err(),
+            PortableError::UnmappedIntegration {
+                integration_id: "int_mail".to_string()
+            }
+        );
+        let mapping = ImportMapping::new().with_integration("int_mail", "int_my_mail");
+        assert_eq!(
+            document.import(&mapping).unwrap_err(),
+            PortableError::UnmappedModel { node_id: model_id }
+        );
+
+        let mapping = mapping.with_model(model_id, "int_my_llm", "llama3");
+        let imported = document.import(&mapping).unwrap();
+
+        assert_ne!(imported.id, workflow.id);
+        assert!(!imported.is_enabled());
+        assert!(imported.graph.get_node(fetch_id).is_none());
+        assert_eq!(imported.graph.node_count(), 4);
+        assert!(imported.validate().is_ok());
+        let configs: Vec<&NodeConfig> = imported.graph.nodes().map(|node| &node.config).collect();
+        assert!(configs.iter().any(|config| matches!(
+            config,
+            NodeConfig::Integration(c) if c.parameters["integration_id"] == "int_my_mail"
+        )));
+        assert!(configs.iter().any(|config| matches!(
+            config,
+            NodeConfig::Configuration(ConfigurationNodeConfig::OpenAiModel {
+                integration_id,
+                model_id,
+            }) if integration_id == "int_my_llm" && model_id == "llama3"
+        )));
+        assert!(configs.iter().any(|config| matches!(
+            config,
+            NodeConfig::Trigger(TriggerNodeConfig::IntegrationEvent { integration_id, .. })
+                if integration_id == "int_my_mail"
+        )));
+    }
+}
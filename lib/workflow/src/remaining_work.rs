@@ -77,6 +77,14 @@ impl RemainingWorkGraph {
         }
     }
 
+    /// Returns an executing node to pending without completing it.
+    ///
+    /// Used for fan-out scope nodes whose remaining items are blocked: the
+    /// node stays in the graph, blocked by its failed predecessors.
+    pub fn mark_pending(&mut self, node_id: NodeId) {
+        self.executing.remove(&node_id);
+    }
+
     /// Marks a node as completed and removes it from the graph.
     ///
     /// This unblocks downstream nodes that were waiting for this node.
@@ -0,0 +1,326 @@
+//! Text renderings of workflow graphs.
+//!
+//! Graphs render as Graphviz DOT or Mermaid flowcharts, so changes to a
+//! workflow can be reviewed in pull requests and design documents. Nodes are
+//! labeled with their name and category, and edges with the ports they
+//! connect. A state overlay colors the nodes by their execution state in a
+//! run; nodes without a state keep the default style.
+
+use crate::execution::NodeExecutionState;
+use crate::graph::WorkflowGraph;
+use crate::node::{NodeCategory, NodeId};
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// The execution state of each node in a run.
+pub type StateOverlay = HashMap<NodeId, NodeExecutionState>;
+
+/// A text format for workflow graphs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderFormat {
+    /// Graphviz DOT.
+    Dot,
+    /// Mermaid flowchart.
+    Mermaid,
+}
+
+impl RenderFormat {
+    /// Returns the file extension for the format.
+    #[must_use]
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Dot => "dot",
+            Self::Mermaid => "mmd",
+        }
+    }
+
+    /// Returns the media type for the format.
+    #[must_use]
+    pub fn media_type(self) -> &'static str {
+        match self {
+            Self::Dot => "text/vnd.graphviz",
+            Self::Mermaid => "text/vnd.mermaid",
+        }
+    }
+}
+
+/// Renders a graph in the given format.
+#[must_use]
+pub fn render(
+    graph: &WorkflowGraph,
+    format: RenderFormat,
+    overlay: Option<&StateOverlay>,
+) -> String {
+    match format {
+        RenderFormat::Dot => to_dot(graph, overlay),
+        RenderFormat::Mermaid => to_mermaid(graph, overlay),
+    }
+}
+
+/// Renders a graph as a Graphviz DOT digraph.
+#[must_use]
+pub fn to_dot(graph: &WorkflowGraph, overlay: Option<&StateOverlay>) -> String {
+    let ids = render_ids(graph);
+    let mut out = String::from("digraph workflow {\n");
+    out.push_str("    rankdir=TB;\n");
+    out.push_str("    node [shape=box, style=\"rounded,filled\", fillcolor=\"#ffffff\"];\n");
+
+    for node in graph.nodes() {
+        let label = format!("{}\n({})", node.name, category_label(&node.category()));
+        let _ = write!(
+            out,
+            "    {} [label=\"{}\"",
+            ids[&node.id],
+            dot_escape(&label)
+        );
+        if let Some(state) = overlay.and_then(|states| states.get(&node.id)) {
+            let (fill, stroke) = state_colors(*state);
+            let _ = write!(
+                out,
+                ", fillcolor=\"{fill}\", color=\"{stroke}\", tooltip=\"{}\"",
+                state_label(*state)
+            );
+        }
+        out.push_str("];\n");
+    }
+
+    for (source, edge, target) in graph.edges() {
+        let label = format!("{} -> {}", edge.source_port, edge.target_port);
+        let _ = writeln!(
+            out,
+            "    {} -> {} [label=\"{}\"];",
+            ids[&source.id],
+            ids[&target.id],
+            dot_escape(&label)
+        );
+    }
+
+    out.push_str("}\n");
+    out
+}
+
+/// Renders a graph as a Mermaid flowchart.
+#[must_use]
+pub fn to_mermaid(graph: &WorkflowGraph, overlay: Option<&StateOverlay>) -> String {
+    let ids = render_ids(graph);
+    let mut out = String::from("flowchart TB\n");
+
+    for node in graph.nodes() {
+        let _ = writeln!(
+            out,
+            "    {}[\"{}<br/>({})\"]",
+            ids[&node.id],
+            mermaid_escape(&node.name),
+            category_label(&node.category())
+        );
+    }
+
+    for (source, edge, target) in graph.edges() {
+        // Only the port names are escaped, so the arrow stays readable
+        let label = format!(
+            "{} -> {}",
+            mermaid_escape(&edge.source_port),
+            mermaid_escape(&edge.target_port)
+        );
+        let _ = writeln!(
+            out,
+            "    {} -->|\"{}\"| {}",
+            ids[&source.id], label, ids[&target.id]
+        );
+    }
+
+    if let Some(states) = overlay {
+        // One class per state in use, assigned in graph order
+        let mut classes: Vec<(NodeExecutionState, Vec<&str>)> = Vec::new();
+        for node in graph.nodes() {
+            let Some(state) = states.get(&node.id) else {
+                continue;
+            };
+            let id = ids[&node.id].as_str();
+            match classes.iter_mut().find(|(s, _)| s == state) {
+                Some((_, nodes)) => nodes.push(id),
+                None => classes.push((*state, vec![id])),
+            }
+        }
+        for (state, nodes) in classes {
+            let (fill, stroke) = state_colors(state);
+            let _ = writeln!(
+                out,
+                "    classDef {} fill:{fill},stroke:{stroke}",
+                state_label(state)
+            );
+            let _ = writeln!(out, "    class {} {}", nodes.join(","), state_label(state));
+        }
+    }
+
+    out
+}
+
+/// Assigns short identifiers to the nodes, in graph order. Node IDs are
+/// not used directly so the output stays readable and the same graph
+/// renders the same way after its node IDs are regenerated.
+fn render_ids(graph: &WorkflowGraph) -> HashMap<NodeId, String> {
+    graph
+        .nodes()
+        .enumerate()
+        .map(|(index, node)| (node.id, format!("n{index}")))
+        .collect()
+}
+
+fn category_label(category: &NodeCategory) -> &'static str {
+    match category {
+        NodeCategory::Trigger => "trigger",
+        NodeCategory::AiLayer => "ai_layer",
+        NodeCategory::Integration => "integration",
+        NodeCategory::Transform => "transform",
+        NodeCategory::ControlFlow => "control_flow",
+        NodeCategory::Memory => "memory",
+        NodeCategory::Output => "output",
+        NodeCategory::Configuration => "configuration",
+        NodeCategory::SubWorkflow => "sub_workflow",
+    }
+}
+
+fn state_label(state: NodeExecutionState) -> &'static str {
+    match state {
+        NodeExecutionState::Pending => "pending",
+        NodeExecutionState::Ready => "ready",
+        NodeExecutionState::Running => "running",
+        NodeExecutionState::Waiting => "waiting",
+        NodeExecutionState::Completed => "completed",
+        NodeExecutionState::Failed => "failed",
+        NodeExecutionState::Skipped => "skipped",
+    }
+}
+
+/// Returns the fill and stroke colors for a state.
+fn state_colors(state: NodeExecutionState) -> (&'static str, &'static str) {
+    match state {
+        NodeExecutionState::Pending => ("#f1f3f5", "#868e96"),
+        NodeExecutionState::Ready => ("#fff3bf", "#f08c00"),
+        NodeExecutionState::Running => ("#d0ebff", "#1971c2"),
+        NodeExecutionState::Waiting => ("#ffe8cc", "#e8590c"),
+        NodeExecutionState::Completed => ("#d3f9d8", "#2f9e44"),
+        NodeExecutionState::Failed => ("#ffe3e3", "#e03131"),
+        NodeExecutionState::Skipped => ("#e9ecef", "#adb5bd"),
+    }
+}
+
+/// Escapes text for a quoted Graphviz DOT label.
+fn dot_escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Escapes text for a quoted Mermaid label, using Mermaid's entity codes.
+///
+/// Besides quotes, `#` (which starts an entity code) and the characters
+/// that would otherwise reach the rendered label as HTML are escaped, so a
+/// node name cannot inject entities or markup.
+fn mermaid_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("#quot;"),
+            '#' => escaped.push_str("#35;"),
+            '&' => escaped.push_str("#38;"),
+            '<' => escaped.push_str("#60;"),
+            '>' => escaped.push_str("#62;"),
+            '\n' => escaped.push_str("<br/>"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::edge::Edge;
+    use crate::node::{
+        LogLevel, Node, NodeConfig, OutputNodeConfig, TransformNodeConfig, TriggerNodeConfig,
+    };
+
+    fn sample_graph() -> (WorkflowGraph, NodeId, NodeId) {
+        let mut graph = WorkflowGraph::new();
+        let trigger = graph.add_node(Node::new(
+            "Every \"morning\"",
+            NodeConfig::Trigger(TriggerNodeConfig::Manual),
+        ));
+        let transform = graph.add_node(Node::new(
+            "Summarize",
+            NodeConfig::Transform(TransformNodeConfig {
+                expression: "input".to_string(),
+            }),
+        ));
+        let output = graph.add_node(Node::new(
+            "Log",
+            NodeConfig::Output(OutputNodeConfig::Log {
+                level: LogLevel::Info,
+            }),
+        ));
+        graph
+            .add_edge(trigger, transform, Edge::default_ports())
+            .unwrap();
+        graph
+            .add_edge(transform, output, Edge::default_ports())
+            .unwrap();
+        (graph, trigger, transform)
+    }
+
+    #[test]
+    fn dot_labels_nodes_and_edges() {
+        let (graph, trigger, transform) = sample_graph();
+        let overlay = StateOverlay::from([
+            (trigger, NodeExecutionState::Completed),
+            (transform, NodeExecutionState::Failed),
+        ]);
+
+        let dot = to_dot(&graph, None);
+        assert!(dot.starts_with("digraph workflow {\n"));
+        assert!(dot.contains(r#"n0 [label="Every \"morning\"\n(trigger)"];"#));
+        assert!(dot.contains(r#"n1 [label="Summarize\n(transform)"];"#));
+        assert!(dot.contains(r#"n0 -> n1 [label="output -> input"];"#));
+        assert!(!dot.contains("tooltip"));
+
+        let dot = render(&graph, RenderFormat::Dot, Some(&overlay));
+        assert!(dot.contains(r##"fillcolor="#d3f9d8", color="#2f9e44", tooltip="completed""##));
+        assert!(dot.contains(r#"tooltip="failed""#));
+        assert!(dot.contains(r#"n2 [label="Log\n(output)"];"#));
+    }
+
+    #[test]
+    fn mermaid_labels_nodes_and_edges() {
+        let (graph, trigger, transform) = sample_graph();
+        let overlay = StateOverlay::from([
+            (trigger, NodeExecutionState::Completed),
+            (transform, NodeExecutionState::Completed),
+        ]);
+
+        let mermaid = to_mermaid(&graph, None);
+        assert!(mermaid.starts_with("flowchart TB\n"));
+        assert!(mermaid.contains(r#"n0["Every #quot;morning#quot;<br/>(trigger)"]"#));
+        assert!(mermaid.contains(r#"n1 -->|"output -> input"| n2"#));
+        assert!(!mermaid.contains("classDef"));
+
+        let mermaid = render(&graph, RenderFormat::Mermaid, Some(&overlay));
+        assert!(mermaid.contains("classDef completed fill:#d3f9d8,stroke:#2f9e44"));
+        assert!(mermaid.contains("class n0,n1 completed"));
+    }
+
+    #[test]
+    fn mermaid_escapes_hostile_node_names() {
+        let mut graph = WorkflowGraph::new();
+        graph.add_node(Node::new(
+            "<img src=x onerror=alert(1)> #quot; & \"done\"",
+            NodeConfig::Trigger(TriggerNodeConfig::Manual),
+        ));
+
+        let mermaid = to_mermaid(&graph, None);
+        assert!(mermaid.contains(
+            r##"n0["#60;img src=x onerror=alert(1)#62; #35;quot; #38; #quot;done#quot;<br/>(trigger)"]"##
+        ));
+        assert!(!mermaid.contains("<img"));
+    }
+}
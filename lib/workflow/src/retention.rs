@@ -0,0 +1,186 @@
+//! Retention of node outputs.
+//!
+//! Every node output is kept in the object store until a retention policy
+//! expires it: once the run finished more than a number of days ago, or
+//! once the workflow has a number of newer finished runs. A workflow's
+//! policy is applied on top of the global one, so limits the workflow
+//! leaves unset come from the global policy.
+//!
+//! Expiring a run deletes the outputs it produced and its events, while its
+//! history record stays, marked as having expired outputs. [`run_outputs`]
+//! finds the outputs a run produced from its events.
+
+use crate::execution::ExecutionEvent;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// How long the outputs of a workflow's runs are kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct RetentionPolicy {
+    /// Days after a run finishes until its outputs expire (None for no
+    /// limit).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_age_days: Option<u32>,
+    /// Number of most recent finished runs whose outputs are kept (None
+    /// for no limit).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_runs: Option<u32>,
+}
+
+impl RetentionPolicy {
+    /// Creates a policy that keeps outputs forever.
+    #[must_use]
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+
+    /// Sets the days after which outputs expire.
+    #[must_use]
+    pub fn with_max_age_days(mut self, days: u32) -> Self {
+        self.max_age_days = Some(days);
+        self
+    }
+
+    /// Sets the number of most recent runs whose outputs are kept.
+    #[must_use]
+    pub fn with_max_runs(mut self, runs: u32) -> Self {
+        self.max_runs = Some(runs);
+        self
+    }
+
+    /// Returns true if the policy keeps outputs forever.
+    #[must_use]
+    pub fn is_unlimited(&self) -> bool {
+        self.max_age_days.is_none() && self.max_runs.is_none()
+    }
+
+    /// Returns this policy with the limits it leaves unset taken from
+    /// `fallback`.
+    #[must_use]
+    pub fn or(self, fallback: Self) -> Self {
+        Self {
+            max_age_days: self.max_age_days.or(fallback.max_age_days),
+            max_runs: self.max_runs.or(fallback.max_runs),
+        }
+    }
+
+    /// Returns the time before which finished runs have expired outputs,
+    /// if the policy limits their age.
+    #[must_use]
+    pub fn expired_before(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.max_age_days
+            .map(|days| now - Duration::days(i64::from(days)))
+    }
+}
+
+/// Returns the object store keys of the outputs a run produced, from its
+/// events.
+///
+/// Outputs a retry took over from the failed run it retries are left out,
+/// since they belong to the failed run.
+#[must_use]
+pub fn run_outputs(events: &[ExecutionEvent]) -> BTreeSet<String> {
+    let mut keys = BTreeSet::new();
+    let mut seeded = BTreeSet::new();
+    for event in events {
+        match event {
+            ExecutionEvent::NodeCompleted { output_key, .. } => {
+                keys.insert(output_key.clone());
+            }
+            ExecutionEvent::FanOutExpanded { item_keys, .. } => {
+                keys.extend(item_keys.iter().cloned());
+            }
+            ExecutionEvent::RunSeeded { nodes, .. } => {
+                seeded.extend(nodes.iter().filter_map(|node| node.output_key.clone()));
+            }
+            _ => {}
+        }
+    }
+    keys.retain(|key| !seeded.contains(key));
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::execution::SeededNode;
+    use crate::node::NodeId;
+    use silver_telegram_core::WorkflowRunId;
+
+    fn completed(run_id: WorkflowRunId, node_id: NodeId, key: &str) -> ExecutionEvent {
+        ExecutionEvent::NodeCompleted {
+            run_id,
+            node_id,
+            item_index: None,
+            attempt: 1,
+            output_key: key.to_string(),
+            timestamp: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn workflow_limits_take_precedence_over_global_ones() {
+        let global = RetentionPolicy::unlimited()
+            .with_max_age_days(30)
+            .with_max_runs(100);
+        let workflow = RetentionPolicy::unlimited().with_max_runs(10);
+
+        assert_eq!(
+            workflow.or(global),
+            RetentionPolicy {
+                max_age_days: Some(30),
+                max_runs: Some(10),
+            }
+        );
+        assert!(
+            RetentionPolicy::unlimited()
+                .or(RetentionPolicy::unlimited())
+                .is_unlimited()
+        );
+    }
+
+    #[test]
+    fn age_limit_sets_the_expiry_cutoff() {
+        let now = Utc::now();
+        assert_eq!(RetentionPolicy::unlimited().expired_before(now), None);
+        assert_eq!(
+            RetentionPolicy::unlimited()
+                .with_max_age_days(30)
+                .expired_before(now),
+            Some(now - Duration::days(30))
+        );
+    }
+
+    #[test]
+    fn run_outputs_leave_out_seeded_outputs() {
+        let run_id = WorkflowRunId::new();
+        let (seeded, fan_out, item) = (NodeId::new(), NodeId::new(), NodeId::new());
+        let events = vec![
+            ExecutionEvent::RunSeeded {
+                run_id,
+                source_run_id: WorkflowRunId::new(),
+                from_node: fan_out,
+                nodes: vec![SeededNode {
+                    node_id: seeded,
+                    output_key: Some("seeded".to_string()),
+                    taken_ports: None,
+                }],
+                timestamp: Utc::now(),
+            },
+            ExecutionEvent::FanOutExpanded {
+                run_id,
+                node_id: fan_out,
+                item_keys: vec!["item-0".to_string(), "item-1".to_string()],
+                timestamp: Utc::now(),
+            },
+            completed(run_id, fan_out, "fan-out"),
+            completed(run_id, item, "item-output"),
+        ];
+
+        assert_eq!(
+            run_outputs(&events).into_iter().collect::<Vec<_>>(),
+            ["fan-out", "item-0", "item-1", "item-output"]
+        );
+    }
+}
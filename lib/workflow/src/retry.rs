@@ -0,0 +1,131 @@
+//! Retry policies for node execution.
+//!
+//! A node's retry policy decides whether a failed attempt is retried and
+//! how long to wait first. Delays grow exponentially from
+//! `initial_delay_ms` by `backoff_multiplier`, are capped at
+//! `max_delay_ms`, and are then reduced by up to `jitter` of their length
+//! so that retries of many runs do not hit a recovering service at once.
+//!
+//! The orchestrator records each retry's time in the event stream, so
+//! replaying a run never recomputes a delay.
+
+use crate::worker::NodeErrorKind;
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
+
+/// How a node retries failed attempts.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// Maximum number of attempts, including the first. 1 disables retries.
+    pub max_attempts: u32,
+    /// Delay before the first retry, in milliseconds.
+    pub initial_delay_ms: u64,
+    /// Factor by which the delay grows after each retry.
+    pub backoff_multiplier: f64,
+    /// Upper bound for the delay before jitter, in milliseconds.
+    pub max_delay_ms: u64,
+    /// Fraction of the delay (0.0 to 1.0) that is randomized.
+    pub jitter: f64,
+    /// Kinds of errors that are retried. Other errors fail the node
+    /// immediately.
+    pub retry_on: Vec<NodeErrorKind>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 1,
+            initial_delay_ms: 1_000,
+            backoff_multiplier: 2.0,
+            max_delay_ms: 60_000,
+            jitter: 0.2,
+            retry_on: vec![NodeErrorKind::Timeout, NodeErrorKind::ExternalService],
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Creates a policy that makes up to `max_attempts` attempts, retrying
+    /// timeouts and external service errors with the default backoff.
+    #[must_use]
+    pub fn with_max_attempts(max_attempts: u32) -> Self {
+        Self {
+            max_attempts,
+            ..Self::default()
+        }
+    }
+
+    /// Returns true if a failed attempt should be retried.
+    ///
+    /// `attempt` is the number of the attempt that failed, starting at 1.
+    #[must_use]
+    pub fn should_retry(&self, attempt: u32, kind: NodeErrorKind) -> bool {
+        attempt < self.max_attempts && self.retry_on.contains(&kind)
+    }
+
+    /// Returns the delay before retrying a failed attempt.
+    ///
+    /// `attempt` is the number of the attempt that failed, starting at 1.
+    /// `random` is a value in `[0, 1)` that picks the jitter.
+    #[must_use]
+    pub fn delay(&self, attempt: u32, random: f64) -> Duration {
+        let exponent = i32::try_from(attempt.saturating_sub(1)).unwrap_or(i32::MAX);
+        let max_delay = self.max_delay_ms as f64;
+        let delay = (self.initial_delay_ms as f64
+            * self.backoff_multiplier.max(1.0).powi(exponent))
+        .min(max_delay);
+        let jitter = self.jitter.clamp(0.0, 1.0) * random.clamp(0.0, 1.0);
+        Duration::milliseconds((delay * (1.0 - jitter)) as i64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_policy_does_not_retry() {
+        let policy = RetryPolicy::default();
+        assert!(!policy.should_retry(1, NodeErrorKind::Timeout));
+    }
+
+    #[test]
+    fn retries_only_listed_errors_until_max_attempts() {
+        let policy = RetryPolicy::with_max_attempts(3);
+        assert!(policy.should_retry(1, NodeErrorKind::Timeout));
+        assert!(policy.should_retry(2, NodeErrorKind::ExternalService));
+        assert!(!policy.should_retry(3, NodeErrorKind::Timeout));
+        assert!(!policy.should_retry(1, NodeErrorKind::InvalidInput));
+    }
+
+    #[test]
+    fn delay_grows_exponentially_up_to_the_cap() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            initial_delay_ms: 100,
+            backoff_multiplier: 3.0,
+            max_delay_ms: 1_000,
+            jitter: 0.5,
+            ..RetryPolicy::default()
+        };
+        assert_eq!(policy.delay(1, 0.0), Duration::milliseconds(100));
+        assert_eq!(policy.delay(2, 0.0), Duration::milliseconds(300));
+        assert_eq!(policy.delay(3, 0.0), Duration::milliseconds(900));
+        assert_eq!(policy.delay(4, 0.0), Duration::milliseconds(1_000));
+        assert_eq!(policy.delay(40, 0.0), Duration::milliseconds(1_000));
+        // Jitter shortens the delay by up to half
+        assert_eq!(policy.delay(2, 0.5), Duration::milliseconds(225));
+        assert_eq!(policy.delay(2, 1.0), Duration::milliseconds(150));
+    }
+
+    #[test]
+    fn partial_policy_uses_defaults() {
+        let policy: RetryPolicy =
+            serde_json::from_str(r#"{"max_attempts": 4, "retry_on": ["execution_failed"]}"#)
+                .unwrap();
+        assert_eq!(policy.max_attempts, 4);
+        assert_eq!(policy.initial_delay_ms, 1_000);
+        assert_eq!(policy.retry_on, vec![NodeErrorKind::ExecutionFailed]);
+    }
+}
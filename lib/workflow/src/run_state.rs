@@ -7,9 +7,12 @@
 //! - `RunState`: The complete state of a workflow run
 //! - `RunStateBuilder`: Reconstructs state from an event stream
 
-use crate::execution::{ExecutionEvent, ExecutionState, NodeExecution};
+use crate::dry_run::DryRunConfig;
+use crate::execution::{
+    ExecutionEvent, ExecutionState, NodeExecution, NodeExecutionState, SeededNode,
+};
 use crate::graph::WorkflowGraph;
-use crate::node::NodeId;
+use crate::node::{ControlFlowNodeConfig, NodeConfig, NodeId};
 use crate::remaining_work::RemainingWorkGraph;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -27,6 +30,8 @@ pub struct RunState {
     pub run_id: WorkflowRunId,
     /// The workflow being executed.
     pub workflow_id: WorkflowId,
+    /// The workflow version being executed, if the workflow is versioned.
+    pub workflow_version: Option<u32>,
     /// The trigger that initiated this run, if any.
     pub trigger_id: Option<TriggerId>,
     /// Current execution state of the run.
@@ -35,6 +40,8 @@ pub struct RunState {
     pub queued_at: DateTime<Utc>,
     /// When the run started executing.
     pub started_at: Option<DateTime<Utc>>,
+    /// When the run fails if it has not finished.
+    pub deadline: Option<DateTime<Utc>>,
     /// When the run finished.
     pub finished_at: Option<DateTime<Utc>>,
     /// Input data that triggered the run.
@@ -43,12 +50,56 @@ pub struct RunState {
     pub output: Option<JsonValue>,
     /// Error message (if failed).
     pub error: Option<String>,
+    /// How nodes are substituted, if this is a dry run.
+    pub dry_run: Option<DryRunConfig>,
+    /// The failed run this run retries, if any.
+    pub retry_of: Option<WorkflowRunId>,
+    /// The runs that retried this run, oldest first.
+    pub retried_by: Vec<WorkflowRunId>,
     /// Per-node execution state.
     pub node_states: HashMap<NodeId, NodeExecution>,
+    /// Per-item execution state of nodes inside expanded fan-outs.
+    pub item_states: HashMap<(NodeId, usize), NodeExecution>,
+    /// Expanded fan-outs, keyed by FanOut node.
+    pub fan_outs: HashMap<NodeId, FanOutState>,
+    /// The nodes executed per item of each FanOut node.
+    fan_out_scopes: HashMap<NodeId, Vec<NodeId>>,
     /// The remaining work graph for scheduling.
     remaining_work: RemainingWorkGraph,
 }
 
+/// State of a fan-out whose input has been split into items.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FanOutState {
+    /// Object store keys of the items, in order.
+    pub item_keys: Vec<String>,
+    /// Nodes executed once per item, in topological order.
+    pub scope: Vec<NodeId>,
+    /// Whether every item has finished.
+    pub finished: bool,
+}
+
+/// What happened to a node, or to one item of it, as recorded by
+/// [`RunState::record_outcome`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum NodeOutcome {
+    /// The node started executing with the given input.
+    Started(Option<JsonValue>),
+    /// The node completed with the output stored under the given key.
+    Completed(String),
+    /// The node failed with the given error.
+    Failed(String),
+    /// An attempt failed with `error`, and the node is retried as `attempt`
+    /// no earlier than `retry_at`.
+    Retrying {
+        attempt: u32,
+        error: String,
+        retry_at: DateTime<Utc>,
+    },
+    /// The node was skipped.
+    Skipped,
+}
+
 impl RunState {
     /// Returns nodes that are ready to execute.
     #[must_use]
@@ -62,6 +113,13 @@ impl RunState {
         self.execution_state.is_terminal() || self.remaining_work.is_complete()
     }
 
+    /// Returns true if the run is still going at `now` although its
+    /// deadline has passed.
+    #[must_use]
+    pub fn is_past_deadline(&self, now: DateTime<Utc>) -> bool {
+        self.execution_state.is_active() && self.deadline.is_some_and(|deadline| now >= deadline)
+    }
+
     /// Returns true if there are any failed nodes.
     #[must_use]
     pub fn has_failures(&self) -> bool {
@@ -84,6 +142,224 @@ impl RunState {
         }
     }
 
+    /// Records that a node completed in the failed run this run retries.
+    ///
+    /// The node counts as completed without executing, and its output is
+    /// taken over.
+    pub fn mark_node_seeded(&mut self, seeded: &SeededNode) {
+        self.remaining_work.mark_completed(seeded.node_id);
+        if let Some(node_exec) = self.node_states.get_mut(&seeded.node_id) {
+            node_exec.state = NodeExecutionState::Completed;
+            node_exec.output_key = seeded.output_key.clone();
+            node_exec.taken_ports = seeded.taken_ports.clone();
+        }
+    }
+
+    /// Records the output ports taken by a branch node (or one item of it).
+    pub fn mark_branch_taken(
+        &mut self,
+        node_id: NodeId,
+        item_index: Option<usize>,
+        ports: Vec<String>,
+    ) {
+        let exec = match item_index {
+            Some(index) => self.item_states.get_mut(&(node_id, index)),
+            None => self.node_states.get_mut(&node_id),
+        };
+        if let Some(exec) = exec {
+            exec.take_branch(ports);
+        }
+    }
+
+    /// Records what happened to a node, or to one of its items when
+    /// `item_index` is set.
+    ///
+    /// A node's outcome also updates the remaining work graph; an item's
+    /// only updates the item, since its node stays executing until the
+    /// fan-out finishes.
+    pub fn record_outcome(
+        &mut self,
+        node_id: NodeId,
+        item_index: Option<usize>,
+        outcome: NodeOutcome,
+    ) {
+        let Some(index) = item_index else {
+            match outcome {
+                NodeOutcome::Started(input) => self.mark_node_executing(node_id, input),
+                NodeOutcome::Completed(output_key) => self.mark_node_completed(node_id, output_key),
+                NodeOutcome::Failed(error) => self.mark_node_failed(node_id, error),
+                NodeOutcome::Retrying {
+                    attempt,
+                    error,
+                    retry_at,
+                } => self.mark_node_retrying(node_id, attempt, error, retry_at),
+                NodeOutcome::Skipped => self.mark_node_skipped(node_id),
+            }
+            return;
+        };
+        let Some(exec) = self.item_states.get_mut(&(node_id, index)) else {
+            return;
+        };
+        match outcome {
+            NodeOutcome::Started(input) => exec.start(input),
+            NodeOutcome::Completed(output_key) => exec.complete(output_key),
+            NodeOutcome::Failed(error) => exec.fail(error),
+            NodeOutcome::Retrying {
+                attempt,
+                error,
+                retry_at,
+            } => exec.retry(attempt, error, retry_at),
+            NodeOutcome::Skipped => exec.skip(),
+        }
+    }
+
+    /// Returns the execution record of a node, or of one of its items when
+    /// `item_index` is set.
+    #[must_use]
+    pub fn execution(&self, node_id: NodeId, item_index: Option<usize>) -> Option<&NodeExecution> {
+        match item_index {
+            Some(index) => self.item_states.get(&(node_id, index)),
+            None => self.node_states.get(&node_id),
+        }
+    }
+
+    /// Returns the approval, delay, and sub-workflow nodes (and items) that
+    /// are waiting.
+    pub fn waiting_nodes(&self) -> impl Iterator<Item = &NodeExecution> {
+        self.node_states
+            .values()
+            .chain(self.item_states.values())
+            .filter(|exec| exec.state == NodeExecutionState::Waiting)
+    }
+
+    /// Records that an approval node (or one item of it) waits for a
+    /// decision, a delay node for its delay to end, or a sub-workflow node
+    /// for its child run to end, which pauses the run.
+    pub fn mark_waiting(
+        &mut self,
+        node_id: NodeId,
+        item_index: Option<usize>,
+        expires_at: Option<DateTime<Utc>>,
+    ) {
+        let exec = match item_index {
+            Some(index) => self.item_states.get_mut(&(node_id, index)),
+            None => self.node_states.get_mut(&node_id),
+        };
+        if let Some(exec) = exec {
+            exec.wait(expires_at);
+        }
+        self.update_waiting();
+    }
+
+    /// Records that a waiting node stopped waiting. The run resumes once no
+    /// node is waiting.
+    pub fn mark_resumed(&mut self, node_id: NodeId, item_index: Option<usize>) {
+        let exec = match item_index {
+            Some(index) => self.item_states.get_mut(&(node_id, index)),
+            None => self.node_states.get_mut(&node_id),
+        };
+        if let Some(exec) = exec {
+            exec.resume();
+        }
+        self.update_waiting();
+    }
+
+    /// Sets an active run waiting while any node is waiting.
+    fn update_waiting(&mut self) {
+        if !self.execution_state.is_active() {
+            return;
+        }
+        self.execution_state = if self.waiting_nodes().next().is_some() {
+            ExecutionState::Waiting
+        } else {
+            ExecutionState::Running
+        };
+    }
+
+    /// Returns the execution record of one item of a node in a fan-out scope.
+    pub fn item_state_mut(
+        &mut self,
+        node_id: NodeId,
+        item_index: usize,
+    ) -> Option<&mut NodeExecution> {
+        self.item_states.get_mut(&(node_id, item_index))
+    }
+
+    /// Records that a FanOut node split its input into items.
+    ///
+    /// Every node in the fan-out's scope gets a pending execution per item
+    /// and stays executing in the remaining work graph until all items have
+    /// finished.
+    pub fn mark_fan_out_expanded(&mut self, node_id: NodeId, item_keys: Vec<String>) {
+        let scope = self
+            .fan_out_scopes
+            .get(&node_id)
+            .cloned()
+            .unwrap_or_default();
+
+        for &scope_node in &scope {
+            self.mark_node_executing(scope_node, None);
+            for index in 0..item_keys.len() {
+                self.item_states.insert(
+                    (scope_node, index),
+                    NodeExecution::for_item(self.run_id, scope_node, index),
+                );
+            }
+        }
+
+        self.fan_outs.insert(
+            node_id,
+            FanOutState {
+                item_keys,
+                scope,
+                finished: false,
+            },
+        );
+    }
+
+    /// Records that every item of a fan-out has finished.
+    ///
+    /// Scope nodes with a failed item fail, and scope nodes whose items all
+    /// completed or were skipped complete. Scope nodes with items blocked by
+    /// a failure return to pending, where they block downstream nodes.
+    pub fn mark_fan_out_finished(&mut self, node_id: NodeId) {
+        let Some(fan_out) = self.fan_outs.get_mut(&node_id) else {
+            return;
+        };
+        fan_out.finished = true;
+        let item_count = fan_out.item_keys.len();
+        let scope = fan_out.scope.clone();
+
+        for scope_node in scope {
+            let items: Vec<_> = (0..item_count)
+                .filter_map(|index| self.item_states.get(&(scope_node, index)))
+                .collect();
+
+            if let Some(failed) = items
+                .iter()
+                .find(|exec| exec.state == NodeExecutionState::Failed)
+            {
+                let error = format!(
+                    "item {} failed: {}",
+                    failed.item_index.unwrap_or_default(),
+                    failed.error.as_deref().unwrap_or_default()
+                );
+                self.mark_node_failed(scope_node, error);
+            } else if items.iter().all(|exec| exec.state.is_terminal()) {
+                self.remaining_work.mark_completed(scope_node);
+                if let Some(node_exec) = self.node_states.get_mut(&scope_node) {
+                    node_exec.state = NodeExecutionState::Completed;
+                    node_exec.finished_at = Some(Utc::now());
+                }
+            } else {
+                self.remaining_work.mark_pending(scope_node);
+                if let Some(node_exec) = self.node_states.get_mut(&scope_node) {
+                    node_exec.state = NodeExecutionState::Pending;
+                }
+            }
+        }
+    }
+
     /// Marks a node as completed.
     ///
     /// Updates both the node execution record and the remaining work graph.
@@ -104,6 +380,21 @@ impl RunState {
         }
     }
 
+    /// Records a failed attempt of a node that will be retried.
+    ///
+    /// The node stays executing in the remaining work graph.
+    pub fn mark_node_retrying(
+        &mut self,
+        node_id: NodeId,
+        attempt: u32,
+        error: String,
+        retry_at: DateTime<Utc>,
+    ) {
+        if let Some(node_exec) = self.node_states.get_mut(&node_id) {
+            node_exec.retry(attempt, error, retry_at);
+        }
+    }
+
     /// Marks a node as skipped.
     ///
     /// Updates both the node execution record and the remaining work graph.
@@ -166,16 +457,27 @@ impl RunStateBuilder {
         // First event must be RunQueued
         let first_event = events_iter.next().ok_or(RunStateError::NoEvents)?;
 
-        let (run_id, workflow_id, trigger_id, input, queued_at) = match first_event {
-            ExecutionEvent::RunQueued {
-                run_id,
-                workflow_id,
-                trigger_id,
-                input,
-                timestamp,
-            } => (run_id, workflow_id, trigger_id, input, timestamp),
-            _ => return Err(RunStateError::MissingRunQueued),
-        };
+        let (run_id, workflow_id, workflow_version, trigger_id, input, dry_run, queued_at) =
+            match first_event {
+                ExecutionEvent::RunQueued {
+                    run_id,
+                    workflow_id,
+                    workflow_version,
+                    trigger_id,
+                    input,
+                    dry_run,
+                    timestamp,
+                } => (
+                    run_id,
+                    workflow_id,
+                    workflow_version,
+                    trigger_id,
+                    input,
+                    dry_run,
+                    timestamp,
+                ),
+                _ => return Err(RunStateError::MissingRunQueued),
+            };
 
         // Initialize remaining work graph
         let remaining_work = RemainingWorkGraph::from_workflow(&self.workflow_graph);
@@ -186,18 +488,39 @@ impl RunStateBuilder {
             node_states.insert(node.id, NodeExecution::new(run_id, node.id));
         }
 
+        // Nodes inside a fan-out run once per item
+        let fan_out_scopes = self
+            .workflow_graph
+            .nodes()
+            .filter(|node| {
+                matches!(
+                    node.config,
+                    NodeConfig::ControlFlow(ControlFlowNodeConfig::FanOut)
+                )
+            })
+            .map(|node| (node.id, self.workflow_graph.fan_out_scope(node.id)))
+            .collect();
+
         let mut state = RunState {
             run_id,
             workflow_id,
+            workflow_version,
             trigger_id,
             execution_state: ExecutionState::Queued,
             queued_at,
             started_at: None,
+            deadline: None,
             finished_at: None,
             input,
             output: None,
             error: None,
+            dry_run,
+            retry_of: None,
+            retried_by: Vec::new(),
             node_states,
+            item_states: HashMap::new(),
+            fan_outs: HashMap::new(),
+            fan_out_scopes,
             remaining_work,
         };
 
@@ -217,25 +540,126 @@ fn apply_event(state: &mut RunState, event: ExecutionEvent) -> Result<(), RunSta
             // Duplicate RunQueued is an error
             return Err(RunStateError::DuplicateRunQueued);
         }
-        ExecutionEvent::RunStarted { timestamp, .. } => {
+        ExecutionEvent::RunStarted {
+            deadline,
+            timestamp,
+            ..
+        } => {
             state.execution_state = ExecutionState::Running;
             state.started_at = Some(timestamp);
+            state.deadline = deadline;
+        }
+        ExecutionEvent::NodeStarted {
+            node_id,
+            item_index,
+            input,
+            ..
+        } => {
+            state.record_outcome(node_id, item_index, NodeOutcome::Started(input));
+        }
+        ExecutionEvent::BranchTaken {
+            node_id,
+            item_index,
+            ports,
+            ..
+        } => {
+            state.mark_branch_taken(node_id, item_index, ports);
+        }
+        ExecutionEvent::ApprovalRequested {
+            node_id,
+            item_index,
+            expires_at,
+            ..
+        } => {
+            state.mark_waiting(node_id, item_index, expires_at);
+        }
+        ExecutionEvent::ApprovalResolved {
+            node_id,
+            item_index,
+            ..
+        } => {
+            state.mark_resumed(node_id, item_index);
+        }
+        ExecutionEvent::DelayStarted {
+            node_id,
+            item_index,
+            until,
+            ..
+        } => {
+            state.mark_waiting(node_id, item_index, Some(until));
+        }
+        ExecutionEvent::DelayEnded {
+            node_id,
+            item_index,
+            ..
+        } => {
+            state.mark_resumed(node_id, item_index);
         }
-        ExecutionEvent::NodeStarted { node_id, input, .. } => {
-            state.mark_node_executing(node_id, input);
+        ExecutionEvent::SubWorkflowStarted {
+            node_id,
+            item_index,
+            ..
+        } => {
+            state.mark_waiting(node_id, item_index, None);
+        }
+        ExecutionEvent::SubWorkflowEnded {
+            node_id,
+            item_index,
+            ..
+        } => {
+            state.mark_resumed(node_id, item_index);
+        }
+        ExecutionEvent::NodeSubstituted { .. } => {
+            // The NodeCompleted that follows records the response
         }
         ExecutionEvent::NodeCompleted {
             node_id,
+            item_index,
             output_key,
             ..
         } => {
-            state.mark_node_completed(node_id, output_key);
+            state.record_outcome(node_id, item_index, NodeOutcome::Completed(output_key));
+        }
+        ExecutionEvent::NodeFailed {
+            node_id,
+            item_index,
+            error,
+            ..
+        } => {
+            state.record_outcome(node_id, item_index, NodeOutcome::Failed(error));
+        }
+        ExecutionEvent::NodeRetryScheduled {
+            node_id,
+            item_index,
+            attempt,
+            error,
+            retry_at,
+            ..
+        } => {
+            state.record_outcome(
+                node_id,
+                item_index,
+                NodeOutcome::Retrying {
+                    attempt,
+                    error,
+                    retry_at,
+                },
+            );
+        }
+        ExecutionEvent::NodeSkipped {
+            node_id,
+            item_index,
+            ..
+        } => {
+            state.record_outcome(node_id, item_index, NodeOutcome::Skipped);
         }
-        ExecutionEvent::NodeFailed { node_id, error, .. } => {
-            state.mark_node_failed(node_id, error);
+        ExecutionEvent::FanOutExpanded {
+            node_id, item_keys, ..
+        } => {
+            state.mark_fan_out_expanded(node_id, item_keys);
         }
-        ExecutionEvent::NodeSkipped { node_id, .. } => {
-            state.mark_node_skipped(node_id);
+        ExecutionEvent::FanOutFinished { node_id, .. } => {
+            state.mark_fan_out_finished(node_id);
         }
         ExecutionEvent::RunCompleted {
             output, timestamp, ..
@@ -250,6 +674,19 @@ fn apply_event(state: &mut RunState, event: ExecutionEvent) -> Result<(), RunSta
         ExecutionEvent::RunCancelled { timestamp, .. } => {
             state.cancel(timestamp);
         }
+        ExecutionEvent::RunSeeded {
+            source_run_id,
+            nodes,
+            ..
+        } => {
+            state.retry_of = Some(source_run_id);
+            for seeded in &nodes {
+                state.mark_node_seeded(seeded);
+            }
+        }
+        ExecutionEvent::RunRetried { retry_run_id, .. } => {
+            state.retried_by.push(retry_run_id);
+        }
     }
     Ok(())
 }
@@ -336,8 +773,10 @@ mod tests {
         let events = vec![ExecutionEvent::RunQueued {
             run_id,
             workflow_id,
+            workflow_version: None,
             trigger_id: None,
             input: None,
+            dry_run: None,
             timestamp,
         }];
 
@@ -370,17 +809,21 @@ mod tests {
             ExecutionEvent::RunQueued {
                 run_id,
                 workflow_id,
+                workflow_version: None,
                 trigger_id: None,
                 input: None,
+                dry_run: None,
                 timestamp: t1,
             },
             ExecutionEvent::RunStarted {
                 run_id,
+                deadline: None,
                 timestamp: t2,
             },
             ExecutionEvent::NodeStarted {
                 run_id,
                 node_id: id_a,
+                item_index: None,
                 input: None,
                 timestamp: t3,
             },
@@ -413,23 +856,29 @@ mod tests {
             ExecutionEvent::RunQueued {
                 run_id,
                 workflow_id,
+                workflow_version: None,
                 trigger_id: None,
                 input: None,
+                dry_run: None,
                 timestamp: t1,
             },
             ExecutionEvent::RunStarted {
                 run_id,
+                deadline: None,
                 timestamp: t1,
             },
             ExecutionEvent::NodeStarted {
                 run_id,
                 node_id: id_a,
+                item_index: None,
                 input: None,
                 timestamp: t1,
             },
             ExecutionEvent::NodeCompleted {
                 run_id,
                 node_id: id_a,
+                item_index: None,
+                attempt: 1,
                 output_key: "output_a".to_string(),
                 timestamp: t1,
             },
@@ -461,35 +910,44 @@ mod tests {
             ExecutionEvent::RunQueued {
                 run_id,
                 workflow_id,
+                workflow_version: None,
                 trigger_id: None,
                 input: None,
+                dry_run: None,
                 timestamp: t1,
             },
             ExecutionEvent::RunStarted {
                 run_id,
+                deadline: None,
                 timestamp: t1,
             },
             ExecutionEvent::NodeStarted {
                 run_id,
                 node_id: id_a,
+                item_index: None,
                 input: None,
                 timestamp: t1,
             },
             ExecutionEvent::NodeCompleted {
                 run_id,
                 node_id: id_a,
+                item_index: None,
+                attempt: 1,
                 output_key: "output_a".to_string(),
                 timestamp: t1,
             },
             ExecutionEvent::NodeStarted {
                 run_id,
                 node_id: id_b,
+                item_index: None,
                 input: Some(serde_json::json!({"data": "test"})),
                 timestamp: t1,
             },
             ExecutionEvent::NodeCompleted {
                 run_id,
                 node_id: id_b,
+                item_index: None,
+                attempt: 1,
                 output_key: "output_b".to_string(),
                 timestamp: t1,
             },
@@ -521,23 +979,29 @@ mod tests {
             ExecutionEvent::RunQueued {
                 run_id,
                 workflow_id,
+                workflow_version: None,
                 trigger_id: None,
                 input: None,
+                dry_run: None,
                 timestamp: t1,
             },
             ExecutionEvent::RunStarted {
                 run_id,
+                deadline: None,
                 timestamp: t1,
             },
             ExecutionEvent::NodeStarted {
                 run_id,
                 node_id: id_a,
+                item_index: None,
                 input: None,
                 timestamp: t1,
             },
             ExecutionEvent::NodeFailed {
                 run_id,
                 node_id: id_a,
+                item_index: None,
+                attempt: 1,
                 error: "connection timeout".to_string(),
                 timestamp: t1,
             },
@@ -556,6 +1020,34 @@ mod tests {
         assert!(state.error.is_some());
     }
 
+    #[test]
+    fn record_outcome_updates_the_node_or_only_its_item() {
+        let (graph, id_a, id_b) = create_simple_workflow();
+        let run_id = WorkflowRunId::new();
+        let mut state = RunStateBuilder::new(graph)
+            .build_from_events(vec![ExecutionEvent::RunQueued {
+                run_id,
+                workflow_id: WorkflowId::new(),
+                workflow_version: None,
+                trigger_id: None,
+                input: None,
+                dry_run: None,
+                timestamp: Utc::now(),
+            }])
+            .unwrap();
+
+        state.record_outcome(id_a, None, NodeOutcome::Started(None));
+        assert!(state.ready_nodes().is_empty());
+        state.record_outcome(id_a, None, NodeOutcome::Completed("a".to_string()));
+        assert_eq!(state.node_states[&id_a].output_key.as_deref(), Some("a"));
+        assert_eq!(state.ready_nodes(), vec![id_b]);
+
+        // B runs outside any fan-out, so it has no items to record on
+        state.record_outcome(id_b, Some(0), NodeOutcome::Failed("boom".to_string()));
+        assert_eq!(state.node_states[&id_b].state, NodeExecutionState::Pending);
+        assert!(!state.has_failures());
+    }
+
     #[test]
     fn error_on_no_events() {
         let (graph, _, _) = create_simple_workflow();
@@ -576,6 +1068,7 @@ mod tests {
         // Start with RunStarted instead of RunQueued
         let events = vec![ExecutionEvent::RunStarted {
             run_id,
+            deadline: None,
             timestamp: t1,
         }];
 
@@ -596,15 +1089,19 @@ mod tests {
             ExecutionEvent::RunQueued {
                 run_id,
                 workflow_id,
+                workflow_version: None,
                 trigger_id: None,
                 input: None,
+                dry_run: None,
                 timestamp: t1,
             },
             ExecutionEvent::RunQueued {
                 run_id,
                 workflow_id,
+                workflow_version: None,
                 trigger_id: None,
                 input: None,
+                dry_run: None,
                 timestamp: t1,
             },
         ];
@@ -0,0 +1,193 @@
+//! Invocation of workflows from other workflows.
+//!
+//! A [`SubWorkflowNodeConfig`](crate::node::SubWorkflowNodeConfig) node runs
+//! another workflow as a child of the current run. Workers hand these nodes
+//! to a [`SubWorkflowRunner`], which starts the child run and returns
+//! without waiting for it. The node then waits like an approval or delay
+//! node, until the child run's end resumes it with the child's final output
+//! (see [`Orchestrator::end_sub_workflow`](crate::Orchestrator::end_sub_workflow)).
+//!
+//! Each attempt of a node (or item) gets its own child run, whose ID is
+//! derived from the attempt with [`child_run_id`], so a redelivered work
+//! item finds the child run it already started. Child runs are linked to
+//! their parent, so the runner can see the chain of workflows that led to
+//! an invocation and refuse recursive ones with [`check_call_chain`].
+
+use crate::dry_run::DryRunConfig;
+use crate::node::NodeId;
+use crate::worker::NodeExecutionError;
+use async_trait::async_trait;
+use serde_json::Value as JsonValue;
+use silver_telegram_core::{WorkflowId, WorkflowRunId};
+use ulid::Ulid;
+
+/// Maximum number of nested workflow invocations.
+pub const MAX_SUB_WORKFLOW_DEPTH: usize = 8;
+
+/// A request to run a workflow as a child of a node execution.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubWorkflowRequest {
+    /// The run that invokes the workflow.
+    pub parent_run_id: WorkflowRunId,
+    /// The sub-workflow node.
+    pub parent_node_id: NodeId,
+    /// The item index, for nodes executed per item of a fan-out.
+    pub item_index: Option<usize>,
+    /// The attempt of the node (or item) that invokes the workflow.
+    pub attempt: u32,
+    /// The workflow to run.
+    pub workflow_id: WorkflowId,
+    /// The child run's trigger input.
+    pub input: JsonValue,
+    /// Set if the parent is a dry run, in which case the child run is one
+    /// too.
+    pub dry_run: Option<DryRunConfig>,
+}
+
+impl SubWorkflowRequest {
+    /// Returns the ID of the child run this request starts.
+    #[must_use]
+    pub fn child_run_id(&self) -> WorkflowRunId {
+        child_run_id(
+            self.parent_run_id,
+            self.parent_node_id,
+            self.item_index,
+            self.attempt,
+        )
+    }
+}
+
+/// Starts workflows on behalf of sub-workflow nodes.
+#[async_trait]
+pub trait SubWorkflowRunner: Send + Sync {
+    /// Starts the child run with the request's
+    /// [`child_run_id`](SubWorkflowRequest::child_run_id), unless it was
+    /// started already, and returns its ID without waiting for it to end.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the child run could not be started.
+    async fn start(&self, request: SubWorkflowRequest)
+    -> Result<WorkflowRunId, NodeExecutionError>;
+}
+
+/// FNV-1a 128-bit offset basis.
+const FNV_OFFSET_BASIS: u128 = 0x6c62_272e_07bb_0142_62b8_2175_6295_c58d;
+
+/// FNV-1a 128-bit prime.
+const FNV_PRIME: u128 = 0x0000_0000_0100_0000_0000_0000_0000_013b;
+
+/// Returns the ID of the child run an attempt of a sub-workflow node (or
+/// one item of it) starts.
+///
+/// The ID is the same whenever the attempt is executed, and differs
+/// between attempts, items, nodes, and parent runs. It keeps the parent
+/// run's timestamp, so child runs sort next to their parent.
+#[must_use]
+pub fn child_run_id(
+    parent_run_id: WorkflowRunId,
+    parent_node_id: NodeId,
+    item_index: Option<usize>,
+    attempt: u32,
+) -> WorkflowRunId {
+    let seed = match item_index {
+        Some(index) => format!("{parent_run_id}.{parent_node_id}.{index}.{attempt}"),
+        None => format!("{parent_run_id}.{parent_node_id}.{attempt}"),
+    };
+    let hash = seed.bytes().fold(FNV_OFFSET_BASIS, |hash, byte| {
+        (hash ^ u128::from(byte)).wrapping_mul(FNV_PRIME)
+    });
+    WorkflowRunId::from_ulid(Ulid::from_parts(
+        parent_run_id.as_ulid().timestamp_ms(),
+        hash,
+    ))
+}
+
+/// Checks that a workflow may be invoked from a chain of runs.
+///
+/// `call_chain` holds the workflows of the invoking run and its ancestors,
+/// outermost first.
+///
+/// # Errors
+///
+/// Returns an error if the workflow is already part of the chain, or if
+/// the invocation would nest deeper than [`MAX_SUB_WORKFLOW_DEPTH`].
+pub fn check_call_chain(
+    call_chain: &[WorkflowId],
+    workflow_id: WorkflowId,
+) -> Result<(), NodeExecutionError> {
+    if call_chain.contains(&workflow_id) {
+        let chain: Vec<String> = call_chain
+            .iter()
+            .chain(std::iter::once(&workflow_id))
+            .map(ToString::to_string)
+            .collect();
+        return Err(NodeExecutionError::InvalidInput {
+            message: format!("recursive sub-workflow invocation: {}", chain.join(" → ")),
+        });
+    }
+    if call_chain.len() >= MAX_SUB_WORKFLOW_DEPTH {
+        return Err(NodeExecutionError::InvalidInput {
+            message: format!(
+                "sub-workflows are nested more than {MAX_SUB_WORKFLOW_DEPTH} levels deep"
+            ),
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn child_run_ids_are_derived_from_the_attempt() {
+        let parent_run_id = WorkflowRunId::new();
+        let node_id = NodeId::new();
+        let id = child_run_id(parent_run_id, node_id, None, 1);
+
+        assert_eq!(id, child_run_id(parent_run_id, node_id, None, 1));
+        assert_eq!(
+            id.as_ulid().timestamp_ms(),
+            parent_run_id.as_ulid().timestamp_ms()
+        );
+        let others = [
+            child_run_id(parent_run_id, node_id, None, 2),
+            child_run_id(parent_run_id, node_id, Some(0), 1),
+            child_run_id(parent_run_id, NodeId::new(), None, 1),
+            child_run_id(WorkflowRunId::new(), node_id, None, 1),
+        ];
+        for other in others {
+            assert_ne!(id, other);
+        }
+    }
+
+    #[test]
+    fn new_workflows_may_be_invoked() {
+        let chain = [WorkflowId::new(), WorkflowId::new()];
+        assert!(check_call_chain(&chain, WorkflowId::new()).is_ok());
+    }
+
+    #[test]
+    fn recursive_invocations_are_refused() {
+        let outer = WorkflowId::new();
+        let inner = WorkflowId::new();
+
+        let err = check_call_chain(&[outer, inner], outer).unwrap_err();
+
+        let NodeExecutionError::InvalidInput { message } = err else {
+            panic!("expected invalid input, got {err:?}");
+        };
+        assert!(message.starts_with("recursive sub-workflow invocation"));
+        assert!(message.ends_with(&outer.to_string()));
+    }
+
+    #[test]
+    fn deep_nesting_is_refused() {
+        let chain: Vec<WorkflowId> = (0..MAX_SUB_WORKFLOW_DEPTH)
+            .map(|_| WorkflowId::new())
+            .collect();
+        assert!(check_call_chain(&chain, WorkflowId::new()).is_err());
+        assert!(check_call_chain(&chain[1..], WorkflowId::new()).is_ok());
+    }
+}
@@ -3,20 +3,32 @@
 //! Per ADR-006:
 //! - Workers: Execute nodes, publish completion/failure events
 //! - Clean separation: orchestrator handles graph logic, workers handle execution
-//! - All workers have same capabilities (capability-based routing deferred)
+//! - Work items are routed to the workers that serve the capability their
+//!   node requires (see [`crate::capability`])
 //!
 //! The worker:
 //! 1. Receives work items from the queue
 //! 2. Executes the node
-//! 3. Stores output to Object Store
-//! 4. Publishes completion/failure result
+//! 3. Validates the output against the node's output port schemas
+//! 4. Stores output to Object Store
+//! 5. Publishes completion/failure result
+//!
+//! Sub-workflow nodes only start their child run; the result reports the
+//! node waiting for it (see [`crate::sub_workflow`]).
 
-use crate::node::Node;
+use crate::expression::Expression;
+use crate::node::{Node, NodeConfig};
 use crate::orchestrator::{WorkItem, WorkItemResult};
+use crate::port::SchemaMismatch;
+use crate::sub_workflow::{SubWorkflowRequest, SubWorkflowRunner};
 use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
+use silver_telegram_core::WorkflowRunId;
 use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
+use tokio::sync::Notify;
 
 /// Trait for object storage operations.
 ///
@@ -34,6 +46,21 @@ pub trait ObjectStore: Send + Sync {
     async fn delete(&self, key: &str) -> Result<(), ObjectStoreError>;
 }
 
+#[async_trait]
+impl<T: ObjectStore + ?Sized> ObjectStore for Arc<T> {
+    async fn put(&self, data: &[u8]) -> Result<String, ObjectStoreError> {
+        (**self).put(data).await
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, ObjectStoreError> {
+        (**self).get(key).await
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), ObjectStoreError> {
+        (**self).delete(key).await
+    }
+}
+
 /// Errors from object store operations.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ObjectStoreError {
@@ -89,6 +116,8 @@ pub enum NodeExecutionError {
     ExternalServiceError { service: String, message: String },
     /// Timeout.
     Timeout,
+    /// The run was cancelled.
+    Cancelled,
 }
 
 impl std::fmt::Display for NodeExecutionError {
@@ -103,12 +132,102 @@ impl std::fmt::Display for NodeExecutionError {
                 write!(f, "external service error ({service}): {message}")
             }
             Self::Timeout => write!(f, "execution timed out"),
+            Self::Cancelled => write!(f, "execution cancelled"),
         }
     }
 }
 
 impl std::error::Error for NodeExecutionError {}
 
+impl NodeExecutionError {
+    /// The kind of this error, used to decide whether it is retried.
+    #[must_use]
+    pub fn kind(&self) -> NodeErrorKind {
+        match self {
+            Self::InvalidInput { .. } => NodeErrorKind::InvalidInput,
+            Self::ExecutionFailed { .. } => NodeErrorKind::ExecutionFailed,
+            Self::UnsupportedNodeType { .. } => NodeErrorKind::UnsupportedNodeType,
+            Self::ExternalServiceError { .. } => NodeErrorKind::ExternalService,
+            Self::Timeout => NodeErrorKind::Timeout,
+            Self::Cancelled => NodeErrorKind::Cancelled,
+        }
+    }
+}
+
+/// The kind of a node failure, without its details.
+///
+/// Retry policies list the kinds they retry.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeErrorKind {
+    /// Input validation failed.
+    InvalidInput,
+    /// Execution failed.
+    #[default]
+    ExecutionFailed,
+    /// Node type not supported.
+    UnsupportedNodeType,
+    /// An external service (or the object store) failed.
+    ExternalService,
+    /// Execution timed out.
+    Timeout,
+    /// The run was cancelled.
+    Cancelled,
+}
+
+/// How long a cancelled run is remembered.
+///
+/// Work items of a cancelled run that are delivered later than this still
+/// execute, but the orchestrator ignores their results.
+const CANCELLATION_RETENTION: Duration = Duration::hours(24);
+
+/// Runs that have been cancelled, shared between a worker and whatever
+/// learns about cancellations (see [`crate::nats::listen_for_cancellations`]).
+#[derive(Debug, Clone, Default)]
+pub struct RunCancellations {
+    cancelled: Arc<Mutex<HashMap<WorkflowRunId, DateTime<Utc>>>>,
+    notify: Arc<Notify>,
+}
+
+impl RunCancellations {
+    /// Records that a run was cancelled, waking up its executions.
+    pub fn cancel(&self, run_id: WorkflowRunId) {
+        let now = Utc::now();
+        let mut cancelled = self
+            .cancelled
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        cancelled.retain(|_, at| now - *at < CANCELLATION_RETENTION);
+        cancelled.insert(run_id, now);
+        drop(cancelled);
+        self.notify.notify_waiters();
+    }
+
+    /// Returns true if the run was cancelled.
+    #[must_use]
+    pub fn is_cancelled(&self, run_id: WorkflowRunId) -> bool {
+        self.cancelled
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .contains_key(&run_id)
+    }
+
+    /// Completes once the run is cancelled.
+    pub async fn cancelled(&self, run_id: WorkflowRunId) {
+        loop {
+            // Register for wake-ups before checking, so a cancellation in
+            // between is not missed
+            let notified = self.notify.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+            if self.is_cancelled(run_id) {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
 /// Errors that can occur during worker operations.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum WorkerError {
@@ -120,6 +239,11 @@ pub enum WorkerError {
     NodeNotFound { node_id: String },
     /// Failed to deserialize input.
     DeserializationFailed { message: String },
+    /// The node's output does not match the schema of an output port.
+    InvalidOutput {
+        port_name: String,
+        mismatch: SchemaMismatch,
+    },
 }
 
 impl std::fmt::Display for WorkerError {
@@ -131,12 +255,36 @@ impl std::fmt::Display for WorkerError {
             Self::DeserializationFailed { message } => {
                 write!(f, "deserialization failed: {message}")
             }
+            Self::InvalidOutput {
+                port_name,
+                mismatch,
+            } => {
+                write!(f, "output does not match port '{port_name}' at {mismatch}")
+            }
         }
     }
 }
 
 impl std::error::Error for WorkerError {}
 
+impl WorkerError {
+    /// The kind of this error, used to decide whether it is retried.
+    ///
+    /// Object store failures count as external service errors, except for
+    /// missing inputs, which no retry will fix.
+    #[must_use]
+    pub fn kind(&self) -> NodeErrorKind {
+        match self {
+            Self::ObjectStore(ObjectStoreError::NotFound { .. }) => NodeErrorKind::InvalidInput,
+            Self::ObjectStore(_) => NodeErrorKind::ExternalService,
+            Self::Execution(e) => e.kind(),
+            Self::NodeNotFound { .. } => NodeErrorKind::ExecutionFailed,
+            Self::DeserializationFailed { .. } => NodeErrorKind::InvalidInput,
+            Self::InvalidOutput { .. } => NodeErrorKind::ExecutionFailed,
+        }
+    }
+}
+
 impl From<ObjectStoreError> for WorkerError {
     fn from(e: ObjectStoreError) -> Self {
         Self::ObjectStore(e)
@@ -149,12 +297,22 @@ impl From<NodeExecutionError> for WorkerError {
     }
 }
 
+/// What executing a node produced.
+enum Executed {
+    /// The node's output, stored under this key.
+    Output(String),
+    /// The child run a sub-workflow node started.
+    ChildRun(WorkflowRunId),
+}
+
 /// The workflow worker.
 ///
 /// Executes individual nodes and reports results.
 pub struct Worker<O: ObjectStore, E: NodeExecutor> {
     object_store: O,
     executor: E,
+    cancellations: RunCancellations,
+    sub_workflows: Option<Arc<dyn SubWorkflowRunner>>,
 }
 
 impl<O: ObjectStore, E: NodeExecutor> Worker<O, E> {
@@ -163,37 +321,149 @@ impl<O: ObjectStore, E: NodeExecutor> Worker<O, E> {
         Self {
             object_store,
             executor,
+            cancellations: RunCancellations::default(),
+            sub_workflows: None,
         }
     }
 
+    /// Uses the given cancellations, e.g. ones shared with a listener that
+    /// records cancelled runs.
+    #[must_use]
+    pub fn with_cancellations(mut self, cancellations: RunCancellations) -> Self {
+        self.cancellations = cancellations;
+        self
+    }
+
+    /// Starts the child runs of sub-workflow nodes with the given runner.
+    /// Without one, these nodes fail as unsupported.
+    #[must_use]
+    pub fn with_sub_workflows(mut self, runner: Arc<dyn SubWorkflowRunner>) -> Self {
+        self.sub_workflows = Some(runner);
+        self
+    }
+
+    /// Returns the cancelled runs this worker aborts work for.
+    #[must_use]
+    pub fn cancellations(&self) -> &RunCancellations {
+        &self.cancellations
+    }
+
     /// Processes a work item.
     ///
     /// 1. Retrieves inputs from object store
     /// 2. Executes the node
-    /// 3. Stores output to object store
-    /// 4. Returns the result
+    /// 3. Validates the output against the node's output port schemas
+    /// 4. Stores output to object store
+    /// 5. Returns the result
+    ///
+    /// Work items are executed right away; holding back retries until their
+    /// `not_before` time is up to whoever delivers them (see
+    /// [`crate::worker_runtime`]). If the run is cancelled meanwhile, the
+    /// execution is dropped at its next await point and the item fails as
+    /// cancelled.
+    ///
+    /// Sub-workflow nodes are handed to the sub-workflow runner instead of
+    /// the executor, which starts their child run; the result then reports
+    /// the node waiting for it. In a dry run, the child run is a dry run
+    /// too. Nodes that a dry run substitutes are never executed, should one
+    /// be dispatched.
     pub async fn process(&self, work_item: WorkItem, node: &Node) -> WorkItemResult {
-        match self.execute_node(work_item.clone(), node).await {
-            Ok(output_key) => WorkItemResult::Completed {
+        let outcome = tokio::select! {
+            outcome = self.execute_node(work_item.clone(), node) => outcome,
+            () = self.cancellations.cancelled(work_item.run_id) => {
+                Err(NodeExecutionError::Cancelled.into())
+            }
+        };
+
+        match outcome {
+            Ok(Executed::Output(output_key)) => WorkItemResult::Completed {
                 run_id: work_item.run_id,
                 node_id: work_item.node_id,
+                item_index: work_item.item_index,
+                attempt: work_item.attempt,
                 output_key,
             },
+            Ok(Executed::ChildRun(child_run_id)) => WorkItemResult::Waiting {
+                run_id: work_item.run_id,
+                node_id: work_item.node_id,
+                item_index: work_item.item_index,
+                attempt: work_item.attempt,
+                child_run_id,
+            },
             Err(e) => WorkItemResult::Failed {
                 run_id: work_item.run_id,
                 node_id: work_item.node_id,
+                item_index: work_item.item_index,
+                attempt: work_item.attempt,
                 error: e.to_string(),
+                error_kind: e.kind(),
             },
         }
     }
 
-    /// Executes a node and returns the output key.
-    async fn execute_node(&self, work_item: WorkItem, node: &Node) -> Result<String, WorkerError> {
+    /// Executes a node and returns the output key, or starts the child run
+    /// of a sub-workflow node.
+    async fn execute_node(
+        &self,
+        work_item: WorkItem,
+        node: &Node,
+    ) -> Result<Executed, WorkerError> {
+        // The orchestrator substitutes these nodes in a dry run, so they
+        // must not reach an integration or LLM from here
+        if work_item
+            .dry_run
+            .as_ref()
+            .is_some_and(|dry_run| dry_run.substitutes(node))
+        {
+            return Err(NodeExecutionError::ExecutionFailed {
+                message: "node is substituted in a dry run and must not execute".to_string(),
+            }
+            .into());
+        }
+
         // Retrieve inputs from object store
         let inputs = self.retrieve_inputs(&work_item.inputs).await?;
 
-        // Execute the node
-        let output = self.executor.execute(node, inputs).await?;
+        if let NodeConfig::SubWorkflow(config) = &node.config {
+            let runner = self.sub_workflows.as_ref().ok_or_else(|| {
+                NodeExecutionError::UnsupportedNodeType {
+                    node_type: "SubWorkflow".to_string(),
+                }
+            })?;
+            let child_run_id = runner
+                .start(SubWorkflowRequest {
+                    parent_run_id: work_item.run_id,
+                    parent_node_id: work_item.node_id,
+                    item_index: work_item.item_index,
+                    attempt: work_item.attempt,
+                    workflow_id: config.workflow_id,
+                    input: config.trigger_input(&inputs),
+                    dry_run: work_item.dry_run.clone(),
+                })
+                .await?;
+            return Ok(Executed::ChildRun(child_run_id));
+        }
+
+        // Execute the node, giving up once its timeout passes
+        let execution = self.executor.execute(node, inputs);
+        let output = match node.timeout_ms {
+            Some(timeout_ms) => {
+                tokio::time::timeout(std::time::Duration::from_millis(timeout_ms), execution)
+                    .await
+                    .map_err(|_| NodeExecutionError::Timeout)??
+            }
+            None => execution.await?,
+        };
+
+        // The output flows through every output port, so it must match each
+        for port in &node.outputs {
+            port.schema
+                .validate(&output)
+                .map_err(|mismatch| WorkerError::InvalidOutput {
+                    port_name: port.name.clone(),
+                    mismatch,
+                })?;
+        }
 
         // Store output to object store
         let output_bytes =
@@ -202,7 +472,7 @@ impl<O: ObjectStore, E: NodeExecutor> Worker<O, E> {
             })?;
         let output_key = self.object_store.put(&output_bytes).await?;
 
-        Ok(output_key)
+        Ok(Executed::Output(output_key))
     }
 
     /// Retrieves inputs from object store.
@@ -239,6 +509,41 @@ impl NodeExecutor for EchoExecutor {
     }
 }
 
+/// Executes Transform nodes by evaluating their expression against the
+/// value on the `input` port.
+pub struct TransformExecutor;
+
+#[async_trait]
+impl NodeExecutor for TransformExecutor {
+    async fn execute(
+        &self,
+        node: &Node,
+        inputs: HashMap<String, JsonValue>,
+    ) -> Result<JsonValue, NodeExecutionError> {
+        let NodeConfig::Transform(config) = &node.config else {
+            return Err(NodeExecutionError::UnsupportedNodeType {
+                node_type: format!("{:?}", node.category()),
+            });
+        };
+        let expression = Expression::parse(&config.expression).map_err(|e| {
+            NodeExecutionError::InvalidInput {
+                message: format!("invalid expression: {e}"),
+            }
+        })?;
+        let input = inputs
+            .get("input")
+            .ok_or_else(|| NodeExecutionError::InvalidInput {
+                message: "missing input 'input'".to_string(),
+            })?;
+
+        expression
+            .evaluate(input)
+            .map_err(|e| NodeExecutionError::ExecutionFailed {
+                message: e.to_string(),
+            })
+    }
+}
+
 /// A mock executor that can be configured to succeed or fail.
 pub struct MockExecutor {
     /// If set, all executions will fail with this error.
@@ -284,11 +589,12 @@ impl NodeExecutor for MockExecutor {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::node::{AiLayerNodeConfig, NodeConfig};
-    use silver_telegram_core::WorkflowRunId;
-    use std::sync::{Arc, Mutex};
+    use crate::dry_run::DryRunConfig;
+    use crate::node::{AiLayerNodeConfig, SubWorkflowNodeConfig, TransformNodeConfig};
+    use silver_telegram_core::WorkflowId;
 
     /// In-memory object store for testing.
+    #[derive(Clone)]
     struct InMemoryObjectStore {
         data: Arc<Mutex<HashMap<String, Vec<u8>>>>,
         counter: Arc<Mutex<u64>>,
@@ -349,14 +655,19 @@ mod tests {
             .await
             .unwrap();
 
-        let executor = MockExecutor::succeeding(serde_json::json!({"result": "success"}));
+        let executor = MockExecutor::succeeding(serde_json::json!("success"));
         let worker = Worker::new(object_store, executor);
 
         let node = create_ai_node();
         let work_item = WorkItem {
             run_id: WorkflowRunId::new(),
             node_id: node.id,
+            capability: Some(node.required_capability()),
+            item_index: None,
             inputs: [("context".to_string(), input_key)].into_iter().collect(),
+            attempt: 1,
+            not_before: None,
+            dry_run: None,
         };
 
         let result = worker.process(work_item.clone(), &node).await;
@@ -365,15 +676,22 @@ mod tests {
             WorkItemResult::Completed {
                 run_id,
                 node_id,
+                item_index,
+                attempt,
                 output_key,
             } => {
                 assert_eq!(run_id, work_item.run_id);
                 assert_eq!(node_id, work_item.node_id);
+                assert_eq!(item_index, work_item.item_index);
+                assert_eq!(attempt, work_item.attempt);
                 assert!(!output_key.is_empty());
             }
             WorkItemResult::Failed { error, .. } => {
                 panic!("expected success, got failure: {error}");
             }
+            WorkItemResult::Waiting { .. } => {
+                panic!("expected success, got a waiting node");
+            }
         }
     }
 
@@ -389,7 +707,12 @@ mod tests {
         let work_item = WorkItem {
             run_id: WorkflowRunId::new(),
             node_id: node.id,
+            capability: Some(node.required_capability()),
+            item_index: None,
             inputs: HashMap::new(),
+            attempt: 1,
+            not_before: None,
+            dry_run: None,
         };
 
         let result = worker.process(work_item.clone(), &node).await;
@@ -398,13 +721,19 @@ mod tests {
             WorkItemResult::Failed {
                 run_id,
                 node_id,
+                item_index,
+                attempt,
                 error,
+                error_kind,
             } => {
                 assert_eq!(run_id, work_item.run_id);
                 assert_eq!(node_id, work_item.node_id);
+                assert_eq!(item_index, work_item.item_index);
+                assert_eq!(attempt, work_item.attempt);
+                assert_eq!(error_kind, NodeErrorKind::ExecutionFailed);
                 assert!(error.contains("test error"));
             }
-            WorkItemResult::Completed { .. } => {
+            WorkItemResult::Completed { .. } | WorkItemResult::Waiting { .. } => {
                 panic!("expected failure, got success");
             }
         }
@@ -420,23 +749,75 @@ mod tests {
         let work_item = WorkItem {
             run_id: WorkflowRunId::new(),
             node_id: node.id,
+            capability: Some(node.required_capability()),
+            item_index: None,
             inputs: [("context".to_string(), "nonexistent_key".to_string())]
                 .into_iter()
                 .collect(),
+            attempt: 1,
+            not_before: None,
+            dry_run: None,
         };
 
         let result = worker.process(work_item.clone(), &node).await;
 
         match result {
-            WorkItemResult::Failed { error, .. } => {
+            WorkItemResult::Failed {
+                error, error_kind, ..
+            } => {
                 assert!(error.contains("not found"));
+                assert_eq!(error_kind, NodeErrorKind::InvalidInput);
             }
-            WorkItemResult::Completed { .. } => {
+            WorkItemResult::Completed { .. } | WorkItemResult::Waiting { .. } => {
                 panic!("expected failure due to missing input");
             }
         }
     }
 
+    #[tokio::test]
+    async fn worker_rejects_output_that_does_not_match_its_port() {
+        let object_store = InMemoryObjectStore::new();
+        let executor = MockExecutor::succeeding(serde_json::json!({
+            "category": "urgent",
+            "confidence": "high"
+        }));
+        let worker = Worker::new(object_store, executor);
+
+        let node = Node::new(
+            "Classify",
+            NodeConfig::AiLayer(AiLayerNodeConfig::Classify {
+                categories: vec!["urgent".to_string()],
+            }),
+        );
+        let work_item = WorkItem {
+            run_id: WorkflowRunId::new(),
+            node_id: node.id,
+            capability: Some(node.required_capability()),
+            item_index: None,
+            inputs: HashMap::new(),
+            attempt: 1,
+            not_before: None,
+            dry_run: None,
+        };
+
+        match worker.process(work_item, &node).await {
+            WorkItemResult::Failed {
+                error, error_kind, ..
+            } => {
+                assert_eq!(
+                    error,
+                    "output does not match port 'classification' at $.confidence: \
+                     expected number, found string"
+                );
+                assert_eq!(error_kind, NodeErrorKind::ExecutionFailed);
+            }
+            WorkItemResult::Completed { .. } | WorkItemResult::Waiting { .. } => {
+                panic!("expected the invalid output to be rejected");
+            }
+        }
+        assert!(worker.object_store.data.lock().unwrap().is_empty());
+    }
+
     #[tokio::test]
     async fn echo_executor_echoes_inputs() {
         let executor = EchoExecutor;
@@ -455,17 +836,58 @@ mod tests {
         assert_eq!(result["b"], 123);
     }
 
+    #[tokio::test]
+    async fn transform_executor_evaluates_expression() {
+        let node = Node::new(
+            "Transform",
+            NodeConfig::Transform(TransformNodeConfig {
+                expression: "map(filter(emails, e => e.unread), e => upper(e.subject))".to_string(),
+            }),
+        );
+        let input = serde_json::json!({"emails": [
+            {"subject": "hi", "unread": true},
+            {"subject": "old", "unread": false}
+        ]});
+        let inputs: HashMap<String, JsonValue> =
+            [("input".to_string(), input)].into_iter().collect();
+
+        let result = TransformExecutor.execute(&node, inputs).await.unwrap();
+        assert_eq!(result, serde_json::json!(["HI"]));
+
+        let inputs: HashMap<String, JsonValue> = [(
+            "input".to_string(),
+            serde_json::json!({"emails": "not a list"}),
+        )]
+        .into_iter()
+        .collect();
+        assert!(matches!(
+            TransformExecutor.execute(&node, inputs).await,
+            Err(NodeExecutionError::ExecutionFailed { .. })
+        ));
+        assert!(matches!(
+            TransformExecutor
+                .execute(&create_ai_node(), HashMap::new())
+                .await,
+            Err(NodeExecutionError::UnsupportedNodeType { .. })
+        ));
+    }
+
     #[tokio::test]
     async fn worker_stores_output_in_object_store() {
         let object_store = InMemoryObjectStore::new();
-        let executor = MockExecutor::succeeding(serde_json::json!({"output": "data"}));
+        let executor = MockExecutor::succeeding(serde_json::json!("data"));
         let worker = Worker::new(object_store, executor);
 
         let node = create_ai_node();
         let work_item = WorkItem {
             run_id: WorkflowRunId::new(),
             node_id: node.id,
+            capability: Some(node.required_capability()),
+            item_index: None,
             inputs: HashMap::new(),
+            attempt: 1,
+            not_before: None,
+            dry_run: None,
         };
 
         let result = worker.process(work_item, &node).await;
@@ -474,9 +896,267 @@ mod tests {
             // Verify we can retrieve the output
             let stored = worker.object_store.get(&output_key).await.unwrap();
             let value: JsonValue = serde_json::from_slice(&stored).unwrap();
-            assert_eq!(value["output"], "data");
+            assert_eq!(value, "data");
         } else {
             panic!("expected success");
         }
     }
+
+    #[test]
+    fn errors_are_classified_by_kind() {
+        assert_eq!(NodeExecutionError::Timeout.kind(), NodeErrorKind::Timeout);
+        let external = NodeExecutionError::ExternalServiceError {
+            service: "imap".to_string(),
+            message: "connection reset".to_string(),
+        };
+        assert_eq!(external.kind(), NodeErrorKind::ExternalService);
+        assert_eq!(
+            WorkerError::Execution(external).kind(),
+            NodeErrorKind::ExternalService
+        );
+        assert_eq!(
+            WorkerError::ObjectStore(ObjectStoreError::RetrieveFailed {
+                message: "unavailable".to_string(),
+            })
+            .kind(),
+            NodeErrorKind::ExternalService
+        );
+    }
+
+    /// Executor that never finishes within a test.
+    struct HangingExecutor;
+
+    #[async_trait]
+    impl NodeExecutor for HangingExecutor {
+        async fn execute(
+            &self,
+            _node: &Node,
+            _inputs: HashMap<String, JsonValue>,
+        ) -> Result<JsonValue, NodeExecutionError> {
+            tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+            Ok(JsonValue::Null)
+        }
+    }
+
+    #[tokio::test]
+    async fn worker_enforces_node_timeout() {
+        let worker = Worker::new(InMemoryObjectStore::new(), HangingExecutor);
+        let node = create_ai_node().with_timeout_ms(10);
+        let work_item = WorkItem {
+            run_id: WorkflowRunId::new(),
+            node_id: node.id,
+            capability: Some(node.required_capability()),
+            item_index: None,
+            inputs: HashMap::new(),
+            attempt: 1,
+            not_before: None,
+            dry_run: None,
+        };
+
+        let result = worker.process(work_item, &node).await;
+
+        assert!(matches!(
+            result,
+            WorkItemResult::Failed {
+                error_kind: NodeErrorKind::Timeout,
+                ..
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn cancellation_aborts_running_execution() {
+        let worker = Worker::new(InMemoryObjectStore::new(), HangingExecutor);
+        let node = create_ai_node();
+        let run_id = WorkflowRunId::new();
+        let work_item = WorkItem {
+            run_id,
+            node_id: node.id,
+            capability: Some(node.required_capability()),
+            item_index: None,
+            inputs: HashMap::new(),
+            attempt: 1,
+            not_before: None,
+            dry_run: None,
+        };
+
+        let cancellations = worker.cancellations().clone();
+        let canceller = tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            cancellations.cancel(run_id);
+        });
+        let result = worker.process(work_item.clone(), &node).await;
+        canceller.await.unwrap();
+
+        assert!(matches!(
+            result,
+            WorkItemResult::Failed {
+                error_kind: NodeErrorKind::Cancelled,
+                ..
+            }
+        ));
+
+        // Work items of the run delivered later fail without executing
+        let result = worker.process(work_item, &node).await;
+        assert!(matches!(
+            result,
+            WorkItemResult::Failed {
+                error_kind: NodeErrorKind::Cancelled,
+                ..
+            }
+        ));
+        assert!(!worker.cancellations().is_cancelled(WorkflowRunId::new()));
+    }
+
+    #[tokio::test]
+    async fn worker_refuses_nodes_a_dry_run_substitutes() {
+        let worker = Worker::new(
+            InMemoryObjectStore::new(),
+            MockExecutor::succeeding(serde_json::json!("sent")),
+        );
+        let node = create_ai_node();
+        let work_item = WorkItem {
+            run_id: WorkflowRunId::new(),
+            node_id: node.id,
+            capability: Some(node.required_capability()),
+            item_index: None,
+            inputs: HashMap::new(),
+            attempt: 1,
+            not_before: None,
+            dry_run: Some(DryRunConfig::new().with_stub_llm()),
+        };
+
+        let result = worker.process(work_item.clone(), &node).await;
+        assert!(matches!(
+            result,
+            WorkItemResult::Failed {
+                error_kind: NodeErrorKind::ExecutionFailed,
+                ..
+            }
+        ));
+        assert!(worker.object_store.data.lock().unwrap().is_empty());
+
+        // Without stubbed LLM calls, the dry run executes AI nodes
+        let work_item = WorkItem {
+            dry_run: Some(DryRunConfig::new()),
+            ..work_item
+        };
+        assert!(matches!(
+            worker.process(work_item, &node).await,
+            WorkItemResult::Completed { .. }
+        ));
+    }
+
+    /// Records sub-workflow requests as started child runs.
+    struct RecordingRunner {
+        requests: Mutex<Vec<SubWorkflowRequest>>,
+    }
+
+    #[async_trait]
+    impl SubWorkflowRunner for RecordingRunner {
+        async fn start(
+            &self,
+            request: SubWorkflowRequest,
+        ) -> Result<WorkflowRunId, NodeExecutionError> {
+            let child_run_id = request.child_run_id();
+            self.requests.lock().unwrap().push(request);
+            Ok(child_run_id)
+        }
+    }
+
+    fn create_sub_workflow_node(workflow_id: WorkflowId) -> Node {
+        Node::new(
+            "Summarize Priority Emails",
+            NodeConfig::SubWorkflow(SubWorkflowNodeConfig {
+                workflow_id,
+                input_mapping: [("emails".to_string(), "messages".to_string())]
+                    .into_iter()
+                    .collect(),
+            }),
+        )
+    }
+
+    #[tokio::test]
+    async fn sub_workflow_nodes_start_child_runs_through_the_runner() {
+        let object_store = InMemoryObjectStore::new();
+        let input_key = object_store
+            .put(&serde_json::to_vec(&serde_json::json!(["a", "b"])).unwrap())
+            .await
+            .unwrap();
+        let runner = Arc::new(RecordingRunner {
+            requests: Mutex::new(Vec::new()),
+        });
+        let worker =
+            Worker::new(object_store.clone(), HangingExecutor).with_sub_workflows(runner.clone());
+
+        let workflow_id = WorkflowId::new();
+        let node = create_sub_workflow_node(workflow_id);
+        let work_item = WorkItem {
+            run_id: WorkflowRunId::new(),
+            node_id: node.id,
+            capability: Some(node.required_capability()),
+            item_index: Some(2),
+            inputs: [("emails".to_string(), input_key)].into_iter().collect(),
+            attempt: 1,
+            not_before: None,
+            dry_run: None,
+        };
+
+        let result = worker.process(work_item.clone(), &node).await;
+
+        assert_eq!(
+            result,
+            WorkItemResult::Waiting {
+                run_id: work_item.run_id,
+                node_id: node.id,
+                item_index: Some(2),
+                attempt: 1,
+                child_run_id: crate::sub_workflow::child_run_id(
+                    work_item.run_id,
+                    node.id,
+                    Some(2),
+                    1
+                ),
+            }
+        );
+        let requests = runner.requests.lock().unwrap();
+        assert_eq!(
+            *requests,
+            vec![SubWorkflowRequest {
+                parent_run_id: work_item.run_id,
+                parent_node_id: node.id,
+                item_index: Some(2),
+                attempt: 1,
+                workflow_id,
+                input: serde_json::json!({ "messages": ["a", "b"] }),
+                dry_run: None,
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn sub_workflow_nodes_need_a_runner() {
+        let worker = Worker::new(InMemoryObjectStore::new(), EchoExecutor);
+        let node = create_sub_workflow_node(WorkflowId::new());
+        let work_item = WorkItem {
+            run_id: WorkflowRunId::new(),
+            node_id: node.id,
+            capability: Some(node.required_capability()),
+            item_index: None,
+            inputs: HashMap::new(),
+            attempt: 1,
+            not_before: None,
+            dry_run: None,
+        };
+
+        let result = worker.process(work_item, &node).await;
+
+        assert!(matches!(
+            result,
+            WorkItemResult::Failed {
+                error_kind: NodeErrorKind::UnsupportedNodeType,
+                ..
+            }
+        ));
+    }
 }
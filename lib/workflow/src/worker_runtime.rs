@@ -0,0 +1,627 @@
+//! Worker runtime pulling work items from NATS JetStream.
+//!
+//! [`Worker::process`] executes a single work item; the runtime feeds it from
+//! the work stream and reports back:
+//!
+//! - Work items are pulled through a durable consumer per capability, shared
+//!   by all workers that serve it, with at most `max_concurrency` of them in
+//!   progress per worker; a work item is only pulled once a slot is free, so
+//!   none waits for one while JetStream's acknowledgement deadline runs
+//! - The worker advertises the capabilities it serves for as long as it runs
+//! - While a node executes, the runtime tells JetStream the work item is
+//!   still in progress, so long nodes are not redelivered to another worker
+//! - A work item is acknowledged only after its [`WorkItemResult`] has been
+//!   published, so a crashed worker's work is delivered again
+//! - Retries that arrive before their `not_before` time are delivered again
+//!   once it has come, instead of holding a slot until then
+//! - Transient failures (the node cannot be looked up, or the result cannot
+//!   be published) are retried by a delayed redelivery
+//! - Work items that cannot be decoded, or still fail on their last
+//!   delivery, are moved to the dead-letter stream; so are work items whose
+//!   deliveries ran out because workers crashed on them
+//!
+//! Node failures are not transient here: they become failed results, which
+//! the orchestrator retries according to the node's retry policy. Failing to
+//! acknowledge a work item, or to report its progress, is logged; JetStream
+//! delivers the work item again, and the orchestrator ignores a result it
+//! already has.
+
+use crate::capability::Capability;
+use crate::envelope::Envelope;
+use crate::nats::{NatsEventStore, WORKER_ADVERTISEMENT_TTL, listen_for_cancellations};
+use crate::node::Node;
+use crate::orchestrator::{EventStoreError, WorkItem, WorkItemResult};
+use crate::worker::{NodeErrorKind, NodeExecutor, ObjectStore, Worker};
+use async_nats::jetstream;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::stream::FuturesUnordered;
+use futures::{StreamExt, future, stream};
+use serde::Deserialize;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Semaphore, SemaphorePermit};
+use ulid::Ulid;
+
+/// Default name prefix of the durable consumers workers share.
+const DEFAULT_CONSUMER_NAME: &str = "workers";
+
+/// How long a pull for a capability's next work item waits before the free
+/// slot is offered to the worker's other capabilities.
+const PULL_EXPIRY: Duration = Duration::from_secs(1);
+
+/// Configuration for a worker runtime.
+#[derive(Debug, Clone)]
+pub struct WorkerRuntimeConfig {
+    /// Name under which the worker advertises its capabilities (defaults to
+    /// a unique `worker-<ulid>`).
+    pub worker_id: String,
+    /// Capabilities the worker serves (defaults to those of all node
+    /// categories workers execute).
+    pub capabilities: Vec<Capability>,
+    /// Name prefix of the durable consumers the workers share, one per
+    /// capability, e.g. `workers-ai_layer` (defaults to `workers`).
+    pub consumer_name: String,
+    /// Maximum number of work items a worker processes at once.
+    pub max_concurrency: usize,
+    /// Number of deliveries after which a failing work item is
+    /// dead-lettered.
+    pub max_deliveries: i64,
+    /// How long JetStream waits for an acknowledgement or heartbeat before
+    /// delivering a work item again.
+    pub ack_wait: Duration,
+    /// How often a work item in progress is reported as such; shorter than
+    /// `ack_wait`.
+    pub heartbeat_interval: Duration,
+    /// Delay before a work item that failed transiently is delivered again.
+    pub retry_delay: Duration,
+}
+
+impl Default for WorkerRuntimeConfig {
+    fn default() -> Self {
+        Self {
+            worker_id: format!("worker-{}", Ulid::new()),
+            capabilities: Capability::categories(),
+            consumer_name: DEFAULT_CONSUMER_NAME.to_string(),
+            max_concurrency: 4,
+            max_deliveries: 5,
+            ack_wait: Duration::from_secs(30),
+            heartbeat_interval: Duration::from_secs(10),
+            retry_delay: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Looks up the node a work item executes.
+///
+/// Work items only carry the node's ID; the runtime asks the resolver for
+/// the node, e.g. from the workflow version the run is pinned to.
+#[async_trait]
+pub trait NodeResolver: Send + Sync {
+    /// Returns the node the work item executes.
+    async fn resolve(&self, work_item: &WorkItem) -> Result<Node, ResolveError>;
+}
+
+/// Errors from looking up a work item's node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResolveError {
+    /// The run or node does not exist; the work item fails.
+    NotFound { message: String },
+    /// The lookup failed, e.g. because a database is unavailable; the work
+    /// item is delivered again later.
+    Unavailable { message: String },
+}
+
+impl std::fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotFound { message } => write!(f, "node not found: {message}"),
+            Self::Unavailable { message } => write!(f, "node lookup failed: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for ResolveError {}
+
+/// What happens to a delivered work item once it was handled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Disposition {
+    /// Acknowledge it; its result was published.
+    Ack,
+    /// Deliver it again once it is due, without executing it.
+    Defer { delay: Duration },
+    /// Deliver it again after a delay.
+    Retry { reason: String },
+    /// Move it to the dead-letter stream.
+    DeadLetter { reason: String },
+}
+
+impl Disposition {
+    /// Decides on a work item delivered at `now`, if it is not due yet.
+    ///
+    /// Deferring costs the work item a delivery.
+    fn before_execution(work_item: &WorkItem, now: DateTime<Utc>) -> Option<Self> {
+        let delay = (work_item.not_before? - now).to_std().ok()?;
+        (!delay.is_zero()).then_some(Self::Defer { delay })
+    }
+
+    /// Decides on a work item that failed transiently on a delivery.
+    fn after_failure(reason: String, deliveries: i64, max_deliveries: i64) -> Self {
+        if deliveries >= max_deliveries {
+            Self::DeadLetter {
+                reason: format!("{reason} (after {deliveries} deliveries)"),
+            }
+        } else {
+            Self::Retry { reason }
+        }
+    }
+}
+
+/// Advisory JetStream publishes when a message ran out of deliveries.
+#[derive(Debug, Deserialize)]
+struct MaxDeliveriesAdvisory {
+    stream_seq: u64,
+    deliveries: i64,
+}
+
+/// Runs a worker against the work stream.
+pub struct WorkerRuntime<O: ObjectStore, E: NodeExecutor, R: NodeResolver> {
+    worker: Worker<O, E>,
+    resolver: R,
+    event_store: Arc<NatsEventStore>,
+    config: WorkerRuntimeConfig,
+}
+
+impl<O: ObjectStore, E: NodeExecutor, R: NodeResolver> WorkerRuntime<O, E, R> {
+    /// Creates a runtime that processes work items with `worker`, looking up
+    /// their nodes with `resolver`.
+    pub fn new(
+        worker: Worker<O, E>,
+        resolver: R,
+        event_store: Arc<NatsEventStore>,
+        config: WorkerRuntimeConfig,
+    ) -> Self {
+        Self {
+            worker,
+            resolver,
+            event_store,
+            config,
+        }
+    }
+
+    /// Processes work items until the connection ends.
+    ///
+    /// Also advertises the worker's capabilities, follows cancelled runs,
+    /// aborting their executions, and moves work items that ran out of
+    /// deliveries to the dead-letter stream.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a consumer cannot be created, or an
+    /// advertisement or subscription fails.
+    pub async fn run(&self) -> Result<(), EventStoreError> {
+        tokio::select! {
+            result = self.process_work_items() => result,
+            result = self.advertise() => result,
+            result = self.dead_letter_exhausted() => result,
+            result = listen_for_cancellations(
+                self.event_store.config(),
+                self.worker.cancellations().clone(),
+            ) => result,
+        }
+    }
+
+    /// Pulls work items for all capabilities the worker serves and
+    /// processes up to `max_concurrency` at once.
+    async fn process_work_items(&self) -> Result<(), EventStoreError> {
+        let slots = Semaphore::new(self.config.max_concurrency.max(1));
+        let mut consumers = Vec::new();
+        for capability in &self.config.capabilities {
+            consumers.push(self.consumer(capability).await?);
+        }
+
+        future::join_all(
+            consumers
+                .iter()
+                .map(|consumer| self.process_consumer(consumer, &slots)),
+        )
+        .await;
+        Ok(())
+    }
+
+    /// Pulls a capability's work items one at a time, each once one of the
+    /// worker's `slots` is free, and handles them while pulling the next.
+    /// Never returns.
+    async fn process_consumer(
+        &self,
+        consumer: &jetstream::consumer::PullConsumer,
+        slots: &Semaphore,
+    ) {
+        let mut handling = FuturesUnordered::new();
+        let pull = self.pull(consumer, slots);
+        tokio::pin!(pull);
+
+        loop {
+            tokio::select! {
+                (permit, message) = &mut pull => {
+                    if let Some(message) = message {
+                        handling.push(async move {
+                            self.handle(message).await;
+                            drop(permit);
+                        });
+                    }
+                    pull.set(self.pull(consumer, slots));
+                }
+                Some(()) = handling.next(), if !handling.is_empty() => {}
+            }
+        }
+    }
+
+    /// Waits for a free slot, then pulls the consumer's next work item.
+    ///
+    /// Returns None if no work item arrived within [`PULL_EXPIRY`]. The slot
+    /// is held until the returned permit is dropped. Failed pulls are
+    /// logged and tried again after `retry_delay`, without holding the slot.
+    async fn pull<'a>(
+        &self,
+        consumer: &jetstream::consumer::PullConsumer,
+        slots: &'a Semaphore,
+    ) -> (SemaphorePermit<'a>, Option<jetstream::Message>) {
+        loop {
+            let permit = slots
+                .acquire()
+                .await
+                .unwrap_or_else(|_| unreachable!("worker slots are never closed"));
+
+            let pulled = async {
+                let mut batch = consumer
+                    .batch()
+                    .max_messages(1)
+                    .expires(PULL_EXPIRY)
+                    .messages()
+                    .await
+                    .map_err(|e| e.to_string())?;
+                batch.next().await.transpose().map_err(|e| e.to_string())
+            };
+            match pulled.await {
+                Ok(message) => return (permit, message),
+                Err(e) => {
+                    drop(permit);
+                    tracing::warn!(error = %e, "Failed to pull work items");
+                    tokio::time::sleep(self.config.retry_delay).await;
+                }
+            }
+        }
+    }
+
+    /// Creates or updates the durable consumer of a capability's work
+    /// items.
+    ///
+    /// JetStream stops delivering a work item after `max_deliveries`.
+    async fn consumer(
+        &self,
+        capability: &Capability,
+    ) -> Result<jetstream::consumer::PullConsumer, EventStoreError> {
+        let load_failed = |message: String| EventStoreError::LoadFailed { message };
+        let stream = self
+            .event_store
+            .jetstream()
+            .get_stream(self.event_store.config().work_stream())
+            .await
+            .map_err(|e| load_failed(format!("failed to get stream: {e}")))?;
+
+        let consumer_config = consumer_config(&self.config, capability);
+        let consumer_name = consumer_config.durable_name.clone().unwrap_or_default();
+        stream
+            .get_or_create_consumer(&consumer_name, consumer_config)
+            .await
+            .map_err(|e| load_failed(format!("failed to create consumer: {e}")))
+    }
+
+    /// Advertises the worker's capabilities until an advertisement fails.
+    ///
+    /// Advertisements are refreshed well before they expire, so a worker
+    /// that stops is forgotten soon after.
+    async fn advertise(&self) -> Result<(), EventStoreError> {
+        let mut refresh = tokio::time::interval(WORKER_ADVERTISEMENT_TTL / 3);
+        loop {
+            refresh.tick().await;
+            self.event_store
+                .advertise_worker(&self.config.worker_id, &self.config.capabilities)
+                .await?;
+        }
+    }
+
+    /// Handles one delivery of a work item.
+    ///
+    /// Failures to dead-letter or acknowledge it are logged; JetStream then
+    /// delivers it again.
+    async fn handle(&self, message: jetstream::Message) {
+        let deliveries = message.info().map_or(1, |info| info.delivered);
+
+        let disposition = match Envelope::<WorkItem>::decode(&message.payload) {
+            Ok(envelope) => {
+                let work_item = envelope.into_payload();
+                match Disposition::before_execution(&work_item, Utc::now()) {
+                    Some(deferred) => deferred,
+                    None => {
+                        let work = self.execute(work_item, deliveries);
+                        with_heartbeat(&message, self.config.heartbeat_interval, work).await
+                    }
+                }
+            }
+            Err(e) => Disposition::DeadLetter {
+                reason: format!("undecodable work item: {e}"),
+            },
+        };
+
+        let ack = match disposition {
+            Disposition::Ack => jetstream::AckKind::Ack,
+            Disposition::Defer { delay } => jetstream::AckKind::Nak(Some(delay)),
+            Disposition::Retry { .. } => jetstream::AckKind::Nak(Some(self.config.retry_delay)),
+            Disposition::DeadLetter { reason } => {
+                // Stored before it is removed from the work stream, so it
+                // is delivered again if storing fails
+                if let Err(e) = self
+                    .event_store
+                    .dead_letter(&message.subject, &message.payload, deliveries, &reason)
+                    .await
+                {
+                    tracing::warn!(subject = %message.subject, error = %e, "Failed to dead-letter work item");
+                    return;
+                }
+                jetstream::AckKind::Term
+            }
+        };
+        if let Err(e) = message.ack_with(ack).await {
+            tracing::warn!(subject = %message.subject, error = %e, "Failed to acknowledge work item");
+        }
+    }
+
+    /// Executes a work item and publishes its result.
+    async fn execute(&self, work_item: WorkItem, deliveries: i64) -> Disposition {
+        let max_deliveries = self.config.max_deliveries;
+        let result = match self.resolver.resolve(&work_item).await {
+            Ok(node) => self.worker.process(work_item, &node).await,
+            // The orchestrator would wait forever for a node that does not
+            // exist, so the work item fails instead
+            Err(ResolveError::NotFound { message }) => WorkItemResult::Failed {
+                run_id: work_item.run_id,
+                node_id: work_item.node_id,
+                item_index: work_item.item_index,
+                attempt: work_item.attempt,
+                error: format!("node not found: {message}"),
+                error_kind: NodeErrorKind::ExecutionFailed,
+            },
+            Err(e @ ResolveError::Unavailable { .. }) => {
+                return Disposition::after_failure(e.to_string(), deliveries, max_deliveries);
+            }
+        };
+
+        match self
+            .event_store
+            .publish_work_result(Envelope::new(result))
+            .await
+        {
+            Ok(()) => Disposition::Ack,
+            Err(e) => Disposition::after_failure(e.to_string(), deliveries, max_deliveries),
+        }
+    }
+
+    /// Moves work items that ran out of deliveries to the dead-letter
+    /// stream.
+    ///
+    /// A worker that crashes on a work item never gets to dead-letter it;
+    /// JetStream announces when such a work item ran out of deliveries, and
+    /// it stays in the work stream until it is moved.
+    async fn dead_letter_exhausted(&self) -> Result<(), EventStoreError> {
+        let connection_failed = |message: String| EventStoreError::ConnectionFailed { message };
+        let stream_name = self.event_store.config().work_stream();
+        let mut subscriptions = Vec::new();
+        for capability in &self.config.capabilities {
+            let subject = format!(
+                "$JS.EVENT.ADVISORY.CONSUMER.MAX_DELIVERIES.{stream_name}.{}",
+                consumer_name(&self.config.consumer_name, capability)
+            );
+            let subscription = self
+                .event_store
+                .client()
+                .subscribe(subject)
+                .await
+                .map_err(|e| {
+                    connection_failed(format!("failed to subscribe to advisories: {e}"))
+                })?;
+            subscriptions.push(subscription);
+        }
+        let mut advisories = stream::select_all(subscriptions);
+        let stream = self
+            .event_store
+            .jetstream()
+            .get_stream(stream_name)
+            .await
+            .map_err(|e| connection_failed(format!("failed to get stream: {e}")))?;
+
+        while let Some(advisory) = advisories.next().await {
+            let Ok(advisory) = serde_json::from_slice::<MaxDeliveriesAdvisory>(&advisory.payload)
+            else {
+                continue;
+            };
+            // Another worker may have moved it already
+            let Ok(message) = stream.get_raw_message(advisory.stream_seq).await else {
+                continue;
+            };
+            self.event_store
+                .dead_letter(
+                    &message.subject,
+                    &message.payload,
+                    advisory.deliveries,
+                    "work item was not finished within its deliveries",
+                )
+                .await?;
+            let _ = stream.delete_message(advisory.stream_seq).await;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the name of the durable consumer of a capability's work items.
+fn consumer_name(prefix: &str, capability: &Capability) -> String {
+    format!("{prefix}-{capability}")
+}
+
+/// Returns the configuration of the durable consumer through which a
+/// worker pulls a capability's work items.
+///
+/// The consumer only sees work items published for that capability, so a
+/// work item never reaches a worker that does not serve what it requires.
+fn consumer_config(
+    config: &WorkerRuntimeConfig,
+    capability: &Capability,
+) -> jetstream::consumer::pull::Config {
+    jetstream::consumer::pull::Config {
+        durable_name: Some(consumer_name(&config.consumer_name, capability)),
+        filter_subject: NatsEventStore::work_subject(capability),
+        ack_policy: jetstream::consumer::AckPolicy::Explicit,
+        ack_wait: config.ack_wait,
+        max_deliver: config.max_deliveries,
+        ..Default::default()
+    }
+}
+
+/// Runs `work`, reporting the message as in progress every `interval`.
+///
+/// A failed report is logged and the work goes on; should the message be
+/// delivered again meanwhile, the orchestrator ignores the duplicate
+/// result.
+async fn with_heartbeat<F: Future>(
+    message: &jetstream::Message,
+    interval: Duration,
+    work: F,
+) -> F::Output {
+    tokio::pin!(work);
+    let mut heartbeat = tokio::time::interval(interval);
+    // The first tick completes immediately
+    heartbeat.tick().await;
+
+    loop {
+        tokio::select! {
+            output = &mut work => return output,
+            _ = heartbeat.tick() => {
+                if let Err(e) = message.ack_with(jetstream::AckKind::Progress).await {
+                    tracing::warn!(subject = %message.subject, error = %e, "Failed to report work item progress");
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transient_failures_are_retried_until_the_last_delivery() {
+        assert_eq!(
+            Disposition::after_failure("publish failed".to_string(), 1, 3),
+            Disposition::Retry {
+                reason: "publish failed".to_string()
+            }
+        );
+        assert_eq!(
+            Disposition::after_failure("publish failed".to_string(), 3, 3),
+            Disposition::DeadLetter {
+                reason: "publish failed (after 3 deliveries)".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn retries_are_deferred_until_due() {
+        let now = Utc::now();
+        let mut work_item = WorkItem {
+            run_id: silver_telegram_core::WorkflowRunId::new(),
+            node_id: crate::node::NodeId::new(),
+            capability: None,
+            item_index: None,
+            inputs: std::collections::HashMap::new(),
+            attempt: 2,
+            not_before: Some(now + chrono::Duration::seconds(30)),
+            dry_run: None,
+        };
+        assert_eq!(
+            Disposition::before_execution(&work_item, now),
+            Some(Disposition::Defer {
+                delay: Duration::from_secs(30)
+            })
+        );
+
+        work_item.not_before = Some(now);
+        assert_eq!(Disposition::before_execution(&work_item, now), None);
+        work_item.not_before = None;
+        assert_eq!(Disposition::before_execution(&work_item, now), None);
+    }
+
+    #[test]
+    fn max_deliveries_advisory_decodes() {
+        let payload = br#"{
+            "type": "io.nats.jetstream.advisory.v1.max_deliver",
+            "id": "abc",
+            "timestamp": "2024-12-28T00:00:00Z",
+            "stream": "WORKFLOW_WORK",
+            "consumer": "workers",
+            "stream_seq": 42,
+            "deliveries": 5
+        }"#;
+        let advisory: MaxDeliveriesAdvisory = serde_json::from_slice(payload).unwrap();
+        assert_eq!(advisory.stream_seq, 42);
+        assert_eq!(advisory.deliveries, 5);
+    }
+
+    #[test]
+    fn heartbeats_are_more_frequent_than_redeliveries() {
+        let config = WorkerRuntimeConfig::default();
+        assert!(config.heartbeat_interval < config.ack_wait);
+        assert_eq!(config.consumer_name, DEFAULT_CONSUMER_NAME);
+    }
+
+    #[test]
+    fn work_items_reach_only_workers_serving_their_capability() {
+        let ollama = Capability::new("ollama").unwrap();
+        let work_item = WorkItem {
+            run_id: silver_telegram_core::WorkflowRunId::new(),
+            node_id: crate::node::NodeId::new(),
+            capability: Some(ollama.clone()),
+            item_index: None,
+            inputs: std::collections::HashMap::new(),
+            attempt: 1,
+            not_before: None,
+            dry_run: None,
+        };
+        let subject = NatsEventStore::work_item_subject(&work_item).unwrap();
+
+        let receives = |config: &WorkerRuntimeConfig| {
+            config
+                .capabilities
+                .iter()
+                .any(|capability| consumer_config(config, capability).filter_subject == subject)
+        };
+        let gpu_box = WorkerRuntimeConfig {
+            capabilities: vec![ollama],
+            ..WorkerRuntimeConfig::default()
+        };
+        assert!(receives(&gpu_box));
+        assert!(!receives(&WorkerRuntimeConfig::default()));
+    }
+
+    #[test]
+    fn workers_serve_all_categories_by_default() {
+        let config = WorkerRuntimeConfig::default();
+        assert_eq!(config.capabilities, Capability::categories());
+        assert_ne!(config.worker_id, WorkerRuntimeConfig::default().worker_id);
+        assert_eq!(
+            consumer_name(&config.consumer_name, &Capability::new("ollama").unwrap()),
+            "workers-ollama"
+        );
+    }
+}